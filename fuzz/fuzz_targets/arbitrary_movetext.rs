@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Any text must be handled without panicking, and normalization must be idempotent.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(movetext) = std::str::from_utf8(data) {
+        chess::fuzzing::check_arbitrary_movetext(movetext);
+    }
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Legal games decorated with annotations must keep every movetext invariant.
+fuzz_target!(|input: (Vec<u16>, Vec<u8>, u8)| {
+    let (choices, decorations, result_choice) = input;
+    chess::fuzzing::check_generated_game(&choices, &decorations, result_choice % 5);
+});
@@ -0,0 +1,359 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{
+    BigintInput, VarcharInput, invoke_binary_to_struct_nullable,
+    invoke_binary_varchar_varchar_to_f64_nullable,
+};
+use super::filter::parse_movetext_mainline;
+use super::puzzles::{Eval, eval_series};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use shakmaty::Color;
+#[cfg(feature = "extension")]
+use std::error::Error;
+use std::iter;
+
+/// Evaluations are capped at this many centipawns, and mates count as this, before being
+/// converted to winning chances.
+const CP_CEILING: i32 = 1000;
+/// Evaluation assumed for the starting position, before the first annotated move.
+const INITIAL_CP: i32 = 15;
+
+/// White's winning chances in percent for an evaluation, on the Lichess logistic curve.
+pub fn win_percent(eval: Eval) -> f64 {
+    let cp = match eval {
+        Eval::Centipawns(cp) => cp.clamp(-CP_CEILING, CP_CEILING),
+        Eval::Mate(moves) => CP_CEILING * moves.signum(),
+    };
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.00368208 * f64::from(cp)).exp()) - 1.0)
+}
+
+/// Accuracy in percent of a move that took the mover's winning chances from `before` to
+/// `after`, including Lichess' one point bonus for imperfect analysis.
+pub fn move_accuracy(before: f64, after: f64) -> f64 {
+    if after >= before {
+        return 100.0;
+    }
+    let raw =
+        103.1668100711649 * (-0.04354415386753951 * (before - after)).exp() - 3.166924740191411;
+    (raw + 1.0).clamp(0.0, 100.0)
+}
+
+/// Game accuracy of `color` from the evaluation after each ply of a game starting with White,
+/// as computed by Lichess: the mean of the volatility-weighted mean and the harmonic mean of
+/// its move accuracies. Each move is weighted by the standard deviation of the winning
+/// chances over a window of neighbouring plies, so moves in sharp positions count more.
+/// Returns `None` when `color` made no move.
+pub fn game_accuracy(evals: &[Eval], color: Color) -> Option<f64> {
+    if evals.is_empty() {
+        return None;
+    }
+    let wins: Vec<f64> = iter::once(Eval::Centipawns(INITIAL_CP))
+        .chain(evals.iter().copied())
+        .map(win_percent)
+        .collect();
+    // Lichess pads the front with copies of the first window, so every move gets one.
+    let window_size = (evals.len() / 10).clamp(2, 8);
+    let windows =
+        iter::repeat_n(&wins[..window_size], window_size - 2).chain(wins.windows(window_size));
+
+    let (mut weighted_sum, mut weight_sum, mut inverse_sum, mut moves) = (0.0, 0.0, 0.0, 0);
+    for (ply, (pair, window)) in wins.windows(2).zip(windows).enumerate() {
+        if Color::from_white(ply.is_multiple_of(2)) != color {
+            continue;
+        }
+        let accuracy = color.fold_wb(
+            move_accuracy(pair[0], pair[1]),
+            move_accuracy(pair[1], pair[0]),
+        );
+        let weight = standard_deviation(window).clamp(0.5, 12.0);
+        weighted_sum += accuracy * weight;
+        weight_sum += weight;
+        inverse_sum += 1.0 / accuracy.max(1.0);
+        moves += 1;
+    }
+    if moves == 0 {
+        return None;
+    }
+    let harmonic = f64::from(moves) / inverse_sum;
+    Some((weighted_sum / weight_sum + harmonic) / 2.0)
+}
+
+fn standard_deviation(values: &[f64]) -> f64 {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt()
+}
+
+/// Lichess game accuracy of `color` from the `%eval` annotations of `movetext`.
+/// Spec: move-analysis - Accuracy
+///
+/// Evaluations are read up to the first ply without one; a final mating move is scored as a
+/// mate even without an annotation, as Lichess exports leave it out. Returns `None` when the
+/// movetext cannot be parsed or `color` has no evaluated move.
+pub fn accuracy(movetext: &str, color: Color) -> Option<f64> {
+    let series = eval_series(movetext)?;
+    let mut evals: Vec<Eval> = series.iter().map_while(|eval| *eval).collect();
+    if evals.len() + 1 == series.len()
+        && parse_movetext_mainline(movetext)
+            .sans
+            .last()
+            .is_some_and(|san| san.ends_with('#'))
+    {
+        let white_mates = series.len() % 2 == 1;
+        evals.push(Eval::Mate(if white_mates { 1 } else { -1 }));
+    }
+    game_accuracy(&evals, color)
+}
+
+/// Evaluations that bracket a game: the first one once the opening is over and the last one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpeningEval {
+    /// First evaluated ply at or after the opening ply, with its evaluation.
+    pub opening: Option<(usize, Eval)>,
+    /// Last evaluated ply of the game, with its evaluation.
+    pub last: (usize, Eval),
+}
+
+/// The `%eval` annotations of `movetext` after the opening and at the end of the game.
+/// Spec: move-analysis - Opening Evaluation
+///
+/// The opening ends after ply `opening_ply`, so its evaluation is the first one from that ply
+/// on; games whose evaluations stop earlier have none. Returns `Ok(None)` when the movetext
+/// cannot be parsed or has no evaluation at all.
+pub fn opening_eval(movetext: &str, opening_ply: i64) -> Result<Option<OpeningEval>, String> {
+    let Ok(opening_ply) = usize::try_from(opening_ply) else {
+        return Err(format!(
+            "chess_game_opening_eval: invalid opening_ply {opening_ply}. Expected 0 or more plies."
+        ));
+    };
+    let Some(series) = eval_series(movetext) else {
+        return Ok(None);
+    };
+    // `series[n]` is the evaluation after ply `n + 1`.
+    let mut evaluated = series
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, eval)| eval.map(|eval| (idx + 1, eval)));
+    let Some(last) = evaluated.clone().next_back() else {
+        return Ok(None);
+    };
+    Ok(Some(OpeningEval {
+        opening: evaluated.find(|&(ply, _)| ply >= opening_ply),
+        last,
+    }))
+}
+
+/// `(ply, cp, mate)` struct fields of an evaluated ply.
+fn eval_fields(evaluated: Option<(usize, Eval)>) -> [Option<i32>; 3] {
+    let Some((ply, eval)) = evaluated else {
+        return [None; 3];
+    };
+    let ply = i32::try_from(ply).ok();
+    match eval {
+        Eval::Centipawns(cp) => [ply, Some(cp), None],
+        Eval::Mate(moves) => [ply, None, Some(moves)],
+    }
+}
+
+fn parse_color(raw: &str) -> Option<Color> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "white" | "w" => Some(Color::White),
+        "black" | "b" => Some(Color::Black),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessAccuracyScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessAccuracyScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_binary_varchar_varchar_to_f64_nullable(input, output, |movetext, raw| {
+            let color = parse_color(raw).ok_or_else(|| {
+                format!(
+                    "chess_accuracy: invalid color '{raw}'. Supported values: 'white', 'black'."
+                )
+            })?;
+            Ok(accuracy(movetext, color))
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Double),
+        )]
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessGameOpeningEvalScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessGameOpeningEvalScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_binary_to_struct_nullable::<VarcharInput, BigintInput, _, _, 6>(
+            input,
+            output,
+            |movetext, opening_ply| {
+                Ok(opening_eval(movetext, opening_ply)?.map(|summary| {
+                    let [opening_ply, opening_cp, opening_mate] = eval_fields(summary.opening);
+                    let [final_ply, final_cp, final_mate] = eval_fields(Some(summary.last));
+                    [
+                        opening_ply,
+                        opening_cp,
+                        opening_mate,
+                        final_ply,
+                        final_cp,
+                        final_mate,
+                    ]
+                }))
+            },
+        )
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let integer = || LogicalTypeHandle::from(LogicalTypeId::Integer);
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+            ],
+            LogicalTypeHandle::struct_type(&[
+                ("opening_ply", integer()),
+                ("opening_cp", integer()),
+                ("opening_mate", integer()),
+                ("final_ply", integer()),
+                ("final_cp", integer()),
+                ("final_mate", integer()),
+            ]),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATING_GAME: &str = "1. e4 { [%eval 0.3] } e5 { [%eval 0.3] } 2. Qh5 { [%eval 0.0] } \
+        Nc6 { [%eval 0.1] } 3. Bc4 { [%eval 0.0] } Nf6 { [%eval #1] } 4. Qxf7# 1-0";
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.unwrap();
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn test_win_percent_caps_evals_and_mates() {
+        assert_eq!(win_percent(Eval::Centipawns(0)), 50.0);
+        assert_close(Some(win_percent(Eval::Centipawns(100))), 59.102_589_719);
+        assert_eq!(
+            win_percent(Eval::Centipawns(5000)),
+            win_percent(Eval::Mate(7))
+        );
+        assert_close(
+            Some(win_percent(Eval::Mate(-2))),
+            100.0 - win_percent(Eval::Mate(2)),
+        );
+    }
+
+    #[test]
+    fn test_move_accuracy_curve() {
+        assert_eq!(move_accuracy(50.0, 60.0), 100.0);
+        assert_eq!(move_accuracy(50.0, 50.0), 100.0);
+        assert_close(Some(move_accuracy(60.0, 50.0)), 64.579_828_454);
+        assert_eq!(move_accuracy(100.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_accuracy_matches_lichess_formula() {
+        // Reference values from a direct port of Lichess' AccuracyPercent.
+        assert_close(accuracy(MATING_GAME, Color::White), 95.532_028_405_302_48);
+        assert_close(accuracy(MATING_GAME, Color::Black), 22.126_936_419_272_46);
+
+        let long = "1. d4 { [%eval 0.2] } d5 { [%eval -0.15] } 2. c4 { [%eval 0.4] } \
+            e6 { [%eval 0.35] } 3. Nc3 { [%eval -1.2] } Nf6 { [%eval -1.1] } \
+            4. Bg5 { [%eval -4.0] } Be7 { [%eval -3.8] } 5. e3 { [%eval -3.9] } \
+            O-O { [%eval -9.0] } 6. Nf3 { [%eval #-3] } *";
+        assert_close(accuracy(long, Color::White), 62.628_993_246_029_82);
+        assert_close(accuracy(long, Color::Black), 99.126_605_959_502_03);
+    }
+
+    #[test]
+    fn test_accuracy_stops_at_first_unevaluated_ply() {
+        let steady = "1. e4 { [%eval 0.15] } e5 { [%eval 0.15] } 2. Nf3 Nc6 { [%eval -5.0] } *";
+        assert_eq!(accuracy(steady, Color::White), Some(100.0));
+        assert_eq!(accuracy(steady, Color::Black), Some(100.0));
+
+        assert_eq!(accuracy("1. e4 { [%eval 0.2] } *", Color::Black), None);
+        assert_eq!(accuracy("1. e4 e5 *", Color::White), None);
+        assert_eq!(accuracy("", Color::White), None);
+    }
+
+    #[test]
+    fn test_opening_eval_brackets_the_game() {
+        let game = "1. e4 { [%eval 0.3] } e5 2. Nf3 { [%eval 0.25] } Nc6 { [%eval 0.2] } \
+            3. Bb5 a6 { [%eval #-4] } *";
+        let summary = opening_eval(game, 3).unwrap().unwrap();
+        assert_eq!(summary.opening, Some((3, Eval::Centipawns(25))));
+        assert_eq!(summary.last, (6, Eval::Mate(-4)));
+
+        // The opening ply itself counts; ply 0 takes the first evaluation.
+        assert_eq!(
+            opening_eval(game, 4).unwrap().unwrap().opening,
+            Some((4, Eval::Centipawns(20)))
+        );
+        assert_eq!(
+            opening_eval(game, 0).unwrap().unwrap().opening,
+            Some((1, Eval::Centipawns(30)))
+        );
+        // Evaluations that stop before the opening ply leave only the last one.
+        let summary = opening_eval(game, 7).unwrap().unwrap();
+        assert_eq!(summary.opening, None);
+        assert_eq!(summary.last, (6, Eval::Mate(-4)));
+
+        assert_eq!(opening_eval("1. e4 e5 *", 2), Ok(None));
+        assert_eq!(opening_eval("", 2), Ok(None));
+        assert!(opening_eval(game, -1).is_err());
+    }
+
+    #[test]
+    fn test_eval_fields_split_centipawns_and_mates() {
+        assert_eq!(
+            eval_fields(Some((12, Eval::Centipawns(-40)))),
+            [Some(12), Some(-40), None]
+        );
+        assert_eq!(
+            eval_fields(Some((31, Eval::Mate(2)))),
+            [Some(31), None, Some(2)]
+        );
+        assert_eq!(eval_fields(None), [None; 3]);
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color(" White "), Some(Color::White));
+        assert_eq!(parse_color("b"), Some(Color::Black));
+        assert_eq!(parse_color("both"), None);
+    }
+}
@@ -0,0 +1,171 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::invoke_unary_varchar_to_varchar_i32_map;
+use crate::pgn_visitor_skip_variations;
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use pgn_reader::{Nag, RawComment, Reader, SanPlus, Skip, Visitor};
+use shakmaty::{Role, san::San};
+#[cfg(feature = "extension")]
+use std::error::Error;
+use std::io;
+use std::ops::ControlFlow;
+
+const ROLES: [(Role, &str); 6] = [
+    (Role::Pawn, "pawn"),
+    (Role::Knight, "knight"),
+    (Role::Bishop, "bishop"),
+    (Role::Rook, "rook"),
+    (Role::Queen, "queen"),
+    (Role::King, "king"),
+];
+
+/// Mainline move counts per side and piece type, keyed `white_pawn` .. `black_king`.
+///
+/// Every key is always present (zero when the piece never moved), in White-then-Black,
+/// pawn-to-king order. Counting works from SAN alone: sides alternate starting with White,
+/// castling counts as a king move and null moves are skipped. Returns `None` when the movetext
+/// cannot be parsed.
+pub fn piece_activity(movetext: &str) -> Option<Vec<(String, i32)>> {
+    let mut visitor = PieceActivityVisitor::default();
+    if !movetext.trim().is_empty() {
+        let mut reader = Reader::new(io::Cursor::new(movetext.as_bytes()));
+        reader.read_game(&mut visitor).ok()?;
+    }
+
+    Some(
+        ["white", "black"]
+            .into_iter()
+            .zip(visitor.counts)
+            .flat_map(|(side, counts)| {
+                ROLES
+                    .iter()
+                    .zip(counts)
+                    .map(move |((_, piece), count)| (format!("{side}_{piece}"), count))
+            })
+            .collect(),
+    )
+}
+
+#[derive(Default)]
+struct PieceActivityVisitor {
+    ply: usize,
+    /// `[side][role]` in `ROLES` order; side 0 is White.
+    counts: [[i32; 6]; 2],
+}
+
+impl Visitor for PieceActivityVisitor {
+    type Tags = ();
+    type Movetext = ();
+    type Output = ();
+
+    fn begin_tags(&mut self) -> ControlFlow<Self::Output, Self::Tags> {
+        *self = Self::default();
+        ControlFlow::Continue(())
+    }
+
+    fn begin_movetext(&mut self, _tags: Self::Tags) -> ControlFlow<Self::Output, Self::Movetext> {
+        ControlFlow::Continue(())
+    }
+
+    fn san(
+        &mut self,
+        _movetext: &mut Self::Movetext,
+        san_plus: SanPlus,
+    ) -> ControlFlow<Self::Output> {
+        let role = match san_plus.san {
+            San::Normal { role, .. } | San::Put { role, .. } => Some(role),
+            San::Castle(_) => Some(Role::King),
+            San::Null => None,
+        };
+        if let Some(idx) = role.and_then(|role| ROLES.iter().position(|(r, _)| *r == role)) {
+            self.counts[self.ply % 2][idx] += 1;
+        }
+        self.ply += 1;
+
+        ControlFlow::Continue(())
+    }
+
+    pgn_visitor_skip_variations!();
+
+    fn end_game(&mut self, _movetext: Self::Movetext) -> Self::Output {}
+}
+
+// Spec: move-analysis - Piece Activity
+#[cfg(feature = "extension")]
+pub struct ChessPieceActivityScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessPieceActivityScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_unary_varchar_to_varchar_i32_map(input, output, piece_activity)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            LogicalTypeHandle::map(
+                &LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                &LogicalTypeHandle::from(LogicalTypeId::Integer),
+            ),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(activity: &[(String, i32)], key: &str) -> i32 {
+        activity
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| *v)
+            .expect("key present")
+    }
+
+    #[test]
+    fn test_piece_activity_counts_per_side_and_role() {
+        let activity =
+            piece_activity("1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. O-O Nf6 5. Re1 b5 6. Bb3 d6").unwrap();
+
+        assert_eq!(count(&activity, "white_pawn"), 1);
+        assert_eq!(count(&activity, "white_knight"), 1);
+        assert_eq!(count(&activity, "white_bishop"), 2);
+        assert_eq!(count(&activity, "white_rook"), 1);
+        assert_eq!(count(&activity, "white_king"), 1);
+        assert_eq!(count(&activity, "black_pawn"), 4);
+        assert_eq!(count(&activity, "black_knight"), 2);
+        assert_eq!(count(&activity, "black_queen"), 0);
+    }
+
+    #[test]
+    fn test_piece_activity_has_stable_keys() {
+        let keys: Vec<String> = piece_activity("")
+            .unwrap()
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(keys.len(), 12);
+        assert_eq!(keys.first().map(String::as_str), Some("white_pawn"));
+        assert_eq!(keys.last().map(String::as_str), Some("black_king"));
+    }
+
+    #[test]
+    fn test_piece_activity_ignores_variations_and_comments() {
+        let activity = piece_activity("1. e4 { best } (1. d4 d5) e5 2. Qh5 1-0").unwrap();
+        assert_eq!(count(&activity, "white_pawn"), 1);
+        assert_eq!(count(&activity, "white_queen"), 1);
+        assert_eq!(count(&activity, "black_pawn"), 1);
+        assert_eq!(activity.iter().map(|(_, v)| v).sum::<i32>(), 3);
+    }
+}
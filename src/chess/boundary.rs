@@ -0,0 +1,966 @@
+use memchr::{memchr, memchr2, memchr3};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Synthetic tag injected in front of a recovered tag section so the visitor can flag the game.
+pub const RECOVERED_BOUNDARY_TAG: &[u8] = b"DuckdbChessRecoveredBoundary";
+
+/// Synthetic tag carrying the source byte offset of a game's first tag.
+pub const GAME_OFFSET_TAG: &[u8] = b"DuckdbChessByteOffset";
+
+/// Synthetic tag carrying the 1-based source line of a game's first tag.
+pub const GAME_LINE_TAG: &[u8] = b"DuckdbChessLineNumber";
+
+pub const RECOVERED_BOUNDARY_WARNING: &str =
+    "Recovered game boundary: missing blank line between the previous game's result and [Event]";
+
+const EVENT_TAG_PREFIX: &[u8] = b"[Event ";
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const RESULT_TOKENS: [&[u8]; 4] = [b"1-0", b"0-1", b"1/2-1/2", b"*"];
+
+/// Raw movetext sizes measured by a [`GameBoundaryScanner`], keyed by game byte offset.
+///
+/// The scanner reads ahead of pgn-reader, so a game's size is always recorded by the time the
+/// visitor reaches the end of that game's movetext.
+#[derive(Clone, Debug, Default)]
+pub struct MovetextSizes(Arc<Mutex<VecDeque<(u64, u64)>>>);
+
+impl MovetextSizes {
+    fn push(&self, offset: u64, bytes: u64) {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push_back((offset, bytes));
+    }
+
+    /// Size of the game starting at `offset`; entries of earlier games (skipped by a filter or
+    /// lost to a parser error) are dropped.
+    pub fn take(&self, offset: u64) -> Option<u64> {
+        let mut sizes = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        while let Some(&(front, bytes)) = sizes.front() {
+            if front > offset {
+                return None;
+            }
+            sizes.pop_front();
+            if front == offset {
+                return Some(bytes);
+            }
+        }
+        None
+    }
+}
+
+/// Line-layout repairs made and variant games dropped while scanning, summed over every
+/// reader of one `read_pgn` call.
+#[derive(Debug, Default)]
+pub struct LineDiagnostics {
+    /// Files opened by the scan.
+    pub files: AtomicU64,
+    /// Bare `\r` line endings (classic Mac OS) rewritten to `\n`.
+    pub cr_line_endings: AtomicU64,
+    /// `\r\n` line endings, which pgn-reader handles as they are.
+    pub crlf_line_endings: AtomicU64,
+    /// Tag lines whose movetext continued on the same line and was moved to a line of its own.
+    pub tag_lines_split: AtomicU64,
+    /// `[Event ...]` tags found right after a result and re-emitted as a new game.
+    pub boundaries_recovered: AtomicU64,
+    /// Games dropped by `variant := 'standard'`.
+    pub variant_games_skipped: AtomicU64,
+    /// Games without mainline moves dropped by `skip_empty_games := true`.
+    pub empty_games_skipped: AtomicU64,
+}
+
+impl LineDiagnostics {
+    /// Current counter values, by output column name.
+    pub fn counts(&self) -> [(&'static str, u64); 7] {
+        [
+            ("files", &self.files),
+            ("cr_line_endings", &self.cr_line_endings),
+            ("crlf_line_endings", &self.crlf_line_endings),
+            ("tag_lines_split", &self.tag_lines_split),
+            ("boundaries_recovered", &self.boundaries_recovered),
+            ("variant_games_skipped", &self.variant_games_skipped),
+            ("empty_games_skipped", &self.empty_games_skipped),
+        ]
+        .map(|(name, counter)| (name, counter.load(Ordering::Relaxed)))
+    }
+}
+
+/// Source offsets up to which a scanner has counted its [`LineDiagnostics`], so a scanner
+/// reopened earlier in the same input leaves those repairs uncounted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CountedThrough {
+    /// End of the bytes checked for line endings, which the read buffer keeps ahead of lines.
+    pub line_endings: u64,
+    /// End of the lines checked for layout repairs.
+    pub lines: u64,
+}
+
+/// Live [`CountedThrough`] of a scanner, still readable once the scanner is boxed in a reader.
+#[derive(Debug, Default)]
+pub struct ScanProgress {
+    line_endings: AtomicU64,
+    lines: AtomicU64,
+}
+
+impl ScanProgress {
+    pub fn counted_through(&self) -> CountedThrough {
+        CountedThrough {
+            line_endings: self.line_endings.load(Ordering::Relaxed),
+            lines: self.lines.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Read adapter rewriting bare `\r` line endings to `\n`, byte for byte so source offsets are
+/// unchanged. `\r\n` is left alone.
+struct LineEndingNormalizer<R> {
+    inner: R,
+    /// Byte read past a chunk-final `\r` to tell `\r\n` from a bare `\r`.
+    peeked: Option<u8>,
+    /// Source offset of the next byte returned.
+    offset: u64,
+    /// Line endings before this offset were already counted.
+    counted_from: u64,
+    progress: Arc<ScanProgress>,
+    diagnostics: Arc<LineDiagnostics>,
+}
+
+impl<R: Read> Read for LineEndingNormalizer<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let mut len = 0;
+        if let Some(byte) = self.peeked.take() {
+            buf[0] = byte;
+            len = 1;
+        }
+        len += self.inner.read(&mut buf[len..])?;
+        let start = self.offset;
+        self.offset += len as u64;
+        self.progress
+            .line_endings
+            .store(self.offset, Ordering::Relaxed);
+        let chunk = &mut buf[..len];
+        if !chunk.contains(&b'\r') {
+            return Ok(len);
+        }
+        if chunk.last() == Some(&b'\r') {
+            let mut next = [0u8];
+            if self.inner.read(&mut next)? == 1 {
+                self.peeked = Some(next[0]);
+            }
+        }
+
+        let (mut cr, mut crlf) = (0, 0);
+        for idx in 0..len {
+            if chunk[idx] != b'\r' {
+                continue;
+            }
+            let next = chunk.get(idx + 1).copied().or(self.peeked);
+            let counted = u64::from(start + idx as u64 >= self.counted_from);
+            if next == Some(b'\n') {
+                crlf += counted;
+            } else {
+                chunk[idx] = b'\n';
+                cr += counted;
+            }
+        }
+        self.diagnostics
+            .cr_line_endings
+            .fetch_add(cr, Ordering::Relaxed);
+        self.diagnostics
+            .crlf_line_endings
+            .fetch_add(crlf, Ordering::Relaxed);
+        Ok(len)
+    }
+}
+
+/// Length of the tag pairs at the start of `line` when movetext follows them on the same line,
+/// as in one-line exports (`[Event "A"] [Result "1-0"] 1. e4 1-0`).
+fn tags_before_movetext(line: &[u8]) -> Option<usize> {
+    let mut idx = 0;
+    let mut tags_end = None;
+    loop {
+        while line.get(idx).is_some_and(u8::is_ascii_whitespace) {
+            idx += 1;
+        }
+        if line.get(idx) != Some(&b'[') {
+            break;
+        }
+        let mut in_quotes = false;
+        loop {
+            idx += memchr3(b']', b'"', b'\\', &line[idx..])?;
+            match line[idx] {
+                b']' if !in_quotes => break,
+                b'"' => in_quotes = !in_quotes,
+                // An escape inside a quoted value hides the next byte.
+                b'\\' if in_quotes => idx += 1,
+                _ => {}
+            }
+            idx += 1;
+            if idx >= line.len() {
+                return None;
+            }
+        }
+        idx += 1;
+        tags_end = Some(idx);
+    }
+    if idx >= line.len() {
+        return None;
+    }
+    tags_end
+}
+
+/// Read adapter that finds game boundaries in a PGN byte stream.
+/// Spec: pgn-parsing - Concatenated Game Recovery
+///
+/// Scraped PGN dumps often continue straight from a result token into the next `[Event ...]`
+/// tag. pgn-reader drops that tag (and can merge the following movetext into the previous
+/// game) when it is indented or shares the result's line. Whenever a result token is followed by
+/// `[Event `, the tag is re-emitted at the start of a fresh line after a blank line, preceded by
+/// a [`RECOVERED_BOUNDARY_TAG`] marker that the visitor turns into a warning.
+///
+/// With offset tracking enabled, every tag section is also preceded by a [`GAME_OFFSET_TAG`]
+/// marker holding the source offset of its first tag, so games can be located again later.
+/// Line tracking likewise adds a [`GAME_LINE_TAG`] marker with the line of that tag, counting
+/// from the first line `inner` yields.
+///
+/// With [`MovetextSizes`] attached, the raw bytes from the first to the last movetext line of
+/// each game are counted, up to the empty line, next tag section or end of input that ends it.
+///
+/// Layouts pgn-reader misreads are rewritten as well: bare `\r` line endings become `\n`, and
+/// movetext on the same line as the tags is moved to the next line, so exports that put a whole
+/// game (or several) on one physical line split into games. Every repair is counted in the
+/// scanner's [`LineDiagnostics`].
+pub struct GameBoundaryScanner<R> {
+    inner: BufReader<LineEndingNormalizer<R>>,
+    /// A line read past the end of the buffer of `inner`.
+    line: Vec<u8>,
+    /// Rewritten output not yet returned.
+    out: Vec<u8>,
+    out_pos: usize,
+    state: ScanState,
+}
+
+/// Scan position and game state of a [`GameBoundaryScanner`], kept apart from its input so
+/// lines can be scanned in place in the read buffer.
+struct ScanState {
+    /// Source offset of the next line to read.
+    next_offset: u64,
+    track_offsets: bool,
+    /// 1-based source line of the next line to read.
+    next_line: u64,
+    track_lines: bool,
+    in_comment: bool,
+    in_tags: bool,
+    after_result: bool,
+    movetext_sizes: Option<MovetextSizes>,
+    /// Offset and movetext bytes counted so far of the game being scanned.
+    current_game: Option<(u64, u64)>,
+    /// Repairs on lines before this offset were already counted.
+    counted_from: u64,
+    progress: Arc<ScanProgress>,
+    diagnostics: Arc<LineDiagnostics>,
+}
+
+/// Output of one scanned line. It stays a prefix of the line, without copying, until a
+/// rewrite makes it differ.
+struct LineOutput<'a> {
+    line: &'a [u8],
+    /// Length of the line prefix the output still equals.
+    unchanged: Option<usize>,
+    out: &'a mut Vec<u8>,
+}
+
+impl LineOutput<'_> {
+    /// Append the first `len` bytes of `rest`, a suffix of the line.
+    fn copy(&mut self, rest: &[u8], len: usize) {
+        let start = self.line.len() - rest.len();
+        match self.unchanged {
+            Some(end) if end == start => self.unchanged = Some(start + len),
+            _ => self.push(&rest[..len]),
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        if let Some(end) = self.unchanged.take() {
+            self.out.clear();
+            self.out.extend_from_slice(&self.line[..end]);
+        }
+        self.out.extend_from_slice(bytes);
+    }
+
+    fn ends_line(&self) -> bool {
+        let last = match self.unchanged {
+            Some(end) => self.line[..end].last(),
+            None => self.out.last(),
+        };
+        matches!(last, None | Some(b'\n'))
+    }
+
+    /// Whether the line passes through unchanged; otherwise `out` holds its rewrite.
+    fn finish(mut self) -> bool {
+        if self.unchanged == Some(self.line.len()) {
+            return true;
+        }
+        self.push(&[]);
+        false
+    }
+}
+
+impl<R: Read> GameBoundaryScanner<R> {
+    /// `base_offset` is the source offset of the first byte `inner` yields.
+    pub fn new(inner: R, base_offset: u64, track_offsets: bool) -> Self {
+        let diagnostics = Arc::new(LineDiagnostics::default());
+        let progress = Arc::new(ScanProgress::default());
+        let inner = LineEndingNormalizer {
+            inner,
+            peeked: None,
+            offset: base_offset,
+            counted_from: 0,
+            progress: Arc::clone(&progress),
+            diagnostics: Arc::clone(&diagnostics),
+        };
+        Self {
+            inner: BufReader::with_capacity(64 * 1024, inner),
+            line: Vec::new(),
+            out: Vec::new(),
+            out_pos: 0,
+            state: ScanState {
+                next_offset: base_offset,
+                track_offsets,
+                next_line: 1,
+                track_lines: false,
+                in_comment: false,
+                in_tags: false,
+                after_result: false,
+                movetext_sizes: None,
+                current_game: None,
+                counted_from: 0,
+                progress,
+                diagnostics,
+            },
+        }
+    }
+
+    /// Count repairs into `diagnostics` instead of a private set of counters.
+    pub fn with_diagnostics(mut self, diagnostics: Arc<LineDiagnostics>) -> Self {
+        self.inner.get_mut().diagnostics = Arc::clone(&diagnostics);
+        self.state.diagnostics = diagnostics;
+        self
+    }
+
+    /// Leave repairs before `counted` uncounted, for a scanner reopened over input another
+    /// scanner already counted.
+    pub fn counting_from(mut self, counted: CountedThrough) -> Self {
+        self.inner.get_mut().counted_from = counted.line_endings;
+        self.state.counted_from = counted.lines;
+        self
+    }
+
+    /// How far this scanner has counted its [`LineDiagnostics`].
+    pub fn progress(&self) -> Arc<ScanProgress> {
+        Arc::clone(&self.state.progress)
+    }
+
+    /// Precede each tag section with a [`GAME_LINE_TAG`] marker.
+    pub fn with_line_numbers(mut self) -> Self {
+        self.state.track_lines = true;
+        self
+    }
+
+    /// Number the first line `inner` yields `line` instead of 1, for input read from the
+    /// middle of a file.
+    pub fn starting_at_line(mut self, line: u64) -> Self {
+        self.state.next_line = line;
+        self
+    }
+
+    /// Record each game's raw movetext size into `sizes`; implies offset tracking.
+    pub fn with_movetext_sizes(mut self, sizes: MovetextSizes) -> Self {
+        self.state.track_offsets = true;
+        self.state.movetext_sizes = Some(sizes);
+        self
+    }
+}
+
+impl ScanState {
+    fn finish_game(&mut self) {
+        if let (Some((offset, bytes)), Some(sizes)) =
+            (self.current_game.take(), &self.movetext_sizes)
+        {
+            sizes.push(offset, bytes);
+        }
+    }
+
+    fn count_movetext(&mut self, segment: &[u8]) {
+        if let Some((_, bytes)) = &mut self.current_game
+            && (*bytes > 0 || !segment.trim_ascii().is_empty())
+        {
+            *bytes += segment.len() as u64;
+        }
+    }
+
+    /// Scan the next input line, which ends with its `\n` unless it is the last one. Returns
+    /// `true` when it passes through unchanged; otherwise `out` is replaced by its rewrite.
+    fn scan_line(&mut self, line: &[u8], out: &mut Vec<u8>) -> bool {
+        let line_offset = self.next_offset;
+        self.next_offset += line.len() as u64;
+        self.progress
+            .lines
+            .store(self.next_offset, Ordering::Relaxed);
+        let line_number = self.next_line;
+        self.next_line += 1;
+
+        if !self.in_comment && line.trim_ascii().is_empty() {
+            // A real separator line: nothing to recover.
+            self.after_result = false;
+            // Like pgn-reader, only a truly empty line ends the movetext.
+            if matches!(line, b"\n" | b"\r\n")
+                && self.current_game.is_some_and(|(_, bytes)| bytes > 0)
+            {
+                self.finish_game();
+            }
+        }
+        if !self.in_comment && line.starts_with(b"%") {
+            // A PGN escape line, which pgn-reader skips: pass it through without letting its
+            // text open comments, end tag sections or look like a result.
+            if let Some((_, bytes)) = &mut self.current_game
+                && *bytes > 0
+            {
+                *bytes += line.len() as u64;
+            }
+            return true;
+        }
+        let mut output = LineOutput {
+            line,
+            unchanged: Some(0),
+            out,
+        };
+        let mut rest = line;
+        if line_offset == 0 && rest.starts_with(UTF8_BOM) {
+            output.copy(rest, UTF8_BOM.len());
+            rest = &rest[UTF8_BOM.len()..];
+        }
+        loop {
+            let content = rest.trim_ascii_start();
+            if !self.in_comment && content.starts_with(b"[") {
+                if self.after_result && content.starts_with(EVENT_TAG_PREFIX) {
+                    self.push_recovered_boundary(&mut output);
+                    rest = content;
+                }
+                if !self.in_tags {
+                    self.in_tags = true;
+                    if self.track_offsets {
+                        let offset = line_offset + (line.len() - content.len()) as u64;
+                        push_marker(&mut output, GAME_OFFSET_TAG, offset);
+                        rest = content;
+                        if self.movetext_sizes.is_some() {
+                            self.finish_game();
+                            self.current_game = Some((offset, 0));
+                        }
+                    }
+                    if self.track_lines {
+                        push_marker(&mut output, GAME_LINE_TAG, line_number);
+                        rest = content;
+                    }
+                }
+                self.after_result = false;
+                match tags_before_movetext(rest) {
+                    Some(split) => {
+                        output.copy(rest, split);
+                        output.push(b"\n");
+                        if self.counts_repairs() {
+                            self.diagnostics
+                                .tag_lines_split
+                                .fetch_add(1, Ordering::Relaxed);
+                        }
+                        rest = &rest[split..];
+                        continue;
+                    }
+                    None => {
+                        output.copy(rest, rest.len());
+                        break;
+                    }
+                }
+            }
+            if !content.is_empty() {
+                self.in_tags = false;
+            }
+
+            match self.scan_movetext(rest) {
+                Some(split) => {
+                    self.count_movetext(&rest[..split]);
+                    output.copy(rest, split);
+                    rest = &rest[split..];
+                }
+                None => {
+                    self.count_movetext(rest);
+                    output.copy(rest, rest.len());
+                    break;
+                }
+            }
+        }
+
+        output.finish()
+    }
+
+    /// Whether repairs on the line being scanned count: it ends past `counted_from`, which
+    /// always falls on a line end.
+    fn counts_repairs(&self) -> bool {
+        self.next_offset > self.counted_from
+    }
+
+    fn push_recovered_boundary(&mut self, output: &mut LineOutput) {
+        if self.counts_repairs() {
+            self.diagnostics
+                .boundaries_recovered
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        if !output.ends_line() {
+            output.push(b"\n");
+        }
+        output.push(b"\n[");
+        output.push(RECOVERED_BOUNDARY_TAG);
+        output.push(b" \"1\"]\n");
+    }
+
+    /// Track comment/result state across a movetext segment.
+    ///
+    /// Returns the offset of an `[Event ` tag that follows a result token on the same line
+    /// (including `1-0[Event ...]` with no separating space).
+    fn scan_movetext(&mut self, segment: &[u8]) -> Option<usize> {
+        let mut idx = 0;
+        loop {
+            if self.in_comment {
+                let close = memchr(b'}', &segment[idx..])?;
+                self.in_comment = false;
+                idx += close + 1;
+            }
+
+            let end = memchr2(b'{', b';', &segment[idx..]).map_or(segment.len(), |end| idx + end);
+            if let Some(split) = self.scan_tokens(&segment[idx..end]) {
+                return Some(idx + split);
+            }
+            if segment.get(end) != Some(&b'{') {
+                return None;
+            }
+            self.in_comment = true;
+            idx = end + 1;
+        }
+    }
+
+    /// Track result state across movetext `text` without comments; see [`Self::scan_movetext`].
+    fn scan_tokens(&mut self, text: &[u8]) -> Option<usize> {
+        if memchr(b'[', text).is_none() {
+            // Nothing to split: only whether the last token is a result matters.
+            if let Some(token) = text
+                .split(u8::is_ascii_whitespace)
+                .rfind(|token| !token.is_empty())
+            {
+                self.after_result = RESULT_TOKENS.contains(&token);
+            }
+            return None;
+        }
+
+        let mut idx = 0;
+        while idx < text.len() {
+            if text[idx].is_ascii_whitespace() {
+                idx += 1;
+                continue;
+            }
+            if self.after_result && text[idx..].starts_with(EVENT_TAG_PREFIX) {
+                return Some(idx);
+            }
+
+            let start = idx;
+            while idx < text.len() && !text[idx].is_ascii_whitespace() {
+                idx += 1;
+            }
+            let token = &text[start..idx];
+            if let Some(result) = RESULT_TOKENS.iter().find(|result| {
+                token.starts_with(result)
+                    && text[start + result.len()..].starts_with(EVENT_TAG_PREFIX)
+            }) {
+                self.after_result = true;
+                return Some(start + result.len());
+            }
+            self.after_result = RESULT_TOKENS.contains(&token);
+        }
+
+        None
+    }
+}
+
+fn push_marker(output: &mut LineOutput, tag: &[u8], value: u64) {
+    if !output.ends_line() {
+        output.push(b"\n");
+    }
+    output.push(b"[");
+    output.push(tag);
+    output.push(format!(" \"{value}\"]\n").as_bytes());
+}
+
+impl<R: Read> Read for GameBoundaryScanner<R> {
+    /// Lines that need no rewrite are copied straight from the read buffer, as many as fit.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+        loop {
+            if self.out_pos < self.out.len() {
+                let available = &self.out[self.out_pos..];
+                let n = available.len().min(buf.len() - written);
+                buf[written..written + n].copy_from_slice(&available[..n]);
+                self.out_pos += n;
+                written += n;
+            }
+            if written == buf.len() {
+                return Ok(written);
+            }
+
+            let chunk = self.inner.fill_buf()?;
+            let space = buf.len() - written;
+            match memchr(b'\n', chunk) {
+                Some(end) if end < space => {
+                    let line = &chunk[..=end];
+                    if self.state.scan_line(line, &mut self.out) {
+                        buf[written..=written + end].copy_from_slice(line);
+                        written += line.len();
+                    } else {
+                        self.out_pos = 0;
+                    }
+                    self.inner.consume(end + 1);
+                }
+                _ if written > 0 => return Ok(written),
+                _ => {
+                    // The line is longer than `buf`, or continues past the read buffer.
+                    self.line.clear();
+                    if self.inner.read_until(b'\n', &mut self.line)? == 0 {
+                        self.state.finish_game();
+                        return Ok(0);
+                    }
+                    if self.state.scan_line(&self.line, &mut self.out) {
+                        self.out.clear();
+                        self.out.extend_from_slice(&self.line);
+                    }
+                    self.out_pos = 0;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recover(input: &str) -> String {
+        let mut output = String::new();
+        GameBoundaryScanner::new(input.as_bytes(), 0, false)
+            .read_to_string(&mut output)
+            .unwrap();
+        output
+    }
+
+    fn marker() -> String {
+        format!(
+            "[{} \"1\"]\n",
+            String::from_utf8_lossy(RECOVERED_BOUNDARY_TAG)
+        )
+    }
+
+    #[test]
+    fn test_well_formed_input_passes_through_unchanged() {
+        let pgn =
+            "[Event \"A\"]\n\n1. e4 e5 1-0\n\n[Event \"B\"]\r\n\r\n1. d4 { 1-0 [Event ] } *\n";
+        assert_eq!(recover(pgn), pgn);
+        assert_eq!(recover(""), "");
+    }
+
+    #[test]
+    fn test_event_on_next_line_gets_blank_line_and_marker() {
+        let pgn = "[Event \"A\"]\n\n1. e4 e5 1-0\n  [Event \"B\"]\n1. d4 *";
+        assert_eq!(
+            recover(pgn),
+            format!(
+                "[Event \"A\"]\n\n1. e4 e5 1-0\n\n{}[Event \"B\"]\n1. d4 *",
+                marker()
+            )
+        );
+    }
+
+    #[test]
+    fn test_event_on_result_line_is_split() {
+        let spaced = recover("1. e4 1/2-1/2 [Event \"B\"]\n1. d4 *\n");
+        assert_eq!(
+            spaced,
+            format!("1. e4 1/2-1/2 \n\n{}[Event \"B\"]\n1. d4 *\n", marker())
+        );
+
+        let glued = recover("1. e4 0-1[Event \"B\"]\n");
+        assert_eq!(glued, format!("1. e4 0-1\n\n{}[Event \"B\"]\n", marker()));
+    }
+
+    #[test]
+    fn test_no_split_without_result_or_inside_comment() {
+        let no_result = "1. e4 e5\n[Event \"B\"]\n";
+        assert_eq!(recover(no_result), no_result);
+
+        let in_comment = "1. e4 1-0 {\n[Event \"B\"] }\n";
+        assert_eq!(recover(in_comment), in_comment);
+
+        let other_tag = "1. e4 1-0\n[Site \"B\"]\n";
+        assert_eq!(recover(other_tag), other_tag);
+    }
+
+    #[test]
+    fn test_game_offsets_point_at_first_tag() {
+        let pgn = "[Event \"A\"]\n[Site \"x\"]\n\n1. e4 1-0\n\n  [Event \"B\"]\n\n[Site \"y\"]\n1. d4 0-1 [Event \"C\"]\n1. c4 *\n";
+        let mut output = String::new();
+        GameBoundaryScanner::new(pgn.as_bytes(), 0, true)
+            .read_to_string(&mut output)
+            .unwrap();
+
+        let offsets: Vec<usize> = output
+            .lines()
+            .filter_map(|line| {
+                let rest = line.strip_prefix("[DuckdbChessByteOffset \"")?;
+                rest.strip_suffix("\"]")?.parse().ok()
+            })
+            .collect();
+        // A blank line inside a tag section does not start a new game.
+        assert_eq!(offsets.len(), 3);
+        for (offset, event) in offsets.iter().zip(["A", "B", "C"]) {
+            assert!(pgn[*offset..].starts_with(&format!("[Event \"{event}\"]")));
+        }
+    }
+
+    #[test]
+    fn test_game_lines_count_source_lines() {
+        let pgn = "[Event \"A\"]\r\n\r\n1. e4 1-0\r\n\r\n% note\n  [Event \"B\"]\n\n[Site \"y\"]\n1. d4 0-1 [Event \"C\"]\r1. c4 *";
+        let mut output = String::new();
+        GameBoundaryScanner::new(pgn.as_bytes(), 0, false)
+            .with_line_numbers()
+            .read_to_string(&mut output)
+            .unwrap();
+
+        let lines: Vec<u64> = output
+            .lines()
+            .filter_map(|line| {
+                let rest = line.strip_prefix("[DuckdbChessLineNumber \"")?;
+                rest.strip_suffix("\"]")?.parse().ok()
+            })
+            .collect();
+        // `C` shares the line of `B`'s result; the bare `\r` after it still ends a line.
+        assert_eq!(lines, vec![1, 6, 9]);
+        assert!(!output.contains("DuckdbChessByteOffset"));
+    }
+
+    #[test]
+    fn test_game_offsets_are_relative_to_base_offset() {
+        let mut output = String::new();
+        GameBoundaryScanner::new("\n[Event \"A\"]\n*\n".as_bytes(), 1000, true)
+            .read_to_string(&mut output)
+            .unwrap();
+        assert!(output.contains("[DuckdbChessByteOffset \"1001\"]\n[Event \"A\"]"));
+    }
+
+    #[test]
+    fn test_movetext_sizes_count_raw_movetext_per_game() {
+        let pgn = "[Event \"A\"]\n\n1. e4 {\n\n} e5\n\t\n2. Nf3 1-0\n\n[Event \"B\"]\n\n*\n\n[Event \"C\"]\n*";
+        let sizes = MovetextSizes::default();
+        let mut output = String::new();
+        GameBoundaryScanner::new(pgn.as_bytes(), 0, false)
+            .with_movetext_sizes(sizes.clone())
+            .read_to_string(&mut output)
+            .unwrap();
+
+        let offset = |event: &str| pgn.find(&format!("[Event \"{event}\"]")).unwrap() as u64;
+        // Blank lines inside a comment and whitespace-only lines do not end the movetext.
+        assert_eq!(
+            sizes.take(offset("A")),
+            Some("1. e4 {\n\n} e5\n\t\n2. Nf3 1-0\n".len() as u64)
+        );
+        // Looking up a later game drops the sizes of games that were never read.
+        assert_eq!(sizes.take(offset("C")), Some(1));
+        assert_eq!(sizes.take(offset("B")), None);
+    }
+
+    #[test]
+    fn test_escape_lines_do_not_change_scan_state() {
+        // The `{` of an escape line must not open a comment that hides the next boundary.
+        let pgn = "% exported { by a tool\n[Event \"A\"]\n1. e4 1-0 [Event \"B\"]\n*\n";
+        assert_eq!(
+            recover(pgn),
+            format!(
+                "% exported {{ by a tool\n[Event \"A\"]\n1. e4 1-0 \n\n{}[Event \"B\"]\n*\n",
+                marker()
+            )
+        );
+
+        // An escape line between tags does not start a second game, and one inside the
+        // movetext is counted with it.
+        let pgn = "[Event \"A\"]\n% note\n[Site \"x\"]\n\n1. e4\n% 1-0\ne5 *\n";
+        let sizes = MovetextSizes::default();
+        let mut output = String::new();
+        GameBoundaryScanner::new(pgn.as_bytes(), 0, false)
+            .with_movetext_sizes(sizes.clone())
+            .read_to_string(&mut output)
+            .unwrap();
+        assert_eq!(output.matches("DuckdbChessByteOffset").count(), 1);
+        assert_eq!(sizes.take(0), Some("1. e4\n% 1-0\ne5 *\n".len() as u64));
+    }
+
+    /// Reader yielding one byte per call, so every `\r` ends a chunk.
+    struct ByteAtATime<'a>(&'a [u8]);
+
+    impl Read for ByteAtATime<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((&first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_bare_cr_line_endings_become_lf() {
+        let pgn = "[Event \"A\"]\r\r1. e4 1-0\r\n\r\n[Event \"B\"]\r\r*\r";
+        let expected = "[Event \"A\"]\n\n1. e4 1-0\r\n\r\n[Event \"B\"]\n\n*\n";
+        for chunked in [false, true] {
+            let diagnostics = Arc::new(LineDiagnostics::default());
+            let mut output = String::new();
+            let input: Box<dyn Read> = if chunked {
+                Box::new(ByteAtATime(pgn.as_bytes()))
+            } else {
+                Box::new(pgn.as_bytes())
+            };
+            GameBoundaryScanner::new(input, 0, false)
+                .with_diagnostics(Arc::clone(&diagnostics))
+                .read_to_string(&mut output)
+                .unwrap();
+            assert_eq!(output, expected);
+            assert_eq!(diagnostics.cr_line_endings.load(Ordering::Relaxed), 5);
+            assert_eq!(diagnostics.crlf_line_endings.load(Ordering::Relaxed), 2);
+        }
+    }
+
+    #[test]
+    fn test_movetext_on_tag_line_moves_to_next_line() {
+        let diagnostics = Arc::new(LineDiagnostics::default());
+        let mut output = String::new();
+        GameBoundaryScanner::new(
+            "[Event \"A] \\\"x\"] [Site \"?\"] 1. e4 1-0 [Event \"B\"] 1. d4 *\n".as_bytes(),
+            0,
+            false,
+        )
+        .with_diagnostics(Arc::clone(&diagnostics))
+        .read_to_string(&mut output)
+        .unwrap();
+        assert_eq!(
+            output,
+            format!(
+                "[Event \"A] \\\"x\"] [Site \"?\"]\n 1. e4 1-0 \n\n{}[Event \"B\"]\n 1. d4 *\n",
+                marker()
+            )
+        );
+        assert_eq!(
+            diagnostics.counts(),
+            [
+                ("files", 0),
+                ("cr_line_endings", 0),
+                ("crlf_line_endings", 0),
+                ("tag_lines_split", 2),
+                ("boundaries_recovered", 1),
+                ("variant_games_skipped", 0),
+                ("empty_games_skipped", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_counting_from_skips_repairs_already_counted() {
+        let pgn = "[Event \"A\"] 1. e4 *\r[Event \"B\"] 1. d4 *\r";
+        for chunked in [false, true] {
+            let diagnostics = Arc::new(LineDiagnostics::default());
+            let input: Box<dyn Read> = if chunked {
+                Box::new(ByteAtATime(pgn.as_bytes()))
+            } else {
+                Box::new(pgn.as_bytes())
+            };
+            // The first line, `\r` included, was counted by an earlier scanner.
+            let mut scanner = GameBoundaryScanner::new(input, 0, false)
+                .with_diagnostics(Arc::clone(&diagnostics))
+                .counting_from(CountedThrough {
+                    line_endings: 20,
+                    lines: 20,
+                });
+            let progress = scanner.progress();
+            io::copy(&mut scanner, &mut io::sink()).unwrap();
+            assert_eq!(diagnostics.cr_line_endings.load(Ordering::Relaxed), 1);
+            assert_eq!(diagnostics.tag_lines_split.load(Ordering::Relaxed), 1);
+            assert_eq!(
+                progress.counted_through(),
+                CountedThrough {
+                    line_endings: pgn.len() as u64,
+                    lines: pgn.len() as u64,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_output_does_not_depend_on_read_sizes() {
+        // Lines longer than the read buffer, and rewritten lines, straddle `read` calls.
+        let long_comment = format!("{{{}}}", "x".repeat(70_000));
+        let pgn = format!(
+            "[Event \"A\"] 1. e4 {long_comment} 1-0 [Event \"B\"]\r*\n\n{}",
+            "[Event \"C\"]\n\n1. d4 { [%clk 0:03:00] } d5 0-1\n\n".repeat(2_000)
+        );
+        let mut expected = Vec::new();
+        GameBoundaryScanner::new(pgn.as_bytes(), 0, true)
+            .read_to_end(&mut expected)
+            .unwrap();
+        assert!(expected.starts_with(
+            format!(
+                "[DuckdbChessByteOffset \"0\"]\n[Event \"A\"]\n 1. e4 {long_comment} 1-0 \n\n{}[DuckdbChessByteOffset \"{}\"]\n[Event \"B\"]\n*\n",
+                marker(),
+                pgn.find("[Event \"B\"]").unwrap()
+            )
+            .as_bytes()
+        ));
+
+        for size in [1, 7, 4096] {
+            let mut scanner = GameBoundaryScanner::new(pgn.as_bytes(), 0, true);
+            let mut buf = vec![0; size];
+            let mut output = Vec::new();
+            loop {
+                let n = scanner.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                output.extend_from_slice(&buf[..n]);
+            }
+            assert_eq!(output, expected, "read size {size}");
+        }
+    }
+
+    #[test]
+    fn test_tag_lines_without_movetext_are_not_split() {
+        for pgn in [
+            "[Event \"A\"] [Site \"?\"]  \n\n1. e4 *\n",
+            "[Event \"A\"]\r\n\r\n1. e4 *\r\n",
+            "[Event \"unterminated 1. e4 *\n",
+        ] {
+            assert_eq!(recover(pgn), pgn);
+        }
+    }
+}
@@ -0,0 +1,378 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{
+    invoke_binary_varchar_varchar_to_i64_nullable,
+    invoke_unary_varchar_optional_varchar_to_interval_list,
+};
+use super::timecontrol::{parse_timecontrol, seconds_available_for_move};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use pgn_reader::{Nag, RawComment, Reader, SanPlus, Skip, Visitor};
+#[cfg(feature = "extension")]
+use std::error::Error;
+use std::io;
+use std::ops::ControlFlow;
+
+pub(crate) const MICROS_PER_SECOND: i64 = 1_000_000;
+
+/// Which plies of a game a clock series covers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ClockSide {
+    White,
+    Black,
+    Both,
+}
+
+impl ClockSide {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "white" | "w" => Some(Self::White),
+            "black" | "b" => Some(Self::Black),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
+
+    /// `ply` is 1-based: odd plies are White's moves.
+    fn includes(self, ply: usize) -> bool {
+        match self {
+            Self::White => !ply.is_multiple_of(2),
+            Self::Black => ply.is_multiple_of(2),
+            Self::Both => true,
+        }
+    }
+}
+
+/// Parse the first `[%clk H:MM:SS(.f)]` annotation in a comment as microseconds.
+pub fn parse_clk_micros(comment: &str) -> Option<i64> {
+    let start = comment.find("[%clk")? + "[%clk".len();
+    let rest = &comment[start..];
+    let end = rest.find(']')?;
+    parse_clock_value(rest[..end].trim())
+}
+
+fn parse_clock_value(raw: &str) -> Option<i64> {
+    let mut parts = raw.split(':').rev();
+    let seconds_part = parts.next()?;
+    let minutes: i64 = parts.next()?.parse().ok()?;
+    let hours: i64 = match parts.next() {
+        Some(h) => h.parse().ok()?,
+        None => 0,
+    };
+    if parts.next().is_some() || hours < 0 || !(0..60).contains(&minutes) {
+        return None;
+    }
+
+    let (whole, fraction) = seconds_part.split_once('.').unwrap_or((seconds_part, ""));
+    let seconds: i64 = whole.parse().ok()?;
+    if !(0..60).contains(&seconds) || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let fraction_micros = fraction
+        .chars()
+        .chain(std::iter::repeat('0'))
+        .take(6)
+        .collect::<String>()
+        .parse::<i64>()
+        .ok()?;
+
+    let total_seconds = hours
+        .checked_mul(3600)?
+        .checked_add(minutes * 60 + seconds)?;
+    total_seconds
+        .checked_mul(MICROS_PER_SECOND)?
+        .checked_add(fraction_micros)
+}
+
+/// Remaining clock after each mainline move of `side`, in move order, from `%clk` comments.
+///
+/// Moves without a clock annotation produce `None` elements so positions in the series still
+/// line up with move numbers. Returns `None` when the movetext cannot be parsed.
+pub fn clock_series(movetext: &str, side: ClockSide) -> Option<Vec<Option<i64>>> {
+    Some(
+        annotation_series(movetext, parse_clk_micros)?
+            .into_iter()
+            .enumerate()
+            .filter(|(idx, _)| side.includes(idx + 1))
+            .map(|(_, clock)| clock)
+            .collect(),
+    )
+}
+
+/// One slot per mainline ply holding the first comment annotation after the move that `parse`
+/// accepts, or `None`. Returns `None` when the movetext cannot be parsed.
+pub(crate) fn annotation_series<T>(
+    movetext: &str,
+    parse: fn(&str) -> Option<T>,
+) -> Option<Vec<Option<T>>> {
+    let mut visitor = AnnotationVisitor {
+        parse,
+        values: Vec::new(),
+    };
+    if !movetext.trim().is_empty() {
+        let mut reader = Reader::new(io::Cursor::new(movetext.as_bytes()));
+        reader.read_game(&mut visitor).ok()?;
+    }
+    Some(visitor.values)
+}
+
+/// Estimated wall-clock length of a game in seconds, from its TimeControl and `%clk` comments.
+///
+/// Each side's thinking time is the time credited by the TimeControl up to its last clocked
+/// move minus the clock remaining after it; the estimate is the sum for both sides, rounded to
+/// the nearest second. Returns `None` when the TimeControl is not a timed control or a side
+/// that moved has no clock annotation.
+pub fn game_duration_seconds(movetext: &str, timecontrol: &str) -> Option<i64> {
+    let parsed = parse_timecontrol(timecontrol).ok()?;
+    let clocks = clock_series(movetext, ClockSide::Both)?;
+    if clocks.is_empty() {
+        return None;
+    }
+
+    let mut total_micros: i64 = 0;
+    for side in 0..clocks.len().min(2) {
+        let (ply_idx, remaining) = clocks
+            .iter()
+            .enumerate()
+            .skip(side)
+            .step_by(2)
+            .filter_map(|(idx, clock)| clock.map(|clock| (idx, clock)))
+            .next_back()?;
+        let move_number = (ply_idx / 2 + 1) as u64;
+        let available = i64::try_from(seconds_available_for_move(&parsed, move_number)?).ok()?;
+        let used = available
+            .checked_mul(MICROS_PER_SECOND)?
+            .saturating_sub(remaining)
+            .max(0);
+        total_micros = total_micros.checked_add(used)?;
+    }
+
+    Some((total_micros + MICROS_PER_SECOND / 2) / MICROS_PER_SECOND)
+}
+
+/// Collects one slot per mainline ply; the first annotation after a move that `parse` accepts
+/// fills its slot.
+struct AnnotationVisitor<T> {
+    parse: fn(&str) -> Option<T>,
+    values: Vec<Option<T>>,
+}
+
+impl<T> Visitor for AnnotationVisitor<T> {
+    type Tags = ();
+    type Movetext = ();
+    type Output = ();
+
+    fn begin_tags(&mut self) -> ControlFlow<Self::Output, Self::Tags> {
+        self.values.clear();
+        ControlFlow::Continue(())
+    }
+
+    fn begin_movetext(&mut self, _tags: Self::Tags) -> ControlFlow<Self::Output, Self::Movetext> {
+        ControlFlow::Continue(())
+    }
+
+    fn san(
+        &mut self,
+        _movetext: &mut Self::Movetext,
+        _san_plus: SanPlus,
+    ) -> ControlFlow<Self::Output> {
+        self.values.push(None);
+        ControlFlow::Continue(())
+    }
+
+    fn comment(
+        &mut self,
+        _movetext: &mut Self::Movetext,
+        comment: RawComment<'_>,
+    ) -> ControlFlow<Self::Output> {
+        if let Some(slot @ None) = self.values.last_mut() {
+            *slot = (self.parse)(&String::from_utf8_lossy(comment.as_bytes()));
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn nag(&mut self, _movetext: &mut Self::Movetext, _nag: Nag) -> ControlFlow<Self::Output> {
+        ControlFlow::Continue(())
+    }
+
+    fn begin_variation(
+        &mut self,
+        _movetext: &mut Self::Movetext,
+    ) -> ControlFlow<Self::Output, Skip> {
+        ControlFlow::Continue(Skip(true))
+    }
+
+    fn end_game(&mut self, _movetext: Self::Movetext) -> Self::Output {}
+}
+
+// Spec: move-analysis - Clock Series
+#[cfg(feature = "extension")]
+pub struct ChessClockSeriesScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessClockSeriesScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_unary_varchar_optional_varchar_to_interval_list(input, output, |movetext, side| {
+            let side = match side {
+                None => ClockSide::Both,
+                Some(raw) => ClockSide::parse(raw).ok_or_else(|| {
+                    format!(
+                        "chess_clock_series: invalid side '{raw}'. Supported values: 'white', 'black', 'both'."
+                    )
+                })?,
+            };
+            Ok(clock_series(movetext, side))
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let series_type =
+            || LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Interval));
+        vec![
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                series_type(),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                series_type(),
+            ),
+        ]
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessGameDurationSecondsScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessGameDurationSecondsScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_binary_varchar_varchar_to_i64_nullable(input, output, game_duration_seconds)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Bigint),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: i64 = MICROS_PER_SECOND;
+
+    #[test]
+    fn test_parse_clk_micros_formats() {
+        assert_eq!(
+            parse_clk_micros("[%eval 0.25] [%clk 1:30:43]"),
+            Some((3600 + 30 * 60 + 43) * SECOND)
+        );
+        assert_eq!(
+            parse_clk_micros("[%clk 0:00:05.3]"),
+            Some(5 * SECOND + 300_000)
+        );
+        assert_eq!(parse_clk_micros("[%clk 2:05]"), Some(125 * SECOND));
+        assert_eq!(
+            parse_clk_micros("[%clk 30:00:00]"),
+            Some(30 * 3600 * SECOND)
+        );
+        assert_eq!(parse_clk_micros("[%clk 1:75:00]"), None);
+        assert_eq!(parse_clk_micros("[%clk soon]"), None);
+        assert_eq!(parse_clk_micros("no clock here"), None);
+    }
+
+    #[test]
+    fn test_clock_series_per_side() {
+        let movetext = "1. e4 { [%clk 0:03:00] } e5 { [%clk 0:02:59] } 2. Nf3 { [%clk 0:02:58] } Nc6 { [%clk 0:02:55] }";
+
+        assert_eq!(
+            clock_series(movetext, ClockSide::White),
+            Some(vec![Some(180 * SECOND), Some(178 * SECOND)])
+        );
+        assert_eq!(
+            clock_series(movetext, ClockSide::Black),
+            Some(vec![Some(179 * SECOND), Some(175 * SECOND)])
+        );
+        assert_eq!(
+            clock_series(movetext, ClockSide::Both).map(|series| series.len()),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn test_clock_series_keeps_gaps_and_ignores_variations() {
+        let movetext = "{ [%clk 9:99:99] } 1. e4 e5 { [%clk 0:02:59] } (1... c5 { [%clk 0:00:01] }) 2. Nf3 { [%clk 0:02:58] } { [%clk 0:00:02] }";
+
+        assert_eq!(
+            clock_series(movetext, ClockSide::Both),
+            Some(vec![None, Some(179 * SECOND), Some(178 * SECOND)])
+        );
+        assert_eq!(clock_series("", ClockSide::Both), Some(vec![]));
+    }
+
+    #[test]
+    fn test_game_duration_seconds_sums_both_sides() {
+        // 3+2: White used 184 - 170 = 14s over two moves, Black 182 - 175 = 7s over one.
+        let movetext = "1. e4 { [%clk 0:03:00] } e5 { [%clk 0:02:55] } 2. Nf3 { [%clk 0:02:50] }";
+        assert_eq!(game_duration_seconds(movetext, "180+2"), Some(21));
+        assert_eq!(game_duration_seconds(movetext, "3+2"), Some(21));
+
+        // Second period: White's 41st move is credited 5400 + 40*30 + 1800 + 30 = 8430s and
+        // Black's 40th 5400 + 40*30 = 6600s.
+        let shuffle = ["Nf3", "Nf6", "Ng1", "Ng8"];
+        let mut plies: Vec<String> = (0..81).map(|ply| shuffle[ply % 4].to_string()).collect();
+        plies[79].push_str(" { [%clk 0:20:00] }");
+        plies[80].push_str(" { [%clk 0:10:00] }");
+        let movetext = plies.join(" ");
+        assert_eq!(
+            game_duration_seconds(&movetext, "40/5400+30:1800+30"),
+            Some((8430 - 600) + (6600 - 1200))
+        );
+    }
+
+    #[test]
+    fn test_game_duration_seconds_requires_clocks_and_timed_control() {
+        let movetext = "1. e4 { [%clk 0:03:00] } e5 { [%clk 0:02:59] }";
+        assert_eq!(game_duration_seconds(movetext, "-"), None);
+        assert_eq!(game_duration_seconds(movetext, "?"), None);
+        assert_eq!(game_duration_seconds("1. e4 e5", "180+2"), None);
+        assert_eq!(game_duration_seconds("", "180+2"), None);
+        // Black has not moved yet: only White's time counts.
+        assert_eq!(
+            game_duration_seconds("1. e4 { [%clk 0:02:50] }", "180+2"),
+            Some(12)
+        );
+    }
+
+    #[test]
+    fn test_clock_side_parse() {
+        assert_eq!(ClockSide::parse("White"), Some(ClockSide::White));
+        assert_eq!(ClockSide::parse(" black "), Some(ClockSide::Black));
+        assert_eq!(ClockSide::parse("both"), Some(ClockSide::Both));
+        assert_eq!(ClockSide::parse("either"), None);
+    }
+}
@@ -0,0 +1,237 @@
+//! Compact binary encoding of a game's mainline.
+//!
+//! A compressed game is one header byte followed by one byte per ply. The header holds the
+//! format version in its high nibble and the result in its low nibble (see [`RESULTS`]). Each
+//! ply is the index of the move played among the legal moves of the position, ordered by
+//! their UCI `from`, `to` and promotion; no position has more than 218 legal moves, so one
+//! byte always suffices. The order only depends on the rules, so the encoding is stable across
+//! shakmaty versions.
+//!
+//! Spec: move-analysis - Moves Compression
+
+#[cfg(feature = "extension")]
+use super::duckdb_impl::string::{decode_duckdb_bytes, decode_duckdb_string, try_insert_varchar};
+use super::filter::{parse_movetext_mainline, push_numbered_move};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, Inserter, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+#[cfg(feature = "extension")]
+use libduckdb_sys::duckdb_string_t;
+use shakmaty::{CastlingMode, Chess, Move, Position, san::San, san::SanPlus, uci::UciMove};
+#[cfg(feature = "extension")]
+use std::error::Error;
+
+const FORMAT_VERSION: u8 = 1;
+
+/// Result markers by their code in the header's low nibble; 0 is a game without a marker.
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Sort key of `m` among the legal moves of a position. Castling is keyed by the king's move,
+/// as in standard UCI.
+fn move_key(m: Move) -> u32 {
+    match m.to_uci(CastlingMode::Standard) {
+        UciMove::Normal {
+            from,
+            to,
+            promotion,
+        } => (u32::from(from) << 9) | (u32::from(to) << 3) | promotion.map_or(0, |r| r as u32),
+        // Drops and null moves are never legal in standard chess.
+        UciMove::Put { .. } | UciMove::Null => u32::MAX,
+    }
+}
+
+/// Encode the mainline and result of `movetext`. Comments, variations and NAGs are dropped.
+/// Returns `None` when the movetext cannot be parsed or contains an illegal move.
+pub fn compress_movetext(movetext: &str) -> Option<Vec<u8>> {
+    let parsed = parse_movetext_mainline(movetext);
+    if parsed.parse_error {
+        return None;
+    }
+    let result_code = match parsed.outcome.as_deref() {
+        None => 0,
+        Some(outcome) => RESULTS.iter().position(|r| *r == outcome)? as u8 + 1,
+    };
+
+    let mut encoded = Vec::with_capacity(parsed.sans.len() + 1);
+    encoded.push((FORMAT_VERSION << 4) | result_code);
+    let mut pos = Chess::default();
+    for san in &parsed.sans {
+        let m = san.parse::<San>().ok()?.to_move(&pos).ok()?;
+        let key = move_key(m);
+        let index = pos
+            .legal_moves()
+            .iter()
+            .filter(|other| move_key(**other) < key)
+            .count();
+        encoded.push(index as u8);
+        pos.play_unchecked(m);
+    }
+    Some(encoded)
+}
+
+/// Decode a game encoded by [`compress_movetext`] into canonical numbered SAN movetext, the
+/// layout of `chess_san_canonical`. Returns `None` for bytes that are not a valid encoding.
+pub fn decompress_movetext(encoded: &[u8]) -> Option<String> {
+    let (&header, plies) = encoded.split_first()?;
+    if header >> 4 != FORMAT_VERSION {
+        return None;
+    }
+    let result = match header & 0x0F {
+        0 => None,
+        code => Some(*RESULTS.get(usize::from(code) - 1)?),
+    };
+
+    let mut output = String::with_capacity(plies.len() * 5);
+    let mut pos = Chess::default();
+    for (ply, &index) in plies.iter().enumerate() {
+        let mut legal = pos.legal_moves();
+        if usize::from(index) >= legal.len() {
+            return None;
+        }
+        legal.sort_unstable_by_key(|m| move_key(*m));
+        let san = SanPlus::from_move_and_play_unchecked(&mut pos, legal[usize::from(index)]);
+        push_numbered_move(&mut output, ply, san);
+    }
+    if let Some(result) = result {
+        if !output.is_empty() {
+            output.push(' ');
+        }
+        output.push_str(result);
+    }
+    Some(output)
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessMovesCompressScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessMovesCompressScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let len = input.len();
+        let input_vec = input.flat_vector(0);
+        let input_slice = input_vec.as_slice_with_len::<duckdb_string_t>(len);
+        let mut output_vec = output.flat_vector();
+
+        for (row, raw) in input_slice.iter().enumerate() {
+            if input_vec.row_is_null(row as u64) {
+                output_vec.set_null(row);
+                continue;
+            }
+            // SAFETY: The input row is checked non-NULL above.
+            let movetext = unsafe { decode_duckdb_string(raw) };
+            match compress_movetext(&movetext) {
+                Some(encoded) => output_vec.insert(row, encoded.as_slice()),
+                None => output_vec.set_null(row),
+            }
+        }
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            LogicalTypeHandle::from(LogicalTypeId::Blob),
+        )]
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessMovesDecompressScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessMovesDecompressScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let len = input.len();
+        let input_vec = input.flat_vector(0);
+        let input_slice = input_vec.as_slice_with_len::<duckdb_string_t>(len);
+        let mut output_vec = output.flat_vector();
+
+        for (row, raw) in input_slice.iter().enumerate() {
+            if input_vec.row_is_null(row as u64) {
+                output_vec.set_null(row);
+                continue;
+            }
+            // SAFETY: The input row is checked non-NULL above.
+            let encoded = unsafe { decode_duckdb_bytes(raw) };
+            match decompress_movetext(encoded) {
+                Some(movetext) => try_insert_varchar(&output_vec, row, &movetext)?,
+                None => output_vec.set_null(row),
+            }
+        }
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Blob)],
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_movetext_round_trips_to_canonical_san() {
+        let movetext = "1. e4 {Best by test} e5 2. Nf3 (2. f4 exf4) Nc6 3. Bb5 a6?! \
+                        4. O-O Nf6 5. Qe2 $1 Be7 6. b4 Nxb4 1/2-1/2";
+        let encoded = compress_movetext(movetext).unwrap();
+        assert_eq!(encoded.len(), 1 + 12);
+        assert_eq!(
+            decompress_movetext(&encoded).as_deref(),
+            Some("1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. O-O Nf6 5. Qe2 Be7 6. b4 Nxb4 1/2-1/2")
+        );
+
+        // Promotions and disambiguation survive; the result marker is optional.
+        let promotion = "1. h4 g5 2. hxg5 Nf6 3. gxf6 Rg8 4. fxe7 Rg6 5. exd8=N";
+        assert_eq!(
+            decompress_movetext(&compress_movetext(promotion).unwrap()).as_deref(),
+            Some(promotion)
+        );
+        assert_eq!(compress_movetext(""), Some(vec![0x10]));
+        assert_eq!(decompress_movetext(&[0x10]).as_deref(), Some(""));
+        assert_eq!(decompress_movetext(&[0x14]).as_deref(), Some("*"));
+    }
+
+    #[test]
+    fn test_move_indices_follow_uci_order() {
+        // From the start position the knight moves from b1 and g1 sort first, then the pawn
+        // moves from a2 to h2.
+        assert_eq!(compress_movetext("1. Na3"), Some(vec![0x10, 0]));
+        assert_eq!(compress_movetext("1. a3"), Some(vec![0x10, 4]));
+        assert_eq!(compress_movetext("1. h4"), Some(vec![0x10, 19]));
+    }
+
+    #[test]
+    fn test_compress_movetext_rejects_illegal_movetext() {
+        assert_eq!(compress_movetext("1. e4 e4"), None);
+        assert_eq!(compress_movetext("1. O-O"), None);
+    }
+
+    #[test]
+    fn test_decompress_movetext_rejects_invalid_encodings() {
+        assert_eq!(decompress_movetext(&[]), None);
+        // Unknown format version and result code.
+        assert_eq!(decompress_movetext(&[0x20]), None);
+        assert_eq!(decompress_movetext(&[0x15]), None);
+        // The start position has 20 legal moves.
+        assert_eq!(decompress_movetext(&[0x10, 20]), None);
+    }
+}
@@ -0,0 +1,98 @@
+//! Decompression of zstd, gzip, bzip2 and xz PGN input, detected from the leading bytes.
+
+use super::visitor::PgnInput;
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use lzma_rust2::XzReader;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use zstd::stream::read::Decoder as ZstdDecoder;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum CompressionMode {
+    /// Detect the format from the leading magic bytes of each file.
+    Auto,
+    Plain,
+    Zstd,
+    Gzip,
+    Bzip2,
+    Xz,
+}
+
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+pub(crate) const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+const XZ_MAGIC: &[u8] = &[0xFD, b'7', b'z', b'X', b'Z', 0x00];
+const MAGIC_BYTES_LEN: usize = 6;
+
+impl CompressionMode {
+    pub(crate) fn detect(magic: &[u8]) -> Self {
+        [
+            (ZSTD_MAGIC, Self::Zstd),
+            (GZIP_MAGIC, Self::Gzip),
+            (BZIP2_MAGIC, Self::Bzip2),
+            (XZ_MAGIC, Self::Xz),
+        ]
+        .into_iter()
+        .find(|(prefix, _)| magic.starts_with(prefix))
+        .map_or(Self::Plain, |(_, mode)| mode)
+    }
+}
+
+/// Read up to `MAGIC_BYTES_LEN` leading bytes without requiring a seekable input.
+pub(crate) fn read_magic_bytes(input: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut magic = Vec::with_capacity(MAGIC_BYTES_LEN);
+    input
+        .by_ref()
+        .take(MAGIC_BYTES_LEN as u64)
+        .read_to_end(&mut magic)?;
+    Ok(magic)
+}
+
+/// Resolve `Auto` from the leading bytes of `source`. The sniffed bytes are chained back in
+/// front, so pipes and FIFOs (e.g. `/dev/stdin`) work without seeking.
+pub(crate) fn sniff_compression(
+    mut source: PgnInput,
+    compression: CompressionMode,
+) -> io::Result<(PgnInput, CompressionMode)> {
+    match compression {
+        CompressionMode::Auto => {
+            let magic = read_magic_bytes(&mut source)?;
+            let detected = CompressionMode::detect(&magic);
+            Ok((Box::new(io::Cursor::new(magic).chain(source)), detected))
+        }
+        explicit => Ok((source, explicit)),
+    }
+}
+
+pub(crate) fn open_decoder(
+    source: PgnInput,
+    compression: CompressionMode,
+    path: &Path,
+) -> Result<PgnInput, String> {
+    match compression {
+        CompressionMode::Auto | CompressionMode::Plain => Ok(source),
+        CompressionMode::Zstd => ZstdDecoder::new(source)
+            .map(|decoder| Box::new(decoder) as PgnInput)
+            .map_err(|e| {
+                format!(
+                    "Failed to initialize zstd decoder for '{}': {}",
+                    path.display(),
+                    e
+                )
+            }),
+        CompressionMode::Gzip => Ok(Box::new(MultiGzDecoder::new(source))),
+        CompressionMode::Bzip2 => Ok(Box::new(MultiBzDecoder::new(source))),
+        CompressionMode::Xz => Ok(Box::new(XzReader::new(source, true))),
+    }
+}
+
+/// Open the local file at `path` and decode it as its leading bytes say.
+pub(crate) fn open_local_decoded(path: &Path) -> Result<PgnInput, String> {
+    let file =
+        File::open(path).map_err(|e| format!("Failed to open file '{}': {}", path.display(), e))?;
+    let (source, compression) = sniff_compression(Box::new(file), CompressionMode::Auto)
+        .map_err(|e| format!("Failed to read file '{}': {}", path.display(), e))?;
+    open_decoder(source, compression, path)
+}
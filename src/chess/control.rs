@@ -0,0 +1,165 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{
+    VarcharInput, invoke_binary_to_struct_nullable,
+    invoke_unary_varchar_to_varchar_i32_i32_struct_list,
+};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use shakmaty::{Board, CastlingMode, Chess, Color, Position, Square, fen::Fen};
+#[cfg(feature = "extension")]
+use std::error::Error;
+
+/// Board of the position described by `fen`, or `None` when the FEN is invalid.
+fn fen_board(fen: &str) -> Option<Board> {
+    let parsed: Fen = fen.trim().parse().ok()?;
+    let position: Chess = parsed.into_position(CastlingMode::Standard).ok()?;
+    Some(position.board().clone())
+}
+
+/// Pieces of each side attacking `square`, as `[white, black]`. Pinned pieces count; pieces
+/// lined up behind another attacker (x-rays) do not.
+fn attackers(board: &Board, square: Square) -> [i32; 2] {
+    [Color::White, Color::Black]
+        .map(|color| board.attacks_to(square, color, board.occupied()).count() as i32)
+}
+
+/// White and black attackers of `square` (e.g. `e4`, case-insensitive) in the position of
+/// `fen`; `None` for an invalid FEN or square.
+/// Spec: move-analysis - Square Control
+pub fn square_control(fen: &str, square: &str) -> Option<[i32; 2]> {
+    let square = Square::from_ascii(square.trim().to_ascii_lowercase().as_bytes()).ok()?;
+    Some(attackers(&fen_board(fen)?, square))
+}
+
+/// [`square_control`] of every square, from `a1`, `b1`, ... to `h8`.
+pub fn board_control(fen: &str) -> Option<Vec<(Square, [i32; 2])>> {
+    let board = fen_board(fen)?;
+    Some(
+        Square::ALL
+            .into_iter()
+            .map(|square| (square, attackers(&board, square)))
+            .collect(),
+    )
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessSquareControlScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessSquareControlScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        if input.num_columns() == 1 {
+            return invoke_unary_varchar_to_varchar_i32_i32_struct_list(input, output, |fen| {
+                board_control(fen).map(|squares| {
+                    squares
+                        .into_iter()
+                        .map(|(square, [white, black])| (square.to_string(), white, black))
+                        .collect()
+                })
+            });
+        }
+
+        invoke_binary_to_struct_nullable::<VarcharInput, VarcharInput, _, _, 2>(
+            input,
+            output,
+            |fen, square| Ok(square_control(fen, square)),
+        )
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let attackers = [
+            (
+                "white_attackers",
+                LogicalTypeHandle::from(LogicalTypeId::Integer),
+            ),
+            (
+                "black_attackers",
+                LogicalTypeHandle::from(LogicalTypeId::Integer),
+            ),
+        ];
+        let square_type = LogicalTypeHandle::struct_type(&[
+            ("square", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            (
+                "white_attackers",
+                LogicalTypeHandle::from(LogicalTypeId::Integer),
+            ),
+            (
+                "black_attackers",
+                LogicalTypeHandle::from(LogicalTypeId::Integer),
+            ),
+        ]);
+        vec![
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::struct_type(&attackers),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                LogicalTypeHandle::list(&square_type),
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test]
+    fn test_square_control_counts_attackers_per_side() {
+        // f3 is covered by the e2/g2 pawns and the g1 knight.
+        assert_eq!(square_control(START_FEN, "f3"), Some([3, 0]));
+        assert_eq!(square_control(START_FEN, "F6"), Some([0, 3]));
+        assert_eq!(square_control(START_FEN, " e4 "), Some([0, 0]));
+        // Occupied squares count the pieces defending them.
+        assert_eq!(square_control(START_FEN, "d2"), Some([4, 0]));
+        assert_eq!(square_control(START_FEN, "e1"), Some([1, 0]));
+    }
+
+    #[test]
+    fn test_square_control_ignores_x_rays_but_counts_pins() {
+        // The d1 queen is behind the d2 rook; the e2 bishop is pinned by the e8 rook.
+        let fen = "4r1k1/8/8/8/8/8/3RB3/3QK3 w - - 0 1";
+        assert_eq!(square_control(fen, "d5"), Some([1, 0]));
+        assert_eq!(square_control(fen, "f3"), Some([1, 0]));
+        assert_eq!(square_control(fen, "e2"), Some([3, 1]));
+    }
+
+    #[test]
+    fn test_square_control_rejects_invalid_input() {
+        assert_eq!(square_control("not a fen", "e4"), None);
+        assert_eq!(square_control(START_FEN, "i9"), None);
+        assert_eq!(square_control(START_FEN, ""), None);
+        assert_eq!(board_control(""), None);
+    }
+
+    #[test]
+    fn test_board_control_lists_every_square_in_order() {
+        let squares = board_control(START_FEN).unwrap();
+        assert_eq!(squares.len(), 64);
+        assert_eq!(squares[0], (Square::A1, [0, 0]));
+        assert_eq!(squares[21], (Square::F3, [3, 0]));
+        assert_eq!(squares[63].0, Square::H8);
+        for (square, control) in squares {
+            assert_eq!(
+                Some(control),
+                square_control(START_FEN, &square.to_string())
+            );
+        }
+    }
+}
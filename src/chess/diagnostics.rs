@@ -0,0 +1,171 @@
+use super::boundary::LineDiagnostics;
+use super::duckdb_impl::bind_info_ffi::{self, NamedParameterVarchar};
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vtab::{BindInfo, InitInfo, TableFunctionInfo, VTab},
+};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Scans whose counters a [`ScanDiagnostics`] keeps.
+const RECENT_SCANS: usize = 64;
+
+/// Line-layout counters of the recently started `read_pgn` scans of one database, keyed by
+/// path pattern.
+///
+/// Clones share the same scans. `read_pgn` and `read_pgn_line_diagnostics` are registered
+/// with one instance per database, so scans of other databases never replace its entries.
+#[derive(Clone, Default)]
+pub struct ScanDiagnostics(Arc<Mutex<VecDeque<ScanEntry>>>);
+
+struct ScanEntry {
+    path_pattern: String,
+    diagnostics: Arc<LineDiagnostics>,
+}
+
+impl ScanDiagnostics {
+    /// Fresh counters for a scan of `path_pattern`, replacing those of its previous scan.
+    pub(crate) fn start_scan(&self, path_pattern: &str) -> Arc<LineDiagnostics> {
+        let diagnostics = Arc::new(LineDiagnostics::default());
+        let mut scans = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        scans.retain(|scan| scan.path_pattern != path_pattern);
+        if scans.len() == RECENT_SCANS {
+            scans.pop_front();
+        }
+        scans.push_back(ScanEntry {
+            path_pattern: path_pattern.to_string(),
+            diagnostics: Arc::clone(&diagnostics),
+        });
+        diagnostics
+    }
+
+    /// Counters of the most recently started scan of `path_pattern`, or of any scan when it
+    /// is `None`.
+    pub(crate) fn latest(&self, path_pattern: Option<&str>) -> Option<Arc<LineDiagnostics>> {
+        let scans = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        scans
+            .iter()
+            .rev()
+            .find(|scan| path_pattern.is_none_or(|pattern| scan.path_pattern == pattern))
+            .map(|scan| Arc::clone(&scan.diagnostics))
+    }
+}
+
+pub struct ReadPgnLineDiagnosticsBindData {
+    /// `path_pattern := ...`: report the latest scan of this pattern instead of the latest.
+    path_pattern: Option<String>,
+}
+
+pub struct ReadPgnLineDiagnosticsInitData {
+    done: AtomicBool,
+    diagnostics: Option<Arc<LineDiagnostics>>,
+}
+
+/// `read_pgn_line_diagnostics(path_pattern := NULL)`: one row with the line-layout repairs of
+/// the most recently started `read_pgn` scan of this database, or of `path_pattern`.
+pub struct ReadPgnLineDiagnosticsVTab;
+
+impl VTab for ReadPgnLineDiagnosticsVTab {
+    type InitData = ReadPgnLineDiagnosticsInitData;
+    type BindData = ReadPgnLineDiagnosticsBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        for (name, _) in LineDiagnostics::default().counts() {
+            bind.add_result_column(name, LogicalTypeHandle::from(LogicalTypeId::UBigint));
+        }
+        let path_pattern = match bind_info_ffi::get_named_parameter_varchar(bind, "path_pattern")? {
+            NamedParameterVarchar::Missing | NamedParameterVarchar::Null => None,
+            NamedParameterVarchar::Value(pattern) => Some(pattern),
+        };
+        Ok(ReadPgnLineDiagnosticsBindData { path_pattern })
+    }
+
+    fn init(init: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        // Safety: both pointers are set by DuckDB for the duration of the query, and the extra
+        // info is the `ScanDiagnostics` the function was registered with.
+        let (bind_data, scans) = unsafe {
+            (
+                &*init.get_bind_data::<ReadPgnLineDiagnosticsBindData>(),
+                &*init.get_extra_info::<ScanDiagnostics>(),
+            )
+        };
+        Ok(ReadPgnLineDiagnosticsInitData {
+            done: AtomicBool::new(false),
+            diagnostics: scans.latest(bind_data.path_pattern.as_deref()),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let init_data = func.get_init_data();
+        if init_data.done.swap(true, Ordering::Relaxed) {
+            output.set_len(0);
+            return Ok(());
+        }
+
+        let counts = init_data
+            .diagnostics
+            .as_deref()
+            .map(LineDiagnostics::counts)
+            .unwrap_or_else(|| LineDiagnostics::default().counts());
+        for (column_idx, (_, count)) in counts.into_iter().enumerate() {
+            output.flat_vector(column_idx).as_mut_slice::<u64>()[0] = count;
+        }
+        output.set_len(1);
+        Ok(())
+    }
+
+    fn named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+        Some(vec![(
+            "path_pattern".to_string(),
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_diagnostics_are_kept_per_pattern() {
+        let scans = ScanDiagnostics::default();
+        assert!(scans.latest(None).is_none());
+
+        let first = scans.start_scan("a.pgn");
+        first.files.store(1, Ordering::Relaxed);
+        let second = scans.start_scan("b/*.pgn");
+        second.files.store(3, Ordering::Relaxed);
+
+        let files = |pattern| {
+            scans
+                .latest(pattern)
+                .map(|diagnostics| diagnostics.files.load(Ordering::Relaxed))
+        };
+        assert_eq!(files(None), Some(3));
+        assert_eq!(files(Some("a.pgn")), Some(1));
+        assert_eq!(files(Some("c.pgn")), None);
+
+        // A new scan of a pattern replaces its counters and becomes the latest.
+        scans.start_scan("a.pgn");
+        assert_eq!(files(None), Some(0));
+        assert_eq!(files(Some("b/*.pgn")), Some(3));
+
+        // Clones share the same scans.
+        assert!(scans.clone().latest(Some("b/*.pgn")).is_some());
+
+        for idx in 0..RECENT_SCANS {
+            scans.start_scan(&format!("{idx}.pgn"));
+        }
+        assert_eq!(files(Some("b/*.pgn")), None);
+    }
+}
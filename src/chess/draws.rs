@@ -0,0 +1,442 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{
+    VarcharInput, invoke_ternary_nullable, invoke_unary_nullable,
+    invoke_unary_varchar_to_f64_i32_struct_nullable,
+};
+use super::elo::white_score;
+use super::moves::zobrist_hash_of_position;
+use super::quality::SHORT_DRAW_PLIES;
+use super::replay::ReplayedGame;
+#[cfg(feature = "extension")]
+use super::replay::{ReplayCache, on_illegal_arg, on_illegal_signatures};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use shakmaty::{Bitboard, Chess, Color, Position};
+use std::collections::HashMap;
+#[cfg(feature = "extension")]
+use std::error::Error;
+
+/// Half-moves without a capture or pawn move after which either side may claim a draw.
+const FIFTY_MOVE_HALFMOVES: u32 = 100;
+
+/// Why a drawn game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawKind {
+    Stalemate,
+    InsufficientMaterial,
+    Repetition,
+    FiftyMove,
+    Adjudicated,
+    AgreedEarly,
+    Agreed,
+}
+
+impl DrawKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Stalemate => "stalemate",
+            Self::InsufficientMaterial => "insufficient-material",
+            Self::Repetition => "repetition",
+            Self::FiftyMove => "50-move",
+            Self::Adjudicated => "adjudicated",
+            Self::AgreedEarly => "agreed-early",
+            Self::Agreed => "agreed",
+        }
+    }
+}
+
+/// Whether `result` records a draw.
+pub fn is_draw(result: &str) -> bool {
+    white_score(result) == Some(0.5)
+}
+
+/// Classify a drawn game from its final position, falling back to the Termination tag and the
+/// game length.
+/// Spec: move-analysis - Draw Classification
+///
+/// Checked in order: stalemate, insufficient material, threefold repetition of the final
+/// position, the fifty-move rule, a Termination tag mentioning adjudication, and finally an
+/// agreement, which is `AgreedEarly` when shorter than [`SHORT_DRAW_PLIES`] plies.
+pub fn classify_draw(game: &ReplayedGame, termination: &str) -> DrawKind {
+    let pos = game.final_position();
+    let final_hash = zobrist_hash_of_position(pos);
+    let occurrences = game
+        .positions
+        .iter()
+        .filter(|seen| zobrist_hash_of_position(seen) == final_hash)
+        .count();
+
+    if pos.is_stalemate() {
+        DrawKind::Stalemate
+    } else if pos.is_insufficient_material() {
+        DrawKind::InsufficientMaterial
+    } else if occurrences >= 3 {
+        DrawKind::Repetition
+    } else if pos.halfmoves() >= FIFTY_MOVE_HALFMOVES {
+        DrawKind::FiftyMove
+    } else if termination.to_ascii_lowercase().contains("adjudicat") {
+        DrawKind::Adjudicated
+    } else if game.plies() < SHORT_DRAW_PLIES as usize {
+        DrawKind::AgreedEarly
+    } else {
+        DrawKind::Agreed
+    }
+}
+
+/// How often the most repeated position of `game` occurred, and the ply after which any
+/// position occurred for the third time, if one did.
+/// Spec: move-analysis - Repetition Count
+///
+/// Positions are the same when the pieces, the side to move, the castling rights and a legal
+/// en passant capture match, as for a threefold repetition claim. The start position counts as
+/// the first occurrence, and ply `n` is the position after `n` half-moves.
+pub fn repetition_count(game: &ReplayedGame) -> (i32, Option<i32>) {
+    let mut occurrences: HashMap<u64, i32> = HashMap::with_capacity(game.positions.len());
+    let mut max_repetitions = 0;
+    let mut threefold_ply = None;
+    for (ply, pos) in game.positions.iter().enumerate() {
+        let count = occurrences
+            .entry(zobrist_hash_of_position(pos))
+            .or_default();
+        *count += 1;
+        max_repetitions = max_repetitions.max(*count);
+        if *count == 3 && threefold_ply.is_none() {
+            threefold_ply = Some(ply as i32);
+        }
+    }
+    (max_repetitions, threefold_ply)
+}
+
+/// Whether `pos` is a trivially drawn ending that neither side can win by force.
+/// Spec: move-analysis - Theoretical Draw
+///
+/// Recognizes insufficient material (bare kings, a lone minor piece, bishops all on one
+/// square color) and the pawnless endings where no side can force mate: at most one minor
+/// piece each, or two knights against a bare king. A position that is checkmate, or where the
+/// side to move mates in one, is not a draw.
+pub fn is_theoretical_draw(pos: &Chess) -> bool {
+    if pos.is_insufficient_material() {
+        return true;
+    }
+    let board = pos.board();
+    if (board.pawns() | board.rooks() | board.queens()).any() {
+        return false;
+    }
+    let minors = |color: Color| board.by_color(color) & (board.knights() | board.bishops());
+    let (white, black) = (minors(Color::White), minors(Color::Black));
+    let two_knights_vs_bare = |pair: Bitboard, bare: Bitboard| {
+        bare.is_empty() && pair.count() == 2 && pair == pair & board.knights()
+    };
+    let drawn = (white.count() <= 1 && black.count() <= 1)
+        || two_knights_vs_bare(white, black)
+        || two_knights_vs_bare(black, white);
+    drawn && !pos.is_checkmate() && !mates_in_one(pos)
+}
+
+fn mates_in_one(pos: &Chess) -> bool {
+    pos.legal_moves().into_iter().any(|m| {
+        let mut next = pos.clone();
+        next.play_unchecked(m);
+        next.is_checkmate()
+    })
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessDrawsClassificationScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessDrawsClassificationScalar {
+    type State = ReplayCache;
+
+    unsafe fn invoke(
+        cache: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let on_illegal = on_illegal_arg(input, 3)?;
+        invoke_ternary_nullable::<VarcharInput, VarcharInput, VarcharInput, _, _>(
+            input,
+            output,
+            |movetext, result, termination| {
+                // Decisive and unfinished games are not worth a replay.
+                if !is_draw(result) {
+                    return Ok(None);
+                }
+                Ok(cache
+                    .replay_with(movetext, on_illegal)
+                    .map_err(|e| format!("chess_draws_classification: {e}"))?
+                    .map(|game| classify_draw(&game, termination).label().to_string()))
+            },
+        )
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        on_illegal_signatures(
+            || {
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ]
+            },
+            || LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessIsTheoreticalDrawScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessIsTheoreticalDrawScalar {
+    type State = ReplayCache;
+
+    unsafe fn invoke(
+        cache: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let on_illegal = on_illegal_arg(input, 1)?;
+        invoke_unary_nullable::<VarcharInput, _, _>(input, output, |movetext| {
+            Ok(cache
+                .replay_with(movetext, on_illegal)
+                .map_err(|e| format!("chess_is_theoretical_draw: {e}"))?
+                .map(|game| is_theoretical_draw(game.final_position())))
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        on_illegal_signatures(
+            || vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            || LogicalTypeHandle::from(LogicalTypeId::Boolean),
+        )
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessRepetitionCountScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessRepetitionCountScalar {
+    type State = ReplayCache;
+
+    unsafe fn invoke(
+        cache: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let on_illegal = on_illegal_arg(input, 1)?;
+        // The struct helper cannot fail a row, so an `error` mode failure is raised afterwards.
+        let mut failure = None;
+        invoke_unary_varchar_to_f64_i32_struct_nullable::<_, 0, 2>(input, output, |movetext| {
+            match cache.replay_with(movetext, on_illegal) {
+                Ok(game) => {
+                    let (max_repetitions, threefold_ply) = repetition_count(&*game?);
+                    Some(([], [Some(max_repetitions), threefold_ply]))
+                }
+                Err(e) => {
+                    failure.get_or_insert(e);
+                    None
+                }
+            }
+        })?;
+        match failure {
+            Some(e) => Err(format!("chess_repetition_count: {e}").into()),
+            None => Ok(()),
+        }
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        on_illegal_signatures(
+            || vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            || {
+                LogicalTypeHandle::struct_type(&[
+                    (
+                        "max_repetitions",
+                        LogicalTypeHandle::from(LogicalTypeId::Integer),
+                    ),
+                    (
+                        "threefold_ply",
+                        LogicalTypeHandle::from(LogicalTypeId::Integer),
+                    ),
+                ])
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::replay::replay_mainline;
+    use shakmaty::{Chess, Role, san::San, san::SanPlus};
+
+    fn classify(movetext: &str, termination: &str) -> Option<&'static str> {
+        replay_mainline(movetext).map(|game| classify_draw(&game, termination).label())
+    }
+
+    /// Knights out and back: the start position recurs after every fourth ply.
+    fn shuffle(plies: usize) -> String {
+        let moves = ["Nf3", "Nf6", "Ng1", "Ng8"];
+        (0..plies)
+            .map(|ply| moves[ply % 4])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Locked pawns, then quiet piece moves that never revisit a position, so only the
+    /// fifty-move rule applies.
+    fn quiet_walk(plies: usize) -> String {
+        let opening = ["e4", "e5", "d3", "d6", "c3", "c6", "f3", "f6"];
+        let mut pos = Chess::default();
+        for san in opening {
+            let m = san.parse::<San>().unwrap().to_move(&pos).unwrap();
+            pos.play_unchecked(m);
+        }
+        let mut seen = vec![zobrist_hash_of_position(&pos)];
+        let mut sans: Vec<String> = opening.iter().map(|san| san.to_string()).collect();
+        for _ in 0..plies {
+            let (next, san) = pos
+                .legal_moves()
+                .into_iter()
+                .filter(|m| !m.is_capture() && m.role() != Role::Pawn)
+                .find_map(|m| {
+                    let mut next = pos.clone();
+                    let san = SanPlus::from_move_and_play_unchecked(&mut next, m);
+                    (!seen.contains(&zobrist_hash_of_position(&next))).then_some((next, san))
+                })
+                .expect("a fresh quiet move");
+            seen.push(zobrist_hash_of_position(&next));
+            sans.push(san.to_string());
+            pos = next;
+        }
+        sans.join(" ")
+    }
+
+    #[test]
+    fn test_classify_draw_terminal_positions() {
+        // Sam Loyd's ten-move stalemate.
+        assert_eq!(
+            classify(
+                "1. e3 a5 2. Qh5 Ra6 3. Qxa5 h5 4. h4 Rah6 5. Qxc7 f6 6. Qxd7+ Kf7 7. Qxb7 Qd3 \
+                 8. Qxb8 Qh7 9. Qxc8 Kg6 10. Qe6",
+                ""
+            ),
+            Some("stalemate")
+        );
+    }
+
+    #[test]
+    fn test_classify_draw_insufficient_material() {
+        // Everything is traded off except Black's dark-squared bishop.
+        let movetext = "e4 c5 Nc3 d5 exd5 Qxd5 Nxd5 Be6 Nxe7 Kxe7 f4 Bxa2 Rxa2 c4 Rxa7 Rxa7 \
+                        Bxc4 g5 Bxf7 gxf4 Bxg8 Rxg8 Qf3 Rxg2 Qxb7+ Rxb7 Kf1 Rxd2 Bxd2 Rxb2 Bxf4 \
+                        Rxc2 Bxb8 Rxh2 Bxh2 h6 Bd6+ Kxd6 Rxh6+ Bxh6 Ne2 Kc6 Nc1 Bxc1";
+        assert_eq!(classify(movetext, ""), Some("insufficient-material"));
+    }
+
+    #[test]
+    fn test_classify_draw_repetition_and_fifty_moves() {
+        assert_eq!(classify(&shuffle(8), ""), Some("repetition"));
+        // The start position appears only twice after four plies.
+        assert_eq!(classify(&shuffle(4), ""), Some("agreed-early"));
+        // A repetition is reported even once the fifty-move rule also applies.
+        assert_eq!(classify(&shuffle(104), ""), Some("repetition"));
+
+        assert_eq!(classify(&quiet_walk(100), ""), Some("50-move"));
+        assert_eq!(classify(&quiet_walk(99), ""), Some("agreed"));
+    }
+
+    #[test]
+    fn test_classify_draw_adjudication_and_agreement() {
+        assert_eq!(classify("1. e4 e5", "Adjudication"), Some("adjudicated"));
+        assert_eq!(classify("1. e4 e5", "Normal"), Some("agreed-early"));
+        let long_game = "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 \
+                         8. c3 O-O 9. h3 Nb8 10. d4 Nbd7 11. Nbd2 Bb7 12. Bc2 Re8 13. Nf1 Bf8 \
+                         14. Ng3 g6 15. a4 c5 16. d5 c4 17. Bg5 h6 18. Be3 Nc5 19. Qd2 h5 \
+                         20. Bg5 Be7";
+        assert_eq!(classify(long_game, ""), Some("agreed"));
+    }
+
+    #[test]
+    fn test_classify_draw_requires_drawn_replayable_game() {
+        assert!(is_draw("1/2-1/2"));
+        assert!(is_draw("½-½"));
+        assert!(!is_draw("1-0"));
+        assert!(!is_draw("*"));
+        assert_eq!(classify("1. e4 e4", ""), None);
+    }
+
+    fn repetitions(movetext: &str) -> Option<(i32, Option<i32>)> {
+        replay_mainline(movetext).map(|game| repetition_count(&game))
+    }
+
+    #[test]
+    fn test_repetition_count() {
+        assert_eq!(repetitions(""), Some((1, None)));
+        assert_eq!(repetitions("1. e4 e5 2. Nf3 Nc6"), Some((1, None)));
+        // The start position recurs after plies 4 and 8.
+        assert_eq!(repetitions(&shuffle(4)), Some((2, None)));
+        assert_eq!(repetitions(&shuffle(8)), Some((3, Some(8))));
+        assert_eq!(repetitions(&shuffle(13)), Some((4, Some(8))));
+        // After 1. Nf3 the position recurs at plies 5 and 9, after the start position's third
+        // occurrence at ply 8.
+        assert_eq!(repetitions(&shuffle(9)), Some((3, Some(8))));
+    }
+
+    #[test]
+    fn test_repetition_count_distinguishes_side_to_move_and_castling_rights() {
+        // The king walks out and back, so the pieces return but castling rights do not.
+        assert_eq!(
+            repetitions("1. e4 e5 2. Ke2 Ke7 3. Ke1 Ke8 4. Ke2 Ke7 5. Ke1 Ke8"),
+            Some((2, None))
+        );
+    }
+
+    fn theoretical_draw(fen: &str) -> bool {
+        let pos: Chess = fen
+            .parse::<shakmaty::fen::Fen>()
+            .unwrap()
+            .into_position(shakmaty::CastlingMode::Standard)
+            .unwrap();
+        is_theoretical_draw(&pos)
+    }
+
+    #[test]
+    fn test_is_theoretical_draw_recognizes_trivial_endings() {
+        // Insufficient material.
+        assert!(theoretical_draw("8/8/4k3/8/8/3K4/8/8 w - - 0 1"));
+        assert!(theoretical_draw("8/8/4k3/8/8/3KB3/8/8 b - - 0 1"));
+        assert!(theoretical_draw("8/8/4k3/8/8/3KN3/8/8 w - - 0 1"));
+        assert!(theoretical_draw("8/8/2b1k3/8/8/3KB3/8/8 w - - 0 1"));
+        // Lone minor pieces on both sides, and two knights against a bare king.
+        assert!(theoretical_draw("8/8/2n1k3/8/8/3KB3/8/8 w - - 0 1"));
+        assert!(theoretical_draw("8/8/3bk3/8/8/3KB3/8/8 w - - 0 1"));
+        assert!(theoretical_draw("8/8/4k3/8/8/3KNN2/8/8 w - - 0 1"));
+    }
+
+    #[test]
+    fn test_is_theoretical_draw_rejects_winnable_material_and_mates() {
+        assert!(!theoretical_draw("8/8/4k3/8/8/2RK4/8/8 w - - 0 1"));
+        assert!(!theoretical_draw("8/8/4k3/8/8/3KBN2/8/8 w - - 0 1"));
+        assert!(!theoretical_draw("8/8/4k3/8/8/2BK1B2/8/8 w - - 0 1"));
+        assert!(!theoretical_draw("8/8/4k3/8/3P4/3K4/8/8 w - - 0 1"));
+        // Two knights against a knight can still be won.
+        assert!(!theoretical_draw("8/8/2n1k3/8/8/3KNN2/8/8 w - - 0 1"));
+        // The defender's own knight takes an escape square: Bb7 mates.
+        assert!(!theoretical_draw("k7/n1K5/B7/8/8/8/8/8 w - - 0 1"));
+        assert!(theoretical_draw("k7/n1K5/B7/8/8/8/8/8 b - - 0 1"));
+        // Already mated with two knights.
+        assert!(!theoretical_draw("7k/4NN2/6K1/8/8/8/8/8 b - - 0 1"));
+    }
+
+    #[test]
+    fn test_is_theoretical_draw_from_replayed_game() {
+        let game = replay_mainline("1. e4 e5 2. Nf3 Nc6").unwrap();
+        assert!(!is_theoretical_draw(game.final_position()));
+    }
+}
@@ -1,7 +1,12 @@
 use duckdb::vtab::BindInfo;
 use libduckdb_sys::{
-    duckdb_bind_get_named_parameter, duckdb_bind_info, duckdb_destroy_value, duckdb_free,
-    duckdb_get_varchar, duckdb_is_null_value,
+    DUCKDB_TYPE_DUCKDB_TYPE_MAP, DUCKDB_TYPE_DUCKDB_TYPE_STRUCT, duckdb_bind_get_named_parameter,
+    duckdb_bind_info, duckdb_client_context, duckdb_client_context_get_config_option,
+    duckdb_destroy_client_context, duckdb_destroy_value, duckdb_free, duckdb_get_bool,
+    duckdb_get_date, duckdb_get_int64, duckdb_get_map_key, duckdb_get_map_size,
+    duckdb_get_map_value, duckdb_get_struct_child, duckdb_get_type_id, duckdb_get_value_type,
+    duckdb_get_varchar, duckdb_is_null_value, duckdb_struct_type_child_count,
+    duckdb_struct_type_child_name, duckdb_table_function_get_client_context, duckdb_value,
 };
 use std::ffi::{CStr, CString};
 use std::os::raw::c_void;
@@ -52,7 +57,243 @@ pub(crate) fn get_named_parameter_varchar(
     result
 }
 
-fn bind_info_ptr(bind: &BindInfo) -> duckdb_bind_info {
+/// Read an integer named parameter; omitted and NULL values both map to `None`.
+pub(crate) fn get_named_parameter_i64(
+    bind: &BindInfo,
+    name: &str,
+) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+    let name_cstr = CString::new(name)?;
+
+    // SAFETY: See `get_named_parameter_varchar`.
+    let mut value =
+        unsafe { duckdb_bind_get_named_parameter(bind_info_ptr(bind), name_cstr.as_ptr()) };
+    if value.is_null() {
+        return Ok(None);
+    }
+
+    // SAFETY: `value` is a valid `duckdb_value` handle returned by DuckDB. The parameter is
+    // declared as BIGINT, so DuckDB has already cast it before binding.
+    let result = unsafe {
+        if duckdb_is_null_value(value) {
+            None
+        } else {
+            Some(duckdb_get_int64(value))
+        }
+    };
+
+    // SAFETY: `value` has not been destroyed yet and must be released once.
+    unsafe {
+        duckdb_destroy_value(&mut value);
+    }
+
+    Ok(result)
+}
+
+/// Read a `DATE` named parameter as days since 1970-01-01; omitted and NULL values both map
+/// to `None`.
+pub(crate) fn get_named_parameter_date(
+    bind: &BindInfo,
+    name: &str,
+) -> Result<Option<i32>, Box<dyn std::error::Error>> {
+    let name_cstr = CString::new(name)?;
+
+    // SAFETY: See `get_named_parameter_varchar`.
+    let mut value =
+        unsafe { duckdb_bind_get_named_parameter(bind_info_ptr(bind), name_cstr.as_ptr()) };
+    if value.is_null() {
+        return Ok(None);
+    }
+
+    // SAFETY: `value` is a valid `duckdb_value` handle returned by DuckDB. The parameter is
+    // declared as DATE, so DuckDB has already cast it before binding.
+    let result = unsafe {
+        if duckdb_is_null_value(value) {
+            None
+        } else {
+            Some(duckdb_get_date(value).days)
+        }
+    };
+
+    // SAFETY: `value` has not been destroyed yet and must be released once.
+    unsafe {
+        duckdb_destroy_value(&mut value);
+    }
+
+    Ok(result)
+}
+
+/// Read a boolean named parameter; omitted and NULL values both map to `None`.
+pub(crate) fn get_named_parameter_bool(
+    bind: &BindInfo,
+    name: &str,
+) -> Result<Option<bool>, Box<dyn std::error::Error>> {
+    let name_cstr = CString::new(name)?;
+
+    // SAFETY: See `get_named_parameter_varchar`.
+    let mut value =
+        unsafe { duckdb_bind_get_named_parameter(bind_info_ptr(bind), name_cstr.as_ptr()) };
+    if value.is_null() {
+        return Ok(None);
+    }
+
+    // SAFETY: `value` is a valid `duckdb_value` handle returned by DuckDB. The parameter is
+    // declared as BOOLEAN, so DuckDB has already cast it before binding.
+    let result = unsafe {
+        if duckdb_is_null_value(value) {
+            None
+        } else {
+            Some(duckdb_get_bool(value))
+        }
+    };
+
+    // SAFETY: `value` has not been destroyed yet and must be released once.
+    unsafe {
+        duckdb_destroy_value(&mut value);
+    }
+
+    Ok(result)
+}
+
+pub(crate) type StringPairs = Vec<(String, String)>;
+
+/// Read a `STRUCT` or `MAP` named parameter as `(key, value)` text pairs, in declaration
+/// order; omitted and NULL values both map to `None`.
+///
+/// The parameter must be declared as `ANY` so DuckDB passes the literal through unchanged.
+pub(crate) fn get_named_parameter_string_pairs(
+    bind: &BindInfo,
+    name: &str,
+) -> Result<Option<StringPairs>, Box<dyn std::error::Error>> {
+    let name_cstr = CString::new(name)?;
+
+    // SAFETY: See `get_named_parameter_varchar`.
+    let mut value =
+        unsafe { duckdb_bind_get_named_parameter(bind_info_ptr(bind), name_cstr.as_ptr()) };
+    if value.is_null() {
+        return Ok(None);
+    }
+
+    // SAFETY: `value` is a valid `duckdb_value` handle returned by DuckDB. The logical type
+    // returned by `duckdb_get_value_type` is owned by `value` and must not be destroyed; child
+    // values and names are released by the helpers below.
+    let result = unsafe {
+        if duckdb_is_null_value(value) {
+            Ok(None)
+        } else {
+            let logical_type = duckdb_get_value_type(value);
+            match duckdb_get_type_id(logical_type) {
+                DUCKDB_TYPE_DUCKDB_TYPE_STRUCT => {
+                    let count = duckdb_struct_type_child_count(logical_type);
+                    (0..count)
+                        .map(|idx| {
+                            let key = take_duckdb_string(duckdb_struct_type_child_name(
+                                logical_type,
+                                idx,
+                            ));
+                            let value = take_value_varchar(duckdb_get_struct_child(value, idx));
+                            key.zip(value).ok_or_else(|| invalid_pairs_error(name))
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                        .map(Some)
+                }
+                DUCKDB_TYPE_DUCKDB_TYPE_MAP => {
+                    let count = duckdb_get_map_size(value);
+                    (0..count)
+                        .map(|idx| {
+                            let key = take_value_varchar(duckdb_get_map_key(value, idx));
+                            let value = take_value_varchar(duckdb_get_map_value(value, idx));
+                            key.zip(value).ok_or_else(|| invalid_pairs_error(name))
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                        .map(Some)
+                }
+                _ => Err(invalid_pairs_error(name)),
+            }
+        }
+    };
+
+    // SAFETY: `value` has not been destroyed yet and must be released once.
+    unsafe {
+        duckdb_destroy_value(&mut value);
+    }
+
+    result
+}
+
+/// Read a session setting (e.g. `duckdb_chess_default_compression`) as text from the client
+/// context of the query being bound; unknown and NULL settings both map to `None`.
+pub(crate) fn get_setting_varchar(
+    bind: &BindInfo,
+    name: &str,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let name_cstr = CString::new(name)?;
+    let mut context: duckdb_client_context = std::ptr::null_mut();
+
+    // SAFETY: `bind_info_ptr` yields the raw bind handle of this callback. The client context
+    // and the returned value are owned by this function and destroyed exactly once below.
+    unsafe {
+        duckdb_table_function_get_client_context(bind_info_ptr(bind), &mut context);
+        if context.is_null() {
+            return Ok(None);
+        }
+        let value = duckdb_client_context_get_config_option(
+            context,
+            name_cstr.as_ptr(),
+            std::ptr::null_mut(),
+        );
+        let text = take_value_varchar(value);
+        duckdb_destroy_client_context(&mut context);
+        Ok(text)
+    }
+}
+
+fn invalid_pairs_error(name: &str) -> Box<dyn std::error::Error> {
+    format!(
+        "Named parameter '{}' must be a STRUCT or MAP of VARCHAR values, e.g. {{'Variant': 'VARCHAR'}}",
+        name
+    )
+    .into()
+}
+
+/// Copy and free a DuckDB-allocated C string.
+///
+/// # Safety
+/// `ptr` must be NULL or a string allocated by DuckDB that is not used afterwards.
+unsafe fn take_duckdb_string(ptr: *mut std::os::raw::c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+
+    // SAFETY: Guaranteed by the caller.
+    unsafe {
+        let text = CStr::from_ptr(ptr).to_string_lossy().into_owned();
+        duckdb_free(ptr as *mut c_void);
+        Some(text)
+    }
+}
+
+/// Read a child value as text and destroy it; NULL values map to `None`.
+///
+/// # Safety
+/// `value` must be NULL or an owned `duckdb_value` that is not used afterwards.
+unsafe fn take_value_varchar(mut value: duckdb_value) -> Option<String> {
+    if value.is_null() {
+        return None;
+    }
+
+    // SAFETY: Guaranteed by the caller; `value` is destroyed exactly once.
+    unsafe {
+        let text = if duckdb_is_null_value(value) {
+            None
+        } else {
+            take_duckdb_string(duckdb_get_varchar(value))
+        };
+        duckdb_destroy_value(&mut value);
+        text
+    }
+}
+
+pub(crate) fn bind_info_ptr(bind: &BindInfo) -> duckdb_bind_info {
     // SAFETY: duckdb-rs v1.4.4 stores `duckdb_bind_info` as the only field inside
     // `duckdb::vtab::BindInfo` (see duckdb/src/vtab/function.rs). The wrapper does not expose
     // a public raw accessor or null-aware typed named-parameter accessor in this version, so this
@@ -0,0 +1,212 @@
+//! Files opened through DuckDB's file system, so URLs such as `s3://` are read with the
+//! extensions (e.g. httpfs) and secrets of the querying connection.
+
+use super::bind_info_ffi::bind_info_ptr;
+use duckdb::vtab::BindInfo;
+use libduckdb_sys::{
+    DuckDBSuccess, duckdb_client_context, duckdb_client_context_get_file_system,
+    duckdb_create_file_open_options, duckdb_destroy_client_context, duckdb_destroy_error_data,
+    duckdb_destroy_file_handle, duckdb_destroy_file_open_options, duckdb_destroy_file_system,
+    duckdb_error_data, duckdb_error_data_message, duckdb_file_flag_DUCKDB_FILE_FLAG_READ,
+    duckdb_file_handle, duckdb_file_handle_error_data, duckdb_file_handle_read,
+    duckdb_file_handle_seek, duckdb_file_handle_size, duckdb_file_handle_tell,
+    duckdb_file_open_options_set_flag, duckdb_file_system, duckdb_file_system_error_data,
+    duckdb_file_system_open, duckdb_table_function_get_client_context,
+};
+use std::ffi::{CStr, CString};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::raw::c_void;
+use std::sync::Mutex;
+
+/// Whether `path` is a URL (`scheme://...`) that only DuckDB's file system can open.
+///
+/// The scheme needs at least two characters so Windows drive letters stay local paths.
+pub(crate) fn is_remote_path(path: &str) -> bool {
+    let Some((scheme, _)) = path.split_once("://") else {
+        return false;
+    };
+    scheme.len() > 1
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// DuckDB's file system for the connection running the query being bound.
+pub(crate) struct DuckDbFileSystem {
+    /// DuckDB records the last open error on the handle, so opens are serialized.
+    handle: Mutex<duckdb_file_system>,
+}
+
+// SAFETY: The handle refers to the client file system of the query's connection, which
+// outlives the bind data. Opens go through the mutex, and file handles are independent.
+unsafe impl Send for DuckDbFileSystem {}
+// SAFETY: See `Send`.
+unsafe impl Sync for DuckDbFileSystem {}
+
+impl DuckDbFileSystem {
+    /// File system of the client context of `bind`.
+    pub(crate) fn from_bind(bind: &BindInfo) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut context: duckdb_client_context = std::ptr::null_mut();
+
+        // SAFETY: `bind_info_ptr` yields the raw bind handle of this callback. The client context
+        // is owned by this function and destroyed exactly once; the file system wrapper only
+        // references the connection's file system, so it stays valid afterwards.
+        let handle = unsafe {
+            duckdb_table_function_get_client_context(bind_info_ptr(bind), &mut context);
+            if context.is_null() {
+                return Err("Failed to access the DuckDB client context".into());
+            }
+            let handle = duckdb_client_context_get_file_system(context);
+            duckdb_destroy_client_context(&mut context);
+            handle
+        };
+        if handle.is_null() {
+            return Err("Failed to access the DuckDB file system".into());
+        }
+
+        Ok(Self {
+            handle: Mutex::new(handle),
+        })
+    }
+
+    /// Open `path` for reading.
+    pub(crate) fn open(&self, path: &str) -> Result<DuckDbFile, String> {
+        let path_cstr = CString::new(path).map_err(|e| e.to_string())?;
+        let handle = match self.handle.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        // SAFETY: `handle` is a live file system wrapper, and the options are created and
+        // destroyed exactly once here. On failure the error is read before the lock is released.
+        unsafe {
+            let mut options = duckdb_create_file_open_options();
+            duckdb_file_open_options_set_flag(
+                options,
+                duckdb_file_flag_DUCKDB_FILE_FLAG_READ,
+                true,
+            );
+            let mut file: duckdb_file_handle = std::ptr::null_mut();
+            let state = duckdb_file_system_open(*handle, path_cstr.as_ptr(), options, &mut file);
+            duckdb_destroy_file_open_options(&mut options);
+            if state != DuckDBSuccess || file.is_null() {
+                return Err(take_error_message(duckdb_file_system_error_data(*handle)));
+            }
+            Ok(DuckDbFile { handle: file })
+        }
+    }
+}
+
+impl Drop for DuckDbFileSystem {
+    fn drop(&mut self) {
+        let handle = self
+            .handle
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // SAFETY: The wrapper is destroyed exactly once.
+        unsafe { duckdb_destroy_file_system(handle) };
+    }
+}
+
+/// A file opened with [`DuckDbFileSystem::open`].
+pub(crate) struct DuckDbFile {
+    handle: duckdb_file_handle,
+}
+
+// SAFETY: A file handle is only used by the reader that owns it, one call at a time.
+unsafe impl Send for DuckDbFile {}
+
+impl DuckDbFile {
+    /// Size of the file in bytes.
+    pub(crate) fn len(&self) -> io::Result<u64> {
+        // SAFETY: `handle` is live until drop.
+        let size = unsafe { duckdb_file_handle_size(self.handle) };
+        u64::try_from(size).map_err(|_| self.last_error())
+    }
+
+    fn last_error(&self) -> io::Error {
+        // SAFETY: `handle` is live until drop; the error data is destroyed by the helper.
+        io::Error::other(unsafe { take_error_message(duckdb_file_handle_error_data(self.handle)) })
+    }
+}
+
+impl Read for DuckDbFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = i64::try_from(buf.len()).unwrap_or(i64::MAX);
+        // SAFETY: `handle` is live until drop and `buf` has room for `size` bytes.
+        let read =
+            unsafe { duckdb_file_handle_read(self.handle, buf.as_mut_ptr() as *mut c_void, size) };
+        usize::try_from(read).map_err(|_| self.last_error())
+    }
+}
+
+impl Seek for DuckDbFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.len()?.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.stream_position()?.checked_add_signed(delta),
+        }
+        .and_then(|target| i64::try_from(target).ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek position"))?;
+
+        // SAFETY: `handle` is live until drop.
+        if unsafe { duckdb_file_handle_seek(self.handle, target) } != DuckDBSuccess {
+            return Err(self.last_error());
+        }
+        Ok(target as u64)
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        // SAFETY: `handle` is live until drop.
+        let position = unsafe { duckdb_file_handle_tell(self.handle) };
+        u64::try_from(position).map_err(|_| self.last_error())
+    }
+}
+
+impl Drop for DuckDbFile {
+    fn drop(&mut self) {
+        // SAFETY: The handle is closed and destroyed exactly once.
+        unsafe { duckdb_destroy_file_handle(&mut self.handle) };
+    }
+}
+
+/// Copy the message of `error` and destroy it.
+///
+/// # Safety
+/// `error` must be an owned `duckdb_error_data` that is not used afterwards.
+unsafe fn take_error_message(mut error: duckdb_error_data) -> String {
+    // SAFETY: Guaranteed by the caller; the message is copied before `error` is destroyed.
+    unsafe {
+        let message = duckdb_error_data_message(error);
+        let text = if message.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(message).to_string_lossy().into_owned()
+        };
+        duckdb_destroy_error_data(&mut error);
+        if text.is_empty() {
+            "unknown DuckDB file system error".to_string()
+        } else {
+            text
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_remote_path() {
+        assert!(is_remote_path("s3://bucket/games.pgn.zst"));
+        assert!(is_remote_path("https://example.com/games.pgn?token=abc"));
+        assert!(is_remote_path("s3a://bucket/games.pgn"));
+        assert!(!is_remote_path("games/2024.pgn"));
+        assert!(!is_remote_path("/dev/stdin"));
+        assert!(!is_remote_path("C://games.pgn"));
+        assert!(!is_remote_path("://games.pgn"));
+        assert!(!is_remote_path("my dir://games.pgn"));
+    }
+}
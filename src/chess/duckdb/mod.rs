@@ -1,3 +1,4 @@
 pub(crate) mod bind_info_ffi;
+pub(crate) mod file_system;
 pub(crate) mod scalar;
-mod string;
+pub(crate) mod string;
@@ -12,26 +12,24 @@
 //!
 //! Callers MUST ensure the input/output column logical types match the helper being used
 //! (e.g., `VARCHAR` inputs for `duckdb_string_t`, `BIGINT` outputs for `i64`, etc.).
+//!
+//! # Constant vectors
+//! DuckDB flattens every input vector before calling a C API scalar (and marks the result
+//! constant again when all inputs were constant), so helpers only ever see flat vectors with
+//! a validity mask.
 
+use std::borrow::Cow;
 use std::error::Error;
-use std::ffi::CString;
 
 use duckdb::{
     Result,
     core::{DataChunkHandle, FlatVector, Inserter, LogicalTypeId},
     vtab::arrow::WritableVector,
 };
-use libduckdb_sys::duckdb_string_t;
-
-use super::string::decode_duckdb_string;
+use libduckdb_sys::{duckdb_date, duckdb_interval, duckdb_string_t};
 
-#[derive(Debug, Clone, Copy)]
-pub enum VarcharNullBehavior {
-    /// Output NULL when the input row is NULL.
-    Null,
-    /// Output a static string when the input row is NULL.
-    Static(&'static str),
-}
+use super::string::{decode_duckdb_string, try_insert_varchar};
+use crate::chess::types::PgnDate;
 
 #[derive(Debug, Clone)]
 pub enum VarcharOutput {
@@ -54,70 +52,468 @@ fn ensure_type(
     Ok(())
 }
 
-/// Invoke a unary `VARCHAR -> VARCHAR` scalar.
-pub fn invoke_unary_varchar_to_varchar<F>(
+/// An input column type the generic multi-column helpers can read.
+pub trait ScalarInput {
+    const TYPE: LogicalTypeId;
+    /// Physical row layout in the flat vector.
+    type Raw;
+    /// Per-row value decoded from `Raw`; may borrow from the vector.
+    type Decoded<'a>;
+    /// What the scalar closure receives for one row.
+    type Arg<'r>;
+
+    /// # Safety
+    /// `raw` must be a non-NULL row of a vector of type `TYPE`.
+    unsafe fn decode(raw: &Self::Raw) -> Self::Decoded<'_>;
+
+    fn arg<'r>(decoded: &'r Self::Decoded<'_>) -> Self::Arg<'r>;
+}
+
+/// `VARCHAR` input, passed to closures as `&str`.
+pub struct VarcharInput;
+
+/// `BIGINT` input, passed to closures as `i64`.
+pub struct BigintInput;
+
+/// `DOUBLE` input, passed to closures as `f64`.
+pub struct DoubleInput;
+
+impl ScalarInput for VarcharInput {
+    const TYPE: LogicalTypeId = LogicalTypeId::Varchar;
+    type Raw = duckdb_string_t;
+    type Decoded<'a> = Cow<'a, str>;
+    type Arg<'r> = &'r str;
+
+    unsafe fn decode(raw: &Self::Raw) -> Self::Decoded<'_> {
+        // SAFETY: The caller guarantees a non-NULL VARCHAR row.
+        unsafe { decode_duckdb_string(raw) }
+    }
+
+    fn arg<'r>(decoded: &'r Self::Decoded<'_>) -> Self::Arg<'r> {
+        decoded.as_ref()
+    }
+}
+
+impl ScalarInput for BigintInput {
+    const TYPE: LogicalTypeId = LogicalTypeId::Bigint;
+    type Raw = i64;
+    type Decoded<'a> = i64;
+    type Arg<'r> = i64;
+
+    unsafe fn decode(raw: &Self::Raw) -> Self::Decoded<'_> {
+        *raw
+    }
+
+    fn arg<'r>(decoded: &'r Self::Decoded<'_>) -> Self::Arg<'r> {
+        *decoded
+    }
+}
+
+impl ScalarInput for DoubleInput {
+    const TYPE: LogicalTypeId = LogicalTypeId::Double;
+    type Raw = f64;
+    type Decoded<'a> = f64;
+    type Arg<'r> = f64;
+
+    unsafe fn decode(raw: &Self::Raw) -> Self::Decoded<'_> {
+        *raw
+    }
+
+    fn arg<'r>(decoded: &'r Self::Decoded<'_>) -> Self::Arg<'r> {
+        *decoded
+    }
+}
+
+/// An output column type the generic multi-column helpers can write.
+pub trait ScalarOutput {
+    const TYPE: LogicalTypeId;
+
+    fn write(self, vec: &mut FlatVector, row: usize);
+}
+
+macro_rules! copy_scalar_output {
+    ($value:ty, $type_id:expr) => {
+        impl ScalarOutput for $value {
+            const TYPE: LogicalTypeId = $type_id;
+
+            fn write(self, vec: &mut FlatVector, row: usize) {
+                vec.as_mut_slice::<$value>()[row] = self;
+            }
+        }
+    };
+}
+
+copy_scalar_output!(bool, LogicalTypeId::Boolean);
+copy_scalar_output!(i32, LogicalTypeId::Integer);
+copy_scalar_output!(i64, LogicalTypeId::Bigint);
+copy_scalar_output!(u64, LogicalTypeId::UBigint);
+copy_scalar_output!(f64, LogicalTypeId::Double);
+
+/// A nullable value, such as a struct field that may be missing.
+impl<T: ScalarOutput> ScalarOutput for Option<T> {
+    const TYPE: LogicalTypeId = T::TYPE;
+
+    fn write(self, vec: &mut FlatVector, row: usize) {
+        match self {
+            Some(value) => value.write(vec, row),
+            None => vec.set_null(row),
+        }
+    }
+}
+
+impl ScalarOutput for String {
+    const TYPE: LogicalTypeId = LogicalTypeId::Varchar;
+
+    fn write(self, vec: &mut FlatVector, row: usize) {
+        vec.insert(row, self.as_str());
+    }
+}
+
+impl ScalarOutput for PgnDate {
+    const TYPE: LogicalTypeId = LogicalTypeId::Date;
+
+    fn write(self, vec: &mut FlatVector, row: usize) {
+        vec.as_mut_slice::<duckdb_date>()[row] = duckdb_date { days: self.days };
+    }
+}
+
+fn typed_input<I: ScalarInput>(
+    input: &DataChunkHandle,
+    idx: usize,
+) -> Result<FlatVector, Box<dyn Error>> {
+    let vec = input.flat_vector(idx);
+    ensure_type(&vec, I::TYPE, &format!("input[{idx}]"))?;
+    Ok(vec)
+}
+
+/// Read an optional trailing `VARCHAR` option from column `idx`, such as a macro argument with a
+/// default. Returns `None` when the column is absent or NULL; the option applies to the whole
+/// call, so differing values across rows are an error.
+pub fn constant_varchar_arg(
+    input: &DataChunkHandle,
+    idx: usize,
+    name: &str,
+) -> Result<Option<String>, Box<dyn Error>> {
+    if input.num_columns() <= idx {
+        return Ok(None);
+    }
+    let vec = typed_input::<VarcharInput>(input, idx)?;
+    let mut value: Option<Option<String>> = None;
+    for (i, raw) in vec
+        .as_slice_with_len::<duckdb_string_t>(input.len())
+        .iter()
+        .enumerate()
+    {
+        let row = (!vec.row_is_null(i as u64)).then(|| {
+            // SAFETY: Row nullability is checked above.
+            unsafe { decode_duckdb_string(raw) }.into_owned()
+        });
+        match &value {
+            None => value = Some(row),
+            Some(first) if *first != row => {
+                return Err(format!("{name} must be the same for every row").into());
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(value.flatten())
+}
+
+/// Invoke a unary scalar over any [`ScalarInput`] column.
+///
+/// This helper outputs NULL when the input row is NULL or when `f` returns `Ok(None)`; an
+/// `Err` fails the whole query.
+pub fn invoke_unary_nullable<A, O, F>(
     input: &DataChunkHandle,
     output: &mut dyn WritableVector,
-    null_behavior: VarcharNullBehavior,
     mut f: F,
 ) -> Result<(), Box<dyn Error>>
 where
-    F: FnMut(&str) -> Result<VarcharOutput, Box<dyn Error>>,
+    A: ScalarInput,
+    O: ScalarOutput,
+    F: for<'r> FnMut(A::Arg<'r>) -> Result<Option<O>, Box<dyn Error>>,
 {
     let len = input.len();
-    let input_vec = input.flat_vector(0);
-    ensure_type(&input_vec, LogicalTypeId::Varchar, "input[0]")?;
-    let input_slice = input_vec.as_slice::<duckdb_string_t>();
+    let input_vec = typed_input::<A>(input, 0)?;
+    let input_slice = input_vec.as_slice_with_len::<A::Raw>(len);
     let mut output_vec = output.flat_vector();
-    ensure_type(&output_vec, LogicalTypeId::Varchar, "output")?;
+    ensure_type(&output_vec, O::TYPE, "output")?;
 
-    for (i, s) in input_slice.iter().take(len).enumerate() {
+    for (i, raw) in input_slice.iter().enumerate() {
         if input_vec.row_is_null(i as u64) {
-            match null_behavior {
-                VarcharNullBehavior::Null => output_vec.set_null(i),
-                VarcharNullBehavior::Static(v) => output_vec.insert(i, CString::new(v)?),
-            }
+            output_vec.set_null(i);
             continue;
         }
 
-        // SAFETY: Row nullability is checked above.
-        let val = unsafe { decode_duckdb_string(s) };
-        match f(val.as_ref())? {
-            VarcharOutput::Null => output_vec.set_null(i),
-            VarcharOutput::Value(v) => output_vec.insert(i, CString::new(v)?),
+        // SAFETY: The input row is checked non-NULL above.
+        let value = unsafe { A::decode(raw) };
+        match f(A::arg(&value))? {
+            Some(v) => v.write(&mut output_vec, i),
+            None => output_vec.set_null(i),
+        }
+    }
+
+    Ok(())
+}
+
+/// Invoke a binary scalar over any two [`ScalarInput`] columns.
+///
+/// This helper outputs NULL when either input row is NULL or when `f` returns `Ok(None)`; an
+/// `Err` fails the whole query.
+pub fn invoke_binary_nullable<A, B, O, F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    A: ScalarInput,
+    B: ScalarInput,
+    O: ScalarOutput,
+    F: for<'r> FnMut(A::Arg<'r>, B::Arg<'r>) -> Result<Option<O>, Box<dyn Error>>,
+{
+    let len = input.len();
+    let input_vec_0 = typed_input::<A>(input, 0)?;
+    let input_vec_1 = typed_input::<B>(input, 1)?;
+    let input_slice_0 = input_vec_0.as_slice_with_len::<A::Raw>(len);
+    let input_slice_1 = input_vec_1.as_slice_with_len::<B::Raw>(len);
+    let mut output_vec = output.flat_vector();
+    ensure_type(&output_vec, O::TYPE, "output")?;
+
+    for i in 0..len {
+        if input_vec_0.row_is_null(i as u64) || input_vec_1.row_is_null(i as u64) {
+            output_vec.set_null(i);
+            continue;
+        }
+
+        // SAFETY: Both input rows are checked non-NULL above.
+        let (left, right) = unsafe { (A::decode(&input_slice_0[i]), B::decode(&input_slice_1[i])) };
+        match f(A::arg(&left), B::arg(&right))? {
+            Some(v) => v.write(&mut output_vec, i),
+            None => output_vec.set_null(i),
+        }
+    }
+
+    Ok(())
+}
+
+/// Invoke a ternary scalar over any three [`ScalarInput`] columns.
+///
+/// This helper outputs NULL when any input row is NULL or when `f` returns `Ok(None)`; an
+/// `Err` fails the whole query.
+pub fn invoke_ternary_nullable<A, B, C, O, F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    A: ScalarInput,
+    B: ScalarInput,
+    C: ScalarInput,
+    O: ScalarOutput,
+    F: for<'r> FnMut(A::Arg<'r>, B::Arg<'r>, C::Arg<'r>) -> Result<Option<O>, Box<dyn Error>>,
+{
+    let len = input.len();
+    let input_vec_0 = typed_input::<A>(input, 0)?;
+    let input_vec_1 = typed_input::<B>(input, 1)?;
+    let input_vec_2 = typed_input::<C>(input, 2)?;
+    let input_slice_0 = input_vec_0.as_slice_with_len::<A::Raw>(len);
+    let input_slice_1 = input_vec_1.as_slice_with_len::<B::Raw>(len);
+    let input_slice_2 = input_vec_2.as_slice_with_len::<C::Raw>(len);
+    let mut output_vec = output.flat_vector();
+    ensure_type(&output_vec, O::TYPE, "output")?;
+
+    for i in 0..len {
+        if input_vec_0.row_is_null(i as u64)
+            || input_vec_1.row_is_null(i as u64)
+            || input_vec_2.row_is_null(i as u64)
+        {
+            output_vec.set_null(i);
+            continue;
+        }
+
+        // SAFETY: All input rows are checked non-NULL above.
+        let (first, second, third) = unsafe {
+            (
+                A::decode(&input_slice_0[i]),
+                B::decode(&input_slice_1[i]),
+                C::decode(&input_slice_2[i]),
+            )
+        };
+        match f(A::arg(&first), B::arg(&second), C::arg(&third))? {
+            Some(v) => v.write(&mut output_vec, i),
+            None => output_vec.set_null(i),
+        }
+    }
+
+    Ok(())
+}
+
+/// Invoke a binary scalar over any two [`ScalarInput`] columns that returns a `STRUCT` of `N`
+/// fields of the same [`ScalarOutput`] type.
+///
+/// `f` returns the struct fields in order. This helper outputs NULL when either input row is
+/// NULL or when `f` returns `Ok(None)`; an `Err` fails the whole query.
+pub fn invoke_binary_to_struct_nullable<A, B, O, F, const N: usize>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    A: ScalarInput,
+    B: ScalarInput,
+    O: ScalarOutput,
+    F: for<'r> FnMut(A::Arg<'r>, B::Arg<'r>) -> Result<Option<[O; N]>, Box<dyn Error>>,
+{
+    let len = input.len();
+    let input_vec_0 = typed_input::<A>(input, 0)?;
+    let input_vec_1 = typed_input::<B>(input, 1)?;
+    let input_slice_0 = input_vec_0.as_slice_with_len::<A::Raw>(len);
+    let input_slice_1 = input_vec_1.as_slice_with_len::<B::Raw>(len);
+
+    let mut struct_vec = output.struct_vector();
+    let mut fields: Vec<FlatVector> = (0..N).map(|idx| struct_vec.child(idx, len)).collect();
+    for (idx, field) in fields.iter().enumerate() {
+        ensure_type(field, O::TYPE, &format!("output.child[{idx}]"))?;
+    }
+
+    for i in 0..len {
+        let row = if input_vec_0.row_is_null(i as u64) || input_vec_1.row_is_null(i as u64) {
+            None
+        } else {
+            // SAFETY: Both input rows are checked non-NULL above.
+            let (left, right) =
+                unsafe { (A::decode(&input_slice_0[i]), B::decode(&input_slice_1[i])) };
+            f(A::arg(&left), B::arg(&right))?
+        };
+
+        match row {
+            Some(values) => {
+                for (field, value) in fields.iter_mut().zip(values) {
+                    value.write(field, i);
+                }
+            }
+            None => {
+                // DuckDB expects the fields of a NULL struct to be NULL as well.
+                struct_vec.set_null(i);
+                fields.iter_mut().for_each(|field| field.set_null(i));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Invoke a four-argument scalar over any [`ScalarInput`] columns that returns a `STRUCT` of
+/// `N` fields of the same [`ScalarOutput`] type.
+///
+/// `f` returns the struct fields in order. This helper outputs NULL when any input row is NULL
+/// or when `f` returns `Ok(None)`; an `Err` fails the whole query.
+pub fn invoke_quaternary_to_struct_nullable<A, B, C, D, O, F, const N: usize>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    A: ScalarInput,
+    B: ScalarInput,
+    C: ScalarInput,
+    D: ScalarInput,
+    O: ScalarOutput,
+    F: for<'r> FnMut(
+        A::Arg<'r>,
+        B::Arg<'r>,
+        C::Arg<'r>,
+        D::Arg<'r>,
+    ) -> Result<Option<[O; N]>, Box<dyn Error>>,
+{
+    let len = input.len();
+    let input_vec_0 = typed_input::<A>(input, 0)?;
+    let input_vec_1 = typed_input::<B>(input, 1)?;
+    let input_vec_2 = typed_input::<C>(input, 2)?;
+    let input_vec_3 = typed_input::<D>(input, 3)?;
+    let input_slice_0 = input_vec_0.as_slice_with_len::<A::Raw>(len);
+    let input_slice_1 = input_vec_1.as_slice_with_len::<B::Raw>(len);
+    let input_slice_2 = input_vec_2.as_slice_with_len::<C::Raw>(len);
+    let input_slice_3 = input_vec_3.as_slice_with_len::<D::Raw>(len);
+
+    let mut struct_vec = output.struct_vector();
+    let mut fields: Vec<FlatVector> = (0..N).map(|idx| struct_vec.child(idx, len)).collect();
+    for (idx, field) in fields.iter().enumerate() {
+        ensure_type(field, O::TYPE, &format!("output.child[{idx}]"))?;
+    }
+
+    for i in 0..len {
+        let row = if [&input_vec_0, &input_vec_1, &input_vec_2, &input_vec_3]
+            .iter()
+            .any(|vec| vec.row_is_null(i as u64))
+        {
+            None
+        } else {
+            // SAFETY: All input rows are checked non-NULL above.
+            let (first, second, third, fourth) = unsafe {
+                (
+                    A::decode(&input_slice_0[i]),
+                    B::decode(&input_slice_1[i]),
+                    C::decode(&input_slice_2[i]),
+                    D::decode(&input_slice_3[i]),
+                )
+            };
+            f(
+                A::arg(&first),
+                B::arg(&second),
+                C::arg(&third),
+                D::arg(&fourth),
+            )?
+        };
+
+        match row {
+            Some(values) => {
+                for (field, value) in fields.iter_mut().zip(values) {
+                    value.write(field, i);
+                }
+            }
+            None => {
+                // DuckDB expects the fields of a NULL struct to be NULL as well.
+                struct_vec.set_null(i);
+                fields.iter_mut().for_each(|field| field.set_null(i));
+            }
         }
     }
 
     Ok(())
 }
 
-/// Invoke a unary `VARCHAR -> BIGINT` scalar with a default output value for NULL inputs.
-pub fn invoke_unary_varchar_to_i64_default<F>(
+/// Invoke a unary `VARCHAR -> VARCHAR` scalar.
+///
+/// This helper outputs NULL when the input row is NULL or when `f` returns
+/// `VarcharOutput::Null`.
+pub fn invoke_unary_varchar_to_varchar<F>(
     input: &DataChunkHandle,
     output: &mut dyn WritableVector,
-    default_on_null: i64,
     mut f: F,
 ) -> Result<(), Box<dyn Error>>
 where
-    F: FnMut(&str) -> i64,
+    F: FnMut(&str) -> Result<VarcharOutput, Box<dyn Error>>,
 {
     let len = input.len();
     let input_vec = input.flat_vector(0);
     ensure_type(&input_vec, LogicalTypeId::Varchar, "input[0]")?;
     let input_slice = input_vec.as_slice::<duckdb_string_t>();
     let mut output_vec = output.flat_vector();
-    ensure_type(&output_vec, LogicalTypeId::Bigint, "output")?;
-    let output_slice = output_vec.as_mut_slice::<i64>();
+    ensure_type(&output_vec, LogicalTypeId::Varchar, "output")?;
 
     for (i, s) in input_slice.iter().take(len).enumerate() {
         if input_vec.row_is_null(i as u64) {
-            output_slice[i] = default_on_null;
+            output_vec.set_null(i);
             continue;
         }
 
         // SAFETY: Row nullability is checked above.
         let val = unsafe { decode_duckdb_string(s) };
-        output_slice[i] = f(val.as_ref());
+        match f(val.as_ref())? {
+            VarcharOutput::Null => output_vec.set_null(i),
+            VarcharOutput::Value(v) => try_insert_varchar(&output_vec, i, &v)?,
+        }
     }
 
     Ok(())
@@ -169,43 +565,61 @@ pub fn invoke_binary_varchar_varchar_to_bool_nullable<F>(
 where
     F: FnMut(&str, &str) -> bool,
 {
-    let len = input.len();
-    let input_vec_0 = input.flat_vector(0);
-    let input_vec_1 = input.flat_vector(1);
-    ensure_type(&input_vec_0, LogicalTypeId::Varchar, "input[0]")?;
-    ensure_type(&input_vec_1, LogicalTypeId::Varchar, "input[1]")?;
-    let input_slice_0 = input_vec_0.as_slice::<duckdb_string_t>();
-    let input_slice_1 = input_vec_1.as_slice::<duckdb_string_t>();
-    let mut output_vec = output.flat_vector();
-    ensure_type(&output_vec, LogicalTypeId::Boolean, "output")?;
+    invoke_binary_nullable::<VarcharInput, VarcharInput, _, _>(input, output, |left, right| {
+        Ok(Some(f(left, right)))
+    })
+}
 
-    for (i, (left_s, right_s)) in input_slice_0
-        .iter()
-        .take(len)
-        .zip(input_slice_1.iter().take(len))
-        .enumerate()
-    {
-        if input_vec_0.row_is_null(i as u64) || input_vec_1.row_is_null(i as u64) {
-            output_vec.set_null(i);
-            continue;
-        }
+/// Invoke a binary `VARCHAR, VARCHAR -> BIGINT` scalar.
+///
+/// This helper outputs NULL when either input row is NULL or when `f` returns `None`.
+pub fn invoke_binary_varchar_varchar_to_i64_nullable<F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str, &str) -> Option<i64>,
+{
+    invoke_binary_nullable::<VarcharInput, VarcharInput, _, _>(input, output, |left, right| {
+        Ok(f(left, right))
+    })
+}
 
-        // SAFETY: Both input rows are checked non-NULL above.
-        let left = unsafe { decode_duckdb_string(left_s) };
-        // SAFETY: Both input rows are checked non-NULL above.
-        let right = unsafe { decode_duckdb_string(right_s) };
-        output_vec.as_mut_slice::<bool>()[i] = f(left.as_ref(), right.as_ref());
-    }
+/// Invoke a binary `VARCHAR, VARCHAR -> DOUBLE` scalar.
+///
+/// This helper outputs NULL when either input row is NULL or when `f` returns `Ok(None)`.
+pub fn invoke_binary_varchar_varchar_to_f64_nullable<F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str, &str) -> Result<Option<f64>, Box<dyn Error>>,
+{
+    invoke_binary_nullable::<VarcharInput, VarcharInput, _, _>(input, output, f)
+}
 
-    Ok(())
+/// Invoke a binary `VARCHAR, VARCHAR -> INTEGER` scalar.
+///
+/// This helper outputs NULL when either input row is NULL or when `f` returns `Ok(None)`.
+pub fn invoke_binary_varchar_varchar_to_i32_nullable<F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str, &str) -> Result<Option<i32>, Box<dyn Error>>,
+{
+    invoke_binary_nullable::<VarcharInput, VarcharInput, _, _>(input, output, f)
 }
 
 /// Invoke a `VARCHAR -> VARCHAR` scalar that optionally reads a per-row `BIGINT` argument from
-/// column 1 if present.
+/// column 1 if present. A missing or NULL argument is passed as `None`; a NULL `VARCHAR` row
+/// gives NULL.
 pub fn invoke_unary_varchar_optional_i64_to_varchar<F>(
     input: &DataChunkHandle,
     output: &mut dyn WritableVector,
-    null_behavior: VarcharNullBehavior,
     mut f: F,
 ) -> Result<(), Box<dyn Error>>
 where
@@ -230,10 +644,7 @@ where
 
     for (i, s) in input_slice.iter().take(len).enumerate() {
         if input_vec.row_is_null(i as u64) {
-            match null_behavior {
-                VarcharNullBehavior::Null => output_vec.set_null(i),
-                VarcharNullBehavior::Static(v) => output_vec.insert(i, CString::new(v)?),
-            }
+            output_vec.set_null(i);
             continue;
         }
 
@@ -252,9 +663,1027 @@ where
 
         match f(val.as_ref(), arg)? {
             VarcharOutput::Null => output_vec.set_null(i),
-            VarcharOutput::Value(v) => output_vec.insert(i, CString::new(v)?),
+            VarcharOutput::Value(v) => try_insert_varchar(&output_vec, i, &v)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Invoke a `VARCHAR -> VARCHAR` scalar that optionally reads a per-row `BOOLEAN` argument from
+/// column 1 if present. A missing or NULL flag is passed as `false`; a NULL `VARCHAR` row gives
+/// NULL.
+pub fn invoke_unary_varchar_optional_bool_to_varchar<F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str, bool) -> Result<VarcharOutput, Box<dyn Error>>,
+{
+    let len = input.len();
+    let input_vec = input.flat_vector(0);
+    ensure_type(&input_vec, LogicalTypeId::Varchar, "input[0]")?;
+    let input_slice = input_vec.as_slice::<duckdb_string_t>();
+    let flag_vec = if input.num_columns() > 1 {
+        Some(input.flat_vector(1))
+    } else {
+        None
+    };
+    if let Some(vec) = &flag_vec {
+        ensure_type(vec, LogicalTypeId::Boolean, "input[1]")?;
+    }
+    let flag_slice = flag_vec.as_ref().map(|v| v.as_slice::<bool>());
+
+    let mut output_vec = output.flat_vector();
+    ensure_type(&output_vec, LogicalTypeId::Varchar, "output")?;
+
+    for (i, s) in input_slice.iter().take(len).enumerate() {
+        if input_vec.row_is_null(i as u64) {
+            output_vec.set_null(i);
+            continue;
+        }
+
+        // SAFETY: Row nullability is checked above.
+        let val = unsafe { decode_duckdb_string(s) };
+        let flag = match (&flag_vec, &flag_slice) {
+            (Some(vec), Some(slice)) => !vec.row_is_null(i as u64) && slice[i],
+            _ => false,
+        };
+
+        match f(val.as_ref(), flag)? {
+            VarcharOutput::Null => output_vec.set_null(i),
+            VarcharOutput::Value(v) => try_insert_varchar(&output_vec, i, &v)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Invoke a binary `VARCHAR, BIGINT -> BIGINT` scalar.
+///
+/// This helper outputs NULL when either input row is NULL or when `f` returns `None`.
+pub fn invoke_binary_varchar_i64_to_i64_nullable<F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str, i64) -> Option<i64>,
+{
+    invoke_binary_nullable::<VarcharInput, BigintInput, _, _>(input, output, |s, arg| Ok(f(s, arg)))
+}
+
+/// Invoke a binary `LIST(VARCHAR), VARCHAR -> BIGINT` scalar.
+///
+/// `f` receives the list elements (NULL elements as `None`) and the second argument. This
+/// helper outputs NULL when either input row is NULL or when `f` returns `None`.
+pub fn invoke_binary_varchar_list_varchar_to_i64_nullable<F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&[Option<&str>], &str) -> Option<i64>,
+{
+    let len = input.len();
+    let list_rows = input.flat_vector(0);
+    let input_vec_1 = input.flat_vector(1);
+    ensure_type(&list_rows, LogicalTypeId::List, "input[0]")?;
+    ensure_type(&input_vec_1, LogicalTypeId::Varchar, "input[1]")?;
+    let list_vec = input.list_vector(0);
+    let elements = list_vec.child(list_vec.len());
+    ensure_type(&elements, LogicalTypeId::Varchar, "input[0].child")?;
+    let element_slice = elements.as_slice_with_len::<duckdb_string_t>(list_vec.len());
+    let input_slice_1 = input_vec_1.as_slice::<duckdb_string_t>();
+    let mut output_vec = output.flat_vector();
+    ensure_type(&output_vec, LogicalTypeId::Bigint, "output")?;
+
+    let mut items: Vec<Option<std::borrow::Cow<'_, str>>> = Vec::new();
+    for (i, s) in input_slice_1.iter().take(len).enumerate() {
+        if list_rows.row_is_null(i as u64) || input_vec_1.row_is_null(i as u64) {
+            output_vec.set_null(i);
+            continue;
+        }
+
+        let (offset, length) = list_vec.get_entry(i);
+        items.clear();
+        for (idx, element) in element_slice.iter().enumerate().skip(offset).take(length) {
+            if elements.row_is_null(idx as u64) {
+                items.push(None);
+            } else {
+                // SAFETY: Element nullability is checked above.
+                items.push(Some(unsafe { decode_duckdb_string(element) }));
+            }
+        }
+        let item_refs: Vec<Option<&str>> = items.iter().map(|item| item.as_deref()).collect();
+
+        // SAFETY: The input row is checked non-NULL above.
+        let val = unsafe { decode_duckdb_string(s) };
+        match f(&item_refs, val.as_ref()) {
+            Some(v) => output_vec.as_mut_slice::<i64>()[i] = v,
+            None => output_vec.set_null(i),
+        }
+    }
+
+    Ok(())
+}
+
+/// Invoke a unary `VARCHAR -> LIST(STRUCT(VARCHAR, ...))` scalar.
+///
+/// `f` returns one `[String; N]` per list element, in struct field order. This helper outputs
+/// NULL when the input row is NULL or when `f` returns `None`.
+pub fn invoke_unary_varchar_to_varchar_struct_list<F, const N: usize>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str) -> Option<Vec<[String; N]>>,
+{
+    let len = input.len();
+    let input_vec = input.flat_vector(0);
+    ensure_type(&input_vec, LogicalTypeId::Varchar, "input[0]")?;
+    let input_slice = input_vec.as_slice::<duckdb_string_t>();
+
+    let mut rows = Vec::with_capacity(len);
+    for (i, s) in input_slice.iter().take(len).enumerate() {
+        if input_vec.row_is_null(i as u64) {
+            rows.push(None);
+            continue;
+        }
+
+        // SAFETY: Row nullability is checked above.
+        let val = unsafe { decode_duckdb_string(s) };
+        rows.push(f(val.as_ref()));
+    }
+
+    let total: usize = rows.iter().flatten().map(Vec::len).sum();
+    let mut list_vec = output.list_vector();
+    let struct_vec = list_vec.struct_child(total);
+    let fields: Vec<FlatVector> = (0..N).map(|idx| struct_vec.child(idx, total)).collect();
+    for (idx, field) in fields.iter().enumerate() {
+        ensure_type(
+            field,
+            LogicalTypeId::Varchar,
+            &format!("output.child[{idx}]"),
+        )?;
+    }
+
+    let mut offset = 0;
+    for (i, row) in rows.into_iter().enumerate() {
+        let Some(elements) = row else {
+            list_vec.set_null(i);
+            list_vec.set_entry(i, offset, 0);
+            continue;
+        };
+
+        list_vec.set_entry(i, offset, elements.len());
+        for element in elements {
+            for (field, value) in fields.iter().zip(element) {
+                field.insert(offset, value.as_str());
+            }
+            offset += 1;
+        }
+    }
+    list_vec.set_len(offset);
+
+    Ok(())
+}
+
+/// Invoke a unary `VARCHAR -> MAP(VARCHAR, INTEGER)` scalar.
+///
+/// `f` returns the `(key, value)` entries in output order. This helper outputs NULL when the
+/// input row is NULL or when `f` returns `None`.
+pub fn invoke_unary_varchar_to_varchar_i32_map<F, K>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str) -> Option<Vec<(K, i32)>>,
+    K: AsRef<str>,
+{
+    let len = input.len();
+    let input_vec = input.flat_vector(0);
+    ensure_type(&input_vec, LogicalTypeId::Varchar, "input[0]")?;
+    let input_slice = input_vec.as_slice::<duckdb_string_t>();
+
+    let mut rows = Vec::with_capacity(len);
+    for (i, s) in input_slice.iter().take(len).enumerate() {
+        if input_vec.row_is_null(i as u64) {
+            rows.push(None);
+            continue;
+        }
+
+        // SAFETY: Row nullability is checked above.
+        let val = unsafe { decode_duckdb_string(s) };
+        rows.push(f(val.as_ref()));
+    }
+
+    write_varchar_i32_maps(output, rows)
+}
+
+/// Invoke a binary `LIST(VARCHAR), BIGINT -> MAP(VARCHAR, INTEGER)` scalar.
+///
+/// `f` receives the list elements (NULL elements as `None`) and the second argument, and
+/// returns the `(key, value)` entries in output order. This helper outputs NULL when either
+/// input row is NULL or when `f` returns `None`.
+pub fn invoke_binary_varchar_list_i64_to_varchar_i32_map<F, K>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&[Option<&str>], i64) -> Option<Vec<(K, i32)>>,
+    K: AsRef<str>,
+{
+    let len = input.len();
+    let list_rows = input.flat_vector(0);
+    let input_vec_1 = input.flat_vector(1);
+    ensure_type(&list_rows, LogicalTypeId::List, "input[0]")?;
+    ensure_type(&input_vec_1, LogicalTypeId::Bigint, "input[1]")?;
+    let list_vec = input.list_vector(0);
+    let elements = list_vec.child(list_vec.len());
+    ensure_type(&elements, LogicalTypeId::Varchar, "input[0].child")?;
+    let element_slice = elements.as_slice_with_len::<duckdb_string_t>(list_vec.len());
+    let input_slice_1 = input_vec_1.as_slice::<i64>();
+
+    let mut rows = Vec::with_capacity(len);
+    let mut items: Vec<Option<Cow<'_, str>>> = Vec::new();
+    for (i, arg) in input_slice_1.iter().take(len).enumerate() {
+        if list_rows.row_is_null(i as u64) || input_vec_1.row_is_null(i as u64) {
+            rows.push(None);
+            continue;
+        }
+
+        let (offset, length) = list_vec.get_entry(i);
+        items.clear();
+        for (idx, element) in element_slice.iter().enumerate().skip(offset).take(length) {
+            if elements.row_is_null(idx as u64) {
+                items.push(None);
+            } else {
+                // SAFETY: Element nullability is checked above.
+                items.push(Some(unsafe { decode_duckdb_string(element) }));
+            }
+        }
+        let item_refs: Vec<Option<&str>> = items.iter().map(|item| item.as_deref()).collect();
+        rows.push(f(&item_refs, *arg));
+    }
+
+    write_varchar_i32_maps(output, rows)
+}
+
+/// Write one `MAP(VARCHAR, INTEGER)` per row, or NULL for `None` rows.
+fn write_varchar_i32_maps<K: AsRef<str>>(
+    output: &mut dyn WritableVector,
+    rows: Vec<Option<Vec<(K, i32)>>>,
+) -> Result<(), Box<dyn Error>> {
+    // MAP vectors are physically `LIST(STRUCT(key, value))`.
+    let total: usize = rows.iter().flatten().map(Vec::len).sum();
+    let mut map_vec = output.list_vector();
+    let entries = map_vec.struct_child(total);
+    let keys = entries.child(0, total);
+    let mut values = entries.child(1, total);
+    ensure_type(&keys, LogicalTypeId::Varchar, "output.key")?;
+    ensure_type(&values, LogicalTypeId::Integer, "output.value")?;
+
+    let mut offset = 0;
+    for (i, row) in rows.into_iter().enumerate() {
+        let Some(elements) = row else {
+            map_vec.set_null(i);
+            map_vec.set_entry(i, offset, 0);
+            continue;
+        };
+
+        map_vec.set_entry(i, offset, elements.len());
+        for (key, value) in elements {
+            keys.insert(offset, key.as_ref());
+            values.as_mut_slice_with_len::<i32>(total)[offset] = value;
+            offset += 1;
+        }
+    }
+    map_vec.set_len(offset);
+
+    Ok(())
+}
+
+/// Invoke a unary `VARCHAR -> MAP(VARCHAR, VARCHAR)` scalar.
+///
+/// `f` returns the `(key, value)` entries in output order; keys must be unique. This helper
+/// outputs NULL when the input row is NULL or when `f` returns `None`.
+pub fn invoke_unary_varchar_to_varchar_varchar_map<F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str) -> Option<Vec<(String, String)>>,
+{
+    let len = input.len();
+    let input_vec = input.flat_vector(0);
+    ensure_type(&input_vec, LogicalTypeId::Varchar, "input[0]")?;
+    let input_slice = input_vec.as_slice::<duckdb_string_t>();
+
+    let mut rows = Vec::with_capacity(len);
+    for (i, s) in input_slice.iter().take(len).enumerate() {
+        if input_vec.row_is_null(i as u64) {
+            rows.push(None);
+            continue;
+        }
+
+        // SAFETY: Row nullability is checked above.
+        let val = unsafe { decode_duckdb_string(s) };
+        rows.push(f(val.as_ref()));
+    }
+
+    // MAP vectors are physically `LIST(STRUCT(key, value))`.
+    let total: usize = rows.iter().flatten().map(Vec::len).sum();
+    let mut map_vec = output.list_vector();
+    let entries = map_vec.struct_child(total);
+    let keys = entries.child(0, total);
+    let values = entries.child(1, total);
+    ensure_type(&keys, LogicalTypeId::Varchar, "output.key")?;
+    ensure_type(&values, LogicalTypeId::Varchar, "output.value")?;
+
+    let mut offset = 0;
+    for (i, row) in rows.into_iter().enumerate() {
+        let Some(elements) = row else {
+            map_vec.set_null(i);
+            map_vec.set_entry(i, offset, 0);
+            continue;
+        };
+
+        map_vec.set_entry(i, offset, elements.len());
+        for (key, value) in elements {
+            keys.insert(offset, key.as_str());
+            values.insert(offset, value.as_str());
+            offset += 1;
+        }
+    }
+    map_vec.set_len(offset);
+
+    Ok(())
+}
+
+/// Invoke a unary `VARCHAR -> LIST(STRUCT(BIGINT, UBIGINT))` scalar.
+///
+/// `f` returns one `(i64, u64)` pair per list element, in struct field order. This helper
+/// outputs NULL when the input row is NULL or when `f` returns `None`.
+pub fn invoke_unary_varchar_to_i64_u64_struct_list<F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str) -> Option<Vec<(i64, u64)>>,
+{
+    let len = input.len();
+    let input_vec = input.flat_vector(0);
+    ensure_type(&input_vec, LogicalTypeId::Varchar, "input[0]")?;
+    let input_slice = input_vec.as_slice::<duckdb_string_t>();
+
+    let mut rows = Vec::with_capacity(len);
+    for (i, s) in input_slice.iter().take(len).enumerate() {
+        if input_vec.row_is_null(i as u64) {
+            rows.push(None);
+            continue;
+        }
+
+        // SAFETY: Row nullability is checked above.
+        let val = unsafe { decode_duckdb_string(s) };
+        rows.push(f(val.as_ref()));
+    }
+
+    let total: usize = rows.iter().flatten().map(Vec::len).sum();
+    let mut list_vec = output.list_vector();
+    let struct_vec = list_vec.struct_child(total);
+    let mut first = struct_vec.child(0, total);
+    let mut second = struct_vec.child(1, total);
+    ensure_type(&first, LogicalTypeId::Bigint, "output.child[0]")?;
+    ensure_type(&second, LogicalTypeId::UBigint, "output.child[1]")?;
+
+    let mut offset = 0;
+    for (i, row) in rows.into_iter().enumerate() {
+        let Some(elements) = row else {
+            list_vec.set_null(i);
+            list_vec.set_entry(i, offset, 0);
+            continue;
+        };
+
+        list_vec.set_entry(i, offset, elements.len());
+        for (a, b) in elements {
+            first.as_mut_slice_with_len::<i64>(total)[offset] = a;
+            second.as_mut_slice_with_len::<u64>(total)[offset] = b;
+            offset += 1;
+        }
+    }
+    list_vec.set_len(offset);
+
+    Ok(())
+}
+
+/// Invoke a unary `VARCHAR -> LIST(STRUCT(VARCHAR, INTEGER, INTEGER))` scalar.
+///
+/// `f` returns one `(String, i32, i32)` tuple per list element, in struct field order. This
+/// helper outputs NULL when the input row is NULL or when `f` returns `None`.
+pub fn invoke_unary_varchar_to_varchar_i32_i32_struct_list<F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str) -> Option<Vec<(String, i32, i32)>>,
+{
+    let len = input.len();
+    let input_vec = input.flat_vector(0);
+    ensure_type(&input_vec, LogicalTypeId::Varchar, "input[0]")?;
+    let input_slice = input_vec.as_slice::<duckdb_string_t>();
+
+    let mut rows = Vec::with_capacity(len);
+    for (i, s) in input_slice.iter().take(len).enumerate() {
+        if input_vec.row_is_null(i as u64) {
+            rows.push(None);
+            continue;
+        }
+
+        // SAFETY: Row nullability is checked above.
+        let val = unsafe { decode_duckdb_string(s) };
+        rows.push(f(val.as_ref()));
+    }
+
+    let total: usize = rows.iter().flatten().map(Vec::len).sum();
+    let mut list_vec = output.list_vector();
+    let struct_vec = list_vec.struct_child(total);
+    let first = struct_vec.child(0, total);
+    let mut second = struct_vec.child(1, total);
+    let mut third = struct_vec.child(2, total);
+    ensure_type(&first, LogicalTypeId::Varchar, "output.child[0]")?;
+    ensure_type(&second, LogicalTypeId::Integer, "output.child[1]")?;
+    ensure_type(&third, LogicalTypeId::Integer, "output.child[2]")?;
+
+    let mut offset = 0;
+    for (i, row) in rows.into_iter().enumerate() {
+        let Some(elements) = row else {
+            list_vec.set_null(i);
+            list_vec.set_entry(i, offset, 0);
+            continue;
+        };
+
+        list_vec.set_entry(i, offset, elements.len());
+        for (a, b, c) in elements {
+            first.insert(offset, a.as_str());
+            second.as_mut_slice_with_len::<i32>(total)[offset] = b;
+            third.as_mut_slice_with_len::<i32>(total)[offset] = c;
+            offset += 1;
+        }
+    }
+    list_vec.set_len(offset);
+
+    Ok(())
+}
+
+/// Invoke a `VARCHAR -> LIST(INTERVAL)` scalar that optionally reads a per-row `VARCHAR`
+/// argument from column 1 if present.
+///
+/// `f` returns one element per list entry as microseconds (`None` for a NULL element). This
+/// helper outputs NULL when any input row is NULL or when `f` returns `None`.
+pub fn invoke_unary_varchar_optional_varchar_to_interval_list<F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str, Option<&str>) -> Result<Option<Vec<Option<i64>>>, Box<dyn Error>>,
+{
+    let len = input.len();
+    let input_vec = input.flat_vector(0);
+    ensure_type(&input_vec, LogicalTypeId::Varchar, "input[0]")?;
+    let input_slice = input_vec.as_slice::<duckdb_string_t>();
+    let arg_vec = if input.num_columns() > 1 {
+        Some(input.flat_vector(1))
+    } else {
+        None
+    };
+    if let Some(vec) = &arg_vec {
+        ensure_type(vec, LogicalTypeId::Varchar, "input[1]")?;
+    }
+    let arg_slice = arg_vec.as_ref().map(|v| v.as_slice::<duckdb_string_t>());
+
+    let mut rows = Vec::with_capacity(len);
+    for (i, s) in input_slice.iter().take(len).enumerate() {
+        if input_vec.row_is_null(i as u64) {
+            rows.push(None);
+            continue;
+        }
+
+        let arg = match (&arg_vec, &arg_slice) {
+            (Some(vec), Some(slice)) => {
+                if vec.row_is_null(i as u64) {
+                    rows.push(None);
+                    continue;
+                }
+                // SAFETY: Row nullability is checked above.
+                Some(unsafe { decode_duckdb_string(&slice[i]) })
+            }
+            _ => None,
+        };
+
+        // SAFETY: Row nullability is checked above.
+        let val = unsafe { decode_duckdb_string(s) };
+        rows.push(f(val.as_ref(), arg.as_deref())?);
+    }
+
+    let total: usize = rows.iter().flatten().map(Vec::len).sum();
+    let mut list_vec = output.list_vector();
+    let mut child = list_vec.child(total);
+    ensure_type(&child, LogicalTypeId::Interval, "output.child")?;
+
+    let mut offset = 0;
+    for (i, row) in rows.into_iter().enumerate() {
+        let Some(elements) = row else {
+            list_vec.set_null(i);
+            list_vec.set_entry(i, offset, 0);
+            continue;
+        };
+
+        list_vec.set_entry(i, offset, elements.len());
+        for element in elements {
+            match element {
+                Some(micros) => {
+                    child.as_mut_slice_with_len::<duckdb_interval>(total)[offset] =
+                        duckdb_interval {
+                            months: 0,
+                            days: 0,
+                            micros,
+                        };
+                }
+                None => child.set_null(offset),
+            }
+            offset += 1;
+        }
+    }
+    list_vec.set_len(offset);
+
+    Ok(())
+}
+
+/// Invoke a ternary `BIGINT, BIGINT, VARCHAR -> DOUBLE` scalar.
+///
+/// This helper outputs NULL when any input row is NULL or when `f` returns `None`.
+pub fn invoke_i64_i64_varchar_to_f64_nullable<F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(i64, i64, &str) -> Option<f64>,
+{
+    invoke_ternary_nullable::<BigintInput, BigintInput, VarcharInput, _, _>(
+        input,
+        output,
+        |first, second, s| Ok(f(first, second, s)),
+    )
+}
+
+/// Invoke a `LIST(BIGINT), BIGINT, ... -> LIST(INTEGER)` scalar with `N` trailing `BIGINT`
+/// arguments.
+///
+/// `f` receives the list elements (NULL elements as `None`) and the other arguments. This
+/// helper outputs NULL when any input row is NULL or when `f` returns `Ok(None)`; an `Err` fails
+/// the whole query.
+pub fn invoke_bigint_list_i64s_to_i32_list_nullable<F, const N: usize>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&[Option<i64>], [i64; N]) -> Result<Option<Vec<i32>>, Box<dyn Error>>,
+{
+    let len = input.len();
+    let list_rows = input.flat_vector(0);
+    ensure_type(&list_rows, LogicalTypeId::List, "input[0]")?;
+    let list_vec = input.list_vector(0);
+    let elements = list_vec.child(list_vec.len());
+    ensure_type(&elements, LogicalTypeId::Bigint, "input[0].child")?;
+    let element_slice = elements.as_slice_with_len::<i64>(list_vec.len());
+    let arg_vecs: Vec<FlatVector> = (1..=N).map(|idx| input.flat_vector(idx)).collect();
+    for (idx, vec) in arg_vecs.iter().enumerate() {
+        ensure_type(vec, LogicalTypeId::Bigint, &format!("input[{}]", idx + 1))?;
+    }
+
+    let mut rows = Vec::with_capacity(len);
+    let mut items = Vec::new();
+    for i in 0..len {
+        if list_rows.row_is_null(i as u64) || arg_vecs.iter().any(|vec| vec.row_is_null(i as u64)) {
+            rows.push(None);
+            continue;
+        }
+
+        let (offset, length) = list_vec.get_entry(i);
+        items.clear();
+        items.extend(
+            (offset..offset + length)
+                .map(|idx| (!elements.row_is_null(idx as u64)).then(|| element_slice[idx])),
+        );
+        let args: [i64; N] = std::array::from_fn(|idx| arg_vecs[idx].as_slice::<i64>()[i]);
+        rows.push(f(&items, args)?);
+    }
+
+    let total: usize = rows.iter().flatten().map(Vec::len).sum();
+    let mut output_list = output.list_vector();
+    let mut values_vec = output_list.child(total);
+    ensure_type(&values_vec, LogicalTypeId::Integer, "output.child")?;
+    let values = values_vec.as_mut_slice_with_len::<i32>(total);
+
+    let mut offset = 0;
+    for (i, row) in rows.into_iter().enumerate() {
+        let Some(row) = row else {
+            output_list.set_null(i);
+            output_list.set_entry(i, offset, 0);
+            continue;
+        };
+
+        output_list.set_entry(i, offset, row.len());
+        values[offset..offset + row.len()].copy_from_slice(&row);
+        offset += row.len();
+    }
+    output_list.set_len(offset);
+
+    Ok(())
+}
+
+/// Invoke a `LIST(BIGINT), DOUBLE -> DOUBLE` scalar.
+///
+/// `f` receives the list elements (NULL elements as `None`) and the `DOUBLE` argument. This
+/// helper outputs NULL when either input row is NULL or when `f` returns `Ok(None)`; an `Err`
+/// fails the whole query.
+pub fn invoke_bigint_list_f64_to_f64_nullable<F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&[Option<i64>], f64) -> Result<Option<f64>, Box<dyn Error>>,
+{
+    let len = input.len();
+    let list_rows = input.flat_vector(0);
+    ensure_type(&list_rows, LogicalTypeId::List, "input[0]")?;
+    let list_vec = input.list_vector(0);
+    let elements = list_vec.child(list_vec.len());
+    ensure_type(&elements, LogicalTypeId::Bigint, "input[0].child")?;
+    let element_slice = elements.as_slice_with_len::<i64>(list_vec.len());
+    let arg_vec = typed_input::<DoubleInput>(input, 1)?;
+    let arg_slice = arg_vec.as_slice_with_len::<f64>(len);
+    let mut output_vec = output.flat_vector();
+    ensure_type(&output_vec, LogicalTypeId::Double, "output")?;
+
+    let mut items = Vec::new();
+    for (i, &arg) in arg_slice.iter().enumerate() {
+        if list_rows.row_is_null(i as u64) || arg_vec.row_is_null(i as u64) {
+            output_vec.set_null(i);
+            continue;
+        }
+
+        let (offset, length) = list_vec.get_entry(i);
+        items.clear();
+        items.extend(
+            (offset..offset + length)
+                .map(|idx| (!elements.row_is_null(idx as u64)).then(|| element_slice[idx])),
+        );
+        match f(&items, arg)? {
+            Some(v) => v.write(&mut output_vec, i),
+            None => output_vec.set_null(i),
+        }
+    }
+
+    Ok(())
+}
+
+/// Invoke a binary scalar over any two [`ScalarInput`] columns that returns a fixed-size
+/// `LIST(FLOAT)` of `N` elements.
+///
+/// This helper outputs NULL when either input row is NULL or when `f` returns `Ok(None)`; an
+/// `Err` fails the whole query.
+pub fn invoke_binary_to_f32_list_nullable<A, B, F, const N: usize>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    A: ScalarInput,
+    B: ScalarInput,
+    F: for<'r> FnMut(A::Arg<'r>, B::Arg<'r>) -> Result<Option<[f32; N]>, Box<dyn Error>>,
+{
+    let len = input.len();
+    let input_vec_0 = typed_input::<A>(input, 0)?;
+    let input_vec_1 = typed_input::<B>(input, 1)?;
+    let input_slice_0 = input_vec_0.as_slice_with_len::<A::Raw>(len);
+    let input_slice_1 = input_vec_1.as_slice_with_len::<B::Raw>(len);
+
+    let mut rows = Vec::with_capacity(len);
+    for i in 0..len {
+        if input_vec_0.row_is_null(i as u64) || input_vec_1.row_is_null(i as u64) {
+            rows.push(None);
+            continue;
+        }
+
+        // SAFETY: Both input rows are checked non-NULL above.
+        let (left, right) = unsafe { (A::decode(&input_slice_0[i]), B::decode(&input_slice_1[i])) };
+        rows.push(f(A::arg(&left), B::arg(&right))?);
+    }
+
+    let total = rows.iter().flatten().count() * N;
+    let mut output_list = output.list_vector();
+    let mut values_vec = output_list.child(total);
+    ensure_type(&values_vec, LogicalTypeId::Float, "output.child")?;
+    let values = values_vec.as_mut_slice_with_len::<f32>(total);
+
+    let mut offset = 0;
+    for (i, row) in rows.into_iter().enumerate() {
+        let Some(row) = row else {
+            output_list.set_null(i);
+            output_list.set_entry(i, offset, 0);
+            continue;
+        };
+
+        output_list.set_entry(i, offset, N);
+        values[offset..offset + N].copy_from_slice(&row);
+        offset += N;
+    }
+    output_list.set_len(offset);
+
+    Ok(())
+}
+
+/// Invoke a binary `VARCHAR, BIGINT -> LIST(UBIGINT)` scalar.
+///
+/// This helper outputs NULL when either input row is NULL or when `f` returns `Ok(None)`; an
+/// `Err` fails the whole query.
+pub fn invoke_binary_varchar_i64_to_u64_list_nullable<F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str, i64) -> Result<Option<Vec<u64>>, Box<dyn Error>>,
+{
+    let len = input.len();
+    let input_vec_0 = typed_input::<VarcharInput>(input, 0)?;
+    let input_vec_1 = typed_input::<BigintInput>(input, 1)?;
+    let input_slice_0 = input_vec_0.as_slice_with_len::<duckdb_string_t>(len);
+    let input_slice_1 = input_vec_1.as_slice_with_len::<i64>(len);
+
+    let mut rows = Vec::with_capacity(len);
+    for i in 0..len {
+        if input_vec_0.row_is_null(i as u64) || input_vec_1.row_is_null(i as u64) {
+            rows.push(None);
+            continue;
+        }
+
+        // SAFETY: The VARCHAR row is checked non-NULL above.
+        let text = unsafe { decode_duckdb_string(&input_slice_0[i]) };
+        rows.push(f(text.as_ref(), input_slice_1[i])?);
+    }
+
+    let total: usize = rows.iter().flatten().map(Vec::len).sum();
+    let mut output_list = output.list_vector();
+    let mut values_vec = output_list.child(total);
+    ensure_type(&values_vec, LogicalTypeId::UBigint, "output.child")?;
+    let values = values_vec.as_mut_slice_with_len::<u64>(total);
+
+    let mut offset = 0;
+    for (i, row) in rows.into_iter().enumerate() {
+        let Some(row) = row else {
+            output_list.set_null(i);
+            output_list.set_entry(i, offset, 0);
+            continue;
+        };
+
+        output_list.set_entry(i, offset, row.len());
+        values[offset..offset + row.len()].copy_from_slice(&row);
+        offset += row.len();
+    }
+    output_list.set_len(offset);
+
+    Ok(())
+}
+
+/// Invoke a unary `VARCHAR -> LIST(VARCHAR)` scalar.
+///
+/// This helper outputs NULL when the input row is NULL or when `f` returns `Ok(None)`; an
+/// `Err` fails the whole query.
+pub fn invoke_unary_varchar_to_varchar_list_nullable<F>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str) -> Result<Option<Vec<String>>, Box<dyn Error>>,
+{
+    let len = input.len();
+    let input_vec = typed_input::<VarcharInput>(input, 0)?;
+    let input_slice = input_vec.as_slice_with_len::<duckdb_string_t>(len);
+
+    let mut rows = Vec::with_capacity(len);
+    for (i, raw) in input_slice.iter().enumerate() {
+        if input_vec.row_is_null(i as u64) {
+            rows.push(None);
+            continue;
+        }
+
+        // SAFETY: The VARCHAR row is checked non-NULL above.
+        let text = unsafe { decode_duckdb_string(raw) };
+        rows.push(f(text.as_ref())?);
+    }
+
+    let total: usize = rows.iter().flatten().map(Vec::len).sum();
+    let mut output_list = output.list_vector();
+    let values_vec = output_list.child(total);
+    ensure_type(&values_vec, LogicalTypeId::Varchar, "output.child")?;
+
+    let mut offset = 0;
+    for (i, row) in rows.into_iter().enumerate() {
+        let Some(row) = row else {
+            output_list.set_null(i);
+            output_list.set_entry(i, offset, 0);
+            continue;
+        };
+
+        output_list.set_entry(i, offset, row.len());
+        for value in row {
+            try_insert_varchar(&values_vec, offset, &value)?;
+            offset += 1;
+        }
+    }
+    output_list.set_len(offset);
+
+    Ok(())
+}
+
+/// Invoke an `N`-ary `BIGINT, ... -> VARCHAR` scalar.
+///
+/// This helper outputs NULL when any input row is NULL or when `f` returns `Ok(None)`; an
+/// `Err` fails the whole query.
+pub fn invoke_i64s_to_varchar_nullable<F, const N: usize>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut([i64; N]) -> Result<Option<String>, Box<dyn Error>>,
+{
+    let len = input.len();
+    let input_vecs: Vec<FlatVector> = (0..N).map(|idx| input.flat_vector(idx)).collect();
+    for (idx, vec) in input_vecs.iter().enumerate() {
+        ensure_type(vec, LogicalTypeId::Bigint, &format!("input[{idx}]"))?;
+    }
+    let mut output_vec = output.flat_vector();
+    ensure_type(&output_vec, LogicalTypeId::Varchar, "output")?;
+
+    for i in 0..len {
+        if input_vecs.iter().any(|vec| vec.row_is_null(i as u64)) {
+            output_vec.set_null(i);
+            continue;
+        }
+
+        let args: [i64; N] = std::array::from_fn(|idx| input_vecs[idx].as_slice::<i64>()[i]);
+        match f(args)? {
+            Some(v) => output_vec.insert(i, v.as_str()),
+            None => output_vec.set_null(i),
+        }
+    }
+
+    Ok(())
+}
+
+/// Invoke a unary `VARCHAR -> STRUCT(DOUBLE, ..., INTEGER, ...)` scalar with `D` leading
+/// `DOUBLE` fields followed by `I` nullable `INTEGER` fields.
+///
+/// This helper outputs NULL when the input row is NULL or when `f` returns `None`.
+pub fn invoke_unary_varchar_to_f64_i32_struct_nullable<F, const D: usize, const I: usize>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str) -> Option<([f64; D], [Option<i32>; I])>,
+{
+    let len = input.len();
+    let input_vec = input.flat_vector(0);
+    ensure_type(&input_vec, LogicalTypeId::Varchar, "input[0]")?;
+    let input_slice = input_vec.as_slice_with_len::<duckdb_string_t>(len);
+
+    let mut struct_vec = output.struct_vector();
+    let mut fields: Vec<FlatVector> = (0..D + I).map(|idx| struct_vec.child(idx, len)).collect();
+    for (idx, field) in fields.iter().enumerate() {
+        let expected = if idx < D {
+            LogicalTypeId::Double
+        } else {
+            LogicalTypeId::Integer
+        };
+        ensure_type(field, expected, &format!("output.child[{idx}]"))?;
+    }
+
+    for (i, raw) in input_slice.iter().enumerate() {
+        let row = if input_vec.row_is_null(i as u64) {
+            None
+        } else {
+            // SAFETY: The input row is checked non-NULL above.
+            let value = unsafe { decode_duckdb_string(raw) };
+            f(value.as_ref())
+        };
+
+        let Some((doubles, integers)) = row else {
+            // DuckDB expects the fields of a NULL struct to be NULL as well.
+            struct_vec.set_null(i);
+            fields.iter_mut().for_each(|field| field.set_null(i));
+            continue;
+        };
+
+        let (double_fields, integer_fields) = fields.split_at_mut(D);
+        for (field, value) in double_fields.iter_mut().zip(doubles) {
+            value.write(field, i);
+        }
+        for (field, value) in integer_fields.iter_mut().zip(integers) {
+            match value {
+                Some(value) => value.write(field, i),
+                None => field.set_null(i),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Invoke a unary `VARCHAR -> LIST(STRUCT(INTEGER, ..., VARCHAR))` scalar with `I` leading
+/// nullable `INTEGER` fields followed by one nullable `VARCHAR` field.
+///
+/// `f` returns one element per list entry, in struct field order. This helper outputs NULL when
+/// the input row is NULL or when `f` returns `None`.
+pub fn invoke_unary_varchar_to_i32_varchar_struct_list<F, const I: usize>(
+    input: &DataChunkHandle,
+    output: &mut dyn WritableVector,
+    mut f: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&str) -> Option<Vec<([Option<i32>; I], Option<String>)>>,
+{
+    let len = input.len();
+    let input_vec = input.flat_vector(0);
+    ensure_type(&input_vec, LogicalTypeId::Varchar, "input[0]")?;
+    let input_slice = input_vec.as_slice_with_len::<duckdb_string_t>(len);
+
+    let mut rows = Vec::with_capacity(len);
+    for (i, raw) in input_slice.iter().enumerate() {
+        if input_vec.row_is_null(i as u64) {
+            rows.push(None);
+            continue;
+        }
+
+        // SAFETY: Row nullability is checked above.
+        let value = unsafe { decode_duckdb_string(raw) };
+        rows.push(f(value.as_ref()));
+    }
+
+    let total: usize = rows.iter().flatten().map(Vec::len).sum();
+    let mut list_vec = output.list_vector();
+    let struct_vec = list_vec.struct_child(total);
+    let mut fields: Vec<FlatVector> = (0..=I).map(|idx| struct_vec.child(idx, total)).collect();
+    for (idx, field) in fields.iter().enumerate() {
+        let expected = if idx < I {
+            LogicalTypeId::Integer
+        } else {
+            LogicalTypeId::Varchar
+        };
+        ensure_type(field, expected, &format!("output.child[{idx}]"))?;
+    }
+
+    let mut offset = 0;
+    for (i, row) in rows.into_iter().enumerate() {
+        let Some(elements) = row else {
+            list_vec.set_null(i);
+            list_vec.set_entry(i, offset, 0);
+            continue;
+        };
+
+        list_vec.set_entry(i, offset, elements.len());
+        for (integers, text) in elements {
+            let (integer_fields, text_field) = fields.split_at_mut(I);
+            for (field, value) in integer_fields.iter_mut().zip(integers) {
+                match value {
+                    Some(value) => field.as_mut_slice_with_len::<i32>(total)[offset] = value,
+                    None => field.set_null(offset),
+                }
+            }
+            match text {
+                Some(text) => text_field[0].insert(offset, text.as_str()),
+                None => text_field[0].set_null(offset),
+            }
+            offset += 1;
         }
     }
+    list_vec.set_len(offset);
 
     Ok(())
 }
@@ -1,5 +1,7 @@
+use duckdb::core::{FlatVector, Inserter};
 use libduckdb_sys::duckdb_string_t;
 use std::borrow::Cow;
+use std::error::Error;
 
 /// Decode a DuckDB string value into a Rust `Cow<str>`.
 ///
@@ -12,32 +14,73 @@ use std::borrow::Cow;
 /// The returned borrowed string is only valid while DuckDB owns the backing
 /// vector memory for the active invocation.
 pub unsafe fn decode_duckdb_string<'a>(s: &'a duckdb_string_t) -> Cow<'a, str> {
+    // SAFETY: The caller upholds the contract of `decode_duckdb_bytes`.
+    String::from_utf8_lossy(unsafe { decode_duckdb_bytes(s) })
+}
+
+/// Raw bytes of a DuckDB `VARCHAR` or `BLOB` value, which share the `duckdb_string_t` layout.
+///
+/// # Safety
+///
+/// Same contract as [`decode_duckdb_string`].
+pub unsafe fn decode_duckdb_bytes(s: &duckdb_string_t) -> &[u8] {
     // SAFETY: Reading the inlined union field is part of DuckDB's string layout.
     let inlined_len = unsafe { s.value.inlined.length };
 
     if inlined_len <= 12 {
         let len = inlined_len as usize;
         if len == 0 {
-            return Cow::Borrowed("");
+            return &[];
         }
 
         // SAFETY: `len <= 12` for inlined strings.
         let inlined = unsafe { &s.value.inlined.inlined };
         // SAFETY: `inlined` contains `len` initialized bytes.
-        let bytes = unsafe { std::slice::from_raw_parts(inlined.as_ptr() as *const u8, len) };
-        String::from_utf8_lossy(bytes)
+        unsafe { std::slice::from_raw_parts(inlined.as_ptr() as *const u8, len) }
     } else {
         // SAFETY: Reading pointer representation fields is valid here.
         let len = unsafe { s.value.pointer.length } as usize;
         if len == 0 {
-            return Cow::Borrowed("");
+            return &[];
         }
 
         // SAFETY: DuckDB provides valid pointer storage for non-inlined strings.
         let ptr = unsafe { s.value.pointer.ptr };
         // SAFETY: `ptr` references `len` bytes for this value.
-        let bytes = unsafe { std::slice::from_raw_parts(ptr as *const u8, len) };
-        String::from_utf8_lossy(bytes)
+        unsafe { std::slice::from_raw_parts(ptr as *const u8, len) }
+    }
+}
+
+/// `value` with interior NUL bytes replaced by spaces; borrowed when it has none.
+pub fn sanitize_interior_nul(value: &str) -> Cow<'_, str> {
+    if value.contains('\0') {
+        Cow::Owned(value.replace('\0', " "))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Write `value` to row `row` of a `VARCHAR` vector. DuckDB copies the bytes by length, so no
+/// NUL-terminated copy is made; interior NUL bytes are replaced by spaces first, as `read_pgn`
+/// does.
+pub fn insert_varchar(vec: &FlatVector, row: usize, value: &str) {
+    vec.insert(row, sanitize_interior_nul(value).as_ref());
+}
+
+/// Write a scalar's `value` to row `row` of a `VARCHAR` vector by length, like
+/// [`insert_varchar`], but fail on an interior NUL byte with the error a `CString` copy gives.
+pub fn try_insert_varchar(vec: &FlatVector, row: usize, value: &str) -> Result<(), Box<dyn Error>> {
+    check_no_interior_nul(value)?;
+    vec.insert(row, value);
+    Ok(())
+}
+
+fn check_no_interior_nul(value: &str) -> Result<(), String> {
+    match value.find('\0') {
+        Some(position) => Err(format!(
+            "nul byte found in provided data at position: {position}"
+        )),
+        None => Ok(()),
     }
 }
 
@@ -83,6 +126,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sanitize_interior_nul() {
+        assert!(matches!(
+            sanitize_interior_nul("1. e4"),
+            Cow::Borrowed("1. e4")
+        ));
+        assert_eq!(sanitize_interior_nul("x\0y"), "x y");
+    }
+
+    #[test]
+    fn test_check_no_interior_nul_matches_cstring_error() {
+        assert_eq!(check_no_interior_nul("1. e4"), Ok(()));
+        let cstring_error = std::ffi::CString::new("x\0y").unwrap_err().to_string();
+        assert_eq!(check_no_interior_nul("x\0y"), Err(cstring_error));
+    }
+
     #[test]
     fn test_decode_duckdb_string_inlined() {
         let input = make_inlined_duckdb_string(b"e4");
@@ -0,0 +1,584 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{
+    BigintInput, DoubleInput, invoke_bigint_list_f64_to_f64_nullable,
+    invoke_bigint_list_i64s_to_i32_list_nullable, invoke_binary_varchar_varchar_to_f64_nullable,
+    invoke_i64_i64_varchar_to_f64_nullable, invoke_i64s_to_varchar_nullable,
+    invoke_quaternary_to_struct_nullable,
+};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+#[cfg(feature = "extension")]
+use std::error::Error;
+
+/// Expected score of a player rated `elo` against `opponent_elo` (logistic Elo curve).
+pub fn expected_score(elo: f64, opponent_elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent_elo - elo) / 400.0))
+}
+
+/// White's score for a decisive or drawn PGN result; `None` for unfinished/unknown results.
+pub fn white_score(result: &str) -> Option<f64> {
+    match result.trim() {
+        "1-0" => Some(1.0),
+        "0-1" => Some(0.0),
+        "1/2-1/2" | "½-½" => Some(0.5),
+        _ => None,
+    }
+}
+
+/// Score (1 win, 0.5 draw, 0 loss) of the side named by `perspective` (`white`/`w` or
+/// `black`/`b`, case-insensitive); `Ok(None)` for unfinished/unknown results.
+pub fn perspective_score(result: &str, perspective: &str) -> Result<Option<f64>, String> {
+    let white = match perspective.trim().to_ascii_lowercase().as_str() {
+        "white" | "w" => true,
+        "black" | "b" => false,
+        _ => {
+            return Err(format!(
+                "chess_wdl: invalid perspective_color '{perspective}'. Supported values: 'white', 'black'."
+            ));
+        }
+    };
+    Ok(white_score(result).map(|score| if white { score } else { 1.0 - score }))
+}
+
+/// White's actual score minus the Elo-expected score for one game.
+pub fn white_upset_delta(white_elo: i64, black_elo: i64, result: &str) -> Option<f64> {
+    let actual = white_score(result)?;
+    Some(actual - expected_score(white_elo as f64, black_elo as f64))
+}
+
+/// Furthest a performance rating may sit from the opponents' average, as in FIDE's table of
+/// rating differences; perfect and zero scores land on this bound.
+const MAX_PERFORMANCE_DIFFERENCE: f64 = 800.0;
+
+/// Performance rating of `score` points against `opponent_elos`: the rating whose Elo-expected
+/// score over those games equals `score`, within 800 points of the opponents' average.
+///
+/// `Ok(None)` without games or when an opponent rating is missing.
+pub fn performance_rating(
+    opponent_elos: &[Option<i64>],
+    score: f64,
+) -> Result<Option<f64>, String> {
+    let Some(opponents) = opponent_elos
+        .iter()
+        .map(|elo| elo.map(|elo| elo as f64))
+        .collect::<Option<Vec<f64>>>()
+    else {
+        return Ok(None);
+    };
+    if opponents.is_empty() {
+        return Ok(None);
+    }
+    let games = opponents.len() as f64;
+    if !(0.0..=games).contains(&score) {
+        return Err(format!(
+            "chess_performance_rating: invalid score {score}. Expected 0 to {games} points for \
+             {games} games."
+        ));
+    }
+
+    // The expected score grows with the rating, so bisect between the bounds.
+    let average = opponents.iter().sum::<f64>() / games;
+    let (mut lo, mut hi) = (
+        average - MAX_PERFORMANCE_DIFFERENCE,
+        average + MAX_PERFORMANCE_DIFFERENCE,
+    );
+    for _ in 0..64 {
+        let mid = (lo + hi) / 2.0;
+        let expected: f64 = opponents.iter().map(|&opp| expected_score(mid, opp)).sum();
+        if expected < score {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Ok(Some((lo + hi) / 2.0))
+}
+
+/// Most games a `chess_match_odds` match may have; the score distribution costs quadratic time in
+/// the number of games.
+const MAX_MATCH_GAMES: i64 = 1_000;
+
+/// Probabilities that player A wins, draws and loses a match of `games` games against player B.
+///
+/// Each game is independent: A scores the Elo-expected score on average and draws with
+/// probability `draw_rate`, capped at what the expected score allows (a 0.9 favourite cannot
+/// draw more than 20% of games). A match is drawn when both players end on the same score.
+pub fn match_odds(elo_a: i64, elo_b: i64, games: i64, draw_rate: f64) -> Result<[f64; 3], String> {
+    if !(1..=MAX_MATCH_GAMES).contains(&games) {
+        return Err(format!(
+            "chess_match_odds: invalid games {games}. Expected 1 to {MAX_MATCH_GAMES} games."
+        ));
+    }
+    if !(0.0..=1.0).contains(&draw_rate) {
+        return Err(format!(
+            "chess_match_odds: invalid draw_rate {draw_rate}. Expected a value from 0 to 1."
+        ));
+    }
+
+    let expected = expected_score(elo_a as f64, elo_b as f64);
+    let draw = draw_rate.min(2.0 * expected.min(1.0 - expected));
+    let win = expected - draw / 2.0;
+    let loss = 1.0 - expected - draw / 2.0;
+
+    // `half_points[k]`: probability that A has scored `k` half-points so far.
+    let games = games as usize;
+    let mut half_points = vec![0.0; 2 * games + 1];
+    half_points[0] = 1.0;
+    for played in 0..games {
+        for k in (0..=2 * played).rev() {
+            let p = std::mem::take(&mut half_points[k]);
+            half_points[k] += p * loss;
+            half_points[k + 1] += p * draw;
+            half_points[k + 2] += p * win;
+        }
+    }
+
+    let (b_wins, rest) = half_points.split_at(games);
+    let (tied, a_wins) = rest.split_at(1);
+    Ok([a_wins.iter().sum(), tied[0], b_wins.iter().sum()])
+}
+
+/// Rating cohort label for `elo`: `"{lo}-{hi}"` for `width`-wide buckets counted from `min`,
+/// `"<{min}"` below the range and `"{max}+"` from `max` up. A last bucket that would run past
+/// `max` is cut short there.
+pub fn elo_bucket(elo: i64, width: i64, min: i64, max: i64) -> Result<String, String> {
+    check_bucket_layout("chess_elo_bucket", width, min, max)?;
+
+    if elo < min {
+        return Ok(format!("<{min}"));
+    }
+    if elo >= max {
+        return Ok(format!("{max}+"));
+    }
+    // `elo - min < max - min`, so only the bucket end can overflow.
+    let lo = min + (elo - min) / width * width;
+    let hi = lo.saturating_add(width).min(max) - 1;
+    Ok(format!("{lo}-{hi}"))
+}
+
+/// Most buckets a `chess_elo_histogram` layout may have, so a tiny width cannot allocate
+/// unbounded lists.
+const MAX_HISTOGRAM_BUCKETS: i128 = 10_000;
+
+fn check_bucket_layout(function: &str, width: i64, min: i64, max: i64) -> Result<(), String> {
+    if width <= 0 {
+        return Err(format!(
+            "{function}: invalid width {width}. Expected a positive number of points."
+        ));
+    }
+    if min >= max {
+        return Err(format!(
+            "{function}: invalid range min {min}, max {max}. Expected min < max."
+        ));
+    }
+    Ok(())
+}
+
+/// Rating counts in the [`elo_bucket`] layout: ratings below `min` first, then one count per
+/// `width`-wide bucket counted from `min` (the last one cut short at `max`), then ratings from
+/// `max` up. NULL ratings are not counted.
+pub fn elo_histogram(
+    elos: &[Option<i64>],
+    width: i64,
+    min: i64,
+    max: i64,
+) -> Result<Vec<i32>, String> {
+    check_bucket_layout("chess_elo_histogram", width, min, max)?;
+    let (width, min) = (i128::from(width), i128::from(min));
+    let buckets = (i128::from(max) - min + width - 1) / width;
+    if buckets > MAX_HISTOGRAM_BUCKETS {
+        return Err(format!(
+            "chess_elo_histogram: width {width} makes {buckets} buckets. Expected at most \
+             {MAX_HISTOGRAM_BUCKETS}; use a larger width or a narrower range."
+        ));
+    }
+
+    let mut counts = vec![0i32; buckets as usize + 2];
+    for &elo in elos.iter().flatten() {
+        let slot = if i128::from(elo) < min {
+            0
+        } else if elo >= max {
+            counts.len() - 1
+        } else {
+            ((i128::from(elo) - min) / width) as usize + 1
+        };
+        counts[slot] = counts[slot].saturating_add(1);
+    }
+    Ok(counts)
+}
+
+// Per-game term summed by the `chess_upset_score` aggregate macro.
+#[cfg(feature = "extension")]
+pub struct ChessUpsetScoreScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessUpsetScoreScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_i64_i64_varchar_to_f64_nullable(input, output, white_upset_delta)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Double),
+        )]
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessMatchOddsScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessMatchOddsScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_quaternary_to_struct_nullable::<
+            BigintInput,
+            BigintInput,
+            BigintInput,
+            DoubleInput,
+            _,
+            _,
+            3,
+        >(input, output, |elo_a, elo_b, games, draw_rate| {
+            Ok(Some(match_odds(elo_a, elo_b, games, draw_rate)?))
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+                LogicalTypeHandle::from(LogicalTypeId::Double),
+            ],
+            LogicalTypeHandle::struct_type(&[
+                ("p_a_wins", LogicalTypeHandle::from(LogicalTypeId::Double)),
+                ("p_draw", LogicalTypeHandle::from(LogicalTypeId::Double)),
+                ("p_b_wins", LogicalTypeHandle::from(LogicalTypeId::Double)),
+            ]),
+        )]
+    }
+}
+
+// Per-game points counted by the `chess_wdl` aggregate macro.
+#[cfg(feature = "extension")]
+pub struct ChessWdlScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessWdlScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_binary_varchar_varchar_to_f64_nullable(input, output, |result, perspective| {
+            Ok(perspective_score(result, perspective)?)
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Double),
+        )]
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessPerformanceRatingScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessPerformanceRatingScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_bigint_list_f64_to_f64_nullable(input, output, |opponent_elos, score| {
+            Ok(performance_rating(opponent_elos, score)?)
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Bigint)),
+                LogicalTypeHandle::from(LogicalTypeId::Double),
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Double),
+        )]
+    }
+}
+
+// Backs the `chess_elo_bucket` macro, which supplies the default bucket layout.
+#[cfg(feature = "extension")]
+pub struct ChessEloBucketScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessEloBucketScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_i64s_to_varchar_nullable(input, output, |[elo, width, min, max]| {
+            Ok(Some(elo_bucket(elo, width, min, max)?))
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Bigint), // elo
+                LogicalTypeHandle::from(LogicalTypeId::Bigint), // width
+                LogicalTypeHandle::from(LogicalTypeId::Bigint), // min
+                LogicalTypeHandle::from(LogicalTypeId::Bigint), // max
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )]
+    }
+}
+
+// Backs the `chess_elo_histogram` aggregate macro, which collects each group's ratings.
+#[cfg(feature = "extension")]
+pub struct ChessEloHistogramScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessEloHistogramScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_bigint_list_i64s_to_i32_list_nullable(input, output, |elos, [width, min, max]| {
+            Ok(Some(elo_histogram(elos, width, min, max)?))
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Bigint)), // elos
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),                           // width
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),                           // min
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),                           // max
+            ],
+            LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Integer)),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_expected_score_is_symmetric() {
+        assert_close(expected_score(1500.0, 1500.0), 0.5);
+        assert_close(expected_score(1900.0, 1500.0), 10.0 / 11.0);
+        assert_close(
+            expected_score(1900.0, 1500.0) + expected_score(1500.0, 1900.0),
+            1.0,
+        );
+    }
+
+    #[test]
+    fn test_white_score_parses_results() {
+        assert_eq!(white_score("1-0"), Some(1.0));
+        assert_eq!(white_score("0-1"), Some(0.0));
+        assert_eq!(white_score("1/2-1/2"), Some(0.5));
+        assert_eq!(white_score(" 1-0 "), Some(1.0));
+        assert_eq!(white_score("*"), None);
+        assert_eq!(white_score(""), None);
+    }
+
+    #[test]
+    fn test_white_upset_delta() {
+        assert_close(white_upset_delta(1500, 1500, "1-0").unwrap(), 0.5);
+        assert_close(white_upset_delta(1500, 1900, "1-0").unwrap(), 10.0 / 11.0);
+        assert_close(white_upset_delta(1900, 1500, "0-1").unwrap(), -10.0 / 11.0);
+        assert_close(white_upset_delta(1500, 1500, "1/2-1/2").unwrap(), 0.0);
+        assert_eq!(white_upset_delta(1500, 1500, "*"), None);
+    }
+
+    #[test]
+    fn test_perspective_score_flips_for_black() {
+        assert_eq!(perspective_score("1-0", "white"), Ok(Some(1.0)));
+        assert_eq!(perspective_score("1-0", "Black"), Ok(Some(0.0)));
+        assert_eq!(perspective_score("0-1", " b "), Ok(Some(1.0)));
+        assert_eq!(perspective_score("1/2-1/2", "BLACK"), Ok(Some(0.5)));
+        assert_eq!(perspective_score("*", "w"), Ok(None));
+        assert!(perspective_score("1-0", "both").is_err());
+    }
+
+    #[test]
+    fn test_performance_rating() {
+        let rating = |elos: &[i64], score| {
+            let elos: Vec<Option<i64>> = elos.iter().copied().map(Some).collect();
+            performance_rating(&elos, score).unwrap().unwrap()
+        };
+
+        // An even score performs at the opponents' average.
+        assert!((rating(&[1500], 0.5) - 1500.0).abs() < 1e-6);
+        assert!((rating(&[1400, 1600], 1.0) - 1500.0).abs() < 1e-6);
+        // Beating a 1500 three times in four performs at the rating expected to do so.
+        let r = rating(&[1500, 1500, 1500, 1500], 3.0);
+        assert!((expected_score(r, 1500.0) - 0.75).abs() < 1e-9);
+        assert!((r - (1500.0 + 400.0 * 3f64.log10())).abs() < 1e-6);
+        // Perfect and zero scores are capped.
+        assert!((rating(&[1500, 1700], 2.0) - 2400.0).abs() < 1e-6);
+        assert!((rating(&[1500, 1700], 0.0) - 800.0).abs() < 1e-6);
+
+        assert_eq!(performance_rating(&[], 0.0), Ok(None));
+        assert_eq!(performance_rating(&[Some(1500), None], 1.0), Ok(None));
+        assert!(performance_rating(&[Some(1500)], 1.5).is_err());
+        assert!(performance_rating(&[Some(1500)], -0.5).is_err());
+        assert!(performance_rating(&[Some(1500)], f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_match_odds() {
+        // Equal players, one game: win and loss share what draws leave.
+        let [a, d, b] = match_odds(1500, 1500, 1, 0.3).unwrap();
+        assert_close(a, 0.35);
+        assert_close(d, 0.3);
+        assert_close(b, 0.35);
+
+        // Two games without draws: A wins 2-0 or B wins 2-0, otherwise 1-1.
+        let [a, d, b] = match_odds(1500, 1500, 2, 0.0).unwrap();
+        assert_close(a, 0.25);
+        assert_close(d, 0.5);
+        assert_close(b, 0.25);
+
+        // The favourite's edge grows with the match length.
+        let short = match_odds(1700, 1500, 1, 0.2).unwrap()[0];
+        let long = match_odds(1700, 1500, 12, 0.2).unwrap()[0];
+        assert!(long > short);
+        let [a, d, b] = match_odds(1700, 1500, 12, 0.2).unwrap();
+        assert_close(a + d + b, 1.0);
+
+        // A draw rate the expected score cannot support is capped.
+        let [a, d, b] = match_odds(2400, 1000, 1, 1.0).unwrap();
+        assert_close(b, 0.0);
+        assert_close(a + d, 1.0);
+
+        assert!(match_odds(1500, 1500, 0, 0.3).is_err());
+        assert!(match_odds(1500, 1500, 1001, 0.3).is_err());
+        assert!(match_odds(1500, 1500, 1, 1.5).is_err());
+        assert!(match_odds(1500, 1500, 1, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_elo_bucket_labels() {
+        let bucket = |elo| elo_bucket(elo, 100, 800, 2800).unwrap();
+        assert_eq!(bucket(1200), "1200-1299");
+        assert_eq!(bucket(1399), "1300-1399");
+        assert_eq!(bucket(800), "800-899");
+        assert_eq!(bucket(799), "<800");
+        assert_eq!(bucket(2799), "2700-2799");
+        assert_eq!(bucket(2800), "2800+");
+        assert_eq!(elo_bucket(1450, 200, 1000, 2000).unwrap(), "1400-1599");
+        // The last bucket stops at max.
+        assert_eq!(elo_bucket(2650, 300, 800, 2800).unwrap(), "2600-2799");
+    }
+
+    #[test]
+    fn test_elo_histogram_counts_in_bucket_layout() {
+        let elos = [
+            Some(750),
+            Some(800),
+            Some(899),
+            None,
+            Some(1450),
+            Some(2799),
+            Some(3000),
+        ];
+        let counts = elo_histogram(&elos, 100, 800, 2800).unwrap();
+        assert_eq!(counts.len(), 22);
+        assert_eq!(counts[0], 1); // <800
+        assert_eq!(counts[1], 2); // 800-899
+        assert_eq!(counts[7], 1); // 1400-1499
+        assert_eq!(counts[20], 1); // 2700-2799
+        assert_eq!(counts[21], 1); // 2800+
+        assert_eq!(counts.iter().sum::<i32>(), 6);
+
+        // The last bucket stops at max, and an empty group still has every bucket.
+        assert_eq!(
+            elo_histogram(&[Some(2650)], 300, 800, 2800).unwrap(),
+            [0, 0, 0, 0, 0, 0, 0, 1, 0]
+        );
+        assert_eq!(elo_histogram(&[], 500, 1000, 2000).unwrap(), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_elo_histogram_rejects_invalid_layout() {
+        let err = elo_histogram(&[], -5, 800, 2800).unwrap_err();
+        assert!(
+            err.starts_with("chess_elo_histogram: invalid width -5"),
+            "{err}"
+        );
+        assert!(
+            elo_histogram(&[], 100, 2800, 800)
+                .unwrap_err()
+                .contains("invalid range")
+        );
+        assert!(
+            elo_histogram(&[], 1, i64::MIN, i64::MAX)
+                .unwrap_err()
+                .contains("at most 10000")
+        );
+    }
+
+    #[test]
+    fn test_elo_bucket_rejects_invalid_layout() {
+        assert!(
+            elo_bucket(1500, 0, 800, 2800)
+                .unwrap_err()
+                .contains("invalid width 0")
+        );
+        assert!(
+            elo_bucket(1500, 100, 2000, 2000)
+                .unwrap_err()
+                .contains("invalid range")
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use super::clock::annotation_series;
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::invoke_unary_varchar_to_i32_varchar_struct_list;
+use super::puzzles::{Eval, parse_eval, parse_eval_value};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use shakmaty::san::SanPlus;
+#[cfg(feature = "extension")]
+use std::error::Error;
+
+/// Output fields of `chess_engine_comments`, in struct order: the integers, then `pv`.
+#[cfg(feature = "extension")]
+const INTEGER_FIELDS: [&str; 4] = ["ply", "depth", "cp", "mate"];
+
+/// An engine line read from a move comment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EngineComment {
+    /// Search depth, when the comment records one.
+    pub depth: Option<i32>,
+    /// Score as written; engine-match exports score from the mover's side.
+    pub eval: Eval,
+    /// Principal variation in SAN, space-separated, when the comment carries one.
+    pub pv: Option<String>,
+}
+
+/// Parse the engine line in a comment.
+///
+/// Accepts a `[%eval score,depth]` command, or the `score[/depth] [time] [pv...]` layout written
+/// by Arena, cutechess, Scid and ChessBase engine matches and annotations, such as
+/// `+0.43/18 12s Nf3 d5` or `-M5/30 0.8s`. Scores are pawns with a sign or a decimal point, or
+/// mates written `M5`, `#5` or `#-5`. The PV stops at the first token that is not SAN; move
+/// numbers and parentheses around it are skipped. Comments that do not start with a score,
+/// such as prose, return `None`.
+pub fn parse_engine_comment(comment: &str) -> Option<EngineComment> {
+    if comment.contains("[%eval") {
+        return parse_eval_command(comment);
+    }
+
+    let text = strip_commands(comment);
+    let mut tokens = text
+        .split_whitespace()
+        .map(|token| token.trim_matches(|c| c == '(' || c == ')' || c == ','))
+        .filter(|token| !token.is_empty())
+        .peekable();
+    let first = tokens.next()?;
+    let (score, depth) = match first.split_once('/') {
+        Some((score, depth)) => (score, Some(parse_depth(depth)?)),
+        None => (first, None),
+    };
+    let eval = parse_score(score)?;
+
+    tokens.next_if(|token| is_time(token));
+    let pv: Vec<&str> = tokens
+        .map(strip_move_number)
+        .filter(|token| !token.is_empty())
+        .take_while(|token| token.parse::<SanPlus>().is_ok())
+        .collect();
+
+    Some(EngineComment {
+        depth,
+        eval,
+        pv: (!pv.is_empty()).then(|| pv.join(" ")),
+    })
+}
+
+fn parse_eval_command(comment: &str) -> Option<EngineComment> {
+    let eval = parse_eval(comment)?;
+    let start = comment.find("[%eval")? + "[%eval".len();
+    let rest = &comment[start..];
+    let raw = &rest[..rest.find(']')?];
+    let depth = raw
+        .split_once(',')
+        .and_then(|(_, depth)| parse_depth(depth.trim()));
+    Some(EngineComment {
+        depth,
+        eval,
+        pv: None,
+    })
+}
+
+/// Remove `[%...]` commands such as `[%clk ...]`, keeping the text around them.
+fn strip_commands(comment: &str) -> String {
+    let mut text = String::with_capacity(comment.len());
+    let mut rest = comment;
+    while let Some(start) = rest.find("[%") {
+        text.push_str(&rest[..start]);
+        match rest[start..].find(']') {
+            Some(end) => rest = &rest[start + end + 1..],
+            None => return text,
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+/// A pawn score needs a sign or a decimal point, so bare numbers in prose are not scores.
+fn parse_score(score: &str) -> Option<Eval> {
+    let (negative, unsigned) = match score.as_bytes().first()? {
+        b'+' => (false, &score[1..]),
+        b'-' => (true, &score[1..]),
+        _ => (false, score),
+    };
+    if let Some(moves) = unsigned.strip_prefix(['M', '#']) {
+        let sign = if negative { "-" } else { "" };
+        return parse_eval_value(&format!("#{sign}{moves}"));
+    }
+    if unsigned.is_empty() || !unsigned.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+        return None;
+    }
+    if unsigned.len() == score.len() && !unsigned.contains('.') {
+        return None;
+    }
+    parse_eval_value(score)
+}
+
+/// Leading digits of a depth such as `18` or `18/25` (depth/selective depth).
+fn parse_depth(depth: &str) -> Option<i32> {
+    let digits = depth.split(|c: char| !c.is_ascii_digit()).next()?;
+    digits.parse().ok()
+}
+
+/// Search time after the score: `12s`, `0.53s`, `12` or `1:05`.
+fn is_time(token: &str) -> bool {
+    let number = token.strip_suffix('s').unwrap_or(token);
+    !number.is_empty()
+        && number.bytes().any(|b| b.is_ascii_digit())
+        && number
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b == b'.' || b == b':')
+}
+
+/// Drop a leading move number: `12.Nf3` gives `Nf3`, `12...d5` gives `d5`, a lone `12.` gives
+/// an empty token.
+fn strip_move_number(token: &str) -> &str {
+    let rest = token.trim_start_matches(|c: char| c.is_ascii_digit());
+    if rest.len() < token.len() && rest.starts_with('.') {
+        rest.trim_start_matches('.')
+    } else {
+        token
+    }
+}
+
+/// Engine lines after each mainline ply that carries one, as `(ply, line)` with 1-based plies.
+/// Returns `None` when the movetext cannot be parsed.
+pub fn engine_comments(movetext: &str) -> Option<Vec<(usize, EngineComment)>> {
+    Some(
+        annotation_series(movetext, parse_engine_comment)?
+            .into_iter()
+            .enumerate()
+            .filter_map(|(idx, line)| Some((idx + 1, line?)))
+            .collect(),
+    )
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessEngineCommentsScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessEngineCommentsScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_unary_varchar_to_i32_varchar_struct_list(input, output, |movetext| {
+            Some(
+                engine_comments(movetext)?
+                    .into_iter()
+                    .map(|(ply, line)| {
+                        let (cp, mate) = match line.eval {
+                            Eval::Centipawns(cp) => (Some(cp), None),
+                            Eval::Mate(moves) => (None, Some(moves)),
+                        };
+                        let ply = i32::try_from(ply).ok();
+                        ([ply, line.depth, cp, mate], line.pv)
+                    })
+                    .collect(),
+            )
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let mut fields: Vec<_> = INTEGER_FIELDS
+            .iter()
+            .map(|name| (*name, LogicalTypeHandle::from(LogicalTypeId::Integer)))
+            .collect();
+        fields.push(("pv", LogicalTypeHandle::from(LogicalTypeId::Varchar)));
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            LogicalTypeHandle::list(&LogicalTypeHandle::struct_type(&fields)),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(depth: Option<i32>, eval: Eval, pv: Option<&str>) -> Option<EngineComment> {
+        Some(EngineComment {
+            depth,
+            eval,
+            pv: pv.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn test_parse_engine_comment_arena_and_cutechess_layouts() {
+        assert_eq!(
+            parse_engine_comment("+0.43/18 12s Nf3 d5 c4"),
+            line(Some(18), Eval::Centipawns(43), Some("Nf3 d5 c4"))
+        );
+        assert_eq!(
+            parse_engine_comment(" -1.20/22 0.53s "),
+            line(Some(22), Eval::Centipawns(-120), None)
+        );
+        assert_eq!(
+            parse_engine_comment("-M5/30 1:05"),
+            line(Some(30), Eval::Mate(-5), None)
+        );
+        assert_eq!(
+            parse_engine_comment("0.00/25/40 3s (12.Nf3 d5 13.O-O)"),
+            line(Some(25), Eval::Centipawns(0), Some("Nf3 d5 O-O"))
+        );
+    }
+
+    #[test]
+    fn test_parse_engine_comment_score_without_depth() {
+        assert_eq!(
+            parse_engine_comment("+0.43 e4 e5 good move"),
+            line(None, Eval::Centipawns(43), Some("e4 e5"))
+        );
+        assert_eq!(
+            parse_engine_comment("#3 Qxf7+ Ke7"),
+            line(None, Eval::Mate(3), Some("Qxf7+ Ke7"))
+        );
+    }
+
+    #[test]
+    fn test_parse_engine_comment_eval_command() {
+        assert_eq!(
+            parse_engine_comment("[%clk 0:03:00] [%eval -0.3,24]"),
+            line(Some(24), Eval::Centipawns(-30), None)
+        );
+        assert_eq!(
+            parse_engine_comment("[%clk 0:03:00] +0.5/12"),
+            line(Some(12), Eval::Centipawns(50), None)
+        );
+    }
+
+    #[test]
+    fn test_parse_engine_comment_rejects_prose() {
+        assert_eq!(parse_engine_comment("Book"), None);
+        assert_eq!(parse_engine_comment("18 moves later White resigned"), None);
+        assert_eq!(parse_engine_comment("+M0/10"), None);
+        assert_eq!(parse_engine_comment("+0.43/deep"), None);
+        assert_eq!(parse_engine_comment(""), None);
+    }
+
+    #[test]
+    fn test_engine_comments_numbers_plies() {
+        let lines =
+            engine_comments("1. e4 { Book } e5 { +0.20/15 2s Nf3 } 2. Nf3 { -0.10/16 } 1-0")
+                .unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                (
+                    2,
+                    line(Some(15), Eval::Centipawns(20), Some("Nf3")).unwrap()
+                ),
+                (3, line(Some(16), Eval::Centipawns(-10), None).unwrap()),
+            ]
+        );
+        assert_eq!(engine_comments(""), Some(Vec::new()));
+    }
+}
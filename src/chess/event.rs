@@ -0,0 +1,265 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{VarcharInput, invoke_ternary_nullable};
+use super::timecontrol::categorize_timecontrol;
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+#[cfg(feature = "extension")]
+use std::error::Error;
+
+/// Site or Event fragments that only show up for games played on a server.
+const ONLINE_MARKERS: [&str; 8] = [
+    "lichess.org",
+    "chess.com",
+    "chess24",
+    "playchess",
+    "chessclub.com",
+    "freechess.org",
+    "internet",
+    "online",
+];
+/// Event fragments of games played outside any event.
+const CASUAL_MARKERS: [&str; 4] = ["casual", "friendly", "training", "offhand"];
+/// Event fragments of faster-than-classical over-the-board events.
+const FAST_MARKERS: [&str; 5] = ["rapid", "blitz", "bullet", "armageddon", "speed"];
+
+/// Kind of event a game was played in, guessed from its tags.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventType {
+    OtbClassical,
+    OtbRapid,
+    OnlineArena,
+    OnlineSwiss,
+    Match,
+    Simul,
+    Casual,
+}
+
+impl EventType {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::OtbClassical => "otb-classical",
+            Self::OtbRapid => "otb-rapid",
+            Self::OnlineArena => "online-arena",
+            Self::OnlineSwiss => "online-swiss",
+            Self::Match => "match",
+            Self::Simul => "simul",
+            Self::Casual => "casual",
+        }
+    }
+}
+
+/// Guess the kind of event from the Event, Site and TimeControl tags.
+///
+/// Simuls are recognised first, then games on a known server (Lichess and Chess.com URLs,
+/// `Online` sites): arenas (`Rated Blitz Arena`, `/tournament/` links), swisses (`/swiss/`
+/// links, `Titled Tuesday`), matches, and everything else as casual, which covers
+/// `Rated Blitz game` pairings outside any event. Over-the-board games are matches
+/// (`Match`, `A vs B`), casual games (`Casual`, `Friendly`, `Training`), or tournament games
+/// split into `OtbRapid` and `OtbClassical` by the event name or the TimeControl category.
+/// Returns `None` when neither Event nor Site carries any information.
+pub fn classify_event(event: &str, site: &str, timecontrol: &str) -> Option<EventType> {
+    let event = known_tag(event).to_lowercase();
+    let site = known_tag(site).to_lowercase();
+    if event.is_empty() && site.is_empty() {
+        return None;
+    }
+    let mentions = |markers: &[&str]| {
+        markers
+            .iter()
+            .any(|marker| event.contains(marker) || site.contains(marker))
+    };
+
+    let kind = if event.contains("simul") {
+        EventType::Simul
+    } else if mentions(&ONLINE_MARKERS) {
+        if event.contains("arena") || mentions(&["/tournament/"]) {
+            EventType::OnlineArena
+        } else if event.contains("swiss") || event.contains("titled tuesday") {
+            EventType::OnlineSwiss
+        } else if is_match(&event) {
+            EventType::Match
+        } else {
+            EventType::Casual
+        }
+    } else if is_match(&event) {
+        EventType::Match
+    } else if CASUAL_MARKERS.iter().any(|marker| event.contains(marker)) {
+        EventType::Casual
+    } else if FAST_MARKERS.iter().any(|marker| event.contains(marker))
+        || matches!(
+            categorize_timecontrol(timecontrol),
+            Some("ultra-bullet" | "bullet" | "blitz" | "rapid")
+        )
+    {
+        EventType::OtbRapid
+    } else {
+        EventType::OtbClassical
+    };
+    Some(kind)
+}
+
+/// The tag value with the PGN "unknown" placeholder `?` treated as empty.
+fn known_tag(value: &str) -> &str {
+    let value = value.trim();
+    if value.chars().all(|c| c == '?') {
+        ""
+    } else {
+        value
+    }
+}
+
+fn is_match(event: &str) -> bool {
+    event.contains("match")
+        || [" vs ", " vs. ", " v "]
+            .iter()
+            .any(|sep| event.contains(sep))
+}
+
+/// One row of `chess_event_type_impl(event, site, timecontrol)`. The public macro passes
+/// NULL tags as empty strings, so the result is NULL only when every tag is missing or
+/// uninformative.
+#[cfg(any(test, feature = "extension"))]
+fn event_type_of_tags(event: &str, site: &str, timecontrol: &str) -> Option<String> {
+    classify_event(event, site, timecontrol).map(|kind| kind.label().to_string())
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessEventTypeScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessEventTypeScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_ternary_nullable::<VarcharInput, VarcharInput, VarcharInput, _, _>(
+            input,
+            output,
+            |event, site, timecontrol| Ok(event_type_of_tags(event, site, timecontrol)),
+        )
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(event: &str, site: &str, timecontrol: &str) -> Option<&'static str> {
+        classify_event(event, site, timecontrol).map(EventType::label)
+    }
+
+    #[test]
+    fn test_classify_event_online_games() {
+        assert_eq!(
+            classify("Rated Blitz Arena", "https://lichess.org/abcd1234", "180+0"),
+            Some("online-arena")
+        );
+        assert_eq!(
+            classify(
+                "Hourly Bullet https://lichess.org/tournament/x1y2z3",
+                "https://lichess.org/abcd1234",
+                "60+0"
+            ),
+            Some("online-arena")
+        );
+        assert_eq!(
+            classify(
+                "Weekly Rapid https://lichess.org/swiss/q1w2e3",
+                "https://lichess.org/abcd1234",
+                "600+0"
+            ),
+            Some("online-swiss")
+        );
+        assert_eq!(
+            classify("Titled Tuesday Blitz", "Chess.com", "180+1"),
+            Some("online-swiss")
+        );
+        assert_eq!(
+            classify("Rated Blitz game", "https://lichess.org/abcd1234", "300+0"),
+            Some("casual")
+        );
+        assert_eq!(classify("Live Chess", "Chess.com", "600"), Some("casual"));
+        assert_eq!(
+            classify("Speed Chess Championship Match", "Chess.com", "300+1"),
+            Some("match")
+        );
+    }
+
+    #[test]
+    fn test_classify_event_over_the_board() {
+        assert_eq!(
+            classify("Tata Steel Masters", "Wijk aan Zee NED", "40/7200:3600"),
+            Some("otb-classical")
+        );
+        assert_eq!(
+            classify("Tata Steel Masters", "Wijk aan Zee NED", "?"),
+            Some("otb-classical")
+        );
+        assert_eq!(
+            classify("World Rapid Championship", "Almaty KAZ", "?"),
+            Some("otb-rapid")
+        );
+        assert_eq!(
+            classify("Club Championship", "Leiden NED", "900+10"),
+            Some("otb-rapid")
+        );
+        assert_eq!(
+            classify("World Championship Match", "London ENG", "?"),
+            Some("match")
+        );
+        assert_eq!(
+            classify("Kasparov vs Deep Blue", "Philadelphia USA", ""),
+            Some("match")
+        );
+        assert_eq!(classify("Casual game", "London ENG", ""), Some("casual"));
+    }
+
+    #[test]
+    fn test_classify_event_simuls_and_missing_tags() {
+        assert_eq!(
+            classify("Simul, 30b", "Buenos Aires ARG", ""),
+            Some("simul")
+        );
+        assert_eq!(
+            classify(
+                "Simultaneous exhibition",
+                "https://lichess.org/abcd1234",
+                "1800+60"
+            ),
+            Some("simul")
+        );
+        assert_eq!(classify("?", "?", "180+2"), None);
+        assert_eq!(classify("", " ", ""), None);
+        assert_eq!(classify("?", "Leiden NED", "180+2"), Some("otb-rapid"));
+    }
+    #[test]
+    fn test_event_type_of_tags_treats_empty_tags_as_missing() {
+        assert_eq!(event_type_of_tags("", "", ""), None);
+        assert_eq!(
+            event_type_of_tags("", "Leiden NED", "180+2").as_deref(),
+            Some("otb-rapid")
+        );
+        assert_eq!(
+            event_type_of_tags("Simul, 30b", "", "").as_deref(),
+            Some("simul")
+        );
+    }
+}
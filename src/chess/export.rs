@@ -0,0 +1,411 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::string::{decode_duckdb_string, try_insert_varchar};
+use super::moves::is_result_marker;
+use chrono::{NaiveDate, TimeDelta};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, FlatVector, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+#[cfg(feature = "extension")]
+use libduckdb_sys::duckdb_string_t;
+#[cfg(feature = "extension")]
+use std::error::Error;
+
+/// PGN export format keeps every line below 80 characters.
+const PGN_MAX_LINE_LEN: usize = 79;
+
+/// Seven Tag Roster in export order, with the placeholder used when a tag is missing.
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+/// Struct fields that are never exported as tags.
+#[cfg(feature = "extension")]
+const NON_TAG_FIELDS: [&str; 2] = ["movetext", "parse_error"];
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+const TIME_TZ_OFFSET_SENTINEL_SECONDS: i64 = 16 * 60 * 60 - 1;
+
+/// Render a single game as export-format PGN text.
+///
+/// `tags` are emitted in the order given after the Seven Tag Roster; tags with a NULL value
+/// are omitted. When `Date` is missing, `UTCDate` is used as a fallback. The game result is
+/// appended to the movetext unless the movetext already ends with a result marker.
+pub fn render_game_pgn(tags: &[(String, Option<String>)], movetext: &str) -> String {
+    let lookup = |name: &str| {
+        tags.iter()
+            .find(|(tag, value)| tag.eq_ignore_ascii_case(name) && value.is_some())
+            .and_then(|(_, value)| value.as_deref())
+    };
+
+    let mut out = String::with_capacity(movetext.len() + 64 * (tags.len() + 1));
+    let mut result = "*";
+    for (name, placeholder) in SEVEN_TAG_ROSTER {
+        let value = match name {
+            "Date" => lookup("Date").or_else(|| lookup("UTCDate")),
+            _ => lookup(name),
+        }
+        .unwrap_or(placeholder);
+        if name == "Result" {
+            result = value;
+        }
+        push_tag(&mut out, name, value);
+    }
+
+    for (name, value) in tags {
+        let Some(value) = value else {
+            continue;
+        };
+        let is_roster_tag = SEVEN_TAG_ROSTER
+            .iter()
+            .any(|(roster, _)| roster.eq_ignore_ascii_case(name));
+        if name.is_empty() || is_roster_tag {
+            continue;
+        }
+        push_tag(&mut out, name, value);
+    }
+
+    out.push('\n');
+
+    let mut tokens: Vec<&str> = movetext.split_whitespace().collect();
+    if !tokens.last().is_some_and(|token| is_result_marker(token)) {
+        tokens.push(result);
+    }
+    push_wrapped(&mut out, &tokens);
+
+    out
+}
+
+fn push_tag(out: &mut String, name: &str, value: &str) {
+    out.push('[');
+    out.push_str(name);
+    out.push_str(" \"");
+    for c in value.chars() {
+        match c {
+            '\\' | '"' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_control() => out.push(' '),
+            c => out.push(c),
+        }
+    }
+    out.push_str("\"]\n");
+}
+
+fn push_wrapped(out: &mut String, tokens: &[&str]) {
+    let mut line_len = 0;
+    for token in tokens {
+        let token_len = token.chars().count();
+        if line_len > 0 {
+            if line_len + 1 + token_len > PGN_MAX_LINE_LEN {
+                out.push('\n');
+                line_len = 0;
+            } else {
+                out.push(' ');
+                line_len += 1;
+            }
+        }
+        out.push_str(token);
+        line_len += token_len;
+    }
+    out.push('\n');
+}
+
+fn format_pgn_date(days: i32) -> Option<String> {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1)?;
+    let date = epoch.checked_add_signed(TimeDelta::try_days(days.into())?)?;
+    Some(date.format("%Y.%m.%d").to_string())
+}
+
+fn format_pgn_time(micros: i64) -> String {
+    let seconds = micros
+        .div_euclid(MICROS_PER_SECOND)
+        .rem_euclid(SECONDS_PER_DAY);
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    )
+}
+
+/// Format a packed `TIMETZ` value as a UTC `HH:MM:SS` PGN time.
+fn format_pgn_time_tz(bits: u64) -> String {
+    let micros = (bits >> 24) as i64;
+    let offset_seconds = TIME_TZ_OFFSET_SENTINEL_SECONDS - (bits & ((1 << 24) - 1)) as i64;
+    format_pgn_time(micros - offset_seconds * MICROS_PER_SECOND)
+}
+
+#[cfg(feature = "extension")]
+pub(crate) enum FieldRole {
+    Movetext,
+    Tag,
+    Skip,
+}
+
+/// One field of a game `STRUCT` argument, such as a whole `read_pgn` row.
+#[cfg(feature = "extension")]
+pub(crate) struct GameField {
+    pub(crate) name: String,
+    pub(crate) role: FieldRole,
+    type_id: LogicalTypeId,
+    vector: FlatVector,
+}
+
+#[cfg(feature = "extension")]
+impl GameField {
+    /// Classify the field `name` of a game struct; errors name `function`.
+    pub(crate) fn new(
+        name: String,
+        vector: FlatVector,
+        function: &str,
+    ) -> Result<Self, Box<dyn Error>> {
+        let type_id = vector.logical_type().id();
+        let role = if name.eq_ignore_ascii_case("movetext") {
+            FieldRole::Movetext
+        } else if NON_TAG_FIELDS.iter().any(|f| f.eq_ignore_ascii_case(&name)) {
+            FieldRole::Skip
+        } else {
+            match type_id {
+                LogicalTypeId::List
+                | LogicalTypeId::Struct
+                | LogicalTypeId::Map
+                | LogicalTypeId::Array
+                | LogicalTypeId::Union => FieldRole::Skip,
+                LogicalTypeId::Varchar
+                | LogicalTypeId::Boolean
+                | LogicalTypeId::Tinyint
+                | LogicalTypeId::Smallint
+                | LogicalTypeId::Integer
+                | LogicalTypeId::Bigint
+                | LogicalTypeId::UTinyint
+                | LogicalTypeId::USmallint
+                | LogicalTypeId::UInteger
+                | LogicalTypeId::UBigint
+                | LogicalTypeId::Date
+                | LogicalTypeId::Time
+                | LogicalTypeId::TimeTZ => FieldRole::Tag,
+                other => {
+                    return Err(format!(
+                        "{function}: unsupported type {other:?} for field '{name}'"
+                    )
+                    .into());
+                }
+            }
+        };
+
+        if matches!(role, FieldRole::Movetext) && type_id != LogicalTypeId::Varchar {
+            return Err(format!("{function}: field '{name}' must be VARCHAR").into());
+        }
+
+        Ok(Self {
+            name,
+            role,
+            type_id,
+            vector,
+        })
+    }
+
+    /// Read the value at `row` as PGN tag text.
+    ///
+    /// # Safety
+    /// `row` must be within the current chunk.
+    pub(crate) unsafe fn value(&self, row: usize) -> Option<String> {
+        if self.vector.row_is_null(row as u64) {
+            return None;
+        }
+
+        let v = &self.vector;
+        match self.type_id {
+            LogicalTypeId::Varchar => {
+                let s = &v.as_slice::<duckdb_string_t>()[row];
+                // SAFETY: Row nullability is checked above.
+                Some(unsafe { decode_duckdb_string(s) }.into_owned())
+            }
+            LogicalTypeId::Boolean => Some(v.as_slice::<bool>()[row].to_string()),
+            LogicalTypeId::Tinyint => Some(v.as_slice::<i8>()[row].to_string()),
+            LogicalTypeId::Smallint => Some(v.as_slice::<i16>()[row].to_string()),
+            LogicalTypeId::Integer => Some(v.as_slice::<i32>()[row].to_string()),
+            LogicalTypeId::Bigint => Some(v.as_slice::<i64>()[row].to_string()),
+            LogicalTypeId::UTinyint => Some(v.as_slice::<u8>()[row].to_string()),
+            LogicalTypeId::USmallint => Some(v.as_slice::<u16>()[row].to_string()),
+            LogicalTypeId::UInteger => Some(v.as_slice::<u32>()[row].to_string()),
+            LogicalTypeId::UBigint => Some(v.as_slice::<u64>()[row].to_string()),
+            LogicalTypeId::Date => format_pgn_date(v.as_slice::<i32>()[row]),
+            LogicalTypeId::Time => Some(format_pgn_time(v.as_slice::<i64>()[row])),
+            LogicalTypeId::TimeTZ => Some(format_pgn_time_tz(v.as_slice::<u64>()[row])),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessGamePgnScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessGamePgnScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let len = input.len();
+        let game_vec = input.flat_vector(0);
+        if game_vec.logical_type().id() != LogicalTypeId::Struct {
+            return Err("chess_game_pgn: expected a STRUCT argument (e.g. a read_pgn row)".into());
+        }
+
+        let game = input.struct_vector(0);
+        let fields = (0..game.num_children())
+            .map(|idx| {
+                let name = game.child_name(idx).to_string_lossy().into_owned();
+                GameField::new(name, game.child(idx, len), "chess_game_pgn")
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut output_vec = output.flat_vector();
+        let mut tags = Vec::with_capacity(fields.len());
+        for row in 0..len {
+            if game_vec.row_is_null(row as u64) {
+                output_vec.set_null(row);
+                continue;
+            }
+
+            tags.clear();
+            let mut movetext = None;
+            for field in &fields {
+                match field.role {
+                    FieldRole::Skip => {}
+                    // SAFETY: `row` is within the input chunk.
+                    FieldRole::Movetext => movetext = unsafe { field.value(row) },
+                    // SAFETY: `row` is within the input chunk.
+                    FieldRole::Tag => tags.push((field.name.clone(), unsafe { field.value(row) })),
+                }
+            }
+
+            let pgn = render_game_pgn(&tags, movetext.as_deref().unwrap_or_default());
+            try_insert_varchar(&output_vec, row, &pgn)?;
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Any)],
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(name: &str, value: Option<&str>) -> (String, Option<String>) {
+        (name.to_string(), value.map(str::to_string))
+    }
+
+    #[test]
+    fn test_render_game_pgn_seven_tag_roster_and_extra_tags() {
+        let tags = vec![
+            tag("White", Some("Alice")),
+            tag("Event", Some("Club")),
+            tag("Black", Some("Bob")),
+            tag("Result", Some("1-0")),
+            tag("WhiteElo", Some("1500")),
+            tag("BlackTitle", None),
+        ];
+
+        let pgn = render_game_pgn(&tags, "1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7#");
+        assert_eq!(
+            pgn,
+            "[Event \"Club\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+             [White \"Alice\"]\n[Black \"Bob\"]\n[Result \"1-0\"]\n[WhiteElo \"1500\"]\n\n\
+             1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n"
+        );
+    }
+
+    #[test]
+    fn test_render_game_pgn_escapes_tag_values() {
+        let tags = vec![tag("Event", Some(r#"The "Big" \ Open"#))];
+        let pgn = render_game_pgn(&tags, "");
+        assert!(pgn.starts_with("[Event \"The \\\"Big\\\" \\\\ Open\"]\n"));
+    }
+
+    #[test]
+    fn test_render_game_pgn_replaces_control_characters_in_tags() {
+        let tags = vec![tag("Site", Some("line1\nline2"))];
+        let pgn = render_game_pgn(&tags, "");
+        assert!(pgn.contains("[Site \"line1 line2\"]\n"));
+    }
+
+    #[test]
+    fn test_render_game_pgn_uses_utcdate_when_date_missing() {
+        let tags = vec![tag("UTCDate", Some("2024.01.15"))];
+        let pgn = render_game_pgn(&tags, "");
+        assert!(pgn.contains("[Date \"2024.01.15\"]\n"));
+        assert!(pgn.contains("[UTCDate \"2024.01.15\"]\n"));
+    }
+
+    #[test]
+    fn test_render_game_pgn_does_not_duplicate_result_marker() {
+        let tags = vec![tag("Result", Some("0-1"))];
+        let pgn = render_game_pgn(&tags, "1. f3 e5 2. g4 Qh4# 0-1");
+        assert!(pgn.ends_with("\n\n1. f3 e5 2. g4 Qh4# 0-1\n"));
+    }
+
+    #[test]
+    fn test_render_game_pgn_empty_movetext_emits_result() {
+        let pgn = render_game_pgn(&[], "");
+        assert!(pgn.ends_with("[Result \"*\"]\n\n*\n"));
+    }
+
+    #[test]
+    fn test_render_game_pgn_wraps_lines_below_80_chars() {
+        let movetext = "1. e4 { a fairly long comment that keeps going } e5 ".repeat(10);
+        let pgn = render_game_pgn(&[], &movetext);
+        let body = pgn.split("\n\n").nth(1).unwrap();
+
+        assert!(body.lines().count() > 1);
+        assert!(body.lines().all(|line| line.len() < 80));
+        assert_eq!(
+            body.split_whitespace().collect::<Vec<_>>(),
+            format!("{movetext} *")
+                .split_whitespace()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_format_pgn_date() {
+        assert_eq!(format_pgn_date(0).as_deref(), Some("1970.01.01"));
+        assert_eq!(format_pgn_date(19737).as_deref(), Some("2024.01.15"));
+        assert_eq!(format_pgn_date(-1).as_deref(), Some("1969.12.31"));
+    }
+
+    #[test]
+    fn test_format_pgn_time_tz_converts_to_utc() {
+        let pack = |micros: u64, offset_seconds: i64| {
+            let encoded = (TIME_TZ_OFFSET_SENTINEL_SECONDS - offset_seconds) as u64;
+            (micros << 24) | encoded
+        };
+
+        let noon = 12 * 3600 * 1_000_000;
+        assert_eq!(format_pgn_time_tz(pack(noon, 0)), "12:00:00");
+        assert_eq!(format_pgn_time_tz(pack(noon, 3600)), "11:00:00");
+        assert_eq!(format_pgn_time_tz(pack(0, 5400)), "22:30:00");
+    }
+}
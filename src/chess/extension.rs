@@ -0,0 +1,472 @@
+//! DuckDB extension entrypoint: registers table functions, scalars, and SQL macros.
+
+use super::accuracy::{ChessAccuracyScalar, ChessGameOpeningEvalScalar};
+use super::activity::ChessPieceActivityScalar;
+use super::clock::{ChessClockSeriesScalar, ChessGameDurationSecondsScalar};
+use super::compress::{ChessMovesCompressScalar, ChessMovesDecompressScalar};
+use super::control::ChessSquareControlScalar;
+use super::diagnostics::{ReadPgnLineDiagnosticsVTab, ScanDiagnostics};
+use super::draws::{
+    ChessDrawsClassificationScalar, ChessIsTheoreticalDrawScalar, ChessRepetitionCountScalar,
+};
+use super::elo::{
+    ChessEloBucketScalar, ChessEloHistogramScalar, ChessMatchOddsScalar,
+    ChessPerformanceRatingScalar, ChessUpsetScoreScalar, ChessWdlScalar,
+};
+use super::engine::ChessEngineCommentsScalar;
+use super::event::ChessEventTypeScalar;
+use super::export::ChessGamePgnScalar;
+use super::features::ChessFeaturesScalar;
+use super::fen::{ChessFenNormalizeScalar, ChessFenValidateScalar};
+use super::filter::{ChessMovesDropLastScalar, ChessMovesNormalizeScalar, ChessSanCanonicalScalar};
+use super::headers::{
+    ChessDateInferScalar, ChessHeadersCompleteScalar, ChessPgnHeadersScalar, ChessTagValidateScalar,
+};
+use super::language::ChessCommentLanguageScalar;
+use super::lichess_reader::ReadLichessNdjsonVTab;
+use super::mate::ChessHasMateInScalar;
+use super::moves::{
+    ChessFenEpdScalar, ChessLegalMovesScalar, ChessMovesHashScalar, ChessMovesJsonScalar,
+    ChessMovesSanFrequenciesScalar, ChessMovesSubsetScalar, ChessPlyCountScalar,
+    ChessSanIsLegalScalar,
+};
+use super::openings::{ChessEcoValidateScalar, ChessOpeningNameScalar, OpeningBooks};
+use super::parse_pgn::ParsePgnScalar;
+use super::perft::ChessPerftVTab;
+use super::pgncache_reader::{ReadPgnCacheVTab, WritePgnCacheVTab};
+use super::phase::ChessGamePhaseDurationsScalar;
+use super::positions::{
+    ChessFenHashScalar, ChessMovesPrefixHashesScalar, ChessPositionHashesScalar,
+};
+use super::puzzles::ChessPuzzleCandidatesScalar;
+use super::quality::ChessGameQualityScalar;
+use super::reader::ReadPgnVTab;
+use super::repertoire_reader::ChessOpeningColorsStatsVTab;
+use super::replay::{ChessReplayDroppedPliesScalar, ReplayCache};
+use super::settings::register_settings;
+use super::style::{ChessCastleSidesScalar, ChessMovesStyleScalar};
+use super::tablebase::{ChessTablebaseDtzScalar, ChessTablebaseWdlScalar};
+use super::tags_reader::PgnTagsVTab;
+use super::timecontrol::{
+    ChessTimecontrolCategoryScalar, ChessTimecontrolJsonScalar, ChessTimecontrolNormalizeScalar,
+    ChessTimecontrolSecondsForMoveScalar,
+};
+use super::tokens::ChessMovetextTokensDebugScalar;
+use super::version::DuckdbChessVersionVTab;
+use ::duckdb::{Connection, Result};
+use libduckdb_sys::{
+    duckdb_database, duckdb_extension_access, duckdb_extension_info, duckdb_rs_extension_api_init,
+};
+use std::error::Error;
+use std::ffi::CString;
+
+/// Oldest DuckDB C API version the extension is built against.
+const MINIMUM_API_VERSION: &str = "v1.2.0";
+
+/// C entrypoint called by DuckDB on `LOAD chess`.
+///
+/// Written by hand rather than generated by `duckdb_ext_macros::duckdb_extension` because
+/// registering config options needs the raw database handle, which `Connection` does not expose.
+///
+/// # Safety
+/// Must only be called by DuckDB's extension loader with valid `info` and `access` pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chess_init_c_api(
+    info: duckdb_extension_info,
+    access: *const duckdb_extension_access,
+) -> bool {
+    // SAFETY: DuckDB passes valid loader handles; see the function contract.
+    let result = unsafe { init_extension(info, access) };
+    if let Err(err) = result {
+        let message = CString::new(err.to_string())
+            .unwrap_or_else(|_| c"duckdb-chess failed to load".to_owned());
+        // SAFETY: `access` is valid for the duration of the load call.
+        unsafe {
+            if let Some(set_error) = (*access).set_error {
+                set_error(info, message.as_ptr());
+            }
+        }
+        return false;
+    }
+    true
+}
+
+unsafe fn init_extension(
+    info: duckdb_extension_info,
+    access: *const duckdb_extension_access,
+) -> Result<(), Box<dyn Error>> {
+    // SAFETY: Loader handles are valid per the `chess_init_c_api` contract; the database
+    // handle is owned by DuckDB and outlives this call.
+    unsafe {
+        duckdb_rs_extension_api_init(info, access, MINIMUM_API_VERSION)?;
+        let get_database = (*access).get_database.ok_or("Failed to get database")?;
+        let db: duckdb_database = *get_database(info);
+        register_settings(db)?;
+        extension_entrypoint(Connection::open_from_raw(db.cast())?)
+    }
+}
+
+fn extension_entrypoint(con: Connection) -> Result<(), Box<dyn Error>> {
+    // Table functions
+    // `read_pgn` scans record their line diagnostics where `read_pgn_line_diagnostics` finds
+    // them, separately for each database.
+    let scan_diagnostics = ScanDiagnostics::default();
+    con.register_table_function_with_extra_info::<ReadPgnVTab, _>("read_pgn", &scan_diagnostics)?;
+    con.register_table_function_with_extra_info::<ReadPgnLineDiagnosticsVTab, _>(
+        "read_pgn_line_diagnostics",
+        &scan_diagnostics,
+    )?;
+    con.register_table_function::<ReadLichessNdjsonVTab>("read_lichess_ndjson")?;
+    con.register_table_function::<PgnTagsVTab>("pgn_tags")?;
+    con.register_table_function::<WritePgnCacheVTab>("write_pgn_cache")?;
+    con.register_table_function::<ReadPgnCacheVTab>("read_pgn_cache")?;
+    con.register_table_function::<DuckdbChessVersionVTab>("duckdb_chess_version")?;
+    con.register_table_function::<ChessPerftVTab>("chess_perft")?;
+    con.register_table_function::<ChessOpeningColorsStatsVTab>("chess_opening_colors_stats")?;
+
+    // Scalar functions
+    // Scalars are registered under their public names and return NULL for NULL input. A
+    // `*_impl` name behind a SQL macro is only used for default arguments, session settings,
+    // aggregates, and the multi-tag scalars whose NULL tags count as missing.
+    con.register_scalar_function::<ChessMovesJsonScalar>("chess_moves_json_impl")?;
+    con.register_scalar_function::<ChessMovesNormalizeScalar>("chess_moves_normalize")?;
+    con.register_scalar_function::<ChessMovesDropLastScalar>("chess_moves_drop_last")?;
+    con.register_scalar_function::<ChessMovesCompressScalar>("chess_moves_compress")?;
+    con.register_scalar_function::<ChessMovesDecompressScalar>("chess_moves_decompress")?;
+    con.register_scalar_function::<ChessSanCanonicalScalar>("chess_san_canonical")?;
+    con.register_scalar_function::<ChessMovesHashScalar>("chess_moves_hash")?;
+    con.register_scalar_function::<ChessMovesSubsetScalar>("chess_moves_subset")?;
+    con.register_scalar_function::<ChessPositionHashesScalar>("chess_position_hashes")?;
+    con.register_scalar_function::<ChessMovesPrefixHashesScalar>("chess_moves_prefix_hashes")?;
+    con.register_scalar_function::<ChessFenHashScalar>("chess_fen_hash")?;
+    con.register_scalar_function::<ChessSquareControlScalar>("chess_square_control")?;
+    con.register_scalar_function::<ParsePgnScalar>("parse_pgn_impl")?;
+    con.register_scalar_function::<ChessFenEpdScalar>("chess_fen_epd")?;
+    con.register_scalar_function::<ChessFenValidateScalar>("chess_fen_validate")?;
+    con.register_scalar_function::<ChessFenNormalizeScalar>("chess_fen_normalize")?;
+    con.register_scalar_function::<ChessPlyCountScalar>("chess_ply_count")?;
+    con.register_scalar_function::<ChessMovesSanFrequenciesScalar>(
+        "chess_moves_san_frequencies_impl",
+    )?;
+    con.register_scalar_function::<ChessTimecontrolNormalizeScalar>(
+        "chess_timecontrol_normalize_impl",
+    )?;
+    con.register_scalar_function::<ChessTimecontrolJsonScalar>("chess_timecontrol_json")?;
+    con.register_scalar_function::<ChessTimecontrolCategoryScalar>("chess_timecontrol_category")?;
+    con.register_scalar_function::<ChessTimecontrolSecondsForMoveScalar>(
+        "chess_timecontrol_seconds_for_move",
+    )?;
+    con.register_scalar_function::<ChessClockSeriesScalar>("chess_clock_series")?;
+    con.register_scalar_function::<ChessGameDurationSecondsScalar>("chess_game_duration_seconds")?;
+    // Scalars that replay whole games share one cache, so calling several of them on the same
+    // movetext replays it once.
+    let replay_cache = ReplayCache::default();
+    con.register_scalar_function_with_state::<ChessGamePhaseDurationsScalar>(
+        "chess_game_phase_durations_impl",
+        &replay_cache,
+    )?;
+    con.register_scalar_function_with_state::<ChessDrawsClassificationScalar>(
+        "chess_draws_classification_impl",
+        &replay_cache,
+    )?;
+    con.register_scalar_function_with_state::<ChessIsTheoreticalDrawScalar>(
+        "chess_is_theoretical_draw_impl",
+        &replay_cache,
+    )?;
+    con.register_scalar_function_with_state::<ChessRepetitionCountScalar>(
+        "chess_repetition_count_impl",
+        &replay_cache,
+    )?;
+    con.register_scalar_function_with_state::<ChessFeaturesScalar>(
+        "chess_features_impl",
+        &replay_cache,
+    )?;
+    con.register_scalar_function_with_state::<ChessPuzzleCandidatesScalar>(
+        "chess_puzzle_candidates_impl",
+        &replay_cache,
+    )?;
+    con.register_scalar_function_with_state::<ChessMovesStyleScalar>(
+        "chess_moves_style_impl",
+        &replay_cache,
+    )?;
+    con.register_scalar_function_with_state::<ChessCastleSidesScalar>(
+        "chess_castle_sides_impl",
+        &replay_cache,
+    )?;
+    con.register_scalar_function_with_state::<ChessReplayDroppedPliesScalar>(
+        "chess_replay_dropped_plies",
+        &replay_cache,
+    )?;
+    con.register_scalar_function_with_state::<ChessGameQualityScalar>(
+        "chess_game_quality_impl",
+        &replay_cache,
+    )?;
+    con.register_scalar_function::<ChessPieceActivityScalar>("chess_piece_activity")?;
+    con.register_scalar_function::<ChessAccuracyScalar>("chess_accuracy")?;
+    con.register_scalar_function::<ChessGameOpeningEvalScalar>("chess_game_opening_eval_impl")?;
+    con.register_scalar_function::<ChessEngineCommentsScalar>("chess_engine_comments")?;
+    con.register_scalar_function::<ChessCommentLanguageScalar>("chess_comment_language")?;
+    con.register_scalar_function::<ChessTablebaseWdlScalar>("chess_tablebase_wdl")?;
+    con.register_scalar_function::<ChessTablebaseDtzScalar>("chess_tablebase_dtz")?;
+    con.register_scalar_function::<ChessHasMateInScalar>("chess_has_mate_in")?;
+    con.register_scalar_function::<ChessPgnHeadersScalar>("chess_pgn_headers")?;
+    con.register_scalar_function::<ChessTagValidateScalar>("chess_tag_validate")?;
+    con.register_scalar_function::<ChessHeadersCompleteScalar>("chess_headers_complete")?;
+    con.register_scalar_function::<ChessDateInferScalar>("chess_date_infer_impl")?;
+    con.register_scalar_function::<ChessEventTypeScalar>("chess_event_type_impl")?;
+    // Opening scalars share the loaded books, so each book is built once per database.
+    let opening_books = OpeningBooks::default();
+    con.register_scalar_function_with_state::<ChessOpeningNameScalar>(
+        "chess_opening_name_impl",
+        &opening_books,
+    )?;
+    con.register_scalar_function_with_state::<ChessEcoValidateScalar>(
+        "chess_eco_validate_impl",
+        &opening_books,
+    )?;
+    con.register_scalar_function::<ChessSanIsLegalScalar>("chess_san_is_legal")?;
+    con.register_scalar_function::<ChessLegalMovesScalar>("chess_legal_moves")?;
+    con.register_scalar_function::<ChessGamePgnScalar>("chess_game_pgn")?;
+    con.register_scalar_function::<ChessUpsetScoreScalar>("chess_upset_score_impl")?;
+    con.register_scalar_function::<ChessWdlScalar>("chess_wdl_impl")?;
+    con.register_scalar_function::<ChessEloBucketScalar>("chess_elo_bucket_impl")?;
+    con.register_scalar_function::<ChessEloHistogramScalar>("chess_elo_histogram_impl")?;
+    con.register_scalar_function::<ChessPerformanceRatingScalar>("chess_performance_rating")?;
+    con.register_scalar_function::<ChessMatchOddsScalar>("chess_match_odds")?;
+    con.register_scalar_function::<ChessMovetextTokensDebugScalar>("chess_movetext_tokens_debug")?;
+
+    // Aggregates are SQL macros wrapping DuckDB's built-in aggregates around per-row `*_impl`
+    // scalars, so they also work as window functions.
+    // `chess_event_type` and `chess_date_infer` pass NULL tags as '', which their scalars
+    // treat as missing: DuckDB folds a call with a literal NULL argument to NULL before the
+    // scalar runs. They return NULL when every tag is missing.
+    con.execute_batch(
+        "CREATE OR REPLACE MACRO chess_moves_json(movetext, max_ply := NULL) AS
+           chess_moves_json_impl(movetext, coalesce(max_ply, 9223372036854775807));
+         CREATE OR REPLACE MACRO chess_moves_san_frequencies(movetext, max_ply := NULL) AS
+           chess_moves_san_frequencies_impl(
+             coalesce(list(movetext), []), coalesce(max_ply, 9223372036854775807));
+         CREATE OR REPLACE MACRO chess_timecontrol_normalize(timecontrol) AS
+           chess_timecontrol_normalize_impl(timecontrol, current_setting('duckdb_chess_strict')),
+           (timecontrol, strict) AS chess_timecontrol_normalize_impl(timecontrol, strict);
+         CREATE OR REPLACE MACRO chess_opening_name(movetext) AS
+           chess_opening_name_impl(movetext, current_setting('duckdb_chess_opening_book'));
+         CREATE OR REPLACE MACRO chess_eco_validate(eco, movetext) AS
+           chess_eco_validate_impl(eco, movetext, current_setting('duckdb_chess_opening_book'));
+         CREATE OR REPLACE MACRO chess_game_opening_eval(movetext, opening_ply := 20) AS
+           chess_game_opening_eval_impl(movetext, opening_ply);
+         CREATE OR REPLACE MACRO chess_elo_bucket(elo, width := 100, min := 800, max := 2800) AS
+           chess_elo_bucket_impl(elo, width, min, max);
+         CREATE OR REPLACE MACRO chess_event_type(event, site, timecontrol) AS
+           chess_event_type_impl(coalesce(event, ''), coalesce(site, ''), coalesce(timecontrol, ''));
+         CREATE OR REPLACE MACRO chess_game_phase_durations(movetext, timecontrol,
+             on_illegal := 'null') AS
+           chess_game_phase_durations_impl(movetext, timecontrol, on_illegal);
+         CREATE OR REPLACE MACRO chess_draws_classification(movetext, result,
+             on_illegal := 'null') AS
+           chess_draws_classification_impl(movetext, result, '', on_illegal),
+           (movetext, result, termination, on_illegal := 'null') AS
+           chess_draws_classification_impl(movetext, result, coalesce(termination, ''),
+             on_illegal);
+         CREATE OR REPLACE MACRO chess_is_theoretical_draw(movetext, on_illegal := 'null') AS
+           chess_is_theoretical_draw_impl(movetext, on_illegal);
+         CREATE OR REPLACE MACRO chess_repetition_count(movetext, on_illegal := 'null') AS
+           chess_repetition_count_impl(movetext, on_illegal);
+         CREATE OR REPLACE MACRO chess_features(movetext, ply, on_illegal := 'null') AS
+           chess_features_impl(movetext, ply, on_illegal);
+         CREATE OR REPLACE MACRO chess_moves_style(movetext, on_illegal := 'null') AS
+           chess_moves_style_impl(movetext, on_illegal);
+         CREATE OR REPLACE MACRO chess_game_quality(movetext, on_illegal := 'null') AS
+           chess_game_quality_impl(movetext, on_illegal);
+         CREATE OR REPLACE MACRO chess_date_infer(utc_date, date, event_date) AS
+           chess_date_infer_impl(coalesce(utc_date, ''), coalesce(date, ''),
+             coalesce(event_date, ''));
+         CREATE OR REPLACE MACRO chess_upset_score(white_elo, black_elo, result) AS
+           sum(chess_upset_score_impl(white_elo, black_elo, result));
+         CREATE OR REPLACE MACRO chess_elo_histogram(elo, width := 100, min := 800, max := 2800) AS
+           chess_elo_histogram_impl(coalesce(list(CAST(elo AS BIGINT)), []), width, min, max);
+         CREATE OR REPLACE MACRO chess_wdl(result, perspective_color) AS
+           struct_pack(
+             wins := count_if(chess_wdl_impl(result, perspective_color) = 1.0),
+             draws := count_if(chess_wdl_impl(result, perspective_color) = 0.5),
+             losses := count_if(chess_wdl_impl(result, perspective_color) = 0.0),
+             score := avg(chess_wdl_impl(result, perspective_color))
+           );
+         CREATE OR REPLACE MACRO chess_counts_by_result(result) AS
+           struct_pack(
+             games := count(*),
+             white_wins := count_if(chess_wdl_impl(result, 'white') = 1.0),
+             draws := count_if(chess_wdl_impl(result, 'white') = 0.5),
+             black_wins := count_if(chess_wdl_impl(result, 'white') = 0.0),
+             unfinished := count(*) - count(chess_wdl_impl(result, 'white'))
+           );
+         CREATE OR REPLACE MACRO chess_head_to_head_color_impl(white, black, player_a, player_b) AS
+           CASE
+             WHEN white = player_a AND black = player_b THEN 'white'
+             WHEN white = player_b AND black = player_a THEN 'black'
+           END;
+         CREATE OR REPLACE MACRO chess_head_to_head(white, black, result, player_a, player_b) AS
+           struct_pack(
+             games := count(chess_head_to_head_color_impl(white, black, player_a, player_b)),
+             a_wins := count_if(chess_wdl_impl(result,
+               chess_head_to_head_color_impl(white, black, player_a, player_b)) = 1.0),
+             draws := count_if(chess_wdl_impl(result,
+               chess_head_to_head_color_impl(white, black, player_a, player_b)) = 0.5),
+             b_wins := count_if(chess_wdl_impl(result,
+               chess_head_to_head_color_impl(white, black, player_a, player_b)) = 0.0),
+             a_score := coalesce(sum(chess_wdl_impl(result,
+               chess_head_to_head_color_impl(white, black, player_a, player_b))), 0),
+             b_score := coalesce(sum(1.0 - chess_wdl_impl(result,
+               chess_head_to_head_color_impl(white, black, player_a, player_b))), 0),
+             a_white_games := count_if(
+               chess_head_to_head_color_impl(white, black, player_a, player_b) = 'white'),
+             a_white_score := coalesce(sum(chess_wdl_impl(result, 'white')) FILTER (
+               WHERE chess_head_to_head_color_impl(white, black, player_a, player_b) = 'white'), 0),
+             a_black_games := count_if(
+               chess_head_to_head_color_impl(white, black, player_a, player_b) = 'black'),
+             a_black_score := coalesce(sum(chess_wdl_impl(result, 'black')) FILTER (
+               WHERE chess_head_to_head_color_impl(white, black, player_a, player_b) = 'black'), 0)
+           );
+         CREATE OR REPLACE MACRO chess_player_color_impl(white, black, player) AS
+           CASE WHEN white = player THEN 'white' WHEN black = player THEN 'black' END;
+         CREATE OR REPLACE MACRO chess_player_top_eco_impl(counts) AS
+           list_sort(list_transform(map_entries(counts),
+             e -> struct_pack(n := -CAST(e.value AS BIGINT), eco := e.key)))[1].eco;
+         CREATE OR REPLACE MACRO chess_player_win_streak_impl(outcomes) AS
+           coalesce(list_max(list_transform(string_split_regex(outcomes, '[DL]'),
+             s -> length(s))), 0);
+         CREATE OR REPLACE MACRO chess_player_summary(player, white, black, result, white_elo,
+             black_elo, eco, played_at) AS
+           struct_pack(
+             games := count(chess_player_color_impl(white, black, player)),
+             score := coalesce(sum(chess_wdl_impl(result,
+               chess_player_color_impl(white, black, player))), 0),
+             white_score := coalesce(sum(chess_wdl_impl(result, 'white')) FILTER (
+               WHERE chess_player_color_impl(white, black, player) = 'white'), 0),
+             black_score := coalesce(sum(chess_wdl_impl(result, 'black')) FILTER (
+               WHERE chess_player_color_impl(white, black, player) = 'black'), 0),
+             avg_opponent_elo := avg(CASE chess_player_color_impl(white, black, player)
+               WHEN 'white' THEN black_elo WHEN 'black' THEN white_elo END),
+             most_played_eco := chess_player_top_eco_impl(histogram(eco) FILTER (
+               WHERE chess_player_color_impl(white, black, player) IS NOT NULL)),
+             longest_win_streak := chess_player_win_streak_impl(string_agg(
+               CASE chess_wdl_impl(result, chess_player_color_impl(white, black, player))
+                 WHEN 1.0 THEN 'W' WHEN 0.5 THEN 'D' WHEN 0.0 THEN 'L' END,
+               '' ORDER BY played_at))
+           );
+         CREATE OR REPLACE MACRO chess_opening_success_row_impl(opening, games, wins, draws) AS
+           struct_pack(
+             opening := opening,
+             games := games,
+             win_pct := 100.0 * wins / games,
+             draw_pct := 100.0 * draws / games,
+             loss_pct := 100.0 * (games - wins - draws) / games
+           );
+         CREATE OR REPLACE MACRO chess_opening_success_count_impl(counts, opening) AS
+           coalesce(map_values(counts)[list_position(map_keys(counts), opening)], 0);
+         CREATE OR REPLACE MACRO chess_opening_success_matrix_impl(games, wins, draws) AS
+           coalesce(list_transform(map_entries(games), e -> chess_opening_success_row_impl(
+             e.key, e.value,
+             chess_opening_success_count_impl(wins, e.key),
+             chess_opening_success_count_impl(draws, e.key))), []);
+         CREATE OR REPLACE MACRO chess_opening_success_matrix(opening, result, perspective_color) AS
+           chess_opening_success_matrix_impl(
+             histogram(opening) FILTER (
+               WHERE chess_wdl_impl(result, perspective_color) IS NOT NULL),
+             histogram(opening) FILTER (WHERE chess_wdl_impl(result, perspective_color) = 1.0),
+             histogram(opening) FILTER (WHERE chess_wdl_impl(result, perspective_color) = 0.5));
+         CREATE OR REPLACE MACRO chess_castle_side_row_impl(matchup, games, white_wins, draws) AS
+           struct_pack(
+             white_castle := split_part(matchup, ' vs ', 1),
+             black_castle := split_part(matchup, ' vs ', 2),
+             games := games,
+             white_wins := white_wins,
+             draws := draws,
+             black_wins := games - white_wins - draws,
+             white_score := (white_wins + 0.5 * draws) / games
+           );
+         CREATE OR REPLACE MACRO chess_castle_side_results_impl(games, white_wins, draws) AS
+           coalesce(list_transform(map_entries(games), e -> chess_castle_side_row_impl(
+             e.key, e.value,
+             chess_opening_success_count_impl(white_wins, e.key),
+             chess_opening_success_count_impl(draws, e.key))), []);
+         CREATE OR REPLACE MACRO chess_castle_side_results(movetext, result,
+             on_illegal := 'null') AS
+           chess_castle_side_results_impl(
+             histogram(chess_castle_sides_impl(movetext, on_illegal)) FILTER (
+               WHERE chess_wdl_impl(result, 'white') IS NOT NULL),
+             histogram(chess_castle_sides_impl(movetext, on_illegal)) FILTER (
+               WHERE chess_wdl_impl(result, 'white') = 1.0),
+             histogram(chess_castle_sides_impl(movetext, on_illegal)) FILTER (
+               WHERE chess_wdl_impl(result, 'white') = 0.5));",
+    )?;
+
+    // Table functions cannot consume another query's rows, so functions over a games table are
+    // table macros that unnest per-row scalar output from `query_table(...)`.
+    con.execute_batch(
+        "CREATE OR REPLACE MACRO chess_position_index(games) AS TABLE
+           SELECT pos.position_hash, game_id, pos.ply
+           FROM (
+             SELECT game_id, unnest(chess_position_hashes(movetext)) AS pos
+             FROM query_table(games)
+           );
+         CREATE OR REPLACE MACRO chess_puzzle_candidates(games, on_illegal := 'null') AS TABLE
+           SELECT game_id, CAST(c.ply AS BIGINT) AS ply, c.fen, c.best_line,
+             string_split(c.themes, ' ') AS themes
+           FROM (
+             SELECT game_id, unnest(chess_puzzle_candidates_impl(movetext, on_illegal)) AS c
+             FROM query_table(games)
+           );
+         CREATE OR REPLACE MACRO chess_timecontrol_profile(games) AS TABLE
+           SELECT raw,
+             CASE
+               WHEN raw IS NULL OR trim(raw) = '' THEN 'missing'
+               WHEN tc->>'normalized' IS NULL THEN 'invalid'
+               WHEN (tc->>'inferred')::BOOLEAN THEN 'inferred'
+               WHEN json_array_length(tc->'warnings') > 0 THEN 'repaired'
+               ELSE 'valid'
+             END AS outcome,
+             tc->>'normalized' AS normalized,
+             tc->>'category' AS category,
+             count
+           FROM (
+             SELECT raw, chess_timecontrol_json(raw)::JSON AS tc, count
+             FROM (
+               SELECT TimeControl AS raw, count(*) AS count
+               FROM query_table(games)
+               GROUP BY ALL
+             )
+           )
+           ORDER BY count DESC, raw NULLS LAST;
+         CREATE OR REPLACE MACRO chess_timecontrol_locale_report(games) AS TABLE
+           SELECT raw,
+             tc->>'stage' AS stage,
+             tc->>'rule' AS rule,
+             from_json(tc->'warnings', '[\"VARCHAR\"]') AS warnings,
+             tc->>'normalized' AS normalized,
+             (tc->>'confidence')::DOUBLE AS confidence,
+             count
+           FROM (
+             SELECT raw, chess_timecontrol_json(raw)::JSON AS tc, count
+             FROM (
+               SELECT TimeControl AS raw, count(*) AS count
+               FROM query_table(games)
+               WHERE TimeControl IS NOT NULL
+               GROUP BY ALL
+             )
+           )
+           ORDER BY count DESC, raw;
+         CREATE OR REPLACE MACRO parse_pgn(games) AS TABLE
+           SELECT * EXCLUDE (pgn, parsed_game), parsed_game.*
+           FROM (
+             SELECT *, unnest(parse_pgn_impl(pgn)) AS parsed_game
+             FROM query_table(games)
+           );
+         CREATE OR REPLACE MACRO read_pgn_new_games(path_pattern, ingested) AS TABLE
+           SELECT g.*
+           FROM read_pgn(path_pattern, game_id := true) g
+           ANTI JOIN query_table(ingested) s ON g.game_id = s.game_id;",
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,267 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{BigintInput, VarcharInput, invoke_binary_to_f32_list_nullable};
+#[cfg(feature = "extension")]
+use super::replay::{ReplayCache, on_illegal_arg, on_illegal_signatures};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use shakmaty::{Bitboard, Board, Chess, Color, File, Position, Role, Square, attacks};
+#[cfg(feature = "extension")]
+use std::error::Error;
+
+/// Names of the `chess_features` elements, in list order. Per-side features come in
+/// white/black pairs.
+pub const FEATURE_NAMES: [&str; 25] = [
+    "white_to_move",
+    "white_pawns",
+    "white_knights",
+    "white_bishops",
+    "white_rooks",
+    "white_queens",
+    "black_pawns",
+    "black_knights",
+    "black_bishops",
+    "black_rooks",
+    "black_queens",
+    "white_mobility",
+    "black_mobility",
+    "white_pawn_shield",
+    "black_pawn_shield",
+    "white_king_zone_attacked",
+    "black_king_zone_attacked",
+    "white_in_check",
+    "black_in_check",
+    "white_doubled_pawns",
+    "black_doubled_pawns",
+    "white_isolated_pawns",
+    "black_isolated_pawns",
+    "white_passed_pawns",
+    "black_passed_pawns",
+];
+
+const COLORS: [Color; 2] = [Color::White, Color::Black];
+const MATERIAL_ROLES: [Role; 5] = [
+    Role::Pawn,
+    Role::Knight,
+    Role::Bishop,
+    Role::Rook,
+    Role::Queen,
+];
+
+/// Handcrafted features of a position, in [`FEATURE_NAMES`] order.
+///
+/// Mobility counts the pseudo-legal target squares of knights, bishops, rooks and queens, so it
+/// ignores pins and is defined for the side not to move. The pawn shield counts own pawns one
+/// or two ranks in front of the king on its file and the adjacent files. The king zone is the
+/// king's square and its neighbours; a zone square counts once however many enemy pieces
+/// attack it.
+pub fn position_features(pos: &Chess) -> [f32; FEATURE_NAMES.len()] {
+    let board = pos.board();
+    let mut features = Vec::with_capacity(FEATURE_NAMES.len());
+    features.push(f32::from(u8::from(pos.turn() == Color::White)));
+    for color in COLORS {
+        features.extend(
+            MATERIAL_ROLES.map(|role| (board.by_role(role) & board.by_color(color)).count() as f32),
+        );
+    }
+    let per_side: [fn(&Board, Color) -> usize; 7] = [
+        mobility,
+        pawn_shield,
+        king_zone_attacked,
+        |board, color| usize::from(in_check(board, color)),
+        doubled_pawns,
+        isolated_pawns,
+        passed_pawns,
+    ];
+    for feature in per_side {
+        features.extend(COLORS.map(|color| feature(board, color) as f32));
+    }
+    features.try_into().expect("one value per feature name")
+}
+
+fn mobility(board: &Board, color: Color) -> usize {
+    let pieces = board.by_color(color) & !board.pawns() & !board.kings();
+    pieces
+        .into_iter()
+        .map(|square| (board.attacks_from(square) & !board.by_color(color)).count())
+        .sum()
+}
+
+fn pawns(board: &Board, color: Color) -> Bitboard {
+    board.pawns() & board.by_color(color)
+}
+
+/// Ranks `square` is ahead of `from`, seen from `color`'s side of the board.
+fn ranks_ahead(color: Color, from: Square, square: Square) -> i32 {
+    let delta = i32::from(square.rank()) - i32::from(from.rank());
+    color.fold_wb(delta, -delta)
+}
+
+fn file_distance(a: Square, b: Square) -> u32 {
+    i32::from(a.file()).abs_diff(i32::from(b.file()))
+}
+
+fn pawn_shield(board: &Board, color: Color) -> usize {
+    let Some(king) = board.king_of(color) else {
+        return 0;
+    };
+    pawns(board, color)
+        .into_iter()
+        .filter(|&pawn| {
+            file_distance(king, pawn) <= 1 && (1..=2).contains(&ranks_ahead(color, king, pawn))
+        })
+        .count()
+}
+
+fn king_zone_attacked(board: &Board, color: Color) -> usize {
+    let Some(king) = board.king_of(color) else {
+        return 0;
+    };
+    let zone = attacks::king_attacks(king).with(king);
+    zone.into_iter()
+        .filter(|&square| board.attacks_to(square, !color, board.occupied()).any())
+        .count()
+}
+
+fn in_check(board: &Board, color: Color) -> bool {
+    board
+        .king_of(color)
+        .is_some_and(|king| board.attacks_to(king, !color, board.occupied()).any())
+}
+
+/// Pawns beyond the first on each file.
+fn doubled_pawns(board: &Board, color: Color) -> usize {
+    let pawns = pawns(board, color);
+    File::ALL
+        .into_iter()
+        .map(|file| {
+            (pawns & Bitboard::from_file(file))
+                .count()
+                .saturating_sub(1)
+        })
+        .sum()
+}
+
+/// Pawns with no pawn of the same color on an adjacent file.
+fn isolated_pawns(board: &Board, color: Color) -> usize {
+    let pawns = pawns(board, color);
+    pawns
+        .into_iter()
+        .filter(|&pawn| {
+            !pawns
+                .into_iter()
+                .any(|other| file_distance(pawn, other) == 1)
+        })
+        .count()
+}
+
+/// Pawns with no enemy pawn ahead of them on their file or an adjacent file.
+fn passed_pawns(board: &Board, color: Color) -> usize {
+    let enemy = pawns(board, !color);
+    pawns(board, color)
+        .into_iter()
+        .filter(|&pawn| {
+            !enemy
+                .into_iter()
+                .any(|other| file_distance(pawn, other) <= 1 && ranks_ahead(color, pawn, other) > 0)
+        })
+        .count()
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessFeaturesScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessFeaturesScalar {
+    type State = ReplayCache;
+
+    unsafe fn invoke(
+        cache: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let on_illegal = on_illegal_arg(input, 2)?;
+        invoke_binary_to_f32_list_nullable::<VarcharInput, BigintInput, _, { FEATURE_NAMES.len() }>(
+            input,
+            output,
+            |movetext, ply| {
+                let ply = usize::try_from(ply).map_err(|_| {
+                    format!("chess_features: invalid ply {ply}. Expected a non-negative ply.")
+                })?;
+                let Some(game) = cache
+                    .replay_with(movetext, on_illegal)
+                    .map_err(|e| format!("chess_features: {e}"))?
+                else {
+                    return Ok(None);
+                };
+                Ok(game.positions.get(ply).map(position_features))
+            },
+        )
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        on_illegal_signatures(
+            || {
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Bigint),
+                ]
+            },
+            || LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Float)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shakmaty::{CastlingMode, fen::Fen};
+
+    fn features(fen: &str) -> [f32; FEATURE_NAMES.len()] {
+        let fen: Fen = fen.parse().unwrap();
+        position_features(&fen.into_position(CastlingMode::Standard).unwrap())
+    }
+
+    fn feature(values: &[f32], name: &str) -> f32 {
+        values[FEATURE_NAMES.iter().position(|n| *n == name).unwrap()]
+    }
+
+    #[test]
+    fn test_position_features_start_position() {
+        let values = position_features(&Chess::default());
+        assert_eq!(
+            values,
+            [
+                1.0, 8.0, 2.0, 2.0, 2.0, 1.0, 8.0, 2.0, 2.0, 2.0, 1.0, 4.0, 4.0, 3.0, 3.0, 0.0,
+                0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_position_features_king_safety() {
+        // Scholar's mate: the black king is in check and the queen and bishop attack e8, f8, d7, e7 and f7 in its zone.
+        let values = features("r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4");
+        assert_eq!(feature(&values, "white_to_move"), 0.0);
+        assert_eq!(feature(&values, "black_in_check"), 1.0);
+        assert_eq!(feature(&values, "white_in_check"), 0.0);
+        assert_eq!(feature(&values, "black_king_zone_attacked"), 5.0);
+        assert_eq!(feature(&values, "black_pawn_shield"), 1.0);
+        assert_eq!(feature(&values, "white_queens"), 1.0);
+    }
+
+    #[test]
+    fn test_position_features_pawn_structure() {
+        // White: doubled c-pawns, every pawn isolated and passed. Black: h-pawn only.
+        let values = features("4k3/7p/8/P7/2P5/2P5/8/4K3 w - - 0 1");
+        assert_eq!(feature(&values, "white_doubled_pawns"), 1.0);
+        assert_eq!(feature(&values, "white_isolated_pawns"), 3.0);
+        assert_eq!(feature(&values, "white_passed_pawns"), 3.0);
+        assert_eq!(feature(&values, "black_doubled_pawns"), 0.0);
+        assert_eq!(feature(&values, "black_isolated_pawns"), 1.0);
+        assert_eq!(feature(&values, "black_passed_pawns"), 1.0);
+    }
+}
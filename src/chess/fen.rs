@@ -0,0 +1,229 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{VarcharOutput, invoke_unary_varchar_to_varchar};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use shakmaty::{CastlingMode, Chess, EnPassantMode, PositionError, fen::Fen};
+#[cfg(feature = "extension")]
+use std::error::Error;
+
+const FEN_FIELD_COUNT: usize = 6;
+const CASTLING_ORDER: &str = "KQkq";
+
+/// Describe why `fen` is not a complete, legal standard-chess FEN; `None` when it is valid.
+///
+/// Stricter than the parser used elsewhere: all six fields are required, the fullmove number
+/// must be positive and castling rights must be written in `KQkq` order.
+pub fn fen_validate(fen: &str) -> Option<String> {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    if fields.is_empty() {
+        return Some("empty fen".to_string());
+    }
+    if fields.len() != FEN_FIELD_COUNT {
+        return Some(format!(
+            "expected {FEN_FIELD_COUNT} space-separated fields, got {}",
+            fields.len()
+        ));
+    }
+
+    let parsed: Fen = match fields.join(" ").parse() {
+        Ok(parsed) => parsed,
+        Err(err) => return Some(err.to_string()),
+    };
+    if fields[5]
+        .parse::<u32>()
+        .is_ok_and(|fullmoves| fullmoves == 0)
+    {
+        return Some("fullmove number must be at least 1".to_string());
+    }
+    let castling = fields[2];
+    if castling != "-" && castling != canonical_castling(castling) {
+        return Some(format!(
+            "castling field '{castling}' is not in canonical {CASTLING_ORDER} order"
+        ));
+    }
+
+    parsed
+        .into_position::<Chess>(CastlingMode::Standard)
+        .err()
+        .map(|err| err.to_string())
+}
+
+/// Rewrite `fen` into canonical form, or `None` when it cannot be repaired.
+///
+/// Missing halfmove/fullmove counters default to `0 1`, castling rights without a matching
+/// king and rook are dropped (the rest are written in `KQkq` order), and the en passant square
+/// is kept only when an en passant capture is actually legal.
+pub fn fen_normalize(fen: &str) -> Option<String> {
+    let fen = fen.trim();
+    if fen.is_empty() {
+        return None;
+    }
+
+    let parsed: Fen = fen.parse().ok()?;
+    let position: Chess = parsed
+        .into_position(CastlingMode::Standard)
+        .or_else(PositionError::ignore_invalid_castling_rights)
+        .or_else(PositionError::ignore_invalid_ep_square)
+        .ok()?;
+    Some(Fen::from_position(&position, EnPassantMode::Legal).to_string())
+}
+
+/// `KQkq`-ordered, de-duplicated castling rights; unknown characters are kept at the end so
+/// they still fail the comparison in `fen_validate`.
+fn canonical_castling(castling: &str) -> String {
+    let mut canonical: String = CASTLING_ORDER
+        .chars()
+        .filter(|right| castling.contains(*right))
+        .collect();
+    canonical.extend(castling.chars().filter(|c| !CASTLING_ORDER.contains(*c)));
+    canonical
+}
+
+// Spec: move-analysis - FEN Validation
+#[cfg(feature = "extension")]
+pub struct ChessFenValidateScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessFenValidateScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_unary_varchar_to_varchar(input, output, |fen| {
+            Ok(match fen_validate(fen) {
+                Some(problem) => VarcharOutput::Value(problem),
+                None => VarcharOutput::Null,
+            })
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )]
+    }
+}
+
+// Spec: move-analysis - FEN Normalization
+#[cfg(feature = "extension")]
+pub struct ChessFenNormalizeScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessFenNormalizeScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_unary_varchar_to_varchar(input, output, |fen| {
+            Ok(match fen_normalize(fen) {
+                Some(normalized) => VarcharOutput::Value(normalized),
+                None => VarcharOutput::Null,
+            })
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test]
+    fn test_fen_validate_accepts_valid_fens() {
+        assert_eq!(fen_validate(START_FEN), None);
+        assert_eq!(
+            fen_validate("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            None
+        );
+        assert_eq!(fen_validate("  8/8/8/8/8/8/8/K6k w - - 12 80 "), None);
+    }
+
+    #[test]
+    fn test_fen_validate_reports_problems() {
+        assert_eq!(fen_validate("").as_deref(), Some("empty fen"));
+        assert_eq!(
+            fen_validate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -").as_deref(),
+            Some("expected 6 space-separated fields, got 4")
+        );
+        assert_eq!(
+            fen_validate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1").as_deref(),
+            Some("invalid board part in fen")
+        );
+        assert_eq!(
+            fen_validate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0").as_deref(),
+            Some("fullmove number must be at least 1")
+        );
+        assert_eq!(
+            fen_validate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w qkQK - 0 1").as_deref(),
+            Some("castling field 'qkQK' is not in canonical KQkq order")
+        );
+        assert_eq!(
+            fen_validate("8/8/8/8/8/8/8/K6k w KQ - 0 1").as_deref(),
+            Some("illegal position: invalid castling rights")
+        );
+        assert_eq!(
+            fen_validate("8/8/8/8/8/8/8/7k w - - 0 1").as_deref(),
+            Some("illegal position: missing king")
+        );
+    }
+
+    #[test]
+    fn test_fen_normalize_fills_missing_counters() {
+        assert_eq!(
+            fen_normalize("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -").as_deref(),
+            Some(START_FEN)
+        );
+        assert_eq!(fen_normalize(START_FEN).as_deref(), Some(START_FEN));
+    }
+
+    #[test]
+    fn test_fen_normalize_repairs_castling_and_en_passant() {
+        // No black pawn can capture on e3, so the en passant square is dropped.
+        assert_eq!(
+            fen_normalize("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").as_deref(),
+            Some("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+        );
+        // A legal en passant capture keeps the square.
+        assert_eq!(
+            fen_normalize("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+                .as_deref(),
+            Some("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+        );
+        // Castling rights without king/rook are dropped; the rest are reordered.
+        assert_eq!(
+            fen_normalize("r3k3/8/8/8/8/8/8/4K2R w kqKQ - 0 1").as_deref(),
+            Some("r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1")
+        );
+        // An impossible en passant square is dropped instead of rejecting the position.
+        assert_eq!(
+            fen_normalize("8/8/8/8/8/8/8/K6k w - e6 0 1").as_deref(),
+            Some("8/8/8/8/8/8/8/K6k w - - 0 1")
+        );
+    }
+
+    #[test]
+    fn test_fen_normalize_rejects_unrepairable_fens() {
+        assert_eq!(fen_normalize(""), None);
+        assert_eq!(fen_normalize("not a fen"), None);
+        assert_eq!(fen_normalize("8/8/8/8/8/8/8/7k w - - 0 1"), None);
+    }
+}
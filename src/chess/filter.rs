@@ -1,18 +1,23 @@
+#[cfg(feature = "extension")]
 use super::duckdb_impl::scalar::{
-    VarcharNullBehavior, VarcharOutput, invoke_unary_varchar_to_varchar,
+    BigintInput, VarcharInput, VarcharOutput, invoke_binary_nullable,
+    invoke_unary_varchar_to_varchar,
 };
+#[cfg(feature = "extension")]
 use duckdb::{
     core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
     vscalar::{ScalarFunctionSignature, VScalar},
     vtab::arrow::WritableVector,
 };
 use smallvec::SmallVec;
+#[cfg(feature = "extension")]
 use std::error::Error;
 use std::fmt::Write;
 use std::io;
 use std::ops::ControlFlow;
 
 use pgn_reader::{Nag, Outcome, RawComment, Reader, SanPlus, Skip, Visitor};
+use shakmaty::Chess;
 
 use crate::pgn_visitor_skip_variations;
 
@@ -35,6 +40,26 @@ pub fn normalize_movetext(movetext: &str) -> String {
     }
 }
 
+/// Replay the mainline and rewrite every SAN in canonical form: minimal correct
+/// disambiguation and recomputed check/mate suffixes. Output uses the same layout as
+/// [`normalize_movetext`].
+///
+/// Returns `None` when the movetext cannot be parsed or contains an illegal move.
+/// Spec: move-analysis - SAN Canonicalization
+pub fn canonicalize_san_movetext(movetext: &str) -> Option<String> {
+    if movetext.trim().is_empty() {
+        return Some(String::new());
+    }
+
+    let mut reader = Reader::new(io::Cursor::new(movetext.as_bytes()));
+    let mut visitor = CanonicalSanVisitor::default();
+
+    match reader.read_game(&mut visitor) {
+        Ok(Some(())) if !visitor.illegal => Some(visitor.output),
+        _ => None,
+    }
+}
+
 pub(crate) struct ParsedMovetext {
     pub sans: MoveList,
     pub outcome: Option<String>,
@@ -72,6 +97,41 @@ pub(crate) fn parse_movetext_mainline(movetext: &str) -> ParsedMovetext {
     }
 }
 
+/// Append the move at 0-based `ply` to `output`, numbering White's moves (`1. e4 e5 2. Nf3`).
+pub(crate) fn push_numbered_move(output: &mut String, ply: usize, san: impl std::fmt::Display) {
+    if ply.is_multiple_of(2) {
+        if !output.is_empty() {
+            output.push(' ');
+        }
+        let _ = write!(output, "{}. ", ply / 2 + 1);
+    } else {
+        output.push(' ');
+    }
+    let _ = write!(output, "{san}");
+}
+
+/// Mainline of `movetext` without its last `n` plies, in the [`normalize_movetext`] layout and
+/// without a result marker, since the shortened game is unfinished.
+/// Spec: move-analysis - Drop Last Plies
+///
+/// Dropping at least as many plies as the game has yields an empty string. Returns `None` when
+/// the movetext cannot be parsed.
+pub fn drop_last_plies(movetext: &str, n: u64) -> Option<String> {
+    let parsed = parse_movetext_mainline(movetext);
+    if parsed.parse_error {
+        return None;
+    }
+    let keep = parsed
+        .sans
+        .len()
+        .saturating_sub(usize::try_from(n).unwrap_or(usize::MAX));
+    let mut output = String::new();
+    for (ply, san) in parsed.sans[..keep].iter().enumerate() {
+        push_numbered_move(&mut output, ply, san);
+    }
+    Some(output)
+}
+
 #[derive(Default)]
 struct NormalizeSerializeVisitor {
     output: String,
@@ -100,18 +160,82 @@ impl Visitor for NormalizeSerializeVisitor {
         _movetext: &mut Self::Movetext,
         san_plus: SanPlus,
     ) -> ControlFlow<Self::Output> {
+        push_numbered_move(&mut self.output, self.move_count, san_plus);
+        self.move_count += 1;
+        ControlFlow::Continue(())
+    }
+
+    pgn_visitor_skip_variations!();
+
+    fn outcome(
+        &mut self,
+        _movetext: &mut Self::Movetext,
+        outcome: Outcome,
+    ) -> ControlFlow<Self::Output> {
+        self.outcome = Some(outcome.to_string());
+        ControlFlow::Continue(())
+    }
+
+    fn end_game(&mut self, _movetext: Self::Movetext) -> Self::Output {
+        if let Some(outcome) = self.outcome.take() {
+            if !self.output.is_empty() {
+                self.output.push(' ');
+            }
+            self.output.push_str(&outcome);
+        }
+    }
+}
+
+#[derive(Default)]
+struct CanonicalSanVisitor {
+    output: String,
+    position: Chess,
+    move_count: usize,
+    outcome: Option<String>,
+    illegal: bool,
+}
+
+impl Visitor for CanonicalSanVisitor {
+    type Tags = ();
+    type Movetext = ();
+    type Output = ();
+
+    fn begin_tags(&mut self) -> ControlFlow<Self::Output, Self::Tags> {
+        self.output.clear();
+        self.position = Chess::default();
+        self.move_count = 0;
+        self.outcome = None;
+        self.illegal = false;
+        ControlFlow::Continue(())
+    }
+
+    fn begin_movetext(&mut self, _tags: Self::Tags) -> ControlFlow<Self::Output, Self::Movetext> {
+        ControlFlow::Continue(())
+    }
+
+    fn san(
+        &mut self,
+        _movetext: &mut Self::Movetext,
+        san_plus: SanPlus,
+    ) -> ControlFlow<Self::Output> {
+        let Ok(m) = san_plus.san.to_move(&self.position) else {
+            self.illegal = true;
+            return ControlFlow::Break(());
+        };
+
         if self.move_count.is_multiple_of(2) {
             if !self.output.is_empty() {
                 self.output.push(' ');
             }
-            let move_no = (self.move_count / 2) + 1;
-            let _ = write!(self.output, "{}.", move_no);
-            self.output.push(' ');
+            let _ = write!(self.output, "{}. ", (self.move_count / 2) + 1);
         } else {
             self.output.push(' ');
         }
 
-        let _ = write!(self.output, "{}", san_plus);
+        // `from_move_and_play_unchecked` picks the minimal disambiguation and the suffix
+        // matching the resulting position.
+        let canonical = SanPlus::from_move_and_play_unchecked(&mut self.position, m);
+        let _ = write!(self.output, "{}", canonical);
         self.move_count += 1;
         ControlFlow::Continue(())
     }
@@ -181,8 +305,10 @@ impl Visitor for NormalizeVisitor {
     fn end_game(&mut self, _movetext: Self::Movetext) -> Self::Output {}
 }
 
+#[cfg(feature = "extension")]
 pub struct ChessMovesNormalizeScalar;
 
+#[cfg(feature = "extension")]
 impl VScalar for ChessMovesNormalizeScalar {
     type State = ();
 
@@ -191,7 +317,7 @@ impl VScalar for ChessMovesNormalizeScalar {
         input: &mut DataChunkHandle,
         output: &mut dyn WritableVector,
     ) -> Result<(), Box<dyn Error>> {
-        invoke_unary_varchar_to_varchar(input, output, VarcharNullBehavior::Null, |movetext| {
+        invoke_unary_varchar_to_varchar(input, output, |movetext| {
             Ok(VarcharOutput::Value(normalize_movetext(movetext)))
         })
     }
@@ -204,6 +330,68 @@ impl VScalar for ChessMovesNormalizeScalar {
     }
 }
 
+#[cfg(feature = "extension")]
+pub struct ChessMovesDropLastScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessMovesDropLastScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_binary_nullable::<VarcharInput, BigintInput, _, _>(input, output, |movetext, n| {
+            let n = u64::try_from(n).map_err(|_| {
+                format!(
+                    "chess_moves_drop_last: invalid n {n}. Expected a non-negative number of plies."
+                )
+            })?;
+            Ok(drop_last_plies(movetext, n))
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )]
+    }
+}
+
+// Spec: move-analysis - SAN Canonicalization
+#[cfg(feature = "extension")]
+pub struct ChessSanCanonicalScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessSanCanonicalScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_unary_varchar_to_varchar(input, output, |movetext| {
+            Ok(match canonicalize_san_movetext(movetext) {
+                Some(canonical) => VarcharOutput::Value(canonical),
+                None => VarcharOutput::Null,
+            })
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -226,6 +414,79 @@ mod tests {
         assert_eq!(normalize_movetext("1. e4$10"), "1. e4");
     }
 
+    #[test]
+    fn test_canonicalize_san_drops_redundant_disambiguation() {
+        assert_eq!(
+            canonicalize_san_movetext("1. e4 e5 2. Ngf3 Nbc6 3. Bf1b5").as_deref(),
+            Some("1. e4 e5 2. Nf3 Nc6 3. Bb5")
+        );
+        assert_eq!(
+            canonicalize_san_movetext("1. e4 e5 2. Ngf3").as_deref(),
+            canonicalize_san_movetext("1. e4 e5 2. Nf3").as_deref()
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_san_adds_required_disambiguation() {
+        // Knights on c3 and d4 can both reach b5: a full-square hint becomes the file hint.
+        let input = "1. Nf3 a6 2. Nd4 a5 3. Nc3 a4 4. Nc3b5 a3";
+        assert_eq!(
+            canonicalize_san_movetext(input).as_deref(),
+            Some("1. Nf3 a6 2. Nd4 a5 3. Nc3 a4 4. Ncb5 a3")
+        );
+
+        // An ambiguous SAN cannot be resolved.
+        let input = "1. Nf3 a6 2. Nd4 a5 3. Nc3 a4 4. Nb5 a3";
+        assert_eq!(canonicalize_san_movetext(input), None);
+    }
+
+    #[test]
+    fn test_canonicalize_san_normalizes_check_suffixes() {
+        assert_eq!(
+            canonicalize_san_movetext("1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7 1-0").as_deref(),
+            Some("1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0")
+        );
+        assert_eq!(
+            canonicalize_san_movetext("1. e4 e5 2. Qh5 Nbc6 3. Bf1c4 Ng8f6 4. Qxf7").as_deref(),
+            Some("1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7#")
+        );
+        assert_eq!(
+            canonicalize_san_movetext("1. e4+ e5").as_deref(),
+            Some("1. e4 e5")
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_san_strips_annotations_and_rejects_illegal() {
+        assert_eq!(
+            canonicalize_san_movetext("1. e4! {best} (1. d4) e5 $1").as_deref(),
+            Some("1. e4 e5")
+        );
+        assert_eq!(canonicalize_san_movetext("1. e4 e5 2. Ke3"), None);
+        assert_eq!(canonicalize_san_movetext("").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_drop_last_plies_renumbers_mainline() {
+        let movetext = "1. e4 { best } e5 2. Nf3 (2. f4 exf4) Nc6 3. Bb5 a6?! 1-0";
+        assert_eq!(
+            drop_last_plies(movetext, 0).as_deref(),
+            Some("1. e4 e5 2. Nf3 Nc6 3. Bb5 a6")
+        );
+        assert_eq!(
+            drop_last_plies(movetext, 1).as_deref(),
+            Some("1. e4 e5 2. Nf3 Nc6 3. Bb5")
+        );
+        assert_eq!(
+            drop_last_plies(movetext, 2).as_deref(),
+            Some("1. e4 e5 2. Nf3 Nc6")
+        );
+        assert_eq!(drop_last_plies(movetext, 6).as_deref(), Some(""));
+        assert_eq!(drop_last_plies(movetext, u64::MAX).as_deref(), Some(""));
+        assert_eq!(drop_last_plies("", 1).as_deref(), Some(""));
+        assert_eq!(drop_last_plies("1. e4 { unterminated", 1), None);
+    }
+
     #[test]
     fn test_normalize_empty() {
         assert_eq!(normalize_movetext(""), "");
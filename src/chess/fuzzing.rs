@@ -0,0 +1,216 @@
+//! Movetext invariants shared by the property tests and the `cargo fuzz` targets in `fuzz/`.
+//!
+//! Games are generated by replaying legal moves with shakmaty, so every check below runs
+//! against movetext whose canonical form is known up front.
+
+use super::compress::{compress_movetext, decompress_movetext};
+use super::filter::{canonicalize_san_movetext, normalize_movetext};
+use super::moves::{
+    check_moves_subset, movetext_final_zobrist_hash, ply_count, zobrist_hash_of_position,
+};
+use shakmaty::{Chess, Position, san::SanPlus};
+use std::fmt::Write;
+
+/// Longest generated game, in plies.
+pub const MAX_GENERATED_PLIES: usize = 160;
+
+const RESULT_MARKERS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// A legal game generated from fuzzer choices.
+pub struct GeneratedGame {
+    pub sans: Vec<String>,
+    pub final_position: Chess,
+}
+
+impl GeneratedGame {
+    /// Play one legal move per choice (modulo the number of legal moves) until the choices run
+    /// out, the game ends, or `MAX_GENERATED_PLIES` is reached.
+    pub fn from_choices(choices: &[u16]) -> Self {
+        let mut position = Chess::default();
+        let mut sans = Vec::new();
+        for &choice in choices.iter().take(MAX_GENERATED_PLIES) {
+            let legal = position.legal_moves();
+            if legal.is_empty() {
+                break;
+            }
+            let m = legal[usize::from(choice) % legal.len()];
+            sans.push(SanPlus::from_move_and_play_unchecked(&mut position, m).to_string());
+        }
+        Self {
+            sans,
+            final_position: position,
+        }
+    }
+
+    /// Canonical numbered movetext, the layout `chess_moves_normalize` produces.
+    pub fn plain_movetext(&self, plies: usize, result: Option<&str>) -> String {
+        let mut out = String::new();
+        for (ply, san) in self.sans.iter().take(plies).enumerate() {
+            if !out.is_empty() {
+                out.push(' ');
+            }
+            if ply % 2 == 0 {
+                let _ = write!(out, "{}. ", ply / 2 + 1);
+            }
+            out.push_str(san);
+        }
+        if let Some(result) = result {
+            if !out.is_empty() {
+                out.push(' ');
+            }
+            out.push_str(result);
+        }
+        out
+    }
+
+    /// The same mainline decorated with comments, NAGs, suffix annotations, variations and
+    /// irregular whitespace, one decoration byte per ply.
+    pub fn annotated_movetext(&self, decorations: &[u8], result: Option<&str>) -> String {
+        let mut out = String::new();
+        let mut interrupted = false;
+        for (ply, san) in self.sans.iter().enumerate() {
+            let decoration = decorations.get(ply).copied().unwrap_or(0);
+            // A blank line ends the game in PGN, so never follow a line comment with another newline.
+            if !out.ends_with('\n') {
+                out.push(if decoration & 0x80 != 0 { '\n' } else { ' ' });
+            }
+            if ply % 2 == 0 {
+                let _ = write!(out, "{}.", ply / 2 + 1);
+                if decoration & 0x40 == 0 {
+                    out.push(' ');
+                }
+            } else if interrupted {
+                let _ = write!(out, "{}... ", ply / 2 + 1);
+            }
+            out.push_str(san);
+
+            interrupted = true;
+            match decoration % 8 {
+                1 => out.push_str(["!", "?", "!!", "??", "!?", "?!"][ply % 6]),
+                2 => {
+                    let _ = write!(out, " ${}", decoration % 20);
+                }
+                3 => out.push_str(" { [%clk 0:01:00] a (quoted) comment; }"),
+                4 => out.push_str(" ( Nf3 { sideline } Nc6 ( a6 ) )"),
+                5 => out.push_str(" ; rest-of-line comment {\n"),
+                _ => interrupted = false,
+            }
+        }
+        if let Some(result) = result {
+            out.push(' ');
+            out.push_str(result);
+        }
+        out
+    }
+}
+
+/// Assert every invariant for the game generated from `choices`, decorated with `decorations`.
+pub fn check_generated_game(choices: &[u16], decorations: &[u8], result_choice: u8) {
+    let game = GeneratedGame::from_choices(choices);
+    let result = RESULT_MARKERS.get(usize::from(result_choice)).copied();
+    let plain = game.plain_movetext(game.sans.len(), result);
+    let annotated = game.annotated_movetext(decorations, result);
+    let plies = game.sans.len();
+
+    // Normalization is idempotent and ignores annotations.
+    let normalized = normalize_movetext(&annotated);
+    assert_eq!(normalized, plain, "normalize({annotated:?})");
+    assert_eq!(normalize_movetext(&normalized), normalized);
+
+    // Generated SAN is already canonical, so canonicalization must not change a legal game.
+    assert_eq!(
+        canonicalize_san_movetext(&annotated).as_deref(),
+        Some(plain.as_str()),
+        "canonicalize({annotated:?})"
+    );
+
+    // Compression stores one byte per ply and decodes to the canonical movetext.
+    let compressed = compress_movetext(&annotated).expect("a legal game compresses");
+    assert_eq!(compressed.len(), plies + 1);
+    assert_eq!(
+        decompress_movetext(&compressed).as_deref(),
+        Some(plain.as_str()),
+        "decompress(compress({annotated:?}))"
+    );
+
+    // Replay reaches the generated final position.
+    let expected_hash =
+        (plies > 0 || result.is_some()).then(|| zobrist_hash_of_position(&game.final_position));
+    assert_eq!(movetext_final_zobrist_hash(&annotated), expected_hash);
+    assert_eq!(movetext_final_zobrist_hash(&plain), expected_hash);
+    assert_eq!(ply_count(&annotated), plies as i64);
+
+    // Every prefix is a subset of the game; nothing longer is a subset of a strict prefix.
+    for prefix_plies in [0, plies / 2, plies] {
+        let prefix = game.plain_movetext(prefix_plies, None);
+        assert!(
+            check_moves_subset(&prefix, &annotated),
+            "{prefix:?} should be a subset of {annotated:?}"
+        );
+        if prefix_plies < plies {
+            assert!(!check_moves_subset(&plain, &prefix));
+        }
+    }
+}
+
+/// Assert the invariants that hold for any input: no panics, normalization is idempotent, and
+/// compression round-trips to the canonical movetext.
+pub fn check_arbitrary_movetext(movetext: &str) {
+    let normalized = normalize_movetext(movetext);
+    assert_eq!(
+        normalize_movetext(&normalized),
+        normalized,
+        "normalize is not idempotent for {movetext:?}"
+    );
+    if let Some(canonical) = canonicalize_san_movetext(movetext) {
+        assert_eq!(
+            canonicalize_san_movetext(&canonical),
+            Some(canonical.clone())
+        );
+    }
+    if let Some(compressed) = compress_movetext(movetext) {
+        assert_eq!(
+            decompress_movetext(&compressed),
+            canonicalize_san_movetext(movetext),
+            "decompress(compress({movetext:?}))"
+        );
+    }
+    let _ = decompress_movetext(movetext.as_bytes());
+    let _ = movetext_final_zobrist_hash(movetext);
+    let _ = ply_count(movetext);
+    let _ = check_moves_subset(movetext, movetext);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn prop_generated_games_keep_invariants(
+            choices in prop::collection::vec(any::<u16>(), 0..MAX_GENERATED_PLIES),
+            decorations in prop::collection::vec(any::<u8>(), 0..MAX_GENERATED_PLIES),
+            result_choice in 0u8..5,
+        ) {
+            check_generated_game(&choices, &decorations, result_choice);
+        }
+
+        #[test]
+        fn prop_arbitrary_movetext_keeps_invariants(movetext in "[0-9a-hKQRBNOx=+#!?$.(){}; \n-]{0,80}") {
+            check_arbitrary_movetext(&movetext);
+        }
+    }
+
+    #[test]
+    fn test_annotated_movetext_restarts_black_move_numbers() {
+        let game = GeneratedGame::from_choices(&[0, 0, 0]);
+        assert_eq!(game.sans.len(), 3);
+        let annotated = game.annotated_movetext(&[3, 0, 0], Some("*"));
+        assert!(annotated.contains("comment; } 1... "), "{annotated}");
+        assert_eq!(
+            normalize_movetext(&annotated),
+            game.plain_movetext(3, Some("*"))
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Streaming access to the `read_pgn` parser from Rust, without going through DuckDB.
+
+#[cfg(feature = "compression")]
+use super::compression::open_local_decoded;
+use super::types::{ExtraTagColumn, GameRecord};
+use super::visitor::{GameFilter, MovetextStyle, PgnReaderState, ScanOptions, VariantFilter};
+use std::io::{self, Read};
+use std::iter::FusedIterator;
+#[cfg(feature = "compression")]
+use std::path::Path;
+
+/// Options of a [`PgnGameIter`], mirroring the `read_pgn` named parameters.
+#[derive(Clone, Debug, Default)]
+pub struct PgnReadOptions {
+    /// `min_elo := ...`: skip games unless both players are rated at least this.
+    pub min_elo: Option<i64>,
+    /// `max_elo := ...`: skip games unless both players are rated at most this.
+    pub max_elo: Option<i64>,
+    /// `variant := ...`: unlike `read_pgn`, every game is kept unless this is set to
+    /// [`VariantFilter::Standard`].
+    pub variant: VariantFilter,
+    /// `columns := {...}`: tags converted into [`GameRecord::extra_tags`], in this order.
+    pub columns: Vec<ExtraTagColumn>,
+    pub movetext_style: MovetextStyle,
+    pub elo_lenient: bool,
+    /// Keep the raw Elo tags in [`GameRecord::white_elo_raw`] and `black_elo_raw`.
+    pub elo_raw_columns: bool,
+    /// Fill [`GameRecord::byte_offset`] and [`GameRecord::movetext_bytes`], like
+    /// `schema_version := 3`.
+    pub track_offsets: bool,
+}
+
+impl PgnReadOptions {
+    fn scan_options(self) -> io::Result<ScanOptions> {
+        let filter = GameFilter::new(self.min_elo, self.max_elo)
+            .map_err(|msg| io::Error::new(io::ErrorKind::InvalidInput, msg))?
+            .with_variant(self.variant);
+        Ok(ScanOptions {
+            filter,
+            extra_columns: self.columns.into(),
+            track_byte_offsets: self.track_offsets,
+            track_movetext_bytes: self.track_offsets,
+            movetext_style: self.movetext_style,
+            elo_lenient: self.elo_lenient,
+            elo_raw_columns: self.elo_raw_columns,
+            ..ScanOptions::default()
+        })
+    }
+}
+
+/// Iterator over the games of a PGN stream, parsed exactly like `read_pgn` rows.
+///
+/// Games that fail to parse are still yielded, with [`GameRecord::parse_error`] set. An `Err`
+/// is only returned for an I/O error, after which the iterator is exhausted.
+pub struct PgnGameIter {
+    state: PgnReaderState,
+    finished: bool,
+}
+
+impl PgnGameIter {
+    /// Parse uncompressed PGN from `input`. `pgn-reader` buffers the input itself.
+    pub fn new<R: Read + Send + 'static>(input: R, options: PgnReadOptions) -> io::Result<Self> {
+        Ok(Self {
+            state: PgnReaderState::new(Box::new(input), 0, options.scan_options()?),
+            finished: false,
+        })
+    }
+
+    /// Parse the PGN file at `path`, decompressing zstd, gzip, bzip2 and xz files detected
+    /// from their leading bytes.
+    #[cfg(feature = "compression")]
+    pub fn open(path: impl AsRef<Path>, options: PgnReadOptions) -> io::Result<Self> {
+        let scan_options = options.scan_options()?;
+        let input = open_local_decoded(path.as_ref()).map_err(io::Error::other)?;
+        Ok(Self {
+            state: PgnReaderState::new(input, 0, scan_options),
+            finished: false,
+        })
+    }
+}
+
+impl Iterator for PgnGameIter {
+    type Item = io::Result<GameRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            match self.state.pgn_reader.read_game(&mut self.state.visitor) {
+                Ok(Some(_)) => {
+                    // `None` when the Elo filter rejected the game.
+                    if let Some(game) = self.state.visitor.current_game.take() {
+                        return Some(Ok(game));
+                    }
+                }
+                Ok(None) => self.finished = true,
+                Err(err) => {
+                    self.finished = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl FusedIterator for PgnGameIter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::types::{ExtraTagType, ExtraTagValue};
+
+    const GAMES: &str = r#"[Event "First"]
+[White "Alice"]
+[Black "Bob"]
+[WhiteElo "2100"]
+[BlackElo "1900"]
+[Board "3"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 1-0
+
+[Event "Second"]
+[White "Carol"]
+[Black "Dave"]
+[WhiteElo "1500?"]
+[BlackElo "1600"]
+[Result "0-1"]
+
+1. d4 d5 2. Xx9 0-1
+"#;
+
+    fn read_all(options: PgnReadOptions) -> Vec<GameRecord> {
+        PgnGameIter::new(io::Cursor::new(GAMES), options)
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_pgn_game_iter_yields_read_pgn_records() {
+        let games = read_all(PgnReadOptions::default());
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].event.as_deref(), Some("First"));
+        assert_eq!(games[0].white_elo, Some(2100));
+        assert_eq!(games[0].movetext, "1. e4 e5 2. Nf3");
+        assert_eq!(games[0].parse_error, None);
+        assert_eq!(games[0].byte_offset, None);
+        // Unparseable games are yielded with their parse error.
+        assert_eq!(games[1].white_elo, None);
+        assert!(games[1].parse_error.is_some());
+    }
+
+    #[test]
+    fn test_pgn_game_iter_applies_options() {
+        let games = read_all(PgnReadOptions {
+            min_elo: Some(1800),
+            columns: vec![ExtraTagColumn {
+                tag: "Board".to_string(),
+                tag_type: ExtraTagType::Integer,
+            }],
+            movetext_style: MovetextStyle::Uci,
+            track_offsets: true,
+            ..PgnReadOptions::default()
+        });
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].extra_tags, [Some(ExtraTagValue::Integer(3))]);
+        assert_eq!(games[0].movetext, "e2e4 e7e5 g1f3");
+        assert_eq!(games[0].byte_offset, Some(0));
+
+        let lenient = read_all(PgnReadOptions {
+            elo_lenient: true,
+            elo_raw_columns: true,
+            ..PgnReadOptions::default()
+        });
+        assert_eq!(lenient[1].white_elo, Some(1500));
+        assert_eq!(lenient[1].white_elo_raw.as_deref(), Some("1500?"));
+    }
+
+    #[test]
+    fn test_pgn_game_iter_rejects_invalid_elo_range() {
+        let err = PgnGameIter::new(
+            io::empty(),
+            PgnReadOptions {
+                min_elo: Some(2000),
+                max_elo: Some(1000),
+                ..PgnReadOptions::default()
+            },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("Invalid Elo range"));
+    }
+
+    #[test]
+    fn test_pgn_game_iter_stops_after_io_error() {
+        struct FailingReader;
+        impl Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk on fire"))
+            }
+        }
+
+        let mut games = PgnGameIter::new(FailingReader, PgnReadOptions::default()).unwrap();
+        assert_eq!(
+            games.next().unwrap().unwrap_err().to_string(),
+            "disk on fire"
+        );
+        assert!(games.next().is_none());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_pgn_game_iter_opens_compressed_files() {
+        let plain = PgnGameIter::open("test/pgn_files/sample.pgn", PgnReadOptions::default())
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        for compressed in [
+            "sample.pgn.zst",
+            "sample.pgn.gz",
+            "sample.pgn.bz2",
+            "sample.pgn.xz",
+        ] {
+            let games = PgnGameIter::open(
+                format!("test/pgn_files/{compressed}"),
+                PgnReadOptions::default(),
+            )
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+            assert_eq!(format!("{games:?}"), format!("{plain:?}"), "{compressed}");
+        }
+
+        let err = PgnGameIter::open("test/pgn_files/missing.pgn", PgnReadOptions::default())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Failed to open file"));
+    }
+}
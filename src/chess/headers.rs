@@ -0,0 +1,594 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{
+    VarcharInput, invoke_binary_nullable, invoke_ternary_nullable,
+    invoke_unary_varchar_to_varchar_varchar_map,
+};
+#[cfg(feature = "extension")]
+use super::export::{FieldRole, GameField};
+use super::timecontrol::normalize_timecontrol_strict;
+#[cfg(any(test, feature = "extension"))]
+use super::types::PgnDate;
+#[cfg(any(test, feature = "extension"))]
+use super::visitor::infer_date;
+use chrono::NaiveDate;
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, Inserter, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use pgn_reader::{RawTag, Reader, Visitor};
+#[cfg(feature = "extension")]
+use std::error::Error;
+use std::io;
+use std::ops::ControlFlow;
+
+/// Values allowed in the Result tag.
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+/// Tags scored by `chess_headers_complete`: the Seven Tag Roster, then common extended tags.
+pub const COMPLETENESS_TAGS: [&str; 12] = [
+    "Event",
+    "Site",
+    "Date",
+    "Round",
+    "White",
+    "Black",
+    "Result",
+    "WhiteElo",
+    "BlackElo",
+    "ECO",
+    "TimeControl",
+    "Termination",
+];
+/// Titles accepted in WhiteTitle/BlackTitle: the FIDE titles, national and Lichess titles, and
+/// `-` for none.
+const TITLES: [&str; 12] = [
+    "GM", "IM", "FM", "CM", "WGM", "WIM", "WFM", "WCM", "NM", "LM", "BOT", "-",
+];
+
+/// Collects the tag pairs of a game and stops before its movetext, which the reader then skips.
+#[derive(Default)]
+pub(crate) struct HeadersVisitor {
+    pub(crate) tags: Vec<(String, String)>,
+    /// Keep every occurrence of a repeated tag instead of only the first.
+    pub(crate) keep_repeated: bool,
+}
+
+impl Visitor for HeadersVisitor {
+    type Tags = ();
+    type Movetext = ();
+    type Output = ();
+
+    fn begin_tags(&mut self) -> ControlFlow<Self::Output, Self::Tags> {
+        self.tags.clear();
+        ControlFlow::Continue(())
+    }
+
+    fn tag(
+        &mut self,
+        _tags: &mut Self::Tags,
+        name: &[u8],
+        value: RawTag<'_>,
+    ) -> ControlFlow<Self::Output> {
+        let name = String::from_utf8_lossy(name.trim_ascii());
+        // MAP keys must be unique; the first occurrence wins, as in `read_pgn`.
+        if self.keep_repeated || !self.tags.iter().any(|(key, _)| *key == name) {
+            let value = value.decode_utf8_lossy().into_owned();
+            self.tags.push((name.into_owned(), value));
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn begin_movetext(&mut self, _tags: Self::Tags) -> ControlFlow<Self::Output, Self::Movetext> {
+        ControlFlow::Break(())
+    }
+
+    fn end_game(&mut self, _movetext: Self::Movetext) -> Self::Output {}
+}
+
+/// Tag pairs of the first game in `pgn_text`, in file order, with `\"` and `\\` unescaped.
+/// Spec: pgn-parsing - Raw Header Extraction
+///
+/// Movetext is never parsed. Text without a tag section yields an empty list.
+pub fn pgn_headers(pgn_text: &str) -> Vec<(String, String)> {
+    let mut reader = Reader::new(io::Cursor::new(pgn_text.as_bytes()));
+    let mut visitor = HeadersVisitor::default();
+    // Reading from memory cannot fail with an I/O error.
+    let _ = reader.read_game(&mut visitor);
+    visitor.tags
+}
+
+/// Describe why `value` is not a valid PGN value for the tag `tag_name`; `None` when it is
+/// valid or the tag has no rule.
+/// Spec: pgn-parsing - Tag Validation
+///
+/// Tag names are case-sensitive, as in the PGN standard. Checked tags: Result, Date, EventDate
+/// and UTCDate (`YYYY.MM.DD` with `?` for unknown parts), Time and UTCTime (`HH:MM:SS`), Round
+/// (`?`, `-` or dot-separated positive integers such as `3.1`), WhiteTitle and BlackTitle,
+/// WhiteElo and BlackElo (digits or `-`), ECO (`A00`..`E99`) and TimeControl (the strict
+/// PGN grammar of `chess_timecontrol_normalize(tc, true)`).
+pub fn tag_validate(tag_name: &str, value: &str) -> Option<String> {
+    match tag_name {
+        "Result" => (!RESULTS.contains(&value))
+            .then(|| format!("result '{value}' is not one of 1-0, 0-1, 1/2-1/2 or *")),
+        "Date" | "EventDate" | "UTCDate" => validate_date(value),
+        "Time" | "UTCTime" => validate_time(value),
+        "Round" => validate_round(value),
+        "WhiteTitle" | "BlackTitle" => (!TITLES.contains(&value))
+            .then(|| format!("title '{value}' is not one of {}", TITLES.join(", "))),
+        "WhiteElo" | "BlackElo" => (value != "-" && !is_digits(value))
+            .then(|| format!("elo '{value}' is not a non-negative integer or -")),
+        "ECO" => validate_eco(value),
+        "TimeControl" => normalize_timecontrol_strict(value)
+            .is_none()
+            .then(|| format!("time control '{value}' does not follow the PGN grammar")),
+        _ => None,
+    }
+}
+
+/// Share of [`COMPLETENESS_TAGS`] with a known value, and the missing tags in that order.
+/// Spec: pgn-parsing - Header Completeness
+///
+/// Tag names match case-insensitively and the first occurrence wins. A value is unknown when it
+/// is NULL or made only of `?`, `.` and `-` (so `""`, `?`, `-` and `????.??.??`); the Result `*`
+/// counts as known. `Date` falls back to `UTCDate`, as in `chess_game_pgn`.
+pub fn headers_complete(tags: &[(String, Option<String>)]) -> (f64, Vec<&'static str>) {
+    let lookup = |name: &str| {
+        tags.iter()
+            .find(|(tag, _)| tag.eq_ignore_ascii_case(name))
+            .and_then(|(_, value)| value.as_deref())
+            .filter(|value| !is_unknown_tag_value(value))
+    };
+    let missing: Vec<&'static str> = COMPLETENESS_TAGS
+        .into_iter()
+        .filter(|&name| match name {
+            "Date" => lookup("Date").or_else(|| lookup("UTCDate")).is_none(),
+            _ => lookup(name).is_none(),
+        })
+        .collect();
+    let present = COMPLETENESS_TAGS.len() - missing.len();
+    (present as f64 / COMPLETENESS_TAGS.len() as f64, missing)
+}
+
+fn is_unknown_tag_value(value: &str) -> bool {
+    value.trim().chars().all(|c| matches!(c, '?' | '.' | '-'))
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn validate_date(value: &str) -> Option<String> {
+    let parts: Vec<&str> = value.split('.').collect();
+    let well_formed = parts.len() == 3
+        && parts.iter().zip([4, 2, 2]).all(|(part, len)| {
+            part.len() == len && (is_digits(part) || part.bytes().all(|b| b == b'?'))
+        });
+    if !well_formed {
+        return Some(format!("date '{value}' is not in YYYY.MM.DD form"));
+    }
+
+    let known = |part: &str| part.parse::<u32>().ok();
+    let (year, month, day) = (known(parts[0]), known(parts[1]), known(parts[2]));
+    if month.is_some_and(|month| !(1..=12).contains(&month)) {
+        return Some(format!("date '{value}' has month out of range"));
+    }
+    if day.is_some_and(|day| !(1..=31).contains(&day)) {
+        return Some(format!("date '{value}' has day out of range"));
+    }
+    if let (Some(year), Some(month), Some(day)) = (year, month, day)
+        && NaiveDate::from_ymd_opt(year as i32, month, day).is_none()
+    {
+        return Some(format!("date '{value}' does not exist"));
+    }
+    None
+}
+
+fn validate_time(value: &str) -> Option<String> {
+    let parts: Vec<&str> = value.split(':').collect();
+    let in_range = parts.len() == 3
+        && parts.iter().zip([23, 59, 60]).all(|(part, max)| {
+            part.len() == 2 && is_digits(part) && part.parse::<u32>().is_ok_and(|n| n <= max)
+        });
+    (!in_range).then(|| format!("time '{value}' is not in HH:MM:SS form"))
+}
+
+fn validate_round(value: &str) -> Option<String> {
+    let valid = value == "?"
+        || value == "-"
+        || value
+            .split('.')
+            .all(|level| is_digits(level) && level.parse::<u64>().is_ok_and(|n| n > 0));
+    (!valid).then(|| format!("round '{value}' is not ?, - or dot-separated positive integers"))
+}
+
+fn validate_eco(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let valid = bytes.len() == 3
+        && (b'A'..=b'E').contains(&bytes[0])
+        && bytes[1..].iter().all(u8::is_ascii_digit);
+    (!valid).then(|| format!("eco '{value}' is not a code from A00 to E99"))
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessPgnHeadersScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessPgnHeadersScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_unary_varchar_to_varchar_varchar_map(input, output, |pgn_text| {
+            Some(pgn_headers(pgn_text))
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            LogicalTypeHandle::map(
+                &LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                &LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+        )]
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessTagValidateScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessTagValidateScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_binary_nullable::<VarcharInput, VarcharInput, _, _>(
+            input,
+            output,
+            |tag_name, value| Ok(tag_validate(tag_name, value)),
+        )
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )]
+    }
+}
+
+/// `chess_headers_complete(game)` over a game `STRUCT`, read like `chess_game_pgn`.
+#[cfg(feature = "extension")]
+pub struct ChessHeadersCompleteScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessHeadersCompleteScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let len = input.len();
+        let game_vec = input.flat_vector(0);
+        if game_vec.logical_type().id() != LogicalTypeId::Struct {
+            return Err(
+                "chess_headers_complete: expected a STRUCT argument (e.g. a read_pgn row)".into(),
+            );
+        }
+
+        let game = input.struct_vector(0);
+        let fields = (0..game.num_children())
+            .map(|idx| {
+                let name = game.child_name(idx).to_string_lossy().into_owned();
+                GameField::new(name, game.child(idx, len), "chess_headers_complete")
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut rows = Vec::with_capacity(len);
+        let mut tags = Vec::with_capacity(fields.len());
+        for row in 0..len {
+            if game_vec.row_is_null(row as u64) {
+                rows.push(None);
+                continue;
+            }
+
+            tags.clear();
+            for field in fields.iter().filter(|f| matches!(f.role, FieldRole::Tag)) {
+                // SAFETY: `row` is within the input chunk.
+                tags.push((field.name.clone(), unsafe { field.value(row) }));
+            }
+            rows.push(Some(headers_complete(&tags)));
+        }
+
+        let total: usize = rows
+            .iter()
+            .flatten()
+            .map(|(_, missing)| missing.len())
+            .sum();
+        let mut struct_vec = output.struct_vector();
+        let mut score_vec = struct_vec.child(0, len);
+        let mut missing_vec = struct_vec.list_vector_child(1);
+        let missing_names = missing_vec.child(total);
+
+        let mut offset = 0;
+        for (row, completeness) in rows.into_iter().enumerate() {
+            let Some((score, missing)) = completeness else {
+                // DuckDB expects the fields of a NULL struct to be NULL as well.
+                struct_vec.set_null(row);
+                score_vec.set_null(row);
+                missing_vec.set_null(row);
+                missing_vec.set_entry(row, offset, 0);
+                continue;
+            };
+
+            score_vec.as_mut_slice::<f64>()[row] = score;
+            missing_vec.set_entry(row, offset, missing.len());
+            for name in missing {
+                missing_names.insert(offset, name);
+                offset += 1;
+            }
+        }
+        missing_vec.set_len(offset);
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Any)],
+            LogicalTypeHandle::struct_type(&[
+                ("score", LogicalTypeHandle::from(LogicalTypeId::Double)),
+                (
+                    "missing",
+                    LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                ),
+            ]),
+        )]
+    }
+}
+
+/// One row of `chess_date_infer_impl(utc_date, date, event_date)`. The public macro passes
+/// NULL tags as empty strings, which count as missing, so the result is NULL only when no tag
+/// holds a usable date.
+#[cfg(any(test, feature = "extension"))]
+fn date_of_tags(utc_date: &str, date: &str, event_date: &str) -> Option<PgnDate> {
+    infer_date(Some(utc_date), Some(date), Some(event_date))
+}
+
+/// `chess_date_infer_impl(utc_date, date, event_date)`, behind the `chess_date_infer` macro.
+#[cfg(feature = "extension")]
+pub struct ChessDateInferScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessDateInferScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_ternary_nullable::<VarcharInput, VarcharInput, VarcharInput, _, _>(
+            input,
+            output,
+            |utc_date, date, event_date| Ok(date_of_tags(utc_date, date, event_date)),
+        )
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Date),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_pgn_headers_reads_first_tag_section_only() {
+        let pgn = r#"[Event "Rated Blitz"]
+[White "Alice"]
+[Annotator "A \"quoted\" name"]
+[Event "Duplicate"]
+
+1. e4 e5 1-0
+
+[Event "Second Game"]
+
+1. d4 *"#;
+        assert_eq!(
+            pgn_headers(pgn),
+            pairs(&[
+                ("Event", "Rated Blitz"),
+                ("White", "Alice"),
+                ("Annotator", "A \"quoted\" name"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_headers_visitor_keeps_repeated_tags_of_every_game() {
+        let pgn = "[Event \"A\"]\n[Event \"B\"]\n\n1. e4 {[%clk 0:01:00]} *\n\n1. d4 *\n\n[Site \"?\"]\n\n*";
+        let mut reader = Reader::new(io::Cursor::new(pgn.as_bytes()));
+        let mut visitor = HeadersVisitor {
+            keep_repeated: true,
+            ..HeadersVisitor::default()
+        };
+        let mut games = Vec::new();
+        while reader.read_game(&mut visitor).unwrap().is_some() {
+            games.push(std::mem::take(&mut visitor.tags));
+        }
+        assert_eq!(
+            games,
+            [
+                pairs(&[("Event", "A"), ("Event", "B")]),
+                Vec::new(),
+                pairs(&[("Site", "?")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pgn_headers_without_tags_is_empty() {
+        assert_eq!(pgn_headers(""), Vec::new());
+        assert_eq!(pgn_headers("1. e4 e5 2. Nf3 *"), Vec::new());
+        assert_eq!(pgn_headers("[Site \"?\"]"), pairs(&[("Site", "?")]));
+        assert_eq!(
+            pgn_headers("% escape line\n[ Site\t \"C:\\\\PGN\" ]"),
+            pairs(&[("Site", "C:\\PGN")])
+        );
+    }
+
+    #[test]
+    fn test_pgn_headers_ignores_broken_movetext() {
+        assert_eq!(
+            pgn_headers("[Result \"*\"]\n\n1. e4 {unterminated ) ) Zz9 *"),
+            pairs(&[("Result", "*")])
+        );
+    }
+
+    #[test]
+    fn test_tag_validate_result_and_round() {
+        for result in ["1-0", "0-1", "1/2-1/2", "*"] {
+            assert_eq!(tag_validate("Result", result), None);
+        }
+        assert!(tag_validate("Result", "½-½").is_some());
+        assert!(tag_validate("Result", "1-0 ").is_some());
+
+        for round in ["?", "-", "1", "12", "3.1.2"] {
+            assert_eq!(tag_validate("Round", round), None);
+        }
+        for round in ["", "0", "1.", "R3", "3.0", "1-2"] {
+            assert!(tag_validate("Round", round).is_some(), "{round}");
+        }
+    }
+
+    #[test]
+    fn test_tag_validate_dates_and_times() {
+        for date in ["2024.02.29", "????.??.??", "1999.??.??", "2024.05.??"] {
+            assert_eq!(tag_validate("Date", date), None);
+        }
+        assert_eq!(
+            tag_validate("Date", "2024-01-15").as_deref(),
+            Some("date '2024-01-15' is not in YYYY.MM.DD form")
+        );
+        assert_eq!(
+            tag_validate("UTCDate", "2023.02.29").as_deref(),
+            Some("date '2023.02.29' does not exist")
+        );
+        assert!(tag_validate("EventDate", "2024.13.??").is_some());
+        assert!(tag_validate("Date", "2024.1.5").is_some());
+        assert!(tag_validate("Date", "20?4.01.05").is_some());
+
+        assert_eq!(tag_validate("UTCTime", "23:59:60"), None);
+        assert!(tag_validate("Time", "24:00:00").is_some());
+        assert!(tag_validate("Time", "9:05:00").is_some());
+    }
+
+    #[test]
+    fn test_tag_validate_vocabularies() {
+        assert_eq!(tag_validate("WhiteTitle", "GM"), None);
+        assert_eq!(tag_validate("BlackTitle", "-"), None);
+        assert!(tag_validate("WhiteTitle", "gm").is_some());
+        assert!(tag_validate("BlackTitle", "Grandmaster").is_some());
+
+        assert_eq!(tag_validate("WhiteElo", "2850"), None);
+        assert_eq!(tag_validate("BlackElo", "-"), None);
+        assert!(tag_validate("WhiteElo", "?").is_some());
+        assert!(tag_validate("WhiteElo", "2850.5").is_some());
+
+        assert_eq!(tag_validate("ECO", "B90"), None);
+        assert!(tag_validate("ECO", "F00").is_some());
+        assert!(tag_validate("ECO", "B9").is_some());
+
+        assert_eq!(tag_validate("TimeControl", "40/7200:3600"), None);
+        assert_eq!(tag_validate("TimeControl", "?"), None);
+        assert!(tag_validate("TimeControl", "15 + 10").is_some());
+    }
+
+    #[test]
+    fn test_tag_validate_ignores_unknown_tags() {
+        assert_eq!(tag_validate("Event", ""), None);
+        assert_eq!(tag_validate("Opening", "anything"), None);
+        // Tag names are case-sensitive.
+        assert_eq!(tag_validate("result", "nonsense"), None);
+    }
+
+    #[test]
+    fn test_headers_complete() {
+        let tag = |name: &str, value: Option<&str>| (name.to_string(), value.map(str::to_string));
+
+        let (score, missing) = headers_complete(&[]);
+        assert_eq!(score, 0.0);
+        assert_eq!(missing, COMPLETENESS_TAGS);
+
+        let tags: Vec<_> = COMPLETENESS_TAGS
+            .iter()
+            .map(|name| tag(name, Some("x")))
+            .collect();
+        assert_eq!(headers_complete(&tags), (1.0, vec![]));
+
+        let tags = [
+            tag("event", Some("Club")),
+            tag("Site", Some("?")),
+            tag("Date", Some("????.??.??")),
+            tag("UTCDate", Some("2024.01.15")),
+            tag("Round", Some("-")),
+            tag("White", Some("")),
+            tag("Black", None),
+            tag("Result", Some("*")),
+            tag("WhiteElo", Some("1500")),
+        ];
+        let (score, missing) = headers_complete(&tags);
+        assert_eq!(score, 4.0 / 12.0);
+        assert_eq!(
+            missing,
+            [
+                "Site",
+                "Round",
+                "White",
+                "Black",
+                "BlackElo",
+                "ECO",
+                "TimeControl",
+                "Termination"
+            ]
+        );
+    }
+    #[test]
+    fn test_date_of_tags_treats_empty_tags_as_missing() {
+        assert_eq!(date_of_tags("", "", ""), None);
+        assert_eq!(
+            date_of_tags("", "2024.03.15", ""),
+            infer_date(None, Some("2024.03.15"), None)
+        );
+        assert!(date_of_tags("", "", "2024.06.??").is_some());
+    }
+}
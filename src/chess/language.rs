@@ -0,0 +1,140 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{VarcharOutput, invoke_unary_varchar_to_varchar};
+use super::tokens::{TokenKind, tokenize_movetext};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+#[cfg(feature = "extension")]
+use std::error::Error;
+
+/// Detect the language of the comments in `movetext`, as an ISO 639-3 code (`eng`, `deu`, ...).
+/// Spec: move-analysis - Comment Language
+///
+/// Comments from the mainline and from variations are joined; `[%...]` commands such as clocks
+/// and evals are ignored. Returns `None` when there is no comment prose or when the detector is
+/// not confident, which is common for very short comments.
+pub fn comment_language(movetext: &str) -> Option<&'static str> {
+    let text = comment_text(movetext);
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    whatlang::detect(&text)
+        .filter(whatlang::Info::is_reliable)
+        .map(|info| info.lang().code())
+}
+
+/// Prose from every `{...}` and `;` comment, one comment per line.
+fn comment_text(movetext: &str) -> String {
+    let mut text = String::new();
+    for token in tokenize_movetext(movetext) {
+        if token.kind != TokenKind::Comment {
+            continue;
+        }
+        let body = match token.text.strip_prefix('{') {
+            Some(braced) => braced.strip_suffix('}').unwrap_or(braced),
+            None => token.text.trim_start_matches(';'),
+        };
+        let prose = strip_commands(body);
+        let prose = prose.trim();
+        if !prose.is_empty() {
+            text.push_str(prose);
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Remove `[%cmd ...]` annotations, keeping the surrounding text.
+fn strip_commands(comment: &str) -> String {
+    let mut prose = String::with_capacity(comment.len());
+    let mut rest = comment;
+    while let Some(start) = rest.find("[%") {
+        prose.push_str(&rest[..start]);
+        prose.push(' ');
+        rest = rest[start..]
+            .find(']')
+            .map_or("", |end| &rest[start + end + 1..]);
+    }
+    prose.push_str(rest);
+    prose
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessCommentLanguageScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessCommentLanguageScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_unary_varchar_to_varchar(input, output, |movetext| {
+            Ok(match comment_language(movetext) {
+                Some(code) => VarcharOutput::Value(code.to_string()),
+                None => VarcharOutput::Null,
+            })
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comment_text_skips_commands_and_moves() {
+        assert_eq!(
+            comment_text(
+                "1. e4 { [%clk 0:03:00] A classical opening move. } e5 (1... c5 ; Sicilian\n) 2. Nf3 {[%eval 0.3]}"
+            ),
+            "A classical opening move.\nSicilian\n"
+        );
+        assert_eq!(strip_commands("a [%clk 0:01:00] b [%eval"), "a   b  ");
+    }
+
+    #[test]
+    fn test_comment_language_detects_common_languages() {
+        assert_eq!(
+            comment_language(
+                "1. e4 { White takes control of the center and opens lines for the queen and the bishop. } e5"
+            ),
+            Some("eng")
+        );
+        assert_eq!(
+            comment_language(
+                "1. e4 { Weiß besetzt das Zentrum und öffnet die Linien für die Dame und den Läufer. } e5"
+            ),
+            Some("deu")
+        );
+        assert_eq!(
+            comment_language(
+                "1. e4 { Las blancas ocupan el centro y abren líneas para la dama y el alfil. } e5"
+            ),
+            Some("spa")
+        );
+    }
+
+    #[test]
+    fn test_comment_language_without_prose_is_none() {
+        assert_eq!(comment_language(""), None);
+        assert_eq!(comment_language("1. e4 e5 2. Nf3 Nc6"), None);
+        assert_eq!(
+            comment_language("1. e4 { [%clk 0:03:00] } e5 { [%eval 0.2] }"),
+            None
+        );
+    }
+}
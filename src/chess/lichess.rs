@@ -0,0 +1,370 @@
+//! Conversion of Lichess API game exports (one JSON object per line) into `GameRecord`s.
+//!
+//! Spec: pgn-parsing - Lichess NDJSON Reading
+
+use super::types::{GameRecord, PgnDate, PgnTimeTz};
+use super::visitor::GameVisitor;
+use chrono::DateTime;
+use serde_json::Value;
+use shakmaty::{CastlingMode, Chess, Color, Move, Position, fen::Fen, san::SanPlus, uci::UciMove};
+use std::fmt::Write;
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+const MICROS_PER_CENTISECOND: i64 = 10_000;
+
+/// Engine evaluation after a ply, as exported in the Lichess `analysis` array.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LichessEval {
+    /// Centipawns from White's point of view.
+    pub cp: Option<i32>,
+    /// Moves to mate; negative when Black mates.
+    pub mate: Option<i32>,
+}
+
+/// One exported game: the `read_pgn` columns plus the per-ply arrays Lichess exports natively.
+#[derive(Clone, Debug, Default)]
+pub struct LichessGame {
+    pub record: GameRecord,
+    /// Remaining clock after each ply, in microseconds; `None` when clocks were not exported.
+    pub clocks: Option<Vec<i64>>,
+    /// `None` when the game has no computer analysis.
+    pub evals: Option<Vec<LichessEval>>,
+}
+
+/// Parse one NDJSON line. Malformed input still yields a game, with `parse_error` set.
+pub fn parse_lichess_game(line: &str) -> LichessGame {
+    let json: Value = match serde_json::from_str(line) {
+        Ok(json @ Value::Object(_)) => json,
+        Ok(_) => {
+            return LichessGame::from_error(
+                "Invalid Lichess game: expected a JSON object".to_string(),
+            );
+        }
+        Err(err) => return LichessGame::from_error(format!("Invalid Lichess game: {err}")),
+    };
+
+    let white = &json["players"]["white"];
+    let black = &json["players"]["black"];
+    let status = json["status"].as_str();
+    let (utc_date, utc_time) = json["createdAt"]
+        .as_i64()
+        .map_or((None, None), split_timestamp_millis);
+
+    let mut record = GameRecord {
+        event: Some(event_name(&json)),
+        site: json["id"]
+            .as_str()
+            .map(|id| format!("https://lichess.org/{id}")),
+        source: json["source"].as_str().map(str::to_string),
+        white: player_name(white),
+        black: player_name(black),
+        result: Some(result(json["winner"].as_str(), status).to_string()),
+        white_title: white["user"]["title"].as_str().map(str::to_string),
+        black_title: black["user"]["title"].as_str().map(str::to_string),
+        white_elo: white["rating"].as_u64().and_then(|r| u32::try_from(r).ok()),
+        black_elo: black["rating"].as_u64().and_then(|r| u32::try_from(r).ok()),
+        utc_date,
+        utc_time,
+        eco: json["opening"]["eco"].as_str().map(str::to_string),
+        opening: json["opening"]["name"].as_str().map(str::to_string),
+        termination: status.map(|status| termination(status).to_string()),
+        time_control: Some(time_control(&json)),
+        ..GameRecord::default()
+    };
+
+    let moves = json["moves"].as_str().unwrap_or_default();
+    match convert_moves(&json, moves) {
+        Ok(movetext) => record.movetext = movetext,
+        Err((movetext, error)) => {
+            record.movetext = movetext;
+            record.parse_error = Some(error);
+        }
+    }
+
+    LichessGame {
+        record,
+        clocks: json["clocks"].as_array().map(|clocks| {
+            clocks
+                .iter()
+                .filter_map(Value::as_i64)
+                .map(|centis| centis * MICROS_PER_CENTISECOND)
+                .collect()
+        }),
+        evals: json["analysis"].as_array().map(|analysis| {
+            analysis
+                .iter()
+                .map(|entry| LichessEval {
+                    cp: entry["eval"].as_i64().and_then(|v| i32::try_from(v).ok()),
+                    mate: entry["mate"].as_i64().and_then(|v| i32::try_from(v).ok()),
+                })
+                .collect()
+        }),
+    }
+}
+
+impl LichessGame {
+    /// A row carrying only `parse_error`, for input that could not be read as a game.
+    pub fn from_error(error: String) -> Self {
+        Self {
+            record: GameRecord {
+                parse_error: Some(error),
+                ..GameRecord::default()
+            },
+            ..Self::default()
+        }
+    }
+}
+
+/// `Rated Blitz game`, as in Lichess' own PGN export.
+fn event_name(json: &Value) -> String {
+    let rated = if json["rated"].as_bool().unwrap_or(false) {
+        "Rated"
+    } else {
+        "Casual"
+    };
+    let perf = json["perf"]
+        .as_str()
+        .or_else(|| json["speed"].as_str())
+        .unwrap_or("unknown");
+    let mut chars = perf.chars();
+    let perf: String = chars
+        .next()
+        .map(|first| first.to_ascii_uppercase())
+        .into_iter()
+        .chain(chars)
+        .collect();
+    format!("{rated} {perf} game")
+}
+
+fn player_name(player: &Value) -> Option<String> {
+    if let Some(name) = player["user"]["name"].as_str() {
+        return Some(name.to_string());
+    }
+    player["aiLevel"]
+        .as_u64()
+        .map(|level| format!("lichess AI level {level}"))
+}
+
+fn result(winner: Option<&str>, status: Option<&str>) -> &'static str {
+    match (winner, status) {
+        (Some("white"), _) => "1-0",
+        (Some("black"), _) => "0-1",
+        (_, None | Some("created" | "started" | "aborted" | "noStart" | "unknownFinish")) => "*",
+        _ => "1/2-1/2",
+    }
+}
+
+/// PGN `Termination` value Lichess writes for a game status.
+fn termination(status: &str) -> &'static str {
+    match status {
+        "created" | "started" => "Unterminated",
+        "aborted" | "noStart" => "Abandoned",
+        "timeout" | "outoftime" => "Time forfeit",
+        "cheat" => "Rules infraction",
+        "unknownFinish" => "Unknown",
+        _ => "Normal",
+    }
+}
+
+fn time_control(json: &Value) -> String {
+    let clock = &json["clock"];
+    match (clock["initial"].as_u64(), clock["increment"].as_u64()) {
+        (Some(initial), Some(increment)) => format!("{initial}+{increment}"),
+        _ => "-".to_string(),
+    }
+}
+
+fn split_timestamp_millis(millis: i64) -> (Option<PgnDate>, Option<PgnTimeTz>) {
+    if DateTime::from_timestamp_millis(millis).is_none() {
+        return (None, None);
+    }
+    let micros = millis * 1_000;
+    let Ok(days) = i32::try_from(micros.div_euclid(MICROS_PER_DAY)) else {
+        return (None, None);
+    };
+    (
+        Some(PgnDate { days }),
+        Some(GameVisitor::pack_time_tz(
+            micros.rem_euclid(MICROS_PER_DAY),
+            0,
+        )),
+    )
+}
+
+/// Replay space-separated SAN or UCI moves into numbered SAN movetext.
+///
+/// On failure returns the movetext converted so far together with the error.
+fn convert_moves(json: &Value, moves: &str) -> Result<String, (String, String)> {
+    let variant = json["variant"].as_str().unwrap_or("standard");
+    let castling_mode = match variant {
+        "standard" | "fromPosition" => CastlingMode::Standard,
+        "chess960" => CastlingMode::Chess960,
+        _ => {
+            return Err((
+                String::new(),
+                format!("Unsupported variant '{variant}'; movetext was not converted"),
+            ));
+        }
+    };
+
+    let mut pos = match json["initialFen"].as_str() {
+        Some(fen) => fen
+            .parse::<Fen>()
+            .map_err(|err| err.to_string())
+            .and_then(|fen| {
+                fen.into_position::<Chess>(castling_mode)
+                    .map_err(|err| err.to_string())
+            })
+            .map_err(|err| (String::new(), format!("Invalid initialFen '{fen}': {err}")))?,
+        None => Chess::default(),
+    };
+
+    let mut movetext = String::new();
+    for (ply, token) in moves.split_whitespace().enumerate() {
+        let Some(m) = parse_move(&pos, token) else {
+            return Err((
+                movetext,
+                format!("Illegal move '{}' at ply {}", token, ply + 1),
+            ));
+        };
+
+        if !movetext.is_empty() {
+            movetext.push(' ');
+        }
+        let fullmoves = pos.fullmoves();
+        match pos.turn() {
+            Color::White => write!(movetext, "{fullmoves}. ").unwrap(),
+            Color::Black if ply == 0 => write!(movetext, "{fullmoves}... ").unwrap(),
+            Color::Black => {}
+        }
+        let san = SanPlus::from_move_and_play_unchecked(&mut pos, m);
+        write!(movetext, "{san}").unwrap();
+    }
+    Ok(movetext)
+}
+
+/// Lichess exports SAN by default and UCI on request; accept either per move.
+fn parse_move(pos: &Chess, token: &str) -> Option<Move> {
+    let san = token
+        .parse::<SanPlus>()
+        .ok()
+        .and_then(|san_plus| san_plus.san.to_move(pos).ok());
+    san.or_else(|| token.parse::<UciMove>().ok()?.to_move(pos).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_lines() -> Vec<String> {
+        std::fs::read_to_string("test/pgn_files/lichess_games.ndjson")
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_lichess_game_maps_pgn_columns() {
+        let game = parse_lichess_game(&fixture_lines()[0]);
+        let record = &game.record;
+
+        assert_eq!(record.event.as_deref(), Some("Rated Blitz game"));
+        assert_eq!(record.site.as_deref(), Some("https://lichess.org/q7ZvsdUF"));
+        assert_eq!(record.white.as_deref(), Some("Lance5500"));
+        assert_eq!(record.white_title.as_deref(), Some("LM"));
+        assert_eq!(record.black_title, None);
+        assert_eq!(record.white_elo, Some(2389));
+        assert_eq!(record.result.as_deref(), Some("1-0"));
+        assert_eq!(record.termination.as_deref(), Some("Normal"));
+        assert_eq!(record.time_control.as_deref(), Some("180+0"));
+        assert_eq!(record.eco.as_deref(), Some("C50"));
+        assert_eq!(record.source.as_deref(), Some("pool"));
+        assert_eq!(record.movetext, "1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7#");
+        assert_eq!(record.parse_error, None);
+
+        // 2017-12-28 23:52:30.384 UTC
+        assert_eq!(record.utc_date, Some(PgnDate { days: 17528 }));
+        assert_eq!(
+            record.utc_time,
+            Some(GameVisitor::pack_time_tz(85_950_384_000, 0))
+        );
+    }
+
+    #[test]
+    fn test_parse_lichess_game_exports_clocks_and_evals() {
+        let game = parse_lichess_game(&fixture_lines()[0]);
+        let clocks = game.clocks.unwrap();
+        assert_eq!(clocks.len(), 7);
+        assert_eq!(clocks[0], 180_030_000);
+
+        let evals = game.evals.unwrap();
+        assert_eq!(
+            evals[0],
+            LichessEval {
+                cp: Some(30),
+                mate: None
+            }
+        );
+        assert_eq!(
+            evals[5],
+            LichessEval {
+                cp: None,
+                mate: Some(1)
+            }
+        );
+
+        let game = parse_lichess_game(&fixture_lines()[1]);
+        assert_eq!(game.clocks, None);
+        assert_eq!(game.evals, None);
+    }
+
+    #[test]
+    fn test_parse_lichess_game_converts_uci_moves() {
+        let game = parse_lichess_game(&fixture_lines()[1]);
+        let record = &game.record;
+        assert_eq!(record.event.as_deref(), Some("Casual Rapid game"));
+        assert_eq!(record.white.as_deref(), Some("lichess AI level 3"));
+        assert_eq!(record.white_elo, None);
+        assert_eq!(record.result.as_deref(), Some("1/2-1/2"));
+        assert_eq!(record.movetext, "1. e4 e5 2. Nf3 Nc6 3. Bc4 Nf6 4. O-O");
+    }
+
+    #[test]
+    fn test_parse_lichess_game_reports_errors() {
+        let lines = fixture_lines();
+        let game = parse_lichess_game(&lines[3]);
+        assert!(
+            game.record
+                .parse_error
+                .as_deref()
+                .is_some_and(|err| err.starts_with("Invalid Lichess game:"))
+        );
+        assert_eq!(game.record.white, None);
+
+        let game = parse_lichess_game(&lines[4]);
+        assert_eq!(game.record.movetext, "1. e4 e5");
+        assert_eq!(
+            game.record.parse_error.as_deref(),
+            Some("Illegal move 'Ke3' at ply 3")
+        );
+        assert_eq!(game.record.result.as_deref(), Some("0-1"));
+        assert_eq!(game.record.time_control.as_deref(), Some("-"));
+
+        let game = parse_lichess_game(r#"{"variant":"atomic","moves":"e4 e5"}"#);
+        assert_eq!(
+            game.record.parse_error.as_deref(),
+            Some("Unsupported variant 'atomic'; movetext was not converted")
+        );
+        assert_eq!(game.record.result.as_deref(), Some("*"));
+    }
+
+    #[test]
+    fn test_parse_lichess_game_starts_from_initial_fen() {
+        let game = parse_lichess_game(
+            r#"{"variant":"fromPosition","initialFen":"4k3/8/8/8/8/8/4P3/4K3 b - - 0 12","moves":"Kd7 e4"}"#,
+        );
+        assert_eq!(game.record.movetext, "12... Kd7 13. e4");
+        assert_eq!(game.record.parse_error, None);
+    }
+}
@@ -0,0 +1,272 @@
+use super::{
+    lichess::{LichessGame, parse_lichess_game},
+    log,
+    reader::{
+        ChunkWriter, CompressionMode, OptionalColumns, PATH_PATTERN_PARAM_INDEX,
+        READ_PGN_DEFAULT_SCHEMA_VERSION, apply_log_level_setting, expand_path_pattern,
+        open_input_stream, read_pgn_columns, resolve_compression_mode,
+        resolve_file_discovery_options,
+    },
+    visitor::PgnInput,
+};
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vtab::{BindInfo, InitInfo, TableFunctionInfo, VTab},
+};
+use libduckdb_sys::duckdb_interval;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+const CLOCKS_COLUMN: &str = "clocks";
+const EVALS_COLUMN: &str = "evals";
+
+#[repr(C)]
+pub struct ReadLichessNdjsonBindData {
+    paths: Vec<PathBuf>,
+    compression: CompressionMode,
+}
+
+#[repr(C)]
+pub struct ReadLichessNdjsonInitData {
+    state: Mutex<NdjsonScanState>,
+}
+
+pub struct ReadLichessNdjsonVTab;
+
+struct NdjsonScanState {
+    next_path_idx: usize,
+    current: Option<NdjsonReader>,
+}
+
+struct NdjsonReader {
+    input: BufReader<PgnInput>,
+    path_idx: usize,
+    line_number: u64,
+}
+
+fn lock_scan_state(state: &Mutex<NdjsonScanState>) -> MutexGuard<'_, NdjsonScanState> {
+    match state.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            log::warn("Recovering poisoned read_lichess_ndjson state mutex");
+            poisoned.into_inner()
+        }
+    }
+}
+
+/// Read the next non-blank line as a game, moving on to the next file at EOF.
+///
+/// Unreadable files are skipped with a warning when several paths were matched; a single
+/// explicit path fails hard, like `read_pgn`. I/O errors mid-file end that file with an error row.
+fn read_next_game(
+    state: &mut NdjsonScanState,
+    bind_data: &ReadLichessNdjsonBindData,
+) -> Result<Option<LichessGame>, Box<dyn std::error::Error>> {
+    let mut line = Vec::new();
+    loop {
+        let Some(reader) = state.current.as_mut() else {
+            let Some(path) = bind_data.paths.get(state.next_path_idx) else {
+                return Ok(None);
+            };
+            let path_idx = state.next_path_idx;
+            state.next_path_idx += 1;
+            match open_input_stream(path, bind_data.compression, 0, false, None) {
+                Ok(input) => {
+                    state.current = Some(NdjsonReader {
+                        input: BufReader::new(input),
+                        path_idx,
+                        line_number: 0,
+                    });
+                }
+                Err(err_msg) if bind_data.paths.len() == 1 => return Err(err_msg.into()),
+                Err(err_msg) => log::warn(&err_msg),
+            }
+            continue;
+        };
+
+        line.clear();
+        match reader.input.read_until(b'\n', &mut line) {
+            Ok(0) => state.current = None,
+            Ok(_) => {
+                reader.line_number += 1;
+                let text = String::from_utf8_lossy(&line);
+                if !text.trim().is_empty() {
+                    return Ok(Some(parse_lichess_game(&text)));
+                }
+            }
+            Err(error) => {
+                let error_msg = format!(
+                    "Read error: file='{}'; line={}; error={}",
+                    bind_data.paths[reader.path_idx].display(),
+                    reader.line_number + 1,
+                    error
+                );
+                log::warn(&error_msg);
+                state.current = None;
+                return Ok(Some(LichessGame::from_error(error_msg)));
+            }
+        }
+    }
+}
+
+fn write_clocks(output: &DataChunkHandle, column: usize, games: &[LichessGame]) {
+    let total: usize = games
+        .iter()
+        .filter_map(|g| g.clocks.as_ref())
+        .map(Vec::len)
+        .sum();
+    let mut list_vec = output.list_vector(column);
+    let mut child = list_vec.child(total);
+
+    let mut offset = 0;
+    for (row, game) in games.iter().enumerate() {
+        let Some(clocks) = &game.clocks else {
+            list_vec.set_null(row);
+            list_vec.set_entry(row, offset, 0);
+            continue;
+        };
+
+        list_vec.set_entry(row, offset, clocks.len());
+        for &micros in clocks {
+            child.as_mut_slice_with_len::<duckdb_interval>(total)[offset] = duckdb_interval {
+                months: 0,
+                days: 0,
+                micros,
+            };
+            offset += 1;
+        }
+    }
+    list_vec.set_len(offset);
+}
+
+fn write_evals(output: &DataChunkHandle, column: usize, games: &[LichessGame]) {
+    let total: usize = games
+        .iter()
+        .filter_map(|g| g.evals.as_ref())
+        .map(Vec::len)
+        .sum();
+    let mut list_vec = output.list_vector(column);
+    let struct_vec = list_vec.struct_child(total);
+    let mut cp_vec = struct_vec.child(0, total);
+    let mut mate_vec = struct_vec.child(1, total);
+
+    let mut offset = 0;
+    for (row, game) in games.iter().enumerate() {
+        let Some(evals) = &game.evals else {
+            list_vec.set_null(row);
+            list_vec.set_entry(row, offset, 0);
+            continue;
+        };
+
+        list_vec.set_entry(row, offset, evals.len());
+        for eval in evals {
+            for (vector, value) in [(&mut cp_vec, eval.cp), (&mut mate_vec, eval.mate)] {
+                match value {
+                    Some(value) => vector.as_mut_slice_with_len::<i32>(total)[offset] = value,
+                    None => vector.set_null(offset),
+                }
+            }
+            offset += 1;
+        }
+    }
+    list_vec.set_len(offset);
+}
+
+impl VTab for ReadLichessNdjsonVTab {
+    type InitData = ReadLichessNdjsonInitData;
+    type BindData = ReadLichessNdjsonBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        let pattern = bind.get_parameter(PATH_PATTERN_PARAM_INDEX).to_string();
+        apply_log_level_setting(bind)?;
+        let compression = resolve_compression_mode(bind)?;
+        let discovery = resolve_file_discovery_options(bind)?;
+        let paths = expand_path_pattern(&pattern, discovery)?;
+
+        for column in read_pgn_columns(READ_PGN_DEFAULT_SCHEMA_VERSION) {
+            bind.add_result_column(column.name, column.logical_type_handle());
+        }
+        bind.add_result_column(
+            CLOCKS_COLUMN,
+            LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Interval)),
+        );
+        bind.add_result_column(
+            EVALS_COLUMN,
+            LogicalTypeHandle::list(&LogicalTypeHandle::struct_type(&[
+                ("cp", LogicalTypeHandle::from(LogicalTypeId::Integer)),
+                ("mate", LogicalTypeHandle::from(LogicalTypeId::Integer)),
+            ])),
+        );
+
+        Ok(ReadLichessNdjsonBindData { paths, compression })
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(ReadLichessNdjsonInitData {
+            state: Mutex::new(NdjsonScanState {
+                next_path_idx: 0,
+                current: None,
+            }),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let init_data = func.get_init_data();
+        let bind_data = func.get_bind_data();
+        let capacity = output.flat_vector(0).capacity();
+
+        let mut games = Vec::with_capacity(capacity);
+        {
+            let mut state = lock_scan_state(&init_data.state);
+            while games.len() < capacity {
+                match read_next_game(&mut state, bind_data)? {
+                    Some(game) => games.push(game),
+                    None => break,
+                }
+            }
+        }
+
+        let mut chunk_writer = ChunkWriter::new(
+            output,
+            READ_PGN_DEFAULT_SCHEMA_VERSION,
+            OptionalColumns::default(),
+            &[],
+        );
+        for game in &games {
+            chunk_writer.write_row(&game.record);
+        }
+        chunk_writer.set_output_len();
+
+        let clocks_column = read_pgn_columns(READ_PGN_DEFAULT_SCHEMA_VERSION).count();
+        write_clocks(output, clocks_column, &games);
+        write_evals(output, clocks_column + 1, &games);
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![
+            LogicalTypeHandle::from(LogicalTypeId::Varchar), // path pattern (required)
+        ])
+    }
+
+    fn named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+        Some(vec![
+            (
+                "compression".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "follow_symlinks".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "skip_hidden".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+        ])
+    }
+}
@@ -1,5 +1,6 @@
 use std::env;
 use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU8, Ordering};
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(u8)]
@@ -9,24 +10,59 @@ enum Level {
 }
 
 impl Level {
-    fn from_str(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
-            "error" | "err" => Self::Error,
-            "warn" | "warning" => Self::Warn,
-            _ => Self::Error,
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "error" | "err" => Some(Self::Error),
+            "warn" | "warning" => Some(Self::Warn),
+            _ => None,
+        }
+    }
+
+    fn from_u8(raw: u8) -> Self {
+        if raw == Self::Warn as u8 {
+            Self::Warn
+        } else {
+            Self::Error
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warn => "warn",
         }
     }
 }
 
-static CHESS_LOG: LazyLock<Level> = LazyLock::new(|| {
-    env::var("CHESS_LOG")
-        .map(|s| Level::from_str(&s))
-        .unwrap_or(Level::Error)
+// Starts from `CHESS_LOG`; `set_level` (driven by `duckdb_chess_log_level`) overrides it.
+static CHESS_LOG: LazyLock<AtomicU8> = LazyLock::new(|| {
+    let level = env::var("CHESS_LOG")
+        .ok()
+        .and_then(|s| Level::parse(&s))
+        .unwrap_or(Level::Error);
+    AtomicU8::new(level as u8)
 });
 
+fn current_level() -> Level {
+    Level::from_u8(CHESS_LOG.load(Ordering::Relaxed))
+}
+
+/// Name of the active log level (`error` or `warn`).
+pub fn level_name() -> &'static str {
+    current_level().name()
+}
+
+/// Set the process-wide log level from a level name.
+pub fn set_level(raw: &str) -> Result<(), String> {
+    let level = Level::parse(raw)
+        .ok_or_else(|| format!("Invalid log level '{raw}'. Supported values: 'error', 'warn'."))?;
+    CHESS_LOG.store(level as u8, Ordering::Relaxed);
+    Ok(())
+}
+
 macro_rules! log {
     ($level:expr, $prefix:expr, $msg:expr) => {
-        if *CHESS_LOG >= $level {
+        if current_level() >= $level {
             eprintln!(concat!($prefix, ": {}"), $msg.as_ref());
         }
     };
@@ -37,3 +73,16 @@ pub fn error(msg: impl AsRef<str>) {
 pub fn warn(msg: impl AsRef<str>) {
     log!(Level::Warn, "WARN", msg);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_parse_accepts_aliases() {
+        assert!(Level::parse("WARNING") == Some(Level::Warn));
+        assert!(Level::parse(" err ") == Some(Level::Error));
+        assert!(Level::parse("debug").is_none());
+        assert!(set_level("verbose").is_err());
+    }
+}
@@ -0,0 +1,142 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{BigintInput, VarcharInput, invoke_binary_nullable};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use shakmaty::{CastlingMode, Chess, Position, fen::Fen};
+#[cfg(feature = "extension")]
+use std::error::Error;
+
+/// Deepest supported search, in moves of the side to move; mate in 4 can already take seconds
+/// for a single quiet position.
+pub const MAX_MATE_MOVES: i64 = 3;
+
+/// Whether the side to move in `fen` can force checkmate within `moves` of its own moves,
+/// against every defence. `None` for an invalid FEN.
+/// Spec: move-analysis - Forced Mate Search
+///
+/// A shorter mate also counts, so a mate in 1 is a mate in 3. Stalemate and the other draw
+/// rules are not mates; the halfmove clock and repetitions are ignored.
+pub fn has_mate_in(fen: &str, moves: i64) -> Result<Option<bool>, String> {
+    if !(1..=MAX_MATE_MOVES).contains(&moves) {
+        return Err(format!(
+            "chess_has_mate_in: invalid n {moves}. Expected 1 to {MAX_MATE_MOVES}."
+        ));
+    }
+
+    let Some(pos) = fen
+        .trim()
+        .parse::<Fen>()
+        .ok()
+        .and_then(|fen| fen.into_position::<Chess>(CastlingMode::Standard).ok())
+    else {
+        return Ok(None);
+    };
+    Ok(Some(mates_within(&pos, moves as u32)))
+}
+
+/// Some move of the side to move mates now or leaves every reply mated within `moves - 1`.
+fn mates_within(pos: &Chess, moves: u32) -> bool {
+    pos.legal_moves().into_iter().any(|m| {
+        let mut after = pos.clone();
+        after.play_unchecked(m);
+        if after.is_checkmate() {
+            return true;
+        }
+        if moves == 1 {
+            return false;
+        }
+
+        let replies = after.legal_moves();
+        // No replies without check is stalemate.
+        !replies.is_empty()
+            && replies.into_iter().all(|reply| {
+                let mut next = after.clone();
+                next.play_unchecked(reply);
+                mates_within(&next, moves - 1)
+            })
+    })
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessHasMateInScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessHasMateInScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_binary_nullable::<VarcharInput, BigintInput, _, _>(input, output, |fen, moves| {
+            Ok(has_mate_in(fen, moves)?)
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Boolean),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_mate_in() {
+        // Back-rank mate in 1: Re8#.
+        let back_rank = "6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1";
+        assert_eq!(has_mate_in(back_rank, 1), Ok(Some(true)));
+        assert_eq!(has_mate_in(back_rank, 3), Ok(Some(true)));
+
+        // Mate in 2 with the king and rook against a cornered king: 1. Kb6 Kb8 2. Rd8#.
+        let mate_in_2 = "k7/8/2K5/8/8/8/8/3R4 w - - 0 1";
+        assert_eq!(has_mate_in(mate_in_2, 1), Ok(Some(false)));
+        assert_eq!(has_mate_in(mate_in_2, 2), Ok(Some(true)));
+
+        // Start position, and a side that is already mated.
+        assert_eq!(
+            has_mate_in(
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                2
+            ),
+            Ok(Some(false))
+        );
+        assert_eq!(
+            has_mate_in("4R1k1/5ppp/8/8/8/8/8/6K1 b - - 1 1", 3),
+            Ok(Some(false))
+        );
+    }
+
+    #[test]
+    fn test_has_mate_in_stalemate_is_not_mate() {
+        // Black to move is stalemated, so there is nothing to search.
+        assert_eq!(
+            has_mate_in("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1", 3),
+            Ok(Some(false))
+        );
+        // Lone kings cannot mate.
+        assert_eq!(
+            has_mate_in("k7/8/1K6/8/8/8/8/8 w - - 0 1", 3),
+            Ok(Some(false))
+        );
+    }
+
+    #[test]
+    fn test_has_mate_in_invalid_input() {
+        assert_eq!(has_mate_in("not a fen", 1), Ok(None));
+        assert!(has_mate_in("4R1k1/5ppp/8/8/8/8/8/6K1 b - - 1 1", 0).is_err());
+        assert!(has_mate_in("4R1k1/5ppp/8/8/8/8/8/6K1 b - - 1 1", 4).is_err());
+    }
+}
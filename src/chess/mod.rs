@@ -1,53 +1,80 @@
+// Most of the pure-Rust core (no `extension` feature) is only reachable through the DuckDB
+// bindings, so it would otherwise be reported as dead code.
+#![cfg_attr(not(feature = "extension"), allow(dead_code))]
+
+mod accuracy;
+mod activity;
+mod boundary;
+mod clock;
+mod compress;
+#[cfg(feature = "compression")]
+mod compression;
+mod control;
+#[cfg(feature = "extension")]
+mod diagnostics;
+mod draws;
+#[cfg(feature = "extension")]
 #[path = "duckdb/mod.rs"]
 mod duckdb_impl;
+mod elo;
+mod engine;
 mod error;
+mod event;
+mod export;
+#[cfg(feature = "extension")]
+mod extension;
+mod features;
+mod fen;
 mod filter;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fuzzing;
+mod game_iter;
+mod headers;
+mod language;
+mod lichess;
+#[cfg(feature = "extension")]
+mod lichess_reader;
 mod log;
+mod mate;
 mod moves;
+mod openings;
+#[cfg(feature = "extension")]
+mod parse_pgn;
+#[cfg(feature = "extension")]
+mod perft;
+#[cfg(feature = "extension")]
+mod pgncache;
+#[cfg(feature = "extension")]
+mod pgncache_reader;
+mod phase;
+mod positions;
+mod prefetch;
+mod puzzles;
+mod quality;
+#[cfg(feature = "extension")]
 mod reader;
+mod repertoire;
+#[cfg(feature = "extension")]
+mod repertoire_reader;
+mod replay;
+#[cfg(feature = "extension")]
+mod settings;
+mod style;
+#[cfg(feature = "extension")]
+mod tablebase;
+#[cfg(feature = "extension")]
+mod tags_reader;
 mod timecontrol;
+mod tokens;
 mod types;
+mod validate;
+#[cfg(feature = "extension")]
+mod version;
 mod visitor;
 
 pub use error::ErrorAccumulator;
-
-use ::duckdb::{Connection, Result};
-use duckdb_ext_macros::duckdb_extension;
-use filter::ChessMovesNormalizeScalar;
-use moves::{
-    ChessFenEpdScalar, ChessMovesHashScalar, ChessMovesJsonScalar, ChessMovesSubsetScalar,
-    ChessPlyCountScalar,
+pub use game_iter::{PgnGameIter, PgnReadOptions};
+pub use types::{
+    ExtraTagColumn, ExtraTagType, ExtraTagValue, GameRecord, MoveRecord, PgnDate, PgnTimeTz,
 };
-use reader::ReadPgnVTab;
-use std::error::Error;
-use timecontrol::{
-    ChessTimecontrolCategoryScalar, ChessTimecontrolJsonScalar, ChessTimecontrolNormalizeScalar,
-};
-
-#[duckdb_extension(name = "chess")]
-pub unsafe fn extension_entrypoint(con: Connection) -> Result<(), Box<dyn Error>> {
-    // Table functions
-    con.register_table_function::<ReadPgnVTab>("read_pgn")?;
-
-    // Scalar functions
-    // Register internal implementations, then expose stable public names via SQL macros.
-    // This avoids DuckDB's default NULL-in-NULL-out behavior for scalar functions.
-    con.register_scalar_function::<ChessMovesJsonScalar>("chess_moves_json_impl")?;
-    con.register_scalar_function::<ChessMovesNormalizeScalar>("chess_moves_normalize")?;
-    con.register_scalar_function::<ChessMovesHashScalar>("chess_moves_hash")?;
-    con.register_scalar_function::<ChessMovesSubsetScalar>("chess_moves_subset")?;
-    con.register_scalar_function::<ChessFenEpdScalar>("chess_fen_epd")?;
-    con.register_scalar_function::<ChessPlyCountScalar>("chess_ply_count_impl")?;
-    con.register_scalar_function::<ChessTimecontrolNormalizeScalar>("chess_timecontrol_normalize")?;
-    con.register_scalar_function::<ChessTimecontrolJsonScalar>("chess_timecontrol_json")?;
-    con.register_scalar_function::<ChessTimecontrolCategoryScalar>("chess_timecontrol_category")?;
-
-    con.execute_batch(
-        "CREATE OR REPLACE MACRO chess_moves_json(movetext, max_ply := NULL) AS
-           chess_moves_json_impl(coalesce(movetext, ''), coalesce(max_ply, 9223372036854775807));
-         CREATE OR REPLACE MACRO chess_ply_count(movetext) AS
-           chess_ply_count_impl(coalesce(movetext, ''));",
-    )?;
-
-    Ok(())
-}
+pub use visitor::{MovetextStyle, VariantFilter};
@@ -1,3 +1,4 @@
+#[cfg(feature = "extension")]
 use ::duckdb::{
     Result,
     core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
@@ -5,26 +6,38 @@ use ::duckdb::{
     vtab::arrow::WritableVector,
 };
 use pgn_reader::{Nag, RawComment, Reader, SanPlus as PgnSanPlus, Skip, Visitor};
-use shakmaty::{Chess, EnPassantMode, Position, fen::Fen, san::SanPlus, zobrist::Zobrist64};
+use shakmaty::{
+    CastlingMode, Chess, EnPassantMode, Position, fen::Fen, san::SanPlus, zobrist::Zobrist64,
+};
 use smallvec::SmallVec;
+use std::cell::OnceCell;
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::Write;
 use std::io;
 use std::ops::ControlFlow;
 
+#[cfg(feature = "extension")]
 use super::duckdb_impl::scalar::{
-    VarcharNullBehavior, VarcharOutput, invoke_binary_varchar_varchar_to_bool_nullable,
-    invoke_unary_varchar_optional_i64_to_varchar, invoke_unary_varchar_to_i64_default,
-    invoke_unary_varchar_to_u64_nullable, invoke_unary_varchar_to_varchar,
+    VarcharInput, VarcharOutput, invoke_binary_varchar_list_i64_to_varchar_i32_map,
+    invoke_binary_varchar_list_varchar_to_i64_nullable,
+    invoke_binary_varchar_varchar_to_bool_nullable, invoke_unary_nullable,
+    invoke_unary_varchar_optional_i64_to_varchar, invoke_unary_varchar_to_u64_nullable,
+    invoke_unary_varchar_to_varchar, invoke_unary_varchar_to_varchar_list_nullable,
 };
+#[cfg(feature = "extension")]
 use super::log;
+use crate::chess::clock::{MICROS_PER_SECOND, parse_clk_micros};
 use crate::chess::filter::parse_movetext_mainline;
+use crate::chess::puzzles::{Eval, parse_eval};
 use crate::pgn_visitor_skip_variations;
 
 type MoveList = SmallVec<[String; 128]>;
 
+#[cfg(feature = "extension")]
 pub struct ChessMovesJsonScalar;
 
+#[cfg(feature = "extension")]
 impl VScalar for ChessMovesJsonScalar {
     type State = ();
 
@@ -35,11 +48,8 @@ impl VScalar for ChessMovesJsonScalar {
     ) -> Result<(), Box<dyn Error>> {
         let mut logged_error = false;
 
-        invoke_unary_varchar_optional_i64_to_varchar(
-            input,
-            output,
-            VarcharNullBehavior::Static("[]"),
-            |movetext, max_ply| match process_moves_with_limit(movetext, max_ply) {
+        invoke_unary_varchar_optional_i64_to_varchar(input, output, |movetext, max_ply| {
+            match process_moves_with_limit(movetext, max_ply) {
                 Ok(json) => Ok(VarcharOutput::Value(json)),
                 Err(e) => {
                     if !logged_error {
@@ -48,8 +58,8 @@ impl VScalar for ChessMovesJsonScalar {
                     }
                     Ok(VarcharOutput::Value("[]".to_string()))
                 }
-            },
-        )
+            }
+        })
     }
 
     fn signatures() -> Vec<ScalarFunctionSignature> {
@@ -97,6 +107,10 @@ struct MovesJsonVisitor {
     first: bool,
     ply: usize,
     max_ply: Option<usize>,
+    /// The last move's object is left open until its comments have been read.
+    open_move: bool,
+    clk_micros: Option<i64>,
+    eval: Option<Eval>,
 }
 
 impl MovesJsonVisitor {
@@ -107,6 +121,9 @@ impl MovesJsonVisitor {
             first: true,
             ply: 0,
             max_ply,
+            open_move: false,
+            clk_micros: None,
+            eval: None,
         };
         visitor.reset();
         visitor
@@ -118,9 +135,42 @@ impl MovesJsonVisitor {
         self.json.push('[');
         self.first = true;
         self.ply = 0;
+        self.open_move = false;
+        self.clk_micros = None;
+        self.eval = None;
+    }
+
+    /// Close the open move object with the annotations read from its comments.
+    fn close_move(&mut self) {
+        if !std::mem::take(&mut self.open_move) {
+            return;
+        }
+        if let Some(micros) = self.clk_micros.take() {
+            let _ = write!(
+                self.json,
+                r#","clk_seconds":{}"#,
+                micros / MICROS_PER_SECOND
+            );
+            let fraction = micros % MICROS_PER_SECOND;
+            if fraction != 0 {
+                let digits = format!("{fraction:06}");
+                let _ = write!(self.json, ".{}", digits.trim_end_matches('0'));
+            }
+        }
+        match self.eval.take() {
+            Some(Eval::Centipawns(cp)) => {
+                let _ = write!(self.json, r#","eval_cp":{cp}"#);
+            }
+            Some(Eval::Mate(moves)) => {
+                let _ = write!(self.json, r#","eval_mate":{moves}"#);
+            }
+            None => {}
+        }
+        self.json.push('}');
     }
 
     fn finish(mut self) -> String {
+        self.close_move();
         self.json.push(']');
         self.json
     }
@@ -159,6 +209,7 @@ impl Visitor for MovesJsonVisitor {
         self.position.play_unchecked(next_move);
         self.ply += 1;
 
+        self.close_move();
         if !self.first {
             self.json.push(',');
         }
@@ -169,14 +220,45 @@ impl Visitor for MovesJsonVisitor {
 
         let _ = write!(
             self.json,
-            r#"{{"ply":{},"move":"{}","fen":"{}","epd":"{}"}}"#,
+            r#"{{"ply":{},"move":"{}","fen":"{}","epd":"{}""#,
             self.ply, san_plus, fen, epd
         );
+        self.open_move = true;
 
         ControlFlow::Continue(())
     }
 
-    pgn_visitor_skip_variations!();
+    fn comment(
+        &mut self,
+        _movetext: &mut Self::Movetext,
+        comment: RawComment<'_>,
+    ) -> ControlFlow<Self::Output> {
+        if self.open_move {
+            let comment = String::from_utf8_lossy(comment.as_bytes());
+            self.clk_micros = self.clk_micros.or_else(|| parse_clk_micros(&comment));
+            self.eval = self.eval.or_else(|| parse_eval(&comment));
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn partial_comment(
+        &mut self,
+        _movetext: &mut Self::Movetext,
+        _comment: RawComment<'_>,
+    ) -> ControlFlow<Self::Output> {
+        ControlFlow::Continue(())
+    }
+
+    fn nag(&mut self, _movetext: &mut Self::Movetext, _nag: Nag) -> ControlFlow<Self::Output> {
+        ControlFlow::Continue(())
+    }
+
+    fn begin_variation(
+        &mut self,
+        _movetext: &mut Self::Movetext,
+    ) -> ControlFlow<Self::Output, Skip> {
+        ControlFlow::Continue(Skip(true))
+    }
 
     fn end_game(&mut self, _movetext: Self::Movetext) -> Self::Output {}
 }
@@ -206,8 +288,10 @@ fn fen_to_epd(fen: &str) -> Option<String> {
 }
 
 // Spec: move-analysis - FEN to EPD
+#[cfg(feature = "extension")]
 pub struct ChessFenEpdScalar;
 
+#[cfg(feature = "extension")]
 impl VScalar for ChessFenEpdScalar {
     type State = ();
 
@@ -216,7 +300,7 @@ impl VScalar for ChessFenEpdScalar {
         input: &mut DataChunkHandle,
         output: &mut dyn WritableVector,
     ) -> Result<(), Box<dyn Error>> {
-        invoke_unary_varchar_to_varchar(input, output, VarcharNullBehavior::Null, |fen| {
+        invoke_unary_varchar_to_varchar(input, output, |fen| {
             Ok(match fen_to_epd(fen) {
                 Some(epd) => VarcharOutput::Value(epd),
                 None => VarcharOutput::Null,
@@ -233,8 +317,10 @@ impl VScalar for ChessFenEpdScalar {
 }
 
 // Spec: move-analysis - Ply Count
+#[cfg(feature = "extension")]
 pub struct ChessPlyCountScalar;
 
+#[cfg(feature = "extension")]
 impl VScalar for ChessPlyCountScalar {
     type State = ();
 
@@ -243,7 +329,9 @@ impl VScalar for ChessPlyCountScalar {
         input: &mut DataChunkHandle,
         output: &mut dyn WritableVector,
     ) -> Result<(), Box<dyn Error>> {
-        invoke_unary_varchar_to_i64_default(input, output, 0, ply_count)
+        invoke_unary_nullable::<VarcharInput, _, _>(input, output, |movetext| {
+            Ok(Some(ply_count(movetext)))
+        })
     }
 
     fn signatures() -> Vec<ScalarFunctionSignature> {
@@ -254,7 +342,7 @@ impl VScalar for ChessPlyCountScalar {
     }
 }
 
-fn ply_count(movetext: &str) -> i64 {
+pub(crate) fn ply_count(movetext: &str) -> i64 {
     if movetext.trim().is_empty() {
         return 0;
     }
@@ -301,10 +389,74 @@ impl Visitor for PlyCountVisitor {
     fn end_game(&mut self, _movetext: Self::Movetext) -> Self::Output {}
 }
 
+#[cfg(feature = "extension")]
+pub struct ChessMovesSanFrequenciesScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessMovesSanFrequenciesScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_binary_varchar_list_i64_to_varchar_i32_map(input, output, |movetexts, max_ply| {
+            Some(san_frequencies(
+                movetexts.iter().copied(),
+                usize::try_from(max_ply).unwrap_or(0),
+            ))
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)), // movetexts
+                LogicalTypeHandle::from(LogicalTypeId::Bigint), // max_ply
+            ],
+            LogicalTypeHandle::map(
+                &LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                &LogicalTypeHandle::from(LogicalTypeId::Integer),
+            ),
+        )]
+    }
+}
+
+/// How often each SAN occurs in the first `max_ply` mainline plies of `movetexts`, most
+/// frequent first and ties by SAN.
+/// Spec: move-analysis - SAN Frequencies
+///
+/// NULL movetexts and movetexts that do not parse are skipped. Counts beyond `i32::MAX`
+/// saturate.
+pub(crate) fn san_frequencies<'a>(
+    movetexts: impl IntoIterator<Item = Option<&'a str>>,
+    max_ply: usize,
+) -> Vec<(String, i32)> {
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    for movetext in movetexts.into_iter().flatten() {
+        let parsed = parse_movetext_mainline(movetext);
+        if parsed.parse_error {
+            continue;
+        }
+        for san in parsed.sans.into_iter().take(max_ply) {
+            *counts.entry(san).or_default() += 1;
+        }
+    }
+
+    let mut frequencies: Vec<(String, i32)> = counts
+        .into_iter()
+        .map(|(san, count)| (san, i32::try_from(count).unwrap_or(i32::MAX)))
+        .collect();
+    frequencies.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    frequencies
+}
+
 // Spec: move-analysis - Moves Hashing
+#[cfg(feature = "extension")]
 pub struct ChessMovesHashScalar;
 
-fn zobrist_hash_of_position(pos: &Chess) -> u64 {
+pub(crate) fn zobrist_hash_of_position(pos: &Chess) -> u64 {
     let Zobrist64(v) = pos.zobrist_hash::<Zobrist64>(EnPassantMode::Legal);
     v
 }
@@ -357,7 +509,7 @@ impl Visitor for ZobristHashVisitor {
     fn end_game(&mut self, _movetext: Self::Movetext) -> Self::Output {}
 }
 
-fn movetext_final_zobrist_hash(movetext: &str) -> Option<u64> {
+pub(crate) fn movetext_final_zobrist_hash(movetext: &str) -> Option<u64> {
     if movetext.trim().is_empty() {
         return None;
     }
@@ -373,6 +525,7 @@ fn movetext_final_zobrist_hash(movetext: &str) -> Option<u64> {
     }
 }
 
+#[cfg(feature = "extension")]
 impl VScalar for ChessMovesHashScalar {
     type State = ();
 
@@ -393,8 +546,10 @@ impl VScalar for ChessMovesHashScalar {
 }
 
 // Spec: move-analysis - Subsumption Detection
+#[cfg(feature = "extension")]
 pub struct ChessMovesSubsetScalar;
 
+#[cfg(feature = "extension")]
 impl VScalar for ChessMovesSubsetScalar {
     type State = ();
 
@@ -403,37 +558,107 @@ impl VScalar for ChessMovesSubsetScalar {
         input: &mut DataChunkHandle,
         output: &mut dyn WritableVector,
     ) -> Result<(), Box<dyn Error>> {
+        if input.flat_vector(0).logical_type().id() == LogicalTypeId::List {
+            return invoke_binary_varchar_list_varchar_to_i64_nullable(
+                input,
+                output,
+                |lines, movetext| {
+                    first_matching_line(lines, movetext).and_then(|idx| i64::try_from(idx).ok())
+                },
+            );
+        }
+
         invoke_binary_varchar_varchar_to_bool_nullable(input, output, check_moves_subset)
     }
 
     fn signatures() -> Vec<ScalarFunctionSignature> {
-        vec![ScalarFunctionSignature::exact(
-            vec![
-                LogicalTypeHandle::from(LogicalTypeId::Varchar),
-                LogicalTypeHandle::from(LogicalTypeId::Varchar),
-            ],
-            LogicalTypeHandle::from(LogicalTypeId::Boolean),
-        )]
+        vec![
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+            ),
+        ]
     }
 }
 
-fn check_moves_subset(short_movetext: &str, long_movetext: &str) -> bool {
-    if let Some(fast_result) = check_moves_subset_fast(short_movetext, long_movetext) {
-        return fast_result;
-    }
+pub(crate) fn check_moves_subset(short_movetext: &str, long_movetext: &str) -> bool {
+    SubsetTarget::new(long_movetext).starts_with(short_movetext)
+}
 
-    check_moves_subset_with_parser(short_movetext, long_movetext)
+/// Return the 1-based index of the first line in `lines` that is a mainline prefix of
+/// `movetext`. NULL lines never match.
+fn first_matching_line(lines: &[Option<&str>], movetext: &str) -> Option<usize> {
+    let target = SubsetTarget::new(movetext);
+    lines
+        .iter()
+        .position(|line| line.is_some_and(|line| target.starts_with(line)))
+        .map(|idx| idx + 1)
 }
 
-fn check_moves_subset_fast(short_movetext: &str, long_movetext: &str) -> Option<bool> {
-    if !is_clean_mainline_movetext(short_movetext) || !is_clean_mainline_movetext(long_movetext) {
-        return None;
+/// The long side of a subset check, with its mainline SANs extracted lazily and reused
+/// across several candidate prefixes.
+struct SubsetTarget<'a> {
+    movetext: &'a str,
+    is_clean: bool,
+    fast_sans: OnceCell<Option<MoveList>>,
+    parsed_sans: OnceCell<Option<MoveList>>,
+}
+
+impl<'a> SubsetTarget<'a> {
+    fn new(movetext: &'a str) -> Self {
+        Self {
+            movetext,
+            is_clean: is_clean_mainline_movetext(movetext),
+            fast_sans: OnceCell::new(),
+            parsed_sans: OnceCell::new(),
+        }
+    }
+
+    fn starts_with(&self, short_movetext: &str) -> bool {
+        if let Some(fast_result) = self.starts_with_fast(short_movetext) {
+            return fast_result;
+        }
+
+        self.starts_with_parsed(short_movetext)
+    }
+
+    fn starts_with_parsed(&self, short_movetext: &str) -> bool {
+        let Some(long_moves) = self
+            .parsed_sans
+            .get_or_init(|| parse_subset_sans(self.movetext))
+        else {
+            return false;
+        };
+        let Some(short_moves) = parse_subset_sans(short_movetext) else {
+            return false;
+        };
+
+        is_prefix_subset(&short_moves, long_moves)
     }
 
-    let short_moves = extract_clean_mainline_sans(short_movetext)?;
-    let long_moves = extract_clean_mainline_sans(long_movetext)?;
+    fn starts_with_fast(&self, short_movetext: &str) -> Option<bool> {
+        if !self.is_clean || !is_clean_mainline_movetext(short_movetext) {
+            return None;
+        }
 
-    Some(is_prefix_subset(&short_moves, &long_moves))
+        let short_moves = extract_clean_mainline_sans(short_movetext)?;
+        let long_moves = self
+            .fast_sans
+            .get_or_init(|| extract_clean_mainline_sans(self.movetext))
+            .as_ref()?;
+
+        Some(is_prefix_subset(&short_moves, long_moves))
+    }
 }
 
 fn is_clean_mainline_movetext(movetext: &str) -> bool {
@@ -477,7 +702,7 @@ fn is_uncertain_syntax_char(c: char) -> bool {
     matches!(c, '{' | '}' | '(' | ')' | '$' | '!' | '?' | ';')
 }
 
-fn is_move_number_token(token: &str) -> bool {
+pub(crate) fn is_move_number_token(token: &str) -> bool {
     let Some(first_dot_index) = token.find('.') else {
         return false;
     };
@@ -494,11 +719,11 @@ fn is_move_number_token(token: &str) -> bool {
     dots == "." || dots == "..."
 }
 
-fn is_result_marker(token: &str) -> bool {
+pub(crate) fn is_result_marker(token: &str) -> bool {
     matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*")
 }
 
-fn looks_like_san_token(token: &str) -> bool {
+pub(crate) fn looks_like_san_token(token: &str) -> bool {
     if token.is_empty() || !token.is_ascii() || token.contains('.') {
         return false;
     }
@@ -571,31 +796,142 @@ fn is_prefix_subset(short_moves: &[String], long_moves: &[String]) -> bool {
         .all(|(short, long)| short == long)
 }
 
-fn check_moves_subset_with_parser(short_movetext: &str, long_movetext: &str) -> bool {
-    let short_parsed = parse_movetext_mainline(short_movetext);
-    let long_parsed = parse_movetext_mainline(long_movetext);
-    let short_non_empty = !short_movetext.trim().is_empty();
-    let long_non_empty = !long_movetext.trim().is_empty();
+/// Parse movetext for a subset check; `None` when the movetext cannot be parsed.
+fn parse_subset_sans(movetext: &str) -> Option<MoveList> {
+    let parsed = parse_movetext_mainline(movetext);
+    let non_empty = !movetext.trim().is_empty();
+    let parse_failed =
+        parsed.parse_error || (non_empty && parsed.sans.is_empty() && parsed.outcome.is_none());
 
-    let short_parse_failed = short_parsed.parse_error
-        || (short_non_empty && short_parsed.sans.is_empty() && short_parsed.outcome.is_none());
-    let long_parse_failed = long_parsed.parse_error
-        || (long_non_empty && long_parsed.sans.is_empty() && long_parsed.outcome.is_none());
+    if parse_failed {
+        None
+    } else {
+        Some(parsed.sans)
+    }
+}
 
-    if short_parse_failed {
+// Spec: move-analysis - Legal Continuation
+#[cfg(feature = "extension")]
+pub struct ChessSanIsLegalScalar;
+
+/// Whether `san` is a legal move in the position reached by the mainline of
+/// `movetext_prefix`. A prefix that does not parse or replay reaches no position, so every
+/// candidate is rejected.
+pub(crate) fn san_is_legal(movetext_prefix: &str, san: &str) -> bool {
+    let Some(prefix_sans) = parse_subset_sans(movetext_prefix) else {
         return false;
+    };
+    let mut pos = Chess::default();
+    for prefix_san in &prefix_sans {
+        match prefix_san
+            .parse::<SanPlus>()
+            .ok()
+            .and_then(|san_plus| san_plus.san.to_move(&pos).ok())
+        {
+            Some(m) => pos.play_unchecked(m),
+            None => return false,
+        }
     }
-    if long_parse_failed {
-        return false;
+
+    san.trim()
+        .parse::<SanPlus>()
+        .is_ok_and(|san_plus| san_plus.san.to_move(&pos).is_ok())
+}
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessSanIsLegalScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_binary_varchar_varchar_to_bool_nullable(input, output, san_is_legal)
     }
 
-    is_prefix_subset(&short_parsed.sans, &long_parsed.sans)
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Boolean),
+        )]
+    }
+}
+
+/// Legal moves of the side to move in `fen`, as SAN with check and mate suffixes, sorted so the
+/// list is stable. `None` for an invalid FEN; checkmate and stalemate give an empty list.
+pub fn legal_moves(fen: &str) -> Option<Vec<String>> {
+    let pos = fen
+        .trim()
+        .parse::<Fen>()
+        .ok()?
+        .into_position::<Chess>(CastlingMode::Standard)
+        .ok()?;
+    let mut sans: Vec<String> = pos
+        .legal_moves()
+        .into_iter()
+        .map(|m| SanPlus::from_move(pos.clone(), m).to_string())
+        .collect();
+    sans.sort_unstable();
+    Some(sans)
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessLegalMovesScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessLegalMovesScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_unary_varchar_to_varchar_list_nullable(input, output, |fen| Ok(legal_moves(fen)))
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+        )]
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_legal_moves_lists_sorted_san() {
+        let start =
+            legal_moves("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        assert_eq!(start.len(), 20);
+        assert_eq!(&start[..4], ["Na3", "Nc3", "Nf3", "Nh3"]);
+        assert!(start.contains(&"e4".to_string()));
+
+        // Check and mate suffixes and castling; the king blocks the h1 rook from b1.
+        let moves = legal_moves("6k1/5ppp/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        assert!(moves.contains(&"Ra8#".to_string()));
+        assert!(moves.contains(&"O-O".to_string()));
+        assert!(moves.contains(&"O-O-O".to_string()));
+        assert!(moves.contains(&"Rb1".to_string()));
+
+        // Checkmated and stalemated sides have no moves.
+        assert_eq!(
+            legal_moves("R5k1/5ppp/8/8/8/8/8/4K3 b - - 1 1"),
+            Some(vec![])
+        );
+        assert_eq!(legal_moves("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"), Some(vec![]));
+
+        assert_eq!(legal_moves("not a fen"), None);
+        assert_eq!(legal_moves("8/8/8/8/8/8/8/8 w - - 0 1"), None);
+    }
+
     #[test]
     fn test_process_moves_basic() {
         let input = "1. e4 e5";
@@ -650,6 +986,36 @@ mod tests {
         assert!(!json.contains("INVALID"));
     }
 
+    #[test]
+    fn test_process_moves_includes_clock_and_eval_annotations() {
+        let input = "1. e4 { [%eval 0.17] [%clk 0:03:00] } e5 { [%clk 0:02:59.5] } \
+            2. Qh5 { [%eval #-3] } ( 2. Nf3 { [%eval 0.3] } ) Nc6 { no annotation } *";
+        let json = process_moves_with_limit(input, None).unwrap();
+        assert!(json.contains(r#""ply":1,"move":"e4""#));
+        assert!(
+            json.contains(r#""clk_seconds":180,"eval_cp":17}"#),
+            "{json}"
+        );
+        assert!(json.contains(r#""clk_seconds":179.5}"#), "{json}");
+        assert!(json.contains(r#""eval_mate":-3}"#), "{json}");
+        assert!(!json.contains(r#""eval_cp":30"#), "{json}");
+
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let moves = parsed.as_array().unwrap();
+        assert_eq!(moves.len(), 4);
+        assert!(moves[3].get("clk_seconds").is_none());
+        assert!(moves[3].get("eval_cp").is_none());
+    }
+
+    #[test]
+    fn test_process_moves_annotations_survive_max_ply() {
+        let input = "1. e4 { [%clk 0:01:00] } e5 { [%clk 0:00:59] } *";
+        let json = process_moves_with_limit(input, Some(1)).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.as_array().unwrap().len(), 1);
+        assert_eq!(parsed[0]["clk_seconds"], 60);
+    }
+
     #[test]
     fn test_process_moves_malformed_non_pgn_returns_empty_array() {
         let json = process_moves_with_limit("this is not movetext", None).unwrap();
@@ -698,6 +1064,45 @@ mod tests {
         assert_eq!(ply_count("   \n\t"), 0);
     }
 
+    #[test]
+    fn test_san_frequencies_counts_across_games() {
+        let games = [
+            Some("1. e4 e5 2. Nf3 Nc6 1-0"),
+            Some("1. e4 c5 2. Nf3 d6 *"),
+            None,
+            Some("1. d4 { quiet } Nf6 2. Nf3 e6 0-1"),
+        ];
+        assert_eq!(
+            san_frequencies(games, usize::MAX),
+            vec![
+                ("Nf3".to_string(), 3),
+                ("e4".to_string(), 2),
+                ("Nc6".to_string(), 1),
+                ("Nf6".to_string(), 1),
+                ("c5".to_string(), 1),
+                ("d4".to_string(), 1),
+                ("d6".to_string(), 1),
+                ("e5".to_string(), 1),
+                ("e6".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            san_frequencies(games, 1),
+            vec![("e4".to_string(), 2), ("d4".to_string(), 1)]
+        );
+        assert!(san_frequencies(games, 0).is_empty());
+    }
+
+    #[test]
+    fn test_san_frequencies_skips_unparseable_movetext() {
+        let games = [Some("1. e4 { unterminated comment"), Some("1. e4 e5")];
+        assert_eq!(
+            san_frequencies(games, usize::MAX),
+            vec![("e4".to_string(), 1), ("e5".to_string(), 1)]
+        );
+        assert!(san_frequencies([], usize::MAX).is_empty());
+    }
+
     #[test]
     fn test_chess_moves_hash_consistency_formatting() {
         // Test identical moves with different formatting produce same hash
@@ -809,6 +1214,32 @@ mod tests {
         assert!(check_moves_subset("", ""));
     }
 
+    #[test]
+    fn test_first_matching_line_returns_one_based_index() {
+        let lines = [
+            Some("1. d4"),
+            Some("1. e4 c5"),
+            Some("1. e4 e5"),
+            Some("1. e4"),
+        ];
+        assert_eq!(first_matching_line(&lines, "1. e4 e5 2. Nf3 Nc6"), Some(3));
+        assert_eq!(first_matching_line(&lines, "1. c4 e5"), None);
+        assert_eq!(first_matching_line(&[], "1. e4"), None);
+    }
+
+    #[test]
+    fn test_first_matching_line_skips_null_and_noisy_lines() {
+        let lines = [
+            None,
+            Some("not movetext"),
+            Some("1. e4 {main} e5! (1... c5)"),
+        ];
+        assert_eq!(
+            first_matching_line(&lines, "1. e4 e5 2. Nf3 {comment} Nc6"),
+            Some(3)
+        );
+    }
+
     #[test]
     fn test_chess_moves_subset_invalid_non_empty_short() {
         assert!(!check_moves_subset("not movetext", "1. e4"));
@@ -824,6 +1255,14 @@ mod tests {
         assert!(!check_moves_subset("not movetext", "still not movetext"));
     }
 
+    fn check_moves_subset_fast(short_movetext: &str, long_movetext: &str) -> Option<bool> {
+        SubsetTarget::new(long_movetext).starts_with_fast(short_movetext)
+    }
+
+    fn check_moves_subset_with_parser(short_movetext: &str, long_movetext: &str) -> bool {
+        SubsetTarget::new(long_movetext).starts_with_parsed(short_movetext)
+    }
+
     #[test]
     fn test_chess_moves_subset_fast_path_clean_equivalence() {
         let cases = [
@@ -895,4 +1334,26 @@ mod tests {
         assert!(!is_clean_mainline_movetext("1. e4! e5?"));
         assert!(!is_clean_mainline_movetext("not movetext"));
     }
+
+    #[test]
+    fn test_san_is_legal_after_prefix() {
+        assert!(san_is_legal("", "e4"));
+        assert!(san_is_legal("1. e4 e5 2. Nf3", " Nc6 "));
+        assert!(san_is_legal(
+            "1. e4 { main } e5 (1... c5) 2. Nf3 Nc6 3. Bb5 a6",
+            "Bxc6"
+        ));
+        assert!(san_is_legal("1. e4 e5 2. Nf3 Nc6 3. Bc4 Nf6", "O-O"));
+        assert!(!san_is_legal("1. e4 e5 2. Nf3 Nc6", "O-O"));
+        assert!(!san_is_legal("1. e4 e5", "e5"));
+        assert!(!san_is_legal("1. e4 e5", "not a move"));
+    }
+
+    #[test]
+    fn test_san_is_legal_rejects_unplayable_prefixes() {
+        assert!(!san_is_legal("1. e4 e4", "Nf3"));
+        assert!(!san_is_legal("1. e4 {", "e5"));
+        // No move is legal once the prefix has ended the game.
+        assert!(!san_is_legal("1. f3 e5 2. g4 Qh4#", "Kf2"));
+    }
 }
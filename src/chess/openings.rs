@@ -0,0 +1,463 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{
+    VarcharInput, invoke_binary_to_struct_nullable, invoke_ternary_nullable,
+};
+use super::filter::parse_movetext_mainline;
+use super::moves::zobrist_hash_of_position;
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use shakmaty::{Chess, Position, san::San};
+use std::collections::{BTreeSet, HashMap};
+#[cfg(feature = "extension")]
+use std::error::Error;
+use std::fs;
+use std::ops::Bound;
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
+
+/// Named openings in the `eco`/`name`/`pgn` TSV layout of lichess-org/chess-openings (CC0): a
+/// subset of the main lines, not the full dataset, which can be loaded as a custom book.
+const OPENINGS_TSV: &str = include_str!("data/openings.tsv");
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Opening<'a> {
+    pub eco: &'a str,
+    pub name: &'a str,
+}
+
+/// An immutable opening tree built from a TSV book.
+/// Spec: move-analysis - Opening Book
+pub struct OpeningBook {
+    /// ECO code and name of every book line that names a new position.
+    openings: Vec<(Box<str>, Box<str>)>,
+    /// Indexes into `openings` keyed by the Zobrist hash of the position their line ends in, so
+    /// transposed move orders find the same name.
+    by_position: HashMap<u64, usize>,
+    /// Longest line in the book; no later position can match.
+    max_plies: usize,
+    /// ECO codes of the book lines.
+    ecos: BTreeSet<Box<str>>,
+    /// ECO codes of the book lines through each position of the book, keyed like
+    /// `by_position`.
+    continuations: HashMap<u64, BTreeSet<Box<str>>>,
+}
+
+static BUNDLED_BOOK: LazyLock<Arc<OpeningBook>> =
+    LazyLock::new(|| Arc::new(OpeningBook::from_tsv(OPENINGS_TSV)));
+
+/// The bundled opening book, built on first use.
+pub(crate) fn bundled_book() -> &'static OpeningBook {
+    &BUNDLED_BOOK
+}
+
+fn play_san(pos: &mut Chess, san: &str) -> bool {
+    san.parse::<San>().is_ok_and(|san| play(pos, &san))
+}
+
+fn play(pos: &mut Chess, san: &San) -> bool {
+    match san.to_move(pos) {
+        Ok(m) => {
+            pos.play_unchecked(m);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+impl OpeningBook {
+    /// Build a book from `eco`/`name`/`pgn` rows after a header line. Rows whose line does not
+    /// replay are skipped; the first row naming a position wins.
+    pub fn from_tsv(tsv: &str) -> Self {
+        let mut book = OpeningBook {
+            openings: Vec::new(),
+            by_position: HashMap::new(),
+            max_plies: 0,
+            ecos: BTreeSet::new(),
+            continuations: HashMap::new(),
+        };
+        for row in tsv.lines().skip(1) {
+            let mut fields = row.trim_end_matches('\r').split('\t');
+            let (Some(eco), Some(name), Some(pgn)) = (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+
+            let parsed = parse_movetext_mainline(pgn);
+            let mut pos = Chess::default();
+            let mut line = Vec::with_capacity(parsed.sans.len());
+            let replayed = !parsed.parse_error
+                && parsed.sans.iter().all(|san| {
+                    let played = play_san(&mut pos, san);
+                    line.push(zobrist_hash_of_position(&pos));
+                    played
+                });
+            if !replayed || parsed.sans.is_empty() {
+                continue;
+            }
+
+            book.max_plies = book.max_plies.max(parsed.sans.len());
+            book.ecos.insert(eco.into());
+            for hash in line {
+                book.continuations
+                    .entry(hash)
+                    .or_default()
+                    .insert(eco.into());
+            }
+            book.by_position
+                .entry(zobrist_hash_of_position(&pos))
+                .or_insert_with(|| {
+                    book.openings.push((eco.into(), name.into()));
+                    book.openings.len() - 1
+                });
+        }
+        book
+    }
+
+    /// Whether no row of the book replayed.
+    pub fn is_empty(&self) -> bool {
+        self.openings.is_empty()
+    }
+
+    /// Longest line in the book; later plies never change the opening of a game.
+    pub fn max_plies(&self) -> usize {
+        self.max_plies
+    }
+
+    /// The named openings the mainline passes through, in move order and keyed by position,
+    /// replaying at most as many plies as the longest book line. Replay stops at the first
+    /// illegal move.
+    fn opening_path(&self, movetext: &str) -> Vec<(u64, Opening<'_>)> {
+        let parsed = parse_movetext_mainline(movetext);
+        self.san_path(parsed.sans.iter().map(|san| san.parse::<San>().ok()))
+    }
+
+    /// [`Self::opening_path`] over already parsed mainline moves; `None` stops the replay like
+    /// an illegal move.
+    fn san_path(&self, sans: impl IntoIterator<Item = Option<San>>) -> Vec<(u64, Opening<'_>)> {
+        let mut pos = Chess::default();
+        let mut path = Vec::new();
+        for san in sans.into_iter().take(self.max_plies) {
+            if !san.is_some_and(|san| play(&mut pos, &san)) {
+                break;
+            }
+            let hash = zobrist_hash_of_position(&pos);
+            if let Some(&idx) = self.by_position.get(&hash) {
+                let (eco, name) = &self.openings[idx];
+                path.push((hash, Opening { eco, name }));
+            }
+        }
+        path
+    }
+
+    /// The last position of the mainline that is a named opening.
+    /// Spec: move-analysis - Opening Name
+    pub fn opening_name(&self, movetext: &str) -> Option<Opening<'_>> {
+        self.opening_path(movetext)
+            .pop()
+            .map(|(_, opening)| opening)
+    }
+
+    /// [`Self::opening_name`] of the mainline moves `sans`.
+    pub fn opening_of_sans(&self, sans: &[San]) -> Option<Opening<'_>> {
+        self.san_path(sans.iter().copied().map(Some))
+            .pop()
+            .map(|(_, opening)| opening)
+    }
+
+    /// Check a tagged ECO code against the moves; returns the ECO of the deepest named opening
+    /// the mainline reaches when the tag contradicts it, and `None` when the tag is consistent
+    /// or the game reaches no named opening.
+    /// Spec: move-analysis - ECO Validation
+    ///
+    /// A tag is consistent when it is the code of an opening on the mainline, or a higher code
+    /// of the same volume that may name a variation past the deepest opening reached: the book
+    /// is too coarse to refute a line it does not list. Such a tag is only contradicted by a
+    /// book code between the two whose line leaves that deepest position's subtree, e.g. `C11`
+    /// for a game that reached the French Advance (`C02`). Tags are compared
+    /// case-insensitively; malformed or empty tags are always corrected.
+    pub fn eco_validate(&self, tag: &str, movetext: &str) -> Option<&str> {
+        let path = self.opening_path(movetext);
+        let &(deepest_hash, Opening { eco: deepest, .. }) = path.last()?;
+        let tag = tag.trim().to_ascii_uppercase();
+        if path.iter().any(|(_, opening)| opening.eco == tag) {
+            return None;
+        }
+
+        let continuations = &self.continuations[&deepest_hash];
+        let refines_deepest = is_eco_code(&tag)
+            && tag.as_bytes()[0] == deepest.as_bytes()[0]
+            && tag.as_str() > deepest
+            && self
+                .ecos
+                .range::<str, _>((Bound::Included(deepest), Bound::Included(tag.as_str())))
+                .all(|eco| continuations.contains(eco));
+        (!refines_deepest).then_some(deepest)
+    }
+}
+
+/// `A00` to `E99`.
+fn is_eco_code(code: &str) -> bool {
+    matches!(code.as_bytes(), [b'A'..=b'E', tens, ones] if tens.is_ascii_digit() && ones.is_ascii_digit())
+}
+
+/// Opening books by path, shared by every scalar call of a database: the bundled book is built
+/// on first use and each custom file is read once, so rows never rebuild or re-read a book.
+/// The empty path selects the bundled book. Clones share the same books.
+#[derive(Clone, Default)]
+pub struct OpeningBooks {
+    custom: Arc<Mutex<HashMap<String, Arc<OpeningBook>>>>,
+}
+
+impl OpeningBooks {
+    /// The book at `path`, loading it on first use. Failed loads are not cached, so fixing the
+    /// file and retrying works without reloading the extension.
+    pub fn get(&self, path: &str) -> Result<Arc<OpeningBook>, String> {
+        let path = path.trim();
+        if path.is_empty() {
+            return Ok(Arc::clone(&BUNDLED_BOOK));
+        }
+
+        // Held while loading so concurrent first calls read the file once.
+        let mut custom = self.custom.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(book) = custom.get(path) {
+            return Ok(Arc::clone(book));
+        }
+        let tsv = fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read opening book '{path}': {e}"))?;
+        let book = OpeningBook::from_tsv(&tsv);
+        if book.is_empty() {
+            return Err(format!(
+                "Opening book '{path}' has no playable eco/name/pgn rows"
+            ));
+        }
+        let book = Arc::new(book);
+        custom.insert(path.to_string(), Arc::clone(&book));
+        Ok(book)
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessOpeningNameScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessOpeningNameScalar {
+    type State = OpeningBooks;
+
+    unsafe fn invoke(
+        books: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_binary_to_struct_nullable::<VarcharInput, VarcharInput, _, _, 2>(
+            input,
+            output,
+            |movetext, book_path| {
+                let book = books.get(book_path)?;
+                Ok(book
+                    .opening_name(movetext)
+                    .map(|opening| [opening.eco.to_string(), opening.name.to_string()]))
+            },
+        )
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ],
+            LogicalTypeHandle::struct_type(&[
+                ("eco", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                ("name", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ]),
+        )]
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessEcoValidateScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessEcoValidateScalar {
+    type State = OpeningBooks;
+
+    unsafe fn invoke(
+        books: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_ternary_nullable::<VarcharInput, VarcharInput, VarcharInput, _, _>(
+            input,
+            output,
+            |eco, movetext, book_path| {
+                let book = books.get(book_path)?;
+                Ok(book.eco_validate(eco, movetext).map(str::to_string))
+            },
+        )
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(movetext: &str) -> Option<(&'static str, &'static str)> {
+        BUNDLED_BOOK
+            .opening_name(movetext)
+            .map(|opening| (opening.eco, opening.name))
+    }
+
+    #[test]
+    fn test_embedded_book_lines_all_replay() {
+        let rows = OPENINGS_TSV.lines().skip(1).count();
+        assert!(rows > 100);
+        // Every row names a distinct position.
+        assert_eq!(BUNDLED_BOOK.openings.len(), rows);
+        assert_eq!(BUNDLED_BOOK.max_plies, 11);
+    }
+
+    #[test]
+    fn test_opening_name_returns_deepest_match() {
+        assert_eq!(
+            named("1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 a6 6. Be3 e5 7. Nb3 Be6"),
+            Some(("B90", "Sicilian Defense: Najdorf Variation"))
+        );
+        // Annotations and the result marker are ignored.
+        assert_eq!(
+            named("1. e4 { [%clk 0:03:00] } e5 2. Nf3 Nc6 3. Bb5 $1 ( 3. Bc4 ) a6 1-0"),
+            Some(("C70", "Ruy Lopez: Morphy Defense"))
+        );
+        // Leaving the book keeps the last named position.
+        assert_eq!(
+            named("1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7#"),
+            Some(("C20", "King's Pawn Game"))
+        );
+    }
+
+    #[test]
+    fn test_opening_name_finds_transpositions() {
+        assert_eq!(
+            named("1. c4 e6 2. d4 d5"),
+            Some(("D30", "Queen's Gambit Declined"))
+        );
+    }
+
+    #[test]
+    fn test_opening_name_without_match() {
+        assert_eq!(named(""), None);
+        assert_eq!(named("1. e5"), None);
+        // Replay stops at the first illegal move.
+        assert_eq!(named("1. e4 Ke7 2. d4"), Some(("B00", "King's Pawn Game")));
+    }
+
+    const NAJDORF: &str = "1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 a6";
+    const MORPHY: &str = "1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O";
+
+    #[test]
+    fn test_eco_validate_accepts_codes_on_the_mainline() {
+        assert_eq!(BUNDLED_BOOK.eco_validate("B90", NAJDORF), None);
+        assert_eq!(BUNDLED_BOOK.eco_validate(" b90 ", NAJDORF), None);
+        // A coarser code of an opening the game passes through still matches.
+        assert_eq!(BUNDLED_BOOK.eco_validate("B20", NAJDORF), None);
+        assert_eq!(BUNDLED_BOOK.eco_validate("C20", MORPHY), None);
+    }
+
+    #[test]
+    fn test_eco_validate_accepts_variations_past_the_book() {
+        // Ruy Lopez lines after the Morphy Defense are not in the book.
+        assert_eq!(BUNDLED_BOOK.eco_validate("C78", MORPHY), None);
+        // The Evans Gambit continues from the Giuoco Piano, so C54 is not contradicted.
+        assert_eq!(
+            BUNDLED_BOOK.eco_validate("C54", "1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. c3 Nf6 5. d4"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_eco_validate_corrects_contradicted_tags() {
+        assert_eq!(BUNDLED_BOOK.eco_validate("C50", NAJDORF), Some("B90"));
+        assert_eq!(BUNDLED_BOOK.eco_validate("C50", MORPHY), Some("C70"));
+        // The Tarrasch (C03) leaves the Advance Variation's subtree.
+        assert_eq!(
+            BUNDLED_BOOK.eco_validate("C11", "1. e4 e6 2. d4 d5 3. e5 c5"),
+            Some("C02")
+        );
+        assert_eq!(BUNDLED_BOOK.eco_validate("", NAJDORF), Some("B90"));
+        assert_eq!(BUNDLED_BOOK.eco_validate("?", NAJDORF), Some("B90"));
+    }
+
+    #[test]
+    fn test_eco_validate_without_named_opening() {
+        assert_eq!(BUNDLED_BOOK.eco_validate("A00", ""), None);
+        assert_eq!(BUNDLED_BOOK.eco_validate("A00", "1. e5"), None);
+    }
+
+    const CUSTOM_TSV: &str = "eco\tname\tpgn\n\
+        B20\tSicilian\t1. e4 c5\r\n\
+        X99\tBroken line\t1. e4 e4\n\
+        B27\tHyperaccelerated\t1. e4 c5 2. Nf3 g6\n";
+
+    #[test]
+    fn test_custom_book_from_tsv() {
+        let book = OpeningBook::from_tsv(CUSTOM_TSV);
+        // The unplayable row is skipped.
+        assert_eq!(book.openings.len(), 2);
+        assert_eq!(
+            book.opening_name("1. e4 c5 2. Nf3 g6 3. d4"),
+            Some(Opening {
+                eco: "B27",
+                name: "Hyperaccelerated"
+            })
+        );
+        assert_eq!(book.opening_name("1. e4 e5"), None);
+        assert_eq!(book.eco_validate("C20", "1. e4 c5"), Some("B20"));
+        assert!(OpeningBook::from_tsv("eco\tname\tpgn\n").is_empty());
+    }
+
+    #[test]
+    fn test_opening_books_load_each_path_once() {
+        let books = OpeningBooks::default();
+        assert!(Arc::ptr_eq(&books.get("").unwrap(), &BUNDLED_BOOK));
+
+        let path =
+            std::env::temp_dir().join(format!("duckdb-chess-{}-openings.tsv", std::process::id()));
+        fs::write(&path, CUSTOM_TSV).unwrap();
+        let path = path.to_str().unwrap();
+        let first = books.get(path).unwrap();
+        // Clones share the cache, and the file is not read again.
+        fs::remove_file(path).unwrap();
+        let second = books.clone().get(path).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(second.openings.len(), 2);
+    }
+
+    #[test]
+    fn test_opening_books_report_unusable_files() {
+        let books = OpeningBooks::default();
+        let err = books.get("/nonexistent/openings.tsv").err().unwrap();
+        assert!(err.starts_with("Cannot read opening book '/nonexistent/openings.tsv'"));
+
+        let path = std::env::temp_dir().join(format!(
+            "duckdb-chess-{}-empty-openings.tsv",
+            std::process::id()
+        ));
+        fs::write(&path, "eco\tname\tpgn\n").unwrap();
+        let err = books.get(path.to_str().unwrap()).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(err.ends_with("has no playable eco/name/pgn rows"));
+    }
+}
@@ -0,0 +1,147 @@
+use super::{
+    duckdb_impl::string::decode_duckdb_bytes,
+    reader::{
+        ChunkWriter, READ_PGN_DEFAULT_SCHEMA_VERSION, ReadNextGameOutcome, read_next_game,
+        read_pgn_columns,
+    },
+    types::GameRecord,
+    visitor::{GameFilter, PgnReaderState, ScanOptions, VariantFilter},
+};
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use libduckdb_sys::duckdb_string_t;
+use std::error::Error;
+use std::io::Cursor;
+use std::path::Path;
+
+/// Stands in for the file path in `parse_error` messages of games parsed from a value.
+const PARSE_PGN_SOURCE: &str = "parse_pgn";
+
+/// Parse every game of a PGN text value, with the `read_pgn` defaults: standard chess only
+/// and numbered movetext.
+/// Spec: pgn-parsing - Parse PGN Values
+fn parse_games(pgn: &[u8]) -> Vec<GameRecord> {
+    let options = ScanOptions {
+        filter: GameFilter::default().with_variant(VariantFilter::Standard),
+        track_line_numbers: true,
+        ..ScanOptions::default()
+    };
+    let mut reader = PgnReaderState::new(Box::new(Cursor::new(pgn.to_vec())), 0, options);
+    let mut games = Vec::new();
+    loop {
+        match read_next_game(&mut reader, Path::new(PARSE_PGN_SOURCE)) {
+            ReadNextGameOutcome::GameReady => games.push(std::mem::take(&mut reader.record_buffer)),
+            ReadNextGameOutcome::GameSkipped => {}
+            ReadNextGameOutcome::ReaderFinished => return games,
+        }
+    }
+}
+
+/// `parse_pgn_impl(pgn)`: the games of a `VARCHAR` or `BLOB` PGN value as a list of structs
+/// with the default `read_pgn` columns. The `parse_pgn(games)` table macro unnests it.
+pub struct ParsePgnScalar;
+
+impl VScalar for ParsePgnScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let len = input.len();
+        let input_vec = input.flat_vector(0);
+        let input_slice = input_vec.as_slice::<duckdb_string_t>();
+
+        let mut rows = Vec::with_capacity(len);
+        for (i, s) in input_slice.iter().take(len).enumerate() {
+            if input_vec.row_is_null(i as u64) {
+                rows.push(None);
+                continue;
+            }
+
+            // SAFETY: Row nullability is checked above; VARCHAR and BLOB share the layout.
+            let pgn = unsafe { decode_duckdb_bytes(s) };
+            rows.push(Some(parse_games(pgn)));
+        }
+
+        let total: usize = rows.iter().flatten().map(Vec::len).sum();
+        let mut list_vec = output.list_vector();
+        let mut writer = ChunkWriter::for_struct(list_vec.struct_child(total), 0, total);
+        for (i, row) in rows.into_iter().enumerate() {
+            let offset = writer.row_count();
+            let Some(games) = row else {
+                list_vec.set_null(i);
+                list_vec.set_entry(i, offset, 0);
+                continue;
+            };
+
+            list_vec.set_entry(i, offset, games.len());
+            for game in &games {
+                writer.write_row(game);
+            }
+        }
+        list_vec.set_len(total);
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        [LogicalTypeId::Varchar, LogicalTypeId::Blob]
+            .into_iter()
+            .map(|input_type| {
+                ScalarFunctionSignature::exact(
+                    vec![LogicalTypeHandle::from(input_type)],
+                    games_type(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// `LIST(STRUCT(...))` with one field per default `read_pgn` column.
+fn games_type() -> LogicalTypeHandle {
+    let fields: Vec<_> = read_pgn_columns(READ_PGN_DEFAULT_SCHEMA_VERSION)
+        .map(|column| (column.name, column.logical_type_handle()))
+        .collect();
+    LogicalTypeHandle::list(&LogicalTypeHandle::struct_type(&fields))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_GAMES: &str = "[Event \"First\"]\n[White \"A\"]\n[Black \"B\"]\n[Result \"1-0\"]\n\n\
+        1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n\n\
+        [Event \"Second\"]\n[Result \"1/2-1/2\"]\n\n1. d4 d5 1/2-1/2\n";
+
+    #[test]
+    fn test_parse_games_reads_every_game_of_a_value() {
+        let games = parse_games(TWO_GAMES.as_bytes());
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].event.as_deref(), Some("First"));
+        assert_eq!(games[0].white.as_deref(), Some("A"));
+        assert_eq!(games[0].result.as_deref(), Some("1-0"));
+        assert_eq!(games[0].movetext, "1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7#");
+        assert_eq!(games[1].event.as_deref(), Some("Second"));
+        assert_eq!(games[1].movetext, "1. d4 d5");
+    }
+
+    #[test]
+    fn test_parse_games_empty_value_has_no_games() {
+        assert!(parse_games(b"").is_empty());
+        assert!(parse_games(b"   \n\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_games_skips_variants_like_read_pgn() {
+        let pgn = "[Event \"Crazy\"]\n[Variant \"Crazyhouse\"]\n[Result \"*\"]\n\n1. e4 *\n\n\
+            [Event \"Standard\"]\n[Result \"*\"]\n\n1. e4 *\n";
+        let games = parse_games(pgn.as_bytes());
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].event.as_deref(), Some("Standard"));
+    }
+}
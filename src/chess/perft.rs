@@ -0,0 +1,220 @@
+use super::duckdb_impl::bind_info_ffi;
+use duckdb::{
+    core::{DataChunkHandle, Inserter, LogicalTypeHandle, LogicalTypeId},
+    vtab::{BindInfo, InitInfo, TableFunctionInfo, VTab},
+};
+use shakmaty::{CastlingMode, Chess, Position, fen::Fen, perft};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Deepest supported search; the start position already has ~3.2 billion nodes at depth 7.
+const MAX_PERFT_DEPTH: i64 = 10;
+
+pub struct ChessPerftBindData {
+    position: Chess,
+    depth: u32,
+    divide: bool,
+}
+
+pub struct ChessPerftInitData {
+    done: AtomicBool,
+}
+
+pub struct ChessPerftVTab;
+
+/// One `chess_perft()` row; `uci` is set for the per-move split rows of `divide := true`.
+#[derive(Debug, PartialEq, Eq)]
+struct PerftRow {
+    depth: u32,
+    uci: Option<String>,
+    nodes: u64,
+}
+
+fn parse_perft_fen(fen: &str) -> Result<Chess, String> {
+    let fen = fen.trim();
+    fen.parse::<Fen>()
+        .map_err(|err| err.to_string())
+        .and_then(|parsed| {
+            let mode = CastlingMode::detect(parsed.as_setup());
+            parsed
+                .into_position::<Chess>(mode)
+                .map_err(|err| err.to_string())
+        })
+        .map_err(|err| format!("chess_perft: invalid FEN '{fen}': {err}"))
+}
+
+fn parse_perft_depth(depth: i64) -> Result<u32, String> {
+    if (1..=MAX_PERFT_DEPTH).contains(&depth) {
+        Ok(depth as u32)
+    } else {
+        Err(format!(
+            "chess_perft: invalid depth {depth}. Expected 1 to {MAX_PERFT_DEPTH}."
+        ))
+    }
+}
+
+/// Leaf node counts for every depth up to `depth`, followed by the per-move split at `depth`
+/// when `divide` is set (moves in UCI notation, sorted).
+/// Spec: move-analysis - Perft
+fn perft_rows(position: &Chess, depth: u32, divide: bool) -> Vec<PerftRow> {
+    let mut rows: Vec<PerftRow> = (1..=depth)
+        .map(|d| PerftRow {
+            depth: d,
+            uci: None,
+            nodes: perft(position, d),
+        })
+        .collect();
+
+    if divide {
+        let mode = position.castles().mode();
+        let mut splits: Vec<PerftRow> = position
+            .legal_moves()
+            .into_iter()
+            .map(|m| {
+                let mut child = position.clone();
+                child.play_unchecked(m);
+                PerftRow {
+                    depth,
+                    uci: Some(m.to_uci(mode).to_string()),
+                    nodes: perft(&child, depth - 1),
+                }
+            })
+            .collect();
+        splits.sort_by(|a, b| a.uci.cmp(&b.uci));
+        rows.extend(splits);
+    }
+    rows
+}
+
+impl VTab for ChessPerftVTab {
+    type InitData = ChessPerftInitData;
+    type BindData = ChessPerftBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        let position = parse_perft_fen(&bind.get_parameter(0).to_string())?;
+        let depth = parse_perft_depth(bind.get_parameter(1).to_int64())?;
+        let divide = bind_info_ffi::get_named_parameter_bool(bind, "divide")?.unwrap_or(false);
+
+        bind.add_result_column("depth", LogicalTypeHandle::from(LogicalTypeId::Integer));
+        bind.add_result_column("move", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("nodes", LogicalTypeHandle::from(LogicalTypeId::UBigint));
+
+        Ok(ChessPerftBindData {
+            position,
+            depth,
+            divide,
+        })
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(ChessPerftInitData {
+            done: AtomicBool::new(false),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if func.get_init_data().done.swap(true, Ordering::Relaxed) {
+            output.set_len(0);
+            return Ok(());
+        }
+
+        let bind_data = func.get_bind_data();
+        // At most MAX_PERFT_DEPTH totals plus one split per legal move (< 256), one chunk.
+        let rows = perft_rows(&bind_data.position, bind_data.depth, bind_data.divide);
+
+        let mut depth_vec = output.flat_vector(0);
+        let mut move_vec = output.flat_vector(1);
+        let mut nodes_vec = output.flat_vector(2);
+        for (row_idx, row) in rows.iter().enumerate() {
+            depth_vec.as_mut_slice::<i32>()[row_idx] = row.depth as i32;
+            match &row.uci {
+                Some(uci) => move_vec.insert(row_idx, uci.as_str()),
+                None => move_vec.set_null(row_idx),
+            }
+            nodes_vec.as_mut_slice::<u64>()[row_idx] = row.nodes;
+        }
+        output.set_len(rows.len());
+
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![
+            LogicalTypeHandle::from(LogicalTypeId::Varchar), // fen
+            LogicalTypeHandle::from(LogicalTypeId::Bigint),  // depth
+        ])
+    }
+
+    fn named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+        Some(vec![(
+            "divide".to_string(),
+            LogicalTypeHandle::from(LogicalTypeId::Boolean),
+        )])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    fn totals(fen: &str, depth: u32) -> Vec<u64> {
+        let position = parse_perft_fen(fen).unwrap();
+        perft_rows(&position, depth, false)
+            .into_iter()
+            .map(|row| row.nodes)
+            .collect()
+    }
+
+    #[test]
+    fn test_perft_rows_match_reference_counts() {
+        assert_eq!(
+            totals(
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                4
+            ),
+            vec![20, 400, 8_902, 197_281]
+        );
+        assert_eq!(totals(KIWIPETE, 3), vec![48, 2_039, 97_862]);
+        // Chess960 castling rights (Shredder-FEN) are detected from the FEN.
+        assert_eq!(
+            totals(
+                "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+                3
+            ),
+            vec![21, 528, 12_189]
+        );
+    }
+
+    #[test]
+    fn test_perft_divide_splits_sum_to_total() {
+        let position = parse_perft_fen(KIWIPETE).unwrap();
+        let rows = perft_rows(&position, 2, true);
+        let (totals, splits): (Vec<_>, Vec<_>) = rows.iter().partition(|row| row.uci.is_none());
+
+        assert_eq!(totals.len(), 2);
+        assert_eq!(splits.len(), 48);
+        assert_eq!(
+            splits.iter().map(|row| row.nodes).sum::<u64>(),
+            totals[1].nodes
+        );
+        assert!(splits.iter().any(|row| row.uci.as_deref() == Some("e1g1")));
+        assert!(splits.windows(2).all(|pair| pair[0].uci < pair[1].uci));
+    }
+
+    #[test]
+    fn test_perft_rejects_invalid_input() {
+        assert!(
+            parse_perft_fen("not a fen")
+                .unwrap_err()
+                .starts_with("chess_perft: invalid FEN 'not a fen'")
+        );
+        assert_eq!(parse_perft_depth(MAX_PERFT_DEPTH), Ok(10));
+        for depth in [0, -1, MAX_PERFT_DEPTH + 1] {
+            assert!(parse_perft_depth(depth).is_err());
+        }
+    }
+}
@@ -0,0 +1,893 @@
+//! `.pgncache` files: parsed `GameRecord`s stored column by column, so later sessions can
+//! skip PGN parsing.
+//!
+//! A file is the magic bytes and format version followed by blocks of up to
+//! [`PGN_CACHE_BLOCK_GAMES`] games. Each block starts with its game count and a zone map of
+//! the block's Elo, date and ECO ranges, then the byte length of its column frames: one
+//! zstd-compressed frame per column, each prefixed with its compressed length. Readers with a
+//! [`CacheFilter`] seek past blocks whose zone map rules out every game, like Parquet row-group
+//! pruning. Tag columns are dictionary-encoded per block, since values such as `Event`,
+//! `Result` or `TimeControl` repeat across games.
+//!
+//! Spec: pgn-parsing - PGN Cache Files
+
+use super::types::{GameRecord, PgnDate, PgnTimeTz};
+use chrono::{NaiveDate, TimeDelta};
+use std::collections::HashMap;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+const PGN_CACHE_MAGIC: &[u8; 8] = b"PGNCACHE";
+const PGN_CACHE_FORMAT_VERSION: u32 = 2;
+/// Games per block; one block fills one DuckDB vector when read back.
+pub(crate) const PGN_CACHE_BLOCK_GAMES: usize = 2048;
+/// zstd level of the column frames; favours write speed, the cache is rebuilt often.
+const COLUMN_COMPRESSION_LEVEL: i32 = 3;
+
+type TagField = (
+    fn(&GameRecord) -> &Option<String>,
+    fn(&mut GameRecord) -> &mut Option<String>,
+);
+
+/// Tag-valued columns, dictionary-encoded.
+const TAG_FIELDS: [TagField; 13] = [
+    (|g| &g.event, |g| &mut g.event),
+    (|g| &g.site, |g| &mut g.site),
+    (|g| &g.source, |g| &mut g.source),
+    (|g| &g.white, |g| &mut g.white),
+    (|g| &g.black, |g| &mut g.black),
+    (|g| &g.result, |g| &mut g.result),
+    (|g| &g.white_title, |g| &mut g.white_title),
+    (|g| &g.black_title, |g| &mut g.black_title),
+    (|g| &g.eco, |g| &mut g.eco),
+    (|g| &g.opening, |g| &mut g.opening),
+    (|g| &g.termination, |g| &mut g.termination),
+    (|g| &g.time_control, |g| &mut g.time_control),
+    (|g| &g.parse_error, |g| &mut g.parse_error),
+];
+
+type IntField = (fn(&GameRecord) -> Option<u64>, fn(&mut GameRecord, u64));
+
+/// Optional integer columns, stored as their bit patterns widened to `u64`.
+const INT_FIELDS: [IntField; 6] = [
+    (
+        |g| g.white_elo.map(u64::from),
+        |g, v| g.white_elo = Some(v as u32),
+    ),
+    (
+        |g| g.black_elo.map(u64::from),
+        |g, v| g.black_elo = Some(v as u32),
+    ),
+    (
+        |g| g.utc_date.map(|date| u64::from(date.days as u32)),
+        |g, v| {
+            g.utc_date = Some(PgnDate {
+                days: v as u32 as i32,
+            })
+        },
+    ),
+    (
+        |g| g.utc_time.map(|time| time.bits),
+        |g, v| g.utc_time = Some(PgnTimeTz { bits: v }),
+    ),
+    (|g| g.byte_offset, |g, v| g.byte_offset = Some(v)),
+    (|g| g.movetext_bytes, |g, v| g.movetext_bytes = Some(v)),
+];
+
+const HAS_CLK_FLAG: u8 = 1;
+const HAS_EVAL_FLAG: u8 = 1 << 1;
+const HAS_VARIATIONS_FLAG: u8 = 1 << 2;
+
+/// Tag columns, then movetext, the integer columns and the annotation flags.
+const COLUMN_COUNT: usize = TAG_FIELDS.len() + 1 + INT_FIELDS.len() + 1;
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid PGN cache: {}", message.into()),
+    )
+}
+
+/// Min/max of a column's non-NULL values within one block; `None` when all are NULL.
+type ZoneRange<T> = Option<(T, T)>;
+
+/// Per-block column statistics stored ahead of the column frames, so readers can rule out a
+/// block without decompressing it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct BlockZoneMap {
+    white_elo: ZoneRange<u32>,
+    black_elo: ZoneRange<u32>,
+    utc_date: ZoneRange<i32>,
+    eco: ZoneRange<String>,
+}
+
+fn widen<T: PartialOrd + Clone>(range: &mut ZoneRange<T>, value: &T) {
+    match range {
+        None => *range = Some((value.clone(), value.clone())),
+        Some((min, max)) => {
+            if value < min {
+                *min = value.clone();
+            }
+            if value > max {
+                *max = value.clone();
+            }
+        }
+    }
+}
+
+impl BlockZoneMap {
+    fn of(games: &[GameRecord]) -> Self {
+        let mut zone_map = Self::default();
+        for game in games {
+            if let Some(elo) = &game.white_elo {
+                widen(&mut zone_map.white_elo, elo);
+            }
+            if let Some(elo) = &game.black_elo {
+                widen(&mut zone_map.black_elo, elo);
+            }
+            if let Some(date) = &game.utc_date {
+                widen(&mut zone_map.utc_date, &date.days);
+            }
+            if let Some(eco) = &game.eco {
+                widen(&mut zone_map.eco, eco);
+            }
+        }
+        zone_map
+    }
+
+    /// A presence byte per range, followed by its bounds when present.
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let int_ranges = [
+            self.white_elo
+                .map(|(min, max)| (u64::from(min), u64::from(max))),
+            self.black_elo
+                .map(|(min, max)| (u64::from(min), u64::from(max))),
+            self.utc_date
+                .map(|(min, max)| (u64::from(min as u32), u64::from(max as u32))),
+        ];
+        for range in int_ranges {
+            bytes.push(range.is_some() as u8);
+            if let Some((min, max)) = range {
+                bytes.extend_from_slice(&min.to_le_bytes());
+                bytes.extend_from_slice(&max.to_le_bytes());
+            }
+        }
+        bytes.push(self.eco.is_some() as u8);
+        if let Some((min, max)) = &self.eco {
+            put_bytes(&mut bytes, min.as_bytes());
+            put_bytes(&mut bytes, max.as_bytes());
+        }
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let mut cursor = ColumnCursor { bytes, pos: 0 };
+        let elo = |(min, max): (u64, u64)| (min as u32, max as u32);
+        let zone_map = Self {
+            white_elo: cursor.range(ColumnCursor::u64)?.map(elo),
+            black_elo: cursor.range(ColumnCursor::u64)?.map(elo),
+            utc_date: cursor
+                .range(ColumnCursor::u64)?
+                .map(|(min, max)| (min as u32 as i32, max as u32 as i32)),
+            eco: cursor.range(ColumnCursor::string)?,
+        };
+        cursor.finish()?;
+        Ok(zone_map)
+    }
+}
+
+/// Games kept by `read_pgn_cache`. Bounds are inclusive, and a game missing a bounded value
+/// never matches: with Elo bounds, both players must be rated within them, like `read_pgn`'s
+/// `min_elo` / `max_elo`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct CacheFilter {
+    pub(crate) min_elo: Option<i64>,
+    pub(crate) max_elo: Option<i64>,
+    /// Bounds on `UTCDate`, in days since 1970-01-01.
+    pub(crate) min_date: Option<i32>,
+    pub(crate) max_date: Option<i32>,
+    pub(crate) min_eco: Option<String>,
+    pub(crate) max_eco: Option<String>,
+}
+
+/// Whether `range` overlaps the inclusive bounds. Without bounds anything overlaps; with
+/// bounds, an all-NULL range never does.
+fn overlaps<T: PartialOrd>(range: Option<(T, T)>, min: Option<T>, max: Option<T>) -> bool {
+    if min.is_none() && max.is_none() {
+        return true;
+    }
+    range.is_some_and(|(low, high)| {
+        min.is_none_or(|min| high >= min) && max.is_none_or(|max| low <= max)
+    })
+}
+
+impl CacheFilter {
+    /// Reject ranges whose lower bound exceeds the upper one, like `read_pgn`'s Elo bounds.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let format_date = |days: i32| {
+            NaiveDate::from_ymd_opt(1970, 1, 1)
+                .and_then(|epoch| epoch.checked_add_signed(TimeDelta::try_days(days.into())?))
+                .map_or_else(|| days.to_string(), |date| date.to_string())
+        };
+        let inverted = [
+            (self
+                .min_elo
+                .zip(self.max_elo)
+                .filter(|(min, max)| min > max))
+            .map(|(min, max)| ("Elo", "elo", min.to_string(), max.to_string())),
+            (self
+                .min_date
+                .zip(self.max_date)
+                .filter(|(min, max)| min > max))
+            .map(|(min, max)| ("date", "date", format_date(min), format_date(max))),
+            (self.min_eco.as_ref().zip(self.max_eco.as_ref()))
+                .filter(|(min, max)| min > max)
+                .map(|(min, max)| ("ECO", "eco", min.clone(), max.clone())),
+        ];
+        match inverted.into_iter().flatten().next() {
+            Some((label, param, min, max)) => Err(format!(
+                "Invalid {label} range: min_{param} ({min}) is greater than max_{param} ({max})."
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        *self != Self::default()
+    }
+
+    fn elo_bounds(&self, range: ZoneRange<u32>) -> bool {
+        let range = range.map(|(min, max)| (i64::from(min), i64::from(max)));
+        overlaps(range, self.min_elo, self.max_elo)
+    }
+
+    fn eco_bounds(&self, range: Option<(&str, &str)>) -> bool {
+        overlaps(range, self.min_eco.as_deref(), self.max_eco.as_deref())
+    }
+
+    /// Whether a block with this zone map may hold a matching game.
+    fn may_match(&self, zone_map: &BlockZoneMap) -> bool {
+        self.elo_bounds(zone_map.white_elo)
+            && self.elo_bounds(zone_map.black_elo)
+            && overlaps(zone_map.utc_date, self.min_date, self.max_date)
+            && self.eco_bounds(
+                zone_map
+                    .eco
+                    .as_ref()
+                    .map(|(min, max)| (min.as_str(), max.as_str())),
+            )
+    }
+
+    pub(crate) fn matches(&self, game: &GameRecord) -> bool {
+        fn point<T: Copy>(value: T) -> (T, T) {
+            (value, value)
+        }
+        self.elo_bounds(game.white_elo.map(point))
+            && self.elo_bounds(game.black_elo.map(point))
+            && overlaps(
+                game.utc_date.map(|date| point(date.days)),
+                self.min_date,
+                self.max_date,
+            )
+            && self.eco_bounds(game.eco.as_deref().map(point))
+    }
+}
+
+/// Streams games into a `.pgncache` file, one block at a time.
+///
+/// `white_elo_raw`, `black_elo_raw` and `extra_tags` are not stored.
+pub(crate) struct PgnCacheWriter<W: Write> {
+    output: W,
+    pending: Vec<GameRecord>,
+    games: u64,
+}
+
+impl<W: Write> PgnCacheWriter<W> {
+    pub(crate) fn new(mut output: W) -> io::Result<Self> {
+        output.write_all(PGN_CACHE_MAGIC)?;
+        output.write_all(&PGN_CACHE_FORMAT_VERSION.to_le_bytes())?;
+        Ok(Self {
+            output,
+            pending: Vec::with_capacity(PGN_CACHE_BLOCK_GAMES),
+            games: 0,
+        })
+    }
+
+    pub(crate) fn push(&mut self, game: GameRecord) -> io::Result<()> {
+        self.pending.push(game);
+        if self.pending.len() >= PGN_CACHE_BLOCK_GAMES {
+            self.write_block()?;
+        }
+        Ok(())
+    }
+
+    /// Write the last partial block and return the output and the number of games written.
+    pub(crate) fn finish(mut self) -> io::Result<(W, u64)> {
+        if !self.pending.is_empty() {
+            self.write_block()?;
+        }
+        self.output.flush()?;
+        Ok((self.output, self.games))
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        let count =
+            u32::try_from(self.pending.len()).map_err(|_| invalid_data("block too large"))?;
+        let zone_map = BlockZoneMap::of(&self.pending).encode();
+        let mut frames = Vec::new();
+        for column in encode_block(&self.pending) {
+            let frame = zstd::encode_all(column.as_slice(), COLUMN_COMPRESSION_LEVEL)?;
+            frames.extend_from_slice(&(frame.len() as u64).to_le_bytes());
+            frames.extend_from_slice(&frame);
+        }
+        self.output.write_all(&count.to_le_bytes())?;
+        self.output
+            .write_all(&(zone_map.len() as u32).to_le_bytes())?;
+        self.output.write_all(&zone_map)?;
+        self.output
+            .write_all(&(frames.len() as u64).to_le_bytes())?;
+        self.output.write_all(&frames)?;
+        self.games += self.pending.len() as u64;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+/// Reads a `.pgncache` file back one block at a time.
+pub(crate) struct PgnCacheReader<R: Read + Seek> {
+    input: R,
+    filter: CacheFilter,
+    /// Blocks skipped so far because their zone map ruled out every game.
+    blocks_skipped: u64,
+}
+
+impl<R: Read + Seek> PgnCacheReader<R> {
+    /// Check the magic bytes and format version.
+    pub(crate) fn new(mut input: R) -> io::Result<Self> {
+        let mut header = [0u8; 12];
+        input
+            .read_exact(&mut header)
+            .map_err(|_| invalid_data("missing header"))?;
+        if &header[..8] != PGN_CACHE_MAGIC {
+            return Err(invalid_data("not a .pgncache file"));
+        }
+        let version = u32::from_le_bytes(header[8..].try_into().expect("4 bytes"));
+        if version != PGN_CACHE_FORMAT_VERSION {
+            return Err(invalid_data(format!(
+                "unsupported format version {version} (expected {PGN_CACHE_FORMAT_VERSION})"
+            )));
+        }
+        Ok(Self {
+            input,
+            filter: CacheFilter::default(),
+            blocks_skipped: 0,
+        })
+    }
+
+    /// Only return games matching `filter`, skipping blocks it rules out unread.
+    pub(crate) fn with_filter(self, filter: CacheFilter) -> Self {
+        Self { filter, ..self }
+    }
+
+    /// The matching games of the next block that may hold any, or `None` at the end of the
+    /// file. The result is empty when the zone map admits the block but no game matches.
+    pub(crate) fn next_block(&mut self) -> io::Result<Option<Vec<GameRecord>>> {
+        loop {
+            let mut count = [0u8; 4];
+            match self.input.read_exact(&mut count) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(err) => return Err(err),
+            }
+            let count = u32::from_le_bytes(count) as usize;
+            if count == 0 || count > PGN_CACHE_BLOCK_GAMES {
+                return Err(invalid_data("block game count out of range"));
+            }
+
+            let zone_map_len = u32::from_le_bytes(self.read_array()?) as u64;
+            let zone_map = BlockZoneMap::decode(&self.read_frame(zone_map_len)?)?;
+            let frames_len = u64::from_le_bytes(self.read_array()?);
+            if !self.filter.may_match(&zone_map) {
+                let offset =
+                    i64::try_from(frames_len).map_err(|_| invalid_data("block too large"))?;
+                self.input.seek(SeekFrom::Current(offset))?;
+                self.blocks_skipped += 1;
+                continue;
+            }
+
+            let mut columns = Vec::with_capacity(COLUMN_COUNT);
+            let mut read_len = 0;
+            for _ in 0..COLUMN_COUNT {
+                let len = u64::from_le_bytes(self.read_array()?);
+                let frame = self.read_frame(len)?;
+                read_len += 8 + len;
+                columns.push(zstd::decode_all(frame.as_slice())?);
+            }
+            if read_len != frames_len {
+                return Err(invalid_data("block length mismatch"));
+            }
+
+            let mut games = decode_block(count, &columns)?;
+            if self.filter.is_active() {
+                games.retain(|game| self.filter.matches(game));
+            }
+            return Ok(Some(games));
+        }
+    }
+
+    fn read_array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut bytes = [0u8; N];
+        self.input
+            .read_exact(&mut bytes)
+            .map_err(|_| invalid_data("truncated block"))?;
+        Ok(bytes)
+    }
+
+    fn read_frame(&mut self, len: u64) -> io::Result<Vec<u8>> {
+        let mut frame = Vec::new();
+        (&mut self.input).take(len).read_to_end(&mut frame)?;
+        if frame.len() as u64 != len {
+            return Err(invalid_data("truncated block"));
+        }
+        Ok(frame)
+    }
+}
+
+fn encode_block(games: &[GameRecord]) -> Vec<Vec<u8>> {
+    let mut columns = Vec::with_capacity(COLUMN_COUNT);
+
+    for (get, _) in TAG_FIELDS {
+        // Code 0 is NULL; code `n` is the `n`-th dictionary entry.
+        let mut dictionary: HashMap<&str, u32> = HashMap::new();
+        let mut entries = Vec::new();
+        let mut codes = Vec::with_capacity(games.len() * 4);
+        for game in games {
+            let code = get(game).as_deref().map_or(0, |value| {
+                *dictionary.entry(value).or_insert_with(|| {
+                    entries.push(value);
+                    entries.len() as u32
+                })
+            });
+            codes.extend_from_slice(&code.to_le_bytes());
+        }
+
+        let mut column = Vec::new();
+        column.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        for entry in entries {
+            put_bytes(&mut column, entry.as_bytes());
+        }
+        column.extend_from_slice(&codes);
+        columns.push(column);
+    }
+
+    let mut movetext = Vec::new();
+    for game in games {
+        put_bytes(&mut movetext, game.movetext.as_bytes());
+    }
+    columns.push(movetext);
+
+    for (get, _) in INT_FIELDS {
+        // One presence byte per game, then the present values.
+        let values: Vec<Option<u64>> = games.iter().map(get).collect();
+        let mut column: Vec<u8> = values.iter().map(|value| value.is_some() as u8).collect();
+        for value in values.into_iter().flatten() {
+            column.extend_from_slice(&value.to_le_bytes());
+        }
+        columns.push(column);
+    }
+
+    columns.push(
+        games
+            .iter()
+            .map(|game| {
+                let mut flags = 0;
+                for (set, flag) in [
+                    (game.has_clk, HAS_CLK_FLAG),
+                    (game.has_eval, HAS_EVAL_FLAG),
+                    (game.has_variations, HAS_VARIATIONS_FLAG),
+                ] {
+                    if set {
+                        flags |= flag;
+                    }
+                }
+                flags
+            })
+            .collect(),
+    );
+    columns
+}
+
+fn decode_block(count: usize, columns: &[Vec<u8>]) -> io::Result<Vec<GameRecord>> {
+    let mut games = vec![GameRecord::default(); count];
+    let mut columns = columns.iter().map(|column| ColumnCursor {
+        bytes: column,
+        pos: 0,
+    });
+    let mut next_column = || columns.next().ok_or_else(|| invalid_data("missing column"));
+
+    for (_, get_mut) in TAG_FIELDS {
+        let mut column = next_column()?;
+        let entries = (0..column.u32()?)
+            .map(|_| column.string())
+            .collect::<io::Result<Vec<_>>>()?;
+        for game in &mut games {
+            *get_mut(game) = match column.u32()? {
+                0 => None,
+                code => Some(
+                    entries
+                        .get(code as usize - 1)
+                        .ok_or_else(|| invalid_data("dictionary code out of range"))?
+                        .clone(),
+                ),
+            };
+        }
+        column.finish()?;
+    }
+
+    let mut movetext = next_column()?;
+    for game in &mut games {
+        game.movetext = movetext.string()?;
+    }
+    movetext.finish()?;
+
+    for (_, set) in INT_FIELDS {
+        let mut column = next_column()?;
+        let present = column.take(count)?.to_vec();
+        for (game, &present) in games.iter_mut().zip(&present) {
+            if present != 0 {
+                set(game, column.u64()?);
+            }
+        }
+        column.finish()?;
+    }
+
+    let mut flags = next_column()?;
+    for (game, &flag) in games.iter_mut().zip(flags.take(count)?) {
+        game.has_clk = flag & HAS_CLK_FLAG != 0;
+        game.has_eval = flag & HAS_EVAL_FLAG != 0;
+        game.has_variations = flag & HAS_VARIATIONS_FLAG != 0;
+    }
+    flags.finish()?;
+
+    Ok(games)
+}
+
+/// Length-prefixed bytes.
+fn put_bytes(column: &mut Vec<u8>, bytes: &[u8]) {
+    column.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    column.extend_from_slice(bytes);
+}
+
+struct ColumnCursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ColumnCursor<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| invalid_data("truncated column"))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(
+            self.take(4)?.try_into().expect("4 bytes"),
+        ))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(
+            self.take(8)?.try_into().expect("8 bytes"),
+        ))
+    }
+
+    /// A presence byte, then the bounds read by `read` when present.
+    fn range<T>(
+        &mut self,
+        mut read: impl FnMut(&mut Self) -> io::Result<T>,
+    ) -> io::Result<ZoneRange<T>> {
+        if self.take(1)?[0] == 0 {
+            return Ok(None);
+        }
+        Ok(Some((read(self)?, read(self)?)))
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| invalid_data("invalid UTF-8"))
+    }
+
+    fn finish(self) -> io::Result<()> {
+        if self.pos == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(invalid_data("trailing bytes in column"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_game(idx: usize) -> GameRecord {
+        GameRecord {
+            event: Some("Rated Blitz game".to_string()),
+            site: Some(format!("https://lichess.org/{idx:08}")),
+            white: Some(format!("player{}", idx % 7)),
+            black: Some(format!("player{}", idx % 5)),
+            result: Some(["1-0", "0-1", "1/2-1/2"][idx % 3].to_string()),
+            white_elo: (!idx.is_multiple_of(4)).then_some(1500 + idx as u32),
+            black_elo: Some(1600),
+            utc_date: Some(PgnDate {
+                days: -(idx as i32),
+            }),
+            utc_time: idx
+                .is_multiple_of(2)
+                .then_some(PgnTimeTz { bits: u64::MAX - 1 }),
+            time_control: Some("180+2".to_string()),
+            movetext: format!("1. e4 {{ [%clk 0:03:00] }} e5 2. Nf3 {idx}"),
+            byte_offset: Some(idx as u64 * 100),
+            movetext_bytes: Some(40),
+            has_clk: true,
+            has_eval: idx % 2 == 1,
+            has_variations: idx % 3 == 1,
+            parse_error: (idx == 3).then(|| "Illegal move".to_string()),
+            ..GameRecord::default()
+        }
+    }
+
+    fn assert_same_game(actual: &GameRecord, expected: &GameRecord) {
+        assert_eq!(format!("{actual:?}"), format!("{expected:?}"));
+    }
+
+    fn write_cache(games: &[GameRecord]) -> Vec<u8> {
+        let mut writer = PgnCacheWriter::new(Vec::new()).unwrap();
+        for game in games {
+            writer.push(game.clone()).unwrap();
+        }
+        let (bytes, written) = writer.finish().unwrap();
+        assert_eq!(written, games.len() as u64);
+        bytes
+    }
+
+    fn read_cache(bytes: &[u8]) -> io::Result<Vec<Vec<GameRecord>>> {
+        let mut reader = PgnCacheReader::new(io::Cursor::new(bytes))?;
+        let mut blocks = Vec::new();
+        while let Some(block) = reader.next_block()? {
+            blocks.push(block);
+        }
+        Ok(blocks)
+    }
+
+    #[test]
+    fn test_pgn_cache_round_trips_games_in_blocks() {
+        let games: Vec<GameRecord> = (0..PGN_CACHE_BLOCK_GAMES + 5).map(sample_game).collect();
+        let blocks = read_cache(&write_cache(&games)).unwrap();
+        assert_eq!(
+            blocks.iter().map(Vec::len).collect::<Vec<_>>(),
+            [PGN_CACHE_BLOCK_GAMES, 5]
+        );
+        for (actual, expected) in blocks.iter().flatten().zip(&games) {
+            assert_same_game(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_pgn_cache_round_trips_missing_values() {
+        let games = [GameRecord::default(), sample_game(1)];
+        let blocks = read_cache(&write_cache(&games)).unwrap();
+        assert_same_game(&blocks[0][0], &games[0]);
+        assert_same_game(&blocks[0][1], &games[1]);
+
+        assert!(read_cache(&write_cache(&[])).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_pgn_cache_is_smaller_than_its_games() {
+        let games: Vec<GameRecord> = (0..500).map(sample_game).collect();
+        let raw: usize = games
+            .iter()
+            .map(|game| game.movetext.len() + game.site.as_ref().unwrap().len() + 60)
+            .sum();
+        assert!(write_cache(&games).len() < raw / 2);
+    }
+
+    #[test]
+    fn test_pgn_cache_rejects_invalid_files() {
+        let err = |bytes: &[u8]| read_cache(bytes).unwrap_err().to_string();
+        assert_eq!(err(b"[Event"), "Invalid PGN cache: missing header");
+        assert_eq!(
+            err(b"[Event \"Casual\"]"),
+            "Invalid PGN cache: not a .pgncache file"
+        );
+
+        let mut bytes = write_cache(&[sample_game(0)]);
+        bytes[8] = 9;
+        assert_eq!(
+            err(&bytes),
+            "Invalid PGN cache: unsupported format version 9 (expected 2)"
+        );
+
+        let bytes = write_cache(&[sample_game(0)]);
+        assert_eq!(
+            err(&bytes[..bytes.len() - 3]),
+            "Invalid PGN cache: truncated block"
+        );
+
+        // The game count of the first block follows the 12-byte file header.
+        for count in [0, PGN_CACHE_BLOCK_GAMES as u32 + 1, u32::MAX] {
+            let mut bytes = write_cache(&[sample_game(0)]);
+            bytes[12..16].copy_from_slice(&count.to_le_bytes());
+            assert_eq!(
+                err(&bytes),
+                "Invalid PGN cache: block game count out of range"
+            );
+        }
+    }
+
+    /// Two blocks: club games of 2023 with `B` openings, then 2024 games of 2600+ players with
+    /// `C` openings.
+    fn two_block_games() -> Vec<GameRecord> {
+        (0..PGN_CACHE_BLOCK_GAMES * 2)
+            .map(|idx| {
+                let strong = idx >= PGN_CACHE_BLOCK_GAMES;
+                let elo = if strong { 2600 } else { 1500 } + (idx % 100) as u32;
+                GameRecord {
+                    white_elo: Some(elo),
+                    black_elo: Some(elo + 10),
+                    utc_date: Some(PgnDate {
+                        days: if strong { 19_723 } else { 19_358 } + (idx % 365) as i32,
+                    }),
+                    eco: Some(format!(
+                        "{}{:02}",
+                        if strong { 'C' } else { 'B' },
+                        idx % 100
+                    )),
+                    ..sample_game(idx)
+                }
+            })
+            .collect()
+    }
+
+    fn read_filtered(bytes: &[u8], filter: CacheFilter) -> (Vec<GameRecord>, u64) {
+        let mut reader = PgnCacheReader::new(io::Cursor::new(bytes))
+            .unwrap()
+            .with_filter(filter);
+        let mut games = Vec::new();
+        while let Some(block) = reader.next_block().unwrap() {
+            games.extend(block);
+        }
+        (games, reader.blocks_skipped)
+    }
+
+    #[test]
+    fn test_block_zone_map_round_trips() {
+        let games = two_block_games();
+        let zone_map = BlockZoneMap::of(&games[..PGN_CACHE_BLOCK_GAMES]);
+        assert_eq!(zone_map.white_elo, Some((1500, 1599)));
+        assert_eq!(zone_map.black_elo, Some((1510, 1609)));
+        assert_eq!(zone_map.utc_date, Some((19_358, 19_722)));
+        assert_eq!(zone_map.eco, Some(("B00".to_string(), "B99".to_string())));
+        assert_eq!(BlockZoneMap::decode(&zone_map.encode()).unwrap(), zone_map);
+
+        let empty = BlockZoneMap::of(&[GameRecord::default()]);
+        assert_eq!(empty, BlockZoneMap::default());
+        assert_eq!(BlockZoneMap::decode(&empty.encode()).unwrap(), empty);
+
+        let negative = BlockZoneMap::of(&[sample_game(5)]);
+        assert_eq!(negative.utc_date, Some((-5, -5)));
+        assert_eq!(BlockZoneMap::decode(&negative.encode()).unwrap(), negative);
+    }
+
+    #[test]
+    fn test_pgn_cache_filter_skips_blocks_by_zone_map() {
+        let games = two_block_games();
+        let bytes = write_cache(&games);
+
+        // Only 2024 games of 2600+ players: the club block is skipped unread.
+        let (strong, skipped) = read_filtered(
+            &bytes,
+            CacheFilter {
+                min_elo: Some(2600),
+                min_date: Some(19_723),
+                ..CacheFilter::default()
+            },
+        );
+        assert_eq!(skipped, 1);
+        assert_eq!(strong.len(), PGN_CACHE_BLOCK_GAMES);
+        for (actual, expected) in strong.iter().zip(&games[PGN_CACHE_BLOCK_GAMES..]) {
+            assert_same_game(actual, expected);
+        }
+
+        let (sicilians, skipped) = read_filtered(
+            &bytes,
+            CacheFilter {
+                min_eco: Some("B20".to_string()),
+                max_eco: Some("B99".to_string()),
+                ..CacheFilter::default()
+            },
+        );
+        assert_eq!(skipped, 1);
+        let expected = games.iter().filter(|game| {
+            game.eco.as_deref() >= Some("B20") && game.eco.as_deref() <= Some("B99")
+        });
+        assert_eq!(sicilians.len(), expected.count());
+
+        let (none, skipped) = read_filtered(
+            &bytes,
+            CacheFilter {
+                max_elo: Some(1000),
+                ..CacheFilter::default()
+            },
+        );
+        assert_eq!((none.len(), skipped), (0, 2));
+    }
+
+    #[test]
+    fn test_pgn_cache_filter_checks_games_in_matching_blocks() {
+        let bytes = write_cache(&two_block_games());
+        // The club block's Elo range admits 1590, so it is read and its games are filtered.
+        let (games, skipped) = read_filtered(
+            &bytes,
+            CacheFilter {
+                min_elo: Some(1590),
+                max_elo: Some(1605),
+                ..CacheFilter::default()
+            },
+        );
+        assert_eq!(skipped, 1);
+        assert!(!games.is_empty());
+        assert!(games.iter().all(|game| {
+            (1590..=1605).contains(&game.white_elo.unwrap())
+                && (1590..=1605).contains(&game.black_elo.unwrap())
+        }));
+
+        // Games missing a bounded value never match.
+        let filter = CacheFilter {
+            min_date: Some(0),
+            ..CacheFilter::default()
+        };
+        assert!(!filter.matches(&GameRecord::default()));
+        assert!(CacheFilter::default().matches(&GameRecord::default()));
+    }
+
+    #[test]
+    fn test_cache_filter_rejects_inverted_ranges() {
+        let filter = |filter: CacheFilter| filter.validate().err();
+        assert_eq!(filter(CacheFilter::default()), None);
+        assert_eq!(
+            filter(CacheFilter {
+                min_elo: Some(2600),
+                max_elo: Some(2500),
+                ..CacheFilter::default()
+            }),
+            Some("Invalid Elo range: min_elo (2600) is greater than max_elo (2500).".to_string())
+        );
+        assert_eq!(
+            filter(CacheFilter {
+                min_date: Some(19_723),
+                max_date: Some(19_358),
+                ..CacheFilter::default()
+            }),
+            Some(
+                "Invalid date range: min_date (2024-01-01) is greater than max_date (2023-01-01)."
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            filter(CacheFilter {
+                min_eco: Some("C00".to_string()),
+                max_eco: Some("B99".to_string()),
+                ..CacheFilter::default()
+            }),
+            Some("Invalid ECO range: min_eco (C00) is greater than max_eco (B99).".to_string())
+        );
+    }
+}
@@ -0,0 +1,489 @@
+use super::{
+    duckdb_impl::bind_info_ffi::{self, NamedParameterVarchar},
+    log,
+    pgncache::{CacheFilter, PgnCacheReader, PgnCacheWriter},
+    reader::{
+        ChunkWriter, CompressionMode, OptionalColumns, PATH_PATTERN_PARAM_INDEX,
+        ReadNextGameOutcome, apply_log_level_setting, expand_path_pattern, open_input_stream,
+        read_next_game, read_pgn_columns, resolve_compression_mode, resolve_file_discovery_options,
+        resolve_schema_version,
+    },
+    types::GameRecord,
+    visitor::{PgnReaderState, ScanOptions},
+};
+use duckdb::{
+    core::{DataChunkHandle, Inserter, LogicalTypeHandle, LogicalTypeId},
+    vtab::{BindInfo, InitInfo, TableFunctionInfo, VTab},
+};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+const OUT_PATH_PARAM_INDEX: u64 = 1;
+
+#[repr(C)]
+pub struct WritePgnCacheBindData {
+    paths: Vec<PathBuf>,
+    compression: CompressionMode,
+    out_path: PathBuf,
+}
+
+pub struct WritePgnCacheInitData {
+    done: AtomicBool,
+}
+
+/// `write_pgn_cache(pattern, out_path)`: parse the matched PGN files once and store the games
+/// in a `.pgncache` file. Returns one row with the output path, files read, games written and
+/// file size.
+pub struct WritePgnCacheVTab;
+
+/// Counts reported by `write_pgn_cache`.
+struct CacheWriteSummary {
+    files: u64,
+    games: u64,
+    bytes: u64,
+}
+
+/// Parse every path into `out_path`. The cache is written next to it and renamed into place
+/// once complete, so a failed write never leaves a truncated cache behind.
+fn write_cache(
+    bind_data: &WritePgnCacheBindData,
+) -> Result<CacheWriteSummary, Box<dyn std::error::Error>> {
+    let out_path = &bind_data.out_path;
+    let mut partial_path = out_path.clone().into_os_string();
+    partial_path.push(".partial");
+    let partial_path = PathBuf::from(partial_path);
+
+    let summary = write_partial_cache(bind_data, &partial_path)
+        .and_then(|summary| {
+            fs::rename(&partial_path, out_path)
+                .map_err(|err| cache_write_error(out_path, err).into())
+                .map(|()| summary)
+        })
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&partial_path);
+        })?;
+    Ok(summary)
+}
+
+fn write_partial_cache(
+    bind_data: &WritePgnCacheBindData,
+    partial_path: &Path,
+) -> Result<CacheWriteSummary, Box<dyn std::error::Error>> {
+    let write_err = |err| cache_write_error(&bind_data.out_path, err);
+    let file = File::create(partial_path).map_err(write_err)?;
+    let mut writer = PgnCacheWriter::new(BufWriter::new(file)).map_err(write_err)?;
+
+    // Every `read_pgn` column is stored, whatever schema version the cache is read with.
+    let options = ScanOptions {
+        track_byte_offsets: true,
+        track_movetext_bytes: true,
+        ..ScanOptions::default()
+    };
+    let mut files = 0;
+    for (path_idx, path) in bind_data.paths.iter().enumerate() {
+        let input = match open_input_stream(path, bind_data.compression, 0, false, None) {
+            Ok(input) => input,
+            Err(err_msg) if bind_data.paths.len() == 1 => return Err(err_msg.into()),
+            Err(err_msg) => {
+                log::warn(&err_msg);
+                continue;
+            }
+        };
+        files += 1;
+
+        let mut reader = PgnReaderState::new(input, path_idx, options.clone());
+        loop {
+            match read_next_game(&mut reader, path) {
+                ReadNextGameOutcome::GameReady => writer
+                    .push(std::mem::take(&mut reader.record_buffer))
+                    .map_err(write_err)?,
+                ReadNextGameOutcome::GameSkipped => {}
+                ReadNextGameOutcome::ReaderFinished => break,
+            }
+        }
+    }
+
+    let (output, games) = writer.finish().map_err(write_err)?;
+    let bytes = output
+        .into_inner()
+        .map_err(|err| write_err(err.into_error()))?
+        .metadata()
+        .map_err(write_err)?
+        .len();
+    Ok(CacheWriteSummary {
+        files,
+        games,
+        bytes,
+    })
+}
+
+fn cache_write_error(out_path: &Path, err: std::io::Error) -> String {
+    format!(
+        "Failed to write PGN cache '{}': {}",
+        out_path.display(),
+        err
+    )
+}
+
+impl VTab for WritePgnCacheVTab {
+    type InitData = WritePgnCacheInitData;
+    type BindData = WritePgnCacheBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        let pattern = bind.get_parameter(PATH_PATTERN_PARAM_INDEX).to_string();
+        let out_path = PathBuf::from(bind.get_parameter(OUT_PATH_PARAM_INDEX).to_string());
+        apply_log_level_setting(bind)?;
+        let compression = resolve_compression_mode(bind)?;
+        let discovery = resolve_file_discovery_options(bind)?;
+        let paths = expand_path_pattern(&pattern, discovery)?;
+
+        bind.add_result_column("path", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        for name in ["files", "games", "bytes"] {
+            bind.add_result_column(name, LogicalTypeHandle::from(LogicalTypeId::UBigint));
+        }
+        Ok(WritePgnCacheBindData {
+            paths,
+            compression,
+            out_path,
+        })
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(WritePgnCacheInitData {
+            done: AtomicBool::new(false),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if func.get_init_data().done.swap(true, Ordering::Relaxed) {
+            output.set_len(0);
+            return Ok(());
+        }
+
+        let bind_data = func.get_bind_data();
+        let summary = write_cache(bind_data)?;
+        output
+            .flat_vector(0)
+            .insert(0, bind_data.out_path.to_string_lossy().as_ref());
+        for (column_idx, count) in [summary.files, summary.games, summary.bytes]
+            .into_iter()
+            .enumerate()
+        {
+            output.flat_vector(column_idx + 1).as_mut_slice::<u64>()[0] = count;
+        }
+        output.set_len(1);
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![
+            LogicalTypeHandle::from(LogicalTypeId::Varchar), // PGN path pattern (required)
+            LogicalTypeHandle::from(LogicalTypeId::Varchar), // cache output path (required)
+        ])
+    }
+
+    fn named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+        Some(vec![
+            (
+                "compression".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "follow_symlinks".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "skip_hidden".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+        ])
+    }
+}
+
+#[repr(C)]
+pub struct ReadPgnCacheBindData {
+    paths: Vec<PathBuf>,
+    schema_version: u32,
+    filter: CacheFilter,
+}
+
+pub struct ReadPgnCacheInitData {
+    state: Mutex<CacheScanState>,
+}
+
+/// `read_pgn_cache(pattern)`: the games of `.pgncache` files, with the same columns as
+/// `read_pgn` for the requested `schema_version`. Elo, date and ECO bounds skip whole blocks
+/// through their zone maps before filtering games.
+pub struct ReadPgnCacheVTab;
+
+struct CacheScanState {
+    next_path_idx: usize,
+    current: Option<PgnCacheReader<BufReader<File>>>,
+    /// Decoded games not yet returned, when a block is larger than the output chunk.
+    pending: VecDeque<GameRecord>,
+}
+
+fn lock_scan_state(state: &Mutex<CacheScanState>) -> MutexGuard<'_, CacheScanState> {
+    match state.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            log::warn("Recovering poisoned read_pgn_cache state mutex");
+            poisoned.into_inner()
+        }
+    }
+}
+
+/// `min_elo`, `max_elo`, `min_date`, `max_date`, `min_eco` and `max_eco`.
+fn resolve_cache_filter(bind: &BindInfo) -> Result<CacheFilter, Box<dyn std::error::Error>> {
+    let eco = |name| -> Result<Option<String>, Box<dyn std::error::Error>> {
+        Ok(
+            match bind_info_ffi::get_named_parameter_varchar(bind, name)? {
+                NamedParameterVarchar::Value(eco) => Some(eco.trim().to_ascii_uppercase()),
+                NamedParameterVarchar::Missing | NamedParameterVarchar::Null => None,
+            },
+        )
+    };
+    let filter = CacheFilter {
+        min_elo: bind_info_ffi::get_named_parameter_i64(bind, "min_elo")?,
+        max_elo: bind_info_ffi::get_named_parameter_i64(bind, "max_elo")?,
+        min_date: bind_info_ffi::get_named_parameter_date(bind, "min_date")?,
+        max_date: bind_info_ffi::get_named_parameter_date(bind, "max_date")?,
+        min_eco: eco("min_eco")?,
+        max_eco: eco("max_eco")?,
+    };
+    filter.validate()?;
+    Ok(filter)
+}
+
+fn open_cache(path: &Path) -> Result<PgnCacheReader<BufReader<File>>, String> {
+    File::open(path)
+        .and_then(|file| PgnCacheReader::new(BufReader::new(file)))
+        .map_err(|e| format!("Failed to open PGN cache '{}': {}", path.display(), e))
+}
+
+/// Decode the next block into `state.pending`, moving on to the next file at the end of one.
+/// Returns `false` once every file is exhausted.
+///
+/// Files that fail to open are skipped with a warning when several paths were matched; a single
+/// explicit path fails hard, like `read_pgn`. A corrupt block fails the query.
+fn fill_pending(
+    state: &mut CacheScanState,
+    bind_data: &ReadPgnCacheBindData,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    loop {
+        let Some(reader) = state.current.as_mut() else {
+            let Some(path) = bind_data.paths.get(state.next_path_idx) else {
+                return Ok(false);
+            };
+            state.next_path_idx += 1;
+            match open_cache(path) {
+                Ok(reader) => state.current = Some(reader.with_filter(bind_data.filter.clone())),
+                Err(err_msg) if bind_data.paths.len() == 1 => return Err(err_msg.into()),
+                Err(err_msg) => log::warn(&err_msg),
+            }
+            continue;
+        };
+
+        let path = &bind_data.paths[state.next_path_idx - 1];
+        match reader.next_block() {
+            Ok(Some(games)) => {
+                state.pending.extend(games);
+                return Ok(true);
+            }
+            Ok(None) => state.current = None,
+            Err(err) => {
+                return Err(
+                    format!("Failed to read PGN cache '{}': {}", path.display(), err).into(),
+                );
+            }
+        }
+    }
+}
+
+impl VTab for ReadPgnCacheVTab {
+    type InitData = ReadPgnCacheInitData;
+    type BindData = ReadPgnCacheBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        let pattern = bind.get_parameter(PATH_PATTERN_PARAM_INDEX).to_string();
+        apply_log_level_setting(bind)?;
+        let schema_version = resolve_schema_version(bind)?;
+        let filter = resolve_cache_filter(bind)?;
+        let discovery = resolve_file_discovery_options(bind)?;
+        let paths = expand_path_pattern(&pattern, discovery)?;
+
+        for column in read_pgn_columns(schema_version) {
+            bind.add_result_column(column.name, column.logical_type_handle());
+        }
+        Ok(ReadPgnCacheBindData {
+            paths,
+            schema_version,
+            filter,
+        })
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(ReadPgnCacheInitData {
+            state: Mutex::new(CacheScanState {
+                next_path_idx: 0,
+                current: None,
+                pending: VecDeque::new(),
+            }),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let init_data = func.get_init_data();
+        let bind_data = func.get_bind_data();
+        let mut chunk_writer = ChunkWriter::new(
+            output,
+            bind_data.schema_version,
+            OptionalColumns::default(),
+            &[],
+        );
+
+        let mut state = lock_scan_state(&init_data.state);
+        while !chunk_writer.is_full() {
+            match state.pending.pop_front() {
+                Some(game) => chunk_writer.write_row(&game),
+                None if fill_pending(&mut state, bind_data)? => {}
+                None => break,
+            }
+        }
+        chunk_writer.set_output_len();
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![
+            LogicalTypeHandle::from(LogicalTypeId::Varchar), // cache path pattern (required)
+        ])
+    }
+
+    fn named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+        Some(vec![
+            (
+                "schema_version".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+            ),
+            (
+                "min_elo".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+            ),
+            (
+                "max_elo".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+            ),
+            (
+                "min_date".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Date),
+            ),
+            (
+                "max_date".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Date),
+            ),
+            (
+                "min_eco".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "max_eco".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "follow_symlinks".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "skip_hidden".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("duckdb-chess-{}-{name}", std::process::id()))
+    }
+
+    fn parse_directly(path: &Path) -> Vec<GameRecord> {
+        let input = open_input_stream(path, CompressionMode::Auto, 0, false, None).unwrap();
+        let mut reader = PgnReaderState::new(
+            input,
+            0,
+            ScanOptions {
+                track_byte_offsets: true,
+                track_movetext_bytes: true,
+                ..ScanOptions::default()
+            },
+        );
+        let mut games = Vec::new();
+        while let ReadNextGameOutcome::GameReady = read_next_game(&mut reader, path) {
+            games.push(std::mem::take(&mut reader.record_buffer));
+        }
+        games
+    }
+
+    #[test]
+    fn test_write_cache_stores_what_read_pgn_parses() {
+        let out_path = temp_cache_path("sample.pgncache");
+        let bind_data = WritePgnCacheBindData {
+            paths: vec![
+                PathBuf::from("test/pgn_files/sample.pgn"),
+                PathBuf::from("test/pgn_files/definitely-missing-file.pgn"),
+            ],
+            compression: CompressionMode::Auto,
+            out_path: out_path.clone(),
+        };
+
+        let summary = write_cache(&bind_data).unwrap();
+        let expected = parse_directly(Path::new("test/pgn_files/sample.pgn"));
+        assert_eq!(summary.files, 1);
+        assert_eq!(summary.games, expected.len() as u64);
+        assert_eq!(summary.bytes, fs::metadata(&out_path).unwrap().len());
+
+        let mut reader = open_cache(&out_path).unwrap();
+        let cached = reader.next_block().unwrap().unwrap();
+        assert!(reader.next_block().unwrap().is_none());
+        assert_eq!(format!("{cached:?}"), format!("{expected:?}"));
+        fs::remove_file(&out_path).unwrap();
+    }
+
+    #[test]
+    fn test_write_cache_leaves_no_partial_file_on_failure() {
+        let out_path = temp_cache_path("missing.pgncache");
+        let bind_data = WritePgnCacheBindData {
+            paths: vec![PathBuf::from("test/pgn_files/definitely-missing-file.pgn")],
+            compression: CompressionMode::Auto,
+            out_path: out_path.clone(),
+        };
+
+        let err = write_cache(&bind_data).err().unwrap().to_string();
+        assert!(err.contains("Failed to open file"));
+        assert!(!out_path.exists());
+        assert!(!out_path.with_extension("pgncache.partial").exists());
+    }
+
+    #[test]
+    fn test_open_cache_rejects_pgn_files() {
+        let err = open_cache(Path::new("test/pgn_files/sample.pgn"))
+            .err()
+            .unwrap();
+        assert!(err.contains("Failed to open PGN cache"));
+        assert!(err.contains("not a .pgncache file"));
+    }
+}
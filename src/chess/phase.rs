@@ -0,0 +1,267 @@
+use super::clock::{ClockSide, MICROS_PER_SECOND, clock_series};
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{VarcharInput, invoke_binary_to_struct_nullable};
+use super::replay::ReplayedGame;
+#[cfg(feature = "extension")]
+use super::replay::{ReplayCache, on_illegal_arg, on_illegal_signatures};
+use super::timecontrol::{parse_timecontrol, seconds_available_for_move};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use shakmaty::{Bitboard, Chess, Color, Position, Rank};
+#[cfg(feature = "extension")]
+use std::error::Error;
+
+/// Queens, rooks, bishops and knights left on the board at or below which the middlegame starts.
+const MIDDLEGAME_MAX_PIECES: usize = 10;
+/// Queens, rooks, bishops and knights left on the board at or below which the endgame starts.
+const ENDGAME_MAX_PIECES: usize = 6;
+/// A side with fewer of its own pieces on its back rank has developed out of the opening.
+const DEVELOPED_BACK_RANK_PIECES: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GamePhase {
+    Opening,
+    Middlegame,
+    Endgame,
+}
+
+/// Phase of a single position, by material and development.
+///
+/// The endgame starts once at most `ENDGAME_MAX_PIECES` queens, rooks, bishops and knights
+/// remain. The middlegame starts once at most `MIDDLEGAME_MAX_PIECES` remain or either side
+/// has fewer than `DEVELOPED_BACK_RANK_PIECES` of its own pieces on its back rank.
+pub fn position_phase(pos: &Chess) -> GamePhase {
+    let board = pos.board();
+    let pieces = (board.occupied() ^ board.pawns() ^ board.kings()).count();
+    let back_rank_pieces =
+        |color: Color, rank: Rank| (board.by_color(color) & Bitboard::from_rank(rank)).count();
+
+    if pieces <= ENDGAME_MAX_PIECES {
+        GamePhase::Endgame
+    } else if pieces <= MIDDLEGAME_MAX_PIECES
+        || back_rank_pieces(Color::White, Rank::First) < DEVELOPED_BACK_RANK_PIECES
+        || back_rank_pieces(Color::Black, Rank::Eighth) < DEVELOPED_BACK_RANK_PIECES
+    {
+        GamePhase::Middlegame
+    } else {
+        GamePhase::Opening
+    }
+}
+
+/// Phase in which each mainline move was played, i.e. of the position it was played from.
+/// Phases never go back, so a game that re-enters "opening" material stays in the middlegame.
+pub fn move_phases(game: &ReplayedGame) -> Vec<GamePhase> {
+    let mut phase = GamePhase::Opening;
+    game.positions[..game.plies()]
+        .iter()
+        .map(|pos| {
+            phase = phase.max(position_phase(pos));
+            phase
+        })
+        .collect()
+}
+
+/// Thinking time spent in the opening, middlegame and endgame, in seconds, from the
+/// TimeControl and `%clk` comments.
+///
+/// A clocked move is charged the time credited since the side's previous clocked move minus
+/// the change in its clock, so the time of moves without a clock annotation is charged to the
+/// side's next clocked move. Each phase total is rounded to the nearest second. `game` is the
+/// replayed mainline of `movetext`. Returns `None` when the TimeControl is not a timed control
+/// or no move has a clock annotation.
+/// Spec: move-analysis - Game Phase Durations
+pub fn game_phase_durations_seconds(
+    game: &ReplayedGame,
+    movetext: &str,
+    timecontrol: &str,
+) -> Option<[i64; 3]> {
+    let parsed = parse_timecontrol(timecontrol).ok()?;
+    let clocks = clock_series(movetext, ClockSide::Both)?;
+    let phases = move_phases(game);
+    if clocks.iter().all(Option::is_none) {
+        return None;
+    }
+
+    let mut used_before = [0i64; 2];
+    let mut phase_micros = [0i64; 3];
+    for (ply_idx, (clock, phase)) in clocks.iter().zip(&phases).enumerate() {
+        let Some(remaining) = *clock else {
+            continue;
+        };
+        let move_number = (ply_idx / 2 + 1) as u64;
+        let available = i64::try_from(seconds_available_for_move(&parsed, move_number)?).ok()?;
+        let used = available
+            .checked_mul(MICROS_PER_SECOND)?
+            .saturating_sub(remaining);
+
+        let side_used = &mut used_before[ply_idx % 2];
+        let spent = used.saturating_sub(*side_used).max(0);
+        *side_used = used;
+        phase_micros[*phase as usize] = phase_micros[*phase as usize].checked_add(spent)?;
+    }
+
+    Some(phase_micros.map(|micros| (micros + MICROS_PER_SECOND / 2) / MICROS_PER_SECOND))
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessGamePhaseDurationsScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessGamePhaseDurationsScalar {
+    type State = ReplayCache;
+
+    unsafe fn invoke(
+        cache: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let on_illegal = on_illegal_arg(input, 2)?;
+        invoke_binary_to_struct_nullable::<VarcharInput, VarcharInput, _, _, 3>(
+            input,
+            output,
+            |movetext, timecontrol| {
+                let Some(game) = cache
+                    .replay_with(movetext, on_illegal)
+                    .map_err(|e| format!("chess_game_phase_durations: {e}"))?
+                else {
+                    return Ok(None);
+                };
+                Ok(game_phase_durations_seconds(&game, movetext, timecontrol))
+            },
+        )
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        on_illegal_signatures(
+            || {
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ]
+            },
+            || {
+                LogicalTypeHandle::struct_type(&[
+                    (
+                        "opening_seconds",
+                        LogicalTypeHandle::from(LogicalTypeId::Bigint),
+                    ),
+                    (
+                        "middlegame_seconds",
+                        LogicalTypeHandle::from(LogicalTypeId::Bigint),
+                    ),
+                    (
+                        "endgame_seconds",
+                        LogicalTypeHandle::from(LogicalTypeId::Bigint),
+                    ),
+                ])
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::replay::replay_mainline;
+    use shakmaty::{CastlingMode, fen::Fen};
+
+    const DEVELOPING_LINE: &str =
+        "1. Nf3 Nf6 2. Nc3 Nc6 3. g3 g6 4. Bg2 Bg7 5. b3 b6 6. Bb2 Bb7 7. e3 e6 8. Qe2 Qe7";
+
+    fn mainline_phases(movetext: &str) -> Option<Vec<GamePhase>> {
+        replay_mainline(movetext).map(|game| move_phases(&game))
+    }
+
+    fn durations(movetext: &str, timecontrol: &str) -> Option<[i64; 3]> {
+        game_phase_durations_seconds(&replay_mainline(movetext)?, movetext, timecontrol)
+    }
+
+    fn phase_of(fen: &str) -> GamePhase {
+        let pos: Chess = fen
+            .parse::<Fen>()
+            .unwrap()
+            .into_position(CastlingMode::Standard)
+            .unwrap();
+        position_phase(&pos)
+    }
+
+    #[test]
+    fn test_position_phase_by_material_and_development() {
+        assert_eq!(
+            phase_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            GamePhase::Opening
+        );
+        // White's back rank is down to K, R, R.
+        assert_eq!(
+            phase_of("r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/2NP1N2/PPPQ1PPP/R3K2R b KQkq - 0 6"),
+            GamePhase::Middlegame
+        );
+        // Queens and a pair of minors off: 10 pieces left.
+        assert_eq!(
+            phase_of("r1b1kb1r/pppp1ppp/5n2/4p3/4P3/5N2/PPPP1PPP/R1B1KB1R w KQkq - 0 1"),
+            GamePhase::Middlegame
+        );
+        assert_eq!(
+            phase_of("4k3/pp3ppp/2n5/8/8/2N5/PP3PPP/4K3 w - - 0 1"),
+            GamePhase::Endgame
+        );
+    }
+
+    #[test]
+    fn test_move_phases_never_go_back() {
+        let phases = mainline_phases(DEVELOPING_LINE).unwrap();
+        assert_eq!(phases.len(), 16);
+        assert!(
+            phases[..15]
+                .iter()
+                .all(|&phase| phase == GamePhase::Opening)
+        );
+        // 8. Qe2 leaves White's back rank with K, R, R.
+        assert_eq!(phases[15], GamePhase::Middlegame);
+
+        // 9. Qd1 refills White's back rank, but the game stays in the middlegame.
+        let phases = mainline_phases(&format!("{DEVELOPING_LINE} 9. Qd1 Qd8 10. Qe2")).unwrap();
+        assert!(
+            phases[15..]
+                .iter()
+                .all(|&phase| phase == GamePhase::Middlegame)
+        );
+
+        assert_eq!(mainline_phases(""), Some(vec![]));
+        assert_eq!(mainline_phases("1. e4 e4"), None);
+    }
+
+    #[test]
+    fn test_game_phase_durations_splits_thinking_time() {
+        // 3+2, so move n is credited 180 + 2n seconds. White used 2s on move 1 and 196 - 170
+        // = 26s by move 8; Black 1s on move 1 and 196 - 160 = 36s by move 8, whose last 35s
+        // were spent on the first middlegame move.
+        let movetext = DEVELOPING_LINE
+            .replacen("Nf3", "Nf3 { [%clk 0:03:00] }", 1)
+            .replacen("Nf6", "Nf6 { [%clk 0:03:01] }", 1)
+            .replacen("Qe2", "Qe2 { [%clk 0:02:50] }", 1)
+            .replacen("Qe7", "Qe7 { [%clk 0:02:40] }", 1);
+        assert_eq!(durations(&movetext, "180+2"), Some([2 + 1 + 24, 35, 0]));
+    }
+
+    #[test]
+    fn test_game_phase_durations_charges_unclocked_moves_to_next_clock() {
+        let movetext = "1. e4 { [%clk 0:03:00] } e5 { [%clk 0:03:00] } 2. Nf3 Nc6 \
+                        3. Bc4 { [%clk 0:02:50] } Bc5 { [%clk 0:02:58] }";
+        // White used 186 - 170 = 16s by move 3, Black 186 - 178 = 8s.
+        assert_eq!(durations(movetext, "180+2"), Some([2 + 14 + 2 + 6, 0, 0]));
+    }
+
+    #[test]
+    fn test_game_phase_durations_requires_clocks_and_timed_control() {
+        let movetext = "1. e4 { [%clk 0:03:00] } e5 { [%clk 0:02:59] }";
+        assert_eq!(durations(movetext, "-"), None);
+        assert_eq!(durations("1. e4 e5", "180+2"), None);
+        assert_eq!(durations("", "180+2"), None);
+        assert_eq!(durations("1. e4 { [%clk 0:03:00] } e4", "180+2"), None);
+    }
+}
@@ -0,0 +1,274 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{
+    invoke_binary_varchar_i64_to_u64_list_nullable, invoke_unary_varchar_to_i64_u64_struct_list,
+    invoke_unary_varchar_to_u64_nullable,
+};
+use super::moves::zobrist_hash_of_position;
+use crate::pgn_visitor_skip_variations;
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use pgn_reader::{Nag, RawComment, Reader, SanPlus, Skip, Visitor};
+use shakmaty::{CastlingMode, Chess, Position, fen::Fen};
+#[cfg(feature = "extension")]
+use std::error::Error;
+use std::io;
+use std::ops::ControlFlow;
+
+/// Zobrist hash of the position described by `fen`, or `None` when the FEN is invalid.
+///
+/// The hash matches the per-ply hashes of `movetext_position_hashes` and the final-position
+/// hash of `chess_moves_hash`, so FEN lookups can join against either.
+pub fn fen_position_hash(fen: &str) -> Option<u64> {
+    let fen = fen.trim();
+    if fen.is_empty() {
+        return None;
+    }
+
+    let parsed: Fen = fen.parse().ok()?;
+    let position: Chess = parsed.into_position(CastlingMode::Standard).ok()?;
+    Some(zobrist_hash_of_position(&position))
+}
+
+/// `(ply, hash)` for every mainline position, starting with ply 0 (the initial position).
+///
+/// Replay stops at the first illegal move; positions reached before it are kept.
+pub fn movetext_position_hashes(movetext: &str) -> Vec<(u32, u64)> {
+    position_hashes_up_to(movetext, usize::MAX)
+}
+
+/// Hash of the position after each of the first `max_plies` mainline plies, so element `k`
+/// (0-based) matches `chess_moves_hash` of the first `k + 1` plies. Replay stops at the first
+/// illegal move.
+pub fn movetext_prefix_hashes(movetext: &str, max_plies: usize) -> Vec<u64> {
+    position_hashes_up_to(movetext, max_plies)
+        .into_iter()
+        .skip(1)
+        .map(|(_, hash)| hash)
+        .collect()
+}
+
+fn position_hashes_up_to(movetext: &str, max_plies: usize) -> Vec<(u32, u64)> {
+    let mut visitor = PositionHashVisitor {
+        max_plies,
+        ..PositionHashVisitor::default()
+    };
+    visitor.init();
+
+    if !movetext.trim().is_empty() {
+        let mut reader = Reader::new(io::Cursor::new(movetext.as_bytes()));
+        let _ = reader.read_game(&mut visitor);
+    }
+
+    visitor.hashes
+}
+
+#[derive(Default)]
+struct PositionHashVisitor {
+    pos: Chess,
+    hashes: Vec<(u32, u64)>,
+    /// Plies to replay before stopping.
+    max_plies: usize,
+}
+
+impl PositionHashVisitor {
+    fn init(&mut self) {
+        self.pos = Chess::default();
+        self.hashes.clear();
+        self.hashes.push((0, zobrist_hash_of_position(&self.pos)));
+    }
+}
+
+impl Visitor for PositionHashVisitor {
+    type Tags = ();
+    type Movetext = ();
+    type Output = ();
+
+    fn begin_tags(&mut self) -> ControlFlow<Self::Output, Self::Tags> {
+        self.init();
+        ControlFlow::Continue(())
+    }
+
+    fn begin_movetext(&mut self, _tags: Self::Tags) -> ControlFlow<Self::Output, Self::Movetext> {
+        ControlFlow::Continue(())
+    }
+
+    fn san(
+        &mut self,
+        _movetext: &mut Self::Movetext,
+        san_plus: SanPlus,
+    ) -> ControlFlow<Self::Output> {
+        if self.hashes.len() > self.max_plies {
+            return ControlFlow::Break(());
+        }
+        let Ok(m) = san_plus.san.to_move(&self.pos) else {
+            return ControlFlow::Break(());
+        };
+
+        self.pos.play_unchecked(m);
+        let ply = self.hashes.len() as u32;
+        self.hashes.push((ply, zobrist_hash_of_position(&self.pos)));
+
+        ControlFlow::Continue(())
+    }
+
+    pgn_visitor_skip_variations!();
+
+    fn end_game(&mut self, _movetext: Self::Movetext) -> Self::Output {}
+}
+
+// Spec: move-analysis - Position Search Index
+#[cfg(feature = "extension")]
+pub struct ChessPositionHashesScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessPositionHashesScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_unary_varchar_to_i64_u64_struct_list(input, output, |movetext| {
+            Some(
+                movetext_position_hashes(movetext)
+                    .into_iter()
+                    .map(|(ply, hash)| (i64::from(ply), hash))
+                    .collect(),
+            )
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let position_type = LogicalTypeHandle::struct_type(&[
+            ("ply", LogicalTypeHandle::from(LogicalTypeId::Bigint)),
+            (
+                "position_hash",
+                LogicalTypeHandle::from(LogicalTypeId::UBigint),
+            ),
+        ]);
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            LogicalTypeHandle::list(&position_type),
+        )]
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessMovesPrefixHashesScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessMovesPrefixHashesScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_binary_varchar_i64_to_u64_list_nullable(input, output, |movetext, max_plies| {
+            let max_plies = usize::try_from(max_plies).map_err(|_| {
+                format!(
+                    "chess_moves_prefix_hashes: invalid max_plies {max_plies}. Expected a \
+                     non-negative number of plies."
+                )
+            })?;
+            Ok(Some(movetext_prefix_hashes(movetext, max_plies)))
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+            ],
+            LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::UBigint)),
+        )]
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessFenHashScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessFenHashScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_unary_varchar_to_u64_nullable(input, output, fen_position_hash)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            LogicalTypeHandle::from(LogicalTypeId::UBigint),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test]
+    fn test_movetext_position_hashes_starts_at_ply_zero() {
+        let hashes = movetext_position_hashes("1. e4 e5 2. Nf3");
+        let plies: Vec<u32> = hashes.iter().map(|(ply, _)| *ply).collect();
+        assert_eq!(plies, vec![0, 1, 2, 3]);
+        assert_eq!(Some(hashes[0].1), fen_position_hash(START_FEN));
+
+        assert_eq!(movetext_position_hashes("").len(), 1);
+    }
+
+    #[test]
+    fn test_movetext_position_hashes_match_fen_hash() {
+        let hashes = movetext_position_hashes("1. e4 {comment} e5 (1... c5) 2. Nf3");
+        let after_nf3 = "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2";
+        assert_eq!(Some(hashes[3].1), fen_position_hash(after_nf3));
+    }
+
+    #[test]
+    fn test_movetext_position_hashes_transpositions_collide() {
+        let a = movetext_position_hashes("1. Nf3 Nf6 2. Nc3 Nc6");
+        let b = movetext_position_hashes("1. Nc3 Nc6 2. Nf3 Nf6");
+        assert_eq!(a.last(), b.last());
+    }
+
+    #[test]
+    fn test_movetext_position_hashes_stop_at_illegal_move() {
+        let hashes = movetext_position_hashes("1. e4 e5 2. Ke3");
+        assert_eq!(hashes.len(), 3);
+    }
+
+    #[test]
+    fn test_movetext_prefix_hashes_match_position_hashes() {
+        let movetext = "1. e4 e5 2. Nf3 Nc6 3. Bb5";
+        let positions = movetext_position_hashes(movetext);
+        let all = movetext_prefix_hashes(movetext, usize::MAX);
+        assert_eq!(all.len(), 5);
+        assert!(all.iter().zip(&positions[1..]).all(|(a, (_, b))| a == b));
+        assert_eq!(movetext_prefix_hashes(movetext, 2), all[..2]);
+        assert!(movetext_prefix_hashes(movetext, 0).is_empty());
+        assert!(movetext_prefix_hashes("", 10).is_empty());
+        assert_eq!(movetext_prefix_hashes("1. e4 e5 2. Ke3", 10).len(), 2);
+    }
+
+    #[test]
+    fn test_fen_position_hash_ignores_move_counters_and_rejects_invalid() {
+        let counters = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 5 40";
+        assert_eq!(fen_position_hash(START_FEN), fen_position_hash(counters));
+        assert_eq!(fen_position_hash("not a fen"), None);
+        assert_eq!(fen_position_hash("   "), None);
+    }
+}
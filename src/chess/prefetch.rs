@@ -0,0 +1,176 @@
+use std::io::{self, Read};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread;
+
+/// Decoded bytes handed over per block.
+const BLOCK_SIZE: usize = 1 << 20;
+/// Blocks decoded ahead of the consumer before the worker blocks.
+const QUEUE_DEPTH: usize = 4;
+
+/// Reads an input on a background thread, so decoding it overlaps with the consumer's work.
+/// Spec: pgn-parsing - Background Decompression
+///
+/// The worker fills blocks of up to `BLOCK_SIZE` bytes and queues at most `QUEUE_DEPTH` of
+/// them; consumed blocks are sent back to be refilled. A read error is delivered once the
+/// blocks before it are consumed, after which the stream reports end of input.
+pub struct PrefetchReader {
+    blocks: Receiver<io::Result<Vec<u8>>>,
+    spent: SyncSender<Vec<u8>>,
+    current: Vec<u8>,
+    pos: usize,
+}
+
+impl PrefetchReader {
+    pub fn spawn<R: Read + Send + 'static>(inner: R) -> io::Result<Self> {
+        Self::with_block_size(inner, BLOCK_SIZE)
+    }
+
+    fn with_block_size<R: Read + Send + 'static>(
+        mut inner: R,
+        block_size: usize,
+    ) -> io::Result<Self> {
+        let (block_tx, blocks) = mpsc::sync_channel(QUEUE_DEPTH);
+        // One more than the queue, so returning a block never waits for the worker.
+        let (spent, spent_rx) = mpsc::sync_channel::<Vec<u8>>(QUEUE_DEPTH + 1);
+        // The worker is detached rather than joined on drop: it may be blocked reading a pipe
+        // such as `/dev/stdin`, and it exits on its own once the next block cannot be sent.
+        thread::Builder::new()
+            .name("chess-decompress".to_string())
+            .spawn(move || {
+                loop {
+                    let mut block = spent_rx.try_recv().unwrap_or_default();
+                    block.resize(block_size, 0);
+                    let message = match fill_block(&mut inner, &mut block) {
+                        Ok(0) => break,
+                        Ok(len) => {
+                            block.truncate(len);
+                            Ok(block)
+                        }
+                        Err(err) => Err(err),
+                    };
+                    let failed = message.is_err();
+                    if block_tx.send(message).is_err() || failed {
+                        break;
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            blocks,
+            spent,
+            current: Vec::new(),
+            pos: 0,
+        })
+    }
+}
+
+/// Read until `block` is full or the input ends; returns the bytes read.
+fn fill_block(inner: &mut impl Read, block: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < block.len() {
+        match inner.read(&mut block[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(len)
+}
+
+impl Read for PrefetchReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.pos == self.current.len() {
+            let next = match self.blocks.recv() {
+                Ok(block) => block?,
+                // The worker hung up after the last block or an error.
+                Err(_) => return Ok(0),
+            };
+            let spent = std::mem::replace(&mut self.current, next);
+            self.pos = 0;
+            // Only recycled when the worker is still running and has room.
+            let _ = self.spent.try_send(spent);
+        }
+
+        let available = &self.current[self.pos..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Yields its data a few bytes per call, then fails if `error` is set.
+    struct Trickle {
+        data: Vec<u8>,
+        pos: usize,
+        error: bool,
+    }
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.pos == self.data.len() {
+                return if self.error {
+                    Err(io::Error::other("truncated stream"))
+                } else {
+                    Ok(0)
+                };
+            }
+            let len = buf.len().min(3).min(self.data.len() - self.pos);
+            buf[..len].copy_from_slice(&self.data[self.pos..self.pos + len]);
+            self.pos += len;
+            Ok(len)
+        }
+    }
+
+    fn trickle(data: &[u8], error: bool) -> Trickle {
+        Trickle {
+            data: data.to_vec(),
+            pos: 0,
+            error,
+        }
+    }
+
+    #[test]
+    fn test_prefetch_reader_passes_bytes_through_in_order() {
+        let data: Vec<u8> = (0..=255).cycle().take(10_000).collect();
+        for block_size in [1, 7, 4096, 1 << 20] {
+            let mut reader =
+                PrefetchReader::with_block_size(trickle(&data, false), block_size).unwrap();
+            let mut out = Vec::new();
+            reader.read_to_end(&mut out).unwrap();
+            assert_eq!(out, data, "block_size={block_size}");
+            assert_eq!(reader.read(&mut [0; 8]).unwrap(), 0);
+        }
+
+        let mut empty = PrefetchReader::spawn(io::empty()).unwrap();
+        assert_eq!(empty.read(&mut [0; 8]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_prefetch_reader_reports_errors_after_preceding_bytes() {
+        let mut reader = PrefetchReader::with_block_size(trickle(b"1. e4 e5", true), 4).unwrap();
+        let mut out = Vec::new();
+        let err = reader.read_to_end(&mut out).unwrap_err();
+        assert_eq!(err.to_string(), "truncated stream");
+        assert_eq!(out, b"1. e4 e5");
+        assert_eq!(reader.read(&mut [0; 8]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_prefetch_reader_stops_worker_when_dropped_early() {
+        let mut reader = PrefetchReader::with_block_size(io::repeat(b'x'), 16).unwrap();
+        let mut head = [0; 40];
+        reader.read_exact(&mut head).unwrap();
+        assert!(head.iter().all(|&byte| byte == b'x'));
+        // The worker notices the closed queue on its next send instead of reading forever.
+        drop(reader);
+    }
+}
@@ -0,0 +1,391 @@
+use super::clock::annotation_series;
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::invoke_unary_varchar_to_varchar_struct_list;
+use super::replay::ReplayedGame;
+#[cfg(feature = "extension")]
+use super::replay::{ReplayCache, on_illegal_arg, on_illegal_signatures};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use shakmaty::{CastlingMode, Chess, Color, Move, Position, Role, fen::Fen};
+use shakmaty::{EnPassantMode, Square};
+#[cfg(feature = "extension")]
+use std::error::Error;
+
+/// Score of a forced mate in centipawns, less the moves to mate, so any mate outranks any
+/// material balance and shorter mates outrank longer ones.
+const MATE_SCORE: i32 = 100_000;
+/// Largest centipawn evaluation accepted from a `%eval` annotation.
+const MAX_EVAL_CP: i32 = MATE_SCORE / 2;
+/// Gain in centipawns for the side to move that marks the previous move as a blunder.
+const MIN_SWING_CP: i32 = 200;
+/// Score the side to move must have after the blunder, and keep after its first reply.
+const WINNING_CP: i32 = 200;
+/// Score from which a puzzle is `crushing` rather than an `advantage`.
+const CRUSHING_CP: i32 = 500;
+/// Longest solution kept, in plies.
+const MAX_LINE_PLIES: usize = 9;
+
+/// Engine evaluation from a `%eval` annotation, from White's point of view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Eval {
+    Centipawns(i32),
+    /// Moves to mate; negative when Black mates.
+    Mate(i32),
+}
+
+impl Eval {
+    /// Score for `color` in centipawns, with forced mates mapped beyond any material balance.
+    fn score_for(self, color: Color) -> i32 {
+        let white = match self {
+            Self::Centipawns(cp) => cp,
+            Self::Mate(moves) if moves > 0 => MATE_SCORE - moves,
+            Self::Mate(moves) => -MATE_SCORE - moves,
+        };
+        color.fold_wb(white, -white)
+    }
+}
+
+/// Parse the first `[%eval ...]` annotation in a comment: pawns such as `0.25` or `-1.3`, or a
+/// mate such as `#3` or `#-2`, optionally followed by `,depth`.
+pub fn parse_eval(comment: &str) -> Option<Eval> {
+    let start = comment.find("[%eval")? + "[%eval".len();
+    let rest = &comment[start..];
+    let end = rest.find(']')?;
+    let raw = rest[..end].trim();
+    parse_eval_value(raw.split_once(',').map_or(raw, |(value, _)| value))
+}
+
+/// Parse an evaluation written as pawns (`0.25`, `-1.3`) or as a mate (`#3`, `#-2`).
+pub(crate) fn parse_eval_value(value: &str) -> Option<Eval> {
+    let value = value.trim();
+    if let Some(moves) = value.strip_prefix('#') {
+        let moves: i32 = moves.parse().ok()?;
+        return (moves != 0 && moves.abs() < MATE_SCORE / 2).then_some(Eval::Mate(moves));
+    }
+    let pawns: f64 = value.parse().ok()?;
+    let cp = (pawns * 100.0).round();
+    (cp.is_finite() && cp.abs() <= f64::from(MAX_EVAL_CP)).then_some(Eval::Centipawns(cp as i32))
+}
+
+/// A position after a blunder from which the game continued with a forcing refutation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PuzzleCandidate {
+    /// Plies played before the puzzle position.
+    pub ply: usize,
+    pub fen: String,
+    /// The game's continuation in UCI, starting and ending with a move of the side to move.
+    pub best_line: Vec<String>,
+    /// Lichess-style theme names, most general first.
+    pub themes: Vec<&'static str>,
+}
+
+/// Find tactical puzzle candidates in a game with `%eval` annotations.
+/// Spec: move-analysis - Puzzle Candidates
+///
+/// A candidate is the position after a move that gains the opponent at least `MIN_SWING_CP`
+/// and leaves it at least `WINNING_CP` ahead, when the opponent answered with a forcing move
+/// (a check, capture or promotion) that kept the advantage. The solution follows the game
+/// while the solving side keeps playing forcing moves, up to `MAX_LINE_PLIES` plies or mate.
+/// `evals[n]` is the evaluation after ply `n + 1`, as returned for the movetext of `game` by
+/// [`eval_series`]; moves without an evaluation on either side are never candidates.
+pub fn puzzle_candidates(game: &ReplayedGame, evals: &[Option<Eval>]) -> Vec<PuzzleCandidate> {
+    let eval_after = |ply: usize| evals.get(ply.checked_sub(1)?).copied().flatten();
+
+    let mut candidates = Vec::new();
+    let mut ply = 2;
+    while ply <= game.plies() {
+        let solver = game.positions[ply].turn();
+        let (Some(before), Some(after)) = (eval_after(ply - 1), eval_after(ply)) else {
+            ply += 1;
+            continue;
+        };
+        let score = after.score_for(solver);
+        if score - before.score_for(solver) < MIN_SWING_CP || score < WINNING_CP {
+            ply += 1;
+            continue;
+        }
+        let line = forcing_line(game, ply);
+        let kept_advantage =
+            eval_after(ply + 1).is_none_or(|eval| eval.score_for(solver) >= WINNING_CP);
+        if line.is_empty() || !kept_advantage {
+            ply += 1;
+            continue;
+        }
+
+        candidates.push(PuzzleCandidate {
+            ply,
+            fen: Fen::from_position(&game.positions[ply], EnPassantMode::Legal).to_string(),
+            best_line: line
+                .iter()
+                .map(|m| m.to_uci(CastlingMode::Standard).to_string())
+                .collect(),
+            themes: themes(game, ply, &line, score),
+        });
+        // Positions inside a solution are not puzzles of their own.
+        ply += line.len() + 1;
+    }
+    candidates
+}
+
+/// The game's moves from `ply` while the side to move at `ply` keeps playing forcing moves,
+/// ending with that side's last forcing move.
+fn forcing_line(game: &ReplayedGame, ply: usize) -> Vec<Move> {
+    let mut line = Vec::new();
+    let mut idx = ply;
+    while idx < game.plies() && line.len() < MAX_LINE_PLIES {
+        let m = game.moves[idx];
+        let gives_check = game.positions[idx + 1].is_check();
+        if !(gives_check || m.is_capture() || m.is_promotion()) {
+            break;
+        }
+        line.push(m);
+        if game.positions[idx + 1].is_checkmate() || idx + 1 == game.plies() {
+            break;
+        }
+        line.push(game.moves[idx + 1]);
+        idx += 2;
+    }
+    if line.len() % 2 == 0 {
+        line.pop();
+    }
+    line
+}
+
+fn themes(game: &ReplayedGame, ply: usize, line: &[Move], score: i32) -> Vec<&'static str> {
+    let end = &game.positions[ply + line.len()];
+    let solver_moves: Vec<(usize, Move)> = line
+        .iter()
+        .step_by(2)
+        .enumerate()
+        .map(|(idx, &m)| (ply + 2 * idx, m))
+        .collect();
+
+    let mut themes = vec![if end.is_checkmate() {
+        "mate"
+    } else if score >= CRUSHING_CP {
+        "crushing"
+    } else {
+        "advantage"
+    }];
+    if is_hanging_capture(&game.positions[ply], line[0]) {
+        themes.push("hangingPiece");
+    }
+    if solver_moves
+        .iter()
+        .any(|&(idx, m)| is_fork(&game.positions[idx + 1], m.to()))
+    {
+        themes.push("fork");
+    }
+    if solver_moves.iter().any(|(_, m)| m.is_promotion()) {
+        themes.push("promotion");
+    }
+    themes.push(match solver_moves.len() {
+        1 => "oneMove",
+        2 => "short",
+        3 => "long",
+        _ => "veryLong",
+    });
+    themes
+}
+
+/// Whether `m` captures a piece its owner did not defend.
+fn is_hanging_capture(pos: &Chess, m: Move) -> bool {
+    let (Some(from), Some(captured)) = (m.from(), m.capture()) else {
+        return false;
+    };
+    if captured == Role::Pawn {
+        return false;
+    }
+    let board = pos.board();
+    let occupied = board.occupied().without(from);
+    board.attacks_to(m.to(), !pos.turn(), occupied).is_empty()
+}
+
+/// Whether the piece that just moved to `to` attacks two or more enemy pieces other than pawns,
+/// without mating.
+fn is_fork(pos: &Chess, to: Square) -> bool {
+    let board = pos.board();
+    if board.role_at(to) == Some(Role::King) || pos.is_checkmate() {
+        return false;
+    }
+    let targets = board.by_color(pos.turn()) & !board.pawns();
+    (board.attacks_from(to) & targets).count() >= 2
+}
+
+/// One slot per mainline ply holding its `%eval`, or `None`. Returns `None` when the movetext
+/// cannot be parsed.
+pub fn eval_series(movetext: &str) -> Option<Vec<Option<Eval>>> {
+    annotation_series(movetext, parse_eval)
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessPuzzleCandidatesScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessPuzzleCandidatesScalar {
+    type State = ReplayCache;
+
+    unsafe fn invoke(
+        cache: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let on_illegal = on_illegal_arg(input, 1)?;
+        // The list helper cannot fail a row, so an `error` mode failure is raised afterwards.
+        let mut failure = None;
+        invoke_unary_varchar_to_varchar_struct_list(input, output, |movetext| {
+            let evals = eval_series(movetext)?;
+            // Games without engine analysis are not worth a replay.
+            if evals.iter().all(Option::is_none) {
+                return Some(Vec::new());
+            }
+            let game = match cache.replay_with(movetext, on_illegal) {
+                Ok(game) => game?,
+                Err(e) => {
+                    failure.get_or_insert(e);
+                    return None;
+                }
+            };
+            Some(
+                puzzle_candidates(&game, &evals)
+                    .into_iter()
+                    .map(|candidate| {
+                        [
+                            candidate.ply.to_string(),
+                            candidate.fen,
+                            candidate.best_line.join(" "),
+                            candidate.themes.join(" "),
+                        ]
+                    })
+                    .collect(),
+            )
+        })?;
+        match failure {
+            Some(e) => Err(format!("chess_puzzle_candidates: {e}").into()),
+            None => Ok(()),
+        }
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let candidate_type = LogicalTypeHandle::struct_type(&[
+            ("ply", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("fen", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("best_line", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("themes", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+        ]);
+        on_illegal_signatures(
+            || vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            || LogicalTypeHandle::list(&candidate_type),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::replay::replay_mainline;
+
+    fn candidates(movetext: &str) -> Vec<PuzzleCandidate> {
+        let game = replay_mainline(movetext).unwrap();
+        puzzle_candidates(&game, &eval_series(movetext).unwrap())
+    }
+
+    #[test]
+    fn test_parse_eval_formats() {
+        assert_eq!(parse_eval("[%eval 0.25]"), Some(Eval::Centipawns(25)));
+        assert_eq!(
+            parse_eval("[%clk 0:03:00] [%eval -1.3,22]"),
+            Some(Eval::Centipawns(-130))
+        );
+        assert_eq!(parse_eval("[%eval #3]"), Some(Eval::Mate(3)));
+        assert_eq!(parse_eval("[%eval #-2]"), Some(Eval::Mate(-2)));
+        assert_eq!(parse_eval("[%eval #0]"), None);
+        assert_eq!(parse_eval("[%eval abc]"), None);
+        assert_eq!(parse_eval("[%eval 0.25"), None);
+        assert_eq!(parse_eval("[%clk 0:03:00]"), None);
+    }
+
+    #[test]
+    fn test_eval_scores_mates_beyond_material() {
+        assert_eq!(Eval::Centipawns(150).score_for(Color::Black), -150);
+        assert!(Eval::Mate(5).score_for(Color::White) > MAX_EVAL_CP);
+        assert!(Eval::Mate(1).score_for(Color::White) > Eval::Mate(5).score_for(Color::White));
+        assert!(Eval::Mate(-1).score_for(Color::White) < -MAX_EVAL_CP);
+        assert!(Eval::Mate(-1).score_for(Color::Black) > Eval::Mate(-3).score_for(Color::Black));
+    }
+
+    #[test]
+    fn test_puzzle_candidates_mate_in_one() {
+        let found = candidates(
+            "1. e4 { [%eval 0.3] } e5 { [%eval 0.3] } 2. Bc4 { [%eval 0.2] } Nc6 { [%eval 0.2] } \
+             3. Qh5 { [%eval -0.2] } Nf6 { [%eval #1] } 4. Qxf7# 1-0",
+        );
+        assert_eq!(
+            found,
+            vec![PuzzleCandidate {
+                ply: 6,
+                fen: "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4"
+                    .to_string(),
+                best_line: vec!["h5f7".to_string()],
+                themes: vec!["mate", "oneMove"],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_puzzle_candidates_hanging_piece() {
+        let found = candidates(
+            "1. e4 { [%eval 0.3] } e5 { [%eval 0.3] } 2. Nf3 { [%eval 0.3] } Qg5 { [%eval 9.0] } \
+             3. Nxg5 { [%eval 9.2] } Nc6 { [%eval 9.3] } 4. d3",
+        );
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].ply, 4);
+        assert_eq!(found[0].best_line, ["f3g5"]);
+        assert_eq!(found[0].themes, ["crushing", "hangingPiece", "oneMove"]);
+    }
+
+    #[test]
+    fn test_puzzle_candidates_follow_forcing_line() {
+        // 5. Nxf7 forks queen and rook; the solution continues through 6. Kxf2.
+        let found = candidates(
+            "1. e4 { [%eval 0.3] } e5 { [%eval 0.3] } 2. Nf3 { [%eval 0.3] } Nc6 { [%eval 0.3] } \
+             3. Bc4 { [%eval 0.3] } Nf6 { [%eval 0.3] } 4. Ng5 { [%eval 0.5] } Bc5 { [%eval 2.5] } \
+             5. Nxf7 { [%eval 2.4] } Bxf2+ { [%eval 2.6] } 6. Kxf2 { [%eval 2.6] } Nxe4+ \
+             { [%eval 2.5] } 7. Kg1 { [%eval 2.5] }",
+        );
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].ply, 8);
+        assert_eq!(found[0].best_line, ["g5f7", "c5f2", "e1f2"]);
+        assert_eq!(found[0].themes, ["advantage", "fork", "short"]);
+    }
+
+    #[test]
+    fn test_puzzle_candidates_require_swing_and_forcing_reply() {
+        // A quiet reply to the blunder does not make a puzzle.
+        assert!(
+            candidates(
+                "1. e4 { [%eval 0.3] } e5 { [%eval 0.3] } 2. Nf3 { [%eval 0.3] } Qg5 \
+                 { [%eval 9.0] } 3. d3 { [%eval 0.5] }"
+            )
+            .is_empty()
+        );
+        // A capture that throws the advantage away is not the refutation.
+        assert!(
+            candidates(
+                "1. e4 { [%eval 0.3] } e5 { [%eval 0.3] } 2. Nf3 { [%eval 0.3] } Qg5 \
+                 { [%eval 9.0] } 3. Nxe5 { [%eval -0.5] }"
+            )
+            .is_empty()
+        );
+        // Small swings and games without evaluations have no candidates.
+        assert!(
+            candidates("1. e4 { [%eval 0.3] } e5 { [%eval 1.2] } 2. Qh5 { [%eval 1.0] } Nc6")
+                .is_empty()
+        );
+        assert!(candidates("1. e4 e5 2. Nf3 Qg5 3. Nxg5").is_empty());
+    }
+}
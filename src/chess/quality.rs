@@ -0,0 +1,282 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{VarcharInput, invoke_unary_nullable};
+use super::replay::ReplayedGame;
+#[cfg(test)]
+use super::replay::replay_mainline;
+#[cfg(feature = "extension")]
+use super::replay::{ReplayCache, on_illegal_arg, on_illegal_signatures};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use pgn_reader::{KnownOutcome, Nag, Outcome, RawComment, Reader, SanPlus, Skip, Visitor};
+use shakmaty::{Chess, Position};
+#[cfg(feature = "extension")]
+use std::error::Error;
+use std::io;
+use std::ops::ControlFlow;
+
+/// Games reaching this many plies get the full length component.
+const FULL_LENGTH_PLIES: u32 = 80;
+/// Draws shorter than this are treated as likely pre-arranged.
+pub(crate) const SHORT_DRAW_PLIES: u32 = 40;
+
+const LENGTH_WEIGHT: f64 = 0.35;
+const FINISH_WEIGHT: f64 = 0.25;
+const BLUNDER_WEIGHT: f64 = 0.25;
+const ANNOTATION_WEIGHT: f64 = 0.15;
+
+/// Cheap heuristic quality score in `[0, 1]` for filtering junk games before deeper analysis.
+/// Spec: move-analysis - Game Quality
+///
+/// Weighted sum of:
+/// - length: plies relative to [`FULL_LENGTH_PLIES`]
+/// - finish: 1 for a decisive result (checkmate or a `1-0`/`0-1` marker), 0.5 for a draw of at
+///   least [`SHORT_DRAW_PLIES`] plies, 0 for a shorter draw, 0.25 when the result is unknown
+/// - blunders: `1 / (1 + n)` where `n` counts moves that left a mate in one on the board
+/// - annotations: comments with prose (not just `[%...]` commands) and NAGs per ply, capped at 1
+///
+/// Empty movetext scores 0. Returns `None` when the movetext cannot be parsed or contains an
+/// illegal move.
+#[cfg(test)]
+pub fn game_quality(movetext: &str) -> Option<f64> {
+    if movetext.trim().is_empty() {
+        return Some(0.0);
+    }
+    replay_mainline(movetext).map(|game| game_quality_of(movetext, &game))
+}
+
+/// Quality score of `game`, the replayed mainline of `movetext`. Annotations and the result
+/// marker only count up to the last replayed ply, so a game cut short at an illegal move is
+/// scored as it stood before it.
+fn game_quality_of(movetext: &str, game: &ReplayedGame) -> f64 {
+    let plies = game.plies();
+    if plies == 0 {
+        return 0.0;
+    }
+
+    let mut visitor = AnnotationVisitor {
+        max_plies: plies,
+        ..AnnotationVisitor::default()
+    };
+    let mut reader = Reader::new(io::Cursor::new(movetext.as_bytes()));
+    let _ = reader.read_game(&mut visitor);
+
+    let hanging_mates = game.positions[1..]
+        .iter()
+        .filter(|pos| has_mate_in_one(pos))
+        .count();
+    let plies = u32::try_from(plies).unwrap_or(u32::MAX);
+    let length = f64::from(plies.min(FULL_LENGTH_PLIES)) / f64::from(FULL_LENGTH_PLIES);
+    let outcome = game.final_position().outcome().known().or(visitor.outcome);
+    let finish = match outcome {
+        Some(KnownOutcome::Decisive { .. }) => 1.0,
+        Some(KnownOutcome::Draw) if plies < SHORT_DRAW_PLIES => 0.0,
+        Some(KnownOutcome::Draw) => 0.5,
+        None => 0.25,
+    };
+    let blunders = 1.0 / (1.0 + hanging_mates as f64);
+    let annotations = (f64::from(visitor.annotations) / f64::from(plies)).min(1.0);
+
+    LENGTH_WEIGHT * length
+        + FINISH_WEIGHT * finish
+        + BLUNDER_WEIGHT * blunders
+        + ANNOTATION_WEIGHT * annotations
+}
+
+/// Annotations and result marker of the first `max_plies` mainline plies.
+#[derive(Default)]
+struct AnnotationVisitor {
+    max_plies: usize,
+    plies: usize,
+    annotations: u32,
+    outcome: Option<KnownOutcome>,
+}
+
+/// Whether the side to move can deliver checkmate immediately.
+fn has_mate_in_one(pos: &Chess) -> bool {
+    pos.legal_moves().into_iter().any(|m| {
+        let mut next = pos.clone();
+        next.play_unchecked(m);
+        next.is_checkmate()
+    })
+}
+
+/// Comment text other than `[%cmd ...]` annotations such as clocks or evals.
+fn has_prose(comment: &[u8]) -> bool {
+    let mut depth = 0usize;
+    let mut rest = comment;
+    while let Some((&byte, tail)) = rest.split_first() {
+        if rest.starts_with(b"[%") {
+            depth += 1;
+        } else if byte == b']' && depth > 0 {
+            depth -= 1;
+        } else if depth == 0 && !byte.is_ascii_whitespace() {
+            return true;
+        }
+        rest = tail;
+    }
+    false
+}
+
+impl Visitor for AnnotationVisitor {
+    type Tags = ();
+    type Movetext = ();
+    type Output = ();
+
+    fn begin_tags(&mut self) -> ControlFlow<Self::Output, Self::Tags> {
+        ControlFlow::Continue(())
+    }
+
+    fn begin_movetext(&mut self, _tags: Self::Tags) -> ControlFlow<Self::Output, Self::Movetext> {
+        ControlFlow::Continue(())
+    }
+
+    fn san(
+        &mut self,
+        _movetext: &mut Self::Movetext,
+        _san_plus: SanPlus,
+    ) -> ControlFlow<Self::Output> {
+        if self.plies == self.max_plies {
+            return ControlFlow::Break(());
+        }
+        self.plies += 1;
+        ControlFlow::Continue(())
+    }
+
+    fn nag(&mut self, _movetext: &mut Self::Movetext, _nag: Nag) -> ControlFlow<Self::Output> {
+        self.annotations += 1;
+        ControlFlow::Continue(())
+    }
+
+    fn comment(
+        &mut self,
+        _movetext: &mut Self::Movetext,
+        comment: RawComment<'_>,
+    ) -> ControlFlow<Self::Output> {
+        if has_prose(comment.as_bytes()) {
+            self.annotations += 1;
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn begin_variation(
+        &mut self,
+        _movetext: &mut Self::Movetext,
+    ) -> ControlFlow<Self::Output, Skip> {
+        ControlFlow::Continue(Skip(true))
+    }
+
+    fn outcome(
+        &mut self,
+        _movetext: &mut Self::Movetext,
+        outcome: Outcome,
+    ) -> ControlFlow<Self::Output> {
+        self.outcome = outcome.known();
+        ControlFlow::Continue(())
+    }
+
+    fn end_game(&mut self, _movetext: Self::Movetext) -> Self::Output {}
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessGameQualityScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessGameQualityScalar {
+    type State = ReplayCache;
+
+    unsafe fn invoke(
+        cache: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let on_illegal = on_illegal_arg(input, 1)?;
+        invoke_unary_nullable::<VarcharInput, _, _>(input, output, |movetext| {
+            if movetext.trim().is_empty() {
+                return Ok(Some(0.0));
+            }
+            Ok(cache
+                .replay_with(movetext, on_illegal)
+                .map_err(|e| format!("chess_game_quality: {e}"))?
+                .map(|game| game_quality_of(movetext, &game)))
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        on_illegal_signatures(
+            || vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            || LogicalTypeHandle::from(LogicalTypeId::Double),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::replay::replay_mainline_best_effort;
+
+    const SCHOLARS_MATE: &str = "1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7#";
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_game_quality_counts_hanging_mates() {
+        let game = replay_mainline(SCHOLARS_MATE).unwrap();
+        assert_eq!(game.plies(), 7);
+        assert!(game.final_position().is_checkmate());
+
+        // 3...Nf6?? allows Qxf7#.
+        let expected = LENGTH_WEIGHT * 7.0 / 80.0 + FINISH_WEIGHT + BLUNDER_WEIGHT * 0.5;
+        assert_close(game_quality(SCHOLARS_MATE).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_game_quality_of_truncated_game_ignores_the_rest() {
+        // Best effort keeps `1. e4 e5`; the NAG and result marker after the illegal move do
+        // not count.
+        let movetext = "1. e4 { good } e5 2. Ke3 $2 1-0";
+        let game = replay_mainline_best_effort(movetext).unwrap();
+        assert_eq!(game.dropped_plies, 1);
+        assert_eq!(game_quality(movetext), None);
+        let expected = LENGTH_WEIGHT * 2.0 / 80.0
+            + FINISH_WEIGHT * 0.25
+            + BLUNDER_WEIGHT
+            + ANNOTATION_WEIGHT * 0.5;
+        assert_close(game_quality_of(movetext, &game), expected);
+    }
+
+    #[test]
+    fn test_game_quality_penalizes_short_draws() {
+        let short_draw = game_quality("1. e4 e5 2. Nf3 Nc6 1/2-1/2").unwrap();
+        let unknown = game_quality("1. e4 e5 2. Nf3 Nc6").unwrap();
+        let decisive = game_quality("1. e4 e5 2. Nf3 Nc6 1-0").unwrap();
+        assert!(short_draw < unknown);
+        assert!(unknown < decisive);
+        assert_close(decisive - short_draw, FINISH_WEIGHT);
+    }
+
+    #[test]
+    fn test_game_quality_rewards_prose_annotations_only() {
+        let plain = game_quality("1. e4 e5 2. Nf3").unwrap();
+        let clocks = game_quality("1. e4 { [%clk 0:03:00] } e5 { [%eval 0.2] } 2. Nf3").unwrap();
+        let annotated = game_quality("1. e4 { best by test } e5 $2 2. Nf3").unwrap();
+        assert_close(plain, clocks);
+        assert_close(annotated - plain, ANNOTATION_WEIGHT * 2.0 / 3.0);
+    }
+
+    #[test]
+    fn test_game_quality_edge_cases() {
+        assert_eq!(game_quality(""), Some(0.0));
+        assert_eq!(game_quality("1. e4 e5 2. Ke3"), None);
+        assert!(has_prose(b"good move [%clk 0:01:00]"));
+        assert!(!has_prose(b" [%clk 0:01:00] [%eval #3] "));
+    }
+}
@@ -1,42 +1,128 @@
+pub(crate) use super::compression::CompressionMode;
 use super::{
-    duckdb_impl::bind_info_ffi::{self, NamedParameterVarchar},
+    boundary::LineDiagnostics,
+    compression::{open_decoder, read_magic_bytes, sniff_compression},
+    diagnostics::ScanDiagnostics,
+    duckdb_impl::{
+        bind_info_ffi::{self, NamedParameterVarchar},
+        file_system::{DuckDbFile, DuckDbFileSystem, is_remote_path},
+        string,
+    },
+    filter::normalize_movetext,
     log,
-    types::GameRecord,
-    visitor::{PgnInput, PgnReaderState, SharedState},
+    prefetch::PrefetchReader,
+    settings::{
+        DEFAULT_COMPRESSION_SETTING, DEFAULT_MAX_OPEN_FILES, LOG_LEVEL_SETTING,
+        MAX_OPEN_FILES_SETTING,
+    },
+    types::{
+        ExtraTagColumn, ExtraTagType, ExtraTagValue, GameRecord, MoveRecord, PgnDate, PgnTimeTz,
+    },
+    validate::{VALIDATE_SAMPLE_BYTES, VALIDATE_SAMPLE_GAMES, validate_pgn_sample},
+    visitor::{
+        GameFilter, MovesAs, MovetextStyle, PgnInput, PgnReaderState, ResumePoint, ScanOptions,
+        SharedState, VariantFilter,
+    },
 };
 use crate::chess::ErrorAccumulator;
 use duckdb::{
-    core::{DataChunkHandle, Inserter, LogicalTypeHandle, LogicalTypeId},
+    core::{
+        DataChunkHandle, FlatVector, Inserter, ListVector, LogicalTypeHandle, LogicalTypeId,
+        StructVector,
+    },
     vtab::{BindInfo, InitInfo, TableFunctionInfo, VTab},
 };
 use libduckdb_sys::{duckdb_date, duckdb_time_tz};
 use std::borrow::Cow;
-use std::fs::File;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
-use std::sync::{Mutex, MutexGuard};
-use zstd::stream::read::Decoder as ZstdDecoder;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
 
 #[repr(C)]
 pub struct ReadPgnBindData {
+    /// The pattern as written, which keys this scan's `read_pgn_line_diagnostics()` counters.
+    path_pattern: String,
     paths: Vec<PathBuf>,
     compression: CompressionMode,
+    options: ScanOptions,
+    schema_version: u32,
+    optional_columns: OptionalColumns,
+    /// `background_decompression := true`: decode compressed files on a worker thread.
+    background_decompression: bool,
+    /// `duckdb_chess_max_open_files`: idle readers kept open between chunks.
+    max_open_files: usize,
+    /// DuckDB's file system, set when the path is a URL such as `s3://`.
+    file_system: Option<DuckDbFileSystem>,
+}
+
+impl ReadPgnBindData {
+    /// Whether idle readers may be evicted, which needs every game's offset to resume.
+    fn evicts_readers(&self) -> bool {
+        self.paths.len() > self.max_open_files
+    }
+
+    /// Bound columns computed from the movetext, which must be parsed when they are read.
+    /// Invalid UTF-8 in comments counts towards `encoding_issues` and its `parse_error` note.
+    fn movetext_derived_columns(&self) -> Vec<usize> {
+        let first = read_pgn_columns(self.schema_version).count();
+        let columns = &self.optional_columns;
+        [
+            columns.game_id.then(|| first + columns.game_id_offset()),
+            columns
+                .encoding_issues
+                .then(|| first + columns.encoding_issues_offset()),
+            columns
+                .encoding_issues
+                .then_some(ReadPgnColumn::ParseError.index()),
+            columns
+                .normalize
+                .then(|| first + columns.normalized_movetext_offset()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
 }
 
 #[repr(C)]
 pub struct ReadPgnInitData {
     state: Mutex<SharedState>,
+    /// Bound columns the query reads, in output order (projection pushdown).
+    column_indices: Vec<u64>,
+    line_diagnostics: Arc<LineDiagnostics>,
 }
 
 pub struct ReadPgnVTab;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum CompressionMode {
-    Plain,
-    Zstd,
-}
-
-const PATH_PATTERN_PARAM_INDEX: u64 = 0;
-const READ_PGN_COLUMN_COUNT: usize = 18;
+pub(crate) const PATH_PATTERN_PARAM_INDEX: u64 = 0;
+const READ_PGN_COLUMN_COUNT: usize = 23;
+
+/// VARCHAR columns added after the schema columns by `elo_raw_columns := true`.
+const ELO_RAW_COLUMNS: [&str; 2] = ["WhiteEloRaw", "BlackEloRaw"];
+/// UUID column added after the raw Elo columns by `game_id := true`.
+const GAME_ID_COLUMN: &str = "game_id";
+/// UBIGINT column added after `game_id` by `line_number := true`.
+const LINE_NUMBER_COLUMN: &str = "line_number";
+/// UINTEGER column added after `line_number` by `encoding_issues := true`.
+const ENCODING_ISSUES_COLUMN: &str = "encoding_issues";
+/// BOOLEAN column added after `encoding_issues` by `eco_backfill := true`.
+const ECO_INFERRED_COLUMN: &str = "eco_inferred";
+/// VARCHAR column added after `eco_inferred` by `normalize := true`.
+const NORMALIZED_MOVETEXT_COLUMN: &str = "normalized_movetext";
+/// VARCHAR[] column added after `normalized_movetext` by `warnings := true`.
+const WARNINGS_COLUMN: &str = "warnings";
+/// UUID v5 namespace of `game_id`, itself the v5 UUID of
+/// `https://github.com/dotneB/duckdb-chess/read_pgn/game_id` in the URL namespace.
+const GAME_ID_NAMESPACE: Uuid = Uuid::from_u128(0x2024f555_5d4d_5bb9_bf06_e9cf05b5eb35);
+
+/// Latest `read_pgn` output schema.
+pub(crate) const READ_PGN_SCHEMA_VERSION: u32 = 3;
+/// Schema used when `schema_version` is omitted. It stays pinned so that adding columns
+/// in a later schema version never changes the shape of existing `SELECT *` queries.
+pub(crate) const READ_PGN_DEFAULT_SCHEMA_VERSION: u32 = 1;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum ReadPgnColumn {
@@ -58,6 +144,11 @@ enum ReadPgnColumn {
     Movetext = 15,
     ParseError = 16,
     Source = 17,
+    ByteOffset = 18,
+    MovetextBytes = 19,
+    HasClk = 20,
+    HasEval = 21,
+    HasVariations = 22,
 }
 
 impl ReadPgnColumn {
@@ -73,7 +164,9 @@ impl ReadPgnColumn {
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum ReadPgnLogicalType {
     Varchar,
+    Boolean,
     UInteger,
+    UBigInt,
     Date,
     TimeTz,
 }
@@ -82,132 +175,208 @@ impl ReadPgnLogicalType {
     fn to_handle(self) -> LogicalTypeHandle {
         match self {
             Self::Varchar => LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            Self::Boolean => LogicalTypeHandle::from(LogicalTypeId::Boolean),
             Self::UInteger => LogicalTypeHandle::from(LogicalTypeId::UInteger),
+            Self::UBigInt => LogicalTypeHandle::from(LogicalTypeId::UBigint),
             Self::Date => LogicalTypeHandle::from(LogicalTypeId::Date),
             Self::TimeTz => LogicalTypeHandle::from(LogicalTypeId::TimeTZ),
         }
     }
 }
 
-struct ReadPgnColumnDef {
-    name: &'static str,
+pub(crate) struct ReadPgnColumnDef {
+    pub(crate) name: &'static str,
+    /// First `schema_version` that exposes this column. New columns are only ever appended,
+    /// so each schema version projects a prefix of `READ_PGN_COLUMNS`.
+    since_schema_version: u32,
     logical_type: ReadPgnLogicalType,
 }
 
-const READ_PGN_COLUMNS: [ReadPgnColumnDef; READ_PGN_COLUMN_COUNT] = [
+impl ReadPgnColumnDef {
+    pub(crate) fn logical_type_handle(&self) -> LogicalTypeHandle {
+        self.logical_type.to_handle()
+    }
+}
+
+pub(crate) const READ_PGN_COLUMNS: [ReadPgnColumnDef; READ_PGN_COLUMN_COUNT] = [
     ReadPgnColumnDef {
         name: "Event",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::Varchar,
     },
     ReadPgnColumnDef {
         name: "Site",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::Varchar,
     },
     ReadPgnColumnDef {
         name: "White",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::Varchar,
     },
     ReadPgnColumnDef {
         name: "Black",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::Varchar,
     },
     ReadPgnColumnDef {
         name: "Result",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::Varchar,
     },
     ReadPgnColumnDef {
         name: "WhiteTitle",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::Varchar,
     },
     ReadPgnColumnDef {
         name: "BlackTitle",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::Varchar,
     },
     ReadPgnColumnDef {
         name: "WhiteElo",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::UInteger,
     },
     ReadPgnColumnDef {
         name: "BlackElo",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::UInteger,
     },
     ReadPgnColumnDef {
         name: "UTCDate",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::Date,
     },
     ReadPgnColumnDef {
         name: "UTCTime",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::TimeTz,
     },
     ReadPgnColumnDef {
         name: "ECO",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::Varchar,
     },
     ReadPgnColumnDef {
         name: "Opening",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::Varchar,
     },
     ReadPgnColumnDef {
         name: "Termination",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::Varchar,
     },
     ReadPgnColumnDef {
         name: "TimeControl",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::Varchar,
     },
     ReadPgnColumnDef {
         name: "movetext",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::Varchar,
     },
     ReadPgnColumnDef {
         name: "parse_error",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::Varchar,
     },
     ReadPgnColumnDef {
         name: "Source",
+        since_schema_version: 1,
         logical_type: ReadPgnLogicalType::Varchar,
     },
+    ReadPgnColumnDef {
+        name: "byte_offset",
+        since_schema_version: 2,
+        logical_type: ReadPgnLogicalType::UBigInt,
+    },
+    ReadPgnColumnDef {
+        name: "movetext_bytes",
+        since_schema_version: 3,
+        logical_type: ReadPgnLogicalType::UBigInt,
+    },
+    ReadPgnColumnDef {
+        name: "has_clk",
+        since_schema_version: 3,
+        logical_type: ReadPgnLogicalType::Boolean,
+    },
+    ReadPgnColumnDef {
+        name: "has_eval",
+        since_schema_version: 3,
+        logical_type: ReadPgnLogicalType::Boolean,
+    },
+    ReadPgnColumnDef {
+        name: "has_variations",
+        since_schema_version: 3,
+        logical_type: ReadPgnLogicalType::Boolean,
+    },
 ];
 
+/// Columns exposed by `read_pgn` for the given schema version, in output order.
+pub(crate) fn read_pgn_columns(
+    schema_version: u32,
+) -> impl Iterator<Item = &'static ReadPgnColumnDef> {
+    READ_PGN_COLUMNS
+        .iter()
+        .filter(move |column| column.since_schema_version <= schema_version)
+}
+
+const SUPPORTED_EXTRA_TAG_TYPES: &str =
+    "VARCHAR, BOOLEAN, INTEGER, BIGINT, UINTEGER, UBIGINT, DOUBLE, DATE";
+
+const SUPPORTED_COMPRESSION_VALUES: &str = "'auto', 'zstd', 'gzip', 'bzip2', 'xz' or NULL/omitted";
+
 impl CompressionMode {
     fn parse(raw: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let normalized = raw.trim();
-        if normalized.is_empty() {
-            return Err(
-                "Invalid compression value ''. Supported values: 'zstd' or NULL/omitted."
-                    .to_string()
-                    .into(),
-            );
-        }
-
-        if normalized.eq_ignore_ascii_case("zstd") {
-            Ok(Self::Zstd)
-        } else {
-            Err(format!(
-                "Invalid compression value '{}'. Supported values: 'zstd' or NULL/omitted.",
-                normalized
+        let mode = [
+            ("auto", Self::Auto),
+            ("zstd", Self::Zstd),
+            ("gzip", Self::Gzip),
+            ("bzip2", Self::Bzip2),
+            ("xz", Self::Xz),
+        ]
+        .into_iter()
+        .find(|(name, _)| normalized.eq_ignore_ascii_case(name))
+        .map(|(_, mode)| mode);
+
+        mode.ok_or_else(|| {
+            format!(
+                "Invalid compression value '{}'. Supported values: {}.",
+                normalized, SUPPORTED_COMPRESSION_VALUES
             )
-            .into())
-        }
+            .into()
+        })
     }
 }
 
-fn resolve_compression_mode(
+pub(crate) fn resolve_compression_mode(
     bind: &BindInfo,
 ) -> Result<CompressionMode, Box<dyn std::error::Error>> {
     let compression = bind_info_ffi::get_named_parameter_varchar(bind, "compression")?;
-    resolve_compression_mode_from_named_parameter(compression)
+    let default = bind_info_ffi::get_setting_varchar(bind, DEFAULT_COMPRESSION_SETTING)?;
+    resolve_compression_mode_from_named_parameter(
+        compression,
+        parse_default_compression_mode(default)?,
+    )
 }
 
+/// An omitted, NULL or `'null'` parameter falls back to `default`, the session's
+/// `duckdb_chess_default_compression`.
 fn resolve_compression_mode_from_named_parameter(
     compression: NamedParameterVarchar,
+    default: CompressionMode,
 ) -> Result<CompressionMode, Box<dyn std::error::Error>> {
     match compression {
-        NamedParameterVarchar::Missing | NamedParameterVarchar::Null => Ok(CompressionMode::Plain),
+        NamedParameterVarchar::Missing | NamedParameterVarchar::Null => Ok(default),
         NamedParameterVarchar::Value(raw) => {
             let normalized = raw.trim();
             if normalized.eq_ignore_ascii_case("null") {
-                Ok(CompressionMode::Plain)
+                Ok(default)
             } else {
                 CompressionMode::parse(normalized)
             }
@@ -215,25 +384,700 @@ fn resolve_compression_mode_from_named_parameter(
     }
 }
 
-fn open_input_stream(path: &PathBuf, compression: CompressionMode) -> Result<PgnInput, String> {
-    let file =
-        File::open(path).map_err(|e| format!("Failed to open file '{}': {}", path.display(), e))?;
+fn parse_default_compression_mode(
+    setting: Option<String>,
+) -> Result<CompressionMode, Box<dyn std::error::Error>> {
+    match setting {
+        None => Ok(CompressionMode::Auto),
+        Some(raw) => CompressionMode::parse(&raw)
+            .map_err(|err| format!("{DEFAULT_COMPRESSION_SETTING}: {err}").into()),
+    }
+}
+
+/// The session's `duckdb_chess_max_open_files`, which must be a positive integer.
+fn resolve_max_open_files(bind: &BindInfo) -> Result<usize, Box<dyn std::error::Error>> {
+    let setting = bind_info_ffi::get_setting_varchar(bind, MAX_OPEN_FILES_SETTING)?;
+    parse_max_open_files(setting)
+}
 
-    match compression {
-        CompressionMode::Plain => Ok(Box::new(file)),
-        CompressionMode::Zstd => ZstdDecoder::new(file)
-            .map(|decoder| Box::new(decoder) as PgnInput)
-            .map_err(|e| {
-                format!(
-                    "Failed to initialize zstd decoder for '{}': {}",
-                    path.display(),
-                    e
-                )
-            }),
+fn parse_max_open_files(setting: Option<String>) -> Result<usize, Box<dyn std::error::Error>> {
+    let Some(raw) = setting else {
+        return Ok(DEFAULT_MAX_OPEN_FILES);
+    };
+    match raw.trim().parse::<usize>() {
+        Ok(limit) if limit > 0 => Ok(limit),
+        _ => Err(
+            format!("{MAX_OPEN_FILES_SETTING}: expected a positive integer, got '{raw}'").into(),
+        ),
+    }
+}
+
+/// Apply the session's `duckdb_chess_log_level` to the process-wide logger.
+pub(crate) fn apply_log_level_setting(bind: &BindInfo) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(level) = bind_info_ffi::get_setting_varchar(bind, LOG_LEVEL_SETTING)? {
+        log::set_level(&level).map_err(|err| format!("{LOG_LEVEL_SETTING}: {err}"))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn resolve_schema_version(bind: &BindInfo) -> Result<u32, Box<dyn std::error::Error>> {
+    let value = bind_info_ffi::get_named_parameter_i64(bind, "schema_version")?;
+    parse_schema_version(value)
+}
+
+fn parse_schema_version(value: Option<i64>) -> Result<u32, Box<dyn std::error::Error>> {
+    let Some(value) = value else {
+        return Ok(READ_PGN_DEFAULT_SCHEMA_VERSION);
+    };
+
+    match u32::try_from(value) {
+        Ok(version) if (1..=READ_PGN_SCHEMA_VERSION).contains(&version) => Ok(version),
+        _ => Err(format!(
+            "Invalid schema_version {}. Supported values: 1 to {} or NULL/omitted.",
+            value, READ_PGN_SCHEMA_VERSION
+        )
+        .into()),
+    }
+}
+
+fn resolve_movetext_style(bind: &BindInfo) -> Result<MovetextStyle, Box<dyn std::error::Error>> {
+    let style = bind_info_ffi::get_named_parameter_varchar(bind, "movetext_style")?;
+    parse_movetext_style(style)
+}
+
+fn parse_movetext_style(
+    style: NamedParameterVarchar,
+) -> Result<MovetextStyle, Box<dyn std::error::Error>> {
+    match style {
+        NamedParameterVarchar::Missing | NamedParameterVarchar::Null => {
+            Ok(MovetextStyle::default())
+        }
+        NamedParameterVarchar::Value(raw) => Ok(MovetextStyle::parse(&raw)?),
+    }
+}
+
+fn resolve_moves_as(
+    bind: &BindInfo,
+    movetext_style: MovetextStyle,
+) -> Result<MovesAs, Box<dyn std::error::Error>> {
+    let moves_as = bind_info_ffi::get_named_parameter_varchar(bind, "moves_as")?;
+    parse_moves_as(moves_as, movetext_style)
+}
+
+fn parse_moves_as(
+    moves_as: NamedParameterVarchar,
+    movetext_style: MovetextStyle,
+) -> Result<MovesAs, Box<dyn std::error::Error>> {
+    let moves_as = match moves_as {
+        NamedParameterVarchar::Missing | NamedParameterVarchar::Null => MovesAs::default(),
+        NamedParameterVarchar::Value(raw) => MovesAs::parse(&raw)?,
+    };
+    if moves_as != MovesAs::Text && movetext_style == MovetextStyle::Uci {
+        return Err(
+            "moves_as := 'list' or 'struct' holds SAN moves and cannot be combined \
+                    with movetext_style := 'uci'."
+                .into(),
+        );
+    }
+    Ok(moves_as)
+}
+
+/// Type of the bound `movetext` column.
+fn movetext_type_handle(moves_as: MovesAs) -> LogicalTypeHandle {
+    let varchar = || LogicalTypeHandle::from(LogicalTypeId::Varchar);
+    match moves_as {
+        MovesAs::Text => varchar(),
+        MovesAs::List => LogicalTypeHandle::list(&varchar()),
+        MovesAs::Struct => LogicalTypeHandle::list(&LogicalTypeHandle::struct_type(&[
+            ("san", varchar()),
+            ("comment", varchar()),
+            ("nag", LogicalTypeHandle::from(LogicalTypeId::UTinyint)),
+        ])),
+    }
+}
+
+fn resolve_start_offset(
+    bind: &BindInfo,
+    path_count: usize,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let value = bind_info_ffi::get_named_parameter_i64(bind, "start_offset")?;
+    parse_start_offset(value, path_count)
+}
+
+fn parse_start_offset(
+    value: Option<i64>,
+    path_count: usize,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let Some(value) = value else {
+        return Ok(0);
+    };
+
+    let Ok(offset) = u64::try_from(value) else {
+        return Err(format!(
+            "Invalid start_offset {}. Expected a non-negative byte offset.",
+            value
+        )
+        .into());
+    };
+    if offset > 0 && path_count > 1 {
+        return Err(format!(
+            "start_offset requires a single input file, but the pattern matched {} files.",
+            path_count
+        )
+        .into());
+    }
+
+    Ok(offset)
+}
+
+fn resolve_optional_columns(
+    bind: &BindInfo,
+) -> Result<OptionalColumns, Box<dyn std::error::Error>> {
+    let flag = |name: &str| -> Result<bool, Box<dyn std::error::Error>> {
+        Ok(bind_info_ffi::get_named_parameter_bool(bind, name)?.unwrap_or(false))
+    };
+    Ok(OptionalColumns {
+        elo_raw: flag("elo_raw_columns")?,
+        game_id: flag("game_id")?,
+        line_number: flag("line_number")?,
+        encoding_issues: flag("encoding_issues")?,
+        eco_backfill: flag("eco_backfill")?,
+        normalize: flag("normalize")?,
+        warnings: flag("warnings")?,
+    })
+}
+
+fn resolve_extra_columns(
+    bind: &BindInfo,
+    schema_version: u32,
+    optional_columns: OptionalColumns,
+) -> Result<Vec<ExtraTagColumn>, Box<dyn std::error::Error>> {
+    let pairs = bind_info_ffi::get_named_parameter_string_pairs(bind, "columns")?;
+    build_extra_columns(pairs.unwrap_or_default(), schema_version, optional_columns)
+}
+
+fn build_extra_columns(
+    pairs: Vec<(String, String)>,
+    schema_version: u32,
+    optional_columns: OptionalColumns,
+) -> Result<Vec<ExtraTagColumn>, Box<dyn std::error::Error>> {
+    let mut columns: Vec<ExtraTagColumn> = Vec::with_capacity(pairs.len());
+    for (tag, type_name) in pairs {
+        if tag.trim().is_empty() {
+            return Err("Invalid columns entry: tag name must not be empty.".into());
+        }
+
+        let Some(tag_type) = ExtraTagType::parse(&type_name) else {
+            return Err(format!(
+                "Invalid columns type '{}' for tag '{}'. Supported types: {}.",
+                type_name, tag, SUPPORTED_EXTRA_TAG_TYPES
+            )
+            .into());
+        };
+
+        // DuckDB column names are case-insensitive.
+        let clashes_builtin = read_pgn_columns(schema_version)
+            .map(|column| column.name)
+            .chain(optional_columns.names())
+            .any(|name| name.eq_ignore_ascii_case(&tag));
+        let duplicate = columns
+            .iter()
+            .any(|column| column.tag.eq_ignore_ascii_case(&tag));
+        if clashes_builtin || duplicate {
+            return Err(format!("Invalid columns entry: duplicate column name '{}'.", tag).into());
+        }
+
+        columns.push(ExtraTagColumn { tag, tag_type });
+    }
+
+    Ok(columns)
+}
+
+fn extra_tag_type_handle(tag_type: ExtraTagType) -> LogicalTypeHandle {
+    LogicalTypeHandle::from(match tag_type {
+        ExtraTagType::Varchar => LogicalTypeId::Varchar,
+        ExtraTagType::Boolean => LogicalTypeId::Boolean,
+        ExtraTagType::Integer => LogicalTypeId::Integer,
+        ExtraTagType::BigInt => LogicalTypeId::Bigint,
+        ExtraTagType::UInteger => LogicalTypeId::UInteger,
+        ExtraTagType::UBigInt => LogicalTypeId::UBigint,
+        ExtraTagType::Double => LogicalTypeId::Double,
+        ExtraTagType::Date => LogicalTypeId::Date,
+    })
+}
+
+fn resolve_game_filter(bind: &BindInfo) -> Result<GameFilter, Box<dyn std::error::Error>> {
+    let min_elo = bind_info_ffi::get_named_parameter_i64(bind, "min_elo")?;
+    let max_elo = bind_info_ffi::get_named_parameter_i64(bind, "max_elo")?;
+    let variant = bind_info_ffi::get_named_parameter_varchar(bind, "variant")?;
+    Ok(build_game_filter(min_elo, max_elo)?.with_variant(parse_variant_filter(variant)?))
+}
+
+/// `variant := ...`, which keeps only standard chess unless set to `'all'`.
+fn parse_variant_filter(
+    variant: NamedParameterVarchar,
+) -> Result<VariantFilter, Box<dyn std::error::Error>> {
+    match variant {
+        NamedParameterVarchar::Missing | NamedParameterVarchar::Null => Ok(VariantFilter::Standard),
+        NamedParameterVarchar::Value(raw) => Ok(VariantFilter::parse(&raw)?),
+    }
+}
+
+fn build_game_filter(
+    min_elo: Option<i64>,
+    max_elo: Option<i64>,
+) -> Result<GameFilter, Box<dyn std::error::Error>> {
+    Ok(GameFilter::new(min_elo, max_elo)?)
+}
+
+/// A file read by `read_pgn`: local, or opened through DuckDB's file system.
+enum RawInput {
+    Local(File),
+    Remote(DuckDbFile),
+}
+
+impl RawInput {
+    fn open(path: &Path, file_system: Option<&DuckDbFileSystem>) -> Result<Self, String> {
+        match file_system {
+            Some(file_system) => file_system.open(&path.to_string_lossy()).map(Self::Remote),
+            None => File::open(path).map(Self::Local).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| format!("Failed to open file '{}': {}", path.display(), e))
+    }
+
+    fn len(&self) -> io::Result<u64> {
+        match self {
+            Self::Local(file) => file.metadata().map(|metadata| metadata.len()),
+            Self::Remote(file) => file.len(),
+        }
+    }
+}
+
+impl Read for RawInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Local(file) => file.read(buf),
+            Self::Remote(file) => file.read(buf),
+        }
+    }
+}
+
+impl Seek for RawInput {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Self::Local(file) => file.seek(pos),
+            Self::Remote(file) => file.seek(pos),
+        }
+    }
+}
+
+/// Open `path` positioned at `start_offset` bytes into its decompressed content. With
+/// `background_decompression`, compressed input is decoded ahead on a worker thread. With a
+/// `file_system`, `path` is opened through DuckDB (e.g. an `s3://` URL).
+pub(crate) fn open_input_stream(
+    path: &Path,
+    compression: CompressionMode,
+    start_offset: u64,
+    background_decompression: bool,
+    file_system: Option<&DuckDbFileSystem>,
+) -> Result<PgnInput, String> {
+    open_input_stream_with_mode(
+        path,
+        compression,
+        start_offset,
+        background_decompression,
+        file_system,
+    )
+    .map(|(input, _)| input)
+}
+
+/// [`open_input_stream`], also returning the compression the input was decoded with.
+fn open_input_stream_with_mode(
+    path: &Path,
+    compression: CompressionMode,
+    start_offset: u64,
+    background_decompression: bool,
+    file_system: Option<&DuckDbFileSystem>,
+) -> Result<(PgnInput, CompressionMode), String> {
+    let mut file = RawInput::open(path, file_system)?;
+
+    // Sniffed bytes are chained back in front of the file so pipes and FIFOs
+    // (e.g. `/dev/stdin`) work without seeking.
+    let (source, compression): (PgnInput, CompressionMode) = match compression {
+        CompressionMode::Auto => {
+            let magic = read_magic_bytes(&mut file)
+                .map_err(|e| format!("Failed to read file '{}': {}", path.display(), e))?;
+            let detected = CompressionMode::detect(&magic);
+            if detected == CompressionMode::Plain && start_offset > 0 {
+                // Uncompressed input: jump straight to the resume point.
+                seek_to_offset(&mut file, path, start_offset)?;
+                return Ok((Box::new(file), detected));
+            }
+            (Box::new(io::Cursor::new(magic).chain(file)), detected)
+        }
+        explicit => (Box::new(file), explicit),
+    };
+
+    let mut decoded = open_decoder(source, compression, path)?;
+    if background_decompression && compression != CompressionMode::Plain {
+        decoded = Box::new(PrefetchReader::spawn(decoded).map_err(|e| {
+            format!(
+                "Failed to start decompression thread for '{}': {}",
+                path.display(),
+                e
+            )
+        })?);
+    }
+    Ok((
+        skip_decoded_bytes(decoded, path, start_offset)?,
+        compression,
+    ))
+}
+
+fn seek_to_offset(file: &mut RawInput, path: &Path, start_offset: u64) -> Result<(), String> {
+    let len = file
+        .len()
+        .map_err(|e| format!("Failed to read file '{}': {}", path.display(), e))?;
+    if start_offset > len {
+        return Err(start_offset_past_end(path, start_offset, len));
+    }
+
+    file.seek(SeekFrom::Start(start_offset))
+        .map(|_| ())
+        .map_err(|e| format!("Failed to seek file '{}': {}", path.display(), e))
+}
+
+/// Compressed streams cannot seek, so the first `start_offset` decoded bytes are discarded.
+fn skip_decoded_bytes(
+    mut input: PgnInput,
+    path: &Path,
+    start_offset: u64,
+) -> Result<PgnInput, String> {
+    if start_offset == 0 {
+        return Ok(input);
+    }
+
+    let skipped = io::copy(&mut input.by_ref().take(start_offset), &mut io::sink())
+        .map_err(|e| format!("Failed to read file '{}': {}", path.display(), e))?;
+    if skipped < start_offset {
+        return Err(start_offset_past_end(path, start_offset, skipped));
+    }
+
+    Ok(input)
+}
+
+/// Game indexes and line numbers count from where reading starts, so they would shift with
+/// `start_offset`.
+fn check_start_offset_columns(
+    optional_columns: OptionalColumns,
+    start_offset: u64,
+) -> Result<(), String> {
+    if start_offset == 0 {
+        return Ok(());
+    }
+    if optional_columns.game_id {
+        return Err(
+            "game_id cannot be combined with start_offset: ids are derived from each game's \
+             index in the whole file."
+                .to_string(),
+        );
+    }
+    if optional_columns.line_number {
+        return Err(
+            "line_number cannot be combined with start_offset: lines before the offset are \
+             not read."
+                .to_string(),
+        );
     }
+    Ok(())
+}
+
+fn start_offset_past_end(path: &Path, start_offset: u64, len: u64) -> String {
+    format!(
+        "start_offset {} is past the end of '{}' ({} bytes).",
+        start_offset,
+        path.display(),
+        len
+    )
+}
+
+/// Decoded bytes of the first input sampled to estimate `read_pgn`'s row count.
+const CARDINALITY_SAMPLE_BYTES: u64 = 4 << 20;
+
+/// Counts the raw bytes a decoder pulls from the file, to relate decoded sample size to disk size.
+struct CountingReader<R> {
+    inner: R,
+    consumed: Arc<AtomicU64>,
 }
 
-fn collect_glob_paths<I, E, F>(pattern: &str, entries: I, mut warn: F) -> Vec<PathBuf>
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.consumed.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+/// Estimated number of games across `paths`: the games per on-disk byte of a decoded sample
+/// from the first readable file, scaled to the total size of all files. Pipes and other
+/// non-regular files are never sampled, as that would consume their input.
+fn estimate_game_count(paths: &[PathBuf], compression: CompressionMode) -> Option<u64> {
+    let regular_sizes: Vec<(&PathBuf, u64)> = paths
+        .iter()
+        .filter_map(|path| {
+            let metadata = fs::metadata(path).ok()?;
+            metadata.is_file().then_some((path, metadata.len()))
+        })
+        .collect();
+    let total_bytes: u64 = regular_sizes.iter().map(|(_, len)| len).sum();
+
+    let (games, raw_bytes) = regular_sizes
+        .iter()
+        .find_map(|(path, _)| sample_game_density(path, compression).ok())?;
+    if games == 0 || raw_bytes == 0 {
+        return None;
+    }
+    Some((games as f64 * total_bytes as f64 / raw_bytes as f64).round() as u64)
+}
+
+/// Games started in the first `CARDINALITY_SAMPLE_BYTES` decoded bytes of `path`, and the raw
+/// bytes read from disk to decode them.
+fn sample_game_density(path: &Path, compression: CompressionMode) -> io::Result<(u64, u64)> {
+    let consumed = Arc::new(AtomicU64::new(0));
+    let source: PgnInput = Box::new(CountingReader {
+        inner: File::open(path)?,
+        consumed: Arc::clone(&consumed),
+    });
+    let (source, compression) = sniff_compression(source, compression)?;
+
+    let decoded = open_decoder(source, compression, path).map_err(io::Error::other)?;
+    let mut sample = Vec::new();
+    decoded
+        .take(CARDINALITY_SAMPLE_BYTES)
+        .read_to_end(&mut sample)?;
+    Ok((
+        count_tag_sections(&sample),
+        consumed.load(Ordering::Relaxed),
+    ))
+}
+
+/// Fail the bind when the start of an input does not look like PGN, e.g. an HTML error page
+/// saved as `.pgn`, quoting the offending bytes. Remote files are read through DuckDB's file
+/// system; local pipes and other non-regular files are not checked, as that would consume
+/// their input.
+/// Spec: pgn-parsing - Input Validation
+fn validate_inputs(
+    paths: &[PathBuf],
+    compression: CompressionMode,
+    start_offset: u64,
+    file_system: Option<&DuckDbFileSystem>,
+) -> Result<(), String> {
+    for path in paths {
+        let remote = file_system.is_some() && is_remote_path(&path.to_string_lossy());
+        if !remote && !fs::metadata(path).is_ok_and(|metadata| metadata.is_file()) {
+            continue;
+        }
+        let mut sample = Vec::new();
+        open_input_stream(path, compression, start_offset, false, file_system)?
+            .take(VALIDATE_SAMPLE_BYTES)
+            .read_to_end(&mut sample)
+            .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+        if let Err(problem) = validate_pgn_sample(&sample, VALIDATE_SAMPLE_GAMES) {
+            return Err(format!(
+                "'{}' does not look like PGN: {} at byte {}: \"{}\". Use validate := false to \
+                 read it anyway.",
+                path.display(),
+                problem.reason,
+                start_offset + problem.offset as u64,
+                problem.bytes
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Tag sections (one per game) starting in `sample`; a truncated last game still counts.
+fn count_tag_sections(sample: &[u8]) -> u64 {
+    let mut in_tags = false;
+    let mut count = 0;
+    for line in sample.split(|&byte| byte == b'\n') {
+        // `%` escape lines are ignored wherever they appear, even inside a tag section.
+        if line.starts_with(b"%") {
+            continue;
+        }
+        let line = line.trim_ascii();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with(b"[") {
+            count += u64::from(!in_tags);
+            in_tags = true;
+        } else {
+            in_tags = false;
+        }
+    }
+    count
+}
+
+/// How `read_pgn` turns glob matches into input files.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct FileDiscoveryOptions {
+    /// Read files reached through a symlink; when `false` such entries are skipped.
+    follow_symlinks: bool,
+    /// Stop wildcards from matching names that start with `.`.
+    skip_hidden: bool,
+}
+
+impl Default for FileDiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            follow_symlinks: true,
+            skip_hidden: false,
+        }
+    }
+}
+
+pub(crate) fn resolve_file_discovery_options(
+    bind: &BindInfo,
+) -> Result<FileDiscoveryOptions, Box<dyn std::error::Error>> {
+    let defaults = FileDiscoveryOptions::default();
+    Ok(FileDiscoveryOptions {
+        follow_symlinks: bind_info_ffi::get_named_parameter_bool(bind, "follow_symlinks")?
+            .unwrap_or(defaults.follow_symlinks),
+        skip_hidden: bind_info_ffi::get_named_parameter_bool(bind, "skip_hidden")?
+            .unwrap_or(defaults.skip_hidden),
+    })
+}
+
+fn glob_match_options(options: FileDiscoveryOptions) -> glob::MatchOptions {
+    glob::MatchOptions {
+        require_literal_leading_dot: options.skip_hidden,
+        ..glob::MatchOptions::new()
+    }
+}
+
+/// Why a glob match cannot be read as a PGN file, or `None` for a readable regular file.
+fn glob_entry_skip_reason(path: &Path, options: FileDiscoveryOptions) -> Option<String> {
+    let is_symlink = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata.file_type().is_symlink(),
+        Err(error) => return Some(format!("cannot stat: {}", error)),
+    };
+    if is_symlink && !options.follow_symlinks {
+        return Some("symlink (follow_symlinks := false)".to_string());
+    }
+
+    let file_type = match fs::metadata(path) {
+        Ok(metadata) => metadata.file_type(),
+        Err(error) if is_symlink => return Some(format!("broken symlink: {}", error)),
+        Err(error) => return Some(format!("cannot stat: {}", error)),
+    };
+    if file_type.is_file() {
+        None
+    } else if file_type.is_dir() {
+        Some("is a directory".to_string())
+    } else {
+        Some(format!(
+            "not a regular file ({})",
+            special_file_kind(file_type)
+        ))
+    }
+}
+
+#[cfg(unix)]
+fn special_file_kind(file_type: fs::FileType) -> &'static str {
+    use std::os::unix::fs::FileTypeExt;
+
+    if file_type.is_fifo() {
+        "FIFO"
+    } else if file_type.is_socket() {
+        "socket"
+    } else if file_type.is_block_device() {
+        "block device"
+    } else if file_type.is_char_device() {
+        "character device"
+    } else {
+        "unknown file type"
+    }
+}
+
+#[cfg(not(unix))]
+fn special_file_kind(_file_type: fs::FileType) -> &'static str {
+    "special file"
+}
+
+/// Reject an explicitly named path that the discovery options rule out.
+///
+/// Missing or unreadable files are left to `open_input_stream`, and pipes such as `/dev/stdin`
+/// stay readable, so only symlinks (with `follow_symlinks := false`) and directories fail here.
+fn check_explicit_path(path: &Path, options: FileDiscoveryOptions) -> Result<(), String> {
+    if !options.follow_symlinks
+        && fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+    {
+        return Err(format!(
+            "Failed to open file '{}': is a symlink and follow_symlinks is false",
+            path.display()
+        ));
+    }
+    if fs::metadata(path).is_ok_and(|metadata| metadata.is_dir()) {
+        return Err(format!(
+            "Failed to open file '{}': is a directory",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+/// The single input of a URL `pattern`: DuckDB's file system API cannot list files, so globs
+/// are rejected. `?` is not a wildcard here, as it starts the query string of HTTP URLs.
+fn remote_path(pattern: &str) -> Result<PathBuf, String> {
+    if pattern.contains('*') {
+        return Err(format!(
+            "Glob patterns are not supported for remote path '{}'. Name a single file.",
+            pattern
+        ));
+    }
+    Ok(PathBuf::from(pattern))
+}
+
+/// Input files of `pattern`, with DuckDB's file system when it is a URL such as `s3://`, so
+/// httpfs and its secrets apply.
+pub(crate) fn resolve_input_paths(
+    bind: &BindInfo,
+    pattern: &str,
+    discovery: FileDiscoveryOptions,
+) -> Result<(Vec<PathBuf>, Option<DuckDbFileSystem>), Box<dyn std::error::Error>> {
+    if is_remote_path(pattern) {
+        Ok((
+            vec![remote_path(pattern)?],
+            Some(DuckDbFileSystem::from_bind(bind)?),
+        ))
+    } else {
+        Ok((expand_path_pattern(pattern, discovery)?, None))
+    }
+}
+
+/// Expand `pattern` into input files: a glob when it contains `*` or `?`, otherwise a single
+/// explicit path.
+pub(crate) fn expand_path_pattern(
+    pattern: &str,
+    discovery: FileDiscoveryOptions,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    if pattern.contains('*') || pattern.contains('?') {
+        let entries = glob::glob_with(pattern, glob_match_options(discovery))?;
+        Ok(collect_glob_paths(pattern, entries, discovery, log::warn))
+    } else {
+        let path = PathBuf::from(pattern);
+        check_explicit_path(&path, discovery)?;
+        Ok(vec![path])
+    }
+}
+
+fn collect_glob_paths<I, E, F>(
+    pattern: &str,
+    entries: I,
+    options: FileDiscoveryOptions,
+    mut warn: F,
+) -> Vec<PathBuf>
 where
     I: IntoIterator<Item = Result<PathBuf, E>>,
     E: std::fmt::Display,
@@ -242,7 +1086,15 @@ where
     let mut paths = Vec::new();
     for entry in entries {
         match entry {
-            Ok(path) => paths.push(path),
+            Ok(path) => match glob_entry_skip_reason(&path, options) {
+                None => paths.push(path),
+                Some(reason) => warn(format!(
+                    "Skipping '{}' matched by pattern '{}': {}",
+                    path.display(),
+                    pattern,
+                    reason
+                )),
+            },
             Err(error) => warn(format!(
                 "Skipping glob entry for pattern '{}': {}",
                 pattern, error
@@ -276,46 +1128,237 @@ fn sanitize_interior_nul<'a>(
 ) -> Cow<'a, str> {
     if value.contains('\0') {
         parse_error.push(&format!("Sanitized interior NUL in {}", field_name));
-        Cow::Owned(value.replace('\0', " "))
-    } else {
-        Cow::Borrowed(value)
     }
+    string::sanitize_interior_nul(value)
 }
 
-fn sanitize_interior_nul_silent(value: &str) -> Cow<'_, str> {
-    if value.contains('\0') {
-        Cow::Owned(value.replace('\0', " "))
-    } else {
-        Cow::Borrowed(value)
+pub(crate) enum ReadNextGameOutcome {
+    GameReady,
+    GameSkipped,
+    ReaderFinished,
+}
+
+/// Opt-in columns bound after the schema columns and before any `columns := {...}` entries,
+/// in field order.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct OptionalColumns {
+    /// `elo_raw_columns := true`: `ELO_RAW_COLUMNS`.
+    pub(crate) elo_raw: bool,
+    /// `game_id := true`: a deterministic UUID per game.
+    pub(crate) game_id: bool,
+    /// `line_number := true`: the source line of each game's first tag.
+    pub(crate) line_number: bool,
+    /// `encoding_issues := true`: invalid UTF-8 sequences replaced in each game.
+    pub(crate) encoding_issues: bool,
+    /// `eco_backfill := true`: whether `ECO` or `Opening` was classified from the moves.
+    pub(crate) eco_backfill: bool,
+    /// `normalize := true`: the movetext as `chess_moves_normalize` would return it.
+    pub(crate) normalize: bool,
+    /// `warnings := true`: non-fatal tag adjustments such as clamped dates.
+    pub(crate) warnings: bool,
+}
+
+impl OptionalColumns {
+    fn names(self) -> impl Iterator<Item = &'static str> {
+        ELO_RAW_COLUMNS
+            .into_iter()
+            .filter(move |_| self.elo_raw)
+            .chain([GAME_ID_COLUMN].into_iter().filter(move |_| self.game_id))
+            .chain(
+                [LINE_NUMBER_COLUMN]
+                    .into_iter()
+                    .filter(move |_| self.line_number),
+            )
+            .chain(
+                [ENCODING_ISSUES_COLUMN]
+                    .into_iter()
+                    .filter(move |_| self.encoding_issues),
+            )
+            .chain(
+                [ECO_INFERRED_COLUMN]
+                    .into_iter()
+                    .filter(move |_| self.eco_backfill),
+            )
+            .chain(
+                [NORMALIZED_MOVETEXT_COLUMN]
+                    .into_iter()
+                    .filter(move |_| self.normalize),
+            )
+            .chain([WARNINGS_COLUMN].into_iter().filter(move |_| self.warnings))
+    }
+
+    fn count(self) -> usize {
+        self.names().count()
+    }
+
+    /// Position of `game_id` among the optional columns.
+    fn game_id_offset(self) -> usize {
+        if self.elo_raw {
+            ELO_RAW_COLUMNS.len()
+        } else {
+            0
+        }
+    }
+
+    /// Position of `line_number` among the optional columns.
+    fn line_number_offset(self) -> usize {
+        self.game_id_offset() + usize::from(self.game_id)
+    }
+
+    /// Position of `encoding_issues` among the optional columns.
+    fn encoding_issues_offset(self) -> usize {
+        self.line_number_offset() + usize::from(self.line_number)
+    }
+
+    /// Position of `eco_inferred` among the optional columns.
+    fn eco_inferred_offset(self) -> usize {
+        self.encoding_issues_offset() + usize::from(self.encoding_issues)
+    }
+
+    /// Position of `normalized_movetext` among the optional columns.
+    fn normalized_movetext_offset(self) -> usize {
+        self.eco_inferred_offset() + usize::from(self.eco_backfill)
+    }
+
+    /// Position of `warnings` among the optional columns.
+    fn warnings_offset(self) -> usize {
+        self.normalized_movetext_offset() + usize::from(self.normalize)
     }
 }
 
-enum ReadNextGameOutcome {
-    GameReady,
-    ReaderFinished,
+/// Deterministic `game_id`: a UUID v5 over the file path, the game's 1-based index in the
+/// file and its movetext, so re-reading the same file yields the same ids.
+fn game_uuid(path: &Path, game_index: usize, movetext: &str) -> u128 {
+    let path = path.as_os_str().as_encoded_bytes();
+    let index = game_index.to_string();
+    let mut name = Vec::with_capacity(path.len() + index.len() + movetext.len() + 2);
+    for part in [path, index.as_bytes(), movetext.as_bytes()] {
+        if !name.is_empty() {
+            name.push(0);
+        }
+        name.extend_from_slice(part);
+    }
+    Uuid::new_v5(&GAME_ID_NAMESPACE, &name).as_u128()
+}
+
+/// DuckDB stores a UUID as a HUGEINT with the top bit flipped, so that signed comparison
+/// sorts UUIDs in their textual order.
+fn uuid_to_hugeint(uuid: u128) -> i128 {
+    (uuid ^ (1 << 127)) as i128
+}
+
+/// Where a [`ChunkWriter`] puts its rows.
+enum ChunkTarget<'a> {
+    /// The columns of a table function's output chunk.
+    Chunk(&'a mut DataChunkHandle),
+    /// The fields of a `LIST(STRUCT(...))` child vector, as written by `parse_pgn`.
+    Struct {
+        vector: StructVector,
+        capacity: usize,
+    },
 }
 
-struct ChunkWriter<'a> {
-    output: &'a mut DataChunkHandle,
+pub(crate) struct ChunkWriter<'a> {
+    output: ChunkTarget<'a>,
     row_count: usize,
     max_rows: usize,
+    /// Number of leading `READ_PGN_COLUMNS` bound for the requested schema version.
+    column_count: usize,
+    /// Opt-in columns bound right after the schema columns.
+    optional_columns: OptionalColumns,
+    /// Tag-backed columns bound after the schema and optional columns.
+    extra_columns: &'a [ExtraTagColumn],
+    /// Output vector of each bound column, `None` when projection pushdown dropped it.
+    output_columns: Vec<Option<usize>>,
+    /// Type of the `movetext` column.
+    moves_as: MovesAs,
 }
 
 impl<'a> ChunkWriter<'a> {
-    fn new(output: &'a mut DataChunkHandle) -> Self {
+    pub(crate) fn new(
+        output: &'a mut DataChunkHandle,
+        schema_version: u32,
+        optional_columns: OptionalColumns,
+        extra_columns: &'a [ExtraTagColumn],
+    ) -> Self {
         let max_rows = output.flat_vector(0).capacity();
+        let column_count = read_pgn_columns(schema_version).count();
+        let bound_columns = column_count + optional_columns.count() + extra_columns.len();
         Self {
-            output,
+            output: ChunkTarget::Chunk(output),
             row_count: 0,
             max_rows,
+            column_count,
+            optional_columns,
+            extra_columns,
+            output_columns: (0..bound_columns).map(Some).collect(),
+            moves_as: MovesAs::Text,
+        }
+    }
+
+    /// Write rows `first_row..capacity` of the struct child of a list vector, one struct
+    /// field per column of the default schema.
+    pub(crate) fn for_struct(vector: StructVector, first_row: usize, capacity: usize) -> Self {
+        let column_count = read_pgn_columns(READ_PGN_DEFAULT_SCHEMA_VERSION).count();
+        Self {
+            output: ChunkTarget::Struct { vector, capacity },
+            row_count: first_row,
+            max_rows: capacity,
+            column_count,
+            optional_columns: OptionalColumns::default(),
+            extra_columns: &[],
+            output_columns: (0..column_count).map(Some).collect(),
+            moves_as: MovesAs::Text,
         }
     }
 
-    fn is_full(&self) -> bool {
+    /// Number of rows written so far, including the `first_row` offset of `for_struct`.
+    pub(crate) fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    /// Only write the bound columns listed in `column_indices`, in that output order.
+    pub(crate) fn with_projection(mut self, column_indices: &[u64]) -> Self {
+        self.output_columns = projected_output_columns(self.output_columns.len(), column_indices);
+        self
+    }
+
+    /// Write the `movetext` column as `moves_as` lists of `GameRecord::moves`.
+    pub(crate) fn with_moves_as(mut self, moves_as: MovesAs) -> Self {
+        self.moves_as = moves_as;
+        self
+    }
+
+    fn list_vector(&self, column_idx: usize) -> Option<ListVector> {
+        let output_idx = self.output_columns.get(column_idx).copied().flatten()?;
+        Some(match &self.output {
+            ChunkTarget::Chunk(output) => output.list_vector(output_idx),
+            ChunkTarget::Struct { vector, .. } => vector.list_vector_child(output_idx),
+        })
+    }
+
+    fn vector(&self, column_idx: usize) -> Option<FlatVector> {
+        let output_idx = self.output_columns.get(column_idx).copied().flatten()?;
+        Some(match &self.output {
+            ChunkTarget::Chunk(output) => output.flat_vector(output_idx),
+            ChunkTarget::Struct { vector, capacity } => vector.child(output_idx, *capacity),
+        })
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
         self.row_count >= self.max_rows
     }
 
-    fn write_row(&mut self, game: &GameRecord) {
+    /// Whether rows need a `GameRecord::game_id`: the column is bound and projected.
+    fn writes_game_id(&self) -> bool {
+        self.optional_columns.game_id && self.vector(self.game_id_column()).is_some()
+    }
+
+    fn game_id_column(&self) -> usize {
+        self.column_count + self.optional_columns.game_id_offset()
+    }
+
+    pub(crate) fn write_row(&mut self, game: &GameRecord) {
         let row_idx = self.row_count;
         let mut row_parse_error = ErrorAccumulator::default();
         if let Some(parse_error) = game.parse_error.as_deref() {
@@ -393,13 +1436,16 @@ impl<'a> ChunkWriter<'a> {
             &mut row_parse_error,
         );
 
-        let movetext = sanitize_interior_nul(
-            game.movetext.as_str(),
-            ReadPgnColumn::Movetext.name(),
-            &mut row_parse_error,
-        );
-        let movetext_vec = self.output.flat_vector(ReadPgnColumn::Movetext.index());
-        movetext_vec.insert(row_idx, movetext.as_ref());
+        if self.moves_as != MovesAs::Text {
+            self.write_moves(row_idx, &game.moves, &mut row_parse_error);
+        } else if let Some(movetext_vec) = self.vector(ReadPgnColumn::Movetext.index()) {
+            let movetext = sanitize_interior_nul(
+                game.movetext.as_str(),
+                ReadPgnColumn::Movetext.name(),
+                &mut row_parse_error,
+            );
+            movetext_vec.insert(row_idx, movetext.as_ref());
+        }
 
         self.write_optional_varchar(
             ReadPgnColumn::Source,
@@ -408,20 +1454,81 @@ impl<'a> ChunkWriter<'a> {
             &mut row_parse_error,
         );
 
-        let mut parse_error_vec = self.output.flat_vector(ReadPgnColumn::ParseError.index());
-        if row_parse_error.is_empty() {
-            parse_error_vec.set_null(row_idx);
-        } else {
-            let parse_error = row_parse_error.take().unwrap_or_default();
-            let parse_error = sanitize_interior_nul_silent(parse_error.as_str());
-            parse_error_vec.insert(row_idx, parse_error.as_ref());
+        self.write_optional_ubigint(ReadPgnColumn::ByteOffset, row_idx, game.byte_offset);
+        self.write_optional_ubigint(ReadPgnColumn::MovetextBytes, row_idx, game.movetext_bytes);
+        self.write_bool(ReadPgnColumn::HasClk, row_idx, game.has_clk);
+        self.write_bool(ReadPgnColumn::HasEval, row_idx, game.has_eval);
+        self.write_bool(ReadPgnColumn::HasVariations, row_idx, game.has_variations);
+
+        if self.optional_columns.elo_raw {
+            for (offset, raw) in [&game.white_elo_raw, &game.black_elo_raw]
+                .into_iter()
+                .enumerate()
+            {
+                self.write_elo_raw(offset, row_idx, raw.as_deref(), &mut row_parse_error);
+            }
+        }
+
+        if self.optional_columns.game_id
+            && let Some(mut vector) = self.vector(self.game_id_column())
+        {
+            match game.game_id {
+                Some(id) => vector.as_mut_slice::<i128>()[row_idx] = uuid_to_hugeint(id),
+                None => vector.set_null(row_idx),
+            }
+        }
+        if self.optional_columns.line_number
+            && let Some(mut vector) =
+                self.vector(self.column_count + self.optional_columns.line_number_offset())
+        {
+            match game.line_number {
+                Some(line) => vector.as_mut_slice::<u64>()[row_idx] = line,
+                None => vector.set_null(row_idx),
+            }
+        }
+        if self.optional_columns.encoding_issues
+            && let Some(mut vector) =
+                self.vector(self.column_count + self.optional_columns.encoding_issues_offset())
+        {
+            vector.as_mut_slice::<u32>()[row_idx] = game.encoding_issues;
+        }
+        if self.optional_columns.eco_backfill
+            && let Some(mut vector) =
+                self.vector(self.column_count + self.optional_columns.eco_inferred_offset())
+        {
+            vector.as_mut_slice::<bool>()[row_idx] = game.eco_inferred;
+        }
+        if self.optional_columns.normalize
+            && let Some(vector) =
+                self.vector(self.column_count + self.optional_columns.normalized_movetext_offset())
+        {
+            string::insert_varchar(&vector, row_idx, &normalize_movetext(&game.movetext));
+        }
+        if self.optional_columns.warnings {
+            self.write_warnings(row_idx, &game.warnings, &mut row_parse_error);
+        }
+
+        for (offset, value) in game.extra_tags.iter().enumerate() {
+            self.write_extra_tag(offset, row_idx, value.as_ref(), &mut row_parse_error);
+        }
+
+        if let Some(mut parse_error_vec) = self.vector(ReadPgnColumn::ParseError.index()) {
+            if row_parse_error.is_empty() {
+                parse_error_vec.set_null(row_idx);
+            } else {
+                let parse_error = row_parse_error.take().unwrap_or_default();
+                let parse_error = string::sanitize_interior_nul(parse_error.as_str());
+                parse_error_vec.insert(row_idx, parse_error.as_ref());
+            }
         }
 
         self.row_count += 1;
     }
 
-    fn set_output_len(&mut self) {
-        self.output.set_len(self.row_count);
+    pub(crate) fn set_output_len(&mut self) {
+        if let ChunkTarget::Chunk(output) = &mut self.output {
+            output.set_len(self.row_count);
+        }
     }
 
     fn write_optional_varchar(
@@ -431,7 +1538,9 @@ impl<'a> ChunkWriter<'a> {
         value: Option<&str>,
         parse_error: &mut ErrorAccumulator,
     ) {
-        let mut vector = self.output.flat_vector(column.index());
+        let Some(mut vector) = self.vector(column.index()) else {
+            return;
+        };
         if let Some(value) = value {
             let sanitized = sanitize_interior_nul(value, column.name(), parse_error);
             vector.insert(row_idx, sanitized.as_ref());
@@ -440,13 +1549,141 @@ impl<'a> ChunkWriter<'a> {
         }
     }
 
+    /// Append `moves` to the `movetext` list vector, whose child grows across the chunk's rows.
+    fn write_moves(
+        &mut self,
+        row_idx: usize,
+        moves: &[MoveRecord],
+        parse_error: &mut ErrorAccumulator,
+    ) {
+        let Some(mut list_vec) = self.list_vector(ReadPgnColumn::Movetext.index()) else {
+            return;
+        };
+        let offset = list_vec.len();
+        let total = offset + moves.len();
+        match self.moves_as {
+            MovesAs::Text => return,
+            MovesAs::List => {
+                let san_vec = list_vec.child(total);
+                for (idx, m) in moves.iter().enumerate() {
+                    san_vec.insert(offset + idx, m.san.as_str());
+                }
+            }
+            MovesAs::Struct => {
+                let struct_vec = list_vec.struct_child(total);
+                let san_vec = struct_vec.child(0, total);
+                let mut comment_vec = struct_vec.child(1, total);
+                let mut nag_vec = struct_vec.child(2, total);
+                for (idx, m) in moves.iter().enumerate() {
+                    let element = offset + idx;
+                    san_vec.insert(element, m.san.as_str());
+                    match &m.comment {
+                        Some(comment) => {
+                            let comment = sanitize_interior_nul(
+                                comment,
+                                ReadPgnColumn::Movetext.name(),
+                                parse_error,
+                            );
+                            comment_vec.insert(element, comment.as_ref());
+                        }
+                        None => comment_vec.set_null(element),
+                    }
+                    match m.nag {
+                        Some(nag) => nag_vec.as_mut_slice_with_len::<u8>(total)[element] = nag,
+                        None => nag_vec.set_null(element),
+                    }
+                }
+            }
+        }
+        list_vec.set_entry(row_idx, offset, moves.len());
+        list_vec.set_len(total);
+    }
+
+    /// Append `warnings` to the `warnings` list vector, whose child grows across the chunk's
+    /// rows.
+    fn write_warnings(
+        &mut self,
+        row_idx: usize,
+        warnings: &[String],
+        parse_error: &mut ErrorAccumulator,
+    ) {
+        let Some(mut list_vec) =
+            self.list_vector(self.column_count + self.optional_columns.warnings_offset())
+        else {
+            return;
+        };
+        let offset = list_vec.len();
+        let total = offset + warnings.len();
+        let warning_vec = list_vec.child(total);
+        for (idx, warning) in warnings.iter().enumerate() {
+            let warning = sanitize_interior_nul(warning, WARNINGS_COLUMN, parse_error);
+            warning_vec.insert(offset + idx, warning.as_ref());
+        }
+        list_vec.set_entry(row_idx, offset, warnings.len());
+        list_vec.set_len(total);
+    }
+
+    fn write_elo_raw(
+        &mut self,
+        raw_idx: usize,
+        row_idx: usize,
+        value: Option<&str>,
+        parse_error: &mut ErrorAccumulator,
+    ) {
+        let Some(mut vector) = self.vector(self.column_count + raw_idx) else {
+            return;
+        };
+        if let Some(value) = value {
+            let sanitized = sanitize_interior_nul(value, ELO_RAW_COLUMNS[raw_idx], parse_error);
+            vector.insert(row_idx, sanitized.as_ref());
+        } else {
+            vector.set_null(row_idx);
+        }
+    }
+
+    fn write_extra_tag(
+        &mut self,
+        extra_idx: usize,
+        row_idx: usize,
+        value: Option<&ExtraTagValue>,
+        parse_error: &mut ErrorAccumulator,
+    ) {
+        let first_extra_column = self.column_count + self.optional_columns.count();
+        let Some(mut vector) = self.vector(first_extra_column + extra_idx) else {
+            return;
+        };
+        let Some(value) = value else {
+            vector.set_null(row_idx);
+            return;
+        };
+
+        match value {
+            ExtraTagValue::Varchar(v) => {
+                let sanitized =
+                    sanitize_interior_nul(v, &self.extra_columns[extra_idx].tag, parse_error);
+                vector.insert(row_idx, sanitized.as_ref());
+            }
+            ExtraTagValue::Boolean(v) => vector.as_mut_slice::<bool>()[row_idx] = *v,
+            ExtraTagValue::Integer(v) => vector.as_mut_slice::<i32>()[row_idx] = *v,
+            ExtraTagValue::BigInt(v) => vector.as_mut_slice::<i64>()[row_idx] = *v,
+            ExtraTagValue::UInteger(v) => vector.as_mut_slice::<u32>()[row_idx] = *v,
+            ExtraTagValue::UBigInt(v) => vector.as_mut_slice::<u64>()[row_idx] = *v,
+            ExtraTagValue::Double(v) => vector.as_mut_slice::<f64>()[row_idx] = *v,
+            ExtraTagValue::Date(v) => {
+                vector.as_mut_slice::<duckdb_date>()[row_idx] = duckdb_date { days: v.days }
+            }
+        }
+    }
+
     fn write_optional_uinteger(
         &mut self,
         column: ReadPgnColumn,
         row_idx: usize,
         value: Option<u32>,
     ) {
-        let mut vector = self.output.flat_vector(column.index());
+        let Some(mut vector) = self.vector(column.index()) else {
+            return;
+        };
         if let Some(value) = value {
             vector.as_mut_slice::<u32>()[row_idx] = value;
         } else {
@@ -454,15 +1691,40 @@ impl<'a> ChunkWriter<'a> {
         }
     }
 
+    fn write_optional_ubigint(
+        &mut self,
+        column: ReadPgnColumn,
+        row_idx: usize,
+        value: Option<u64>,
+    ) {
+        let Some(mut vector) = self.vector(column.index()) else {
+            return;
+        };
+        if let Some(value) = value {
+            vector.as_mut_slice::<u64>()[row_idx] = value;
+        } else {
+            vector.set_null(row_idx);
+        }
+    }
+
+    fn write_bool(&mut self, column: ReadPgnColumn, row_idx: usize, value: bool) {
+        let Some(mut vector) = self.vector(column.index()) else {
+            return;
+        };
+        vector.as_mut_slice::<bool>()[row_idx] = value;
+    }
+
     fn write_optional_date(
         &mut self,
         column: ReadPgnColumn,
         row_idx: usize,
-        value: Option<duckdb_date>,
+        value: Option<PgnDate>,
     ) {
-        let mut vector = self.output.flat_vector(column.index());
+        let Some(mut vector) = self.vector(column.index()) else {
+            return;
+        };
         if let Some(value) = value {
-            vector.as_mut_slice::<duckdb_date>()[row_idx] = value;
+            vector.as_mut_slice::<duckdb_date>()[row_idx] = duckdb_date { days: value.days };
         } else {
             vector.set_null(row_idx);
         }
@@ -472,11 +1734,13 @@ impl<'a> ChunkWriter<'a> {
         &mut self,
         column: ReadPgnColumn,
         row_idx: usize,
-        value: Option<duckdb_time_tz>,
+        value: Option<PgnTimeTz>,
     ) {
-        let mut vector = self.output.flat_vector(column.index());
+        let Some(mut vector) = self.vector(column.index()) else {
+            return;
+        };
         if let Some(value) = value {
-            vector.as_mut_slice::<duckdb_time_tz>()[row_idx] = value;
+            vector.as_mut_slice::<duckdb_time_tz>()[row_idx] = duckdb_time_tz { bits: value.bits };
         } else {
             vector.set_null(row_idx);
         }
@@ -488,26 +1752,52 @@ fn acquire_reader(
     bind_data: &ReadPgnBindData,
 ) -> Result<Option<PgnReaderState>, Box<dyn std::error::Error>> {
     loop {
-        let path_idx = {
+        let (path_idx, suspended) = {
             let mut state = lock_shared_state(&init_data.state, "acquiring reader");
 
             if let Some(reader) = state.available_readers.pop() {
                 return Ok(Some(reader));
             }
 
-            if state.next_path_idx < bind_data.paths.len() {
+            if let Some(suspended) = state.suspended_readers.pop() {
+                (suspended.path_idx, Some(suspended))
+            } else if state.next_path_idx < bind_data.paths.len() {
                 let path_idx = state.next_path_idx;
                 state.next_path_idx += 1;
-                path_idx
+                (path_idx, None)
             } else {
                 return Ok(None);
             }
         };
 
         let path = &bind_data.paths[path_idx];
-        match open_input_stream(path, bind_data.compression) {
-            Ok(input_stream) => {
-                return Ok(Some(PgnReaderState::new(input_stream, path_idx)));
+        let start_offset = suspended.map_or(bind_data.options.start_offset, |suspended| {
+            suspended.resume_point.byte_offset
+        });
+        match open_input_stream_with_mode(
+            path,
+            bind_data.compression,
+            start_offset,
+            bind_data.background_decompression,
+            bind_data.file_system.as_ref(),
+        ) {
+            Ok((input_stream, compression)) => {
+                let options = reader_scan_options(init_data, bind_data);
+                let Some(suspended) = suspended else {
+                    let mut reader = PgnReaderState::new(input_stream, path_idx, options);
+                    // Reopening compressed input mid-file decodes it again from the start.
+                    reader.seekable = compression == CompressionMode::Plain;
+                    return Ok(Some(reader));
+                };
+
+                let mut reader = PgnReaderState::resume(input_stream, options, suspended);
+                // The first game is the last one returned before the reader was evicted.
+                if !matches!(
+                    read_next_game(&mut reader, path),
+                    ReadNextGameOutcome::ReaderFinished
+                ) {
+                    return Ok(Some(reader));
+                }
             }
             Err(err_msg) => {
                 if bind_data.paths.len() == 1 {
@@ -520,17 +1810,114 @@ fn acquire_reader(
     }
 }
 
-fn read_next_game(reader: &mut PgnReaderState, source_path: &Path) -> ReadNextGameOutcome {
+fn reader_scan_options(init_data: &ReadPgnInitData, bind_data: &ReadPgnBindData) -> ScanOptions {
+    let options = projected_scan_options(
+        &bind_data.options,
+        &init_data.column_indices,
+        &bind_data.movetext_derived_columns(),
+    );
+    ScanOptions {
+        track_byte_offsets: options.track_byte_offsets || bind_data.evicts_readers(),
+        line_diagnostics: Arc::clone(&init_data.line_diagnostics),
+        ..options
+    }
+}
+
+/// Close the least recently used idle readers beyond `max_open_files`, remembering where each
+/// stopped. Readers that cannot be resumed exactly (their last game had no offset) or cheaply
+/// (their input is compressed) stay open.
+fn evict_idle_readers(state: &mut SharedState, max_open_files: usize) {
+    while state.available_readers.len() > max_open_files {
+        let Some((idx, suspended)) = state
+            .available_readers
+            .iter()
+            .enumerate()
+            .find_map(|(idx, reader)| Some((idx, reader.suspend()?)))
+        else {
+            return;
+        };
+        // Dropping the reader closes its file.
+        state.available_readers.remove(idx);
+        state.suspended_readers.push(suspended);
+    }
+}
+
+/// Narrow the scan to the projected columns: offsets and sizes are only tracked, and movetext
+/// only parsed or collected as moves, when a column the query reads needs them.
+/// `movetext_columns` are the bound optional columns computed from the movetext, such as
+/// `game_id`, which hashes it.
+fn projected_scan_options(
+    options: &ScanOptions,
+    column_indices: &[u64],
+    movetext_columns: &[usize],
+) -> ScanOptions {
+    let is_projected = |column: ReadPgnColumn| column_indices.contains(&(column.index() as u64));
+    let derived_projected = movetext_columns
+        .iter()
+        .any(|&column| column_indices.contains(&(column as u64)));
+    ScanOptions {
+        track_byte_offsets: options.track_byte_offsets && is_projected(ReadPgnColumn::ByteOffset),
+        track_movetext_bytes: options.track_movetext_bytes
+            && is_projected(ReadPgnColumn::MovetextBytes),
+        skip_movetext: !derived_projected
+            && !options.skip_empty_games
+            && !options.eco_backfill
+            && ![
+                ReadPgnColumn::Movetext,
+                ReadPgnColumn::HasClk,
+                ReadPgnColumn::HasEval,
+                ReadPgnColumn::HasVariations,
+            ]
+            .into_iter()
+            .any(is_projected),
+        moves_as: if is_projected(ReadPgnColumn::Movetext) {
+            options.moves_as
+        } else {
+            MovesAs::Text
+        },
+        ..options.clone()
+    }
+}
+
+/// Output vector of each of the `bound_columns` bind-time columns. DuckDB may also request
+/// virtual columns such as the row id, which map to no bound column.
+fn projected_output_columns(bound_columns: usize, column_indices: &[u64]) -> Vec<Option<usize>> {
+    let mut output_columns = vec![None; bound_columns];
+    for (output_idx, &column_idx) in column_indices.iter().enumerate() {
+        if let Some(slot) = usize::try_from(column_idx)
+            .ok()
+            .and_then(|idx| output_columns.get_mut(idx))
+        {
+            *slot = Some(output_idx);
+        }
+    }
+    output_columns
+}
+
+fn resume_point(game: &GameRecord, game_index: usize) -> Option<ResumePoint> {
+    Some(ResumePoint {
+        byte_offset: game.byte_offset?,
+        line_number: game.line_number,
+        game_index,
+    })
+}
+
+pub(crate) fn read_next_game(
+    reader: &mut PgnReaderState,
+    source_path: &Path,
+) -> ReadNextGameOutcome {
     let game_index = reader.next_game_index;
 
     match reader.pgn_reader.read_game(&mut reader.visitor) {
         Ok(Some(_)) => {
             reader.next_game_index += 1;
             if let Some(game) = reader.visitor.current_game.take() {
+                reader.resume_point = resume_point(&game, game_index);
                 reader.record_buffer = game;
                 ReadNextGameOutcome::GameReady
             } else {
-                ReadNextGameOutcome::ReaderFinished
+                // Rejected by the scan-time game filter.
+                ReadNextGameOutcome::GameSkipped
             }
         }
         Ok(None) => ReadNextGameOutcome::ReaderFinished,
@@ -546,6 +1933,7 @@ fn read_next_game(reader: &mut PgnReaderState, source_path: &Path) -> ReadNextGa
             reader.visitor.finalize_game_with_error(error_msg);
 
             if let Some(game) = reader.visitor.current_game.take() {
+                reader.resume_point = resume_point(&game, game_index);
                 reader.record_buffer = game;
                 ReadNextGameOutcome::GameReady
             } else {
@@ -561,12 +1949,14 @@ fn write_row(chunk_writer: &mut ChunkWriter<'_>, reader: &PgnReaderState) {
 
 fn finalize_chunk(
     init_data: &ReadPgnInitData,
+    max_open_files: usize,
     current_reader_state: Option<PgnReaderState>,
     chunk_writer: &mut ChunkWriter<'_>,
 ) {
     if let Some(reader) = current_reader_state {
         let mut state = lock_shared_state(&init_data.state, "finalizing chunk");
         state.available_readers.push(reader);
+        evict_idle_readers(&mut state, max_open_files);
     }
 
     chunk_writer.set_output_len();
@@ -578,32 +1968,146 @@ impl VTab for ReadPgnVTab {
 
     fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
         let pattern = bind.get_parameter(PATH_PATTERN_PARAM_INDEX).to_string();
+        apply_log_level_setting(bind)?;
         let compression = resolve_compression_mode(bind)?;
+        let filter = resolve_game_filter(bind)?;
+        let schema_version = resolve_schema_version(bind)?;
+        let elo_lenient =
+            bind_info_ffi::get_named_parameter_bool(bind, "elo_lenient")?.unwrap_or(false);
+        let skip_empty_games =
+            bind_info_ffi::get_named_parameter_bool(bind, "skip_empty_games")?.unwrap_or(false);
+        let optional_columns = resolve_optional_columns(bind)?;
+        let background_decompression =
+            bind_info_ffi::get_named_parameter_bool(bind, "background_decompression")?
+                .unwrap_or(false);
+        let extra_columns = resolve_extra_columns(bind, schema_version, optional_columns)?;
+        let discovery = resolve_file_discovery_options(bind)?;
+        let movetext_style = resolve_movetext_style(bind)?;
+        let moves_as = resolve_moves_as(bind, movetext_style)?;
+        if optional_columns.normalize && movetext_style == MovetextStyle::Uci {
+            return Err(
+                "normalize := true holds SAN moves and cannot be combined with \
+                 movetext_style := 'uci'."
+                    .into(),
+            );
+        }
+        let max_open_files = resolve_max_open_files(bind)?;
 
         // Spec: pgn-parsing - PGN File Reading
         // Expand glob pattern to get list of files (single file or glob pattern)
-        let paths: Vec<PathBuf> = if pattern.contains('*') || pattern.contains('?') {
-            // It's a glob pattern
-            let entries = glob::glob(&pattern)?;
-            collect_glob_paths(&pattern, entries, log::warn)
-        } else {
-            // It's a single file path
-            vec![PathBuf::from(pattern)]
-        };
+        let (paths, file_system) = resolve_input_paths(bind, &pattern, discovery)?;
+        let start_offset = resolve_start_offset(bind, paths.len())?;
+        check_start_offset_columns(optional_columns, start_offset)?;
+        if bind_info_ffi::get_named_parameter_bool(bind, "validate")?.unwrap_or(false) {
+            validate_inputs(&paths, compression, start_offset, file_system.as_ref())?;
+        }
+
+        for column in read_pgn_columns(schema_version) {
+            let logical_type = if column.name == ReadPgnColumn::Movetext.name() {
+                movetext_type_handle(moves_as)
+            } else {
+                column.logical_type_handle()
+            };
+            bind.add_result_column(column.name, logical_type);
+        }
+        if optional_columns.elo_raw {
+            for name in ELO_RAW_COLUMNS {
+                bind.add_result_column(name, LogicalTypeHandle::from(LogicalTypeId::Varchar));
+            }
+        }
+        if optional_columns.game_id {
+            bind.add_result_column(GAME_ID_COLUMN, LogicalTypeHandle::from(LogicalTypeId::Uuid));
+        }
+        if optional_columns.line_number {
+            bind.add_result_column(
+                LINE_NUMBER_COLUMN,
+                LogicalTypeHandle::from(LogicalTypeId::UBigint),
+            );
+        }
+        if optional_columns.encoding_issues {
+            bind.add_result_column(
+                ENCODING_ISSUES_COLUMN,
+                LogicalTypeHandle::from(LogicalTypeId::UInteger),
+            );
+        }
+        if optional_columns.eco_backfill {
+            bind.add_result_column(
+                ECO_INFERRED_COLUMN,
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            );
+        }
+        if optional_columns.normalize {
+            bind.add_result_column(
+                NORMALIZED_MOVETEXT_COLUMN,
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            );
+        }
+        if optional_columns.warnings {
+            bind.add_result_column(
+                WARNINGS_COLUMN,
+                LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            );
+        }
+        for column in extra_columns.iter() {
+            bind.add_result_column(&column.tag, extra_tag_type_handle(column.tag_type));
+        }
 
-        for column in READ_PGN_COLUMNS.iter() {
-            bind.add_result_column(column.name, column.logical_type.to_handle());
+        // Without an estimate DuckDB plans joins and sorts over the scan as if it returned
+        // a single row.
+        if let Some(games) = estimate_game_count(&paths, compression) {
+            bind.set_cardinality(games, false);
         }
 
-        Ok(ReadPgnBindData { paths, compression })
+        Ok(ReadPgnBindData {
+            path_pattern: pattern,
+            paths,
+            compression,
+            options: ScanOptions {
+                filter,
+                extra_columns: extra_columns.into(),
+                start_offset,
+                track_byte_offsets: ReadPgnColumn::ByteOffset.index()
+                    < read_pgn_columns(schema_version).count(),
+                track_movetext_bytes: ReadPgnColumn::MovetextBytes.index()
+                    < read_pgn_columns(schema_version).count(),
+                track_line_numbers: start_offset == 0,
+                // Narrowed per scan from the projected columns, see `projected_scan_options`.
+                skip_movetext: false,
+                movetext_style,
+                moves_as,
+                elo_lenient,
+                elo_raw_columns: optional_columns.elo_raw,
+                skip_empty_games,
+                eco_backfill: optional_columns.eco_backfill,
+                // Replaced per scan in `init`, so each execution counts from zero.
+                line_diagnostics: Arc::default(),
+            },
+            schema_version,
+            optional_columns,
+            background_decompression,
+            max_open_files,
+            file_system,
+        })
     }
 
-    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+    fn init(init: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        // Safety: both pointers are set by DuckDB for the duration of the query, and the extra
+        // info is the `ScanDiagnostics` the function was registered with.
+        let (bind_data, scans) = unsafe {
+            (
+                &*init.get_bind_data::<ReadPgnBindData>(),
+                &*init.get_extra_info::<ScanDiagnostics>(),
+            )
+        };
+        let line_diagnostics = scans.start_scan(&bind_data.path_pattern);
         Ok(ReadPgnInitData {
             state: Mutex::new(SharedState {
                 next_path_idx: 0,
                 available_readers: Vec::new(),
+                suspended_readers: Vec::new(),
             }),
+            column_indices: init.get_column_indices(),
+            line_diagnostics,
         })
     }
 
@@ -613,7 +2117,14 @@ impl VTab for ReadPgnVTab {
     ) -> Result<(), Box<dyn std::error::Error>> {
         let init_data = func.get_init_data();
         let bind_data = func.get_bind_data();
-        let mut chunk_writer = ChunkWriter::new(output);
+        let mut chunk_writer = ChunkWriter::new(
+            output,
+            bind_data.schema_version,
+            bind_data.optional_columns,
+            &bind_data.options.extra_columns,
+        )
+        .with_projection(&init_data.column_indices)
+        .with_moves_as(bind_data.options.moves_as);
         let mut current_reader_state: Option<PgnReaderState> = None;
 
         while !chunk_writer.is_full() {
@@ -631,11 +2142,19 @@ impl VTab for ReadPgnVTab {
                 // "Buffers the underlying reader with an appropriate strategy, so it's not
                 // recommended to add an additional layer of buffering like BufReader."
                 let source_path = &bind_data.paths[reader.path_idx];
+                let game_index = reader.next_game_index;
                 match read_next_game(&mut reader, source_path) {
                     ReadNextGameOutcome::GameReady => {
+                        if chunk_writer.writes_game_id() {
+                            let game = &mut reader.record_buffer;
+                            game.game_id = Some(game_uuid(source_path, game_index, &game.movetext));
+                        }
                         write_row(&mut chunk_writer, &reader);
                         current_reader_state = Some(reader);
                     }
+                    ReadNextGameOutcome::GameSkipped => {
+                        current_reader_state = Some(reader);
+                    }
                     ReadNextGameOutcome::ReaderFinished => {
                         // Reader finished (EOF or no recoverable record)
                         // It will be dropped here and loop will acquire new work.
@@ -644,10 +2163,19 @@ impl VTab for ReadPgnVTab {
             }
         }
 
-        finalize_chunk(init_data, current_reader_state, &mut chunk_writer);
+        finalize_chunk(
+            init_data,
+            bind_data.max_open_files,
+            current_reader_state,
+            &mut chunk_writer,
+        );
         Ok(())
     }
 
+    fn supports_pushdown() -> bool {
+        true
+    }
+
     fn parameters() -> Option<Vec<LogicalTypeHandle>> {
         Some(vec![
             LogicalTypeHandle::from(LogicalTypeId::Varchar), // path pattern (required)
@@ -655,10 +2183,96 @@ impl VTab for ReadPgnVTab {
     }
 
     fn named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
-        Some(vec![(
-            "compression".to_string(),
-            LogicalTypeHandle::from(LogicalTypeId::Varchar),
-        )])
+        Some(vec![
+            (
+                "compression".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "min_elo".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+            ),
+            (
+                "max_elo".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+            ),
+            (
+                "variant".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "schema_version".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+            ),
+            (
+                "start_offset".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+            ),
+            (
+                "columns".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Any),
+            ),
+            (
+                "follow_symlinks".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "skip_hidden".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "movetext_style".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "moves_as".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "elo_lenient".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "elo_raw_columns".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "game_id".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "line_number".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "encoding_issues".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "eco_backfill".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "normalize".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "warnings".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "skip_empty_games".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "background_decompression".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "validate".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+        ])
     }
 }
 
@@ -666,6 +2280,7 @@ impl VTab for ReadPgnVTab {
 #[allow(dead_code)]
 mod tests {
     use super::*;
+    use crate::chess::compression::GZIP_MAGIC;
 
     use std::panic::{self, AssertUnwindSafe};
     use std::path::PathBuf;
@@ -677,7 +2292,7 @@ mod tests {
         let m = month as i32;
         let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + day as i32 - 1;
         let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
-        (era * 146097 + doe - 719468) as i32
+        era * 146097 + doe - 719468
     }
 
     #[test]
@@ -685,8 +2300,15 @@ mod tests {
         // Test that bind data can be created with single file
         let paths = vec![PathBuf::from("test.pgn")];
         let bind_data = ReadPgnBindData {
+            path_pattern: "test.pgn".to_string(),
             paths,
             compression: CompressionMode::Plain,
+            options: ScanOptions::default(),
+            schema_version: READ_PGN_SCHEMA_VERSION,
+            optional_columns: OptionalColumns::default(),
+            background_decompression: false,
+            max_open_files: DEFAULT_MAX_OPEN_FILES,
+            file_system: None,
         };
         assert_eq!(bind_data.paths.len(), 1);
         assert_eq!(bind_data.paths[0], PathBuf::from("test.pgn"));
@@ -698,8 +2320,15 @@ mod tests {
         // Test that bind data can be created with multiple files
         let paths = vec![PathBuf::from("test1.pgn"), PathBuf::from("test2.pgn")];
         let bind_data = ReadPgnBindData {
+            path_pattern: "test*.pgn".to_string(),
             paths,
             compression: CompressionMode::Plain,
+            options: ScanOptions::default(),
+            schema_version: READ_PGN_SCHEMA_VERSION,
+            optional_columns: OptionalColumns::default(),
+            background_decompression: false,
+            max_open_files: DEFAULT_MAX_OPEN_FILES,
+            file_system: None,
         };
         assert_eq!(bind_data.paths.len(), 2);
     }
@@ -710,9 +2339,12 @@ mod tests {
         let state = SharedState {
             next_path_idx: 0,
             available_readers: Vec::new(),
+            suspended_readers: Vec::new(),
         };
         let init_data = ReadPgnInitData {
             state: Mutex::new(state),
+            column_indices: Vec::new(),
+            line_diagnostics: Arc::default(),
         };
         assert_eq!(init_data.state.lock().unwrap().next_path_idx, 0);
         assert!(init_data.state.lock().unwrap().available_readers.is_empty());
@@ -721,34 +2353,132 @@ mod tests {
     #[test]
     fn test_collect_glob_paths_keeps_valid_paths_and_records_entry_errors() {
         let entries = vec![
-            Ok(PathBuf::from("good-1.pgn")),
+            Ok(PathBuf::from("test/pgn_files/game1.pgn")),
             Err("permission denied"),
-            Ok(PathBuf::from("good-2.pgn")),
+            Ok(PathBuf::from("test/pgn_files/game2.pgn")),
         ];
         let mut warnings = Vec::new();
 
-        let paths = collect_glob_paths("fixtures/*.pgn", entries, |warning| warnings.push(warning));
+        let paths = collect_glob_paths(
+            "fixtures/*.pgn",
+            entries,
+            FileDiscoveryOptions::default(),
+            |warning| warnings.push(warning),
+        );
 
         assert_eq!(
             paths,
-            vec![PathBuf::from("good-1.pgn"), PathBuf::from("good-2.pgn")]
+            vec![
+                PathBuf::from("test/pgn_files/game1.pgn"),
+                PathBuf::from("test/pgn_files/game2.pgn")
+            ]
         );
         assert_eq!(warnings.len(), 1);
         assert!(warnings[0].contains("Skipping glob entry for pattern 'fixtures/*.pgn'"));
         assert!(warnings[0].contains("permission denied"));
     }
 
+    fn discover(pattern: &str, options: FileDiscoveryOptions) -> (Vec<PathBuf>, Vec<String>) {
+        let entries = glob::glob_with(pattern, glob_match_options(options)).unwrap();
+        let mut warnings = Vec::new();
+        let paths = collect_glob_paths(pattern, entries, options, |warning| warnings.push(warning));
+        (paths, warnings)
+    }
+
+    #[test]
+    fn test_collect_glob_paths_skips_directories_and_applies_discovery_options() {
+        let pattern = "test/pgn_files/discovery/*.pgn";
+        let dir = Path::new("test/pgn_files/discovery");
+
+        let (paths, warnings) = discover(pattern, FileDiscoveryOptions::default());
+        assert_eq!(
+            paths,
+            vec![
+                dir.join(".hidden.pgn"),
+                dir.join("link.pgn"),
+                dir.join("visible.pgn")
+            ]
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("nested.pgn' matched by pattern"));
+        assert!(warnings[0].ends_with("is a directory"));
+
+        let (paths, warnings) = discover(
+            pattern,
+            FileDiscoveryOptions {
+                follow_symlinks: false,
+                skip_hidden: true,
+            },
+        );
+        assert_eq!(paths, vec![dir.join("visible.pgn")]);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].ends_with("symlink (follow_symlinks := false)"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_glob_entry_skip_reason_reports_special_files() {
+        let options = FileDiscoveryOptions::default();
+        assert_eq!(
+            glob_entry_skip_reason(Path::new("/dev/null"), options).as_deref(),
+            Some("not a regular file (character device)")
+        );
+        assert!(
+            glob_entry_skip_reason(Path::new("test/pgn_files/definitely-missing.pgn"), options)
+                .is_some_and(|reason| reason.starts_with("cannot stat"))
+        );
+    }
+
+    #[test]
+    fn test_check_explicit_path_rejects_directories_and_unfollowed_symlinks() {
+        let options = FileDiscoveryOptions::default();
+        assert!(check_explicit_path(Path::new("test/pgn_files/sample.pgn"), options).is_ok());
+        assert!(
+            check_explicit_path(Path::new("test/pgn_files/definitely-missing.pgn"), options)
+                .is_ok()
+        );
+        assert!(
+            check_explicit_path(Path::new("test/pgn_files/discovery/nested.pgn"), options)
+                .unwrap_err()
+                .ends_with("is a directory")
+        );
+
+        let link = Path::new("test/pgn_files/discovery/link.pgn");
+        assert!(check_explicit_path(link, options).is_ok());
+        assert!(
+            check_explicit_path(
+                link,
+                FileDiscoveryOptions {
+                    follow_symlinks: false,
+                    ..options
+                }
+            )
+            .unwrap_err()
+            .contains("is a symlink")
+        );
+    }
+
     #[test]
     fn test_acquire_reader_single_missing_path_fails_hard() {
         let init_data = ReadPgnInitData {
             state: Mutex::new(SharedState {
                 next_path_idx: 0,
                 available_readers: Vec::new(),
+                suspended_readers: Vec::new(),
             }),
+            column_indices: Vec::new(),
+            line_diagnostics: Arc::default(),
         };
         let bind_data = ReadPgnBindData {
+            path_pattern: String::new(),
             paths: vec![PathBuf::from("test/pgn_files/definitely-missing-file.pgn")],
             compression: CompressionMode::Plain,
+            options: ScanOptions::default(),
+            schema_version: READ_PGN_SCHEMA_VERSION,
+            optional_columns: OptionalColumns::default(),
+            background_decompression: false,
+            max_open_files: DEFAULT_MAX_OPEN_FILES,
+            file_system: None,
         };
 
         let err = match acquire_reader(&init_data, &bind_data) {
@@ -756,31 +2486,237 @@ mod tests {
             Err(err) => err.to_string(),
         };
 
-        assert!(err.contains("Failed to open file"));
-        assert!(err.contains("definitely-missing-file.pgn"));
+        assert!(err.contains("Failed to open file"));
+        assert!(err.contains("definitely-missing-file.pgn"));
+    }
+
+    #[test]
+    fn test_acquire_reader_glob_style_paths_skip_unreadable_entries() {
+        let init_data = ReadPgnInitData {
+            state: Mutex::new(SharedState {
+                next_path_idx: 0,
+                available_readers: Vec::new(),
+                suspended_readers: Vec::new(),
+            }),
+            column_indices: Vec::new(),
+            line_diagnostics: Arc::default(),
+        };
+        let bind_data = ReadPgnBindData {
+            path_pattern: String::new(),
+            paths: vec![
+                PathBuf::from("test/pgn_files/definitely-missing-file.pgn"),
+                PathBuf::from("test/pgn_files/sample.pgn"),
+            ],
+            compression: CompressionMode::Plain,
+            options: ScanOptions::default(),
+            schema_version: READ_PGN_SCHEMA_VERSION,
+            optional_columns: OptionalColumns::default(),
+            background_decompression: false,
+            max_open_files: DEFAULT_MAX_OPEN_FILES,
+            file_system: None,
+        };
+
+        let reader = acquire_reader(&init_data, &bind_data)
+            .expect("multi-path acquisition should continue on unreadable entry")
+            .expect("expected a reader for the readable path");
+
+        assert_eq!(reader.path_idx, 1);
+    }
+
+    #[test]
+    fn test_evicted_reader_resumes_after_last_returned_game() {
+        let init_data = ReadPgnInitData {
+            state: Mutex::new(SharedState {
+                next_path_idx: 0,
+                available_readers: Vec::new(),
+                suspended_readers: Vec::new(),
+            }),
+            column_indices: Vec::new(),
+            line_diagnostics: Arc::default(),
+        };
+        let bind_data = ReadPgnBindData {
+            path_pattern: String::new(),
+            paths: vec![
+                PathBuf::from("test/pgn_files/sample.pgn"),
+                PathBuf::from("test/pgn_files/game1.pgn"),
+            ],
+            compression: CompressionMode::Plain,
+            options: ScanOptions::default(),
+            schema_version: READ_PGN_SCHEMA_VERSION,
+            optional_columns: OptionalColumns::default(),
+            background_decompression: false,
+            max_open_files: 1,
+            file_system: None,
+        };
+        let path = &bind_data.paths[0];
+
+        let mut first = acquire_reader(&init_data, &bind_data).unwrap().unwrap();
+        let second = acquire_reader(&init_data, &bind_data).unwrap().unwrap();
+        for _ in 0..3 {
+            assert!(matches!(
+                read_next_game(&mut first, path),
+                ReadNextGameOutcome::GameReady
+            ));
+        }
+        let expected_event = first.record_buffer.event.clone();
+        {
+            let mut state = lock_shared_state(&init_data.state, "test");
+            state.available_readers.push(first);
+            state.available_readers.push(second);
+            evict_idle_readers(&mut state, bind_data.max_open_files);
+            assert_eq!(state.available_readers.len(), 1);
+            assert_eq!(state.suspended_readers.len(), 1);
+            assert_eq!(state.suspended_readers[0].path_idx, 0);
+            // Leave only the evicted reader to pick up.
+            state.available_readers.clear();
+        }
+
+        let mut resumed = acquire_reader(&init_data, &bind_data).unwrap().unwrap();
+        assert_eq!(resumed.path_idx, 0);
+        assert_eq!(resumed.record_buffer.event, expected_event);
+        assert_eq!(resumed.next_game_index, 4);
+
+        let mut remaining = 0;
+        while matches!(
+            read_next_game(&mut resumed, path),
+            ReadNextGameOutcome::GameReady
+        ) {
+            remaining += 1;
+        }
+        assert_eq!(remaining, 7);
     }
 
-    #[test]
-    fn test_acquire_reader_glob_style_paths_skip_unreadable_entries() {
+    fn eviction_scan(
+        paths: Vec<PathBuf>,
+        compression: CompressionMode,
+        options: ScanOptions,
+    ) -> (ReadPgnInitData, ReadPgnBindData) {
         let init_data = ReadPgnInitData {
             state: Mutex::new(SharedState {
                 next_path_idx: 0,
                 available_readers: Vec::new(),
+                suspended_readers: Vec::new(),
             }),
+            column_indices: Vec::new(),
+            line_diagnostics: Arc::default(),
         };
         let bind_data = ReadPgnBindData {
-            paths: vec![
-                PathBuf::from("test/pgn_files/definitely-missing-file.pgn"),
-                PathBuf::from("test/pgn_files/sample.pgn"),
-            ],
-            compression: CompressionMode::Plain,
+            path_pattern: String::new(),
+            paths,
+            compression,
+            options,
+            schema_version: READ_PGN_SCHEMA_VERSION,
+            optional_columns: OptionalColumns::default(),
+            background_decompression: false,
+            max_open_files: 1,
+            file_system: None,
+        };
+        (init_data, bind_data)
+    }
+
+    #[test]
+    fn test_resumed_reader_does_not_count_diagnostics_twice() {
+        let path = std::env::temp_dir().join(format!(
+            "duckdb-chess-{}-resume-diagnostics.pgn",
+            std::process::id()
+        ));
+        fs::write(
+            &path,
+            "[Event \"A\"]\r[Result \"1-0\"]\r\r1. e4 e5 1-0\r\r\
+             [Event \"B\"]\r\n[Variant \"Chess960\"]\r\n\r\n1. e4 *\r\n\r\n\
+             [Event \"C\"] 1. d4 d5 1-0 [Event \"D\"]\n\n1. c4 *\n",
+        )
+        .unwrap();
+        let options = ScanOptions {
+            filter: GameFilter::default().with_variant(VariantFilter::Standard),
+            ..ScanOptions::default()
         };
+        // A second path makes the scan track offsets so readers can be evicted.
+        let (init_data, bind_data) = eviction_scan(
+            vec![path.clone(), PathBuf::from("test/pgn_files/game1.pgn")],
+            CompressionMode::Plain,
+            options,
+        );
 
-        let reader = acquire_reader(&init_data, &bind_data)
-            .expect("multi-path acquisition should continue on unreadable entry")
-            .expect("expected a reader for the readable path");
+        let mut reader = acquire_reader(&init_data, &bind_data).unwrap().unwrap();
+        assert!(matches!(
+            read_next_game(&mut reader, &path),
+            ReadNextGameOutcome::GameReady
+        ));
+        assert!(matches!(
+            read_next_game(&mut reader, &path),
+            ReadNextGameOutcome::GameSkipped
+        ));
+        {
+            let mut state = lock_shared_state(&init_data.state, "test");
+            state.available_readers.push(reader);
+            evict_idle_readers(&mut state, 0);
+            assert_eq!(state.suspended_readers.len(), 1);
+        }
 
-        assert_eq!(reader.path_idx, 1);
+        // Game A again, then B skipped again, then C and D.
+        let mut reader = acquire_reader(&init_data, &bind_data).unwrap().unwrap();
+        let mut events = vec![reader.record_buffer.event.clone()];
+        loop {
+            match read_next_game(&mut reader, &path) {
+                ReadNextGameOutcome::GameReady => events.push(reader.record_buffer.event.clone()),
+                ReadNextGameOutcome::GameSkipped => {}
+                ReadNextGameOutcome::ReaderFinished => break,
+            }
+        }
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            events,
+            [Some("A"), Some("C"), Some("D")].map(|event| event.map(str::to_string))
+        );
+        assert_eq!(
+            init_data.line_diagnostics.counts(),
+            [
+                ("files", 1),
+                ("cr_line_endings", 5),
+                ("crlf_line_endings", 5),
+                ("tag_lines_split", 1),
+                ("boundaries_recovered", 1),
+                ("variant_games_skipped", 1),
+                ("empty_games_skipped", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compressed_reader_is_not_evicted() {
+        let path = PathBuf::from("test/pgn_files/sample.pgn.zst");
+        let (init_data, bind_data) = eviction_scan(
+            vec![path.clone(), PathBuf::from("test/pgn_files/game1.pgn")],
+            CompressionMode::Auto,
+            ScanOptions::default(),
+        );
+
+        let mut reader = acquire_reader(&init_data, &bind_data).unwrap().unwrap();
+        assert!(matches!(
+            read_next_game(&mut reader, &path),
+            ReadNextGameOutcome::GameReady
+        ));
+        assert!(reader.resume_point.is_some());
+
+        let mut state = lock_shared_state(&init_data.state, "test");
+        state.available_readers.push(reader);
+        evict_idle_readers(&mut state, 0);
+        assert_eq!(state.available_readers.len(), 1);
+        assert!(state.suspended_readers.is_empty());
+    }
+
+    #[test]
+    fn test_parse_max_open_files() {
+        assert_eq!(parse_max_open_files(None).unwrap(), DEFAULT_MAX_OPEN_FILES);
+        assert_eq!(parse_max_open_files(Some("8".to_string())).unwrap(), 8);
+        for invalid in ["0", "-1", "many"] {
+            let err = parse_max_open_files(Some(invalid.to_string()))
+                .unwrap_err()
+                .to_string();
+            assert!(err.starts_with("duckdb_chess_max_open_files: expected a positive integer"));
+        }
     }
 
     #[test]
@@ -788,6 +2724,7 @@ mod tests {
         let state = Mutex::new(SharedState {
             next_path_idx: 0,
             available_readers: Vec::new(),
+            suspended_readers: Vec::new(),
         });
 
         let _ = panic::catch_unwind(AssertUnwindSafe(|| {
@@ -796,10 +2733,21 @@ mod tests {
         }));
         assert!(state.is_poisoned());
 
-        let init_data = ReadPgnInitData { state };
+        let init_data = ReadPgnInitData {
+            state,
+            column_indices: Vec::new(),
+            line_diagnostics: Arc::default(),
+        };
         let bind_data = ReadPgnBindData {
+            path_pattern: String::new(),
             paths: Vec::new(),
             compression: CompressionMode::Plain,
+            options: ScanOptions::default(),
+            schema_version: READ_PGN_SCHEMA_VERSION,
+            optional_columns: OptionalColumns::default(),
+            background_decompression: false,
+            max_open_files: DEFAULT_MAX_OPEN_FILES,
+            file_system: None,
         };
 
         let result = acquire_reader(&init_data, &bind_data)
@@ -828,6 +2776,11 @@ mod tests {
             ("movetext", ReadPgnLogicalType::Varchar),
             ("parse_error", ReadPgnLogicalType::Varchar),
             ("Source", ReadPgnLogicalType::Varchar),
+            ("byte_offset", ReadPgnLogicalType::UBigInt),
+            ("movetext_bytes", ReadPgnLogicalType::UBigInt),
+            ("has_clk", ReadPgnLogicalType::Boolean),
+            ("has_eval", ReadPgnLogicalType::Boolean),
+            ("has_variations", ReadPgnLogicalType::Boolean),
         ];
 
         for (idx, column) in READ_PGN_COLUMNS.iter().enumerate() {
@@ -867,12 +2820,6 @@ mod tests {
         );
     }
 
-    #[test]
-    fn test_sanitize_interior_nul_silent_replaces_interior_nul() {
-        let sanitized = sanitize_interior_nul_silent("x\0y");
-        assert_eq!(sanitized.as_ref(), "x y");
-    }
-
     #[test]
     fn test_parse_compression_mode_zstd_case_insensitive() {
         assert_eq!(
@@ -893,50 +2840,686 @@ mod tests {
 
     #[test]
     fn test_parse_compression_mode_rejects_unsupported_value() {
-        let err = CompressionMode::parse("gzip").unwrap_err().to_string();
-        assert!(err.contains("Invalid compression value 'gzip'"));
+        let err = CompressionMode::parse("lz4").unwrap_err().to_string();
+        assert!(err.contains("Invalid compression value 'lz4'"));
+    }
+
+    #[test]
+    fn test_build_game_filter_accepts_open_and_closed_ranges() {
+        assert_eq!(
+            build_game_filter(None, None).unwrap(),
+            GameFilter::default()
+        );
+
+        let filter = build_game_filter(Some(2500), None).unwrap();
+        assert_eq!(filter.min_elo, Some(2500));
+        assert_eq!(filter.max_elo, None);
+
+        let filter = build_game_filter(Some(2000), Some(2000)).unwrap();
+        assert_eq!(filter.min_elo, Some(2000));
+        assert_eq!(filter.max_elo, Some(2000));
+    }
+
+    #[test]
+    fn test_build_game_filter_rejects_inverted_range() {
+        let err = build_game_filter(Some(2600), Some(2500))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Invalid Elo range"));
+    }
+
+    #[test]
+    fn test_build_extra_columns_parses_types_in_order() {
+        let columns = build_extra_columns(
+            vec![
+                ("Variant".to_string(), "varchar".to_string()),
+                ("Board".to_string(), "INTEGER".to_string()),
+                ("FICSGamesDBGameNo".to_string(), "UBIGINT".to_string()),
+            ],
+            READ_PGN_SCHEMA_VERSION,
+            OptionalColumns::default(),
+        )
+        .unwrap();
+
+        let summary: Vec<(&str, ExtraTagType)> = columns
+            .iter()
+            .map(|column| (column.tag.as_str(), column.tag_type))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Variant", ExtraTagType::Varchar),
+                ("Board", ExtraTagType::Integer),
+                ("FICSGamesDBGameNo", ExtraTagType::UBigInt),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_extra_columns_rejects_invalid_entries() {
+        let cases = [
+            (("Board", "HUGEINT"), "Invalid columns type 'HUGEINT'"),
+            (("event", "VARCHAR"), "duplicate column name 'event'"),
+            (("", "VARCHAR"), "tag name must not be empty"),
+        ];
+
+        for ((tag, type_name), expected) in cases {
+            let err = build_extra_columns(
+                vec![(tag.to_string(), type_name.to_string())],
+                READ_PGN_SCHEMA_VERSION,
+                OptionalColumns::default(),
+            )
+            .unwrap_err()
+            .to_string();
+            assert!(err.contains(expected), "{err}");
+        }
+
+        let err = build_extra_columns(
+            vec![
+                ("Board".to_string(), "INTEGER".to_string()),
+                ("BOARD".to_string(), "VARCHAR".to_string()),
+            ],
+            READ_PGN_SCHEMA_VERSION,
+            OptionalColumns::default(),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("duplicate column name 'BOARD'"), "{err}");
+
+        let cases = [
+            (
+                "WhiteEloRaw",
+                OptionalColumns {
+                    elo_raw: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "Game_Id",
+                OptionalColumns {
+                    game_id: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "LINE_NUMBER",
+                OptionalColumns {
+                    line_number: true,
+                    ..Default::default()
+                },
+            ),
+        ];
+        for (tag, optional_columns) in cases {
+            let pairs = vec![(tag.to_string(), "VARCHAR".to_string())];
+            let unused = OptionalColumns::default();
+            assert!(build_extra_columns(pairs.clone(), READ_PGN_SCHEMA_VERSION, unused).is_ok());
+            let err = build_extra_columns(pairs, READ_PGN_SCHEMA_VERSION, optional_columns)
+                .unwrap_err()
+                .to_string();
+            assert!(
+                err.contains(&format!("duplicate column name '{tag}'")),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_optional_columns_layout() {
+        let all = OptionalColumns {
+            elo_raw: true,
+            game_id: true,
+            line_number: true,
+            encoding_issues: true,
+            eco_backfill: true,
+            normalize: true,
+            warnings: true,
+        };
+        assert_eq!(
+            all.names().collect::<Vec<_>>(),
+            vec![
+                "WhiteEloRaw",
+                "BlackEloRaw",
+                "game_id",
+                "line_number",
+                "encoding_issues",
+                "eco_inferred",
+                "normalized_movetext",
+                "warnings"
+            ]
+        );
+        assert_eq!(
+            (
+                all.game_id_offset(),
+                all.line_number_offset(),
+                all.encoding_issues_offset(),
+                all.eco_inferred_offset(),
+                all.normalized_movetext_offset(),
+                all.warnings_offset()
+            ),
+            (2, 3, 4, 5, 6, 7)
+        );
+
+        let line_only = OptionalColumns {
+            line_number: true,
+            ..Default::default()
+        };
+        assert_eq!(line_only.count(), 1);
+        assert_eq!(line_only.line_number_offset(), 0);
+
+        let encoding_only = OptionalColumns {
+            encoding_issues: true,
+            ..Default::default()
+        };
+        assert_eq!(encoding_only.encoding_issues_offset(), 0);
+
+        let eco_only = OptionalColumns {
+            eco_backfill: true,
+            ..Default::default()
+        };
+        assert_eq!(eco_only.eco_inferred_offset(), 0);
+
+        let normalize_only = OptionalColumns {
+            normalize: true,
+            ..Default::default()
+        };
+        assert_eq!(normalize_only.normalized_movetext_offset(), 0);
+
+        let warnings_only = OptionalColumns {
+            warnings: true,
+            ..Default::default()
+        };
+        assert_eq!(warnings_only.warnings_offset(), 0);
+    }
+
+    #[test]
+    fn test_parse_schema_version_defaults_and_bounds() {
+        assert_eq!(
+            parse_schema_version(None).unwrap(),
+            READ_PGN_DEFAULT_SCHEMA_VERSION
+        );
+        assert_eq!(
+            parse_schema_version(Some(i64::from(READ_PGN_SCHEMA_VERSION))).unwrap(),
+            READ_PGN_SCHEMA_VERSION
+        );
+
+        for invalid in [0, -1, i64::from(READ_PGN_SCHEMA_VERSION) + 1, i64::MAX] {
+            let err = parse_schema_version(Some(invalid)).unwrap_err().to_string();
+            assert!(err.contains("Invalid schema_version"), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_parse_variant_filter_defaults_to_standard() {
+        for missing in [NamedParameterVarchar::Missing, NamedParameterVarchar::Null] {
+            assert_eq!(
+                parse_variant_filter(missing).unwrap(),
+                VariantFilter::Standard
+            );
+        }
+        assert_eq!(
+            parse_variant_filter(NamedParameterVarchar::Value(" ALL ".to_string())).unwrap(),
+            VariantFilter::All
+        );
+
+        let err = parse_variant_filter(NamedParameterVarchar::Value("chess960".to_string()))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "Invalid variant value 'chess960'. Supported values: standard, all."
+        );
+    }
+
+    #[test]
+    fn test_parse_movetext_style_defaults_and_values() {
+        for missing in [NamedParameterVarchar::Missing, NamedParameterVarchar::Null] {
+            assert_eq!(
+                parse_movetext_style(missing).unwrap(),
+                MovetextStyle::Numbered
+            );
+        }
+        assert_eq!(
+            parse_movetext_style(NamedParameterVarchar::Value(" UCI ".to_string())).unwrap(),
+            MovetextStyle::Uci
+        );
+
+        let err = parse_movetext_style(NamedParameterVarchar::Value("lan".to_string()))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "Invalid movetext_style value 'lan'. Supported values: numbered, plain, uci."
+        );
+    }
+
+    #[test]
+    fn test_parse_moves_as_defaults_values_and_uci_conflict() {
+        for missing in [NamedParameterVarchar::Missing, NamedParameterVarchar::Null] {
+            assert_eq!(
+                parse_moves_as(missing, MovetextStyle::Uci).unwrap(),
+                MovesAs::Text
+            );
+        }
+        assert_eq!(
+            parse_moves_as(
+                NamedParameterVarchar::Value(" Struct ".to_string()),
+                MovetextStyle::Plain
+            )
+            .unwrap(),
+            MovesAs::Struct
+        );
+
+        let err = parse_moves_as(
+            NamedParameterVarchar::Value("json".to_string()),
+            MovetextStyle::Numbered,
+        )
+        .unwrap_err()
+        .to_string();
+        assert_eq!(
+            err,
+            "Invalid moves_as value 'json'. Supported values: text, list, struct."
+        );
+
+        let err = parse_moves_as(
+            NamedParameterVarchar::Value("list".to_string()),
+            MovetextStyle::Uci,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("cannot be combined with movetext_style := 'uci'"));
+    }
+
+    #[test]
+    fn test_count_tag_sections_counts_games_not_tags() {
+        let sample = b"[Event \"A\"]\r\n[Site \"x\"]\r\n\r\n1. e4 *\r\n\r\n[Event \"B\"]\n\n[Site \"y\"]\n1. d4 { [%clk 0:01:00] } *\n[Event \"C\"]";
+        assert_eq!(count_tag_sections(sample), 3);
+        assert_eq!(count_tag_sections(b"1. e4 e5 *\n"), 0);
+        assert_eq!(
+            count_tag_sections(b"% [Event]\n[Event \"A\"]\n% note\n[Site \"x\"]\n*\n"),
+            1
+        );
+    }
+
+    #[test]
+    fn test_estimate_game_count_scales_sample_to_total_size() {
+        let plain = PathBuf::from("test/pgn_files/sample.pgn");
+        let zstd = PathBuf::from("test/pgn_files/sample.pgn.zst");
+        // Small files are sampled whole, so a single file is estimated exactly.
+        assert_eq!(
+            estimate_game_count(std::slice::from_ref(&plain), CompressionMode::Auto),
+            Some(10)
+        );
+        assert_eq!(
+            estimate_game_count(&[zstd], CompressionMode::Auto),
+            Some(10)
+        );
+        // Further files are extrapolated from the first one's games per byte.
+        assert_eq!(
+            estimate_game_count(&[plain.clone(), plain.clone()], CompressionMode::Auto),
+            Some(20)
+        );
+
+        let missing = PathBuf::from("test/pgn_files/definitely-missing-file.pgn");
+        assert_eq!(
+            estimate_game_count(&[missing.clone(), plain], CompressionMode::Auto),
+            Some(10)
+        );
+        assert_eq!(estimate_game_count(&[missing], CompressionMode::Auto), None);
+        assert_eq!(
+            estimate_game_count(
+                &[PathBuf::from("test/pgn_files/empty.pgn")],
+                CompressionMode::Auto
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_projected_output_columns_follow_column_indices() {
+        assert_eq!(
+            projected_output_columns(4, &[3, 0]),
+            vec![Some(1), None, None, Some(0)]
+        );
+        // The row id requested by `count(*)` maps to no bound column.
+        assert_eq!(projected_output_columns(2, &[u64::MAX]), vec![None, None]);
+    }
+
+    #[test]
+    fn test_projected_scan_options_skip_unread_work() {
+        let options = ScanOptions {
+            track_byte_offsets: true,
+            track_movetext_bytes: true,
+            ..Default::default()
+        };
+        let index = |column: ReadPgnColumn| column.index() as u64;
+
+        let headers_only = projected_scan_options(
+            &options,
+            &[
+                index(ReadPgnColumn::White),
+                index(ReadPgnColumn::ParseError),
+            ],
+            &[],
+        );
+        assert!(headers_only.skip_movetext);
+        assert!(!headers_only.track_byte_offsets);
+        assert!(!headers_only.track_movetext_bytes);
+
+        for column in [ReadPgnColumn::Movetext, ReadPgnColumn::HasClk] {
+            assert!(!projected_scan_options(&options, &[index(column)], &[]).skip_movetext);
+        }
+        let sizes = projected_scan_options(&options, &[index(ReadPgnColumn::MovetextBytes)], &[]);
+        assert!(sizes.track_movetext_bytes);
+        assert!(sizes.skip_movetext);
+
+        // `game_id` hashes the movetext, so projecting it alone still parses moves.
+        let game_id_column = read_pgn_columns(READ_PGN_SCHEMA_VERSION).count();
+        let ids = projected_scan_options(&options, &[game_id_column as u64], &[game_id_column]);
+        assert!(!ids.skip_movetext);
+        let unread =
+            projected_scan_options(&options, &[index(ReadPgnColumn::White)], &[game_id_column]);
+        assert!(unread.skip_movetext);
+        // So does `normalized_movetext`, whichever derived column is read.
+        let normalized = projected_scan_options(
+            &options,
+            &[game_id_column as u64 + 1],
+            &[game_id_column, game_id_column + 1],
+        );
+        assert!(!normalized.skip_movetext);
+
+        // Telling empty games apart needs their moves.
+        let skip_empty = ScanOptions {
+            skip_empty_games: true,
+            ..options.clone()
+        };
+        let empty_check = projected_scan_options(&skip_empty, &[index(ReadPgnColumn::White)], &[]);
+        assert!(!empty_check.skip_movetext);
+    }
+
+    #[test]
+    fn test_encoding_issues_projection_parses_comments() {
+        let bind_data = ReadPgnBindData {
+            path_pattern: "test.pgn".to_string(),
+            paths: vec![PathBuf::from("test.pgn")],
+            compression: CompressionMode::Plain,
+            options: ScanOptions::default(),
+            schema_version: READ_PGN_SCHEMA_VERSION,
+            optional_columns: OptionalColumns {
+                encoding_issues: true,
+                ..Default::default()
+            },
+            background_decompression: false,
+            max_open_files: DEFAULT_MAX_OPEN_FILES,
+            file_system: None,
+        };
+        let first = read_pgn_columns(READ_PGN_SCHEMA_VERSION).count() as u64;
+        let derived = bind_data.movetext_derived_columns();
+        // Comments may hold invalid UTF-8, so both the count and the `parse_error` note that
+        // reports it need the movetext parsed.
+        for column in [first, ReadPgnColumn::ParseError.index() as u64] {
+            assert!(!projected_scan_options(&bind_data.options, &[column], &derived).skip_movetext);
+        }
+        let white = ReadPgnColumn::White.index() as u64;
+        assert!(projected_scan_options(&bind_data.options, &[white], &derived).skip_movetext);
+
+        let without = ReadPgnBindData {
+            optional_columns: OptionalColumns::default(),
+            ..bind_data
+        };
+        assert!(without.movetext_derived_columns().is_empty());
+    }
+
+    #[test]
+    fn test_game_uuid_is_stable_and_distinguishes_games() {
+        let path = Path::new("games/2024-01.pgn");
+        let id = game_uuid(path, 1, "1. e4 e5 2. Nf3");
+        assert_eq!(id, game_uuid(path, 1, "1. e4 e5 2. Nf3"));
+        assert_eq!(Uuid::from_u128(id).get_version_num(), 5);
+
+        assert_ne!(
+            id,
+            game_uuid(Path::new("games/2024-02.pgn"), 1, "1. e4 e5 2. Nf3")
+        );
+        assert_ne!(id, game_uuid(path, 2, "1. e4 e5 2. Nf3"));
+        assert_ne!(id, game_uuid(path, 1, "1. e4 e5 2. Nc3"));
+        // Parts are separated, so shifting bytes between them changes the id.
+        assert_ne!(game_uuid(path, 1, "1. e4"), game_uuid(path, 11, ". e4"));
+    }
+
+    #[test]
+    fn test_uuid_to_hugeint_preserves_textual_order() {
+        let ids = [0u128, 1, 1 << 127, u128::MAX];
+        let stored: Vec<i128> = ids.into_iter().map(uuid_to_hugeint).collect();
+        assert!(stored.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(stored[0], i128::MIN);
+    }
+
+    #[test]
+    fn test_check_start_offset_columns() {
+        let game_id = OptionalColumns {
+            game_id: true,
+            ..Default::default()
+        };
+        let line_number = OptionalColumns {
+            line_number: true,
+            ..Default::default()
+        };
+        assert!(check_start_offset_columns(game_id, 0).is_ok());
+        assert!(check_start_offset_columns(line_number, 0).is_ok());
+        assert!(check_start_offset_columns(OptionalColumns::default(), 4096).is_ok());
+
+        let err = check_start_offset_columns(game_id, 4096).unwrap_err();
+        assert!(
+            err.contains("game_id cannot be combined with start_offset"),
+            "{err}"
+        );
+        let err = check_start_offset_columns(line_number, 4096).unwrap_err();
+        assert!(
+            err.contains("line_number cannot be combined with start_offset"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_read_pgn_columns_are_grouped_by_schema_version() {
+        // Columns must be appended in schema-version order so each version is a prefix.
+        assert!(
+            READ_PGN_COLUMNS
+                .windows(2)
+                .all(|pair| pair[0].since_schema_version <= pair[1].since_schema_version)
+        );
+        assert_eq!(
+            read_pgn_columns(READ_PGN_SCHEMA_VERSION).count(),
+            READ_PGN_COLUMN_COUNT
+        );
     }
 
     #[test]
-    fn test_resolve_compression_mode_missing_named_parameter_defaults_plain() {
-        let mode = resolve_compression_mode_from_named_parameter(NamedParameterVarchar::Missing)
-            .expect("missing named parameter should default to plain mode");
-        assert_eq!(mode, CompressionMode::Plain);
+    fn test_resolve_compression_mode_missing_named_parameter_defaults_auto() {
+        let mode = resolve_compression_mode_from_named_parameter(
+            NamedParameterVarchar::Missing,
+            CompressionMode::Auto,
+        )
+        .expect("missing named parameter should default to auto-detection");
+        assert_eq!(mode, CompressionMode::Auto);
     }
 
     #[test]
-    fn test_resolve_compression_mode_null_named_parameter_defaults_plain() {
-        let mode = resolve_compression_mode_from_named_parameter(NamedParameterVarchar::Null)
-            .expect("NULL named parameter should default to plain mode");
-        assert_eq!(mode, CompressionMode::Plain);
+    fn test_resolve_compression_mode_null_named_parameter_defaults_auto() {
+        let mode = resolve_compression_mode_from_named_parameter(
+            NamedParameterVarchar::Null,
+            CompressionMode::Auto,
+        )
+        .expect("NULL named parameter should default to auto-detection");
+        assert_eq!(mode, CompressionMode::Auto);
     }
 
     #[test]
     fn test_resolve_compression_mode_zstd_named_parameter() {
-        let mode = resolve_compression_mode_from_named_parameter(NamedParameterVarchar::Value(
-            "ZsTd".to_string(),
-        ))
+        let mode = resolve_compression_mode_from_named_parameter(
+            NamedParameterVarchar::Value("ZsTd".to_string()),
+            CompressionMode::Auto,
+        )
         .expect("zstd named parameter should resolve to zstd mode");
         assert_eq!(mode, CompressionMode::Zstd);
     }
 
     #[test]
-    fn test_resolve_compression_mode_string_null_defaults_plain() {
-        let mode = resolve_compression_mode_from_named_parameter(NamedParameterVarchar::Value(
-            " null ".to_string(),
-        ))
-        .expect("string literal null should resolve to plain mode");
-        assert_eq!(mode, CompressionMode::Plain);
+    fn test_resolve_compression_mode_string_null_defaults_auto() {
+        let mode = resolve_compression_mode_from_named_parameter(
+            NamedParameterVarchar::Value(" null ".to_string()),
+            CompressionMode::Auto,
+        )
+        .expect("string literal null should resolve to auto mode");
+        assert_eq!(mode, CompressionMode::Auto);
     }
 
     #[test]
     fn test_resolve_compression_mode_unsupported_named_parameter_value() {
-        let err = resolve_compression_mode_from_named_parameter(NamedParameterVarchar::Value(
-            "gzip".to_string(),
-        ))
+        let err = resolve_compression_mode_from_named_parameter(
+            NamedParameterVarchar::Value("lz4".to_string()),
+            CompressionMode::Auto,
+        )
         .unwrap_err()
         .to_string();
-        assert!(err.contains("Invalid compression value 'gzip'"));
+        assert!(err.contains("Invalid compression value 'lz4'"));
+    }
+
+    #[test]
+    fn test_resolve_compression_mode_falls_back_to_session_default() {
+        let default = parse_default_compression_mode(Some("GZIP".to_string())).unwrap();
+        let mode =
+            resolve_compression_mode_from_named_parameter(NamedParameterVarchar::Missing, default)
+                .unwrap();
+        assert_eq!(mode, CompressionMode::Gzip);
+
+        let explicit = resolve_compression_mode_from_named_parameter(
+            NamedParameterVarchar::Value("auto".to_string()),
+            default,
+        )
+        .unwrap();
+        assert_eq!(explicit, CompressionMode::Auto);
+
+        assert_eq!(
+            parse_default_compression_mode(None).unwrap(),
+            CompressionMode::Auto
+        );
+        let err = parse_default_compression_mode(Some("lz4".to_string()))
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("duckdb_chess_default_compression: Invalid compression value"));
+    }
+
+    #[test]
+    fn test_parse_compression_mode_supported_values() {
+        assert_eq!(
+            CompressionMode::parse("auto").unwrap(),
+            CompressionMode::Auto
+        );
+        assert_eq!(
+            CompressionMode::parse("GZIP").unwrap(),
+            CompressionMode::Gzip
+        );
+        assert_eq!(
+            CompressionMode::parse(" bzip2 ").unwrap(),
+            CompressionMode::Bzip2
+        );
+        assert_eq!(CompressionMode::parse("xz").unwrap(), CompressionMode::Xz);
+    }
+
+    #[test]
+    fn test_detect_compression_from_magic_bytes() {
+        assert_eq!(
+            CompressionMode::detect(&[0x28, 0xB5, 0x2F, 0xFD, 0x00]),
+            CompressionMode::Zstd
+        );
+        assert_eq!(
+            CompressionMode::detect(&[0x1F, 0x8B, 0x08]),
+            CompressionMode::Gzip
+        );
+        assert_eq!(CompressionMode::detect(b"BZh91AY"), CompressionMode::Bzip2);
+        assert_eq!(
+            CompressionMode::detect(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]),
+            CompressionMode::Xz
+        );
+        assert_eq!(CompressionMode::detect(b"[Event"), CompressionMode::Plain);
+        assert_eq!(CompressionMode::detect(b""), CompressionMode::Plain);
+        assert_eq!(CompressionMode::detect(&[0x1F]), CompressionMode::Plain);
+    }
+
+    #[test]
+    fn test_open_input_stream_auto_detects_compressed_fixtures() {
+        let expected = std::fs::read("test/pgn_files/sample.pgn").unwrap();
+
+        for fixture in [
+            "sample.pgn",
+            "sample.pgn.zst",
+            "sample.pgn.gz",
+            "sample.pgn.bz2",
+            "sample.pgn.xz",
+            "sample_gzip_misnamed.pgn",
+        ] {
+            let path = PathBuf::from("test/pgn_files").join(fixture);
+            for background in [false, true] {
+                let mut input =
+                    open_input_stream(&path, CompressionMode::Auto, 0, background, None)
+                        .unwrap_or_else(|e| panic!("{fixture}: {e}"));
+                let mut decoded = Vec::new();
+                input.read_to_end(&mut decoded).unwrap();
+                assert_eq!(decoded, expected, "{fixture} background={background}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_open_input_stream_explicit_mode_skips_detection() {
+        let path = PathBuf::from("test/pgn_files/sample.pgn.gz");
+        let mut input = open_input_stream(&path, CompressionMode::Plain, 0, false, None).unwrap();
+        let mut raw = Vec::new();
+        input.read_to_end(&mut raw).unwrap();
+        assert!(raw.starts_with(GZIP_MAGIC));
+    }
+
+    #[test]
+    fn test_open_input_stream_start_offset_plain_and_compressed() {
+        let expected = std::fs::read("test/pgn_files/sample.pgn").unwrap();
+        let offset = expected.len() as u64 / 2;
+
+        for fixture in ["sample.pgn", "sample.pgn.zst", "sample.pgn.gz"] {
+            let path = PathBuf::from("test/pgn_files").join(fixture);
+            let mut input = open_input_stream(&path, CompressionMode::Auto, offset, true, None)
+                .unwrap_or_else(|e| panic!("{fixture}: {e}"));
+            let mut decoded = Vec::new();
+            input.read_to_end(&mut decoded).unwrap();
+            assert_eq!(decoded, expected[offset as usize..], "{fixture}");
+        }
+
+        let end = expected.len() as u64;
+        for fixture in ["sample.pgn", "sample.pgn.gz"] {
+            let path = PathBuf::from("test/pgn_files").join(fixture);
+            let mut input =
+                open_input_stream(&path, CompressionMode::Auto, end, false, None).unwrap();
+            let mut decoded = Vec::new();
+            input.read_to_end(&mut decoded).unwrap();
+            assert!(decoded.is_empty(), "{fixture}");
+
+            let err = open_input_stream(&path, CompressionMode::Auto, end + 1, false, None)
+                .err()
+                .expect("offset past the end should fail");
+            assert!(err.contains("past the end"), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_parse_start_offset_bounds() {
+        assert_eq!(parse_start_offset(None, 3).unwrap(), 0);
+        assert_eq!(parse_start_offset(Some(0), 3).unwrap(), 0);
+        assert_eq!(parse_start_offset(Some(4096), 1).unwrap(), 4096);
+
+        let err = parse_start_offset(Some(-1), 1).unwrap_err().to_string();
+        assert!(err.contains("Invalid start_offset -1"), "{err}");
+        let err = parse_start_offset(Some(10), 2).unwrap_err().to_string();
+        assert!(err.contains("single input file"), "{err}");
     }
 
     // Test with actual PGN file content parsing
@@ -1430,7 +4013,7 @@ mod tests {
         let micros = 12i64 * 3600 * 1_000_000;
         let micros_part = (micros as u64) & ((1u64 << 40) - 1);
         let offset_sentinel: i32 = (16 * 60 * 60) - 1;
-        let encoded_offset = offset_sentinel - 0;
+        let encoded_offset = offset_sentinel;
         let offset_part = (encoded_offset as i64 as u64) & ((1u64 << 24) - 1);
         assert_eq!(utc_time.bits, (micros_part << 24) | offset_part);
 
@@ -1447,7 +4030,7 @@ mod tests {
         reader.read_game(&mut visitor).unwrap();
         let game = visitor.current_game.take().unwrap();
         let utc_time = game.utc_time.unwrap();
-        let offset_seconds: i32 = 1 * 3600 + 30 * 60;
+        let offset_seconds: i32 = 3600 + 30 * 60;
         let encoded_offset = offset_sentinel - offset_seconds;
         let offset_part = (encoded_offset as i64 as u64) & ((1u64 << 24) - 1);
         assert_eq!(utc_time.bits, (micros_part << 24) | offset_part);
@@ -0,0 +1,324 @@
+//! Leaf lines of opening repertoire files.
+//!
+//! A repertoire chapter keeps its alternatives as variations, so unlike the game readers the
+//! visitor here walks into every variation and reports each line that ends without a
+//! continuation.
+//!
+//! Spec: pgn-parsing - Repertoire Lines
+
+use super::filter::push_numbered_move;
+use super::puzzles::{Eval, parse_eval};
+use pgn_reader::{RawComment, RawTag, Skip, Visitor};
+use shakmaty::{Chess, EnPassantMode, fen::Fen, san::SanPlus};
+use std::ops::ControlFlow;
+
+/// A line of a repertoire chapter, from the start position to a move without continuation.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RepertoireLine {
+    /// `white` or `black` from the chapter's `Orientation` tag.
+    pub color: Option<&'static str>,
+    /// Plies in the line.
+    pub depth: usize,
+    /// The line in the `chess_san_canonical` layout.
+    pub movetext: String,
+    /// Position after the last move.
+    pub fen: String,
+    /// The last `[%eval ...]` annotation along the line.
+    pub eval: Option<Eval>,
+    /// Prose comment after the last move, without `[%...]` commands.
+    pub comment: Option<String>,
+}
+
+/// Collects the leaf lines of a game, mainline first and then variations in file order.
+///
+/// Chapters with a `FEN` tag and lines with an illegal move yield no lines.
+#[derive(Default)]
+pub(crate) struct RepertoireVisitor {
+    pub(crate) lines: Vec<RepertoireLine>,
+    color: Option<&'static str>,
+    has_fen: bool,
+}
+
+pub(crate) struct RepertoireMovetext {
+    nodes: Vec<Node>,
+    /// Enclosing lines of the variations being read, innermost last.
+    stack: Vec<Frame>,
+    /// The current line played a move since it started.
+    played: bool,
+    illegal: bool,
+}
+
+struct Node {
+    san: SanPlus,
+    /// Position after the move.
+    pos: Chess,
+    eval: Option<Eval>,
+    comment: Option<String>,
+}
+
+/// State of the enclosing line, restored at the end of a variation.
+struct Frame {
+    /// The move the variation replaces.
+    replaced: Option<Node>,
+    base_len: usize,
+    played: bool,
+    illegal: bool,
+}
+
+impl RepertoireVisitor {
+    fn emit(&mut self, movetext: &RepertoireMovetext) {
+        if !movetext.played || movetext.illegal {
+            return;
+        }
+        let Some(leaf) = movetext.nodes.last() else {
+            return;
+        };
+        let mut line = String::new();
+        for (ply, node) in movetext.nodes.iter().enumerate() {
+            push_numbered_move(&mut line, ply, node.san);
+        }
+        self.lines.push(RepertoireLine {
+            color: self.color,
+            depth: movetext.nodes.len(),
+            movetext: line,
+            fen: Fen::from_position(&leaf.pos, EnPassantMode::Legal).to_string(),
+            eval: movetext.nodes.iter().rev().find_map(|node| node.eval),
+            comment: leaf.comment.clone(),
+        });
+    }
+}
+
+impl Visitor for RepertoireVisitor {
+    type Tags = ();
+    type Movetext = RepertoireMovetext;
+    type Output = ();
+
+    fn begin_tags(&mut self) -> ControlFlow<Self::Output, Self::Tags> {
+        self.lines.clear();
+        self.color = None;
+        self.has_fen = false;
+        ControlFlow::Continue(())
+    }
+
+    fn tag(
+        &mut self,
+        _tags: &mut Self::Tags,
+        name: &[u8],
+        value: RawTag<'_>,
+    ) -> ControlFlow<Self::Output> {
+        match name.trim_ascii() {
+            b"Orientation" => {
+                self.color = match value.decode_utf8_lossy().trim().to_lowercase().as_str() {
+                    "white" => Some("white"),
+                    "black" => Some("black"),
+                    _ => None,
+                };
+            }
+            b"FEN" => self.has_fen = true,
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn begin_movetext(&mut self, _tags: Self::Tags) -> ControlFlow<Self::Output, Self::Movetext> {
+        if self.has_fen {
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(RepertoireMovetext {
+            nodes: Vec::new(),
+            stack: Vec::new(),
+            played: false,
+            illegal: false,
+        })
+    }
+
+    fn san(
+        &mut self,
+        movetext: &mut Self::Movetext,
+        san_plus: SanPlus,
+    ) -> ControlFlow<Self::Output> {
+        if movetext.illegal {
+            return ControlFlow::Continue(());
+        }
+        let mut pos = movetext
+            .nodes
+            .last()
+            .map_or_else(Chess::default, |node| node.pos.clone());
+        match san_plus.san.to_move(&pos) {
+            Ok(m) => {
+                let san = SanPlus::from_move_and_play_unchecked(&mut pos, m);
+                movetext.nodes.push(Node {
+                    san,
+                    pos,
+                    eval: None,
+                    comment: None,
+                });
+                movetext.played = true;
+            }
+            Err(_) => movetext.illegal = true,
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn comment(
+        &mut self,
+        movetext: &mut Self::Movetext,
+        comment: RawComment<'_>,
+    ) -> ControlFlow<Self::Output> {
+        // A comment before the first move of a line does not describe a move of it.
+        if !movetext.played || movetext.illegal {
+            return ControlFlow::Continue(());
+        }
+        let Some(node) = movetext.nodes.last_mut() else {
+            return ControlFlow::Continue(());
+        };
+        let text = String::from_utf8_lossy(comment.as_bytes());
+        if let Some(eval) = parse_eval(&text) {
+            node.eval = Some(eval);
+        }
+        let prose = strip_commands(&text);
+        let prose = prose.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !prose.is_empty() {
+            match &mut node.comment {
+                Some(existing) => {
+                    existing.push(' ');
+                    existing.push_str(&prose);
+                }
+                None => node.comment = Some(prose),
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn begin_variation(
+        &mut self,
+        movetext: &mut Self::Movetext,
+    ) -> ControlFlow<Self::Output, Skip> {
+        let replaced = movetext.nodes.pop();
+        movetext.stack.push(Frame {
+            replaced,
+            base_len: movetext.nodes.len(),
+            played: movetext.played,
+            illegal: movetext.illegal,
+        });
+        movetext.played = false;
+        ControlFlow::Continue(Skip(false))
+    }
+
+    fn end_variation(&mut self, movetext: &mut Self::Movetext) -> ControlFlow<Self::Output> {
+        // pgn-reader reports a `)` without a matching `(` too.
+        if let Some(frame) = movetext.stack.pop() {
+            self.emit(movetext);
+            restore(movetext, frame);
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn end_game(&mut self, mut movetext: Self::Movetext) -> Self::Output {
+        // Close variations left open at the end of the game.
+        while let Some(frame) = movetext.stack.pop() {
+            self.emit(&movetext);
+            restore(&mut movetext, frame);
+        }
+        self.emit(&movetext);
+    }
+}
+
+fn restore(movetext: &mut RepertoireMovetext, frame: Frame) {
+    movetext.nodes.truncate(frame.base_len);
+    movetext.nodes.extend(frame.replaced);
+    movetext.played = frame.played;
+    movetext.illegal = frame.illegal;
+}
+
+/// Remove `[%...]` commands such as `[%eval ...]`, keeping the text around them.
+fn strip_commands(comment: &str) -> String {
+    let mut text = String::with_capacity(comment.len());
+    let mut rest = comment;
+    while let Some(start) = rest.find("[%") {
+        text.push_str(&rest[..start]);
+        text.push(' ');
+        match rest[start..].find(']') {
+            Some(end) => rest = &rest[start + end + 1..],
+            None => return text,
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pgn_reader::Reader;
+    use std::io;
+
+    fn lines(pgn: &str) -> Vec<RepertoireLine> {
+        let mut reader = Reader::new(io::Cursor::new(pgn.as_bytes()));
+        let mut visitor = RepertoireVisitor::default();
+        let mut lines = Vec::new();
+        while let Ok(Some(())) = reader.read_game(&mut visitor) {
+            lines.append(&mut visitor.lines);
+        }
+        lines
+    }
+
+    fn movetexts(pgn: &str) -> Vec<(usize, String)> {
+        lines(pgn)
+            .into_iter()
+            .map(|line| (line.depth, line.movetext))
+            .collect()
+    }
+
+    #[test]
+    fn test_repertoire_lines_follow_every_variation() {
+        let pgn = "[Event \"Sicilian\"]\n\n\
+                   1. e4 c5 (1... e5 2. Nf3 (2. Bc4) 2... Nc6) 2. Nf3 d6 (2... Nc6 3. d4) 3. d4 *\n";
+        assert_eq!(
+            movetexts(pgn),
+            vec![
+                (3, "1. e4 e5 2. Bc4".to_string()),
+                (4, "1. e4 e5 2. Nf3 Nc6".to_string()),
+                (5, "1. e4 c5 2. Nf3 Nc6 3. d4".to_string()),
+                (5, "1. e4 c5 2. Nf3 d6 3. d4".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repertoire_lines_keep_orientation_evals_and_leaf_comments() {
+        let pgn = "[Orientation \"Black\"]\n\n\
+                   1. d4 { [%eval 0.2] } Nf6 2. c4 { Main line [%eval 0.3] } e6 { Solid } \
+                   (2... g6 { [%eval #-4] Dubious }) *\n";
+        let lines = lines(pgn);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].color, Some("black"));
+        assert_eq!(lines[0].movetext, "1. d4 Nf6 2. c4 g6");
+        assert_eq!(lines[0].eval, Some(Eval::Mate(-4)));
+        assert_eq!(lines[0].comment.as_deref(), Some("Dubious"));
+        assert_eq!(lines[1].movetext, "1. d4 Nf6 2. c4 e6");
+        assert_eq!(lines[1].eval, Some(Eval::Centipawns(30)));
+        assert_eq!(lines[1].comment.as_deref(), Some("Solid"));
+        assert_eq!(
+            lines[1].fen,
+            "rnbqkb1r/pppp1ppp/4pn2/8/2PP4/8/PP2PPPP/RNBQKBNR w KQkq - 0 3"
+        );
+    }
+
+    #[test]
+    fn test_repertoire_lines_skip_illegal_lines_and_fen_chapters() {
+        assert_eq!(
+            movetexts("1. e4 e5 (1... e4 2. d4) 2. Nf3 *\n"),
+            vec![(3, "1. e4 e5 2. Nf3".to_string())]
+        );
+        assert_eq!(
+            movetexts("[FEN \"8/8/8/8/8/8/8/K1k5 w - - 0 1\"]\n\n1. Ka2 *\n\n1. d4 *\n"),
+            vec![(1, "1. d4".to_string())]
+        );
+        // Empty variations and unterminated ones.
+        assert_eq!(
+            movetexts("1. e4 () e5 (1... c5 *\n"),
+            vec![(2, "1. e4 c5".to_string()), (2, "1. e4 e5".to_string())]
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use super::{
+    duckdb_impl::{file_system::DuckDbFileSystem, string::insert_varchar},
+    log,
+    puzzles::Eval,
+    reader::{
+        CompressionMode, PATH_PATTERN_PARAM_INDEX, apply_log_level_setting, open_input_stream,
+        resolve_compression_mode, resolve_file_discovery_options, resolve_input_paths,
+    },
+    repertoire::{RepertoireLine, RepertoireVisitor},
+    visitor::PgnInput,
+};
+use duckdb::{
+    core::{DataChunkHandle, Inserter, LogicalTypeHandle, LogicalTypeId},
+    vtab::{BindInfo, InitInfo, TableFunctionInfo, VTab},
+};
+use pgn_reader::Reader;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+#[repr(C)]
+pub struct ChessOpeningColorsStatsBindData {
+    paths: Vec<PathBuf>,
+    compression: CompressionMode,
+    file_system: Option<DuckDbFileSystem>,
+}
+
+#[repr(C)]
+pub struct ChessOpeningColorsStatsInitData {
+    state: Mutex<RepertoireScanState>,
+}
+
+/// `chess_opening_colors_stats(path_pattern)`: one row per leaf line of every repertoire
+/// chapter in the matched files.
+pub struct ChessOpeningColorsStatsVTab;
+
+struct RepertoireScanState {
+    next_path_idx: usize,
+    current: Option<RepertoireReader>,
+    /// Lines of the last chapter read that did not fit in the previous chunk.
+    pending: VecDeque<RepertoireRow>,
+}
+
+struct RepertoireReader {
+    reader: Reader<PgnInput>,
+    path_idx: usize,
+    next_game_index: u64,
+}
+
+struct RepertoireRow {
+    path_idx: usize,
+    game_index: u64,
+    line_index: u64,
+    line: RepertoireLine,
+}
+
+fn lock_scan_state(state: &Mutex<RepertoireScanState>) -> MutexGuard<'_, RepertoireScanState> {
+    match state.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            log::warn("Recovering poisoned chess_opening_colors_stats state mutex");
+            poisoned.into_inner()
+        }
+    }
+}
+
+/// Queue the lines of the next chapter, moving on to the next file at EOF; `false` when every
+/// file is done. Files are opened and read errors handled as in `pgn_tags`.
+fn read_next_chapter(
+    state: &mut RepertoireScanState,
+    bind_data: &ChessOpeningColorsStatsBindData,
+    visitor: &mut RepertoireVisitor,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    loop {
+        let Some(reader) = state.current.as_mut() else {
+            let Some(path) = bind_data.paths.get(state.next_path_idx) else {
+                return Ok(false);
+            };
+            let path_idx = state.next_path_idx;
+            state.next_path_idx += 1;
+            match open_input_stream(
+                path,
+                bind_data.compression,
+                0,
+                false,
+                bind_data.file_system.as_ref(),
+            ) {
+                Ok(input) => {
+                    state.current = Some(RepertoireReader {
+                        reader: Reader::new(input),
+                        path_idx,
+                        next_game_index: 0,
+                    });
+                }
+                Err(err_msg) if bind_data.paths.len() == 1 => return Err(err_msg.into()),
+                Err(err_msg) => log::warn(&err_msg),
+            }
+            continue;
+        };
+
+        match reader.reader.read_game(visitor) {
+            Ok(Some(())) => {
+                let game_index = reader.next_game_index;
+                reader.next_game_index += 1;
+                let path_idx = reader.path_idx;
+                state
+                    .pending
+                    .extend(
+                        visitor
+                            .lines
+                            .drain(..)
+                            .enumerate()
+                            .map(|(line_index, line)| RepertoireRow {
+                                path_idx,
+                                game_index,
+                                line_index: line_index as u64,
+                                line,
+                            }),
+                    );
+                return Ok(true);
+            }
+            Ok(None) => state.current = None,
+            Err(error) => {
+                log::warn(format!(
+                    "Read error: file='{}'; game_index={}; error={}",
+                    bind_data.paths[reader.path_idx].display(),
+                    reader.next_game_index,
+                    error
+                ));
+                state.current = None;
+            }
+        }
+    }
+}
+
+impl VTab for ChessOpeningColorsStatsVTab {
+    type InitData = ChessOpeningColorsStatsInitData;
+    type BindData = ChessOpeningColorsStatsBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        let pattern = bind.get_parameter(PATH_PATTERN_PARAM_INDEX).to_string();
+        apply_log_level_setting(bind)?;
+        let compression = resolve_compression_mode(bind)?;
+        let discovery = resolve_file_discovery_options(bind)?;
+        let (paths, file_system) = resolve_input_paths(bind, &pattern, discovery)?;
+
+        bind.add_result_column("file", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("game_index", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("line_index", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("color", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("depth", LogicalTypeHandle::from(LogicalTypeId::Integer));
+        bind.add_result_column("movetext", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("fen", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("eval_cp", LogicalTypeHandle::from(LogicalTypeId::Integer));
+        bind.add_result_column("eval_mate", LogicalTypeHandle::from(LogicalTypeId::Integer));
+        bind.add_result_column("comment", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+
+        Ok(ChessOpeningColorsStatsBindData {
+            paths,
+            compression,
+            file_system,
+        })
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(ChessOpeningColorsStatsInitData {
+            state: Mutex::new(RepertoireScanState {
+                next_path_idx: 0,
+                current: None,
+                pending: VecDeque::new(),
+            }),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let init_data = func.get_init_data();
+        let bind_data = func.get_bind_data();
+        let capacity = output.flat_vector(0).capacity();
+
+        let mut rows = Vec::with_capacity(capacity);
+        {
+            let mut state = lock_scan_state(&init_data.state);
+            let mut visitor = RepertoireVisitor::default();
+            while rows.len() < capacity {
+                match state.pending.pop_front() {
+                    Some(row) => rows.push(row),
+                    None if read_next_chapter(&mut state, bind_data, &mut visitor)? => {}
+                    None => break,
+                }
+            }
+        }
+
+        let file_vec = output.flat_vector(0);
+        let mut game_index_vec = output.flat_vector(1);
+        let mut line_index_vec = output.flat_vector(2);
+        let mut color_vec = output.flat_vector(3);
+        let mut depth_vec = output.flat_vector(4);
+        let movetext_vec = output.flat_vector(5);
+        let fen_vec = output.flat_vector(6);
+        let mut eval_cp_vec = output.flat_vector(7);
+        let mut eval_mate_vec = output.flat_vector(8);
+        let mut comment_vec = output.flat_vector(9);
+        for (row_idx, row) in rows.iter().enumerate() {
+            let line = &row.line;
+            file_vec.insert(
+                row_idx,
+                bind_data.paths[row.path_idx].to_string_lossy().as_ref(),
+            );
+            game_index_vec.as_mut_slice::<i64>()[row_idx] = row.game_index as i64;
+            line_index_vec.as_mut_slice::<i64>()[row_idx] = row.line_index as i64;
+            match line.color {
+                Some(color) => color_vec.insert(row_idx, color),
+                None => color_vec.set_null(row_idx),
+            }
+            depth_vec.as_mut_slice::<i32>()[row_idx] = line.depth as i32;
+            insert_varchar(&movetext_vec, row_idx, &line.movetext);
+            insert_varchar(&fen_vec, row_idx, &line.fen);
+            let (cp, mate) = match line.eval {
+                Some(Eval::Centipawns(cp)) => (Some(cp), None),
+                Some(Eval::Mate(moves)) => (None, Some(moves)),
+                None => (None, None),
+            };
+            for (vector, value) in [(&mut eval_cp_vec, cp), (&mut eval_mate_vec, mate)] {
+                match value {
+                    Some(value) => vector.as_mut_slice::<i32>()[row_idx] = value,
+                    None => vector.set_null(row_idx),
+                }
+            }
+            match &line.comment {
+                Some(comment) => insert_varchar(&comment_vec, row_idx, comment),
+                None => comment_vec.set_null(row_idx),
+            }
+        }
+        output.set_len(rows.len());
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![
+            LogicalTypeHandle::from(LogicalTypeId::Varchar), // path pattern (required)
+        ])
+    }
+
+    fn named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+        Some(vec![
+            (
+                "compression".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "follow_symlinks".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "skip_hidden".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+        ])
+    }
+}
@@ -0,0 +1,544 @@
+//! Replayed mainlines shared by the scalars that walk a game position by position.
+//!
+//! Queries often call several replay-based scalars on the same movetext in one row. Those
+//! scalars are registered with one shared [`ReplayCache`], so the second and later calls reuse
+//! the positions the first one replayed instead of parsing and replaying the game again.
+
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{VarcharInput, constant_varchar_arg, invoke_unary_nullable};
+use super::filter::parse_movetext_mainline;
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use shakmaty::{Chess, Move, Position, san::San};
+use std::collections::HashMap;
+#[cfg(feature = "extension")]
+use std::error::Error;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Games kept by a [`ReplayCache`] before the least recently used one is evicted. DuckDB runs
+/// each scalar over a whole vector of up to 2048 rows before the next one, so the cache holds
+/// two vectors' games for a second scalar on the same rows to find them.
+pub(crate) const REPLAY_CACHE_CAPACITY: usize = 4096;
+/// Smallest number of games per lock of a [`ReplayCache`]; larger caches are split into up to
+/// [`REPLAY_CACHE_MAX_SHARDS`] independently locked shards.
+const REPLAY_CACHE_SHARD_CAPACITY: usize = 256;
+const REPLAY_CACHE_MAX_SHARDS: usize = 16;
+/// Leading movetext bytes compared on a hash match.
+const MOVETEXT_PREFIX_LEN: usize = 16;
+
+/// Every position of a replayed mainline: `positions[0]` is the start position and
+/// `positions[n]` the position after ply `n`, reached by playing `moves[n - 1]`.
+#[derive(Debug)]
+pub struct ReplayedGame {
+    pub positions: Vec<Chess>,
+    pub moves: Vec<Move>,
+    /// Mainline plies from the first illegal move on, which were not replayed.
+    pub dropped_plies: usize,
+}
+
+impl ReplayedGame {
+    pub fn plies(&self) -> usize {
+        self.positions.len() - 1
+    }
+
+    pub fn final_position(&self) -> &Chess {
+        &self.positions[self.plies()]
+    }
+}
+
+/// Replay the mainline from the standard start position. Returns `None` when the movetext
+/// cannot be parsed or contains an illegal move.
+#[cfg(test)]
+pub(crate) fn replay_mainline(movetext: &str) -> Option<ReplayedGame> {
+    replay_mainline_best_effort(movetext).filter(|game| game.dropped_plies == 0)
+}
+
+/// Replay the mainline up to its first illegal move, counting the plies left out in
+/// `dropped_plies`. Returns `None` only when the movetext cannot be parsed.
+pub(crate) fn replay_mainline_best_effort(movetext: &str) -> Option<ReplayedGame> {
+    let parsed = parse_movetext_mainline(movetext);
+    if parsed.parse_error {
+        return None;
+    }
+
+    let mut positions = Vec::with_capacity(parsed.sans.len() + 1);
+    let mut moves = Vec::with_capacity(parsed.sans.len());
+    let mut pos = Chess::default();
+    for san in &parsed.sans {
+        let Some(m) = san
+            .parse::<San>()
+            .ok()
+            .and_then(|san| san.to_move(&pos).ok())
+        else {
+            break;
+        };
+        positions.push(pos.clone());
+        pos.play_unchecked(m);
+        moves.push(m);
+    }
+    positions.push(pos);
+    Some(ReplayedGame {
+        dropped_plies: parsed.sans.len() - moves.len(),
+        positions,
+        moves,
+    })
+}
+
+/// What replay-based scalars do with a game whose mainline contains an illegal move, chosen
+/// with their `on_illegal` argument.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnIllegal {
+    /// Return NULL.
+    #[default]
+    Null,
+    /// Work on the position before the first illegal move.
+    BestEffort,
+    /// Fail the query.
+    Error,
+}
+
+pub(crate) const SUPPORTED_ON_ILLEGAL_VALUES: &str = "'null', 'best_effort', 'error'";
+
+impl OnIllegal {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "null" => Ok(Self::Null),
+            "best_effort" => Ok(Self::BestEffort),
+            "error" => Ok(Self::Error),
+            _ => Err(format!(
+                "Invalid on_illegal '{value}'. Supported values: {SUPPORTED_ON_ILLEGAL_VALUES}."
+            )),
+        }
+    }
+}
+
+/// The `on_illegal` argument of a replay-based scalar, read from input column `idx`. A missing
+/// or NULL argument means [`OnIllegal::Null`].
+#[cfg(feature = "extension")]
+pub(crate) fn on_illegal_arg(
+    input: &DataChunkHandle,
+    idx: usize,
+) -> Result<OnIllegal, Box<dyn Error>> {
+    Ok(constant_varchar_arg(input, idx, "on_illegal")?
+        .map(|value| OnIllegal::parse(&value))
+        .transpose()?
+        .unwrap_or_default())
+}
+
+/// Signatures of a replay-based scalar taking `args`, without and with a trailing `VARCHAR`
+/// `on_illegal` argument.
+#[cfg(feature = "extension")]
+pub(crate) fn on_illegal_signatures(
+    args: impl Fn() -> Vec<LogicalTypeHandle>,
+    return_type: impl Fn() -> LogicalTypeHandle,
+) -> Vec<ScalarFunctionSignature> {
+    let mut with_on_illegal = args();
+    with_on_illegal.push(LogicalTypeHandle::from(LogicalTypeId::Varchar));
+    vec![
+        ScalarFunctionSignature::exact(args(), return_type()),
+        ScalarFunctionSignature::exact(with_on_illegal, return_type()),
+    ]
+}
+
+/// Least-recently-used cache of [`replay_mainline_best_effort`] results keyed by a hash of the
+/// movetext.
+///
+/// Clones share the same entries. Unreplayable movetext is cached too, so repeated calls on
+/// a broken game fail fast. Games are spread over shards by hash, each with its own lock, so
+/// parallel threads rarely wait on each other.
+#[derive(Clone)]
+pub struct ReplayCache(Arc<[Mutex<ReplayLru>]>);
+
+/// One shard: entries in slots linked from most to least recently used, so lookups, inserts
+/// and evictions are O(1).
+struct ReplayLru {
+    capacity: usize,
+    /// Slot of each cached key.
+    index: HashMap<u64, usize>,
+    slots: Vec<ReplayEntry>,
+    /// Most recently used slot, or `NIL` when empty.
+    head: usize,
+    /// Least recently used slot, the next to be evicted.
+    tail: usize,
+}
+
+const NIL: usize = usize::MAX;
+
+struct ReplayEntry {
+    key: u64,
+    /// Tells apart movetexts whose hashes collide without keeping a copy of the movetext.
+    check: MovetextCheck,
+    game: Option<Arc<ReplayedGame>>,
+    prev: usize,
+    next: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct MovetextCheck {
+    len: usize,
+    prefix: [u8; MOVETEXT_PREFIX_LEN],
+}
+
+impl MovetextCheck {
+    fn of(movetext: &str) -> Self {
+        let mut prefix = [0; MOVETEXT_PREFIX_LEN];
+        let bytes = &movetext.as_bytes()[..movetext.len().min(MOVETEXT_PREFIX_LEN)];
+        prefix[..bytes.len()].copy_from_slice(bytes);
+        Self {
+            len: movetext.len(),
+            prefix,
+        }
+    }
+}
+
+impl Default for ReplayCache {
+    fn default() -> Self {
+        Self::with_capacity(REPLAY_CACHE_CAPACITY)
+    }
+}
+
+impl ReplayCache {
+    pub fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let shards = (capacity / REPLAY_CACHE_SHARD_CAPACITY).clamp(1, REPLAY_CACHE_MAX_SHARDS);
+        let shard_capacity = capacity.div_ceil(shards);
+        Self(
+            (0..shards)
+                .map(|_| Mutex::new(ReplayLru::with_capacity(shard_capacity)))
+                .collect(),
+        )
+    }
+
+    /// The replayed mainline of `movetext` as `on_illegal` asks for it. `BestEffort` keeps the
+    /// positions before the first illegal move; `Error` fails on illegal or unparseable
+    /// movetext instead of returning `None`.
+    pub fn replay_with(
+        &self,
+        movetext: &str,
+        on_illegal: OnIllegal,
+    ) -> Result<Option<Arc<ReplayedGame>>, String> {
+        let game = self.replay_best_effort(movetext);
+        match (on_illegal, game) {
+            (OnIllegal::Error, None) => Err("movetext could not be parsed".to_string()),
+            (OnIllegal::Error, Some(game)) if game.dropped_plies > 0 => Err(format!(
+                "illegal move at ply {} of {}",
+                game.plies() + 1,
+                game.plies() + game.dropped_plies
+            )),
+            (OnIllegal::Null, game) => Ok(game.filter(|game| game.dropped_plies == 0)),
+            (_, game) => Ok(game),
+        }
+    }
+
+    /// The mainline of `movetext` replayed up to its first illegal move, replaying it only on
+    /// a cache miss. The lock is not held while replaying, so concurrent misses on the same
+    /// game may both replay it.
+    pub fn replay_best_effort(&self, movetext: &str) -> Option<Arc<ReplayedGame>> {
+        let key = movetext_key(movetext);
+        let check = MovetextCheck::of(movetext);
+        if let Some(game) = self.lock(key).get(key, check) {
+            return game;
+        }
+
+        let game = replay_mainline_best_effort(movetext).map(Arc::new);
+        self.lock(key).insert(key, check, game.clone());
+        game
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        (0..self.0.len())
+            .map(|shard| self.lock((shard as u64) << 32).index.len())
+            .sum()
+    }
+
+    /// The shard holding `key`.
+    fn lock(&self, key: u64) -> MutexGuard<'_, ReplayLru> {
+        // The low bits pick the hash map bucket inside the shard, so shards use the high ones.
+        let shard = (key >> 32) as usize % self.0.len();
+        self.0[shard]
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ReplayLru {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity,
+            index: HashMap::with_capacity(capacity),
+            slots: Vec::with_capacity(capacity),
+            head: NIL,
+            tail: NIL,
+        }
+    }
+
+    fn get(&mut self, key: u64, check: MovetextCheck) -> Option<Option<Arc<ReplayedGame>>> {
+        let slot = *self.index.get(&key)?;
+        if self.slots[slot].check != check {
+            return None;
+        }
+        self.move_to_front(slot);
+        Some(self.slots[slot].game.clone())
+    }
+
+    fn insert(&mut self, key: u64, check: MovetextCheck, game: Option<Arc<ReplayedGame>>) {
+        let slot = if let Some(&slot) = self.index.get(&key) {
+            self.unlink(slot);
+            slot
+        } else if self.slots.len() < self.capacity {
+            self.slots.push(ReplayEntry {
+                key,
+                check,
+                game: None,
+                prev: NIL,
+                next: NIL,
+            });
+            self.slots.len() - 1
+        } else {
+            // Reuse the least recently used slot.
+            let slot = self.tail;
+            self.unlink(slot);
+            self.index.remove(&self.slots[slot].key);
+            slot
+        };
+        let entry = &mut self.slots[slot];
+        entry.key = key;
+        entry.check = check;
+        entry.game = game;
+        self.index.insert(key, slot);
+        self.push_front(slot);
+    }
+
+    fn move_to_front(&mut self, slot: usize) {
+        if self.head != slot {
+            self.unlink(slot);
+            self.push_front(slot);
+        }
+    }
+
+    fn unlink(&mut self, slot: usize) {
+        let ReplayEntry { prev, next, .. } = self.slots[slot];
+        match prev {
+            NIL => self.head = next,
+            prev => self.slots[prev].next = next,
+        }
+        match next {
+            NIL => self.tail = prev,
+            next => self.slots[next].prev = prev,
+        }
+    }
+
+    fn push_front(&mut self, slot: usize) {
+        self.slots[slot].prev = NIL;
+        self.slots[slot].next = self.head;
+        match self.head {
+            NIL => self.tail = slot,
+            head => self.slots[head].prev = slot,
+        }
+        self.head = slot;
+    }
+}
+
+fn movetext_key(movetext: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    movetext.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Reports how many mainline plies the `best_effort` mode of the replay scalars leaves out.
+#[cfg(feature = "extension")]
+pub struct ChessReplayDroppedPliesScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessReplayDroppedPliesScalar {
+    type State = ReplayCache;
+
+    unsafe fn invoke(
+        cache: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_unary_nullable::<VarcharInput, _, _>(input, output, |movetext| {
+            Ok(cache
+                .replay_best_effort(movetext)
+                .map(|game| game.dropped_plies as i64))
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            LogicalTypeHandle::from(LogicalTypeId::Bigint),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay(cache: &ReplayCache, movetext: &str) -> Option<Arc<ReplayedGame>> {
+        cache.replay_with(movetext, OnIllegal::Null).unwrap()
+    }
+
+    #[test]
+    fn test_replay_mainline_keeps_every_position() {
+        let game = replay_mainline("1. e4 { best by test } e5 (1... c5) 2. Nf3 1-0").unwrap();
+        assert_eq!(game.plies(), 3);
+        assert_eq!(game.positions[0], Chess::default());
+        assert_eq!(game.moves.len(), 3);
+        assert_eq!(game.final_position().fullmoves().get(), 2);
+
+        assert_eq!(replay_mainline("").unwrap().plies(), 0);
+        assert!(replay_mainline("1. e4 e4").is_none());
+    }
+
+    #[test]
+    fn test_replay_mainline_best_effort_stops_at_illegal_move() {
+        let game = replay_mainline_best_effort("1. e4 e5 2. Ke3 Nc6 3. Nf3").unwrap();
+        assert_eq!((game.plies(), game.dropped_plies), (2, 3));
+        assert_eq!(
+            replay_mainline_best_effort("1. e4 e5")
+                .unwrap()
+                .dropped_plies,
+            0
+        );
+        assert!(replay_mainline_best_effort("1. e4 {").is_none());
+    }
+
+    #[test]
+    fn test_replay_cache_on_illegal_modes() {
+        let cache = ReplayCache::default();
+        let illegal = "1. e4 e5 2. Ke3 Nc6";
+        assert!(
+            cache
+                .replay_with(illegal, OnIllegal::Null)
+                .unwrap()
+                .is_none()
+        );
+        let game = cache
+            .replay_with(illegal, OnIllegal::BestEffort)
+            .unwrap()
+            .unwrap();
+        assert_eq!((game.plies(), game.dropped_plies), (2, 2));
+        assert_eq!(
+            cache.replay_with(illegal, OnIllegal::Error).unwrap_err(),
+            "illegal move at ply 3 of 4"
+        );
+        assert!(
+            cache
+                .replay_with("1. e4 e5", OnIllegal::Error)
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_on_illegal_parse() {
+        assert_eq!(OnIllegal::parse("NULL"), Ok(OnIllegal::Null));
+        assert_eq!(OnIllegal::parse(" best_effort "), Ok(OnIllegal::BestEffort));
+        assert_eq!(OnIllegal::parse("error"), Ok(OnIllegal::Error));
+        assert!(
+            OnIllegal::parse("skip")
+                .unwrap_err()
+                .contains("Invalid on_illegal 'skip'")
+        );
+    }
+
+    #[test]
+    fn test_replay_cache_reuses_replayed_games() {
+        let cache = ReplayCache::default();
+        let first = replay(&cache, "1. e4 e5").unwrap();
+        let second = replay(&cache.clone(), "1. e4 e5").unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        assert!(replay(&cache, "1. e4 e4").is_none());
+        assert!(replay(&cache, "1. e4 e4").is_none());
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_replay_cache_evicts_least_recently_used() {
+        let cache = ReplayCache::with_capacity(2);
+        let e4 = replay(&cache, "1. e4").unwrap();
+        let d4 = replay(&cache, "1. d4").unwrap();
+        // Touch 1. e4 so 1. d4 is the least recently used entry.
+        assert!(Arc::ptr_eq(&e4, &replay(&cache, "1. e4").unwrap()));
+        replay(&cache, "1. c4").unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert!(Arc::ptr_eq(&e4, &replay(&cache, "1. e4").unwrap()));
+        assert!(!Arc::ptr_eq(&d4, &replay(&cache, "1. d4").unwrap()));
+    }
+
+    #[test]
+    fn test_replay_cache_checks_movetext_on_hash_collision() {
+        let cache = ReplayCache::default();
+        let e4 = replay(&cache, "1. e4").unwrap();
+        // Plant a different game under the key of 1. d4.
+        let key = movetext_key("1. d4");
+        cache
+            .lock(key)
+            .insert(key, MovetextCheck::of("1. e4"), Some(Arc::clone(&e4)));
+        let d4 = replay(&cache, "1. d4").unwrap();
+        assert!(!Arc::ptr_eq(&e4, &d4));
+        assert_eq!(d4.plies(), 1);
+    }
+
+    #[test]
+    fn test_replay_cache_shards_keep_total_capacity() {
+        let cache = ReplayCache::default();
+        assert_eq!(cache.0.len(), REPLAY_CACHE_MAX_SHARDS);
+        assert_eq!(
+            ReplayCache::with_capacity(REPLAY_CACHE_SHARD_CAPACITY)
+                .0
+                .len(),
+            1
+        );
+
+        let games: Vec<String> = (1..=8)
+            .flat_map(|rank| {
+                ["a", "b", "c", "d", "e", "f", "g", "h"]
+                    .map(move |file| format!("1. {file}{} {{ {rank} }}", 3 + rank % 2))
+            })
+            .collect();
+        for game in &games {
+            replay(&cache, game).unwrap();
+        }
+        assert_eq!(cache.len(), games.len());
+        for game in &games {
+            assert!(Arc::ptr_eq(
+                &replay(&cache, game).unwrap(),
+                &replay(&cache, game).unwrap()
+            ));
+        }
+    }
+
+    #[test]
+    fn test_replay_lru_evicts_in_use_order() {
+        let mut lru = ReplayLru::with_capacity(3);
+        let check = MovetextCheck::of("");
+        for key in [1, 2, 3] {
+            lru.insert(key, check, None);
+        }
+        // Touch 1, re-insert 2, then add 4 and 5: 3 and 1 are evicted in that order.
+        assert!(lru.get(1, check).is_some());
+        lru.insert(2, check, None);
+        lru.insert(4, check, None);
+        assert!(lru.get(3, check).is_none());
+        lru.insert(5, check, None);
+        assert!(lru.get(1, check).is_none());
+        for key in [2, 4, 5] {
+            assert!(lru.get(key, check).is_some(), "{key}");
+        }
+        assert_eq!(lru.index.len(), 3);
+        assert_eq!(lru.slots.len(), 3);
+    }
+}
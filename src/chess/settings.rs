@@ -0,0 +1,138 @@
+//! Extension-level settings registered through DuckDB's config-option API.
+//!
+//! Each option is session-scoped: `SET duckdb_chess_* = ...` tunes later calls on the same
+//! connection without passing named parameters. `read_pgn` reads them at bind time through the
+//! client context; SQL macros read them with `current_setting(...)`.
+
+use super::log;
+use libduckdb_sys::{
+    DUCKDB_TYPE_DUCKDB_TYPE_BIGINT, DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN,
+    DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR, DuckDBSuccess, duckdb_config_option_set_default_value,
+    duckdb_config_option_set_description, duckdb_config_option_set_name,
+    duckdb_config_option_set_type, duckdb_connect, duckdb_connection, duckdb_create_bool,
+    duckdb_create_config_option, duckdb_create_int64, duckdb_create_logical_type,
+    duckdb_create_varchar, duckdb_database, duckdb_destroy_config_option,
+    duckdb_destroy_logical_type, duckdb_destroy_value, duckdb_disconnect,
+    duckdb_register_config_option, duckdb_type, duckdb_value,
+};
+use std::error::Error;
+use std::ffi::CString;
+use std::ptr;
+
+pub const LOG_LEVEL_SETTING: &str = "duckdb_chess_log_level";
+pub const DEFAULT_COMPRESSION_SETTING: &str = "duckdb_chess_default_compression";
+pub const STRICT_SETTING: &str = "duckdb_chess_strict";
+pub const OPENING_BOOK_SETTING: &str = "duckdb_chess_opening_book";
+pub const MAX_OPEN_FILES_SETTING: &str = "duckdb_chess_max_open_files";
+
+/// Idle `read_pgn` readers kept open per scan before the least recently used are closed.
+pub const DEFAULT_MAX_OPEN_FILES: usize = 64;
+
+enum SettingDefault {
+    Varchar(String),
+    Bool(bool),
+    BigInt(i64),
+}
+
+struct Setting {
+    name: &'static str,
+    description: &'static str,
+    default: SettingDefault,
+}
+
+fn settings() -> [Setting; 5] {
+    [
+        Setting {
+            name: LOG_LEVEL_SETTING,
+            description: "Minimum level of duckdb-chess warnings written to stderr ('error' or 'warn'); defaults to CHESS_LOG",
+            default: SettingDefault::Varchar(log::level_name().to_string()),
+        },
+        Setting {
+            name: DEFAULT_COMPRESSION_SETTING,
+            description: "Compression used by read_pgn when its compression parameter is omitted or NULL",
+            default: SettingDefault::Varchar("auto".to_string()),
+        },
+        Setting {
+            name: STRICT_SETTING,
+            description: "Default strict flag of the one-argument chess_timecontrol_normalize",
+            default: SettingDefault::Bool(false),
+        },
+        Setting {
+            name: OPENING_BOOK_SETTING,
+            description: "Path of an eco/name/pgn TSV opening book used by chess_opening_name and chess_eco_validate; empty selects the bundled book",
+            default: SettingDefault::Varchar(String::new()),
+        },
+        Setting {
+            name: MAX_OPEN_FILES_SETTING,
+            description: "Idle files a read_pgn scan keeps open; beyond it the least recently used are closed and reopened at their last offset",
+            default: SettingDefault::BigInt(DEFAULT_MAX_OPEN_FILES as i64),
+        },
+    ]
+}
+
+/// Register every `duckdb_chess_*` option on `db`.
+///
+/// # Safety
+/// `db` must be the live database handle passed to the extension entrypoint.
+pub unsafe fn register_settings(db: duckdb_database) -> Result<(), Box<dyn Error>> {
+    let mut con: duckdb_connection = ptr::null_mut();
+    // SAFETY: `db` is valid per the caller contract; the connection is closed below.
+    if unsafe { duckdb_connect(db, &mut con) } != DuckDBSuccess {
+        return Err("Failed to open a connection to register duckdb-chess settings".into());
+    }
+
+    let result = settings()
+        .iter()
+        // SAFETY: `con` was opened above and stays open until after the loop.
+        .try_for_each(|setting| unsafe { register_setting(con, setting) });
+
+    // SAFETY: `con` is a connection opened above and is closed exactly once.
+    unsafe { duckdb_disconnect(&mut con) };
+    result
+}
+
+unsafe fn register_setting(
+    con: duckdb_connection,
+    setting: &Setting,
+) -> Result<(), Box<dyn Error>> {
+    let name = CString::new(setting.name)?;
+    let description = CString::new(setting.description)?;
+    let (type_id, mut default): (duckdb_type, duckdb_value) = match &setting.default {
+        SettingDefault::Varchar(value) => {
+            let value = CString::new(value.as_str())?;
+            // SAFETY: `value` is a valid NUL-terminated string; DuckDB copies it.
+            (DUCKDB_TYPE_DUCKDB_TYPE_VARCHAR, unsafe {
+                duckdb_create_varchar(value.as_ptr())
+            })
+        }
+        // SAFETY: Creating a scalar value has no preconditions.
+        SettingDefault::Bool(value) => (DUCKDB_TYPE_DUCKDB_TYPE_BOOLEAN, unsafe {
+            duckdb_create_bool(*value)
+        }),
+        // SAFETY: Creating a scalar value has no preconditions.
+        SettingDefault::BigInt(value) => (DUCKDB_TYPE_DUCKDB_TYPE_BIGINT, unsafe {
+            duckdb_create_int64(*value)
+        }),
+    };
+
+    // SAFETY: Every handle created here is destroyed exactly once before returning; DuckDB
+    // copies the name, description, type, and default into the option.
+    let state = unsafe {
+        let mut option = duckdb_create_config_option();
+        let mut logical_type = duckdb_create_logical_type(type_id);
+        duckdb_config_option_set_name(option, name.as_ptr());
+        duckdb_config_option_set_type(option, logical_type);
+        duckdb_config_option_set_default_value(option, default);
+        duckdb_config_option_set_description(option, description.as_ptr());
+        let state = duckdb_register_config_option(con, option);
+        duckdb_destroy_logical_type(&mut logical_type);
+        duckdb_destroy_value(&mut default);
+        duckdb_destroy_config_option(&mut option);
+        state
+    };
+
+    if state != DuckDBSuccess {
+        return Err(format!("Failed to register setting '{}'", setting.name).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,211 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::{
+    VarcharInput, invoke_unary_nullable, invoke_unary_varchar_to_f64_i32_struct_nullable,
+};
+use super::replay::ReplayedGame;
+#[cfg(feature = "extension")]
+use super::replay::{ReplayCache, on_illegal_arg, on_illegal_signatures};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use shakmaty::{CastlingSide, Color, Position, Role};
+#[cfg(feature = "extension")]
+use std::error::Error;
+
+/// Output fields of `chess_moves_style`, in struct order: the shares first, then the castling
+/// plies.
+const SHARE_FIELDS: [&str; 5] = [
+    "capture_share",
+    "check_share",
+    "pawn_move_share",
+    "piece_move_share",
+    "queen_move_share",
+];
+const CASTLE_FIELDS: [&str; 2] = ["white_castle_ply", "black_castle_ply"];
+
+/// Cheap style features of a replayed mainline: the share of plies that capture, give check,
+/// move a pawn, move another piece (castling included) and move a queen, then the 1-based ply
+/// at which White and Black castled. `None` for a game without moves.
+pub fn moves_style(game: &ReplayedGame) -> Option<([f64; 5], [Option<i32>; 2])> {
+    let plies = game.plies();
+    if plies == 0 {
+        return None;
+    }
+
+    let mut counts = [0usize; 5];
+    let mut castle_plies = [None; 2];
+    for (ply_idx, m) in game.moves.iter().enumerate() {
+        let flags = [
+            m.is_capture(),
+            game.positions[ply_idx + 1].is_check(),
+            m.role() == Role::Pawn,
+            m.role() != Role::Pawn,
+            m.role() == Role::Queen,
+        ];
+        for (count, flag) in counts.iter_mut().zip(flags) {
+            *count += usize::from(flag);
+        }
+
+        if m.is_castle() {
+            let side = match game.positions[ply_idx].turn() {
+                Color::White => 0,
+                Color::Black => 1,
+            };
+            castle_plies[side].get_or_insert(i32::try_from(ply_idx + 1).ok()?);
+        }
+    }
+
+    Some((
+        counts.map(|count| count as f64 / plies as f64),
+        castle_plies,
+    ))
+}
+
+/// The side White and Black castled to first, as `O-O vs O-O-O`, with `none` for a side that
+/// never castled. Used to group results by castling matchup.
+pub fn castle_sides(game: &ReplayedGame) -> String {
+    let mut sides = [None; 2];
+    for (ply_idx, m) in game.moves.iter().enumerate() {
+        if let Some(castling_side) = m.castling_side() {
+            let side = match game.positions[ply_idx].turn() {
+                Color::White => 0,
+                Color::Black => 1,
+            };
+            sides[side].get_or_insert(castling_side);
+        }
+    }
+    let [white, black] = sides.map(|side| match side {
+        Some(CastlingSide::KingSide) => "O-O",
+        Some(CastlingSide::QueenSide) => "O-O-O",
+        None => "none",
+    });
+    format!("{white} vs {black}")
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessMovesStyleScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessMovesStyleScalar {
+    type State = ReplayCache;
+
+    unsafe fn invoke(
+        cache: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let on_illegal = on_illegal_arg(input, 1)?;
+        // The struct helper cannot fail a row, so an `error` mode failure is raised afterwards.
+        let mut failure = None;
+        invoke_unary_varchar_to_f64_i32_struct_nullable(input, output, |movetext| {
+            match cache.replay_with(movetext, on_illegal) {
+                Ok(game) => moves_style(&*game?),
+                Err(e) => {
+                    failure.get_or_insert(e);
+                    None
+                }
+            }
+        })?;
+        match failure {
+            Some(e) => Err(format!("chess_moves_style: {e}").into()),
+            None => Ok(()),
+        }
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let fields: Vec<_> = SHARE_FIELDS
+            .iter()
+            .map(|name| (*name, LogicalTypeHandle::from(LogicalTypeId::Double)))
+            .chain(
+                CASTLE_FIELDS
+                    .iter()
+                    .map(|name| (*name, LogicalTypeHandle::from(LogicalTypeId::Integer))),
+            )
+            .collect();
+        on_illegal_signatures(
+            || vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            || LogicalTypeHandle::struct_type(&fields),
+        )
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessCastleSidesScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessCastleSidesScalar {
+    type State = ReplayCache;
+
+    unsafe fn invoke(
+        cache: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let on_illegal = on_illegal_arg(input, 1)?;
+        invoke_unary_nullable::<VarcharInput, _, _>(input, output, |movetext| {
+            Ok(cache
+                .replay_with(movetext, on_illegal)
+                .map_err(|e| format!("chess_castle_side_results: {e}"))?
+                .map(|game| castle_sides(&game)))
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        on_illegal_signatures(
+            || vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            || LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::replay::replay_mainline;
+
+    fn style(movetext: &str) -> Option<([f64; 5], [Option<i32>; 2])> {
+        moves_style(&replay_mainline(movetext)?)
+    }
+
+    #[test]
+    fn test_moves_style_counts_share_of_plies() {
+        // Scholar's mate: 7 plies, one capture that is also mate.
+        let (shares, castles) = style("1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7#").unwrap();
+        assert_eq!(
+            shares,
+            [1.0 / 7.0, 1.0 / 7.0, 2.0 / 7.0, 5.0 / 7.0, 2.0 / 7.0]
+        );
+        assert_eq!(castles, [None, None]);
+    }
+
+    #[test]
+    fn test_moves_style_records_castling_ply_per_side() {
+        let (shares, castles) =
+            style("1. e4 e5 2. Nf3 Nf6 3. Bc4 Bc5 4. O-O d6 5. d3 O-O").unwrap();
+        assert_eq!(castles, [Some(7), Some(10)]);
+        assert_eq!(shares[2] + shares[3], 1.0);
+    }
+
+    #[test]
+    fn test_moves_style_none_without_moves_or_when_unreplayable() {
+        assert_eq!(style(""), None);
+        assert_eq!(style("1. e4 e4"), None);
+    }
+
+    #[test]
+    fn test_castle_sides_keeps_first_castle_per_side() {
+        let sides = |movetext| castle_sides(&replay_mainline(movetext).unwrap());
+        assert_eq!(
+            sides("1. e4 e5 2. Nf3 Nf6 3. Bc4 Bc5 4. O-O d6 5. d3 O-O"),
+            "O-O vs O-O"
+        );
+        assert_eq!(
+            sides("1. e4 d5 2. exd5 Qxd5 3. Nc3 Qa5 4. d4 Bf5 5. Bd2 Nc6 6. Nf3 O-O-O"),
+            "none vs O-O-O"
+        );
+        assert_eq!(sides(""), "none vs none");
+    }
+}
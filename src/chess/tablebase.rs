@@ -0,0 +1,199 @@
+use super::duckdb_impl::scalar::invoke_binary_varchar_varchar_to_i32_nullable;
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+use shakmaty::{CastlingMode, Chess, fen::Fen};
+use shakmaty_syzygy::{SyzygyError, Tablebase};
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// Tablebases keyed by the `tb_path` they were opened from. Table files are opened lazily and
+/// stay open, so probing many positions against the same path only scans it once.
+static TABLEBASES: LazyLock<Mutex<HashMap<String, Arc<Tablebase<Chess>>>>> =
+    LazyLock::new(Default::default);
+
+/// Open (or reuse) the tablebase for `tb_path`, a list of directories separated like `PATH`
+/// (`:` on Unix, `;` on Windows), the same convention as engines' `SyzygyPath`.
+fn tablebase(function: &str, tb_path: &str) -> Result<Arc<Tablebase<Chess>>, String> {
+    let mut cache = TABLEBASES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(tables) = cache.get(tb_path) {
+        return Ok(Arc::clone(tables));
+    }
+
+    let mut tables = Tablebase::new();
+    for dir in env::split_paths(tb_path) {
+        tables.add_directory(&dir).map_err(|err| {
+            format!(
+                "{function}: cannot read tablebase directory '{}': {err}",
+                dir.display()
+            )
+        })?;
+    }
+    let tables = Arc::new(tables);
+    cache.insert(tb_path.to_string(), Arc::clone(&tables));
+    Ok(tables)
+}
+
+/// Positions the tables cannot answer are `None`; a table that fails to read is an error.
+fn probe<T>(
+    function: &str,
+    fen: &str,
+    tb_path: &str,
+    probe: impl FnOnce(&Tablebase<Chess>, &Chess) -> Result<T, SyzygyError>,
+) -> Result<Option<T>, String> {
+    let Some(pos) = fen
+        .trim()
+        .parse::<Fen>()
+        .ok()
+        .and_then(|fen| fen.into_position::<Chess>(CastlingMode::Standard).ok())
+    else {
+        return Ok(None);
+    };
+
+    let tables = tablebase(function, tb_path)?;
+    match probe(&tables, &pos) {
+        Ok(value) => Ok(Some(value)),
+        Err(
+            SyzygyError::Castling | SyzygyError::TooManyPieces | SyzygyError::MissingTable { .. },
+        ) => Ok(None),
+        Err(err) => Err(format!("{function}: {err}")),
+    }
+}
+
+/// Win/draw/loss for the side to move: `2` win, `1` win that the 50-move rule can spoil,
+/// `0` draw, `-1` loss saved by the 50-move rule, `-2` loss.
+///
+/// The halfmove clock is ignored, as if the position was just reached by a capture or pawn
+/// move. Only WDL (`.rtbw`) tables are needed.
+pub fn tablebase_wdl(fen: &str, tb_path: &str) -> Result<Option<i32>, String> {
+    probe("chess_tablebase_wdl", fen, tb_path, |tables, pos| {
+        tables.probe_wdl_after_zeroing(pos).map(|wdl| wdl as i32)
+    })
+}
+
+/// Distance to zeroing (plies until a capture or pawn move under optimal play) for the side to
+/// move; positive when winning, negative when losing, `0` for draws. Values may be rounded
+/// by one ply, as stored in the tables. Needs both WDL and DTZ (`.rtbz`) tables.
+pub fn tablebase_dtz(fen: &str, tb_path: &str) -> Result<Option<i32>, String> {
+    probe("chess_tablebase_dtz", fen, tb_path, |tables, pos| {
+        tables.probe_dtz(pos).map(|dtz| dtz.ignore_rounding().0)
+    })
+}
+
+// Spec: move-analysis - Tablebase Probing
+pub struct ChessTablebaseWdlScalar;
+
+impl VScalar for ChessTablebaseWdlScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_binary_varchar_varchar_to_i32_nullable(input, output, |fen, tb_path| {
+            Ok(tablebase_wdl(fen, tb_path)?)
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Integer),
+        )]
+    }
+}
+
+pub struct ChessTablebaseDtzScalar;
+
+impl VScalar for ChessTablebaseDtzScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_binary_varchar_varchar_to_i32_nullable(input, output, |fen, tb_path| {
+            Ok(tablebase_dtz(fen, tb_path)?)
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Integer),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_tb_dir() -> String {
+        let dir = env::temp_dir().join(format!("duckdb-chess-syzygy-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_tablebase_bare_kings_are_drawn_without_tables() {
+        let tb_path = empty_tb_dir();
+        assert_eq!(
+            tablebase_wdl("8/8/8/4k3/8/8/8/4K3 w - - 0 1", &tb_path),
+            Ok(Some(0))
+        );
+        assert_eq!(
+            tablebase_dtz("8/8/8/4k3/8/8/8/4K3 b - - 0 1", &tb_path),
+            Ok(Some(0))
+        );
+    }
+
+    #[test]
+    fn test_tablebase_unanswerable_positions_are_none() {
+        let tb_path = empty_tb_dir();
+        // No KQvK table in the directory.
+        assert_eq!(
+            tablebase_wdl("8/8/8/4k3/8/8/8/3QK3 w - - 0 1", &tb_path),
+            Ok(None)
+        );
+        // Too many pieces and castling rights are never in Syzygy tables.
+        assert_eq!(
+            tablebase_wdl(
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+                &tb_path
+            ),
+            Ok(None)
+        );
+        assert_eq!(
+            tablebase_dtz("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1", &tb_path),
+            Ok(None)
+        );
+        assert_eq!(tablebase_wdl("not a fen", &tb_path), Ok(None));
+    }
+
+    #[test]
+    fn test_tablebase_unreadable_path_is_an_error() {
+        let err = tablebase_wdl(
+            "8/8/8/4k3/8/8/8/4K3 w - - 0 1",
+            "test/pgn_files/definitely-missing-syzygy",
+        )
+        .unwrap_err();
+        assert!(err.starts_with(
+            "chess_tablebase_wdl: cannot read tablebase directory 'test/pgn_files/definitely-missing-syzygy'"
+        ));
+    }
+}
@@ -0,0 +1,231 @@
+use super::{
+    boundary::GameBoundaryScanner,
+    duckdb_impl::{file_system::DuckDbFileSystem, string::insert_varchar},
+    headers::HeadersVisitor,
+    log,
+    reader::{
+        CompressionMode, PATH_PATTERN_PARAM_INDEX, apply_log_level_setting, open_input_stream,
+        resolve_compression_mode, resolve_file_discovery_options, resolve_input_paths,
+    },
+    visitor::PgnInput,
+};
+use duckdb::{
+    core::{DataChunkHandle, Inserter, LogicalTypeHandle, LogicalTypeId},
+    vtab::{BindInfo, InitInfo, TableFunctionInfo, VTab},
+};
+use pgn_reader::Reader;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+#[repr(C)]
+pub struct PgnTagsBindData {
+    paths: Vec<PathBuf>,
+    compression: CompressionMode,
+    file_system: Option<DuckDbFileSystem>,
+}
+
+#[repr(C)]
+pub struct PgnTagsInitData {
+    state: Mutex<TagsScanState>,
+}
+
+pub struct PgnTagsVTab;
+
+struct TagsScanState {
+    next_path_idx: usize,
+    current: Option<TagsReader>,
+    /// Tags of the last game read that did not fit in the previous chunk.
+    pending: VecDeque<TagRow>,
+}
+
+struct TagsReader {
+    reader: Reader<PgnInput>,
+    path_idx: usize,
+    /// 1-based, like the game positions `read_pgn` derives `game_id` from.
+    next_game_index: u64,
+}
+
+struct TagRow {
+    path_idx: usize,
+    game_index: u64,
+    tag: String,
+    value: String,
+}
+
+fn lock_scan_state(state: &Mutex<TagsScanState>) -> MutexGuard<'_, TagsScanState> {
+    match state.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            log::warn("Recovering poisoned pgn_tags state mutex");
+            poisoned.into_inner()
+        }
+    }
+}
+
+/// Queue the tags of the next game, moving on to the next file at EOF; `false` when every file
+/// is done.
+///
+/// Unreadable files are skipped with a warning when several paths were matched; a single
+/// explicit path fails hard, like `read_pgn`. I/O errors mid-file end that file with a warning.
+fn read_next_game(
+    state: &mut TagsScanState,
+    bind_data: &PgnTagsBindData,
+    visitor: &mut HeadersVisitor,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    loop {
+        let Some(reader) = state.current.as_mut() else {
+            let Some(path) = bind_data.paths.get(state.next_path_idx) else {
+                return Ok(false);
+            };
+            let path_idx = state.next_path_idx;
+            state.next_path_idx += 1;
+            match open_input_stream(
+                path,
+                bind_data.compression,
+                0,
+                false,
+                bind_data.file_system.as_ref(),
+            ) {
+                Ok(input) => {
+                    // The scanner splits games exactly like `read_pgn`, including bare CR line
+                    // endings and games that follow a result without a blank line.
+                    let scanner: PgnInput = Box::new(GameBoundaryScanner::new(input, 0, false));
+                    state.current = Some(TagsReader {
+                        reader: Reader::new(scanner),
+                        path_idx,
+                        next_game_index: 1,
+                    });
+                }
+                Err(err_msg) if bind_data.paths.len() == 1 => return Err(err_msg.into()),
+                Err(err_msg) => log::warn(&err_msg),
+            }
+            continue;
+        };
+
+        match reader.reader.read_game(visitor) {
+            Ok(Some(())) => {
+                let game_index = reader.next_game_index;
+                reader.next_game_index += 1;
+                let path_idx = reader.path_idx;
+                state
+                    .pending
+                    .extend(visitor.tags.drain(..).map(|(tag, value)| TagRow {
+                        path_idx,
+                        game_index,
+                        tag,
+                        value,
+                    }));
+                return Ok(true);
+            }
+            Ok(None) => state.current = None,
+            Err(error) => {
+                log::warn(format!(
+                    "Read error: file='{}'; game_index={}; error={}",
+                    bind_data.paths[reader.path_idx].display(),
+                    reader.next_game_index,
+                    error
+                ));
+                state.current = None;
+            }
+        }
+    }
+}
+
+impl VTab for PgnTagsVTab {
+    type InitData = PgnTagsInitData;
+    type BindData = PgnTagsBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        let pattern = bind.get_parameter(PATH_PATTERN_PARAM_INDEX).to_string();
+        apply_log_level_setting(bind)?;
+        let compression = resolve_compression_mode(bind)?;
+        let discovery = resolve_file_discovery_options(bind)?;
+        let (paths, file_system) = resolve_input_paths(bind, &pattern, discovery)?;
+
+        bind.add_result_column("file", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("game_index", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("tag", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("value", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+
+        Ok(PgnTagsBindData {
+            paths,
+            compression,
+            file_system,
+        })
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(PgnTagsInitData {
+            state: Mutex::new(TagsScanState {
+                next_path_idx: 0,
+                current: None,
+                pending: VecDeque::new(),
+            }),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let init_data = func.get_init_data();
+        let bind_data = func.get_bind_data();
+        let capacity = output.flat_vector(0).capacity();
+
+        let mut rows = Vec::with_capacity(capacity);
+        {
+            let mut state = lock_scan_state(&init_data.state);
+            let mut visitor = HeadersVisitor {
+                keep_repeated: true,
+                ..HeadersVisitor::default()
+            };
+            while rows.len() < capacity {
+                match state.pending.pop_front() {
+                    Some(row) => rows.push(row),
+                    None if read_next_game(&mut state, bind_data, &mut visitor)? => {}
+                    None => break,
+                }
+            }
+        }
+
+        let file_vec = output.flat_vector(0);
+        let mut game_index_vec = output.flat_vector(1);
+        let tag_vec = output.flat_vector(2);
+        let value_vec = output.flat_vector(3);
+        for (row_idx, row) in rows.iter().enumerate() {
+            file_vec.insert(
+                row_idx,
+                bind_data.paths[row.path_idx].to_string_lossy().as_ref(),
+            );
+            game_index_vec.as_mut_slice::<i64>()[row_idx] = row.game_index as i64;
+            insert_varchar(&tag_vec, row_idx, &row.tag);
+            insert_varchar(&value_vec, row_idx, &row.value);
+        }
+        output.set_len(rows.len());
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![
+            LogicalTypeHandle::from(LogicalTypeId::Varchar), // path pattern (required)
+        ])
+    }
+
+    fn named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+        Some(vec![
+            (
+                "compression".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "follow_symlinks".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "skip_hidden".to_string(),
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+        ])
+    }
+}
@@ -745,6 +745,8 @@ fn try_apostrophe_notation(
                 warnings: warnings.to_owned(),
                 inferred: true,
                 overflow: false,
+                confidence: 0.0,
+                stage: None,
             }));
         }
     }
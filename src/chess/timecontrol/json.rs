@@ -1,4 +1,7 @@
-use super::{Mode, ParsedTimeControl};
+use super::{
+    Mode, ParsedTimeControl, category_from_parsed_timecontrol,
+    estimated_seconds_from_parsed_timecontrol,
+};
 
 pub(super) fn timecontrol_to_json(parsed: &ParsedTimeControl) -> String {
     let mode_str = match parsed.mode {
@@ -32,18 +35,35 @@ pub(super) fn timecontrol_to_json(parsed: &ParsedTimeControl) -> String {
         .map(|s| serde_json::to_string(s).unwrap_or_else(|_| format!("\"{}\"", s)))
         .unwrap_or_else(|| "null".to_string());
 
+    let optional_string_json = |value: Option<&str>| {
+        value.map_or_else(
+            || "null".to_string(),
+            |value| serde_json::to_string(value).unwrap_or_else(|_| format!("\"{}\"", value)),
+        )
+    };
+
     let warnings_json =
         serde_json::to_string(&parsed.warnings).unwrap_or_else(|_| "[]".to_string());
 
+    let estimated_seconds_json = estimated_seconds_from_parsed_timecontrol(parsed)
+        .map_or_else(|| "null".to_string(), |seconds| seconds.to_string());
+    let category_json = category_from_parsed_timecontrol(parsed)
+        .map_or_else(|| "null".to_string(), |category| format!("\"{category}\""));
+
     format!(
-        r#"{{"raw":{},"normalized":{},"mode":"{}","periods":[{}],"warnings":{},"inferred":{},"overflow":{}}}"#,
+        r#"{{"raw":{},"normalized":{},"mode":"{}","periods":[{}],"warnings":{},"inferred":{},"confidence":{},"stage":{},"rule":{},"overflow":{},"estimated_seconds":{},"category":{}}}"#,
         raw_json,
         normalized_json,
         mode_str,
         periods_json.join(","),
         warnings_json,
         if parsed.inferred { "true" } else { "false" },
-        if parsed.overflow { "true" } else { "false" }
+        parsed.confidence,
+        optional_string_json(parsed.stage),
+        optional_string_json(parsed.matched_rule()),
+        if parsed.overflow { "true" } else { "false" },
+        estimated_seconds_json,
+        category_json
     )
 }
 
@@ -61,6 +81,46 @@ mod tests {
         assert!(json.contains(r#""inferred":true"#));
     }
 
+    #[test]
+    fn test_json_includes_estimate_and_category() {
+        let result = parse_timecontrol("180+2").unwrap();
+        let json = timecontrol_to_json(&result);
+        assert!(json.ends_with(r#""estimated_seconds":260,"category":"blitz"}"#));
+
+        // Untimed or unparsed controls have no estimate; the keys are always present.
+        for raw in ["-", "klassisch"] {
+            let json = timecontrol_to_json(&parse_timecontrol(raw).unwrap());
+            assert!(
+                json.ends_with(r#""estimated_seconds":null,"category":null}"#),
+                "{json}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_includes_confidence() {
+        let json = timecontrol_to_json(&parse_timecontrol("15 + 10").unwrap());
+        assert!(
+            json.contains(r#""inferred":true,"confidence":0.6,"#),
+            "{json}"
+        );
+
+        let json = timecontrol_to_json(&parse_timecontrol("klassisch").unwrap());
+        assert!(json.contains(r#""confidence":0,"#), "{json}");
+    }
+
+    #[test]
+    fn test_json_includes_stage_and_rule() {
+        let json = timecontrol_to_json(&parse_timecontrol("G/90").unwrap());
+        assert!(
+            json.contains(r#""stage":"inference","rule":"interpreted_g_prefix_as_minutes","#),
+            "{json}"
+        );
+
+        let json = timecontrol_to_json(&parse_timecontrol("klassisch").unwrap());
+        assert!(json.contains(r#""stage":null,"rule":null,"#), "{json}");
+    }
+
     #[test]
     fn test_json_always_includes_normalized_key() {
         let result = parse_timecontrol("klassisch").unwrap();
@@ -1,22 +1,28 @@
 use std::error::Error;
 
+#[cfg(feature = "extension")]
 use ::duckdb::vtab::arrow::WritableVector;
+#[cfg(feature = "extension")]
 use ::duckdb::{
     Result,
     core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
     vscalar::{ScalarFunctionSignature, VScalar},
 };
 
+#[cfg(feature = "extension")]
 use super::duckdb_impl::scalar::{
-    VarcharNullBehavior, VarcharOutput, invoke_unary_varchar_to_varchar,
+    VarcharOutput, invoke_binary_varchar_i64_to_i64_nullable,
+    invoke_unary_varchar_optional_bool_to_varchar, invoke_unary_varchar_to_varchar,
 };
 
 mod inference;
 mod json;
 mod strict;
 
+#[cfg(feature = "extension")]
 pub struct ChessTimecontrolNormalizeScalar;
 
+#[cfg(feature = "extension")]
 impl VScalar for ChessTimecontrolNormalizeScalar {
     type State = ();
 
@@ -25,8 +31,13 @@ impl VScalar for ChessTimecontrolNormalizeScalar {
         input: &mut DataChunkHandle,
         output: &mut dyn WritableVector,
     ) -> Result<(), Box<dyn Error>> {
-        invoke_unary_varchar_to_varchar(input, output, VarcharNullBehavior::Null, |timecontrol| {
-            Ok(match normalize_timecontrol(timecontrol) {
+        invoke_unary_varchar_optional_bool_to_varchar(input, output, |timecontrol, strict| {
+            let normalized = if strict {
+                normalize_timecontrol_strict(timecontrol)
+            } else {
+                normalize_timecontrol(timecontrol)
+            };
+            Ok(match normalized {
                 Some(normalized) => VarcharOutput::Value(normalized),
                 None => VarcharOutput::Null,
             })
@@ -34,15 +45,26 @@ impl VScalar for ChessTimecontrolNormalizeScalar {
     }
 
     fn signatures() -> Vec<ScalarFunctionSignature> {
-        vec![ScalarFunctionSignature::exact(
-            vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
-            LogicalTypeHandle::from(LogicalTypeId::Varchar),
-        )]
+        vec![
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Boolean),
+                ],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+        ]
     }
 }
 
+#[cfg(feature = "extension")]
 pub struct ChessTimecontrolJsonScalar;
 
+#[cfg(feature = "extension")]
 impl VScalar for ChessTimecontrolJsonScalar {
     type State = ();
 
@@ -51,7 +73,7 @@ impl VScalar for ChessTimecontrolJsonScalar {
         input: &mut DataChunkHandle,
         output: &mut dyn WritableVector,
     ) -> Result<(), Box<dyn Error>> {
-        invoke_unary_varchar_to_varchar(input, output, VarcharNullBehavior::Null, |timecontrol| {
+        invoke_unary_varchar_to_varchar(input, output, |timecontrol| {
             let json = match parse_timecontrol(timecontrol) {
                 Ok(parsed) => timecontrol_to_json(&parsed),
                 Err(_) => {
@@ -63,6 +85,8 @@ impl VScalar for ChessTimecontrolJsonScalar {
                         warnings: vec!["parse_error".to_string()],
                         inferred: false,
                         overflow: false,
+                        confidence: 0.0,
+                        stage: None,
                     };
                     timecontrol_to_json(&parsed)
                 }
@@ -80,8 +104,10 @@ impl VScalar for ChessTimecontrolJsonScalar {
     }
 }
 
+#[cfg(feature = "extension")]
 pub struct ChessTimecontrolCategoryScalar;
 
+#[cfg(feature = "extension")]
 impl VScalar for ChessTimecontrolCategoryScalar {
     type State = ();
 
@@ -90,7 +116,7 @@ impl VScalar for ChessTimecontrolCategoryScalar {
         input: &mut DataChunkHandle,
         output: &mut dyn WritableVector,
     ) -> Result<(), Box<dyn Error>> {
-        invoke_unary_varchar_to_varchar(input, output, VarcharNullBehavior::Null, |timecontrol| {
+        invoke_unary_varchar_to_varchar(input, output, |timecontrol| {
             Ok(match categorize_timecontrol(timecontrol) {
                 Some(category) => VarcharOutput::Value(category.to_string()),
                 None => VarcharOutput::Null,
@@ -106,6 +132,32 @@ impl VScalar for ChessTimecontrolCategoryScalar {
     }
 }
 
+#[cfg(feature = "extension")]
+pub struct ChessTimecontrolSecondsForMoveScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessTimecontrolSecondsForMoveScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_binary_varchar_i64_to_i64_nullable(input, output, timecontrol_seconds_for_move)
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+            ],
+            LogicalTypeHandle::from(LogicalTypeId::Bigint),
+        )]
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Period {
     pub moves: Option<u32>,
@@ -130,8 +182,58 @@ pub struct ParsedTimeControl {
     pub warnings: Vec<String>,
     pub inferred: bool,
     pub overflow: bool,
+    /// How much to trust `normalized`, from 0.0 (unparsed) to 1.0 (spec-compliant as
+    /// written). Parsers leave it at 0.0; `parse_timecontrol` scores the final result.
+    pub confidence: f64,
+    /// The `parse_timecontrol` stage that matched: `strict`, `inference` or `free_text`.
+    /// Parsers leave it unset, as do inputs no stage accepts.
+    pub stage: Option<&'static str>,
+}
+
+impl ParsedTimeControl {
+    /// The inference rule or free-text template that matched, named by the warning it records.
+    pub fn matched_rule(&self) -> Option<&str> {
+        self.warnings
+            .iter()
+            .rev()
+            .map(String::as_str)
+            .find(|code| code.starts_with("interpreted_") || code.starts_with("matched_"))
+    }
+}
+
+/// Which parser stage of `parse_timecontrol` matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParseStage {
+    /// The PGN TimeControl grammar.
+    Strict,
+    /// Structured shorthands such as `15+10`, `G/90` or `90'+30''`.
+    Inference,
+    /// Free-text templates such as `90 minutes plus 30 seconds per move`.
+    FreeText,
+}
+
+impl ParseStage {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Strict => "strict",
+            Self::Inference => "inference",
+            Self::FreeText => "free_text",
+        }
+    }
+
+    /// Confidence of a match without warnings, in tenths.
+    fn base_confidence_tenths(self) -> usize {
+        match self {
+            Self::Strict => 10,
+            Self::Inference => 8,
+            Self::FreeText => 6,
+        }
+    }
 }
 
+/// Confidence lost per repair warning, in tenths; a match never scores below one tenth.
+const CONFIDENCE_TENTHS_PER_WARNING: usize = 1;
+
 #[derive(Debug, Clone)]
 pub struct TimeControlError {
     pub message: String,
@@ -165,12 +267,24 @@ fn checked_hours_minutes_to_seconds(hours: u32, minutes: u32) -> Option<u32> {
     hours_seconds.checked_add(minutes_seconds)
 }
 
+/// Restore the original input, record `stage` and score how far it and the warnings it
+/// collected had to stray from the spec. Results without a normalized value keep a confidence of 0.0.
 fn with_original_raw(
     raw: &str,
+    stage: ParseStage,
     result: Result<ParsedTimeControl, TimeControlError>,
 ) -> Result<ParsedTimeControl, TimeControlError> {
     result.map(|mut parsed| {
         parsed.raw = raw.to_string();
+        parsed.stage = Some(stage.name());
+        if parsed.normalized.is_some() {
+            let penalty = parsed.warnings.len() * CONFIDENCE_TENTHS_PER_WARNING;
+            let tenths = stage
+                .base_confidence_tenths()
+                .saturating_sub(penalty)
+                .max(1);
+            parsed.confidence = tenths as f64 / 10.0;
+        }
         parsed
     })
 }
@@ -191,6 +305,8 @@ fn inferred_parsed(
             warnings: warnings.to_vec(),
             inferred: true,
             overflow: true,
+            confidence: 0.0,
+            stage: None,
         }
     } else {
         let normalized = periods
@@ -207,6 +323,8 @@ fn inferred_parsed(
             warnings: warnings.to_vec(),
             inferred: true,
             overflow: false,
+            confidence: 0.0,
+            stage: None,
         }
     }
 }
@@ -231,21 +349,23 @@ pub fn parse_timecontrol(raw: &str) -> Result<ParsedTimeControl, TimeControlErro
             warnings,
             inferred: false,
             overflow: false,
+            confidence: 0.0,
+            stage: None,
         });
     }
 
     if let Some(result) = strict::try_strict_parse(&preprocessed.normalized, &mut warnings) {
-        return with_original_raw(raw, result);
+        return with_original_raw(raw, ParseStage::Strict, result);
     }
 
     if let Some(result) = inference::try_inference(&preprocessed.normalized, &mut warnings) {
-        return with_original_raw(raw, result);
+        return with_original_raw(raw, ParseStage::Inference, result);
     }
 
     if let Some(result) =
         inference::try_free_text_templates(&preprocessed.normalized, &mut warnings)
     {
-        return with_original_raw(raw, result);
+        return with_original_raw(raw, ParseStage::FreeText, result);
     }
 
     if let Some(core) = inference::strip_trailing_qualifier_suffix(&preprocessed.normalized) {
@@ -253,15 +373,15 @@ pub fn parse_timecontrol(raw: &str) -> Result<ParsedTimeControl, TimeControlErro
         fallback_warnings.push("ignored_trailing_qualifier_suffix".to_string());
 
         if let Some(result) = strict::try_strict_parse(&core, &mut fallback_warnings) {
-            return with_original_raw(raw, result);
+            return with_original_raw(raw, ParseStage::Strict, result);
         }
 
         if let Some(result) = inference::try_inference(&core, &mut fallback_warnings) {
-            return with_original_raw(raw, result);
+            return with_original_raw(raw, ParseStage::Inference, result);
         }
 
         if let Some(result) = inference::try_free_text_templates(&core, &mut fallback_warnings) {
-            return with_original_raw(raw, result);
+            return with_original_raw(raw, ParseStage::FreeText, result);
         }
     }
 
@@ -273,6 +393,8 @@ pub fn parse_timecontrol(raw: &str) -> Result<ParsedTimeControl, TimeControlErro
         warnings,
         inferred: false,
         overflow: false,
+        confidence: 0.0,
+        stage: None,
     })
 }
 
@@ -283,16 +405,28 @@ pub fn normalize_timecontrol(raw: &str) -> Option<String> {
     }
 }
 
-pub fn category_from_parsed_timecontrol(parsed: &ParsedTimeControl) -> Option<&'static str> {
+/// Normalize only spec-compliant PGN TimeControl values, with no trimming or inference.
+///
+/// Returns `None` for anything the lenient parser would have had to guess about, so ETL
+/// validation can tell conforming tags apart from repaired ones.
+pub fn normalize_timecontrol_strict(raw: &str) -> Option<String> {
+    strict::try_spec_only_parse(raw)?.normalized
+}
+
+/// Lichess' estimated game duration per player, `base + 40 * increment` of the first period,
+/// which decides the category. `None` unless the control is a normal timed one.
+pub fn estimated_seconds_from_parsed_timecontrol(parsed: &ParsedTimeControl) -> Option<u64> {
     if parsed.mode != Mode::Normal || parsed.overflow {
         return None;
     }
 
     let period = parsed.periods.first()?;
     let increment = period.increment_seconds.unwrap_or(0) as u64;
-    let estimated_seconds = period.base_seconds as u64 + 40 * increment;
+    Some(period.base_seconds as u64 + 40 * increment)
+}
 
-    match estimated_seconds {
+pub fn category_from_parsed_timecontrol(parsed: &ParsedTimeControl) -> Option<&'static str> {
+    match estimated_seconds_from_parsed_timecontrol(parsed)? {
         0..=29 => Some("ultra-bullet"),
         30..=179 => Some("bullet"),
         180..=479 => Some("blitz"),
@@ -306,6 +440,64 @@ pub fn categorize_timecontrol(raw: &str) -> Option<&'static str> {
     category_from_parsed_timecontrol(&parsed)
 }
 
+/// Cumulative seconds credited to one player by their `move_number`-th move (1-based).
+///
+/// Sums the base time of every period reached by that move plus one increment per move
+/// played so far (including `move_number`). A final period with a move count repeats, as in
+/// `40/7200`; a final period without one runs to the end of the game.
+pub fn seconds_available_for_move(parsed: &ParsedTimeControl, move_number: u64) -> Option<u64> {
+    if !matches!(parsed.mode, Mode::Normal | Mode::Sandclock) || parsed.overflow {
+        return None;
+    }
+    if move_number == 0 {
+        return None;
+    }
+
+    let (last, earlier) = parsed.periods.split_last()?;
+    let mut total: u64 = 0;
+    let mut first_move: u64 = 1;
+
+    for period in earlier {
+        let base = u64::from(period.base_seconds);
+        let increment = u64::from(period.increment_seconds.unwrap_or(0));
+        total = total.checked_add(base)?;
+
+        let Some(moves) = period.moves.filter(|&m| m > 0).map(u64::from) else {
+            // A period without a move count lasts until the end of the game.
+            let played = move_number - first_move + 1;
+            return total.checked_add(increment.checked_mul(played)?);
+        };
+
+        let last_move = first_move + moves - 1;
+        if move_number <= last_move {
+            let played = move_number - first_move + 1;
+            return total.checked_add(increment.checked_mul(played)?);
+        }
+
+        total = total.checked_add(increment.checked_mul(moves)?)?;
+        first_move = last_move + 1;
+    }
+
+    let base = u64::from(last.base_seconds);
+    let increment = u64::from(last.increment_seconds.unwrap_or(0));
+    let played = move_number - first_move + 1;
+    let periods_started = match last.moves.filter(|&m| m > 0) {
+        Some(moves) => played.div_ceil(u64::from(moves)),
+        None => 1,
+    };
+
+    total
+        .checked_add(base.checked_mul(periods_started)?)?
+        .checked_add(increment.checked_mul(played)?)
+}
+
+pub fn timecontrol_seconds_for_move(raw: &str, move_number: i64) -> Option<i64> {
+    let move_number = u64::try_from(move_number).ok()?;
+    let parsed = parse_timecontrol(raw).ok()?;
+    let seconds = seconds_available_for_move(&parsed, move_number)?;
+    i64::try_from(seconds).ok()
+}
+
 pub fn timecontrol_to_json(parsed: &ParsedTimeControl) -> String {
     json::timecontrol_to_json(parsed)
 }
@@ -321,6 +513,101 @@ mod tests {
         assert_eq!(result.normalized, Some("900+10".to_string()));
     }
 
+    #[test]
+    fn test_normalize_strict_accepts_only_spec_values() {
+        for (raw, expected) in [
+            ("180+2", "180+2"),
+            ("40/5400+30:1800+30", "40/5400+30:1800+30"),
+            ("?", "?"),
+            ("-", "-"),
+            ("*60", "*60"),
+            // No minute-shorthand reinterpretation in strict mode.
+            ("3+2", "3+2"),
+        ] {
+            assert_eq!(
+                normalize_timecontrol_strict(raw).as_deref(),
+                Some(expected),
+                "{raw}"
+            );
+        }
+
+        for raw in [
+            "",
+            " 180+2",
+            "180 + 2",
+            "\"180+2\"",
+            "15 min",
+            "90min./40 + 30min. + 30s./move",
+            "180+",
+            "40/",
+            "1:",
+        ] {
+            assert_eq!(normalize_timecontrol_strict(raw), None, "{raw}");
+        }
+        assert!(normalize_timecontrol("180 + 2").is_some());
+    }
+
+    #[test]
+    fn test_confidence_depends_on_stage_and_warnings() {
+        for (raw, expected) in [
+            ("180+2", 1.0),
+            ("?", 1.0),
+            ("\"180+2\"", 0.9),
+            ("3+2", 0.7),
+            ("G/90", 0.7),
+            ("15 + 10", 0.6),
+            ("15 min", 0.5),
+            ("90+30 rapid", 0.6),
+            ("klassisch", 0.0),
+            ("G71582789", 0.0),
+        ] {
+            assert_eq!(
+                parse_timecontrol(raw).unwrap().confidence,
+                expected,
+                "{raw}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_stage_and_matched_rule() {
+        for (raw, stage, rule) in [
+            ("180+2", Some("strict"), None),
+            ("\"180+2\"", Some("strict"), None),
+            (
+                "15 + 10",
+                Some("inference"),
+                Some("interpreted_small_base_as_minutes"),
+            ),
+            (
+                "G/90",
+                Some("inference"),
+                Some("interpreted_g_prefix_as_minutes"),
+            ),
+            (
+                "90+30 rapid",
+                Some("inference"),
+                Some("interpreted_classical_75_90_as_minutes"),
+            ),
+            (
+                "15 min",
+                Some("free_text"),
+                Some("matched_free_text_template"),
+            ),
+            // Overflowing matches still name their rule.
+            (
+                "G71582789",
+                Some("inference"),
+                Some("interpreted_g_prefix_as_minutes"),
+            ),
+            ("klassisch", None, None),
+        ] {
+            let parsed = parse_timecontrol(raw).unwrap();
+            assert_eq!(parsed.stage, stage, "{raw}");
+            assert_eq!(parsed.matched_rule(), rule, "{raw}");
+        }
+    }
+
     #[test]
     fn test_normalize_function() {
         assert_eq!(normalize_timecontrol("3+2"), Some("180+2".to_string()));
@@ -370,6 +657,8 @@ mod tests {
             warnings: Vec::new(),
             inferred: false,
             overflow: false,
+            confidence: 0.0,
+            stage: None,
         };
 
         assert_eq!(category_from_parsed_timecontrol(&parsed), None);
@@ -381,4 +670,60 @@ mod tests {
         assert!(result.overflow);
         assert_eq!(category_from_parsed_timecontrol(&result), None);
     }
+
+    #[test]
+    fn test_seconds_for_move_single_period_with_increment() {
+        assert_eq!(timecontrol_seconds_for_move("180+2", 1), Some(182));
+        assert_eq!(timecontrol_seconds_for_move("180+2", 40), Some(260));
+        assert_eq!(timecontrol_seconds_for_move("600", 30), Some(600));
+    }
+
+    #[test]
+    fn test_seconds_for_move_multi_period() {
+        // 40 moves in 90 minutes, then 30 minutes for the rest, 30s increment from move 1.
+        let tc = "40/5400+30:1800+30";
+        assert_eq!(timecontrol_seconds_for_move(tc, 1), Some(5430));
+        assert_eq!(timecontrol_seconds_for_move(tc, 40), Some(5400 + 40 * 30));
+        assert_eq!(
+            timecontrol_seconds_for_move(tc, 41),
+            Some(5400 + 1800 + 41 * 30)
+        );
+        assert_eq!(
+            timecontrol_seconds_for_move(tc, 60),
+            Some(5400 + 1800 + 60 * 30)
+        );
+    }
+
+    #[test]
+    fn test_seconds_for_move_repeats_final_period_with_move_count() {
+        assert_eq!(timecontrol_seconds_for_move("40/7200", 40), Some(7200));
+        assert_eq!(timecontrol_seconds_for_move("40/7200", 41), Some(14400));
+        assert_eq!(timecontrol_seconds_for_move("40/7200", 81), Some(21600));
+        assert_eq!(
+            timecontrol_seconds_for_move("40/7200:20/3600", 61),
+            Some(7200 + 3600 + 3600)
+        );
+    }
+
+    #[test]
+    fn test_seconds_for_move_sandclock_is_constant() {
+        assert_eq!(timecontrol_seconds_for_move("*60", 1), Some(60));
+        assert_eq!(timecontrol_seconds_for_move("*60", 100), Some(60));
+    }
+
+    #[test]
+    fn test_seconds_for_move_returns_none_for_unsupported_inputs() {
+        assert_eq!(timecontrol_seconds_for_move("180+2", 0), None);
+        assert_eq!(timecontrol_seconds_for_move("180+2", -1), None);
+        assert_eq!(timecontrol_seconds_for_move("-", 10), None);
+        assert_eq!(timecontrol_seconds_for_move("?", 10), None);
+        assert_eq!(timecontrol_seconds_for_move("invalid", 10), None);
+        assert_eq!(timecontrol_seconds_for_move("", 10), None);
+    }
+
+    #[test]
+    fn test_seconds_for_move_saturates_to_none_on_overflow() {
+        assert_eq!(timecontrol_seconds_for_move("40/7200", i64::MAX), None);
+        assert!(timecontrol_seconds_for_move("180+2", 1_000_000_000).is_some());
+    }
 }
@@ -4,6 +4,30 @@ use super::{Mode, ParsedTimeControl, Period, TimeControlError, parse_u32};
 pub(super) fn try_strict_parse(
     input: &str,
     warnings: &mut Vec<String>,
+) -> Option<Result<ParsedTimeControl, TimeControlError>> {
+    parse_spec_value(input, warnings, true)
+}
+
+/// Parse `input` exactly as written against the PGN TimeControl grammar.
+///
+/// No trimming, unit handling or minute-shorthand reinterpretation: `3+2` is three seconds
+/// plus a two-second increment, and anything outside the spec alphabet is rejected.
+pub(super) fn try_spec_only_parse(input: &str) -> Option<ParsedTimeControl> {
+    if input.is_empty()
+        || !input
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b"+/:*?-".contains(&b))
+    {
+        return None;
+    }
+
+    parse_spec_value(input, &[], false)?.ok()
+}
+
+fn parse_spec_value(
+    input: &str,
+    warnings: &[String],
+    defer_minute_shorthand: bool,
 ) -> Option<Result<ParsedTimeControl, TimeControlError>> {
     if input == "?" {
         return Some(Ok(ParsedTimeControl {
@@ -11,9 +35,11 @@ pub(super) fn try_strict_parse(
             normalized: Some("?".to_string()),
             periods: Vec::new(),
             mode: Mode::Unknown,
-            warnings: warnings.clone(),
+            warnings: warnings.to_vec(),
             inferred: false,
             overflow: false,
+            confidence: 0.0,
+            stage: None,
         }));
     }
 
@@ -23,9 +49,11 @@ pub(super) fn try_strict_parse(
             normalized: Some("-".to_string()),
             periods: Vec::new(),
             mode: Mode::Unlimited,
-            warnings: warnings.clone(),
+            warnings: warnings.to_vec(),
             inferred: false,
             overflow: false,
+            confidence: 0.0,
+            stage: None,
         }));
     }
 
@@ -41,9 +69,11 @@ pub(super) fn try_strict_parse(
                 increment_seconds: None,
             }],
             mode: Mode::Sandclock,
-            warnings: warnings.clone(),
+            warnings: warnings.to_vec(),
             inferred: false,
             overflow: false,
+            confidence: 0.0,
+            stage: None,
         }));
     }
 
@@ -73,15 +103,17 @@ pub(super) fn try_strict_parse(
                 normalized: Some(normalized),
                 periods,
                 mode: Mode::Normal,
-                warnings: warnings.clone(),
+                warnings: warnings.to_vec(),
                 inferred: false,
                 overflow: false,
+                confidence: 0.0,
+                stage: None,
             }));
         }
     }
 
     if let Some(period) = parse_stage(input) {
-        if looks_like_minute_shorthand(&period) {
+        if defer_minute_shorthand && looks_like_minute_shorthand(&period) {
             return None;
         }
         let normalized = format_period(&period);
@@ -90,9 +122,11 @@ pub(super) fn try_strict_parse(
             normalized: Some(normalized.clone()),
             periods: vec![period],
             mode: Mode::Normal,
-            warnings: warnings.clone(),
+            warnings: warnings.to_vec(),
             inferred: false,
             overflow: false,
+            confidence: 0.0,
+            stage: None,
         }));
     }
 
@@ -0,0 +1,272 @@
+#[cfg(feature = "extension")]
+use super::duckdb_impl::scalar::invoke_unary_varchar_to_varchar_struct_list;
+use super::moves::{is_move_number_token, is_result_marker, looks_like_san_token};
+#[cfg(feature = "extension")]
+use duckdb::{
+    core::{DataChunkHandle, LogicalTypeHandle, LogicalTypeId},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::arrow::WritableVector,
+};
+#[cfg(feature = "extension")]
+use std::error::Error;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum TokenKind {
+    Move,
+    MoveNumber,
+    Nag,
+    Comment,
+    VariationStart,
+    VariationEnd,
+    Result,
+    Unknown,
+}
+
+impl TokenKind {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Move => "move",
+            Self::MoveNumber => "move_number",
+            Self::Nag => "nag",
+            Self::Comment => "comment",
+            Self::VariationStart => "variation_start",
+            Self::VariationEnd => "variation_end",
+            Self::Result => "result",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct MovetextToken<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+}
+
+fn is_token_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '{' | '}' | '(' | ')' | ';' | '$')
+}
+
+/// Split movetext into lexical tokens, mirroring how the PGN parser sees them.
+///
+/// Compact move numbers (`1.e4`) and suffix annotations (`e4!?`) are split into separate
+/// tokens. Anything that is neither syntax nor a plausible SAN move is reported as `unknown`.
+pub(crate) fn tokenize_movetext(movetext: &str) -> Vec<MovetextToken<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = movetext;
+
+    loop {
+        rest = rest.trim_start();
+        let Some(c) = rest.chars().next() else {
+            break;
+        };
+
+        let (kind, len) = match c {
+            '{' => (
+                TokenKind::Comment,
+                rest.find('}').map_or(rest.len(), |end| end + 1),
+            ),
+            ';' => (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len())),
+            '(' => (TokenKind::VariationStart, 1),
+            ')' => (TokenKind::VariationEnd, 1),
+            '}' => (TokenKind::Unknown, 1),
+            '$' => {
+                let digits = rest[1..].bytes().take_while(u8::is_ascii_digit).count();
+                let kind = if digits > 0 {
+                    TokenKind::Nag
+                } else {
+                    TokenKind::Unknown
+                };
+                (kind, 1 + digits)
+            }
+            _ => {
+                let len = rest.find(is_token_delimiter).unwrap_or(rest.len());
+                push_word(&mut tokens, &rest[..len]);
+                rest = &rest[len..];
+                continue;
+            }
+        };
+
+        tokens.push(MovetextToken {
+            kind,
+            text: &rest[..len],
+        });
+        rest = &rest[len..];
+    }
+
+    tokens
+}
+
+fn push_word<'a>(tokens: &mut Vec<MovetextToken<'a>>, mut word: &'a str) {
+    if is_result_marker(word) {
+        tokens.push(MovetextToken {
+            kind: TokenKind::Result,
+            text: word,
+        });
+        return;
+    }
+
+    let digits = word.bytes().take_while(u8::is_ascii_digit).count();
+    let dots = word[digits..].bytes().take_while(|&b| b == b'.').count();
+    if digits > 0 && dots > 0 {
+        let (number, remainder) = word.split_at(digits + dots);
+        let kind = if is_move_number_token(number) {
+            TokenKind::MoveNumber
+        } else {
+            TokenKind::Unknown
+        };
+        tokens.push(MovetextToken { kind, text: number });
+        if remainder.is_empty() {
+            return;
+        }
+        word = remainder;
+    }
+
+    let san_len = word.trim_end_matches(['!', '?']).len();
+    let (san, suffix) = word.split_at(san_len);
+    if !san.is_empty() {
+        let kind = if looks_like_san_token(san) || matches!(san, "--" | "Z0") {
+            TokenKind::Move
+        } else {
+            TokenKind::Unknown
+        };
+        tokens.push(MovetextToken { kind, text: san });
+    }
+    if !suffix.is_empty() {
+        tokens.push(MovetextToken {
+            kind: TokenKind::Nag,
+            text: suffix,
+        });
+    }
+}
+
+#[cfg(feature = "extension")]
+pub struct ChessMovetextTokensDebugScalar;
+
+#[cfg(feature = "extension")]
+impl VScalar for ChessMovetextTokensDebugScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        invoke_unary_varchar_to_varchar_struct_list(input, output, |movetext| {
+            Some(
+                tokenize_movetext(movetext)
+                    .into_iter()
+                    .map(|token| [token.kind.as_str().to_string(), token.text.to_string()])
+                    .collect(),
+            )
+        })
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let token_type = LogicalTypeHandle::struct_type(&[
+            ("kind", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("text", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+        ]);
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            LogicalTypeHandle::list(&token_type),
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds_and_texts(movetext: &str) -> Vec<(&'static str, &str)> {
+        tokenize_movetext(movetext)
+            .into_iter()
+            .map(|token| (token.kind.as_str(), token.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_basic_movetext() {
+        assert_eq!(
+            kinds_and_texts("1. e4 e5 2. Nf3 1-0"),
+            vec![
+                ("move_number", "1."),
+                ("move", "e4"),
+                ("move", "e5"),
+                ("move_number", "2."),
+                ("move", "Nf3"),
+                ("result", "1-0"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_annotations_and_variations() {
+        assert_eq!(
+            kinds_and_texts("1. e4!? $1 {best by test} (1. d4 d5) 1... e5"),
+            vec![
+                ("move_number", "1."),
+                ("move", "e4"),
+                ("nag", "!?"),
+                ("nag", "$1"),
+                ("comment", "{best by test}"),
+                ("variation_start", "("),
+                ("move_number", "1."),
+                ("move", "d4"),
+                ("move", "d5"),
+                ("variation_end", ")"),
+                ("move_number", "1..."),
+                ("move", "e5"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_compact_move_numbers_and_castling() {
+        assert_eq!(
+            kinds_and_texts("1.e4 e5 2.O-O+"),
+            vec![
+                ("move_number", "1."),
+                ("move", "e4"),
+                ("move", "e5"),
+                ("move_number", "2."),
+                ("move", "O-O+"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_rest_of_line_and_unterminated_comments() {
+        assert_eq!(
+            kinds_and_texts("e4 ; note\ne5 {open"),
+            vec![
+                ("move", "e4"),
+                ("comment", "; note"),
+                ("move", "e5"),
+                ("comment", "{open"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_reports_unknown_tokens() {
+        assert_eq!(
+            kinds_and_texts("1. e4 xyz } $ 1.. *"),
+            vec![
+                ("move_number", "1."),
+                ("move", "e4"),
+                ("unknown", "xyz"),
+                ("unknown", "}"),
+                ("unknown", "$"),
+                ("unknown", "1.."),
+                ("result", "*"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_empty_input() {
+        assert!(tokenize_movetext("").is_empty());
+        assert!(tokenize_movetext("  \n ").is_empty());
+    }
+}
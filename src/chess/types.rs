@@ -1,5 +1,77 @@
+/// Calendar date as days since 1970-01-01 (same representation as DuckDB `DATE`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PgnDate {
+    pub days: i32,
+}
+
+/// Time of day with UTC offset, packed like DuckDB `TIMETZ`
+/// (micros in the upper 40 bits, encoded offset in the lower 24 bits).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PgnTimeTz {
+    pub bits: u64,
+}
+
+/// Target type of a user-requested tag column (`read_pgn(..., columns := {...})`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtraTagType {
+    Varchar,
+    Boolean,
+    Integer,
+    BigInt,
+    UInteger,
+    UBigInt,
+    Double,
+    Date,
+}
+
+impl ExtraTagType {
+    /// Parse a DuckDB type name (case-insensitive, common aliases accepted).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_uppercase().as_str() {
+            "VARCHAR" | "TEXT" | "STRING" => Some(Self::Varchar),
+            "BOOLEAN" | "BOOL" => Some(Self::Boolean),
+            "INTEGER" | "INT" | "INT4" => Some(Self::Integer),
+            "BIGINT" | "INT8" | "LONG" => Some(Self::BigInt),
+            "UINTEGER" => Some(Self::UInteger),
+            "UBIGINT" => Some(Self::UBigInt),
+            "DOUBLE" | "FLOAT8" => Some(Self::Double),
+            "DATE" => Some(Self::Date),
+            _ => None,
+        }
+    }
+}
+
+/// An additional tag-backed output column; the column is named after the tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtraTagColumn {
+    pub tag: String,
+    pub tag_type: ExtraTagType,
+}
+
+/// Converted value of an [`ExtraTagColumn`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtraTagValue {
+    Varchar(String),
+    Boolean(bool),
+    Integer(i32),
+    BigInt(i64),
+    UInteger(u32),
+    UBigInt(u64),
+    Double(f64),
+    Date(PgnDate),
+}
+
+/// One mainline move, collected for `read_pgn`'s `moves_as := 'list'` / `'struct'` output.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MoveRecord {
+    pub san: String,
+    /// Comments after the move, trimmed and joined with a space.
+    pub comment: Option<String>,
+    /// First NAG after the move; `!` is 1 and `?` is 2.
+    pub nag: Option<u8>,
+}
+
 /// Stores parsed game data from PGN - matches Lichess dataset schema
-use libduckdb_sys::{duckdb_date, duckdb_time_tz};
 
 #[derive(Debug, Clone, Default)]
 pub struct GameRecord {
@@ -16,10 +88,14 @@ pub struct GameRecord {
     pub black_title: Option<String>,
     pub white_elo: Option<u32>,
     pub black_elo: Option<u32>,
+    /// Unconverted `WhiteElo` / `BlackElo` tags; only kept when `ScanOptions::elo_raw_columns`
+    /// is set.
+    pub white_elo_raw: Option<String>,
+    pub black_elo_raw: Option<String>,
 
     // Date/Time
-    pub utc_date: Option<duckdb_date>,
-    pub utc_time: Option<duckdb_time_tz>,
+    pub utc_date: Option<PgnDate>,
+    pub utc_time: Option<PgnTimeTz>,
 
     // Opening info
     pub eco: Option<String>,
@@ -31,8 +107,39 @@ pub struct GameRecord {
 
     // Movetext
     pub movetext: String,
+    /// Mainline moves; only collected when `ScanOptions::moves_as` is not `MovesAs::Text`.
+    pub moves: Vec<MoveRecord>,
+
+    /// Values of the requested extra tag columns, in `columns := {...}` order.
+    pub extra_tags: Vec<Option<ExtraTagValue>>,
+
+    /// Offset of the game's first tag in the decompressed input; only tracked when
+    /// `ScanOptions::track_byte_offsets` is set.
+    pub byte_offset: Option<u64>,
+
+    /// 1-based line of the game's first tag in the decompressed input; only tracked when
+    /// `ScanOptions::track_line_numbers` is set.
+    pub line_number: Option<u64>,
+
+    /// Raw bytes of the game's movetext section in the decompressed input; only tracked when
+    /// `ScanOptions::track_movetext_bytes` is set.
+    pub movetext_bytes: Option<u64>,
+    /// Deterministic UUID of the game; only set by `read_pgn` when `game_id := true`.
+    pub game_id: Option<u128>,
+    /// Mainline comments carry `[%clk]` / `[%eval]` annotations.
+    pub has_clk: bool,
+    pub has_eval: bool,
+    /// At least one variation was skipped.
+    pub has_variations: bool,
+    /// Invalid UTF-8 sequences replaced with U+FFFD while decoding tags and comments.
+    pub encoding_issues: u32,
+    /// `eco` or `opening` was filled in from the moves by `eco_backfill`.
+    pub eco_inferred: bool,
 
     // Parse diagnostics
+    /// Non-fatal adjustments made while converting tags, such as a `Date` of `2015.11.31`
+    /// clamped to 2015-11-30. They do not make `parse_error` non-NULL.
+    pub warnings: Vec<String>,
     /// Spec: data-schema - Parse Error Column
     /// Contains NULL for successfully parsed games or error message for failed games
     pub parse_error: Option<String>,
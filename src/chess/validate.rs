@@ -0,0 +1,278 @@
+use shakmaty::san::SanPlus;
+
+/// Games checked by `read_pgn(..., validate := true)` at the start of each input.
+pub const VALIDATE_SAMPLE_GAMES: usize = 16;
+/// Decoded bytes read at most to find those games.
+pub const VALIDATE_SAMPLE_BYTES: u64 = 1 << 20;
+/// Bytes quoted in the error, starting at the offending one.
+const QUOTED_BYTES: usize = 40;
+
+const RESULTS: [&[u8]; 4] = [b"1-0", b"0-1", b"1/2-1/2", b"*"];
+const NULL_MOVES: [&[u8]; 2] = [b"--", b"Z0"];
+
+/// Where and why the start of an input does not look like PGN.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SampleProblem {
+    /// Offset of the offending byte in the decoded input.
+    pub offset: usize,
+    pub reason: &'static str,
+    /// Up to [`QUOTED_BYTES`] bytes from `offset`, escaped as ASCII.
+    pub bytes: String,
+}
+
+impl SampleProblem {
+    fn new(sample: &[u8], offset: usize, reason: &'static str) -> Self {
+        let end = sample.len().min(offset + QUOTED_BYTES);
+        Self {
+            offset,
+            reason,
+            bytes: sample[offset..end].escape_ascii().to_string(),
+        }
+    }
+}
+
+/// Check that the first `max_games` games of `sample`, the head of a decoded input, look like
+/// PGN. An empty or blank sample is fine.
+/// Spec: pgn-parsing - Input Validation
+///
+/// The check is meant to catch files that are not PGN at all, such as a saved HTML error page,
+/// JSON or binary data, while accepting anything `read_pgn` would read sensibly:
+/// - the sample must not contain NUL bytes;
+/// - a line starting with `[` must be a tag pair (`[Name "value"`);
+/// - each game must start with a tag pair or a movetext token (move number, SAN, result, NAG,
+///   comment or variation), and contain a tag pair or a token outside comments.
+///
+/// `%` escape lines are ignored, and a leading UTF-8 byte order mark is skipped.
+pub fn validate_pgn_sample(sample: &[u8], max_games: usize) -> Result<(), SampleProblem> {
+    if let Some(offset) = sample.iter().position(|&b| b == 0) {
+        return Err(SampleProblem::new(sample, offset, "binary data"));
+    }
+
+    let mut scanner = SampleScanner::default();
+    let mut offset = if sample.starts_with(b"\xEF\xBB\xBF") {
+        3
+    } else {
+        0
+    };
+    while offset < sample.len() && scanner.games < max_games {
+        let end = sample[offset..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(sample.len(), |idx| offset + idx);
+        scanner.line(sample, offset, end)?;
+        offset = end + 1;
+    }
+    scanner.end_game(sample)
+}
+
+/// Game-level state of [`validate_pgn_sample`].
+#[derive(Default)]
+struct SampleScanner {
+    games: usize,
+    /// Offset of the first line of the current game, `None` between games.
+    game_start: Option<usize>,
+    /// The current game has a tag pair or a token outside comments.
+    game_has_content: bool,
+    in_movetext: bool,
+    in_comment: bool,
+}
+
+impl SampleScanner {
+    fn line(&mut self, sample: &[u8], start: usize, end: usize) -> Result<(), SampleProblem> {
+        let line = &sample[start..end];
+        if self.in_comment {
+            match line.iter().position(|&b| b == b'}') {
+                Some(idx) => {
+                    self.in_comment = false;
+                    return self.movetext(sample, start + idx + 1, end, false);
+                }
+                None => return Ok(()),
+            }
+        }
+        if line.starts_with(b"%") {
+            return Ok(());
+        }
+
+        let trimmed = line.trim_ascii_start();
+        let indent = line.len() - trimmed.len();
+        if trimmed.trim_ascii_end().is_empty() {
+            if self.in_movetext {
+                self.end_game(sample)?;
+            }
+            return Ok(());
+        }
+
+        if trimmed.starts_with(b"[") {
+            if self.in_movetext {
+                self.end_game(sample)?;
+            }
+            if !is_tag_pair(trimmed) {
+                return Err(SampleProblem::new(
+                    sample,
+                    start + indent,
+                    "malformed tag pair",
+                ));
+            }
+            self.game_start.get_or_insert(start + indent);
+            self.game_has_content = true;
+            return Ok(());
+        }
+
+        let first_line = self.game_start.is_none();
+        self.game_start.get_or_insert(start + indent);
+        self.in_movetext = true;
+        self.movetext(sample, start + indent, end, first_line)
+    }
+
+    /// Scan the movetext in `sample[start..end]`; with `first_token`, the first token must be
+    /// one that can start a game.
+    fn movetext(
+        &mut self,
+        sample: &[u8],
+        start: usize,
+        end: usize,
+        mut first_token: bool,
+    ) -> Result<(), SampleProblem> {
+        let mut pos = start;
+        while pos < end {
+            match sample[pos] {
+                b'{' => {
+                    match sample[pos..end].iter().position(|&b| b == b'}') {
+                        Some(idx) => pos += idx + 1,
+                        None => {
+                            self.in_comment = true;
+                            return Ok(());
+                        }
+                    }
+                    first_token = false;
+                }
+                b';' => return Ok(()),
+                b if b.is_ascii_whitespace() => pos += 1,
+                _ => {
+                    let token_end = sample[pos..end]
+                        .iter()
+                        .position(|&b| b.is_ascii_whitespace() || b == b'{' || b == b';')
+                        .map_or(end, |idx| pos + idx);
+                    if first_token && !is_movetext_token(&sample[pos..token_end]) {
+                        return Err(SampleProblem::new(
+                            sample,
+                            pos,
+                            "not a tag pair or movetext",
+                        ));
+                    }
+                    first_token = false;
+                    self.game_has_content = true;
+                    pos = token_end;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn end_game(&mut self, sample: &[u8]) -> Result<(), SampleProblem> {
+        self.in_movetext = false;
+        self.in_comment = false;
+        let Some(game_start) = self.game_start.take() else {
+            return Ok(());
+        };
+        if !std::mem::take(&mut self.game_has_content) {
+            return Err(SampleProblem::new(
+                sample,
+                game_start,
+                "no tag pairs or moves outside comments",
+            ));
+        }
+        self.games += 1;
+        Ok(())
+    }
+}
+
+/// `line` starts with `[Name "`, allowing spaces around the name as pgn-reader does.
+fn is_tag_pair(line: &[u8]) -> bool {
+    let rest = line[1..].trim_ascii_start();
+    let name_len = rest
+        .iter()
+        .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+        .count();
+    name_len > 0 && rest[name_len..].trim_ascii_start().starts_with(b"\"")
+}
+
+/// A token that can start a game's movetext.
+fn is_movetext_token(token: &[u8]) -> bool {
+    let digits = token.iter().take_while(|b| b.is_ascii_digit()).count();
+    let dots = token[digits..].iter().take_while(|&&b| b == b'.').count();
+    if digits > 0 && dots > 0 {
+        // A move number, possibly followed by the move: `12.`, `12...`, `1.e4`.
+        let san = &token[digits + dots..];
+        return san.is_empty() || is_movetext_token(san);
+    }
+
+    let stripped = token
+        .iter()
+        .rposition(|b| !b"!?".contains(b))
+        .map_or(&token[..0], |idx| &token[..=idx]);
+    RESULTS.contains(&token)
+        || NULL_MOVES.contains(&stripped)
+        || matches!(token.first(), Some(b'(' | b'$'))
+        || matches!(stripped, b"0-0" | b"0-0-0" | b"0-0+" | b"0-0-0+")
+        || SanPlus::from_ascii(stripped).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reason(sample: &str) -> Option<(usize, &'static str)> {
+        validate_pgn_sample(sample.as_bytes(), VALIDATE_SAMPLE_GAMES)
+            .err()
+            .map(|problem| (problem.offset, problem.reason))
+    }
+
+    #[test]
+    fn test_validate_pgn_sample_accepts_pgn() {
+        for sample in [
+            "",
+            "\n\n",
+            "\u{feff}[Event \"A\"]\n[White \"B\"]\n\n1. e4 e5 2. Nf3 {Main line\ncontinues} Nc6 1-0\n",
+            "% escape <html>\n[ Event \"A\" ]\n\n1.e4 e5?! $1 (1... c5) *\n\n[Event \"B\"]\n\n*\n",
+            "1. e4 e5 *\n\n{Annotated} 1. d4 d5 *\n\n e4 c5 *\n\n0-0 *\n",
+            "[Event \"A\"] [Result \"1-0\"] 1. e4 1-0\n[Event \"B\"]\n\n1. d4\n",
+        ] {
+            assert_eq!(reason(sample), None, "{sample:?}");
+        }
+    }
+
+    #[test]
+    fn test_validate_pgn_sample_rejects_non_pgn() {
+        assert_eq!(
+            reason("<!DOCTYPE html>\n<html><body>Not Found</body></html>\n"),
+            Some((0, "not a tag pair or movetext"))
+        );
+        assert_eq!(
+            reason("{\"id\":\"a1\",\"moves\":\"e4 e5\"}\n{\"id\":\"b2\"}\n"),
+            Some((0, "no tag pairs or moves outside comments"))
+        );
+        assert_eq!(reason("[{\"id\": 1}]\n"), Some((0, "malformed tag pair")));
+        assert_eq!(
+            reason("id,white,black\n1,a,b\n"),
+            Some((0, "not a tag pair or movetext"))
+        );
+        assert_eq!(
+            reason("[Event \"A\"]\n\n1. e4 *\n\n<html>\n"),
+            Some((22, "not a tag pair or movetext"))
+        );
+        assert_eq!(reason("[Event \"A\"]\0"), Some((11, "binary data")));
+    }
+
+    #[test]
+    fn test_validate_pgn_sample_quotes_offending_bytes() {
+        let problem = validate_pgn_sample(b"\n<html>\r\n<head>", 1).unwrap_err();
+        assert_eq!(problem.offset, 1);
+        assert_eq!(problem.bytes, "<html>\\r\\n<head>");
+    }
+
+    #[test]
+    fn test_validate_pgn_sample_stops_after_max_games() {
+        assert_eq!(validate_pgn_sample(b"1. e4 *\n\n<html>\n", 1), Ok(()));
+    }
+}
@@ -0,0 +1,132 @@
+use super::reader::{READ_PGN_DEFAULT_SCHEMA_VERSION, READ_PGN_SCHEMA_VERSION, read_pgn_columns};
+use duckdb::{
+    core::{DataChunkHandle, Inserter, LogicalTypeHandle, LogicalTypeId},
+    vtab::{BindInfo, InitInfo, TableFunctionInfo, VTab},
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const EXTENSION_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub struct DuckdbChessVersionInitData {
+    done: AtomicBool,
+}
+
+pub struct DuckdbChessVersionVTab;
+
+/// One `duckdb_chess_version()` row: the `read_pgn` layout for a single schema version.
+#[derive(Debug, PartialEq, Eq)]
+struct SchemaVersionRow {
+    schema_version: u32,
+    is_default: bool,
+    columns: Vec<&'static str>,
+}
+
+fn schema_version_rows() -> Vec<SchemaVersionRow> {
+    (1..=READ_PGN_SCHEMA_VERSION)
+        .map(|schema_version| SchemaVersionRow {
+            schema_version,
+            is_default: schema_version == READ_PGN_DEFAULT_SCHEMA_VERSION,
+            columns: read_pgn_columns(schema_version)
+                .map(|column| column.name)
+                .collect(),
+        })
+        .collect()
+}
+
+impl VTab for DuckdbChessVersionVTab {
+    type InitData = DuckdbChessVersionInitData;
+    type BindData = ();
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        bind.add_result_column(
+            "extension_version",
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        );
+        bind.add_result_column(
+            "schema_version",
+            LogicalTypeHandle::from(LogicalTypeId::Integer),
+        );
+        bind.add_result_column(
+            "is_default",
+            LogicalTypeHandle::from(LogicalTypeId::Boolean),
+        );
+        bind.add_result_column(
+            "columns",
+            LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+        );
+        Ok(())
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(DuckdbChessVersionInitData {
+            done: AtomicBool::new(false),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if func.get_init_data().done.swap(true, Ordering::Relaxed) {
+            output.set_len(0);
+            return Ok(());
+        }
+
+        let rows = schema_version_rows();
+        let total_columns: usize = rows.iter().map(|row| row.columns.len()).sum();
+
+        let version_vec = output.flat_vector(0);
+        let mut schema_vec = output.flat_vector(1);
+        let mut default_vec = output.flat_vector(2);
+        let mut columns_vec = output.list_vector(3);
+        let column_names = columns_vec.child(total_columns);
+
+        let mut offset = 0;
+        for (row_idx, row) in rows.iter().enumerate() {
+            version_vec.insert(row_idx, EXTENSION_VERSION);
+            schema_vec.as_mut_slice::<i32>()[row_idx] = row.schema_version as i32;
+            default_vec.as_mut_slice::<bool>()[row_idx] = row.is_default;
+
+            columns_vec.set_entry(row_idx, offset, row.columns.len());
+            for name in &row.columns {
+                column_names.insert(offset, *name);
+                offset += 1;
+            }
+        }
+        columns_vec.set_len(offset);
+        output.set_len(rows.len());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_version_rows_cover_every_version() {
+        let rows = schema_version_rows();
+        assert_eq!(rows.len(), READ_PGN_SCHEMA_VERSION as usize);
+        assert_eq!(rows[0].schema_version, 1);
+        assert_eq!(
+            rows.iter().filter(|row| row.is_default).count(),
+            1,
+            "exactly one schema version is the default"
+        );
+    }
+
+    #[test]
+    fn test_schema_version_rows_list_read_pgn_columns_in_order() {
+        let rows = schema_version_rows();
+        let first = &rows[0].columns;
+        assert_eq!(first.first(), Some(&"Event"));
+        assert_eq!(first.last(), Some(&"Source"));
+        assert_eq!(first.len(), 18);
+
+        // Later schema versions only ever append columns.
+        for pair in rows.windows(2) {
+            assert!(pair[1].columns.starts_with(&pair[0].columns));
+        }
+    }
+}
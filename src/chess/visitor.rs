@@ -1,16 +1,22 @@
-use super::types::GameRecord;
+use super::boundary::{
+    CountedThrough, GAME_LINE_TAG, GAME_OFFSET_TAG, GameBoundaryScanner, LineDiagnostics,
+    MovetextSizes, RECOVERED_BOUNDARY_TAG, RECOVERED_BOUNDARY_WARNING, ScanProgress,
+};
+use super::openings::bundled_book;
+use super::types::{
+    ExtraTagColumn, ExtraTagType, ExtraTagValue, GameRecord, MoveRecord, PgnDate, PgnTimeTz,
+};
 use crate::chess::ErrorAccumulator;
-#[cfg(not(test))]
-use libduckdb_sys::duckdb_create_time_tz;
-use libduckdb_sys::{duckdb_date, duckdb_time_tz};
 
 use chrono::{Datelike, NaiveDate, NaiveTime, Timelike};
-use pgn_reader::{Outcome, RawComment, RawTag, Reader, SanPlus, Skip, Visitor};
+use pgn_reader::{Nag, Outcome, RawComment, RawTag, Reader, SanPlus, Skip, Visitor};
+use shakmaty::{CastlingMode, Chess, Position, fen::Fen, san::San};
 use std::fmt::Write;
 use std::io::Read;
 use std::mem;
 use std::ops::ControlFlow;
-use std::sync::LazyLock;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, LazyLock};
 
 static EPOCH: LazyLock<NaiveDate> = LazyLock::new(|| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap());
 
@@ -43,11 +49,233 @@ macro_rules! pgn_visitor_skip_variations {
     };
 }
 
-#[cfg(not(test))]
-#[inline]
-fn create_time_tz(micros: i64, offset_seconds: i32) -> duckdb_time_tz {
-    // SAFETY: Only called inside DuckDB (API initialized).
-    unsafe { duckdb_create_time_tz(micros, offset_seconds) }
+/// Scan-time game filter applied right after the tag section is read.
+///
+/// Games rejected by the filter skip movetext parsing entirely and produce no record.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct GameFilter {
+    pub min_elo: Option<i64>,
+    pub max_elo: Option<i64>,
+    pub variant: VariantFilter,
+}
+
+impl GameFilter {
+    pub fn new(min_elo: Option<i64>, max_elo: Option<i64>) -> Result<Self, String> {
+        if let (Some(min), Some(max)) = (min_elo, max_elo)
+            && min > max
+        {
+            return Err(format!(
+                "Invalid Elo range: min_elo ({}) is greater than max_elo ({}).",
+                min, max
+            ));
+        }
+        Ok(Self {
+            min_elo,
+            max_elo,
+            variant: VariantFilter::default(),
+        })
+    }
+
+    pub fn with_variant(self, variant: VariantFilter) -> Self {
+        Self { variant, ..self }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.min_elo.is_some() || self.max_elo.is_some()
+    }
+
+    fn elo_in_range(&self, raw: &str, elo_lenient: bool) -> bool {
+        let digits = if elo_lenient {
+            lenient_elo_digits(raw)
+        } else {
+            raw.trim()
+        };
+        let Ok(elo) = digits.parse::<u32>() else {
+            return false;
+        };
+        let elo = i64::from(elo);
+        self.min_elo.is_none_or(|min| elo >= min) && self.max_elo.is_none_or(|max| elo <= max)
+    }
+
+    /// Both players must have a parseable Elo within the configured bounds.
+    fn accepts(&self, headers: &HeaderFields, elo_lenient: bool) -> bool {
+        if !self.is_active() {
+            return true;
+        }
+
+        self.elo_in_range(&headers.white_elo, elo_lenient)
+            && self.elo_in_range(&headers.black_elo, elo_lenient)
+    }
+
+    /// Whether a game with these `Variant` and `FEN` tags passes the variant filter.
+    fn accepts_variant(&self, variant_tag: &str, fen_tag: &str) -> bool {
+        self.variant == VariantFilter::All || is_standard_chess(variant_tag, fen_tag)
+    }
+}
+
+/// Which rule sets `read_pgn` keeps (`variant := ...`).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum VariantFilter {
+    /// Keep every game.
+    #[default]
+    All,
+    /// Drop games played under other rules, see [`is_standard_chess`].
+    Standard,
+}
+
+pub const SUPPORTED_VARIANT_VALUES: &str = "standard, all";
+
+impl VariantFilter {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let normalized = raw.trim();
+        [("standard", Self::Standard), ("all", Self::All)]
+            .into_iter()
+            .find(|(name, _)| normalized.eq_ignore_ascii_case(name))
+            .map(|(_, variant)| variant)
+            .ok_or_else(|| {
+                format!(
+                    "Invalid variant value '{}'. Supported values: {}.",
+                    normalized, SUPPORTED_VARIANT_VALUES
+                )
+            })
+    }
+}
+
+/// `Variant` tag values of standard chess games. Lichess writes `Standard`, or `From Position`
+/// for games from a custom setup; most other sources leave the tag out.
+const STANDARD_VARIANT_TAGS: [&str; 4] = ["standard", "normal", "chess", "from position"];
+
+/// Whether a game with these `Variant` and `FEN` tags is played under standard rules.
+/// Spec: pgn-parsing - Variant Filter
+///
+/// A `Variant` tag other than [`STANDARD_VARIANT_TAGS`] marks a variant game, and so does a
+/// `FEN` whose castling rights need Chess960 castling (e.g. `HAha`, or a rook off its corner),
+/// which catches Chess960 games exported without a `Variant` tag.
+fn is_standard_chess(variant_tag: &str, fen_tag: &str) -> bool {
+    let variant = variant_tag.trim();
+    if !variant.is_empty()
+        && !STANDARD_VARIANT_TAGS
+            .iter()
+            .any(|standard| variant.eq_ignore_ascii_case(standard))
+    {
+        return false;
+    }
+    fen_tag
+        .trim()
+        .parse::<Fen>()
+        .ok()
+        .is_none_or(|fen| CastlingMode::detect(fen.as_setup()) == CastlingMode::Standard)
+}
+
+/// Elo digits under `elo_lenient := true`: provisional markers such as `2100?` or `1500*` are
+/// stripped, and unknown ratings (`?`, `-`, empty) become an empty string.
+fn lenient_elo_digits(raw: &str) -> &str {
+    let digits = raw.trim().trim_end_matches(['?', '*']).trim_end();
+    if digits == "-" { "" } else { digits }
+}
+
+/// How mainline moves are rendered into the `movetext` column (`movetext_style := ...`).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MovetextStyle {
+    /// `1. e4 e5 2. Nf3`
+    #[default]
+    Numbered,
+    /// `e4 e5 Nf3`
+    Plain,
+    /// `e2e4 e7e5 g1f3`; the game is replayed from its `FEN` tag or the standard start.
+    Uci,
+}
+
+pub const SUPPORTED_MOVETEXT_STYLE_VALUES: &str = "numbered, plain, uci";
+
+impl MovetextStyle {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let normalized = raw.trim();
+        [
+            ("numbered", Self::Numbered),
+            ("plain", Self::Plain),
+            ("uci", Self::Uci),
+        ]
+        .into_iter()
+        .find(|(name, _)| normalized.eq_ignore_ascii_case(name))
+        .map(|(_, style)| style)
+        .ok_or_else(|| {
+            format!(
+                "Invalid movetext_style value '{}'. Supported values: {}.",
+                normalized, SUPPORTED_MOVETEXT_STYLE_VALUES
+            )
+        })
+    }
+}
+
+/// Type of the `movetext` column (`moves_as := ...`).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MovesAs {
+    /// `VARCHAR` movetext rendered per `MovetextStyle`.
+    #[default]
+    Text,
+    /// `LIST(VARCHAR)` of mainline SAN.
+    List,
+    /// `LIST(STRUCT(san VARCHAR, comment VARCHAR, nag UTINYINT))` of mainline moves.
+    Struct,
+}
+
+pub const SUPPORTED_MOVES_AS_VALUES: &str = "text, list, struct";
+
+impl MovesAs {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let normalized = raw.trim();
+        [
+            ("text", Self::Text),
+            ("list", Self::List),
+            ("struct", Self::Struct),
+        ]
+        .into_iter()
+        .find(|(name, _)| normalized.eq_ignore_ascii_case(name))
+        .map(|(_, moves_as)| moves_as)
+        .ok_or_else(|| {
+            format!(
+                "Invalid moves_as value '{}'. Supported values: {}.",
+                normalized, SUPPORTED_MOVES_AS_VALUES
+            )
+        })
+    }
+}
+
+/// Per-scan options shared by every reader of a `read_pgn` call.
+#[derive(Clone, Debug, Default)]
+pub struct ScanOptions {
+    pub filter: GameFilter,
+    /// Additional tag-backed columns requested via `columns := {...}`.
+    pub extra_columns: Arc<[ExtraTagColumn]>,
+    /// Offset in the decompressed input where reading starts (`start_offset := ...`).
+    pub start_offset: u64,
+    /// Record each game's `byte_offset`; off unless the column is projected by the schema.
+    pub track_byte_offsets: bool,
+    /// Measure each game's raw `movetext_bytes`; off unless the column is projected.
+    pub track_movetext_bytes: bool,
+    /// Record each game's `line_number` and add it to `parse_error` messages. Lines count from
+    /// `start_offset`, so `read_pgn` only sets this when reading from the start.
+    pub track_line_numbers: bool,
+    /// Skip movetext parsing when no projected column reads it. Records then carry an empty
+    /// `movetext` and no annotation flags. Games without a `Result` tag are still parsed, so
+    /// `result` can fall back to the movetext's result marker.
+    pub skip_movetext: bool,
+    pub movetext_style: MovetextStyle,
+    /// Collect `GameRecord::moves` for a `LIST` typed `movetext` column.
+    pub moves_as: MovesAs,
+    /// `elo_lenient := true`: read Elo tags through `lenient_elo_digits`.
+    pub elo_lenient: bool,
+    /// `elo_raw_columns := true`: keep the raw `WhiteElo` / `BlackElo` tag values.
+    pub elo_raw_columns: bool,
+    /// `skip_empty_games := true`: drop games without mainline moves, such as forfeits and
+    /// byes. Games with a parse error are kept so the error stays visible.
+    pub skip_empty_games: bool,
+    /// `eco_backfill := true`: classify games missing an `ECO` or `Opening` tag with the
+    /// bundled opening book and fill the missing values in.
+    pub eco_backfill: bool,
+    /// Counters of the line-layout repairs made by the boundary scanners of this scan.
+    pub line_diagnostics: Arc<LineDiagnostics>,
 }
 
 /// Streaming PGN visitor (pgn-reader).
@@ -59,9 +287,39 @@ fn create_time_tz(micros: i64, offset_seconds: i32) -> duckdb_time_tz {
 pub struct GameVisitor {
     headers: HeaderFields,
     movetext_buffer: String,
+    /// Mainline moves, only collected when `options.moves_as` is not `MovesAs::Text`.
+    moves: Vec<MoveRecord>,
     move_count: u32,
     result_marker: Option<String>,
     parse_error: ErrorAccumulator,
+    /// Non-fatal adjustments made while converting tags, such as clamped dates.
+    warnings: Vec<String>,
+    options: ScanOptions,
+    /// Raw values of `options.extra_columns`, empty when the tag is missing.
+    extra_raw: Vec<String>,
+    byte_offset: Option<u64>,
+    line_number: Option<u64>,
+    /// Raw movetext sizes from this reader's boundary scanner, keyed by `byte_offset`.
+    movetext_sizes: Option<MovetextSizes>,
+    has_clk: bool,
+    has_eval: bool,
+    has_variations: bool,
+    /// Invalid UTF-8 sequences replaced with U+FFFD in the game's tags and comments.
+    encoding_issues: u32,
+    /// `FEN` tag, only captured for `MovetextStyle::Uci`, `VariantFilter::Standard` and
+    /// `eco_backfill`.
+    fen_tag: String,
+    /// `Variant` tag, only captured for `VariantFilter::Standard`.
+    variant_tag: String,
+    /// Board replayed for `MovetextStyle::Uci`; `None` once a move could not be converted.
+    position: Option<Chess>,
+    /// Leading mainline moves for `eco_backfill`, up to the longest line of the opening book.
+    opening_sans: Vec<San>,
+    /// Games still to read that an evicted reader of this input already read, whose skips
+    /// were counted then.
+    replayed_games: usize,
+    /// Whether skipping the current game counts in `options.line_diagnostics`.
+    counts_skips: bool,
     pub current_game: Option<GameRecord>,
 }
 
@@ -129,27 +387,145 @@ impl HeaderFields {
             return;
         }
 
-        let bytes = value.as_bytes();
-        if bytes.is_empty() {
+        if value.as_bytes().is_empty() {
             return;
         }
 
-        *slot = String::from_utf8_lossy(bytes).into_owned();
+        *slot = value.decode_utf8_lossy().into_owned();
+    }
+}
+
+/// Invalid UTF-8 sequences in `bytes`, each of which lossy decoding replaces with one U+FFFD.
+fn invalid_utf8_sequences(bytes: &[u8]) -> u32 {
+    if std::str::from_utf8(bytes).is_ok() {
+        return 0;
     }
+    bytes
+        .utf8_chunks()
+        .filter(|chunk| !chunk.invalid().is_empty())
+        .count() as u32
 }
 
 impl GameVisitor {
+    #[cfg(test)]
     pub fn new() -> Self {
+        Self::with_options(ScanOptions::default())
+    }
+
+    pub fn with_options(options: ScanOptions) -> Self {
+        let extra_raw = vec![String::new(); options.extra_columns.len()];
         Self {
             headers: HeaderFields::default(),
             movetext_buffer: String::new(),
+            moves: Vec::new(),
             move_count: 0,
             result_marker: None,
             parse_error: ErrorAccumulator::default(),
+            warnings: Vec::new(),
+            options,
+            extra_raw,
+            byte_offset: None,
+            line_number: None,
+            movetext_sizes: None,
+            has_clk: false,
+            has_eval: false,
+            has_variations: false,
+            encoding_issues: 0,
+            fen_tag: String::new(),
+            variant_tag: String::new(),
+            position: None,
+            opening_sans: Vec::new(),
+            replayed_games: 0,
+            counts_skips: true,
             current_game: None,
         }
     }
 
+    /// Starting board for UCI rendering, from the `FEN` tag when present.
+    fn start_position(&self) -> Result<Chess, String> {
+        let fen = self.fen_tag.trim();
+        if fen.is_empty() {
+            return Ok(Chess::default());
+        }
+
+        fen.parse::<Fen>()
+            .map_err(|err| err.to_string())
+            .and_then(|parsed| {
+                let mode = CastlingMode::detect(parsed.as_setup());
+                parsed
+                    .into_position::<Chess>(mode)
+                    .map_err(|err| err.to_string())
+            })
+            .map_err(|err| format!("Invalid FEN tag '{fen}': {err}; moves not rendered as UCI"))
+    }
+
+    fn write_uci_move(&mut self, movetext: &mut String, san: &SanPlus) {
+        let Some(pos) = self.position.as_mut() else {
+            return;
+        };
+        match san.san.to_move(pos) {
+            Ok(m) => {
+                if !movetext.is_empty() {
+                    movetext.push(' ');
+                }
+                let _ = write!(movetext, "{}", m.to_uci(pos.castles().mode()));
+                pos.play_unchecked(m);
+            }
+            Err(_) => {
+                self.parse_error.push(&format!(
+                    "Illegal move '{}' at ply {}; remaining moves not rendered as UCI",
+                    san,
+                    self.move_count + 1
+                ));
+                self.position = None;
+            }
+        }
+    }
+
+    fn set_extra_tag(&mut self, key: &[u8], value: RawTag<'_>) {
+        for (column, slot) in self.options.extra_columns.iter().zip(&mut self.extra_raw) {
+            if column.tag.as_bytes() == key && slot.is_empty() {
+                *slot = value.decode_utf8_lossy().into_owned();
+            }
+        }
+    }
+
+    fn parse_extra_tag(
+        raw: &str,
+        column: &ExtraTagColumn,
+        parse_error: &mut ErrorAccumulator,
+        warnings: &mut Vec<String>,
+    ) -> Option<ExtraTagValue> {
+        let s = raw.trim();
+        if s.is_empty() {
+            return None;
+        }
+
+        let label = column.tag.as_str();
+        let value = match column.tag_type {
+            ExtraTagType::Varchar => return Some(ExtraTagValue::Varchar(raw.to_string())),
+            ExtraTagType::Date => {
+                return Self::parse_date_field(s, label, parse_error, warnings)
+                    .map(ExtraTagValue::Date);
+            }
+            ExtraTagType::Boolean => match s.to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" => Some(ExtraTagValue::Boolean(true)),
+                "false" | "0" | "no" => Some(ExtraTagValue::Boolean(false)),
+                _ => None,
+            },
+            ExtraTagType::Integer => s.parse().ok().map(ExtraTagValue::Integer),
+            ExtraTagType::BigInt => s.parse().ok().map(ExtraTagValue::BigInt),
+            ExtraTagType::UInteger => s.parse().ok().map(ExtraTagValue::UInteger),
+            ExtraTagType::UBigInt => s.parse().ok().map(ExtraTagValue::UBigInt),
+            ExtraTagType::Double => s.parse().ok().map(ExtraTagValue::Double),
+        };
+
+        if value.is_none() {
+            parse_error.push(&format!("Conversion error: {label}='{s}'"));
+        }
+        value
+    }
+
     fn normalize_date_separators(s: &str) -> String {
         let s = s.trim();
         if s.contains('.') {
@@ -237,9 +613,10 @@ impl GameVisitor {
         date: Option<&str>,
         event_date: Option<&str>,
         parse_error: &mut ErrorAccumulator,
-    ) -> Option<duckdb_date> {
+        warnings: &mut Vec<String>,
+    ) -> Option<PgnDate> {
         for (raw, label) in Self::rank_date_candidates(utc_date, date, event_date) {
-            if let Some(parsed) = Self::parse_date_field(raw, label, parse_error) {
+            if let Some(parsed) = Self::parse_date_field(raw, label, parse_error, warnings) {
                 return Some(parsed);
             }
         }
@@ -247,11 +624,14 @@ impl GameVisitor {
         None
     }
 
+    /// Parse a PGN date. Unknown months and days read as 01 and days past the end of the month
+    /// are clamped to it, each recorded in `warnings` when the date is returned.
     fn parse_date_field(
         raw: &str,
         label: &str,
         parse_error: &mut ErrorAccumulator,
-    ) -> Option<duckdb_date> {
+        warnings: &mut Vec<String>,
+    ) -> Option<PgnDate> {
         let s = raw.trim();
         if s.is_empty() {
             return None;
@@ -318,7 +698,8 @@ impl GameVisitor {
             return None;
         };
 
-        if day > last_day {
+        let clamped = day > last_day;
+        if clamped {
             day = last_day;
         }
 
@@ -350,7 +731,15 @@ impl GameVisitor {
             }
         };
 
-        Some(duckdb_date { days })
+        if clamped {
+            warnings.push(format!("Clamped {label}='{s}' to {date}"));
+        } else if parts[1].contains('?') || parts[2].contains('?') {
+            warnings.push(format!(
+                "Defaulted unknown month/day of {label}='{s}' to {date}"
+            ));
+        }
+
+        Some(PgnDate { days })
     }
 
     fn parse_uinteger_field(
@@ -371,11 +760,33 @@ impl GameVisitor {
         }
     }
 
+    fn parse_elo_field(
+        raw: &str,
+        label: &str,
+        elo_lenient: bool,
+        parse_error: &mut ErrorAccumulator,
+        warnings: &mut Vec<String>,
+    ) -> Option<u32> {
+        let digits = if elo_lenient {
+            lenient_elo_digits(raw)
+        } else {
+            raw
+        };
+        let elo =
+            Self::parse_uinteger_field((!digits.is_empty()).then_some(digits), label, parse_error);
+        if let Some(elo) = elo
+            && digits != raw.trim()
+        {
+            warnings.push(format!("Stripped {label}='{}' to {elo}", raw.trim()));
+        }
+        elo
+    }
+
     fn parse_time_tz_field(
         raw: &str,
         label: &str,
         parse_error: &mut ErrorAccumulator,
-    ) -> Option<duckdb_time_tz> {
+    ) -> Option<PgnTimeTz> {
         let s = raw.trim();
         if s.is_empty() {
             return None;
@@ -431,7 +842,7 @@ impl GameVisitor {
         utc_time: Option<&str>,
         time: Option<&str>,
         parse_error: &mut ErrorAccumulator,
-    ) -> Option<duckdb_time_tz> {
+    ) -> Option<PgnTimeTz> {
         if let Some(raw) = utc_time
             && let Some(parsed) = Self::parse_time_tz_field(raw, "UTCTime", parse_error)
         {
@@ -447,20 +858,15 @@ impl GameVisitor {
         None
     }
 
-    #[cfg(not(test))]
-    fn pack_time_tz(micros: i64, offset_seconds: i32) -> duckdb_time_tz {
-        create_time_tz(micros, offset_seconds)
-    }
-
-    #[cfg(test)]
-    fn pack_time_tz(micros: i64, offset_seconds: i32) -> duckdb_time_tz {
-        // Unit tests run without DuckDB initializing the C API.
+    /// Same packing as DuckDB's `duckdb_create_time_tz`, computed here so the parser also runs
+    /// outside a loaded extension (`PgnGameIter`, the pure-Rust core and unit tests).
+    pub(crate) fn pack_time_tz(micros: i64, offset_seconds: i32) -> PgnTimeTz {
         const OFFSET_SENTINEL_SECONDS: i32 = 16 * 60 * 60 - 1; // 15:59:59
         let encoded_offset = OFFSET_SENTINEL_SECONDS - offset_seconds;
 
         let micros_part = (micros as u64) & ((1u64 << 40) - 1);
         let offset_part = (encoded_offset as i64 as u64) & ((1u64 << 24) - 1);
-        duckdb_time_tz {
+        PgnTimeTz {
             bits: (micros_part << 24) | offset_part,
         }
     }
@@ -477,22 +883,36 @@ impl GameVisitor {
     }
 
     fn build_game_record(&mut self) {
-        let white_elo = Self::parse_uinteger_field(
-            (!self.headers.white_elo.is_empty()).then_some(self.headers.white_elo.as_str()),
+        let elo_lenient = self.options.elo_lenient;
+        let white_elo = Self::parse_elo_field(
+            &self.headers.white_elo,
             "WhiteElo",
+            elo_lenient,
             &mut self.parse_error,
+            &mut self.warnings,
         );
-        let black_elo = Self::parse_uinteger_field(
-            (!self.headers.black_elo.is_empty()).then_some(self.headers.black_elo.as_str()),
+        let black_elo = Self::parse_elo_field(
+            &self.headers.black_elo,
             "BlackElo",
+            elo_lenient,
             &mut self.parse_error,
+            &mut self.warnings,
         );
+        let (white_elo_raw, black_elo_raw) = if self.options.elo_raw_columns {
+            (
+                HeaderFields::opt_take(&mut self.headers.white_elo),
+                HeaderFields::opt_take(&mut self.headers.black_elo),
+            )
+        } else {
+            (None, None)
+        };
 
         let utc_date = Self::parse_best_date_field(
             (!self.headers.utc_date.is_empty()).then_some(self.headers.utc_date.as_str()),
             (!self.headers.date.is_empty()).then_some(self.headers.date.as_str()),
             (!self.headers.event_date.is_empty()).then_some(self.headers.event_date.as_str()),
             &mut self.parse_error,
+            &mut self.warnings,
         );
         let utc_time = Self::parse_best_time_tz_field(
             (!self.headers.utc_time.is_empty()).then_some(self.headers.utc_time.as_str()),
@@ -500,6 +920,19 @@ impl GameVisitor {
             &mut self.parse_error,
         );
 
+        let extra_tags = self
+            .options
+            .extra_columns
+            .iter()
+            .zip(&mut self.extra_raw)
+            .map(|(column, raw)| {
+                let value =
+                    Self::parse_extra_tag(raw, column, &mut self.parse_error, &mut self.warnings);
+                raw.clear();
+                value
+            })
+            .collect();
+
         let movetext = {
             let needs_trim = {
                 let trimmed = self.movetext_buffer.trim();
@@ -515,6 +948,26 @@ impl GameVisitor {
             }
         };
 
+        if self.encoding_issues > 0 {
+            self.parse_error.push(&format!(
+                "Replaced {} invalid UTF-8 sequence(s) with U+FFFD",
+                self.encoding_issues
+            ));
+        }
+
+        // Point at the game in the source so a reported problem can be found again.
+        if !self.parse_error.is_empty()
+            && let Some(line) = self.line_number
+        {
+            let mut location = format!("Game location: line={line}");
+            if let Some(offset) = self.byte_offset {
+                let _ = write!(location, ", byte_offset={offset}");
+            }
+            self.parse_error.push(&location);
+        }
+
+        let (eco, opening, eco_inferred) = self.backfilled_opening();
+
         self.current_game = Some(GameRecord {
             event: HeaderFields::opt_take(&mut self.headers.event),
             site: HeaderFields::opt_take(&mut self.headers.site),
@@ -527,17 +980,60 @@ impl GameVisitor {
             black_title: HeaderFields::opt_take(&mut self.headers.black_title),
             white_elo,
             black_elo,
+            white_elo_raw,
+            black_elo_raw,
             utc_date,
             utc_time,
-            eco: HeaderFields::opt_take(&mut self.headers.eco),
-            opening: HeaderFields::opt_take(&mut self.headers.opening),
+            eco,
+            opening,
             termination: HeaderFields::opt_take(&mut self.headers.termination),
             time_control: HeaderFields::opt_take(&mut self.headers.time_control),
             movetext,
+            moves: mem::take(&mut self.moves),
+            extra_tags,
+            movetext_bytes: self
+                .movetext_sizes
+                .as_ref()
+                .zip(self.byte_offset)
+                .and_then(|(sizes, offset)| sizes.take(offset)),
+            has_clk: self.has_clk,
+            has_eval: self.has_eval,
+            has_variations: self.has_variations,
+            encoding_issues: self.encoding_issues,
+            eco_inferred,
+            byte_offset: self.byte_offset.take(),
+            line_number: self.line_number.take(),
+            game_id: None,
+            warnings: mem::take(&mut self.warnings),
             parse_error: self.parse_error.take(),
         });
     }
 
+    /// The game's `ECO` and `Opening` tags, with missing or `?` values classified from the
+    /// moves when `eco_backfill` is set, and whether any value was filled in. Games set up from
+    /// a `FEN` are not classified.
+    fn backfilled_opening(&mut self) -> (Option<String>, Option<String>, bool) {
+        let is_missing = |tag: &Option<String>| tag.as_deref().is_none_or(|v| v.trim() == "?");
+        let mut eco = HeaderFields::opt_take(&mut self.headers.eco);
+        let mut opening = HeaderFields::opt_take(&mut self.headers.opening);
+        if !self.options.eco_backfill
+            || !self.fen_tag.trim().is_empty()
+            || !(is_missing(&eco) || is_missing(&opening))
+        {
+            return (eco, opening, false);
+        }
+        let Some(found) = bundled_book().opening_of_sans(&self.opening_sans) else {
+            return (eco, opening, false);
+        };
+        if is_missing(&eco) {
+            eco = Some(found.eco.to_string());
+        }
+        if is_missing(&opening) {
+            opening = Some(found.name.to_string());
+        }
+        (eco, opening, true)
+    }
+
     fn finalize_game(&mut self) {
         self.build_game_record();
     }
@@ -549,6 +1045,24 @@ impl GameVisitor {
     }
 }
 
+/// Date of a game from its `UTCDate`, `Date` and `EventDate` tags, chosen like `read_pgn`'s
+/// `UTCDate` column: the most complete candidate that parses wins, ties go to `UTCDate`, then
+/// `Date`, then `EventDate`. Missing or blank tags are `None` or empty.
+/// Spec: pgn-parsing - Date Inference
+pub fn infer_date(
+    utc_date: Option<&str>,
+    date: Option<&str>,
+    event_date: Option<&str>,
+) -> Option<PgnDate> {
+    GameVisitor::parse_best_date_field(
+        utc_date,
+        date,
+        event_date,
+        &mut ErrorAccumulator::default(),
+        &mut Vec::new(),
+    )
+}
+
 pub type PgnInput = Box<dyn Read + Send>;
 
 pub struct PgnReaderState {
@@ -557,23 +1071,122 @@ pub struct PgnReaderState {
     pub next_game_index: usize,
     pub record_buffer: GameRecord,
     pub visitor: GameVisitor,
+    /// Where to reopen the file after the last returned game, `None` before the first one or
+    /// when that game had no tracked offset.
+    pub resume_point: Option<ResumePoint>,
+    /// Whether the input can be reopened at `resume_point` without decoding everything before
+    /// it; `false` for compressed input.
+    pub seekable: bool,
+    scan_progress: Arc<ScanProgress>,
+}
+
+/// Location of a game a reader returned, so the reader can be closed and reopened there.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResumePoint {
+    /// Source offset of the game's first tag.
+    pub byte_offset: u64,
+    /// Source line of that tag, when lines are tracked.
+    pub line_number: Option<u64>,
+    /// 1-based index of the game in its file.
+    pub game_index: usize,
 }
 
 impl PgnReaderState {
-    pub fn new(input: PgnInput, path_idx: usize) -> Self {
+    pub fn new(input: PgnInput, path_idx: usize, options: ScanOptions) -> Self {
+        options
+            .line_diagnostics
+            .files
+            .fetch_add(1, Ordering::Relaxed);
+        Self::open(input, path_idx, options, 1, CountedThrough::default())
+    }
+
+    /// Reopen an evicted reader from `input` positioned at its resume point. The first game
+    /// read is the one that point describes, which the caller already returned. Repairs and
+    /// skips the evicted reader counted are not counted again.
+    pub fn resume(input: PgnInput, options: ScanOptions, suspended: SuspendedReader) -> Self {
+        let point = suspended.resume_point;
+        let options = ScanOptions {
+            start_offset: point.byte_offset,
+            ..options
+        };
+        let mut state = Self::open(
+            input,
+            suspended.path_idx,
+            options,
+            point.line_number.unwrap_or(1),
+            suspended.counted_through,
+        );
+        state.next_game_index = point.game_index;
+        state.visitor.replayed_games = suspended.next_game_index - point.game_index;
+        state
+    }
+
+    /// Where to reopen this reader once it is closed, `None` when it cannot be resumed
+    /// exactly (its last game had no offset) or cheaply (its input is compressed).
+    pub fn suspend(&self) -> Option<SuspendedReader> {
+        if !self.seekable {
+            return None;
+        }
+        Some(SuspendedReader {
+            path_idx: self.path_idx,
+            resume_point: self.resume_point?,
+            next_game_index: self.next_game_index,
+            counted_through: self.scan_progress.counted_through(),
+        })
+    }
+
+    fn open(
+        input: PgnInput,
+        path_idx: usize,
+        options: ScanOptions,
+        first_line: u64,
+        counted: CountedThrough,
+    ) -> Self {
+        let mut scanner =
+            GameBoundaryScanner::new(input, options.start_offset, options.track_byte_offsets)
+                .with_diagnostics(Arc::clone(&options.line_diagnostics))
+                .counting_from(counted);
+        let scan_progress = scanner.progress();
+        let movetext_sizes = options.track_movetext_bytes.then(MovetextSizes::default);
+        if let Some(sizes) = &movetext_sizes {
+            scanner = scanner.with_movetext_sizes(sizes.clone());
+        }
+
+        if options.track_line_numbers {
+            scanner = scanner.with_line_numbers().starting_at_line(first_line);
+        }
+
+        let mut visitor = GameVisitor::with_options(options);
+        visitor.movetext_sizes = movetext_sizes;
         Self {
-            pgn_reader: Reader::new(input),
+            pgn_reader: Reader::new(Box::new(scanner)),
             path_idx,
             next_game_index: 1,
             record_buffer: GameRecord::default(),
-            visitor: GameVisitor::new(),
+            visitor,
+            resume_point: None,
+            seekable: true,
+            scan_progress,
         }
     }
 }
 
+/// A reader closed to stay within `duckdb_chess_max_open_files`, reopened on demand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SuspendedReader {
+    pub path_idx: usize,
+    pub resume_point: ResumePoint,
+    /// Index the next game read would have had, past any games skipped after the last one
+    /// returned.
+    pub next_game_index: usize,
+    pub counted_through: CountedThrough,
+}
+
 pub struct SharedState {
     pub next_path_idx: usize,
+    /// Idle open readers, least recently used first.
     pub available_readers: Vec<PgnReaderState>,
+    pub suspended_readers: Vec<SuspendedReader>,
 }
 
 impl Visitor for GameVisitor {
@@ -584,10 +1197,25 @@ impl Visitor for GameVisitor {
     fn begin_tags(&mut self) -> ControlFlow<Self::Output, Self::Tags> {
         self.headers.clear();
         self.movetext_buffer.clear();
+        self.moves.clear();
         self.move_count = 0;
         self.result_marker = None;
         self.parse_error = ErrorAccumulator::default();
+        self.warnings.clear();
         self.current_game = None;
+        self.extra_raw.iter_mut().for_each(String::clear);
+        self.byte_offset = None;
+        self.line_number = None;
+        self.has_clk = false;
+        self.has_eval = false;
+        self.has_variations = false;
+        self.encoding_issues = 0;
+        self.fen_tag.clear();
+        self.variant_tag.clear();
+        self.position = None;
+        self.opening_sans.clear();
+        self.counts_skips = self.replayed_games == 0;
+        self.replayed_games = self.replayed_games.saturating_sub(1);
         ControlFlow::Continue(())
     }
 
@@ -597,38 +1225,150 @@ impl Visitor for GameVisitor {
         key: &[u8],
         value: RawTag<'_>,
     ) -> ControlFlow<Self::Output> {
+        // PGN allows any whitespace between `[`, the name and the value; pgn-reader keeps all
+        // but a single space before the quote as part of the name.
+        let key = key.trim_ascii();
+        if key == RECOVERED_BOUNDARY_TAG {
+            self.parse_error.push(RECOVERED_BOUNDARY_WARNING);
+            return ControlFlow::Continue(());
+        }
+        if key == GAME_OFFSET_TAG || key == GAME_LINE_TAG {
+            // The injected markers always lead the tag section; ignore look-alikes in the input.
+            let slot = if key == GAME_OFFSET_TAG {
+                &mut self.byte_offset
+            } else {
+                &mut self.line_number
+            };
+            if slot.is_none() {
+                *slot = std::str::from_utf8(value.as_bytes())
+                    .ok()
+                    .and_then(|raw| raw.parse().ok());
+            }
+            return ControlFlow::Continue(());
+        }
+
+        self.encoding_issues += invalid_utf8_sequences(value.as_bytes());
         self.headers.set_known_tag(key, value);
+        let filter_variants = self.options.filter.variant == VariantFilter::Standard;
+        if (filter_variants
+            || self.options.movetext_style == MovetextStyle::Uci
+            || self.options.eco_backfill)
+            && key == b"FEN"
+        {
+            self.fen_tag = value.decode_utf8_lossy().into_owned();
+        }
+        if filter_variants && key == b"Variant" {
+            self.variant_tag = value.decode_utf8_lossy().into_owned();
+        }
+        if !self.extra_raw.is_empty() {
+            self.set_extra_tag(key, value);
+        }
         ControlFlow::Continue(())
     }
 
     fn begin_movetext(&mut self, _: Self::Tags) -> ControlFlow<Self::Output, Self::Movetext> {
+        // Rejected games leave `current_game` empty; the reader skips their movetext.
+        if !self
+            .options
+            .filter
+            .accepts_variant(&self.variant_tag, &self.fen_tag)
+        {
+            if self.counts_skips {
+                self.options
+                    .line_diagnostics
+                    .variant_games_skipped
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+            return ControlFlow::Break(());
+        }
+        if !self
+            .options
+            .filter
+            .accepts(&self.headers, self.options.elo_lenient)
+        {
+            return ControlFlow::Break(());
+        }
+
+        // Late materialization: the record is complete without the moves, so let the reader
+        // skip the movetext bytes instead of tokenizing them.
+        if self.options.skip_movetext && !self.headers.result.is_empty() {
+            self.finalize_game();
+            return ControlFlow::Break(());
+        }
+
+        if self.options.movetext_style == MovetextStyle::Uci {
+            match self.start_position() {
+                Ok(pos) => self.position = Some(pos),
+                Err(err) => self.parse_error.push(&err),
+            }
+        }
+
         ControlFlow::Continue(String::with_capacity(256))
     }
 
     fn begin_variation(&mut self, _: &mut Self::Movetext) -> ControlFlow<Self::Output, Skip> {
+        self.has_variations = true;
         ControlFlow::Continue(Skip(true))
     }
 
     fn san(&mut self, movetext: &mut Self::Movetext, san: SanPlus) -> ControlFlow<Self::Output> {
-        if !movetext.is_empty() {
-            movetext.push(' ');
+        match self.options.movetext_style {
+            MovetextStyle::Uci => self.write_uci_move(movetext, &san),
+            style => {
+                if !movetext.is_empty() {
+                    movetext.push(' ');
+                }
+                if style == MovetextStyle::Numbered && self.move_count.is_multiple_of(2) {
+                    let _ = write!(movetext, "{}. ", (self.move_count / 2) + 1);
+                }
+                let _ = write!(movetext, "{}", san);
+            }
         }
-
-        if self.move_count.is_multiple_of(2) {
-            let _ = write!(movetext, "{}. ", (self.move_count / 2) + 1);
+        if self.options.eco_backfill && self.opening_sans.len() < bundled_book().max_plies() {
+            self.opening_sans.push(san.san);
+        }
+        if self.options.moves_as != MovesAs::Text {
+            self.moves.push(MoveRecord {
+                san: san.to_string(),
+                ..MoveRecord::default()
+            });
         }
-
-        let _ = write!(movetext, "{}", san);
         self.move_count += 1;
         ControlFlow::Continue(())
     }
 
+    fn nag(&mut self, _movetext: &mut Self::Movetext, nag: Nag) -> ControlFlow<Self::Output> {
+        if let Some(last) = self.moves.last_mut() {
+            last.nag.get_or_insert(nag.0);
+        }
+        ControlFlow::Continue(())
+    }
+
     fn comment(
         &mut self,
         movetext: &mut Self::Movetext,
         comment: RawComment<'_>,
     ) -> ControlFlow<Self::Output> {
-        let comment_str = String::from_utf8_lossy(comment.as_bytes());
+        let bytes = comment.as_bytes();
+        self.has_clk |= bytes.windows(5).any(|w| w == b"[%clk");
+        self.has_eval |= bytes.windows(6).any(|w| w == b"[%eval");
+
+        // Comments after an unconvertible move would be attached to the wrong position.
+        if self.options.movetext_style == MovetextStyle::Uci && self.position.is_none() {
+            return ControlFlow::Continue(());
+        }
+        self.encoding_issues += invalid_utf8_sequences(bytes);
+        let comment_str = String::from_utf8_lossy(bytes);
+        if let Some(last) = self.moves.last_mut() {
+            let text = comment_str.trim();
+            match &mut last.comment {
+                Some(existing) => {
+                    existing.push(' ');
+                    existing.push_str(text);
+                }
+                None => last.comment = Some(text.to_string()),
+            }
+        }
 
         if !movetext.is_empty() {
             movetext.push(' ');
@@ -658,6 +1398,16 @@ impl Visitor for GameVisitor {
             .or_else(|| HeaderFields::opt_take(&mut self.headers.result));
         self.result_marker = marker;
 
+        if self.options.skip_empty_games && self.move_count == 0 && self.parse_error.is_empty() {
+            if self.counts_skips {
+                self.options
+                    .line_diagnostics
+                    .empty_games_skipped
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+            return;
+        }
+
         self.movetext_buffer = movetext;
         self.finalize_game();
     }
@@ -667,6 +1417,38 @@ impl Visitor for GameVisitor {
 mod tests {
     use super::*;
     use pgn_reader::Reader;
+    use std::io;
+
+    fn date_days(year: i32, month: u32, day: u32) -> i32 {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .signed_duration_since(*EPOCH)
+            .num_days() as i32
+    }
+
+    #[test]
+    fn test_infer_date_prefers_complete_candidates_then_tag_order() {
+        let infer = |utc, date, event| infer_date(utc, date, event).map(|d| d.days);
+        assert_eq!(
+            infer(Some("2024.03.??"), Some("2024.03.15"), None),
+            Some(date_days(2024, 3, 15))
+        );
+        assert_eq!(
+            infer(Some("2024.01.02"), Some("2023.05.06"), Some("2022.07.08")),
+            Some(date_days(2024, 1, 2))
+        );
+        assert_eq!(
+            infer(None, Some("2024.??.??"), Some("2024.06.??")),
+            Some(date_days(2024, 6, 1))
+        );
+        // Unparseable candidates fall through to the next one.
+        assert_eq!(
+            infer(Some("2024.13.45"), None, Some("2024.02.30")),
+            Some(date_days(2024, 2, 29))
+        );
+        assert_eq!(infer(Some(""), Some("????.??.??"), None), None);
+        assert_eq!(infer(None, None, None), None);
+    }
 
     #[test]
     fn test_visitor_basic_parsing() {
@@ -687,6 +1469,61 @@ mod tests {
         assert_eq!(game.movetext, "1. e4 e5 2. Nf3");
     }
 
+    #[test]
+    fn test_visitor_extra_tag_columns_are_captured_and_converted() {
+        let pgn = r#"[Event "One"]
+[Variant "Chess960"]
+[Board "3"]
+[Rated "yes"]
+1. e4 1-0
+
+[Event "Two"]
+[Board "x"]
+1. d4 0-1"#;
+
+        let options = ScanOptions {
+            extra_columns: vec![
+                ExtraTagColumn {
+                    tag: "Variant".to_string(),
+                    tag_type: ExtraTagType::Varchar,
+                },
+                ExtraTagColumn {
+                    tag: "Board".to_string(),
+                    tag_type: ExtraTagType::Integer,
+                },
+                ExtraTagColumn {
+                    tag: "Rated".to_string(),
+                    tag_type: ExtraTagType::Boolean,
+                },
+            ]
+            .into(),
+            ..ScanOptions::default()
+        };
+        let mut reader = Reader::new(pgn.as_bytes());
+        let mut visitor = GameVisitor::with_options(options);
+
+        reader.read_game(&mut visitor).unwrap();
+        let first = visitor.current_game.take().expect("first game");
+        assert_eq!(
+            first.extra_tags,
+            vec![
+                Some(ExtraTagValue::Varchar("Chess960".to_string())),
+                Some(ExtraTagValue::Integer(3)),
+                Some(ExtraTagValue::Boolean(true)),
+            ]
+        );
+        assert_eq!(first.parse_error, None);
+
+        // Values from the previous game must not leak; bad conversions become NULL + error.
+        reader.read_game(&mut visitor).unwrap();
+        let second = visitor.current_game.take().expect("second game");
+        assert_eq!(second.extra_tags, vec![None, None, None]);
+        assert_eq!(
+            second.parse_error.as_deref(),
+            Some("Conversion error: Board='x'")
+        );
+    }
+
     #[test]
     fn test_visitor_unknown_headers_are_ignored() {
         let pgn = r#"[Event "Known"]
@@ -705,6 +1542,24 @@ mod tests {
         assert_eq!(game.result.as_deref(), Some("1-0"));
     }
 
+    #[test]
+    fn test_visitor_tags_follow_pgn_escape_rules() {
+        let pgn = "% escape line { before the tags\n[Event \"F/S \\\"Return\\\" Match\"]\n\
+            [\tSite   \"C:\\\\Belgrade\" ]\n% escape line between tags\n[Result \"1/2-1/2\"]\n\n\
+            1. e4 e5\n% 1-0 escape line in the movetext\n2. Nf3 1/2-1/2";
+
+        let mut reader = Reader::new(GameBoundaryScanner::new(pgn.as_bytes(), 0, false));
+        let mut visitor = GameVisitor::new();
+        reader.read_game(&mut visitor).unwrap();
+
+        let game = visitor.current_game.expect("Should have parsed a game");
+        assert_eq!(game.event.as_deref(), Some("F/S \"Return\" Match"));
+        assert_eq!(game.site.as_deref(), Some("C:\\Belgrade"));
+        assert_eq!(game.result.as_deref(), Some("1/2-1/2"));
+        assert_eq!(game.movetext, "1. e4 e5 2. Nf3");
+        assert_eq!(game.parse_error, None);
+    }
+
     #[test]
     fn test_visitor_duplicate_headers_preserve_first_value() {
         let pgn = r#"[Event "First Event"]
@@ -737,6 +1592,125 @@ mod tests {
         assert_eq!(game.movetext, "1. e4 { best by test } e5");
     }
 
+    #[test]
+    fn test_visitor_counts_invalid_utf8_sequences() {
+        let pgn = b"[Event \"Caf\xe9\"]\n[White \"M\xfcller \xff\xfe\"]\n\n1. e4 { \xe9l\xe8ve } e5 { ok } 1-0";
+
+        let mut reader = Reader::new(&pgn[..]);
+        let mut visitor = GameVisitor::new();
+        reader.read_game(&mut visitor).unwrap();
+
+        let game = visitor
+            .current_game
+            .take()
+            .expect("Should have parsed a game");
+        assert_eq!(game.encoding_issues, 6);
+        assert_eq!(game.event.as_deref(), Some("Caf\u{FFFD}"));
+        assert_eq!(game.movetext, "1. e4 { \u{FFFD}l\u{FFFD}ve } e5 { ok }");
+        assert_eq!(
+            game.parse_error.as_deref(),
+            Some("Replaced 6 invalid UTF-8 sequence(s) with U+FFFD")
+        );
+
+        let mut reader = Reader::new(&b"[Event \"Caf\xc3\xa9\"]\n\n1. e4 1-0"[..]);
+        reader.read_game(&mut visitor).unwrap();
+        let game = visitor.current_game.expect("Should have parsed a game");
+        assert_eq!(game.encoding_issues, 0);
+        assert_eq!(game.parse_error, None);
+    }
+
+    fn read_with_style(pgn: &str, movetext_style: MovetextStyle) -> GameRecord {
+        let mut reader = Reader::new(pgn.as_bytes());
+        let mut visitor = GameVisitor::with_options(ScanOptions {
+            movetext_style,
+            ..ScanOptions::default()
+        });
+        reader.read_game(&mut visitor).unwrap();
+        visitor.current_game.expect("Should have parsed a game")
+    }
+
+    #[test]
+    fn test_visitor_movetext_style_plain_and_uci() {
+        let pgn = "1. e4 { open } e5 2. Nf3 Nc6 3. Bc4 Nf6 4. O-O *";
+
+        let plain = read_with_style(pgn, MovetextStyle::Plain);
+        assert_eq!(plain.movetext, "e4 { open } e5 Nf3 Nc6 Bc4 Nf6 O-O");
+
+        let uci = read_with_style(pgn, MovetextStyle::Uci);
+        assert_eq!(uci.movetext, "e2e4 { open } e7e5 g1f3 b8c6 f1c4 g8f6 e1g1");
+        assert_eq!(uci.parse_error, None);
+    }
+
+    #[test]
+    fn test_visitor_collects_moves_with_comments_and_first_nag() {
+        let pgn = "{ intro } 1. e4! { best } { by test } e5 $2 $14 2. Nf3 (2. f4) Nc6 *";
+        let mut reader = Reader::new(pgn.as_bytes());
+        let mut visitor = GameVisitor::with_options(ScanOptions {
+            moves_as: MovesAs::Struct,
+            ..ScanOptions::default()
+        });
+        reader.read_game(&mut visitor).unwrap();
+        let game = visitor.current_game.unwrap();
+
+        let moves: Vec<_> = game
+            .moves
+            .iter()
+            .map(|m| (m.san.as_str(), m.comment.as_deref(), m.nag))
+            .collect();
+        assert_eq!(
+            moves,
+            [
+                ("e4", Some("best by test"), Some(1)),
+                ("e5", None, Some(2)),
+                ("Nf3", None, None),
+                ("Nc6", None, None),
+            ]
+        );
+        assert_eq!(
+            game.movetext,
+            "{ intro } 1. e4 { best } { by test } e5 2. Nf3 Nc6"
+        );
+
+        // Text output does not collect moves.
+        assert!(
+            read_with_style(pgn, MovetextStyle::Numbered)
+                .moves
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_visitor_movetext_style_uci_starts_from_fen_tag() {
+        let pgn = r#"[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1"]
+
+1... Kd7 2. e4 *"#;
+
+        let game = read_with_style(pgn, MovetextStyle::Uci);
+        assert_eq!(game.movetext, "e8d7 e2e4");
+        assert_eq!(game.parse_error, None);
+    }
+
+    #[test]
+    fn test_visitor_movetext_style_uci_stops_at_illegal_move() {
+        let game = read_with_style("1. e4 e5 2. Ke3 { oops } Nf6 *", MovetextStyle::Uci);
+        assert_eq!(game.movetext, "e2e4 e7e5");
+        assert_eq!(
+            game.parse_error.as_deref(),
+            Some("Illegal move 'Ke3' at ply 3; remaining moves not rendered as UCI")
+        );
+
+        let game = read_with_style("[FEN \"not a fen\"]\n\n1. e4 *", MovetextStyle::Uci);
+        assert_eq!(game.movetext, "");
+        assert!(
+            game.parse_error
+                .as_deref()
+                .is_some_and(|err| err.starts_with("Invalid FEN tag 'not a fen'")),
+            "{:?}",
+            game.parse_error
+        );
+    }
+
     #[test]
     fn test_visitor_empty_movetext() {
         let pgn = r#"[Event "Empty"]
@@ -838,4 +1812,565 @@ mod tests {
             "1. d4 { [%eval 0.25] [%clk 1:30:43] } Nf6 { [%eval 0.22] [%clk 1:30:42] }"
         );
     }
+
+    #[test]
+    fn test_visitor_elo_filter_skips_non_qualifying_games() {
+        let pgn = r#"[White "Low"]
+[WhiteElo "2400"]
+[BlackElo "2600"]
+
+1. e4 e5 1-0
+
+[White "High"]
+[WhiteElo "2550"]
+[BlackElo "2700"]
+
+1. d4 d5 0-1
+
+[White "Unrated"]
+[WhiteElo "?"]
+[BlackElo "2600"]
+
+1. c4 1/2-1/2
+"#;
+
+        let filter = GameFilter {
+            min_elo: Some(2500),
+            max_elo: None,
+            ..GameFilter::default()
+        };
+        let mut reader = Reader::new(pgn.as_bytes());
+        let mut visitor = GameVisitor::with_options(ScanOptions {
+            filter,
+            ..ScanOptions::default()
+        });
+
+        let mut whites = Vec::new();
+        while reader.read_game(&mut visitor).unwrap().is_some() {
+            if let Some(game) = visitor.current_game.take() {
+                whites.push(game.white.unwrap_or_default());
+            }
+        }
+
+        assert_eq!(whites, vec!["High".to_string()]);
+    }
+
+    #[test]
+    fn test_visitor_eco_backfill_fills_missing_opening_tags() {
+        let pgn = r#"[White "Untagged"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 1-0
+
+[White "Unknown"]
+[ECO "?"]
+[Opening "Sicilian"]
+
+1. e4 c5 0-1
+
+[White "Tagged"]
+[ECO "C60"]
+[Opening "Spanish"]
+
+1. e4 c5 1-0
+
+[White "Forfeit"]
+
+1-0
+
+[White "Setup"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]
+
+1. e4 1-0
+"#;
+
+        let mut reader = Reader::new(pgn.as_bytes());
+        let mut visitor = GameVisitor::with_options(ScanOptions {
+            eco_backfill: true,
+            ..ScanOptions::default()
+        });
+        let mut games = Vec::new();
+        while reader.read_game(&mut visitor).unwrap().is_some() {
+            let game = visitor.current_game.take().unwrap();
+            games.push((game.eco, game.opening, game.eco_inferred));
+        }
+
+        let some = |value: &str| Some(value.to_string());
+        assert_eq!(
+            games,
+            vec![
+                (some("C70"), some("Ruy Lopez: Morphy Defense"), true),
+                (some("B20"), some("Sicilian"), true),
+                (some("C60"), some("Spanish"), false),
+                (None, None, false),
+                (None, None, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_visitor_skip_empty_games_drops_games_without_moves() {
+        let pgn = r#"[White "Played"]
+
+1. e4 e5 1-0
+
+[White "Forfeit"]
+[Result "1-0"]
+
+1-0
+
+[White "Bye"]
+
+*
+
+[White "Commented"]
+
+{ Black did not show up } 1-0
+"#;
+
+        let read_whites = |skip_empty_games| {
+            let options = ScanOptions {
+                skip_empty_games,
+                ..ScanOptions::default()
+            };
+            let diagnostics = Arc::clone(&options.line_diagnostics);
+            let mut reader = Reader::new(pgn.as_bytes());
+            let mut visitor = GameVisitor::with_options(options);
+            let mut whites = Vec::new();
+            while reader.read_game(&mut visitor).unwrap().is_some() {
+                if let Some(game) = visitor.current_game.take() {
+                    whites.push(game.white.unwrap_or_default());
+                }
+            }
+            (
+                whites,
+                diagnostics.empty_games_skipped.load(Ordering::Relaxed),
+            )
+        };
+
+        assert_eq!(
+            read_whites(false),
+            (
+                vec![
+                    "Played".to_string(),
+                    "Forfeit".to_string(),
+                    "Bye".to_string(),
+                    "Commented".to_string()
+                ],
+                0
+            )
+        );
+        assert_eq!(read_whites(true), (vec!["Played".to_string()], 3));
+    }
+
+    #[test]
+    fn test_visitor_variant_filter_drops_variant_games() {
+        let pgn = r#"[White "Untagged"]
+
+1. e4 e5 1-0
+
+[White "Lichess"]
+[Variant "Standard"]
+
+1. d4 d5 0-1
+
+[White "Atomic"]
+[Variant "Atomic"]
+
+1. Nf3 d5 1-0
+
+[White "Shredder"]
+[FEN "nrbbqkrn/pppppppp/8/8/8/8/PPPPPPPP/NRBBQKRN w GBgb - 0 1"]
+
+1. e4 1-0
+
+[White "Endgame"]
+[Variant "From Position"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]
+
+1. e4 1-0
+"#;
+
+        let read_whites = |variant| {
+            let options = ScanOptions {
+                filter: GameFilter::default().with_variant(variant),
+                ..ScanOptions::default()
+            };
+            let diagnostics = Arc::clone(&options.line_diagnostics);
+            let mut reader = Reader::new(pgn.as_bytes());
+            let mut visitor = GameVisitor::with_options(options);
+            let mut whites = Vec::new();
+            while reader.read_game(&mut visitor).unwrap().is_some() {
+                if let Some(game) = visitor.current_game.take() {
+                    whites.push(game.white.unwrap_or_default());
+                }
+            }
+            (
+                whites,
+                diagnostics.variant_games_skipped.load(Ordering::Relaxed),
+            )
+        };
+
+        assert_eq!(
+            read_whites(VariantFilter::Standard),
+            (
+                vec!["Untagged".into(), "Lichess".into(), "Endgame".into()],
+                2
+            )
+        );
+        assert_eq!(read_whites(VariantFilter::All).0.len(), 5);
+    }
+
+    #[test]
+    fn test_is_standard_chess_reads_variant_and_fen_tags() {
+        assert!(is_standard_chess("", ""));
+        assert!(is_standard_chess(" normal ", ""));
+        assert!(is_standard_chess("", "not a fen"));
+        assert!(is_standard_chess(
+            "",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        ));
+        assert!(!is_standard_chess("Chess960", ""));
+        assert!(!is_standard_chess("crazyhouse", ""));
+        assert!(!is_standard_chess(
+            "",
+            "bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w KQkq - 0 1"
+        ));
+    }
+
+    #[test]
+    fn test_variant_filter_parse() {
+        assert_eq!(
+            VariantFilter::parse(" Standard "),
+            Ok(VariantFilter::Standard)
+        );
+        assert_eq!(VariantFilter::parse("all"), Ok(VariantFilter::All));
+        assert_eq!(
+            VariantFilter::parse("atomic"),
+            Err("Invalid variant value 'atomic'. Supported values: standard, all.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_visitor_skip_movetext_keeps_tags_and_following_games() {
+        let pgn = "[Event \"One\"]\n[Result \"1-0\"]\n\n1. e4 { [%clk 0:01:00] } e5 ( 1... c5 ) 1-0\n\n[Event \"Two\"]\n\n1. d4 *\n";
+        let mut visitor = GameVisitor::with_options(ScanOptions {
+            skip_movetext: true,
+            ..Default::default()
+        });
+        let mut reader = Reader::new(io::Cursor::new(pgn));
+
+        let mut games = Vec::new();
+        while reader.read_game(&mut visitor).unwrap().is_some() {
+            games.push(visitor.current_game.take().expect("game"));
+        }
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].event.as_deref(), Some("One"));
+        assert_eq!(games[0].result.as_deref(), Some("1-0"));
+        assert_eq!(games[0].movetext, "");
+        assert!(!games[0].has_clk && !games[0].has_variations);
+        // Without a Result tag the movetext is still read for its result marker.
+        assert_eq!(games[1].event.as_deref(), Some("Two"));
+        assert_eq!(games[1].result.as_deref(), Some("*"));
+        assert_eq!(games[1].parse_error, None);
+    }
+
+    #[test]
+    fn test_visitor_elo_filter_max_bound_is_inclusive() {
+        let filter = GameFilter {
+            min_elo: Some(1500),
+            max_elo: Some(2000),
+            ..GameFilter::default()
+        };
+        let mut headers = HeaderFields {
+            white_elo: "1500".to_string(),
+            black_elo: "2000".to_string(),
+            ..Default::default()
+        };
+        assert!(filter.accepts(&headers, false));
+
+        headers.black_elo = "2001".to_string();
+        assert!(!filter.accepts(&headers, false));
+
+        headers.black_elo = String::new();
+        assert!(!filter.accepts(&headers, false));
+        assert!(GameFilter::default().accepts(&headers, false));
+    }
+
+    #[test]
+    fn test_visitor_elo_filter_lenient_reads_provisional_ratings() {
+        let filter = GameFilter {
+            min_elo: Some(1500),
+            max_elo: None,
+            ..GameFilter::default()
+        };
+        let headers = HeaderFields {
+            white_elo: "2100?".to_string(),
+            black_elo: " 1600* ".to_string(),
+            ..Default::default()
+        };
+        assert!(!filter.accepts(&headers, false));
+        assert!(filter.accepts(&headers, true));
+    }
+
+    fn read_with_elo_options(pgn: &str, elo_lenient: bool) -> GameRecord {
+        let mut reader = Reader::new(pgn.as_bytes());
+        let mut visitor = GameVisitor::with_options(ScanOptions {
+            elo_lenient,
+            elo_raw_columns: true,
+            ..ScanOptions::default()
+        });
+        reader.read_game(&mut visitor).unwrap();
+        visitor.current_game.expect("Should have parsed a game")
+    }
+
+    #[test]
+    fn test_visitor_elo_lenient_maps_unknown_ratings_to_null() {
+        for (white, black, expected_white, expected_black) in [
+            ("?", "-", None, None),
+            ("2100?", "1500*", Some(2100), Some(1500)),
+            (" ?? ", " 1800 ", None, Some(1800)),
+        ] {
+            let pgn = format!("[WhiteElo \"{white}\"]\n[BlackElo \"{black}\"]\n\n1. e4 *\n");
+
+            let strict = read_with_elo_options(&pgn, false);
+            assert!(strict.parse_error.is_some(), "{white} / {black}");
+
+            let lenient = read_with_elo_options(&pgn, true);
+            assert_eq!(lenient.white_elo, expected_white, "{white}");
+            assert_eq!(lenient.black_elo, expected_black, "{black}");
+            assert_eq!(lenient.parse_error, None);
+            assert_eq!(lenient.white_elo_raw.as_deref(), Some(white));
+            assert_eq!(lenient.black_elo_raw.as_deref(), Some(black));
+        }
+
+        // Values that are not ratings at all still report a conversion error.
+        let game = read_with_elo_options("[WhiteElo \"abc\"]\n\n1. e4 *\n", true);
+        assert_eq!(game.white_elo, None);
+        assert_eq!(
+            game.parse_error.as_deref(),
+            Some("Conversion error: WhiteElo='abc'")
+        );
+    }
+
+    #[test]
+    fn test_visitor_records_warnings_for_adjusted_tags() {
+        let game = read_with_elo_options(
+            "[UTCDate \"2015.11.31\"]\n[WhiteElo \"2100?\"]\n[BlackElo \"1500\"]\n\n1. e4 *\n",
+            true,
+        );
+        assert_eq!(game.parse_error, None);
+        assert_eq!(
+            game.warnings,
+            vec![
+                "Stripped WhiteElo='2100?' to 2100".to_string(),
+                "Clamped UTCDate='2015.11.31' to 2015-11-30".to_string(),
+            ]
+        );
+
+        let game = read_with_style(
+            "[Date \"2015.??.??\"]\n\n1. e4 *\n",
+            MovetextStyle::Numbered,
+        );
+        assert_eq!(
+            game.warnings,
+            vec!["Defaulted unknown month/day of UTCDate (from Date)='2015.??.??' to 2015-01-01"]
+        );
+
+        // Values kept as written, and unknown dates, are not adjustments.
+        let game = read_with_style(
+            "[UTCDate \"2015.11.30\"]\n[Date \"????.??.??\"]\n\n1. e4 *\n",
+            MovetextStyle::Numbered,
+        );
+        assert!(game.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_visitor_elo_raw_columns_are_off_by_default() {
+        let game = read_with_style("[WhiteElo \"2100?\"]\n\n1. e4 *\n", MovetextStyle::Numbered);
+        assert_eq!(game.white_elo_raw, None);
+    }
+
+    #[test]
+    fn test_reader_state_splits_games_missing_blank_lines() {
+        let pgn = "[Event \"One\"]\n\n1. e4 e5 1-0\n  [Event \"Two\"]\n1. d4 0-1 [Event \"Three\"]\n1. c4 *\n";
+        let mut state =
+            PgnReaderState::new(Box::new(io::Cursor::new(pgn)), 0, ScanOptions::default());
+
+        let mut games = Vec::new();
+        while state
+            .pgn_reader
+            .read_game(&mut state.visitor)
+            .unwrap()
+            .is_some()
+        {
+            games.push(state.visitor.current_game.take().expect("game"));
+        }
+
+        let summary: Vec<_> = games
+            .iter()
+            .map(|game| (game.event.as_deref(), game.movetext.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Some("One"), "1. e4 e5"),
+                (Some("Two"), "1. d4"),
+                (Some("Three"), "1. c4"),
+            ]
+        );
+        assert_eq!(games[0].parse_error, None);
+        assert_eq!(
+            games[1].parse_error.as_deref(),
+            Some(RECOVERED_BOUNDARY_WARNING)
+        );
+        assert_eq!(
+            games[2].parse_error.as_deref(),
+            Some(RECOVERED_BOUNDARY_WARNING)
+        );
+    }
+
+    #[test]
+    fn test_reader_state_splits_one_line_and_cr_only_exports() {
+        for pgn in [
+            "[Event \"One\"] [Site \"?\"] 1. e4 e5 1-0 [Event \"Two\"] [Site \"x\"] 1. d4 0-1 [Event \"Three\"] 1. c4 *\n",
+            "[Event \"One\"]\r[Site \"?\"]\r\r1. e4 e5 1-0\r\r[Event \"Two\"]\r[Site \"x\"]\r\r1. d4 0-1\r\r[Event \"Three\"]\r\r1. c4 *\r",
+        ] {
+            let options = ScanOptions::default();
+            let diagnostics = Arc::clone(&options.line_diagnostics);
+            let mut state = PgnReaderState::new(Box::new(io::Cursor::new(pgn)), 0, options);
+
+            let mut games = Vec::new();
+            while state
+                .pgn_reader
+                .read_game(&mut state.visitor)
+                .unwrap()
+                .is_some()
+            {
+                games.push(state.visitor.current_game.take().expect("game"));
+            }
+
+            let summary: Vec<_> = games
+                .iter()
+                .map(|game| (game.event.as_deref(), game.movetext.as_str()))
+                .collect();
+            assert_eq!(
+                summary,
+                vec![
+                    (Some("One"), "1. e4 e5"),
+                    (Some("Two"), "1. d4"),
+                    (Some("Three"), "1. c4"),
+                ],
+                "{pgn:?}"
+            );
+            assert_eq!(games[1].site.as_deref(), Some("x"));
+            assert_eq!(diagnostics.files.load(Ordering::Relaxed), 1);
+        }
+    }
+
+    #[test]
+    fn test_reader_state_records_byte_offsets_from_start_offset() {
+        let pgn = "[Event \"One\"]\n1. e4 1-0\n\n[Event \"Two\"]\n[DuckdbChessByteOffset \"x\"]\n1. d4 0-1\n";
+        let second = pgn.find("[Event \"Two\"]").unwrap();
+        let options = ScanOptions {
+            start_offset: 500,
+            track_byte_offsets: true,
+            ..Default::default()
+        };
+        let mut state = PgnReaderState::new(Box::new(io::Cursor::new(pgn)), 0, options);
+
+        let mut offsets = Vec::new();
+        while state
+            .pgn_reader
+            .read_game(&mut state.visitor)
+            .unwrap()
+            .is_some()
+        {
+            let game = state.visitor.current_game.take().expect("game");
+            assert_eq!(game.parse_error, None);
+            offsets.push(game.byte_offset);
+        }
+        assert_eq!(offsets, vec![Some(500), Some(500 + second as u64)]);
+
+        let mut untracked =
+            PgnReaderState::new(Box::new(io::Cursor::new(pgn)), 0, ScanOptions::default());
+        untracked
+            .pgn_reader
+            .read_game(&mut untracked.visitor)
+            .unwrap();
+        assert_eq!(untracked.visitor.current_game.unwrap().byte_offset, None);
+    }
+
+    #[test]
+    fn test_reader_state_records_line_numbers_in_parse_errors() {
+        let pgn = "[Event \"One\"]\n[WhiteElo \"1500\"]\n\n1. e4 1-0\n\n[Event \"Two\"]\n[WhiteElo \"abc\"]\n\n1. d4 0-1\n\n1. c4 *\n";
+        let options = ScanOptions {
+            track_byte_offsets: true,
+            track_line_numbers: true,
+            ..Default::default()
+        };
+        let mut state = PgnReaderState::new(Box::new(io::Cursor::new(pgn)), 0, options);
+
+        let mut games = Vec::new();
+        while state
+            .pgn_reader
+            .read_game(&mut state.visitor)
+            .unwrap()
+            .is_some()
+        {
+            games.push(state.visitor.current_game.take().expect("game"));
+        }
+        let lines: Vec<Option<u64>> = games.iter().map(|game| game.line_number).collect();
+        // A game without a tag section has no location.
+        assert_eq!(lines, vec![Some(1), Some(6), None]);
+        assert_eq!(games[0].parse_error, None);
+        let second = pgn.find("[Event \"Two\"]").unwrap();
+        assert_eq!(
+            games[1].parse_error.as_deref(),
+            Some(
+                format!(
+                    "Conversion error: WhiteElo='abc'; Game location: line=6, byte_offset={second}"
+                )
+                .as_str()
+            )
+        );
+    }
+
+    #[test]
+    fn test_reader_state_records_movetext_bytes_and_annotation_flags() {
+        let first = "1. e4 { [%clk 0:03:00] }\n \n1... e5 1-0\n";
+        let second = "1. d4 ( 1. c4 ) { [%eval 0.1] } d5 0-1\n";
+        let pgn = format!(
+            "[Event \"One\"]\n\n{first}\n[Event \"Two\"]\n{second}1/2-1/2\n[Event \"Three\"]\n1. c4 *"
+        );
+        let options = ScanOptions {
+            track_movetext_bytes: true,
+            ..Default::default()
+        };
+        let mut state = PgnReaderState::new(Box::new(io::Cursor::new(pgn)), 0, options);
+
+        let mut games = Vec::new();
+        while state
+            .pgn_reader
+            .read_game(&mut state.visitor)
+            .unwrap()
+            .is_some()
+        {
+            let game = state.visitor.current_game.take().expect("game");
+            games.push((
+                game.movetext_bytes,
+                game.has_clk,
+                game.has_eval,
+                game.has_variations,
+            ));
+        }
+        assert_eq!(
+            games,
+            vec![
+                (Some(first.len() as u64), true, false, false),
+                // A missing blank line still ends the movetext at the next tag section.
+                (Some(second.len() as u64 + 8), false, true, true),
+                (Some(7), false, false, false),
+            ]
+        );
+    }
 }
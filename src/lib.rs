@@ -1,5 +1,14 @@
+#[cfg(feature = "extension")]
 extern crate duckdb;
-extern crate duckdb_ext_macros;
+#[cfg(feature = "extension")]
 extern crate libduckdb_sys;
 
 mod chess;
+
+pub use chess::{
+    ExtraTagColumn, ExtraTagType, ExtraTagValue, GameRecord, MoveRecord, MovetextStyle, PgnDate,
+    PgnGameIter, PgnReadOptions, PgnTimeTz, VariantFilter,
+};
+
+#[cfg(feature = "fuzzing")]
+pub use chess::fuzzing;